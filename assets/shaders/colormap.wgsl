@@ -0,0 +1,19 @@
+// Colors of an R8 texture of the values of a color map, looked up in a
+// palette of 256 colors, see `upload::ColormapMaterial`
+@group(1) @binding(0)
+var values: texture_2d<f32>;
+@group(1) @binding(1)
+var values_sampler: sampler;
+@group(1) @binding(2)
+var palette: texture_2d<f32>;
+@group(1) @binding(3)
+var palette_sampler: sampler;
+
+@fragment
+fn fragment(
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    let value = textureSample(values, values_sampler, uv).r;
+    // Center of the texel of the value, stored as round(value * 255)
+    return textureSample(palette, palette_sampler, vec2<f32>((value * 255.0 + 0.5) / 256.0, 0.5));
+}
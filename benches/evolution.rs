@@ -0,0 +1,67 @@
+//! Benchmarks of the kernel, the whole step and the coloring, on every
+//! backend and a few sizes of universe, with criterion
+//! Run with `cargo bench --no-default-features --features fs`; a filter, e.g.
+//! `cargo bench -- 512`, keeps only the benchmarks whose name matches it. The
+//! evolutions are timed by `bench`, so that the kernel is measured apart from
+//! the rest of the step as in the `bench` command
+use std::time::Duration;
+
+use ca_turing_pattern::bench::{bench, bench_coloring, BenchConfig, BenchReport, BACKENDS};
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::render::RenderConfig;
+use ca_turing_pattern::{initialize_universe, Position};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Sides of the square universes timed
+const SIDES: [usize; 3] = [64, 256, 512];
+
+/// Time of a stage of the evolution in a report
+type Stage = fn(&BenchReport) -> Duration;
+
+/// Stages of the evolution timed
+const STAGES: [(&str, Stage); 2] = [("kernel", |report| report.kernel), ("step", |report| report.step)];
+
+/// Time taken by `stage` during `iters` evolutions of `config`
+fn time_evolutions(config: BenchConfig, iters: u64, stage: Stage) -> Duration {
+    let steps = iters.clamp(1, i32::MAX as u64) as i32;
+    stage(&bench(&BenchConfig { steps, ..config }).expect("the benchmarks are valid"))
+}
+
+fn evolution(c: &mut Criterion) {
+    for (name, stage) in STAGES {
+        let mut group = c.benchmark_group(name);
+        // Every sample starts a new simulation, warm-up included
+        group.sample_size(20);
+        for side in SIDES {
+            let dimensions = Position { row: side, col: side };
+            group.throughput(Throughput::Elements((side * side) as u64));
+            for backend in BACKENDS {
+                let config = BenchConfig { backend, dimensions, ..BenchConfig::default() };
+                let id = BenchmarkId::new(backend.name(), format!("{side}x{side}"));
+                group.bench_with_input(id, &config, |b, config| {
+                    b.iter_custom(|iters| time_evolutions(*config, iters, stage))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+fn coloring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("coloring");
+    for side in SIDES {
+        let dimensions = Position { row: side, col: side };
+        let (universe, _) = initialize_universe(&dimensions);
+        group.throughput(Throughput::Elements((side * side) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{side}x{side}")), &universe, |b, universe| {
+            b.iter_custom(|iters| {
+                let times = iters.min(u32::MAX as u64) as u32;
+                bench_coloring(universe, Colormap::default(), RenderConfig::default(), times)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, evolution, coloring);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+fn main() {
+    #[cfg(feature = "capi")]
+    generate_c_header();
+}
+
+/// Write the C header of the `capi` feature to `OUT_DIR`, and to the
+/// directory given by `CA_TURING_PATTERN_INCLUDE_DIR` if it is set, e.g.
+/// `include` to update the header shipped with the sources
+/// The build never writes to the sources otherwise
+#[cfg(feature = "capi")]
+fn generate_c_header() {
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=CA_TURING_PATTERN_INCLUDE_DIR");
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml"))
+        .expect("invalid cbindgen.toml");
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{crate_dir}/src/capi.rs"))
+        .generate()
+        .expect("could not generate the C header");
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    bindings.write_to_file(format!("{out_dir}/ca_turing_pattern.h"));
+    if let Ok(include_dir) = std::env::var("CA_TURING_PATTERN_INCLUDE_DIR") {
+        bindings.write_to_file(std::path::Path::new(&crate_dir).join(include_dir).join("ca_turing_pattern.h"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Creatures following the pattern
+//! Runs the window of the simulation with creatures of another plugin
+//! wandering over the color map, reading the `MorphogenField` resource every
+//! frame: they climb the concentration of B towards the spots, head to the
+//! closest peak when the slope is too gentle to follow, and take its color.
+//! Start it with
+//!
+//! ```sh
+//! cargo run --release --example creatures
+//! ```
+//!
+//! and press `Space` to start evolving the universe.
+use bevy::prelude::*;
+use rand::Rng;
+
+use ca_turing_pattern::app::{self, SimulationState};
+use ca_turing_pattern::config::OutputConfig;
+use ca_turing_pattern::deterministic::portable_rng;
+use ca_turing_pattern::morphogen::MorphogenField;
+use ca_turing_pattern::render::RenderConfig;
+use ca_turing_pattern::rewind::{RewindBuffer, RewindConfig};
+use ca_turing_pattern::session::Brush;
+use ca_turing_pattern::streams::StreamSeeds;
+use ca_turing_pattern::*;
+
+/// Number of creatures
+const CREATURES: usize = 60;
+/// Speed of the creatures, in pixels per second
+const SPEED: f32 = 40.0;
+/// Slope of B below which a creature heads to the closest peak instead
+const GENTLE_SLOPE: f32 = 1e-4;
+/// Distance within which a creature looks for a peak, in pixels
+const SIGHT: f32 = 24.0;
+
+/// Creature moving over the pattern
+#[derive(Component)]
+struct Creature {
+    /// Direction it moves in, of length 1
+    heading: Vec2,
+}
+
+/// Plugin spawning the creatures and moving them along the field
+struct CreaturesPlugin;
+
+impl Plugin for CreaturesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_creatures).add_system(follow_field);
+    }
+}
+
+fn main() {
+    let dimensions = Position { row: 200, col: 300 };
+    let simulation = Simulation::random(
+        Parameters::preset("spots").unwrap(),
+        dimensions,
+        INITIAL_CELLS,
+        &mut rand::thread_rng(),
+    )
+    .expect("the spots preset is valid");
+
+    app::build(SimulationState {
+        simulation,
+        comparisons: Vec::new(),
+        couplings: Vec::new(),
+        max_generations: i32::MAX,
+        output: OutputConfig::default(),
+        events: Vec::new(),
+        preset: Some("spots".to_string()),
+        stats_interval: app::DEFAULT_STATS_INTERVAL,
+        render: RenderConfig::default(),
+        show_activation: false,
+        probe: None,
+        #[cfg(feature = "fs")]
+        recorder: None,
+        #[cfg(feature = "fs")]
+        config_file: None,
+        rewind: RewindBuffer::new(RewindConfig::default()).unwrap(),
+        initial_cells: INITIAL_CELLS,
+        initial: None,
+        name: "creatures".to_string(),
+        seed: None,
+        streams: StreamSeeds::default(),
+        brush: Brush::default(),
+        brush_rng: portable_rng(rand::random()),
+        view: None,
+        profile: false,
+        #[cfg(feature = "control")]
+        control: None,
+    })
+    .add_plugin(CreaturesPlugin)
+    .run();
+}
+
+/// Scatter the creatures over the color map, heading anywhere
+fn spawn_creatures(mut commands: Commands, field: Res<MorphogenField>) {
+    let mut rng = rand::thread_rng();
+    let dimensions = field.dimensions();
+    for _ in 0..CREATURES {
+        let cells = [rng.gen_range(0.0..dimensions.col as f32), rng.gen_range(0.0..dimensions.row as f32)];
+        let [x, y] = field.to_point(cells);
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::WHITE,
+                    custom_size: Some(Vec2::splat(6.0)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, y, 1.0),
+                ..default()
+            },
+            Creature { heading: Vec2::new(angle.cos(), angle.sin()) },
+        ));
+    }
+}
+
+/// Turn the creatures up the slope of B, or towards the closest peak, move
+/// them and color them with the B under them; creatures leaving the color
+/// map turn back
+fn follow_field(
+    time: Res<Time>,
+    field: Res<MorphogenField>,
+    mut creatures: Query<(&mut Creature, &mut Transform, &mut Sprite)>,
+) {
+    for (mut creature, mut transform, mut sprite) in &mut creatures {
+        let point = [transform.translation.x, transform.translation.y];
+        let Some(gradient) = field.gradient(point).map(Vec2::from) else {
+            creature.heading = -creature.heading;
+            transform.translation += (creature.heading * SPEED * time.delta_seconds()).extend(0.0);
+            continue;
+        };
+        let target = if gradient.length() >= GENTLE_SLOPE {
+            Some(gradient.normalize())
+        } else {
+            field
+                .nearest_peak(point, SIGHT)
+                .map(|peak| Vec2::from(peak) - Vec2::from(point))
+                .and_then(|towards| towards.try_normalize())
+        };
+        if let Some(target) = target {
+            creature.heading = creature.heading.lerp(target, 0.1).try_normalize().unwrap_or(target);
+        }
+        transform.translation += (creature.heading * SPEED * time.delta_seconds()).extend(0.0);
+        let b = field.sample(point).unwrap_or(0.0);
+        sprite.color = Color::rgb(1.0, 1.0 - b.clamp(0.0, 1.0), 0.2);
+    }
+}
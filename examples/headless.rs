@@ -0,0 +1,46 @@
+//! Headless run to an image
+//! Evolves a universe from the canonical square of B without a window and
+//! saves its color map, the smallest use of the library:
+//!
+//! ```sh
+//! cargo run --release --example headless --no-default-features --features fs -- [<image>]
+//! ```
+//!
+//! The image is `target/examples/headless.png` unless given.
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::export::save_colored_map;
+use ca_turing_pattern::initial::InitialCondition;
+use ca_turing_pattern::*;
+
+/// Number of evolutions
+const STEPS: i32 = 1000;
+
+fn main() -> Result<(), SimulationError> {
+    let path = std::env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("target/examples/headless.png"), PathBuf::from);
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    let dimensions = Position { row: 128, col: 128 };
+    let (universe, _) = InitialCondition::Square { size: 0.2 }.generate(&dimensions, &mut StdRng::seed_from_u64(0))?;
+    let mut simulation: Simulation = Simulation::new(Parameters::preset("spots").unwrap(), dimensions, universe)?;
+    simulation.run(STEPS);
+
+    save_colored_map(simulation.colored_map(), Colormap::Viridis, &path)?;
+    let stats = simulation.stats();
+    println!(
+        "generation {}: mean of B {:.4}, {}",
+        simulation.generation(),
+        stats.b.mean,
+        simulation.classify().kind
+    );
+    println!("saved {}", path.display());
+    Ok(())
+}
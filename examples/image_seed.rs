@@ -0,0 +1,59 @@
+//! Run seeded from an image
+//! Builds the initial universe from the pixels of an image, B following
+//! their brightness, and saves the color map before and after the
+//! evolution. Without an image, a ring is drawn to seed it:
+//!
+//! ```sh
+//! cargo run --release --example image_seed --no-default-features --features fs -- [<image>]
+//! ```
+//!
+//! The color maps are `initial.png` and `final.png` in
+//! `target/examples/image_seed`.
+use std::path::PathBuf;
+
+use image::{DynamicImage, GrayImage, Luma};
+
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::export::save_colored_map;
+use ca_turing_pattern::initial::{load_image_universe, universe_from_image, Channel};
+use ca_turing_pattern::*;
+
+/// Number of evolutions
+const STEPS: i32 = 2000;
+
+/// Side of the image drawn when none is given, in pixels
+const SIDE: u32 = 128;
+
+fn main() -> Result<(), SimulationError> {
+    let directory = PathBuf::from("target/examples/image_seed");
+    std::fs::create_dir_all(&directory)?;
+
+    let (universe, dimensions) = match std::env::args().nth(1) {
+        Some(path) => load_image_universe(path.as_ref(), Channel::Luminance, false)?,
+        None => universe_from_image(&ring(), Channel::Luminance, false),
+    };
+    let mut simulation: Simulation = Simulation::new(Parameters::preset("mitosis").unwrap(), dimensions, universe)?;
+    save_colored_map(simulation.colored_map(), Colormap::Inferno, &directory.join("initial.png"))?;
+
+    simulation.run(STEPS);
+    save_colored_map(simulation.colored_map(), Colormap::Inferno, &directory.join("final.png"))?;
+    println!(
+        "{}x{} universe at generation {}: {}",
+        dimensions.row,
+        dimensions.col,
+        simulation.generation(),
+        simulation.classify().kind
+    );
+    println!("saved the color maps in {}", directory.display());
+    Ok(())
+}
+
+/// Image black but for a bright ring around its center
+fn ring() -> DynamicImage {
+    let center = SIDE as f32 / 2.0;
+    let image = GrayImage::from_fn(SIDE, SIDE, |x, y| {
+        let distance = (x as f32 - center).hypot(y as f32 - center);
+        Luma([if (distance - SIDE as f32 / 4.0).abs() < 3.0 { 128 } else { 0 }])
+    });
+    DynamicImage::ImageLuma8(image)
+}
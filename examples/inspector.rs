@@ -0,0 +1,89 @@
+//! Interactive window with an egui panel
+//! Runs the window of the simulation with a panel of sliders changing the
+//! parameters through the `SimulationParameters` resource, next to the
+//! statistics of `SimulationStats`, beside the inspector window of the
+//! `inspector` feature:
+//!
+//! ```sh
+//! cargo run --release --example inspector --features inspector
+//! ```
+//!
+//! and press `Space` to start evolving the universe.
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContext};
+
+use ca_turing_pattern::app::{self, SimulationParameters, SimulationState, SimulationStats};
+use ca_turing_pattern::config::OutputConfig;
+use ca_turing_pattern::deterministic::portable_rng;
+use ca_turing_pattern::render::RenderConfig;
+use ca_turing_pattern::rewind::{RewindBuffer, RewindConfig};
+use ca_turing_pattern::session::Brush;
+use ca_turing_pattern::streams::StreamSeeds;
+use ca_turing_pattern::*;
+
+fn main() {
+    let dimensions = Position { row: 256, col: 256 };
+    let simulation = Simulation::random(
+        Parameters::preset("mitosis").unwrap(),
+        dimensions,
+        INITIAL_CELLS,
+        &mut rand::thread_rng(),
+    )
+    .expect("the mitosis preset is valid");
+
+    app::build(SimulationState {
+        simulation,
+        comparisons: Vec::new(),
+        couplings: Vec::new(),
+        max_generations: i32::MAX,
+        output: OutputConfig::default(),
+        events: Vec::new(),
+        preset: Some("mitosis".to_string()),
+        stats_interval: app::DEFAULT_STATS_INTERVAL,
+        render: RenderConfig::default(),
+        show_activation: false,
+        probe: None,
+        #[cfg(feature = "fs")]
+        recorder: None,
+        #[cfg(feature = "fs")]
+        config_file: None,
+        rewind: RewindBuffer::new(RewindConfig::default()).unwrap(),
+        initial_cells: INITIAL_CELLS,
+        initial: None,
+        name: "inspector".to_string(),
+        seed: None,
+        streams: StreamSeeds::default(),
+        brush: Brush::default(),
+        brush_rng: portable_rng(rand::random()),
+        view: None,
+        profile: false,
+        #[cfg(feature = "control")]
+        control: None,
+    })
+    .add_system(parameter_panel)
+    .run();
+}
+
+/// Sliders of the parameters and the statistics of the universe; the
+/// parameters are only written when a slider moved, for the presets and the
+/// configuration file to keep changing them otherwise
+fn parameter_panel(
+    mut context: ResMut<EguiContext>,
+    mut parameters: ResMut<SimulationParameters>,
+    stats: Res<SimulationStats>,
+) {
+    let mut edited = parameters.0;
+    egui::Window::new("Parameters").show(context.ctx_mut(), |ui| {
+        ui.add(egui::Slider::new(&mut edited.f, 0.0..=0.1).text("f"));
+        ui.add(egui::Slider::new(&mut edited.k, 0.0..=0.1).text("k"));
+        ui.add(egui::Slider::new(&mut edited.d_a, 0.0..=1.0).text("d_a"));
+        ui.add(egui::Slider::new(&mut edited.d_b, 0.0..=1.0).text("d_b"));
+        ui.add(egui::Slider::new(&mut edited.r, 0.0..=2.0).text("r"));
+        ui.separator();
+        ui.label(format!("generation {}", stats.generation));
+        ui.label(format!("mean of A {:.4}, of B {:.4}", stats.stats.a.mean, stats.stats.b.mean));
+    });
+    if edited != parameters.0 {
+        parameters.0 = edited;
+    }
+}
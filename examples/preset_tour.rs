@@ -0,0 +1,58 @@
+//! Tour of the presets
+//! Evolves the same seeded universe with every preset of the preset file,
+//! or the built-in presets without it, and saves the color map of each with
+//! the kind of pattern it formed:
+//!
+//! ```sh
+//! cargo run --release --example preset_tour --no-default-features --features fs -- [<directory>]
+//! ```
+//!
+//! The images are `<preset>.png` in `target/examples/presets` unless
+//! another directory is given.
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::export::save_colored_map;
+use ca_turing_pattern::presets::{PresetLibrary, PRESETS_PATH};
+use ca_turing_pattern::*;
+
+/// Number of evolutions of every preset
+const STEPS: i32 = 1000;
+
+fn main() -> Result<(), SimulationError> {
+    let directory = std::env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("target/examples/presets"), PathBuf::from);
+    std::fs::create_dir_all(&directory)?;
+
+    let library = PresetLibrary::load(PRESETS_PATH).unwrap_or_else(|error| {
+        eprintln!("{error}, touring the built-in presets");
+        PresetLibrary {
+            presets: PRESET_NAMES
+                .iter()
+                .filter_map(|name| Some((name.to_string(), Parameters::preset(name)?)))
+                .collect(),
+        }
+    });
+    let dimensions = Position { row: 128, col: 128 };
+    let (universe, _) = initialize_universe_with_rng(&dimensions, INITIAL_CELLS, &mut StdRng::seed_from_u64(0));
+
+    for name in library.names() {
+        let parameters = library.get(name).expect("the names are those of the library");
+        let mut simulation: Simulation = match Simulation::new(parameters, dimensions, universe.clone()) {
+            Ok(simulation) => simulation,
+            Err(error) => {
+                eprintln!("{name}: {error}");
+                continue;
+            }
+        };
+        simulation.run(STEPS);
+        let path = directory.join(format!("{name}.png"));
+        save_colored_map(simulation.colored_map(), Colormap::Magma, &path)?;
+        println!("{name}: {} in {}", simulation.classify().kind, path.display());
+    }
+    Ok(())
+}
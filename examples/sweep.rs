@@ -0,0 +1,41 @@
+//! Parameter sweep
+//! Evolves a grid of values of `f` and `k` from the same seeded universe, in
+//! parallel, and writes the color map of every run, a summary and a montage
+//! of them all, one column per value of `f`:
+//!
+//! ```sh
+//! cargo run --release --example sweep --no-default-features --features fs -- [<directory>]
+//! ```
+//!
+//! The results are in `target/examples/sweep` unless another directory is
+//! given.
+use std::path::PathBuf;
+
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::sweep::{Sweep, SweepRange};
+use ca_turing_pattern::*;
+
+fn main() -> Result<(), SimulationError> {
+    let directory = std::env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("target/examples/sweep"), PathBuf::from);
+
+    let sweep = Sweep {
+        base: Parameters::preset("spots").unwrap(),
+        f: Some(SweepRange { start: 0.02, end: 0.06, count: 4 }),
+        k: Some(SweepRange { start: 0.05, end: 0.07, count: 3 }),
+        dimensions: Position { row: 64, col: 64 },
+        steps: 1000,
+        ..Sweep::default()
+    };
+    let runs = sweep.write(&directory, Colormap::Viridis)?;
+    for run in &runs {
+        let outcome = match &run.error {
+            Some(error) => error.clone(),
+            None => run.kind.to_string(),
+        };
+        println!("f {:.4} k {:.4}: {outcome}", run.parameters.f, run.parameters.k);
+    }
+    println!("wrote {} runs to {}", runs.len(), directory.display());
+    Ok(())
+}
@@ -0,0 +1,59 @@
+//! Web demo of the simulation
+//! Runs a small universe in the canvas of `web/index.html`. Build it with
+//!
+//! ```sh
+//! cargo build --release --example web --target wasm32-unknown-unknown --no-default-features --features bevy
+//! wasm-bindgen --out-dir web/pkg --target web \
+//!     target/wasm32-unknown-unknown/release/examples/web.wasm
+//! ```
+//!
+//! and serve the `web` directory with any static file server.
+use ca_turing_pattern::app::{self, SimulationState};
+use ca_turing_pattern::config::OutputConfig;
+use ca_turing_pattern::deterministic::portable_rng;
+use ca_turing_pattern::render::RenderConfig;
+use ca_turing_pattern::rewind::{RewindBuffer, RewindConfig};
+use ca_turing_pattern::session::Brush;
+use ca_turing_pattern::streams::StreamSeeds;
+use ca_turing_pattern::*;
+
+fn main() {
+    let dimensions = Position { row: 200, col: 200 };
+    let simulation = Simulation::random(
+        Parameters::preset("spots").unwrap(),
+        dimensions,
+        INITIAL_CELLS,
+        &mut rand::thread_rng(),
+    )
+    .expect("the spots preset is valid");
+
+    app::run(SimulationState {
+        simulation,
+        comparisons: Vec::new(),
+        couplings: Vec::new(),
+        max_generations: i32::MAX,
+        output: OutputConfig::default(),
+        events: Vec::new(),
+        preset: Some("spots".to_string()),
+        stats_interval: app::DEFAULT_STATS_INTERVAL,
+        render: RenderConfig::default(),
+        show_activation: false,
+        probe: None,
+        #[cfg(feature = "fs")]
+        recorder: None,
+        #[cfg(feature = "fs")]
+        config_file: None,
+        rewind: RewindBuffer::new(RewindConfig::default()).unwrap(),
+        initial_cells: INITIAL_CELLS,
+        initial: None,
+        name: "Turing patterns".to_string(),
+        seed: None,
+        streams: StreamSeeds::default(),
+        brush: Brush::default(),
+        brush_rng: portable_rng(rand::random()),
+        view: None,
+        profile: false,
+        #[cfg(feature = "control")]
+        control: None,
+    });
+}
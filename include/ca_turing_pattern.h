@@ -0,0 +1,180 @@
+#ifndef CA_TURING_PATTERN_H
+#define CA_TURING_PATTERN_H
+
+/* Generated by cbindgen from src/capi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of the functions, 0 on success and negative on failure
+ */
+typedef enum CaStatus {
+  /**
+   * Success
+   */
+  CA_STATUS_OK = 0,
+  /**
+   * A pointer given is NULL
+   */
+  CA_STATUS_NULL_POINTER = -1,
+  /**
+   * The parameters are invalid, see `Parameters::validate`
+   */
+  CA_STATUS_INVALID_PARAMETERS = -2,
+  /**
+   * The buffer cannot hold every cell
+   */
+  CA_STATUS_BUFFER_TOO_SMALL = -3,
+  /**
+   * The field is not one of the `CA_FIELD_*` constants
+   */
+  CA_STATUS_INVALID_FIELD = -4,
+} CaStatus;
+
+/**
+ * Simulation handle, created with `ca_simulation_new`
+ */
+typedef struct CaSimulation CaSimulation;
+
+/**
+ * Parameters of the simulation, see `Parameters`
+ */
+typedef struct CaParameters {
+  float d_a;
+  float d_b;
+  float f;
+  float k;
+  float r;
+} CaParameters;
+
+/**
+ * Field copied by `ca_simulation_copy_field`, one of the `CA_FIELD_*`
+ * constants
+ * An integer rather than an enum, since C may pass any value
+ */
+typedef uint32_t CaField;
+
+/**
+ * Concentration of A
+ */
+#define CA_FIELD_A 0
+
+/**
+ * Concentration of B
+ */
+#define CA_FIELD_B 1
+
+/**
+ * Color value of the cells, in [0,1]
+ */
+#define CA_FIELD_COLOR 2
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a simulation of `rows` x `cols` cells with the default parameters
+ * and random initial cells drawn from `seed`
+ * Returns NULL if a dimension is 0. The simulation must be released with
+ * `ca_simulation_free`
+ */
+struct CaSimulation *ca_simulation_new(size_t rows, size_t cols, uint64_t seed);
+
+/**
+ * Release a simulation created with `ca_simulation_new`
+ *
+ * # Safety
+ * `simulation` must be NULL or a pointer returned by `ca_simulation_new`
+ * that was not released yet
+ */
+void ca_simulation_free(struct CaSimulation *simulation);
+
+/**
+ * Replace the parameters of the simulation
+ * Returns `CA_STATUS_NULL_POINTER` if `simulation` is NULL and
+ * `CA_STATUS_INVALID_PARAMETERS` if the parameters are invalid, in which
+ * case the current ones are kept
+ *
+ * # Safety
+ * `simulation` must be NULL or a valid pointer returned by
+ * `ca_simulation_new`
+ */
+enum CaStatus ca_simulation_set_parameters(struct CaSimulation *simulation,
+                                           struct CaParameters parameters);
+
+/**
+ * Write the current parameters of the simulation to `parameters`
+ * Returns `CA_STATUS_NULL_POINTER` if a pointer is NULL
+ *
+ * # Safety
+ * `simulation` must be NULL or a valid pointer returned by
+ * `ca_simulation_new`, and `parameters` NULL or writable
+ */
+enum CaStatus ca_simulation_parameters(const struct CaSimulation *simulation,
+                                       struct CaParameters *parameters);
+
+/**
+ * Compute `n` evolutions
+ * Returns `CA_STATUS_NULL_POINTER` if `simulation` is NULL
+ *
+ * # Safety
+ * `simulation` must be NULL or a valid pointer returned by
+ * `ca_simulation_new`
+ */
+enum CaStatus ca_simulation_step(struct CaSimulation *simulation, uint32_t n);
+
+/**
+ * Number of evolutions computed so far, -1 if `simulation` is NULL
+ *
+ * # Safety
+ * `simulation` must be NULL or a valid pointer returned by
+ * `ca_simulation_new`
+ */
+int32_t ca_simulation_generation(const struct CaSimulation *simulation);
+
+/**
+ * Number of rows of the universe, 0 if `simulation` is NULL
+ *
+ * # Safety
+ * `simulation` must be NULL or a valid pointer returned by
+ * `ca_simulation_new`
+ */
+size_t ca_simulation_rows(const struct CaSimulation *simulation);
+
+/**
+ * Number of columns of the universe, 0 if `simulation` is NULL
+ *
+ * # Safety
+ * `simulation` must be NULL or a valid pointer returned by
+ * `ca_simulation_new`
+ */
+size_t ca_simulation_cols(const struct CaSimulation *simulation);
+
+/**
+ * Copy `field` into `buffer` in row-major order
+ * `field` is one of the `CA_FIELD_*` constants and `len` the number of
+ * floats in `buffer`, at least rows * cols. Returns
+ * `CA_STATUS_NULL_POINTER` if a pointer is NULL,
+ * `CA_STATUS_INVALID_FIELD` if the field is unknown and
+ * `CA_STATUS_BUFFER_TOO_SMALL` if the buffer is too small
+ *
+ * # Safety
+ * `simulation` must be NULL or a valid pointer returned by
+ * `ca_simulation_new`, and `buffer` NULL or pointing to `len` writable
+ * floats
+ */
+enum CaStatus ca_simulation_copy_field(const struct CaSimulation *simulation,
+                                       CaField field,
+                                       float *buffer,
+                                       size_t len);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* CA_TURING_PATTERN_H */
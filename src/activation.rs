@@ -0,0 +1,112 @@
+/// Activation times
+/// With tracking, see `Simulation::with_activation_tracking`, every cell
+/// remembers the generation at which its B first exceeded a threshold, the
+/// cells already over it when tracking starts being activated at that
+/// generation. Drawn as a color map, see `ActivationMap::colored_map`, the
+/// times show in one static image where the pattern nucleated, the earliest
+/// cells, and how fast its fronts spread, by how far apart the bands of
+/// successive times are
+use serde::{Deserialize, Serialize};
+
+use crate::{ColoredMap, Float, Position, Universe};
+
+/// Value of the color map of the latest activated cells, the earliest ones
+/// being drawn at 1 and the cells never activated at 0
+pub const LATEST_VALUE: f32 = 0.2;
+
+/// Settings of the tracking of the activation times
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActivationTracking {
+    /// Concentration of B over which a cell is activated
+    pub threshold: f64,
+}
+
+impl Default for ActivationTracking {
+    fn default() -> Self {
+        ActivationTracking { threshold: 0.1 }
+    }
+}
+
+/// Generation at which each cell of a universe was activated
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivationMap {
+    tracking: ActivationTracking,
+    /// Activation time of each cell, `None` if not activated yet
+    times: Vec<Vec<Option<i32>>>,
+}
+
+impl ActivationMap {
+    /// Map of `universe` at `generation`, its cells over the threshold being
+    /// activated at `generation`
+    pub fn new<T: Float>(tracking: ActivationTracking, universe: &Universe<T>, generation: i32) -> ActivationMap {
+        let times = universe.iter().map(|row| vec![None; row.len()]).collect();
+        let mut map = ActivationMap { tracking, times };
+        map.update(universe, generation);
+        map
+    }
+
+    /// Settings of the tracking
+    pub fn tracking(&self) -> ActivationTracking {
+        self.tracking
+    }
+
+    /// Activate at `generation` the cells of `universe` over the threshold
+    /// that were not activated yet
+    pub fn update<T: Float>(&mut self, universe: &Universe<T>, generation: i32) {
+        let threshold = self.tracking.threshold;
+        for (times, cells) in self.times.iter_mut().zip(universe) {
+            for (time, cell) in times.iter_mut().zip(cells) {
+                if time.is_none() && cell.b.to_f64() > threshold {
+                    *time = Some(generation);
+                }
+            }
+        }
+    }
+
+    /// Forget the activations after `generation`, e.g. when the simulation
+    /// goes back to it
+    pub fn forget_after(&mut self, generation: i32) {
+        for time in self.times.iter_mut().flatten() {
+            if time.is_some_and(|time| time > generation) {
+                *time = None;
+            }
+        }
+    }
+
+    /// Generation at which the cell at `position` was activated, `None` if
+    /// it was not or is outside of the universe
+    pub fn time(&self, position: Position) -> Option<i32> {
+        *self.times.get(position.row)?.get(position.col)?
+    }
+
+    /// Activation times of the cells, row by row
+    pub fn times(&self) -> &[Vec<Option<i32>>] {
+        &self.times
+    }
+
+    /// Number of activated cells
+    pub fn activated(&self) -> usize {
+        self.times.iter().flatten().filter(|time| time.is_some()).count()
+    }
+
+    /// Earliest and latest activation times, `None` without activated cells
+    pub fn range(&self) -> Option<(i32, i32)> {
+        let mut times = self.times.iter().flatten().flatten().copied();
+        let first = times.next()?;
+        Some(times.fold((first, first), |(earliest, latest), time| (earliest.min(time), latest.max(time))))
+    }
+
+    /// Color map of the activation times, from 1 for the earliest activated
+    /// cells down to `LATEST_VALUE` for the latest ones, and 0 for the cells
+    /// never activated, to be saved or drawn like any other color map
+    pub fn colored_map(&self) -> ColoredMap {
+        let (earliest, latest) = self.range().unwrap_or((0, 0));
+        let span = (latest - earliest).max(1) as f32;
+        let value = |time: &Option<i32>| match time {
+            Some(time) => 1.0 - (time - earliest) as f32 / span * (1.0 - LATEST_VALUE),
+            None => 0.0,
+        };
+        self.times.iter().map(|row| row.iter().map(value).collect()).collect()
+    }
+}
@@ -0,0 +1,176 @@
+/// Tracking of the active areas of a universe
+/// Once a pattern has formed most of the universe barely changes. With
+/// activity tracking the universe is split in square tiles and a tile is only
+/// evolved if it or one of its eight neighbours changed by at least `epsilon`
+/// during the previous evolution; the cells of the other tiles keep their
+/// concentrations. A change spreads by at most one cell per evolution, so a
+/// tile next to an active one is always evolved in time. Skipping quiescent
+/// tiles is an approximation: their concentrations would still have changed
+/// by less than `epsilon` per evolution
+/// The tiles that changed are also remembered until the color map is drawn
+/// again, see `Simulation::dirty_cells`, so that only they are uploaded to
+/// the textures of the window
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Boundary, Position};
+
+/// Settings of activity tracking, see `Simulation::with_activity_tracking`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActivityTracking {
+    /// Number of cells on each side of a tile
+    pub tile_size: usize,
+    /// Largest change of a concentration for which a tile is quiescent
+    pub epsilon: f64,
+}
+
+impl Default for ActivityTracking {
+    fn default() -> Self {
+        ActivityTracking { tile_size: 32, epsilon: 1e-6 }
+    }
+}
+
+/// Changes of the tiles of a universe during the last evolution
+#[derive(Debug, Clone)]
+pub struct Activity {
+    tracking: ActivityTracking,
+    dimensions: Position,
+    /// Number of rows and columns of tiles
+    tiles: Position,
+    /// Largest change of a concentration in every tile, row by row, infinite
+    /// for the tiles that must be evolved whatever their neighbours
+    changes: Vec<f64>,
+    /// Number of tiles evolved during the last evolution
+    evolved: usize,
+    /// Whether every tile changed since `clear_dirty` was last called
+    dirty: Vec<bool>,
+}
+
+impl Activity {
+    /// Activity of a universe of the given dimensions whose tiles all have to
+    /// be evolved
+    pub fn new(tracking: ActivityTracking, dimensions: Position) -> Activity {
+        let tracking = ActivityTracking { tile_size: tracking.tile_size.max(1), ..tracking };
+        let tiles = Position {
+            row: dimensions.row.div_ceil(tracking.tile_size),
+            col: dimensions.col.div_ceil(tracking.tile_size),
+        };
+        let count = tiles.row * tiles.col;
+        Activity {
+            tracking,
+            dimensions,
+            tiles,
+            changes: vec![f64::INFINITY; count],
+            evolved: count,
+            dirty: vec![true; count],
+        }
+    }
+
+    pub fn tracking(&self) -> ActivityTracking {
+        self.tracking
+    }
+
+    /// Number of rows and columns of tiles
+    pub fn tiles(&self) -> Position {
+        self.tiles
+    }
+
+    /// Fraction of the tiles evolved during the last evolution, 1 before the
+    /// first one
+    pub fn evolved_fraction(&self) -> f64 {
+        match self.changes.len() {
+            0 => 1.0,
+            count => self.evolved as f64 / count as f64,
+        }
+    }
+
+    /// Evolve every tile during the next evolution, e.g. after a change of
+    /// the parameters
+    pub fn wake_all(&mut self) {
+        self.changes.fill(f64::INFINITY);
+        self.dirty.fill(true);
+    }
+
+    /// Evolve the tile of `position` and its neighbours during the next
+    /// evolution, e.g. after a cell was changed
+    /// Positions outside of the universe are ignored
+    pub fn wake(&mut self, position: Position) {
+        if position.row < self.dimensions.row && position.col < self.dimensions.col {
+            let tile = position.row / self.tracking.tile_size * self.tiles.col + position.col / self.tracking.tile_size;
+            self.changes[tile] = f64::INFINITY;
+            self.dirty[tile] = true;
+        }
+    }
+
+    /// Count a change of the cell at `position` made between two evolutions,
+    /// e.g. by the couplings of layers, in the change of its tile, infinite
+    /// for NaN, so the tile is only woken if the change reaches `epsilon`
+    /// Positions outside of the universe are ignored
+    pub(crate) fn add_change(&mut self, position: Position, change: f64) {
+        if position.row < self.dimensions.row && position.col < self.dimensions.col {
+            let tile = position.row / self.tracking.tile_size * self.tiles.col + position.col / self.tracking.tile_size;
+            let change = if change.is_nan() { f64::INFINITY } else { change };
+            self.changes[tile] = self.changes[tile].max(change);
+            self.dirty[tile] |= change > 0.0;
+        }
+    }
+
+    /// Rows and columns of the cells of the tiles that changed since
+    /// `clear_dirty` was last called, every tile being dirty at first
+    pub fn dirty(&self) -> Vec<(Range<usize>, Range<usize>)> {
+        (0..self.dirty.len()).filter(|tile| self.dirty[*tile]).map(|tile| self.tile_cells(tile)).collect()
+    }
+
+    /// Forget the tiles that changed, e.g. once they are drawn
+    pub fn clear_dirty(&mut self) {
+        self.dirty.fill(false);
+    }
+
+    /// Same activity for a universe of `dimensions`, with every tile woken if
+    /// the dimensions changed
+    pub(crate) fn fit(&mut self, dimensions: Position) {
+        if dimensions != self.dimensions {
+            *self = Activity::new(self.tracking, dimensions);
+        }
+    }
+
+    /// Whether every tile has to be evolved during the next evolution, in
+    /// the order of `tile_cells`
+    /// With periodic edges the tiles on opposite edges are neighbours
+    pub(crate) fn due(&self, boundary: Boundary) -> Vec<bool> {
+        let active = |row: usize, col: usize| self.changes[row * self.tiles.col + col] >= self.tracking.epsilon;
+        // Rows or columns of the tiles next to `index`, itself included
+        let around = |index: usize, count: usize| -> Vec<usize> {
+            match boundary {
+                Boundary::Closed => (index.saturating_sub(1)..(index + 2).min(count)).collect(),
+                Boundary::Periodic => vec![(index + count - 1) % count, index, (index + 1) % count],
+            }
+        };
+        (0..self.tiles.row)
+            .flat_map(|row| (0..self.tiles.col).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let cols = around(col, self.tiles.col);
+                around(row, self.tiles.row).into_iter().any(|r| cols.iter().any(|c| active(r, *c)))
+            })
+            .collect()
+    }
+
+    /// Rows and columns of the cells of the tile `tile`
+    pub(crate) fn tile_cells(&self, tile: usize) -> (Range<usize>, Range<usize>) {
+        let size = self.tracking.tile_size;
+        let (row, col) = (tile / self.tiles.col * size, tile % self.tiles.col * size);
+        (row..(row + size).min(self.dimensions.row), col..(col + size).min(self.dimensions.col))
+    }
+
+    /// Record the largest change of every tile during an evolution, 0 for
+    /// the tiles that were skipped and infinite for NaN
+    pub(crate) fn record(&mut self, changes: Vec<f64>, evolved: usize) {
+        for (dirty, change) in self.dirty.iter_mut().zip(&changes) {
+            *dirty |= *change > 0.0;
+        }
+        self.changes = changes;
+        self.evolved = evolved;
+    }
+}
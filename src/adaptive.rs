@@ -0,0 +1,328 @@
+/// Adaptive refinement of the grid
+/// An experimental solver for universes whose patterns leave most of the
+/// cells flat, e.g. spots. The universe is split in square tiles which are
+/// the roots of quad trees of uniform depth: a tile at level `L` holds one
+/// cell for every block of `2^L` by `2^L` cells of the universe. Every
+/// `interval` generations, the tiles whose concentrations change steeply
+/// between neighbouring cells are refined one level, and those which are flat
+/// coarsened one level, averaging blocks of four cells; refined cells copy
+/// the coarse cell they split. A cell at level `L` diffuses with rates
+/// divided by `4^L`, as the Laplacian scales with the square of the spacing.
+/// On the edges of a tile, a neighbour in a finer tile is the average of the
+/// cells covering its block and one in a coarser tile the cell covering it,
+/// which is how the halos are exchanged between levels. With every tile at
+/// level 0 the evolution is that of `reference`
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::evolve_scaled_cell;
+use crate::{
+    color_universe, initialize_universe_with_rng, Boundary, Cell, ColoredMap, Float, Parameters, Position,
+    SimulationError, Universe,
+};
+
+/// Settings of the adaptive refinement
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveConfig {
+    /// Number of cells on each side of a tile at level 0, a multiple of
+    /// `2^max_level` dividing the dimensions of the universe
+    pub tile_size: usize,
+    /// Coarsest level of the tiles
+    pub max_level: u32,
+    /// Change of a concentration between neighbouring cells, per cell of the
+    /// universe, over which a tile is refined
+    pub refine: f64,
+    /// Change under which a tile is coarsened
+    pub coarsen: f64,
+    /// Generations between two adaptations of the levels
+    pub interval: i32,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        AdaptiveConfig { tile_size: 32, max_level: 3, refine: 0.02, coarsen: 0.002, interval: 10 }
+    }
+}
+
+impl AdaptiveConfig {
+    /// Check that tiles of this size at every level fit a universe of
+    /// `dimensions`
+    pub fn validate(&self, dimensions: Position) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidAdaptive(message));
+        let Some(block) = 1usize.checked_shl(self.max_level).filter(|block| *block <= self.tile_size) else {
+            return error(format!("tiles of {} cells cannot be coarsened {} times", self.tile_size, self.max_level));
+        };
+        if !self.tile_size.is_multiple_of(block) {
+            return error(format!("the tile size must be a multiple of {block}"));
+        }
+        if !(dimensions.row.is_multiple_of(self.tile_size) && dimensions.col.is_multiple_of(self.tile_size)) {
+            return error(format!(
+                "the dimensions of {}x{} cells are not multiples of the tile size {}",
+                dimensions.row, dimensions.col, self.tile_size
+            ));
+        }
+        if self.coarsen.is_nan() || self.refine.is_nan() || self.coarsen > self.refine {
+            return error("the threshold of coarsening must not be over that of refining".to_string());
+        }
+        if self.interval < 1 {
+            return error("the interval must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Tile of the universe at one level
+#[derive(Debug, Clone)]
+struct Tile {
+    level: u32,
+    /// Cells of the tile, row by row, each covering `2^level` cells of the
+    /// universe on each side
+    cells: Vec<Cell<f64>>,
+}
+
+impl Tile {
+    /// Number of cells on each side of the tile
+    fn side(&self, tile_size: usize) -> usize {
+        tile_size >> self.level
+    }
+}
+
+/// Simulation on an adaptive grid
+#[derive(Debug, Clone)]
+pub struct AdaptiveSimulation {
+    parameters: Parameters,
+    dimensions: Position,
+    boundary: Boundary,
+    config: AdaptiveConfig,
+    /// Number of rows and columns of tiles
+    tiles_dimensions: Position,
+    /// Tiles, row by row
+    tiles: Vec<Tile>,
+    generation: i32,
+}
+
+impl AdaptiveSimulation {
+    /// Simulation starting from `universe`, with every tile then coarsened
+    /// as far as it is flat
+    /// Fails if the parameters are invalid or the tiles do not fit the
+    /// universe
+    pub fn new<T: Float>(
+        parameters: Parameters,
+        boundary: Boundary,
+        config: AdaptiveConfig,
+        universe: &Universe<T>,
+    ) -> Result<AdaptiveSimulation, SimulationError> {
+        parameters.validate()?;
+        let dimensions = Position { row: universe.len(), col: universe.first().map_or(0, Vec::len) };
+        crate::error::check_dimensions(universe, dimensions)?;
+        config.validate(dimensions)?;
+
+        let tiles_dimensions =
+            Position { row: dimensions.row / config.tile_size, col: dimensions.col / config.tile_size };
+        let mut tiles = Vec::with_capacity(tiles_dimensions.row * tiles_dimensions.col);
+        for tile_row in 0..tiles_dimensions.row {
+            for tile_col in 0..tiles_dimensions.col {
+                let (rows, cols) = (tile_row * config.tile_size, tile_col * config.tile_size);
+                let cells = universe[rows..rows + config.tile_size]
+                    .iter()
+                    .flat_map(|row| row[cols..cols + config.tile_size].iter().map(|cell| cell.cast()))
+                    .collect();
+                tiles.push(Tile { level: 0, cells });
+            }
+        }
+        let mut simulation =
+            AdaptiveSimulation { parameters, dimensions, boundary, config, tiles_dimensions, tiles, generation: 0 };
+        for _ in 0..config.max_level {
+            simulation.adapt();
+        }
+        Ok(simulation)
+    }
+
+    /// Simulation of an empty universe with `n` random initial cells drawn
+    /// from `rng`, see `initialize_universe_with_rng`
+    pub fn random<R: Rng + ?Sized>(
+        parameters: Parameters,
+        dimensions: Position,
+        boundary: Boundary,
+        config: AdaptiveConfig,
+        n: usize,
+        rng: &mut R,
+    ) -> Result<AdaptiveSimulation, SimulationError> {
+        let (universe, _): (Universe<f64>, _) = initialize_universe_with_rng(&dimensions, n, rng);
+        AdaptiveSimulation::new(parameters, boundary, config, &universe)
+    }
+
+    pub fn parameters(&self) -> Parameters {
+        self.parameters
+    }
+
+    pub fn dimensions(&self) -> Position {
+        self.dimensions
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn config(&self) -> AdaptiveConfig {
+        self.config
+    }
+
+    /// Number of evolutions computed so far
+    pub fn generation(&self) -> i32 {
+        self.generation
+    }
+
+    /// Level of every tile, row by row
+    pub fn levels(&self) -> Vec<Vec<u32>> {
+        self.tiles.chunks(self.tiles_dimensions.col.max(1)).map(|row| row.iter().map(|tile| tile.level).collect()).collect()
+    }
+
+    /// Number of cells computed at every evolution, that of a uniform grid
+    /// being the product of the dimensions
+    pub fn cell_count(&self) -> usize {
+        self.tiles.iter().map(|tile| tile.cells.len()).sum()
+    }
+
+    /// Universe with every cell of the universe taking the value of the cell
+    /// of its tile covering it
+    pub fn universe(&self) -> Universe {
+        (0..self.dimensions.row)
+            .map(|row| (0..self.dimensions.col).map(|col| self.covering(row, col).cast()).collect())
+            .collect()
+    }
+
+    /// Color map of `universe`
+    pub fn colored_map(&self) -> ColoredMap {
+        color_universe(&self.universe())
+    }
+
+    /// Index of the tile holding the cell at `row`, `col` of the universe
+    fn tile_index(&self, row: usize, col: usize) -> usize {
+        (row / self.config.tile_size) * self.tiles_dimensions.col + col / self.config.tile_size
+    }
+
+    /// Cell of its tile covering the cell at `row`, `col` of the universe
+    fn covering(&self, row: usize, col: usize) -> Cell<f64> {
+        let tile = &self.tiles[self.tile_index(row, col)];
+        let side = tile.side(self.config.tile_size);
+        let (row, col) = ((row % self.config.tile_size) >> tile.level, (col % self.config.tile_size) >> tile.level);
+        tile.cells[row * side + col]
+    }
+
+    /// Concentrations of the block of `2^level` cells on each side of the
+    /// universe starting at `row`, `col`: the average of the cells covering
+    /// it in a finer tile, the cell covering it otherwise
+    fn block(&self, row: usize, col: usize, level: u32) -> Cell<f64> {
+        let tile = &self.tiles[self.tile_index(row, col)];
+        if tile.level >= level {
+            return self.covering(row, col);
+        }
+        let (side, count) = (tile.side(self.config.tile_size), 1usize << (level - tile.level));
+        let (first_row, first_col) =
+            ((row % self.config.tile_size) >> tile.level, (col % self.config.tile_size) >> tile.level);
+        let mut sum = Cell { a: 0.0, b: 0.0 };
+        for cells in tile.cells[first_row * side..].chunks(side).take(count) {
+            for cell in &cells[first_col..first_col + count] {
+                sum.a += cell.a;
+                sum.b += cell.b;
+            }
+        }
+        let cells = (count * count) as f64;
+        Cell { a: sum.a / cells, b: sum.b / cells }
+    }
+
+    /// Position in the universe `offset` blocks of `block` cells away from
+    /// `index`, along a side of `size` cells, if there is a cell there
+    fn offset(&self, index: usize, offset: isize, block: usize, size: usize) -> Option<usize> {
+        let moved = index as isize + offset * block as isize;
+        match self.boundary {
+            Boundary::Closed => usize::try_from(moved).ok().filter(|moved| *moved < size),
+            Boundary::Periodic => Some(moved.rem_euclid(size as isize) as usize),
+        }
+    }
+
+    /// Compute one evolution of every tile, adapting the levels when due
+    pub fn step(&mut self) {
+        let tile_size = self.config.tile_size;
+
+        let mut evolved = self.tiles.clone();
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let side = tile.side(tile_size);
+            let block = 1usize << tile.level;
+            let scale = (block * block) as f64;
+            let origin = Position {
+                row: (index / self.tiles_dimensions.col) * tile_size,
+                col: (index % self.tiles_dimensions.col) * tile_size,
+            };
+            for (position, cell) in tile.cells.iter().enumerate() {
+                let (row, col) = (position / side, position % side);
+                evolved[index].cells[position] = evolve_scaled_cell(&self.parameters, scale, *cell, |d_row, d_col| {
+                    let (neighbour_row, neighbour_col) = (row as isize + d_row, col as isize + d_col);
+                    if (0..side as isize).contains(&neighbour_row) && (0..side as isize).contains(&neighbour_col) {
+                        return Some(tile.cells[neighbour_row as usize * side + neighbour_col as usize]);
+                    }
+                    let universe_row = self.offset(origin.row + row * block, d_row, block, self.dimensions.row)?;
+                    let universe_col = self.offset(origin.col + col * block, d_col, block, self.dimensions.col)?;
+                    Some(self.block(universe_row, universe_col, tile.level))
+                });
+            }
+        }
+        self.tiles = evolved;
+        self.generation += 1;
+        if self.generation % self.config.interval == 0 {
+            self.adapt();
+        }
+    }
+
+    /// Compute `n` evolutions
+    pub fn run(&mut self, n: i32) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// Refine the steep tiles and coarsen the flat ones, one level each
+    fn adapt(&mut self) {
+        let tile_size = self.config.tile_size;
+        for tile in &mut self.tiles {
+            let side = tile.side(tile_size);
+            let spacing = (1usize << tile.level) as f64;
+            let change = |first: &Cell<f64>, second: &Cell<f64>| (first.a - second.a).abs().max((first.b - second.b).abs());
+            let mut steepest = 0.0f64;
+            for (position, cell) in tile.cells.iter().enumerate() {
+                let (row, col) = (position / side, position % side);
+                if col + 1 < side {
+                    steepest = steepest.max(change(cell, &tile.cells[position + 1]));
+                }
+                if row + 1 < side {
+                    steepest = steepest.max(change(cell, &tile.cells[position + side]));
+                }
+            }
+            let gradient = steepest / spacing;
+
+            if tile.level > 0 && (gradient.is_nan() || gradient > self.config.refine) {
+                // Every cell splits into four copies
+                let fine = side * 2;
+                tile.cells = (0..fine * fine).map(|index| tile.cells[(index / fine / 2) * side + (index % fine) / 2]).collect();
+                tile.level -= 1;
+            } else if tile.level < self.config.max_level && gradient < self.config.coarsen {
+                // Every block of four cells becomes their average
+                let coarse = side / 2;
+                tile.cells = (0..coarse * coarse)
+                    .map(|index| {
+                        let (row, col) = (index / coarse * 2, index % coarse * 2);
+                        let corners = [(row, col), (row, col + 1), (row + 1, col), (row + 1, col + 1)];
+                        let sum = corners.iter().fold(Cell { a: 0.0, b: 0.0 }, |sum, (row, col)| {
+                            let cell = tile.cells[row * side + col];
+                            Cell { a: sum.a + cell.a, b: sum.b + cell.b }
+                        });
+                        Cell { a: sum.a / 4.0, b: sum.b / 4.0 }
+                    })
+                    .collect();
+                tile.level += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+/// Spectral analysis of patterns
+/// The power spectrum of the B field gives the dominant spatial wavelength of
+/// a pattern, the main quantitative output of a run, and how isotropic the
+/// pattern is: spots and labyrinths have no preferred direction, stripes do
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+
+use crate::{Float, Position, Simulation, Universe};
+
+/// Power spectrum of the concentrations of B
+/// `power[row][col]` is the squared magnitude of the discrete Fourier
+/// transform at `row` cycles along the rows and `col` cycles along the
+/// columns (frequencies above half the size are the negative ones). The mean
+/// is subtracted first, so the power at frequency 0 is 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    pub dimensions: Position,
+    pub power: Vec<Vec<f64>>,
+}
+
+/// Dominant wavelength of a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Wavelength {
+    /// Distance between two crests, in cells
+    pub wavelength: f64,
+    /// Inverse of the wavelength, in cycles per cell
+    pub wavenumber: f64,
+    /// Share of the power of the spectrum around the dominant wavenumber
+    pub power: f64,
+    /// 1 when the power around the dominant wavenumber is spread evenly over
+    /// all the directions, 0 when it is concentrated in one direction, as for
+    /// parallel stripes
+    pub isotropy: f64,
+}
+
+impl Spectrum {
+    /// Power spectrum of the B concentrations of `universe`
+    pub fn of<T: Float>(universe: &Universe<T>) -> Spectrum {
+        let rows = universe.len();
+        let cols = universe.first().map_or(0, |row| row.len());
+        let dimensions = Position { row: rows, col: cols };
+        if rows == 0 || cols == 0 {
+            return Spectrum { dimensions, power: Vec::new() };
+        }
+
+        let cells = universe.iter().flatten();
+        let mean = cells.clone().map(|cell| cell.b.to_f64()).sum::<f64>() / (rows * cols) as f64;
+        let mut field: Vec<Complex<f64>> = cells
+            .map(|cell| Complex::new(cell.b.to_f64() - mean, 0.0))
+            .collect();
+
+        // Transform every row, then every column through a transposed copy
+        let mut planner = FftPlanner::new();
+        planner.plan_fft_forward(cols).process(&mut field);
+        let mut transposed: Vec<Complex<f64>> = (0..rows * cols)
+            .map(|index| field[(index % rows) * cols + index / rows])
+            .collect();
+        planner.plan_fft_forward(rows).process(&mut transposed);
+
+        let power = (0..rows)
+            .map(|row| (0..cols).map(|col| transposed[col * rows + row].norm_sqr()).collect())
+            .collect();
+        Spectrum { dimensions, power }
+    }
+
+    /// Frequency of `index` among `size` samples, in cycles per cell
+    fn frequency(index: usize, size: usize) -> f64 {
+        let signed = if index <= size / 2 { index as f64 } else { index as f64 - size as f64 };
+        signed / size as f64
+    }
+
+    /// Wavenumber and direction, in radians, of every frequency with its power
+    fn frequencies(&self) -> impl Iterator<Item = (f64, f64, f64)> + '_ {
+        let Position { row: rows, col: cols } = self.dimensions;
+        self.power.iter().enumerate().flat_map(move |(row, powers)| {
+            let ky = Spectrum::frequency(row, rows);
+            powers.iter().enumerate().map(move |(col, power)| {
+                let kx = Spectrum::frequency(col, cols);
+                (kx.hypot(ky), ky.atan2(kx), *power)
+            })
+        })
+    }
+
+    /// Width of the bins of `radial_profile`, in cycles per cell
+    fn bin_width(&self) -> f64 {
+        1.0 / self.dimensions.row.max(self.dimensions.col).max(1) as f64
+    }
+
+    /// Power summed over rings of wavenumbers
+    /// Bin `i` holds the wavenumbers from `i - 0.5` to `i + 0.5` cycles over
+    /// the largest side of the universe
+    pub fn radial_profile(&self) -> Vec<f64> {
+        let width = self.bin_width();
+        let bins = (0.5f64.hypot(0.5) / width).round() as usize + 1;
+        let mut profile = vec![0.0; bins];
+        for (wavenumber, _, power) in self.frequencies() {
+            profile[((wavenumber / width).round() as usize).min(bins - 1)] += power;
+        }
+        profile
+    }
+
+    /// Dominant wavelength, from the ring of wavenumbers holding the most
+    /// power and its two neighbours
+    /// None for a uniform universe
+    pub fn dominant(&self) -> Option<Wavelength> {
+        let profile = self.radial_profile();
+        let total: f64 = profile.iter().sum();
+        let (peak, _) = profile
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        if total <= f64::EPSILON {
+            return None;
+        }
+
+        let width = self.bin_width();
+        let (low, high) = ((peak as f64 - 1.5) * width, (peak as f64 + 1.5) * width);
+        let (mut power, mut weighted, mut direction) = (0.0, 0.0, Complex::new(0.0, 0.0));
+        for (wavenumber, angle, value) in self.frequencies() {
+            if wavenumber > 0.0 && (low..high).contains(&wavenumber) {
+                power += value;
+                weighted += value * wavenumber;
+                // The spectrum is symmetric, so directions are taken modulo pi
+                direction += Complex::from_polar(value, 2.0 * angle);
+            }
+        }
+
+        let wavenumber = weighted / power;
+        Some(Wavelength {
+            wavelength: 1.0 / wavenumber,
+            wavenumber,
+            power: power / total,
+            isotropy: 1.0 - direction.norm() / power,
+        })
+    }
+}
+
+/// Dominant wavelength of the B concentrations of `universe`, see
+/// `Spectrum::dominant`
+pub fn dominant_wavelength<T: Float>(universe: &Universe<T>) -> Option<Wavelength> {
+    Spectrum::of(universe).dominant()
+}
+
+impl<T: Float> Simulation<T> {
+    /// Dominant wavelength of the current universe, see `dominant_wavelength`
+    pub fn dominant_wavelength(&self) -> Option<Wavelength> {
+        dominant_wavelength(self.universe())
+    }
+}
@@ -0,0 +1,591 @@
+/// Animated exports
+/// The color maps of a run are captured every `interval` generations, as
+/// the frames of `export::FrameSequence` are, and encoded at the end of the
+/// run into one animated file whose format follows its extension: `.gif`
+/// for GIF, with the color map sampled at 256 evenly spaced values, `.png`
+/// or `.apng` for APNG and `.webp` for lossless animated WebP, both with the
+/// full colors of the color map. Every frame is shown for `delay`
+/// milliseconds and the animations loop forever. The encoders work on any
+/// writer, saving to a file requires the `fs` feature
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::Write;
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::Colormap;
+use crate::error::check_interval;
+use crate::{ColoredMap, SimulationError};
+
+/// Largest number of pixels on each side of a WebP frame
+const MAX_WEBP_SIZE: usize = 1 << 14;
+/// Largest number of bits of the codes of an LZW stream of a GIF
+const MAX_LZW_BITS: u32 = 12;
+/// Largest number of bits of the prefix codes of a WebP frame, and of the
+/// code of their code lengths
+const MAX_CODE_LENGTH: u8 = 15;
+const MAX_CODE_LENGTH_CODE_LENGTH: u8 = 7;
+/// Order in which the lengths of the code of the code lengths are written
+const CODE_LENGTH_ORDER: [usize; 19] = [17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+/// Format of an animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimationFormat {
+    /// 256 colors, the most widely supported
+    Gif,
+    /// Animated PNG
+    Apng,
+    /// Lossless animated WebP
+    WebP,
+}
+
+/// Extensions of the animations
+pub const ANIMATION_EXTENSIONS: [&str; 4] = ["gif", "png", "apng", "webp"];
+
+impl AnimationFormat {
+    /// Format given by the extension of `path`, see `ANIMATION_EXTENSIONS`
+    pub fn from_path(path: &Path) -> Option<AnimationFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "png" | "apng" => Some(AnimationFormat::Apng),
+            "webp" => Some(AnimationFormat::WebP),
+            _ => None,
+        }
+    }
+}
+
+/// Settings of an animation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnimationConfig {
+    /// File the animation is written to, its format following its
+    /// extension
+    pub path: PathBuf,
+    /// A frame is captured every `interval` generations
+    pub interval: i32,
+    /// Milliseconds each frame is shown for
+    pub delay: u32,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        AnimationConfig { path: PathBuf::from("animation.gif"), interval: 10, delay: 40 }
+    }
+}
+
+impl AnimationConfig {
+    /// Fails if the interval is below 1
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        check_interval("animation frames", self.interval)
+    }
+}
+
+/// Frames of an animation being captured
+/// A GIF frame keeps one byte per pixel, the color map value rounded to
+/// 1/255, the others the red, green and blue of every pixel
+pub struct Animation {
+    config: AnimationConfig,
+    format: AnimationFormat,
+    colormap: Colormap,
+    width: usize,
+    height: usize,
+    frames: Vec<Vec<u8>>,
+}
+
+impl Animation {
+    /// Animation written to `config.path`, whose extension has to be one of
+    /// `ANIMATION_EXTENSIONS`, and whose interval has to be at least 1
+    pub fn new(config: AnimationConfig, colormap: Colormap) -> Result<Animation, SimulationError> {
+        config.validate()?;
+        let format = AnimationFormat::from_path(&config.path).ok_or_else(|| {
+            SimulationError::Unsupported(format!(
+                "unknown animation format of {}, expected one of: {}",
+                config.path.display(),
+                ANIMATION_EXTENSIONS.join(", ")
+            ))
+        })?;
+        Ok(Animation { config, format, colormap, width: 0, height: 0, frames: Vec::new() })
+    }
+
+    pub fn format(&self) -> AnimationFormat {
+        self.format
+    }
+
+    /// File the animation is written to
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Number of frames captured so far
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Whether a frame has to be captured after computing `generation`
+    pub fn is_due(&self, generation: i32) -> bool {
+        generation % self.config.interval == 0
+    }
+
+    /// Capture `colored_map` as the next frame
+    /// Fails if its dimensions differ from those of the first frame
+    pub fn push(&mut self, colored_map: &ColoredMap) -> Result<(), SimulationError> {
+        let (width, height) = (colored_map.first().map_or(0, Vec::len), colored_map.len());
+        if self.frames.is_empty() {
+            (self.width, self.height) = (width, height);
+        } else if (width, height) != (self.width, self.height) {
+            return Err(SimulationError::Format(format!(
+                "the frames of an animation are {}x{} pixels, not {width}x{height}",
+                self.width, self.height
+            )));
+        }
+        let values = colored_map.iter().flatten();
+        let frame = match self.format {
+            AnimationFormat::Gif => values.map(|value| level(*value)).collect(),
+            AnimationFormat::Apng | AnimationFormat::WebP => {
+                values.flat_map(|value| self.colormap.color(*value)).collect()
+            }
+        };
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    /// Encode the frames captured so far to `writer`
+    /// Fails if there is no frame or if they are too large for the format
+    pub fn encode(&self, writer: impl Write) -> Result<(), SimulationError> {
+        if self.frames.is_empty() {
+            return Err(SimulationError::Format("an animation needs at least one frame".to_string()));
+        }
+        match self.format {
+            AnimationFormat::Gif => {
+                let palette: Vec<[u8; 3]> = (0..=255).map(|level| self.colormap.color(level as f32 / 255.0)).collect();
+                encode_gif(&self.frames, self.width, self.height, &palette, self.config.delay, writer)
+            }
+            AnimationFormat::Apng => encode_apng(&self.frames, self.width, self.height, self.config.delay, writer),
+            AnimationFormat::WebP => encode_webp(&self.frames, self.width, self.height, self.config.delay, writer),
+        }
+    }
+
+    /// Write the animation to its file
+    #[cfg(feature = "fs")]
+    pub fn save(&self) -> Result<(), SimulationError> {
+        let mut writer = BufWriter::new(File::create(&self.config.path)?);
+        self.encode(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+}
+
+/// Color map value rounded to 1/255
+fn level(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn too_large(format: &str, width: usize, height: usize) -> SimulationError {
+    SimulationError::Unsupported(format!("{format} frames cannot be {width}x{height} pixels"))
+}
+
+/// Write `frames` of one palette index per pixel as a looping GIF
+fn encode_gif(
+    frames: &[Vec<u8>],
+    width: usize,
+    height: usize,
+    palette: &[[u8; 3]],
+    delay: u32,
+    mut writer: impl Write,
+) -> Result<(), SimulationError> {
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(too_large("GIF", width, height));
+    };
+    // Delays are counted in hundredths of a second
+    let delay = (delay / 10).min(u16::MAX as u32) as u16;
+
+    writer.write_all(b"GIF89a")?;
+    writer.write_all(&width.to_le_bytes())?;
+    writer.write_all(&height.to_le_bytes())?;
+    // Global color table of 256 colors, no background, square pixels
+    writer.write_all(&[0xf7, 0, 0])?;
+    for color in palette {
+        writer.write_all(color)?;
+    }
+    // Loop forever
+    writer.write_all(&[0x21, 0xff, 11])?;
+    writer.write_all(b"NETSCAPE2.0")?;
+    writer.write_all(&[3, 1, 0, 0, 0])?;
+
+    for frame in frames {
+        // Graphic control extension: frames replace each other after `delay`
+        writer.write_all(&[0x21, 0xf9, 4, 0x04])?;
+        writer.write_all(&delay.to_le_bytes())?;
+        writer.write_all(&[0, 0])?;
+        // Image descriptor covering the screen, with the global colors
+        writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        writer.write_all(&[0, 8])?;
+        for block in lzw(frame).chunks(255) {
+            writer.write_all(&[block.len() as u8])?;
+            writer.write_all(block)?;
+        }
+        writer.write_all(&[0])?;
+    }
+    writer.write_all(&[0x3b])?;
+    Ok(())
+}
+
+/// Bits written from the least significant one, as GIF and WebP do
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Write the `count` lowest bits of `bits`
+    fn write(&mut self, bits: u32, count: u32) {
+        self.buffer |= (bits as u64 & ((1 << count) - 1)) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Bytes written, the last one padded with zeros
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// LZW stream of the 8 bits indices `indices`, as in GIF
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let (clear, end) = (256u32, 257u32);
+    let mut bits = BitWriter::default();
+    let mut codes: HashMap<(u32, u8), u32> = HashMap::new();
+    let (mut next, mut size) = (end + 1, 9);
+    bits.write(clear, size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        bits.write(end, size);
+        return bits.finish();
+    };
+    let mut prefix = first as u32;
+    for &index in rest {
+        if let Some(&code) = codes.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        bits.write(prefix, size);
+        if next < 1 << MAX_LZW_BITS {
+            if next == 1 << size {
+                size += 1;
+            }
+            codes.insert((prefix, index), next);
+            next += 1;
+        } else {
+            bits.write(clear, size);
+            codes.clear();
+            (next, size) = (end + 1, 9);
+        }
+        prefix = index as u32;
+    }
+    bits.write(prefix, size);
+    bits.write(end, size);
+    bits.finish()
+}
+
+/// Write `frames` of red, green and blue bytes as a looping APNG
+fn encode_apng(
+    frames: &[Vec<u8>],
+    width: usize,
+    height: usize,
+    delay: u32,
+    writer: impl Write,
+) -> Result<(), SimulationError> {
+    let (Ok(width), Ok(height)) = (u32::try_from(width), u32::try_from(height)) else {
+        return Err(too_large("APNG", width, height));
+    };
+    let failed = |error: png::EncodingError| SimulationError::Format(format!("could not encode the APNG: {error}"));
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0).map_err(failed)?;
+    encoder.set_frame_delay(delay.min(u16::MAX as u32) as u16, 1000).map_err(failed)?;
+    let mut writer = encoder.write_header().map_err(failed)?;
+    for frame in frames {
+        writer.write_image_data(frame).map_err(failed)?;
+    }
+    writer.finish().map_err(failed)
+}
+
+/// Write a RIFF chunk, padded to an even size
+fn write_chunk(output: &mut Vec<u8>, name: &[u8; 4], payload: &[u8]) {
+    output.extend_from_slice(name);
+    output.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    output.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        output.push(0);
+    }
+}
+
+/// 24 bits little-endian integer
+fn u24(value: usize) -> [u8; 3] {
+    let bytes = (value as u32).to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+/// Write `frames` of red, green and blue bytes as a looping lossless WebP
+fn encode_webp(
+    frames: &[Vec<u8>],
+    width: usize,
+    height: usize,
+    delay: u32,
+    mut writer: impl Write,
+) -> Result<(), SimulationError> {
+    if width == 0 || height == 0 || width > MAX_WEBP_SIZE || height > MAX_WEBP_SIZE {
+        return Err(too_large("WebP", width, height));
+    }
+    let mut chunks = Vec::new();
+    // Extended header with the animation flag, and the size of the canvas
+    let mut header = vec![0x02, 0, 0, 0];
+    header.extend_from_slice(&u24(width - 1));
+    header.extend_from_slice(&u24(height - 1));
+    write_chunk(&mut chunks, b"VP8X", &header);
+    // White background, looping forever
+    write_chunk(&mut chunks, b"ANIM", &[0xff, 0xff, 0xff, 0xff, 0, 0]);
+    for frame in frames {
+        let mut payload = vec![0; 6];
+        payload.extend_from_slice(&u24(width - 1));
+        payload.extend_from_slice(&u24(height - 1));
+        payload.extend_from_slice(&u24(delay.min((1 << 24) - 1) as usize));
+        // Frames replace each other without blending
+        payload.push(0x02);
+        write_chunk(&mut payload, b"VP8L", &vp8l(frame, width, height));
+        write_chunk(&mut chunks, b"ANMF", &payload);
+    }
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(chunks.len() as u32 + 4).to_le_bytes())?;
+    writer.write_all(b"WEBP")?;
+    writer.write_all(&chunks)?;
+    Ok(())
+}
+
+/// Lossless WebP bitstream of an image of red, green and blue bytes
+/// The green is subtracted from the red and the blue, and every channel is
+/// coded with its own prefix code, without backward references
+fn vp8l(pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    bits.write(0x2f, 8);
+    bits.write(width as u32 - 1, 14);
+    bits.write(height as u32 - 1, 14);
+    // No alpha, version 0
+    bits.write(0, 4);
+    // Subtract green transform, then no other transform
+    bits.write(1, 1);
+    bits.write(2, 2);
+    bits.write(0, 1);
+    // No color cache, a single group of prefix codes
+    bits.write(0, 1);
+    bits.write(0, 1);
+
+    let pixels: Vec<[u8; 3]> = pixels
+        .chunks_exact(3)
+        .map(|pixel| [pixel[0].wrapping_sub(pixel[1]), pixel[1], pixel[2].wrapping_sub(pixel[1])])
+        .collect();
+    let mut histograms = [[0u32; 256]; 3];
+    for pixel in &pixels {
+        for (histogram, value) in histograms.iter_mut().zip(pixel) {
+            histogram[*value as usize] += 1;
+        }
+    }
+    // Green, with the unused symbols of backward references, red and blue
+    let codes = [
+        write_prefix_code(&mut bits, &histograms[1], 256 + 24),
+        write_prefix_code(&mut bits, &histograms[0], 256),
+        write_prefix_code(&mut bits, &histograms[2], 256),
+    ];
+    // Opaque alpha and no distance, one symbol each
+    write_simple_code(&mut bits, &[255]);
+    write_simple_code(&mut bits, &[0]);
+
+    for pixel in &pixels {
+        for (code, value) in [(&codes[0], pixel[1]), (&codes[1], pixel[0]), (&codes[2], pixel[2])] {
+            let (bits_of, length) = code[value as usize];
+            bits.write(bits_of, length as u32);
+        }
+    }
+    bits.finish()
+}
+
+/// Write a prefix code of one or two symbols below 256
+/// With one symbol, it takes no bits; with two, the smaller one is coded 0
+fn write_simple_code(bits: &mut BitWriter, symbols: &[u8]) {
+    bits.write(1, 1);
+    bits.write(symbols.len() as u32 - 1, 1);
+    let large = symbols[0] > 1;
+    bits.write(large as u32, 1);
+    bits.write(symbols[0] as u32, if large { 8 } else { 1 });
+    if let Some(&second) = symbols.get(1) {
+        bits.write(second as u32, 8);
+    }
+}
+
+/// Write the prefix code of the symbols counted in `histogram`, of an
+/// alphabet of `size` symbols, and return the bits and length of the code of
+/// every symbol, ready to be written from the least significant bit
+fn write_prefix_code(bits: &mut BitWriter, histogram: &[u32; 256], size: usize) -> Vec<(u32, u8)> {
+    let used: Vec<u8> = (0..=255).filter(|symbol| histogram[*symbol as usize] > 0).collect();
+    if used.len() <= 2 {
+        write_simple_code(bits, &used);
+        let mut codes = vec![(0, 0); 256];
+        if let [first, second] = used[..] {
+            codes[first as usize] = (0, 1);
+            codes[second as usize] = (1, 1);
+        }
+        return codes;
+    }
+
+    let lengths = code_lengths(histogram, MAX_CODE_LENGTH);
+    // The lengths, with runs of zeros as symbols 17 and 18
+    let mut tokens: Vec<(u8, u32)> = Vec::new();
+    let mut symbol = 0;
+    while symbol < size {
+        let length = lengths.get(symbol).copied().unwrap_or(0);
+        let zeros = if length == 0 {
+            (symbol..size).take_while(|symbol| lengths.get(*symbol).copied().unwrap_or(0) == 0).count()
+        } else {
+            0
+        };
+        match zeros {
+            11.. => {
+                let run = zeros.min(138);
+                tokens.push((18, run as u32 - 11));
+                symbol += run;
+            }
+            3.. => {
+                tokens.push((17, zeros as u32 - 3));
+                symbol += zeros;
+            }
+            _ => {
+                tokens.push((length, 0));
+                symbol += 1;
+            }
+        }
+    }
+
+    let mut token_histogram = [0u32; 256];
+    for (token, _) in &tokens {
+        token_histogram[*token as usize] += 1;
+    }
+    let token_lengths = code_lengths(&token_histogram, MAX_CODE_LENGTH_CODE_LENGTH);
+    let token_codes = canonical_codes(&token_lengths);
+    let count = CODE_LENGTH_ORDER.iter().rposition(|token| token_lengths[*token] > 0).map_or(0, |last| last + 1).max(4);
+    bits.write(0, 1);
+    bits.write(count as u32 - 4, 4);
+    for token in &CODE_LENGTH_ORDER[..count] {
+        bits.write(token_lengths[*token] as u32, 3);
+    }
+    // Lengths given for the whole alphabet
+    bits.write(0, 1);
+    for (token, extra) in tokens {
+        let (code, length) = token_codes[token as usize];
+        bits.write(code, length as u32);
+        match token {
+            17 => bits.write(extra, 3),
+            18 => bits.write(extra, 7),
+            _ => {}
+        }
+    }
+    canonical_codes(&lengths)
+}
+
+/// Lengths of the codes of a prefix code of the symbols counted in
+/// `histogram`, none longer than `limit`, at least two symbols having one
+/// so that the code is complete
+fn code_lengths(histogram: &[u32; 256], limit: u8) -> Vec<u8> {
+    let mut counts: Vec<u32> = histogram.to_vec();
+    // A lone symbol is given partners that are never written
+    let missing = 2usize.saturating_sub(counts.iter().filter(|count| **count > 0).count());
+    for count in counts.iter_mut().filter(|count| **count == 0).take(missing) {
+        *count = 1;
+    }
+
+    // Counts are raised to at least `floor` until the tree is shallow enough
+    let mut floor = 1;
+    loop {
+        let lengths = huffman_lengths(&counts, floor);
+        if lengths.iter().all(|length| *length <= limit) {
+            return lengths;
+        }
+        floor *= 2;
+    }
+}
+
+/// Depth of every symbol counted in `counts` in a Huffman tree, counts
+/// below `floor` being raised to it
+fn huffman_lengths(counts: &[u32], floor: u32) -> Vec<u8> {
+    let mut lengths = vec![0u8; counts.len()];
+    // Nodes are the symbols, then the merged pairs, with their children
+    let mut children: Vec<Option<(usize, usize)>> = vec![None; counts.len()];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(symbol, count)| Reverse(((*count).max(floor) as u64, symbol)))
+        .collect();
+    while heap.len() > 1 {
+        let Reverse((first, first_node)) = heap.pop().unwrap();
+        let Reverse((second, second_node)) = heap.pop().unwrap();
+        children.push(Some((first_node, second_node)));
+        heap.push(Reverse((first + second, children.len() - 1)));
+    }
+
+    let mut stack: Vec<(usize, u8)> = heap.pop().map(|Reverse((_, root))| (root, 0)).into_iter().collect();
+    while let Some((node, depth)) = stack.pop() {
+        match children[node] {
+            Some((left, right)) => stack.extend([(left, depth + 1), (right, depth + 1)]),
+            None => lengths[node] = depth,
+        }
+    }
+    lengths
+}
+
+/// Canonical codes of the code lengths `lengths`, as in DEFLATE, with their
+/// bits reversed to be written from the least significant one
+fn canonical_codes(lengths: &[u8]) -> Vec<(u32, u8)> {
+    let mut counts = [0u32; 16];
+    for length in lengths.iter().filter(|length| **length > 0) {
+        counts[*length as usize] += 1;
+    }
+    let mut next = [0u32; 16];
+    let mut code = 0;
+    for length in 1..16 {
+        code = (code + counts[length - 1]) << 1;
+        next[length] = code;
+    }
+    lengths
+        .iter()
+        .map(|&length| {
+            if length == 0 {
+                return (0, 0);
+            }
+            let code = next[length as usize];
+            next[length as usize] += 1;
+            (code.reverse_bits() >> (32 - length as u32), length)
+        })
+        .collect()
+}
@@ -0,0 +1,2742 @@
+/// Interactive visualisation of the simulation with Bevy
+/// The application starts in `AppState::Setup`, where clicking places seeds
+/// of A and B, shown under the cursor and resized with the mouse wheel or
+/// `[` and `]`, e.g. in the universe without seeds of `--initial empty`;
+/// `Space` or `Enter` starts evolving the universe, and `Space` then pauses
+/// and resumes it. It is finished once the generation limit is reached or the
+/// statistics stop changing; a converged universe can still be resumed.
+/// The universe is evolved on a background thread, one generation after the
+/// other, so the frames keep coming however long a generation takes; its
+/// color map is drawn as a texture filling the window whenever a generation
+/// is done, reduced for universes larger than the window, see `render`, only
+/// the tiles that changed being uploaded, see `upload`. A
+/// generation taking longer than `PROGRESSIVE_DELAY` is drawn as it goes,
+/// one row of tiles at a time over the previous one, with an indicator of
+/// its progress at the bottom left of the window. On the web, without
+/// threads, it is evolved once per frame. Other simulations, e.g. with one parameter
+/// changed, can be evolved alongside and drawn next to it in a grid, sharing
+/// the same controls, and coupled to it as layers of one model.
+/// When paused or finished, pressing `S` saves a snapshot of the current
+/// universe and `E` exports the concentrations of A and B (with the `fs`
+/// feature), and the run can be rewound through the keyframes of `rewind`:
+/// dragging along the timeline bar at the bottom of the window, or with the
+/// arrow keys, one keyframe interval at a time, and `Home` and `End`. The
+/// run continues from the generation shown, forgetting the later ones.
+/// The number keys switch to the presets of `assets/presets.ron`, which is
+/// reloaded whenever it changes, as are the parameters of the configuration
+/// file the run was started with, and `-` and `=` halve and double the
+/// resolution of the universe. The backtick key opens the `console`, where
+/// `set`, `preset`, `seed` and `export` change the parameters, draw a new
+/// universe and save its color map without going through a key for each,
+/// the files saved without a path being named after the run, see `naming`,
+/// `brush` changes the seeds placed with the mouse and `session` saves the
+/// whole session, to be restored with `--session`. With the `fs` feature,
+/// `profile save` keeps the parameters, color map, dimensions and initial
+/// condition under a name, see `profiles`, and `profile` and `Tab` switch
+/// between the saved profiles.
+/// Dragging over a color map with `Ctrl` held copies the cells under the
+/// drag into a stamp, see `stamp`, which later clicks, during the setup or
+/// when paused, stamp instead of placing seeds; `R` and `M` turn and mirror
+/// it, and the `stamp` command copies, stamps and forgets it.
+/// A gamepad drives the application without a keyboard: the left stick pans
+/// the view and the right one zooms it, or nudges `f` and `k` while `West` is
+/// held, the triggers change the concentration of B of the seeds, `South`
+/// places one at the center of the view during the setup, `Start` pauses and
+/// resumes, `Select` starts again from a random universe, and the bumpers
+/// cycle through the presets. On a touch screen, one finger paints seeds
+/// during the setup, and two pinch to zoom the view and drag to pan it.
+/// When the rates are modulated, e.g. by a gradient of `f` and `k` drawing a
+/// phase diagram, a right click on a cell evolves its `f` and `k` everywhere
+/// in another simulation drawn next to the others, which later right clicks
+/// retune.
+/// A run whose concentrations blow up, see `blowup`, is paused with the
+/// cells that did highlighted in red and a diagnostic at the top left of the
+/// window; it can be resumed, e.g. after changing the parameters.
+/// `F3` shows the time spent in each stage of the simulation, see `hud`, and
+/// `T` switches between the concentrations and the activation times, when
+/// they are tracked, see `activation`. `C` switches between the color map and
+/// the composite of the species in the channels of the color, see `render`,
+/// whose legend is shown at the top right of the window.
+/// With the `control` feature, MIDI controllers and OSC messages change the
+/// parameters, the brush and the color map live, see `control`.
+/// Statistics of the universe are kept in the `SimulationStats` resource for
+/// other systems to read, and the parameters in `SimulationParameters`, where
+/// changes are applied to the simulation. Both can be inspected and edited
+/// in the inspector window of the `inspector` feature. The concentration of B
+/// of the universe is kept in the `MorphogenField` resource, see
+/// `morphogen`, for systems of other plugins to follow the pattern, and
+/// `SimulationState::frame` gives the pixels drawn, see `render::Frame`;
+/// `build` gives the application without running it, for them to be added.
+/// On the web the window is drawn into the canvas with id `ca-turing-pattern`
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+#[cfg(any(feature = "fs", not(target_arch = "wasm32")))]
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(feature = "fs")]
+use std::time::SystemTime;
+
+use bevy::app::AppExit;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::ecs::system::SystemState;
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::sprite::MaterialMesh2dBundle;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::activation::ActivationMap;
+#[cfg(feature = "fs")]
+use crate::activity::Activity;
+use crate::config::OutputConfig;
+use crate::deterministic::PortableRng;
+#[cfg(feature = "control")]
+use crate::control::{self, ControlChange, ControlConfig, ControlTarget};
+use crate::console::{CommandResult, ConsoleCommand, ConsolePlugin, RegisterCommand, CONSOLE_FONT};
+use crate::hud::HudPlugin;
+use crate::initial::InitialCondition;
+use crate::layers::{apply_couplings, Coupling};
+use crate::morphogen::{MorphogenField, Placement};
+#[cfg(feature = "fs")]
+use crate::naming::expand_path;
+#[cfg(feature = "fs")]
+use crate::noise::Noise;
+use crate::presets::PresetLibrary;
+use crate::profile;
+#[cfg(feature = "fs")]
+use crate::profiles::{self, Profile, ProfileLibrary};
+use crate::colormap::Colormap;
+use crate::render::{
+    self, composite_pixels, dirty_regions, region_pixels, DisplayMode, Frame, PixelFormat, RenderConfig,
+};
+use crate::rewind::RewindBuffer;
+#[cfg(feature = "fs")]
+use crate::session::Session;
+use crate::session::{Brush, View};
+use crate::region::Rect;
+use crate::stamp::Stamp;
+#[cfg(feature = "fs")]
+use crate::replay::{Recorder, ReplayEvent};
+use crate::replay::{apply_event, TimedEvent};
+#[cfg(feature = "fs")]
+use crate::export::{save_colored_map, save_fields};
+#[cfg(feature = "fs")]
+use crate::snapshot::Snapshot;
+use crate::stats::{Stats, CONVERGENCE_TOLERANCE};
+use crate::upload::{ColormapMaterial, TextureUploads, UploadPlugin};
+use crate::streams::{RngStream, StreamSeeds};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ColoredMap;
+use crate::{
+    initialize_universe_with_rng, Cell, EvolvedStep, Parameters, PendingStep, Position, Resampling, Simulation,
+    SimulationError, StepSummary, Universe,
+};
+
+/// Snapshot file used by the `S` key when no snapshot output is configured,
+/// `{run}` standing for the name of the run
+#[cfg(feature = "fs")]
+const DEFAULT_SNAPSHOT_PATH: &str = "{run}.bin";
+/// Fields file used by the `E` key when no fields output is configured
+#[cfg(feature = "fs")]
+const DEFAULT_FIELDS_PATH: &str = "{run}.npy";
+/// CSS selector of the canvas used on the web
+#[cfg(target_arch = "wasm32")]
+const CANVAS_SELECTOR: &str = "#ca-turing-pattern";
+/// Session file used by the `session` command when no session output is
+/// configured
+#[cfg(feature = "fs")]
+const DEFAULT_SESSION_PATH: &str = "{run}_session.bin";
+/// Time between two checks of the configuration file for changes
+#[cfg(feature = "fs")]
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Generations between two updates of `SimulationStats`, unless configured
+/// otherwise
+pub const DEFAULT_STATS_INTERVAL: i32 = 10;
+/// Time a generation computes before its finished rows are drawn, about
+/// three frames
+#[cfg(not(target_arch = "wasm32"))]
+const PROGRESSIVE_DELAY: Duration = Duration::from_millis(50);
+/// Size of the text of the progress indicator
+const PROGRESS_FONT_SIZE: f32 = 14.0;
+
+/// Lifecycle of the application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum AppState {
+    /// Seeds can be placed before the universe starts evolving
+    Setup,
+    Running,
+    Paused,
+    /// The universe reached the generation limit, converged or left [0,1] in
+    /// `Bounds::Strict` mode
+    Finished,
+}
+
+/// Simulation state
+/// Everything required to keep evolving the universe from the Bevy systems.
+/// While `Evolution` computes a generation the universes and color maps of
+/// the simulations are empty, see `Simulation::begin_step`
+#[derive(Resource)]
+pub struct SimulationState {
+    pub simulation: Simulation,
+    /// Simulations drawn next to `simulation` to compare them with it; they
+    /// are evolved and resized along with it, but presets, replayed changes
+    /// and exports only affect `simulation`
+    pub comparisons: Vec<Simulation>,
+    /// Couplings applied after every evolution between `simulation`, layer
+    /// 0, and the comparisons, layers 1, 2, …
+    pub couplings: Vec<Coupling>,
+    /// Evolution stops once `generation` reaches this value
+    pub max_generations: i32,
+    /// Files written on request
+    pub output: OutputConfig,
+    /// Changes still to be applied, e.g. from a replay, in order
+    pub events: Vec<TimedEvent>,
+    /// Name of the preset in use; its parameters are applied again when the
+    /// preset file changes
+    pub preset: Option<String>,
+    /// Generations between two updates of `SimulationStats`
+    pub stats_interval: i32,
+    /// Resolution of the color maps drawn in the window
+    pub render: RenderConfig,
+    /// Draw the activation times of `simulation`, see `activation`, instead
+    /// of its concentrations; toggled with `T`
+    pub show_activation: bool,
+    /// Index in `comparisons` of the simulation evolving the parameters
+    /// picked in the modulated `simulation`, see `pick_parameters`
+    pub probe: Option<usize>,
+    /// Recorder of the parameter changes made from the window
+    #[cfg(feature = "fs")]
+    pub recorder: Option<Recorder>,
+    /// Configuration file whose parameters are applied again whenever they
+    /// change in it
+    #[cfg(feature = "fs")]
+    pub config_file: Option<PathBuf>,
+    /// Keyframes of `simulation` to rewind it, not taken with comparisons
+    pub rewind: RewindBuffer,
+    /// Number of random cells of the universes drawn by the `seed` command
+    pub initial_cells: usize,
+    /// Generated initial condition of the universes drawn by the `seed`
+    /// command, `initial_cells` random cells if there is none
+    pub initial: Option<InitialCondition>,
+    /// Name of the run, see `naming`, titling the window and written instead
+    /// of `{run}` in the paths of the files saved from it
+    pub name: String,
+    /// Seed of the run, if it had one, kept in sessions
+    pub seed: Option<u64>,
+    /// Seeds of the random streams given to the run, kept in sessions
+    pub streams: StreamSeeds,
+    /// Seeds placed with the mouse
+    pub brush: Brush,
+    /// Generator of the jitter of the brush, from the brush stream
+    pub brush_rng: PortableRng,
+    /// Camera and window to start with, e.g. from a session
+    pub view: Option<View>,
+    /// Print the time spent in each stage of the run, see `profile`, when
+    /// the application exits
+    pub profile: bool,
+    /// MIDI and OSC controls listened to, see `control`
+    #[cfg(feature = "control")]
+    pub control: Option<ControlConfig>,
+}
+
+impl SimulationState {
+    /// Whether one of the simulations is computing a generation, its
+    /// universe being away
+    fn is_busy(&self) -> bool {
+        self.simulation.is_busy() || self.comparisons.iter().any(Simulation::is_busy)
+    }
+
+    /// Switch to new parameters, recording the change if a recorder is set
+    fn set_parameters(&mut self, parameters: Parameters) {
+        if let Err(error) = self.simulation.set_parameters(parameters) {
+            error!("ignoring parameters: {error}");
+            return;
+        }
+        self.rewind.mark();
+        #[cfg(feature = "fs")]
+        self.record(ReplayEvent::SetParameters(parameters));
+    }
+
+    /// Give A and B to the cells around `position` in all the simulations,
+    /// recording the change if a recorder is set
+    fn place_seed(&mut self, position: Position) {
+        let dimensions = self.simulation.dimensions();
+        let Brush { radius, cell: seed, jitter } = self.brush;
+        let rows = position.row.saturating_sub(radius)..(position.row + radius + 1).min(dimensions.row);
+        let cols = position.col.saturating_sub(radius)..(position.col + radius + 1).min(dimensions.col);
+        let rng = &mut self.brush_rng;
+        let cells: Vec<(Position, Cell)> = rows
+            .flat_map(|row| cols.clone().map(move |col| Position { row, col }))
+            .map(|position| {
+                let scale = if jitter > 0.0 { 1.0 - jitter * rng.gen::<f32>() } else { 1.0 };
+                (position, Cell { a: seed.a * scale, b: seed.b * scale })
+            })
+            .collect();
+
+        // The seed goes to all the simulations or to none
+        if self.is_busy() {
+            warn!("ignoring the seed: {}", SimulationError::Busy);
+            return;
+        }
+        for simulation in std::iter::once(&mut self.simulation).chain(&mut self.comparisons) {
+            for (position, cell) in &cells {
+                if let Err(error) = simulation.set_cell(*position, *cell) {
+                    error!("could not place the seed: {error}");
+                }
+            }
+        }
+        self.rewind.mark();
+        #[cfg(feature = "fs")]
+        self.record(ReplayEvent::SetCells(cells));
+    }
+
+    /// Stamp `stamp` centered on `position` in all the simulations, see
+    /// `Stamp::stamp`, recording the change if a recorder is set
+    fn place_stamp(&mut self, stamp: &Stamp, position: Position) -> Result<Rect, SimulationError> {
+        if self.is_busy() {
+            return Err(SimulationError::Busy);
+        }
+        let rect = stamp.stamp(&mut self.simulation, position)?;
+        let patch = stamp.oriented();
+        for comparison in &mut self.comparisons {
+            comparison.blit(rect, &patch)?;
+        }
+        self.rewind.mark();
+        #[cfg(feature = "fs")]
+        self.record(ReplayEvent::SetCells(
+            patch
+                .iter()
+                .enumerate()
+                .flat_map(|(row, cells)| {
+                    cells.iter().enumerate().map(move |(col, cell)| {
+                        (Position { row: rect.origin.row + row, col: rect.origin.col + col }, *cell)
+                    })
+                })
+                .collect(),
+        ));
+        Ok(rect)
+    }
+
+    /// Start the simulations again at generation 0 from random cells drawn
+    /// from `seed`, keeping their parameters
+    /// Fails if a recorder is set, a new universe not being a change that a
+    /// replay can record
+    fn reseed(&mut self, seed: u64) -> Result<(), String> {
+        #[cfg(feature = "fs")]
+        if self.recorder.is_some() {
+            return Err("a new universe cannot be recorded in the replay".to_string());
+        }
+        let universe = self.initial_universe(seed)?;
+        for simulation in std::iter::once(&mut self.simulation).chain(&mut self.comparisons) {
+            simulation.restore(simulation.parameters(), 0, universe.clone()).map_err(|error| error.to_string())?;
+        }
+        self.rewind.clear();
+        self.seed = Some(seed);
+        // The universe now comes from the seed of the run
+        self.streams.initial = None;
+        Ok(())
+    }
+
+    /// Universe of the current dimensions drawn from `seed` with the initial
+    /// condition of the `seed` command, else that of the parameters
+    fn initial_universe(&self, seed: u64) -> Result<Universe, String> {
+        let dimensions = self.simulation.dimensions();
+        let mut rng = StdRng::seed_from_u64(seed);
+        Ok(match self.initial.or(self.simulation.parameters().initial) {
+            Some(initial) => initial.generate(&dimensions, &mut rng).map_err(|error| error.to_string())?.0,
+            None => initialize_universe_with_rng(&dimensions, self.initial_cells, &mut rng).0,
+        })
+    }
+
+    /// Evolve a simulation with `parameters` everywhere next to the others,
+    /// from the initial universe of the run and without the modulation, or
+    /// give them to the one started before
+    /// Rewinding is disabled once there is a compared simulation. Returns
+    /// whether a simulation was added
+    fn probe(&mut self, parameters: Parameters) -> Result<bool, String> {
+        if let Some(index) = self.probe {
+            self.comparisons[index].set_parameters(parameters).map_err(|error| error.to_string())?;
+            return Ok(false);
+        }
+        let seed = self.streams.seed(self.seed, RngStream::Initial);
+        let universe = self.initial_universe(seed.unwrap_or_else(rand::random))?;
+        let probe = Simulation::new(parameters, self.simulation.dimensions(), universe)
+            .map_err(|error| error.to_string())?
+            .with_bounds(self.simulation.bounds())
+            .with_boundary(self.simulation.boundary())
+            .with_stencil(self.simulation.stencil());
+        self.comparisons.push(probe);
+        self.probe = Some(self.comparisons.len() - 1);
+        if self.rewind.config().capacity > 0 {
+            info!("rewinding is disabled with compared simulations");
+            self.rewind.disable();
+        }
+        Ok(true)
+    }
+
+    /// Current setup, to be saved as a profile
+    #[cfg(feature = "fs")]
+    fn profile(&self) -> Profile {
+        Profile {
+            parameters: self.simulation.parameters(),
+            colormap: self.output.colormap,
+            dimensions: self.simulation.dimensions(),
+            initial: self.initial.unwrap_or(InitialCondition::Random { cells: self.initial_cells }),
+        }
+    }
+
+    /// Switch to the setup of `profile`, starting the simulations again at
+    /// generation 0 from its initial condition, drawn from the initial stream
+    /// of the run, if seeded
+    /// Fails if the profile is invalid or a recorder is set
+    #[cfg(feature = "fs")]
+    fn apply_profile(&mut self, profile: &Profile) -> Result<(), String> {
+        profile.validate().map_err(|error| error.to_string())?;
+        if self.recorder.is_some() {
+            return Err("a new universe cannot be recorded in the replay".to_string());
+        }
+        let seed = self.streams.seed(self.seed, RngStream::Initial);
+        let mut rng = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+        let (universe, _) = profile.initial.generate(&profile.dimensions, &mut rng).map_err(|error| error.to_string())?;
+        for simulation in std::iter::once(&mut self.simulation).chain(&mut self.comparisons) {
+            simulation.restore(simulation.parameters(), 0, universe.clone()).map_err(|error| error.to_string())?;
+        }
+        self.simulation.set_parameters(profile.parameters).map_err(|error| error.to_string())?;
+        self.output.colormap = profile.colormap;
+        if let InitialCondition::Random { cells } = profile.initial {
+            self.initial_cells = cells;
+        }
+        self.initial = Some(profile.initial);
+        self.preset = None;
+        self.rewind.clear();
+        Ok(())
+    }
+
+    /// Width and height of the grid of color maps, in pixels at a zoom of 1
+    fn grid_size(&self) -> Vec2 {
+        let dimensions = self.render.rendered(self.simulation.dimensions());
+        let (columns, rows) = grid(1 + self.comparisons.len());
+        Vec2::new((dimensions.col * columns) as f32, (dimensions.row * rows) as f32)
+    }
+
+    /// Pixels of `simulation` as the window draws them: its color map, its
+    /// activation times when they are shown, or the composite with the next
+    /// layer, see `render::Frame`
+    /// `None` while `Evolution` computes a generation, the universes being
+    /// empty meanwhile
+    pub fn frame(&self) -> Option<Frame> {
+        let layer = self.comparisons.first().filter(|_| !self.couplings.is_empty());
+        if self.simulation.is_busy() || layer.is_some_and(Simulation::is_busy) {
+            return None;
+        }
+        let (generation, config, colormap) = (self.simulation.generation(), self.render, self.output.colormap);
+        if let Some(activation) = self.simulation.activation().filter(|_| self.show_activation) {
+            return Some(Frame::colored_map(&activation.colored_map(), generation, config, colormap));
+        }
+        Some(match config.mode {
+            DisplayMode::Colormap => Frame::of(&self.simulation, config, colormap),
+            DisplayMode::Composite => {
+                Frame::composite(self.simulation.universe(), layer.map(Simulation::universe), generation, config)
+            }
+        })
+    }
+
+    /// Point of the world at the center of the cell at `position` of
+    /// `simulation`, whose color map is at the top left of the grid
+    fn cell_center(&self, position: Position) -> Vec2 {
+        let dimensions = self.simulation.dimensions();
+        let rendered = self.render.rendered(dimensions);
+        let size = self.grid_size();
+        let cell = Vec2::new(
+            rendered.col as f32 / dimensions.col.max(1) as f32,
+            rendered.row as f32 / dimensions.row.max(1) as f32,
+        );
+        Vec2::new(
+            -size.x / 2.0 + (position.col as f32 + 0.5) * cell.x,
+            size.y / 2.0 - (position.row as f32 + 0.5) * cell.y,
+        )
+    }
+
+    /// Concentration of B of `simulation`, placed where its color map is
+    /// drawn in the world, at the top left of the grid
+    pub fn morphogen_field(&self) -> MorphogenField {
+        let dimensions = self.simulation.dimensions();
+        let rendered = self.render.rendered(dimensions);
+        let size = self.grid_size();
+        MorphogenField::new(self.simulation.universe(), self.simulation.generation()).with_placement(Placement {
+            origin: [-size.x / 2.0, size.y / 2.0],
+            cell_size: [
+                rendered.col as f32 / dimensions.col.max(1) as f32,
+                -(rendered.row as f32) / dimensions.row.max(1) as f32,
+            ],
+        })
+    }
+
+    /// Cell drawn at `point` of the world, in whichever color map, if any
+    fn cell_at(&self, point: Vec2) -> Option<Position> {
+        self.map_at(point).map(|(_, position)| position)
+    }
+
+    /// Index of the color map drawn at `point` of the world, 0 for
+    /// `simulation` and 1, 2, … for the comparisons, and the cell there
+    fn map_at(&self, point: Vec2) -> Option<(usize, Position)> {
+        let count = 1 + self.comparisons.len();
+        let (columns, rows) = grid(count);
+        let size = self.grid_size();
+        // In color maps from the top left corner of the grid
+        let x = (point.x / size.x + 0.5) * columns as f32;
+        let y = (0.5 - point.y / size.y) * rows as f32;
+        let (column, row) = (x as usize, y as usize);
+        if x < 0.0 || y < 0.0 || column >= columns || row * columns + column >= count {
+            return None;
+        }
+        let dimensions = self.simulation.dimensions();
+        let position = Position {
+            row: ((y.fract() * dimensions.row as f32) as usize).min(dimensions.row.saturating_sub(1)),
+            col: ((x.fract() * dimensions.col as f32) as usize).min(dimensions.col.saturating_sub(1)),
+        };
+        Some((row * columns + column, position))
+    }
+
+    /// Resample the universe to new dimensions, recording the change if a
+    /// recorder is set
+    /// Fails while a generation is computed, the universes being away
+    fn resize(&mut self, dimensions: Position) -> Result<(), SimulationError> {
+        if self.is_busy() {
+            return Err(SimulationError::Busy);
+        }
+        for simulation in std::iter::once(&mut self.simulation).chain(&mut self.comparisons) {
+            simulation.resize(dimensions, Resampling::Bilinear)?;
+        }
+        // The keyframes of the old dimensions do not fit the textures
+        self.rewind.clear();
+        #[cfg(feature = "fs")]
+        self.record(ReplayEvent::Resize(dimensions, Resampling::Bilinear));
+        Ok(())
+    }
+
+    /// Take the next evolution out of the simulations that are not stopped,
+    /// with their index: 0 for `simulation`, then the comparisons
+    fn begin_steps(&mut self) -> Vec<(usize, PendingStep)> {
+        std::iter::once(&mut self.simulation)
+            .chain(&mut self.comparisons)
+            .enumerate()
+            .filter(|(index, simulation)| *index == 0 || !simulation.is_stopped())
+            .map(|(index, simulation)| (index, simulation.begin_step()))
+            .collect()
+    }
+
+    /// Put back the evolutions taken by `begin_steps` and apply the couplings
+    fn finish_steps(&mut self, steps: Vec<(usize, EvolvedStep)>) {
+        for (index, step) in steps {
+            match index {
+                0 => self.simulation.finish_step(step),
+                index => self.comparisons[index - 1].finish_step(step),
+            };
+        }
+        if !self.couplings.is_empty() {
+            let mut layers: Vec<&mut Simulation> =
+                std::iter::once(&mut self.simulation).chain(&mut self.comparisons).collect();
+            apply_couplings(&self.couplings, &mut layers);
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    fn record(&mut self, event: ReplayEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(error) = recorder.record(self.simulation.generation(), event) {
+                error!("could not record change: {error}");
+            }
+        }
+    }
+}
+
+/// Event sent after every evolution, e.g. to log or export the run from
+/// other systems
+pub struct SimulationStepped(pub StepSummary);
+
+/// Statistics of the universe, computed again every `stats_interval`
+/// generations since they go through all the cells
+#[derive(Resource, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Resource)]
+pub struct SimulationStats {
+    /// Generation the statistics were computed at
+    pub generation: i32,
+    pub stats: Stats,
+}
+
+/// Parameters of `SimulationState::simulation`
+/// Kept in sync with the simulation, and changes made to it, e.g. from the
+/// inspector, are applied to the simulation like a preset
+#[derive(Resource, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Resource)]
+pub struct SimulationParameters(pub Parameters);
+
+/// Rows of a color map finished while its generation is still computing
+#[cfg(not(target_arch = "wasm32"))]
+struct PartialColors {
+    /// Index of the simulation, as in `SimulationState::begin_steps`
+    index: usize,
+    rows: Range<usize>,
+    colored_map: ColoredMap,
+}
+
+/// Evolutions computed in the background, on a thread of their own
+/// On the web, without threads, they are computed right away
+#[derive(Resource)]
+struct Evolution {
+    #[cfg(not(target_arch = "wasm32"))]
+    jobs: Mutex<Sender<Vec<(usize, PendingStep)>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    results: Mutex<Receiver<Vec<(usize, EvolvedStep)>>>,
+    /// Rows finished by the generation being computed, once it took longer
+    /// than `PROGRESSIVE_DELAY`
+    #[cfg(not(target_arch = "wasm32"))]
+    partials: Mutex<Receiver<PartialColors>>,
+    /// When the generation being computed was started
+    #[cfg(not(target_arch = "wasm32"))]
+    started: Instant,
+    /// Fraction of the rows of the main simulation finished so far
+    progress: f32,
+    #[cfg(target_arch = "wasm32")]
+    result: Option<Vec<(usize, EvolvedStep)>>,
+    /// Whether a generation is being computed
+    busy: bool,
+}
+
+impl Evolution {
+    /// Start the thread computing the evolutions
+    fn new() -> Evolution {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (jobs, pending) = mpsc::channel::<Vec<(usize, PendingStep)>>();
+            let (done, results) = mpsc::channel();
+            let (finished_rows, partials) = mpsc::channel();
+            thread::Builder::new()
+                .name("evolution".to_string())
+                .spawn(move || {
+                    // Ends once the application drops the other end
+                    while let Ok(steps) = pending.recv() {
+                        let started = Instant::now();
+                        let evolved = steps
+                            .into_iter()
+                            .map(|(index, step)| {
+                                let evolved = step.compute_with_progress(&mut |rows, colored_map| {
+                                    if started.elapsed() >= PROGRESSIVE_DELAY {
+                                        let colored_map = colored_map[rows.clone()].to_vec();
+                                        let _ = finished_rows.send(PartialColors { index, rows, colored_map });
+                                    }
+                                });
+                                (index, evolved)
+                            })
+                            .collect();
+                        if done.send(evolved).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .expect("could not start the evolution thread");
+            Evolution {
+                jobs: Mutex::new(jobs),
+                results: Mutex::new(results),
+                partials: Mutex::new(partials),
+                started: Instant::now(),
+                progress: 0.0,
+                busy: false,
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        Evolution { result: None, progress: 0.0, busy: false }
+    }
+
+    /// Compute `steps` in the background
+    fn start(&mut self, steps: Vec<(usize, PendingStep)>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.started = Instant::now();
+            self.jobs.lock().unwrap().send(steps).expect("the evolution thread stopped");
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.result = Some(steps.into_iter().map(|(index, step)| (index, step.compute())).collect());
+        }
+        self.progress = 0.0;
+        self.busy = true;
+    }
+
+    /// Whether the generation being computed is drawn as it goes
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_progressive(&self) -> bool {
+        self.busy && self.started.elapsed() >= PROGRESSIVE_DELAY
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn is_progressive(&self) -> bool {
+        false
+    }
+
+    /// Rows finished since the last call, keeping track of the progress of
+    /// the main simulation, which has `rows` rows
+    #[cfg(not(target_arch = "wasm32"))]
+    fn take_partials(&mut self, rows: usize) -> Vec<PartialColors> {
+        let partials: Vec<PartialColors> = self.partials.lock().unwrap().try_iter().collect();
+        for partial in partials.iter().filter(|partial| partial.index == 0) {
+            self.progress = self.progress.max(partial.rows.end as f32 / rows.max(1) as f32);
+        }
+        partials
+    }
+
+    /// Evolutions computed since `start`, waiting for them if `wait` is set
+    /// and returning None if they are not done yet otherwise
+    fn finish(&mut self, wait: bool) -> Option<Vec<(usize, EvolvedStep)>> {
+        if !self.busy {
+            return None;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let steps = {
+            let results = self.results.lock().unwrap();
+            if wait {
+                Some(results.recv().expect("the evolution thread stopped"))
+            } else {
+                results.try_recv().ok()
+            }
+        };
+        #[cfg(target_arch = "wasm32")]
+        let steps = {
+            let _ = wait;
+            self.result.take()
+        };
+        // The rows of a finished generation are drawn along with the rest
+        #[cfg(not(target_arch = "wasm32"))]
+        if steps.is_some() {
+            self.partials.lock().unwrap().try_iter().for_each(drop);
+        }
+        self.busy = steps.is_none();
+        steps
+    }
+}
+
+/// Put back into `state` the generation computed by `evolution`, if done,
+/// waiting for it if `wait` is set
+fn collect_evolution(
+    evolution: &mut Evolution,
+    state: &mut SimulationState,
+    stepped: &mut EventWriter<SimulationStepped>,
+    wait: bool,
+) {
+    let Some(steps) = evolution.finish(wait) else {
+        return;
+    };
+    state.finish_steps(steps);
+    state.rewind.record(&state.simulation);
+    stepped.send(SimulationStepped(state.simulation.summary()));
+    if let Some(violation) = state.simulation.violation() {
+        error!("stopping: {violation}");
+    }
+    if let Some(leak) = state.simulation.leak() {
+        error!("stopping, mass not conserved: {leak}");
+    }
+}
+
+/// Handles to the textures where the color maps are drawn, the one of
+/// `SimulationState::simulation` first, then those of the comparisons
+#[derive(Resource)]
+struct MapTextures(Vec<Handle<Image>>);
+
+/// Palette coloring the `MapTextures` in the `R8` format, see
+/// `render::palette`
+#[derive(Resource)]
+struct MapPalette(Handle<Image>);
+
+/// Sprite, or mesh in the `R8` format, drawing one of the `MapTextures`
+#[derive(Component)]
+struct MapSprite;
+
+/// Preset file loaded by the asset server
+#[derive(Debug, TypeUuid)]
+#[uuid = "6c3a8a3e-4f0e-4a4e-9d51-0d3f6b1f2c7a"]
+pub struct PresetAsset(pub PresetLibrary);
+
+/// Loader of the preset file, `presets.ron`
+/// Bevy picks the loader of a file by its last extension, so the loader is
+/// registered for `ron`, the preset file being the only RON asset
+#[derive(Default)]
+struct PresetAssetLoader;
+
+impl AssetLoader for PresetAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let library = PresetLibrary::from_ron(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(PresetAsset(library)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+/// Handle to the preset file
+#[derive(Resource)]
+struct Presets(Handle<PresetAsset>);
+
+/// Saved profiles, see `profiles`
+#[cfg(feature = "fs")]
+#[derive(Resource)]
+struct Profiles {
+    library: ProfileLibrary,
+    /// File the profiles are read from and saved to, none if the user
+    /// configuration directory is unknown
+    path: Option<PathBuf>,
+    /// Name of the profile switched to last
+    current: Option<String>,
+}
+
+#[cfg(feature = "fs")]
+impl Profiles {
+    /// Read the profiles of the user configuration directory, none if they
+    /// cannot be read
+    fn load() -> Profiles {
+        let path = profiles::default_path();
+        let library = match &path {
+            Some(path) => ProfileLibrary::load(path).unwrap_or_else(|error| {
+                warn!("ignoring the saved profiles: {error}");
+                ProfileLibrary::default()
+            }),
+            None => ProfileLibrary::default(),
+        };
+        Profiles { library, path, current: None }
+    }
+
+    /// Write the profiles to their file
+    fn save(&self) -> Result<&Path, String> {
+        let path = self.path.as_deref().ok_or("the user configuration directory is unknown")?;
+        self.library.save(path).map_err(|error| error.to_string())?;
+        Ok(path)
+    }
+}
+
+/// Height of the timeline bar along the bottom of the window, in pixels
+const TIMELINE_HEIGHT: f32 = 8.0;
+/// Width of the marker of the generation shown on the timeline bar, in pixels
+const TIMELINE_MARKER_WIDTH: f32 = 3.0;
+
+/// Bar of the generations covered by the rewind buffer
+#[derive(Component)]
+struct TimelineBar;
+
+/// Marker of the generation shown on the timeline bar
+#[derive(Component)]
+struct TimelineMarker;
+
+/// Text telling which generation is being computed while it is drawn as it
+/// goes
+#[derive(Component)]
+struct ProgressIndicator;
+
+/// Text telling which concentration each channel of the composite shows
+#[derive(Component)]
+struct CompositeLegend;
+
+/// Generation of the last blow-up the run was paused on, see `blowup`, and
+/// whose cells are highlighted
+#[derive(Resource, Default)]
+struct BlowupAlert {
+    paused: Option<i32>,
+    highlighted: Option<i32>,
+}
+
+/// Square over a blown up cell
+#[derive(Component)]
+struct BlowupHighlight;
+
+/// Text telling why the run was paused on a blow-up
+#[derive(Component)]
+struct BlowupMessage;
+
+/// Square of the cells seeded by a click, following the cursor during the
+/// setup
+#[derive(Component)]
+struct BrushPreview;
+
+/// Stamp copied from the universe, see `stamp`, placed by clicks instead of
+/// seeds
+#[derive(Resource, Default)]
+struct Stamping {
+    stamp: Option<Stamp>,
+    /// Cell where the drag copying the next stamp started
+    anchor: Option<Position>,
+}
+
+/// Width of the grid of color maps, which the timeline bar spans
+#[derive(Resource)]
+struct TimelineWidth(f32);
+
+/// Smallest and largest number of rows or columns reachable with the resize
+/// keys
+const MIN_RESOLUTION: usize = 16;
+const MAX_RESOLUTION: usize = 2048;
+
+/// Pixels of the window panned per second with the left stick fully tilted
+const PAN_SPEED: f32 = 600.0;
+/// Factor of the zoom per second with the right stick fully tilted
+const ZOOM_SPEED: f32 = 2.0;
+/// Smallest and largest scale of the camera, below 1 zooming in
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 4.0;
+/// Distance between two seeds painted along the stroke of a finger, in
+/// pixels of the window
+const TOUCH_SPACING: f32 = 4.0;
+/// Change of `f` and `k` per second with the right stick fully tilted
+const NUDGE_SPEED: f32 = 0.01;
+/// Seconds between two changes of the parameters while nudging, so that a
+/// held stick does not record a change at every frame
+const NUDGE_INTERVAL: f32 = 0.1;
+/// Change of the concentration of B of the brush per second with a trigger
+/// fully pressed
+const BRUSH_SPEED: f32 = 0.5;
+/// Largest radius of the brush reachable with the mouse wheel and `[` and `]`
+const MAX_BRUSH_RADIUS: usize = 64;
+
+/// Keys selecting the presets, in the order of their names
+const PRESET_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Number of columns and rows of the grid of `count` color maps, as square as
+/// possible
+fn grid(count: usize) -> (usize, usize) {
+    let columns = (1..).find(|columns| columns * columns >= count).unwrap_or(1);
+    (columns, count.div_ceil(columns).max(1))
+}
+
+/// Open a window and run the simulation in it
+/// Blocks until the window is closed
+pub fn run(state: SimulationState) {
+    build(state).run();
+}
+
+/// Application running the simulation in a window, to which other plugins
+/// and systems can be added before running it
+pub fn build(mut state: SimulationState) -> App {
+    if state.render.mode == DisplayMode::Composite && state.render.format == PixelFormat::R8 {
+        info!("the composite is drawn in the rgba8 format");
+        state.render.format = PixelFormat::Rgba8;
+    }
+    if !state.comparisons.is_empty() && state.rewind.config().capacity > 0 {
+        info!("rewinding is disabled with compared simulations");
+        state.rewind.disable();
+    }
+    let dimensions = state.render.rendered(state.simulation.dimensions());
+    let (columns, rows) = grid(1 + state.comparisons.len());
+    let [width, height] = match state.view {
+        Some(view) => view.window,
+        None => [(dimensions.col * columns) as f32, (dimensions.row * rows) as f32],
+    };
+    let stats = SimulationStats {
+        generation: state.simulation.generation(),
+        stats: state.simulation.stats(),
+    };
+    let parameters = SimulationParameters(state.simulation.parameters());
+    let field = state.morphogen_field();
+    #[cfg(feature = "control")]
+    let controls = state.control.as_ref().and_then(|config| match control::listen(config) {
+        Ok(receiver) => Some(ControlInput(std::sync::Mutex::new(receiver))),
+        Err(error) => {
+            error!("could not listen to the controls: {error}");
+            None
+        }
+    });
+
+    let title = state.name.clone();
+    let mut app = App::new();
+    app.insert_resource(state)
+        .insert_resource(stats)
+        .insert_resource(parameters)
+        .insert_resource(field)
+        .insert_resource(Evolution::new())
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    window: WindowDescriptor {
+                        title,
+                        width,
+                        height,
+                        #[cfg(target_arch = "wasm32")]
+                        canvas: Some(CANVAS_SELECTOR.to_string()),
+                        #[cfg(target_arch = "wasm32")]
+                        fit_canvas_to_parent: true,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .set(ImagePlugin::default_nearest())
+                .set(AssetPlugin {
+                    watch_for_changes: !cfg!(target_arch = "wasm32"),
+                    ..default()
+                }),
+        )
+        .register_type::<SimulationStats>()
+        .register_type::<SimulationParameters>()
+        .register_type::<AppState>()
+        .add_state(AppState::Setup)
+        .add_event::<SimulationStepped>()
+        .add_asset::<PresetAsset>()
+        .init_asset_loader::<PresetAssetLoader>()
+        .add_plugin(ConsolePlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(UploadPlugin)
+        .register_command(
+            "set",
+            ConsoleCommand { usage: "<parameter> <value>", help: "change d_a, d_b, f, k or r", run: set_command },
+        )
+        .register_command(
+            "preset",
+            ConsoleCommand { usage: "<name>", help: "switch to a preset", run: preset_command },
+        )
+        .register_command(
+            "seed",
+            ConsoleCommand {
+                usage: "<seed>",
+                help: "start again from random cells drawn from the seed",
+                run: seed_command,
+            },
+        )
+        .register_command(
+            "brush",
+            ConsoleCommand {
+                usage: "<radius> [<a> <b> [<jitter>]]",
+                help: "change the size, the concentrations and their jitter of the seeds placed with the mouse",
+                run: brush_command,
+            },
+        )
+        .register_command(
+            "stamp",
+            ConsoleCommand {
+                usage: "[<row> <col> <rows> <cols>|at <row> <col>|rotate|mirror|clear]",
+                help: "copy a patch of the universe, stamp it, turn or mirror it, or forget it",
+                run: stamp_command,
+            },
+        )
+        .init_resource::<Stamping>()
+        .add_startup_system(setup)
+        .add_startup_system(load_presets)
+        .add_system(select_preset)
+        .add_system(reload_preset)
+        .add_system_to_stage(CoreStage::PreUpdate, finish_evolution)
+        .add_system_to_stage(CoreStage::PreUpdate, update_morphogen_field.after(finish_evolution))
+        .add_system(sync_parameters.after(select_preset).after(reload_preset))
+        .add_system(resize_universe)
+        .add_system(toggle_running)
+        .add_system(move_camera)
+        .add_system(pinch_view)
+        .add_system(nudge_with_gamepad.before(sync_parameters))
+        .add_system(reset_with_gamepad)
+        .add_system_set(
+            SystemSet::on_update(AppState::Setup)
+                .with_system(place_seeds)
+                .with_system(paint_seeds)
+                .with_system(resize_brush)
+                .with_system(use_stamp),
+        )
+        .add_system_set(SystemSet::on_update(AppState::Paused).with_system(use_stamp))
+        .add_system(preview_brush)
+        .add_system(pick_parameters)
+        .add_system_set(SystemSet::on_update(AppState::Running).with_system(update_stats))
+        .add_startup_system(spawn_progress_indicator)
+        .add_system(toggle_activation.before(draw_colored_map))
+        .add_system(toggle_composite.before(draw_colored_map))
+        .add_startup_system(spawn_composite_legend)
+        .add_system(show_composite_legend)
+        .init_resource::<BlowupAlert>()
+        .add_startup_system(spawn_blowup_message)
+        .add_system_set(SystemSet::on_update(AppState::Running).with_system(pause_on_blowup))
+        .add_system(highlight_blowup.after(pause_on_blowup))
+        .add_system(draw_colored_map)
+        .add_system(show_progress)
+        .add_system(draw_timeline)
+        .add_system_to_stage(CoreStage::Last, report_profile)
+        .add_system_set_to_stage(
+            CoreStage::PostUpdate,
+            SystemSet::on_update(AppState::Running).with_system(start_evolution),
+        );
+
+    for state in [AppState::Paused, AppState::Finished] {
+        app.add_system_set(SystemSet::on_update(state).with_system(scrub_timeline));
+    }
+    #[cfg(feature = "fs")]
+    app.register_command(
+        "export",
+        ConsoleCommand { usage: "[<path>]", help: "save the color map as an image", run: export_command },
+    )
+    .register_command(
+        "session",
+        ConsoleCommand { usage: "[<path>]", help: "save the whole session to be restored later", run: session_command },
+    );
+    #[cfg(feature = "fs")]
+    app.insert_resource(Profiles::load())
+        .register_command(
+            "profile",
+            ConsoleCommand {
+                usage: "[save|delete] [<name>]",
+                help: "switch to a saved profile, save the current setup as one or forget one, or list them",
+                run: profile_command,
+            },
+        )
+        .add_system(cycle_profiles)
+        .add_system(reload_config.before(sync_parameters));
+    #[cfg(feature = "fs")]
+    for state in [AppState::Paused, AppState::Finished] {
+        app.add_system_set(
+            SystemSet::on_update(state)
+                .with_system(save_snapshot)
+                .with_system(export_fields),
+        );
+    }
+    #[cfg(feature = "control")]
+    if let Some(controls) = controls {
+        app.insert_resource(controls).add_system(apply_controls.before(sync_parameters));
+    }
+    #[cfg(feature = "inspector")]
+    app.add_plugin(bevy_inspector_egui::quick::WorldInspectorPlugin);
+
+    app
+}
+
+/// Create a texture for each color map, laid out in a grid from the top left,
+/// and the sprites drawing them, or the meshes coloring them with `palette`
+/// in the `R8` format
+fn spawn_maps(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColormapMaterial>,
+    palette: &Handle<Image>,
+    state: &SimulationState,
+) {
+    let dimensions = state.render.rendered(state.simulation.dimensions());
+    let size = Extent3d {
+        width: dimensions.col as u32,
+        height: dimensions.row as u32,
+        depth_or_array_layers: 1,
+    };
+    let count = 1 + state.comparisons.len();
+    let (columns, rows) = grid(count);
+    let (width, height) = (size.width as f32, size.height as f32);
+
+    let mut handles = Vec::with_capacity(count);
+    for index in 0..count {
+        let image = match state.render.format {
+            PixelFormat::Rgba8 => Image::new_fill(size, TextureDimension::D2, &[0, 0, 0, 255], TextureFormat::Rgba8UnormSrgb),
+            PixelFormat::R8 => Image::new_fill(size, TextureDimension::D2, &[0], TextureFormat::R8Unorm),
+        };
+        let handle = images.add(image);
+        let (column, row) = ((index % columns) as f32, (index / columns) as f32);
+        let transform = Transform::from_xyz(
+            (column + 0.5 - columns as f32 / 2.0) * width,
+            (rows as f32 / 2.0 - row - 0.5) * height,
+            0.0,
+        );
+        match state.render.format {
+            PixelFormat::Rgba8 => commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(width, height)),
+                        ..default()
+                    },
+                    texture: handle.clone(),
+                    transform,
+                    ..default()
+                },
+                MapSprite,
+            )),
+            PixelFormat::R8 => commands.spawn((
+                MaterialMesh2dBundle {
+                    mesh: meshes.add(Mesh::from(shape::Quad::new(Vec2::new(width, height)))).into(),
+                    material: materials.add(ColormapMaterial { values: handle.clone(), palette: palette.clone() }),
+                    transform,
+                    ..default()
+                },
+                MapSprite,
+            )),
+        };
+        handles.push(handle);
+    }
+    commands.insert_resource(MapTextures(handles));
+}
+
+/// Create the textures for the color maps and the camera looking at them
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColormapMaterial>>,
+    state: Res<SimulationState>,
+) {
+    let mut camera = Camera2dBundle::default();
+    if let Some(view) = state.view {
+        camera.transform = Transform {
+            translation: Vec3::from_array(view.translation),
+            rotation: Quat::from_array(view.rotation),
+            scale: Vec3::from_array(view.scale),
+        };
+    }
+    let camera = commands.spawn(camera).id();
+    let palette = images.add(Image::new(
+        Extent3d { width: 256, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        render::palette(state.output.colormap),
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    spawn_maps(&mut commands, &mut images, &mut meshes, &mut materials, &palette, &state);
+    commands.insert_resource(MapPalette(palette));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite { color: Color::rgba(1.0, 1.0, 1.0, 0.3), ..default() },
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        },
+        BrushPreview,
+    ));
+
+    // The timeline bar is drawn by the camera, staying along the bottom of
+    // the window whatever the view; the camera of `Camera2dBundle` sees
+    // from 1000 behind it
+    let Vec2 { x: width, y: height } = state.grid_size();
+    let bottom = -height / 2.0 + TIMELINE_HEIGHT / 2.0;
+    commands.entity(camera).with_children(|parent| {
+        parent.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.5, 0.5, 0.5, 0.6),
+                    custom_size: Some(Vec2::new(width, TIMELINE_HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, bottom, -2.0),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            TimelineBar,
+        ));
+        parent.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::WHITE,
+                    custom_size: Some(Vec2::new(TIMELINE_MARKER_WIDTH, TIMELINE_HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, bottom, -1.0),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            TimelineMarker,
+        ));
+    });
+    commands.insert_resource(TimelineWidth(width));
+}
+
+/// Whether `button` was just pressed on any of the gamepads
+fn gamepad_just_pressed(gamepads: &Gamepads, buttons: &Input<GamepadButton>, button: GamepadButtonType) -> bool {
+    gamepads.iter().any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+}
+
+/// Whether `button` is held on any of the gamepads
+fn gamepad_pressed(gamepads: &Gamepads, buttons: &Input<GamepadButton>, button: GamepadButtonType) -> bool {
+    gamepads.iter().any(|gamepad| buttons.pressed(GamepadButton::new(gamepad, button)))
+}
+
+/// Tilt of a stick of any of the gamepads, the largest one if several are
+fn stick(gamepads: &Gamepads, axes: &Axis<GamepadAxis>, [x, y]: [GamepadAxisType; 2]) -> Vec2 {
+    gamepads
+        .iter()
+        .map(|gamepad| {
+            let axis = |axis| axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.0);
+            Vec2::new(axis(x), axis(y))
+        })
+        .fold(Vec2::ZERO, |tilt, other| if other.length() > tilt.length() { other } else { tilt })
+}
+
+/// Start evolving the universe when `Space`, `Enter` or `Start` is pressed
+/// during the setup, and pause or resume it afterwards with `Space` or
+/// `Start`; a converged universe can be resumed, e.g. after switching to
+/// another preset
+fn toggle_running(
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    state: Res<SimulationState>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let starting = keys.just_pressed(KeyCode::Return) && *app_state.current() == AppState::Setup;
+    if !keys.just_pressed(KeyCode::Space)
+        && !starting
+        && !gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::Start)
+    {
+        return;
+    }
+    let can_continue =
+        state.simulation.generation() < state.max_generations && !state.simulation.is_stopped();
+    let next = match app_state.current() {
+        AppState::Setup | AppState::Paused => AppState::Running,
+        AppState::Running => AppState::Paused,
+        AppState::Finished if can_continue => AppState::Running,
+        AppState::Finished => return,
+    };
+    // Fails only if the state is already changing this frame
+    let _ = app_state.set(next);
+}
+
+/// Place a seed on the cell under the cursor when the left button is
+/// pressed, or at the center of the view when `South` is, in whichever color
+/// map it is drawn; clicks stamp instead when there is a stamp, see
+/// `use_stamp`
+#[allow(clippy::too_many_arguments)]
+fn place_seeds(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    stamping: Res<Stamping>,
+    mut state: ResMut<SimulationState>,
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let stamps = stamping.stamp.is_some() || keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    // Measured from the bottom left corner of the window
+    let point = if buttons.just_pressed(MouseButton::Left) && !stamps {
+        window.cursor_position()
+    } else if gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::South) {
+        Some(Vec2::new(window.width(), window.height()) / 2.0)
+    } else {
+        None
+    };
+    let Some(point) = point else {
+        return;
+    };
+    let Ok((camera, transform)) = cameras.get_single() else {
+        return;
+    };
+    let Some(ray) = camera.viewport_to_world(transform, point) else {
+        return;
+    };
+    if let Some(position) = state.cell_at(ray.origin.truncate()) {
+        state.place_seed(position);
+    }
+}
+
+/// Copy the cells under a drag of the left button with `Ctrl` held into the
+/// stamp, stamp it centered on the cell clicked without, and turn it a
+/// quarter turn clockwise with `R` or mirror it with `M`
+#[allow(clippy::too_many_arguments)]
+fn use_stamp(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut stamping: ResMut<Stamping>,
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    if let Some(stamp) = &mut stamping.stamp {
+        if keys.just_pressed(KeyCode::R) {
+            stamp.rotate();
+        }
+        if keys.just_pressed(KeyCode::M) {
+            stamp.mirror();
+        }
+    }
+    let selecting = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let (pressed, released) = (buttons.just_pressed(MouseButton::Left), buttons.just_released(MouseButton::Left));
+    if !pressed && !released {
+        return;
+    }
+    let cursor = windows.get_primary().and_then(Window::cursor_position);
+    let cell = match (cursor, cameras.get_single()) {
+        (Some(cursor), Ok((camera, transform))) => camera
+            .viewport_to_world(transform, cursor)
+            .and_then(|ray| state.cell_at(ray.origin.truncate())),
+        _ => None,
+    };
+    if pressed && selecting {
+        stamping.anchor = cell;
+        return;
+    }
+    // The universe changes, or is copied, while no generation is computing
+    if released {
+        let (Some(start), Some(end)) = (stamping.anchor.take(), cell) else {
+            return;
+        };
+        collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+        let origin = Position { row: start.row.min(end.row), col: start.col.min(end.col) };
+        let dimensions = Position { row: start.row.abs_diff(end.row) + 1, col: start.col.abs_diff(end.col) + 1 };
+        match Stamp::copy(state.simulation.universe(), Rect { origin, dimensions }) {
+            Ok(stamp) => {
+                info!("copied a stamp of {}x{} cells", dimensions.row, dimensions.col);
+                stamping.stamp = Some(stamp);
+            }
+            Err(error) => error!("could not copy the stamp: {error}"),
+        }
+        return;
+    }
+    let (Some(stamp), Some(position)) = (&stamping.stamp, cell) else {
+        return;
+    };
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+    if let Err(error) = state.place_stamp(stamp, position) {
+        error!("could not stamp: {error}");
+    }
+}
+
+/// Evolve the `f` and `k` of the cell under the cursor everywhere in a
+/// simulation drawn next to the others when the right button is pressed on
+/// a simulation whose rates are modulated, e.g. by a gradient drawing its
+/// phase diagram, or give them to the simulation picked before
+#[allow(clippy::too_many_arguments)]
+fn pick_parameters(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    sprites: Query<Entity, With<MapSprite>>,
+    palette: Res<MapPalette>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColormapMaterial>>,
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    if !buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some(point) = windows.get_primary().and_then(Window::cursor_position) else {
+        return;
+    };
+    let Ok((camera, transform)) = cameras.get_single() else {
+        return;
+    };
+    let Some((0, position)) = camera
+        .viewport_to_world(transform, point)
+        .and_then(|ray| state.map_at(ray.origin.truncate()))
+    else {
+        return;
+    };
+    if state.simulation.modulation().is_none() {
+        warn!("the rates are the same everywhere, run with --modulation to pick them");
+        return;
+    }
+
+    // The simulations change while no generation is computing
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+    let parameters = state.simulation.parameters_at(position);
+    match state.probe(parameters) {
+        Ok(added) => {
+            if added {
+                for sprite in &sprites {
+                    commands.entity(sprite).despawn();
+                }
+                spawn_maps(&mut commands, &mut images, &mut meshes, &mut materials, &palette.0, &state);
+            }
+            info!("evolving f = {} and k = {} everywhere", parameters.f, parameters.k);
+        }
+        Err(error) => error!("could not pick the parameters: {error}"),
+    }
+}
+
+/// Change the radius of the brush with the mouse wheel, or `[` and `]`,
+/// during the setup
+fn resize_brush(keys: Res<Input<KeyCode>>, mut wheel: EventReader<MouseWheel>, mut state: ResMut<SimulationState>) {
+    let scrolled: i32 = wheel.iter().map(|event| event.y.signum() as i32).sum();
+    let step = scrolled + keys.just_pressed(KeyCode::RBracket) as i32 - keys.just_pressed(KeyCode::LBracket) as i32;
+    let radius = (state.brush.radius as i32 + step).clamp(0, MAX_BRUSH_RADIUS as i32) as usize;
+    if radius != state.brush.radius {
+        state.brush.radius = radius;
+        info!("seeds of radius {radius}");
+    }
+}
+
+/// Show the cells a click would seed under the cursor during the setup, or
+/// stamp during the setup and when paused
+fn preview_brush(
+    windows: Res<Windows>,
+    app_state: Res<State<AppState>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    state: Res<SimulationState>,
+    stamping: Res<Stamping>,
+    mut previews: Query<(&mut Visibility, &mut Transform, &mut Sprite), With<BrushPreview>>,
+) {
+    let cursor = windows.get_primary().and_then(|window| window.cursor_position());
+    let shown = match app_state.current() {
+        AppState::Setup => true,
+        AppState::Paused => stamping.stamp.is_some(),
+        _ => false,
+    };
+    let point = match (cursor, cameras.get_single()) {
+        (Some(cursor), Ok((camera, transform))) if shown => {
+            camera.viewport_to_world(transform, cursor).map(|ray| ray.origin.truncate())
+        }
+        _ => None,
+    };
+    let radius = state.brush.radius;
+    let size = match &stamping.stamp {
+        Some(stamp) => stamp.dimensions(),
+        None => Position { row: 2 * radius + 1, col: 2 * radius + 1 },
+    };
+    let dimensions = state.simulation.dimensions();
+    // Size of a cell in the world, the size of the texture it is drawn in
+    let cell = state.render.rendered(dimensions).col as f32 / dimensions.col.max(1) as f32;
+    for (mut visibility, mut transform, mut sprite) in &mut previews {
+        visibility.is_visible = point.is_some_and(|point| state.cell_at(point).is_some());
+        if let Some(point) = point {
+            transform.translation = point.extend(transform.translation.z);
+            sprite.custom_size = Some(Vec2::new(size.col as f32, size.row as f32) * cell);
+        }
+    }
+}
+
+/// Pan the view with the left stick of a gamepad, and zoom it with the right
+/// one unless `West` is held, within the grid of color maps
+fn move_camera(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    state: Res<SimulationState>,
+    mut cameras: Query<&mut Transform, With<Camera2d>>,
+) {
+    let pan = stick(&gamepads, &axes, [GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY]);
+    let nudging = gamepad_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::West);
+    let zoom = match nudging {
+        true => 0.0,
+        false => stick(&gamepads, &axes, [GamepadAxisType::RightStickX, GamepadAxisType::RightStickY]).y,
+    };
+    if pan == Vec2::ZERO && zoom == 0.0 {
+        return;
+    }
+    let seconds = time.delta_seconds();
+    let bounds = state.grid_size() / 2.0;
+    for mut transform in &mut cameras {
+        // Tilting up zooms in
+        let scale = transform.scale.x * ZOOM_SPEED.powf(-zoom * seconds);
+        let translation = transform.translation.truncate() + pan * PAN_SPEED * scale * seconds;
+        set_view(&mut transform, translation, scale, bounds);
+    }
+}
+
+/// Move the camera to `translation` and zoom it to `scale`, within the grid
+/// of color maps spanning `bounds` on each side of the origin
+fn set_view(transform: &mut Transform, translation: Vec2, scale: f32, bounds: Vec2) {
+    let scale = scale.clamp(MIN_ZOOM, MAX_ZOOM);
+    transform.scale = Vec3::new(scale, scale, 1.0);
+    transform.translation = translation.clamp(-bounds, bounds).extend(transform.translation.z);
+}
+
+/// Pinch two fingers on a touch screen to zoom the view and drag them to
+/// pan it, keeping the point of the grid between them under them
+fn pinch_view(
+    touches: Res<Touches>,
+    windows: Res<Windows>,
+    state: Res<SimulationState>,
+    mut cameras: Query<&mut Transform, With<Camera2d>>,
+) {
+    let mut active = touches.iter();
+    let (Some(first), Some(second), None) = (active.next(), active.next(), active.next()) else {
+        return;
+    };
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    // Touches are measured from the top left corner, the world from the
+    // center of the window upwards
+    let center = Vec2::new(window.width(), window.height()) / 2.0;
+    let offset = |position: Vec2| Vec2::new(position.x - center.x, center.y - position.y);
+    let (before, after) = (
+        [first.previous_position(), second.previous_position()],
+        [first.position(), second.position()],
+    );
+    let (distance_before, distance_after) = (before[0].distance(before[1]), after[0].distance(after[1]));
+    if before == after || distance_before == 0.0 || distance_after == 0.0 {
+        return;
+    }
+    let (middle_before, middle_after) = (offset((before[0] + before[1]) / 2.0), offset((after[0] + after[1]) / 2.0));
+    let bounds = state.grid_size() / 2.0;
+    for mut transform in &mut cameras {
+        let scale = transform.scale.x;
+        let zoomed = (scale * distance_before / distance_after).clamp(MIN_ZOOM, MAX_ZOOM);
+        // The point of the world under the fingers stays under them
+        let translation = transform.translation.truncate() + middle_before * scale - middle_after * zoomed;
+        set_view(&mut transform, translation, zoomed, bounds);
+    }
+}
+
+/// Paint seeds along the stroke of one finger on a touch screen, in
+/// whichever color map it is drawn; a finger left on the screen after
+/// pinching does not paint
+fn paint_seeds(
+    touches: Res<Touches>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut painting: Local<Option<u64>>,
+    mut state: ResMut<SimulationState>,
+) {
+    let mut active = touches.iter();
+    let (Some(touch), None) = (active.next(), active.next()) else {
+        *painting = None;
+        return;
+    };
+    let started = touches.just_pressed(touch.id());
+    if started {
+        *painting = Some(touch.id());
+    }
+    if *painting != Some(touch.id()) || (!started && touch.delta() == Vec2::ZERO) {
+        return;
+    }
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let Ok((camera, transform)) = cameras.get_single() else {
+        return;
+    };
+
+    let (start, end) = (touch.previous_position(), touch.position());
+    let steps = if started { 1 } else { ((end - start).length() / TOUCH_SPACING).ceil().max(1.0) as usize };
+    let mut positions: Vec<Position> = Vec::with_capacity(steps);
+    for step in 1..=steps {
+        let point = start.lerp(end, step as f32 / steps as f32);
+        // Touches are measured from the top left corner
+        let point = Vec2::new(point.x, window.height() - point.y);
+        let Some(ray) = camera.viewport_to_world(transform, point) else {
+            continue;
+        };
+        if let Some(position) = state.cell_at(ray.origin.truncate()) {
+            if positions.last() != Some(&position) {
+                positions.push(position);
+            }
+        }
+    }
+    for position in positions {
+        state.place_seed(position);
+    }
+}
+
+/// Nudge `f` up and down and `k` left and right with the right stick of a
+/// gamepad while `West` is held, every `NUDGE_INTERVAL` and only if they
+/// change, and change the concentration of B of the brush with the
+/// triggers, up with the right one and down with the left one
+fn nudge_with_gamepad(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    triggers: Res<Axis<GamepadButton>>,
+    mut state: ResMut<SimulationState>,
+    mut pending: Local<f32>,
+) {
+    let seconds = time.delta_seconds();
+    let trigger = |button| {
+        gamepads
+            .iter()
+            .filter_map(|gamepad| triggers.get(GamepadButton::new(gamepad, button)))
+            .fold(0.0, f32::max)
+    };
+    let strength = trigger(GamepadButtonType::RightTrigger2) - trigger(GamepadButtonType::LeftTrigger2);
+    if strength != 0.0 {
+        let b = &mut state.brush.cell.b;
+        *b = (*b + strength * BRUSH_SPEED * seconds).clamp(0.0, 1.0);
+    }
+
+    let nudging = gamepad_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::West);
+    let tilt = stick(&gamepads, &axes, [GamepadAxisType::RightStickX, GamepadAxisType::RightStickY]);
+    if !nudging || tilt == Vec2::ZERO {
+        *pending = 0.0;
+        return;
+    }
+    // Seconds of nudging not applied yet
+    *pending += seconds;
+    if *pending < NUDGE_INTERVAL {
+        return;
+    }
+    let seconds = std::mem::take(&mut *pending);
+    let current = state.simulation.parameters();
+    let mut parameters = current;
+    parameters.f = (parameters.f + tilt.y * NUDGE_SPEED * seconds).clamp(0.0, 1.0);
+    parameters.k = (parameters.k + tilt.x * NUDGE_SPEED * seconds).clamp(0.0, 1.0);
+    if parameters == current {
+        return;
+    }
+    state.set_parameters(parameters);
+    state.preset = None;
+}
+
+/// Start again from a random universe when `Select` is pressed on a gamepad
+fn reset_with_gamepad(
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stats: ResMut<SimulationStats>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    if !gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::Select) {
+        return;
+    }
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+    let seed = rand::random();
+    match state.reseed(seed) {
+        Ok(()) => {
+            info!("started again from seed {seed}");
+            *stats = SimulationStats { generation: 0, stats: state.simulation.stats() };
+        }
+        Err(error) => warn!("could not start again: {error}"),
+    }
+}
+
+/// Put back the generation computed in the background once done
+fn finish_evolution(
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    if evolution.busy {
+        collect_evolution(&mut evolution, &mut state, &mut stepped, false);
+    }
+}
+
+/// Copy the universe into `MorphogenField` whenever it changed, before the
+/// systems of the frame read it; the universe is away while a generation is
+/// computed, so the field keeps the previous one until then
+fn update_morphogen_field(evolution: Res<Evolution>, state: Res<SimulationState>, mut field: ResMut<MorphogenField>) {
+    if evolution.busy || !state.is_changed() {
+        return;
+    }
+    let updated = state.morphogen_field();
+    if *field != updated {
+        *field = updated;
+    }
+}
+
+/// Start computing the next generation in the background once the previous
+/// one is done, finishing once the generation limit is reached or the
+/// simulation stopped
+fn start_evolution(
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if evolution.busy {
+        return;
+    }
+    if state.simulation.generation() >= state.max_generations || state.simulation.is_stopped() {
+        info!("finished at generation {}", state.simulation.generation());
+        let _ = app_state.set(AppState::Finished);
+        return;
+    }
+
+    let state = &mut *state;
+    let generation = state.simulation.generation();
+    let pending = state
+        .events
+        .iter()
+        .take_while(|timed| timed.generation <= generation)
+        .count();
+    for timed in state.events.drain(..pending) {
+        if let Err(error) = apply_event(&timed.event, &mut state.simulation) {
+            error!("could not replay generation {}: {error}", timed.generation);
+        }
+        state.rewind.mark();
+    }
+    if state.rewind.is_marked() {
+        state.rewind.keep(&state.simulation);
+    }
+
+    evolution.start(state.begin_steps());
+}
+
+/// Apply the changes made to `SimulationParameters`, or mirror the parameters
+/// of the simulation there when they changed otherwise, e.g. with a preset
+fn sync_parameters(mut state: ResMut<SimulationState>, mut parameters: ResMut<SimulationParameters>) {
+    let current = state.simulation.parameters();
+    if parameters.0 == current {
+        return;
+    }
+    if parameters.is_changed() && !parameters.is_added() {
+        state.set_parameters(parameters.0);
+    } else {
+        parameters.bypass_change_detection().0 = current;
+    }
+}
+
+/// Changes sent by the MIDI and OSC controls
+#[cfg(feature = "control")]
+#[derive(Resource)]
+struct ControlInput(std::sync::Mutex<std::sync::mpsc::Receiver<ControlChange>>);
+
+/// Apply the changes sent by the controls since the last frame
+#[cfg(feature = "control")]
+fn apply_controls(input: Res<ControlInput>, mut state: ResMut<SimulationState>) {
+    let changes: Vec<ControlChange> = match input.0.lock() {
+        Ok(receiver) => receiver.try_iter().collect(),
+        Err(_) => return,
+    };
+    if changes.is_empty() {
+        return;
+    }
+    let mut parameters = state.simulation.parameters();
+    for ControlChange { target, value } in changes.iter().copied() {
+        match target {
+            ControlTarget::DiffusionA => parameters.d_a = value,
+            ControlTarget::DiffusionB => parameters.d_b = value,
+            ControlTarget::F => parameters.f = value,
+            ControlTarget::K => parameters.k = value,
+            ControlTarget::R => parameters.r = value,
+            ControlTarget::BrushRadius => state.brush.radius = value.round().max(0.0) as usize,
+            ControlTarget::BrushB => state.brush.cell.b = value.clamp(0.0, 1.0),
+            ControlTarget::Palette => {
+                if let Some(colormap) = (ControlChange { target, value }).colormap() {
+                    state.output.colormap = colormap;
+                }
+            }
+        }
+    }
+    if parameters != state.simulation.parameters() {
+        state.set_parameters(parameters);
+        state.preset = None;
+    }
+}
+
+/// Compute the statistics again once `stats_interval` generations have
+/// passed, finishing if they barely changed since the last time
+fn update_stats(
+    evolution: Res<Evolution>,
+    state: Res<SimulationState>,
+    mut stats: ResMut<SimulationStats>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let generation = state.simulation.generation();
+    if evolution.busy || generation == stats.generation || generation % state.stats_interval != 0 {
+        return;
+    }
+
+    let previous = *stats;
+    *stats = SimulationStats { generation, stats: state.simulation.stats() };
+    let change = stats.stats.change_since(&previous.stats, generation - previous.generation);
+    if change < CONVERGENCE_TOLERANCE {
+        info!("converged at generation {generation}");
+        let _ = app_state.set(AppState::Finished);
+    }
+}
+
+/// Halve the resolution of the universe when `-` is pressed and double it
+/// when `=` is pressed, resampling the cells and the textures; the window
+/// keeps its size, and the textures the resolution of `SimulationState::render`
+fn resize_universe(
+    keys: Res<Input<KeyCode>>,
+    textures: Res<MapTextures>,
+    mut images: ResMut<Assets<Image>>,
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    let dimensions = state.simulation.dimensions();
+    let scale = |size: usize| {
+        if keys.just_pressed(KeyCode::Minus) {
+            (size / 2).max(MIN_RESOLUTION)
+        } else if keys.just_pressed(KeyCode::Equals) {
+            (size * 2).min(MAX_RESOLUTION)
+        } else {
+            size
+        }
+    };
+    let resized = Position { row: scale(dimensions.row), col: scale(dimensions.col) };
+    if resized == dimensions {
+        return;
+    }
+
+    info!("resizing the universe to {}x{} cells", resized.row, resized.col);
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+    if let Err(error) = state.resize(resized) {
+        error!("could not resize the universe: {error}");
+        return;
+    }
+    resize_textures(&textures, &mut images, state.render.rendered(resized));
+}
+
+/// Give the textures of the color maps the dimensions `rendered`
+fn resize_textures(textures: &MapTextures, images: &mut Assets<Image>, rendered: Position) {
+    for texture in &textures.0 {
+        if let Some(image) = images.get_mut(texture) {
+            image.resize(Extent3d {
+                width: rendered.col as u32,
+                height: rendered.row as u32,
+                depth_or_array_layers: 1,
+            });
+        }
+    }
+}
+
+/// Color the cells of the color maps that changed and queue them to be
+/// uploaded to the textures, reduced as `SimulationState::render` says, and
+/// the rows already finished of a long generation while it computes
+/// Everything is drawn again when the textures, the color map or the
+/// resolution change, when the activity is not tracked, when the activation
+/// times are shown, whose colors depend on the latest of them, or when the
+/// composite shows the next layer, whose changes are not tracked along
+#[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+#[allow(clippy::too_many_arguments)]
+fn draw_colored_map(
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    textures: Res<MapTextures>,
+    palette: Res<MapPalette>,
+    mut images: ResMut<Assets<Image>>,
+    mut uploads: ResMut<TextureUploads>,
+    mut drawn: Local<Option<(Colormap, RenderConfig, bool)>>,
+) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if evolution.busy && !state.show_activation && state.render.mode == DisplayMode::Colormap {
+        draw_partials(&mut evolution, &state, &textures, &mut uploads);
+    }
+    if evolution.busy || !(state.is_changed() || textures.is_changed()) {
+        return;
+    }
+
+    let (colormap, config, show_activation) = (state.output.colormap, state.render, state.show_activation);
+    if drawn.is_none_or(|(drawn, ..)| drawn != colormap) {
+        if let Some(image) = images.get_mut(&palette.0) {
+            image.data = render::palette(colormap);
+        }
+    }
+    let redraw = textures.is_changed() || show_activation || *drawn != Some((colormap, config, show_activation));
+    *drawn = Some((colormap, config, show_activation));
+
+    let dimensions = state.simulation.dimensions();
+    let (rendered, factor) = (config.rendered(dimensions), config.factor(dimensions));
+    let composite = config.mode == DisplayMode::Composite;
+    // The cells drawn are forgotten without it being a change of the state
+    let state = state.bypass_change_detection();
+    let layered = composite && !state.couplings.is_empty();
+    let regions: Vec<_> = std::iter::once(&mut state.simulation)
+        .chain(&mut state.comparisons)
+        .map(|simulation| {
+            let regions = match simulation.dirty_cells() {
+                Some(dirty) if !(redraw || layered) => dirty_regions(&dirty, factor),
+                _ => vec![(0..rendered.row, 0..rendered.col)],
+            };
+            simulation.clear_dirty();
+            regions
+        })
+        .collect();
+    let simulations: Vec<&Simulation> = std::iter::once(&state.simulation).chain(&state.comparisons).collect();
+    for (index, (regions, texture)) in regions.iter().zip(&textures.0).enumerate() {
+        let simulation = simulations[index];
+        let activation = simulation.activation().filter(|_| show_activation).map(ActivationMap::colored_map);
+        let colored_map = activation.as_ref().unwrap_or(simulation.colored_map());
+        let layer = simulations.get(index + 1).filter(|_| layered).map(|layer| layer.universe());
+        for region in regions {
+            let pixels = profile::time(profile::COLORING, || match composite && activation.is_none() {
+                true => composite_pixels(simulation.universe(), layer, region, config, factor),
+                false => region_pixels(colored_map, region, config, factor, colormap),
+            });
+            profile::time(profile::UPLOAD, || uploads.push(texture, region, pixels));
+        }
+    }
+}
+
+/// Queue the rows finished by the generation being computed to be uploaded
+/// to the textures, over the previous generation
+/// Rows of tiles starting between two rows of a reduced texture are left for
+/// the finished generation
+#[cfg(not(target_arch = "wasm32"))]
+fn draw_partials(
+    evolution: &mut Evolution,
+    state: &SimulationState,
+    textures: &MapTextures,
+    uploads: &mut TextureUploads,
+) {
+    let dimensions = state.simulation.dimensions();
+    let (rendered, factor) = (state.render.rendered(dimensions), state.render.factor(dimensions));
+    for partial in evolution.take_partials(dimensions.row) {
+        if !partial.rows.start.is_multiple_of(factor) {
+            continue;
+        }
+        let Some(texture) = textures.0.get(partial.index) else {
+            continue;
+        };
+        let start = partial.rows.start / factor;
+        let rows = partial.colored_map.len().div_ceil(factor).min(rendered.row.saturating_sub(start));
+        let pixels =
+            region_pixels(&partial.colored_map, &(0..rows, 0..rendered.col), state.render, factor, state.output.colormap);
+        uploads.push(texture, &(start..start + rows, 0..rendered.col), pixels);
+    }
+}
+
+/// Switch between drawing the concentrations and the activation times when
+/// `T` is pressed
+fn toggle_activation(keys: Res<Input<KeyCode>>, mut state: ResMut<SimulationState>) {
+    if !keys.just_pressed(KeyCode::T) {
+        return;
+    }
+    if state.simulation.activation().is_none() {
+        warn!("the activation times are not tracked, run with --activation-threshold");
+        return;
+    }
+    state.show_activation = !state.show_activation;
+}
+
+/// Switch between the color map and the composite of the species when `C`
+/// is pressed
+fn toggle_composite(keys: Res<Input<KeyCode>>, mut state: ResMut<SimulationState>) {
+    if !keys.just_pressed(KeyCode::C) {
+        return;
+    }
+    if state.render.format == PixelFormat::R8 {
+        warn!("the composite cannot be drawn in the r8 format, run with --pixel-format rgba8");
+        return;
+    }
+    state.render.mode = match state.render.mode {
+        DisplayMode::Colormap => DisplayMode::Composite,
+        DisplayMode::Composite => DisplayMode::Colormap,
+    };
+}
+
+/// Create the legend of the composite, hidden while the color map is drawn
+fn spawn_composite_legend(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::default().with_style(Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            position: UiRect { right: Val::Px(6.0), top: Val::Px(6.0), ..default() },
+            ..default()
+        }),
+        CompositeLegend,
+    ));
+}
+
+/// Show the concentration of each channel of the composite, in its color,
+/// while the composite is drawn
+fn show_composite_legend(
+    state: Res<SimulationState>,
+    asset_server: Res<AssetServer>,
+    mut legend: Query<(&mut Text, &mut Style), With<CompositeLegend>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    let visible = state.render.mode == DisplayMode::Composite && !state.show_activation;
+    let channels = [Color::rgb(1.0, 0.3, 0.3), Color::rgb(0.3, 1.0, 0.3), Color::rgb(0.4, 0.6, 1.0)];
+    for (mut text, mut style) in &mut legend {
+        style.display = if visible { Display::Flex } else { Display::None };
+        if !visible {
+            continue;
+        }
+        let font = asset_server.load(CONSOLE_FONT);
+        text.sections = state
+            .render
+            .channels
+            .sources()
+            .iter()
+            .zip(channels)
+            .filter_map(|(source, color)| {
+                let style = TextStyle { font: font.clone(), font_size: PROGRESS_FONT_SIZE, color };
+                Some(TextSection::new(format!("{}\n", source.label()?), style))
+            })
+            .collect();
+    }
+}
+
+/// Pause the run on the blow-ups found by the check of the simulation, see
+/// `blowup`, once each, so that resuming it evolves at least until the next
+/// scan of the universe
+fn pause_on_blowup(
+    state: Res<SimulationState>,
+    mut alert: ResMut<BlowupAlert>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let Some(blowup) = state.simulation.blowup() else {
+        return;
+    };
+    if alert.paused == Some(blowup.generation) {
+        return;
+    }
+    alert.paused = Some(blowup.generation);
+    error!("pausing: {blowup}");
+    // Fails only if the state is already changing this frame
+    let _ = app_state.set(AppState::Paused);
+}
+
+/// Create the message of the blow-ups, hidden until there is one
+fn spawn_blowup_message(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style = TextStyle {
+        font: asset_server.load(CONSOLE_FONT),
+        font_size: PROGRESS_FONT_SIZE,
+        color: Color::rgb(1.0, 0.3, 0.3),
+    };
+    commands.spawn((
+        TextBundle::from_section("", style).with_style(Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(6.0), top: Val::Px(6.0), ..default() },
+            ..default()
+        }),
+        BlowupMessage,
+    ));
+}
+
+/// Highlight the blown up cells of the last scan in red, and tell what blew
+/// up, with which parameters and which rate is suspected, until a scan finds
+/// none or the universe is restored
+fn highlight_blowup(
+    mut commands: Commands,
+    state: Res<SimulationState>,
+    mut alert: ResMut<BlowupAlert>,
+    highlights: Query<Entity, With<BlowupHighlight>>,
+    mut message: Query<(&mut Text, &mut Style), With<BlowupMessage>>,
+) {
+    let blowup = state.simulation.blowup();
+    if !state.is_changed() || alert.highlighted == blowup.map(|blowup| blowup.generation) {
+        return;
+    }
+    alert.highlighted = blowup.map(|blowup| blowup.generation);
+    for highlight in &highlights {
+        commands.entity(highlight).despawn();
+    }
+    for (mut text, mut style) in &mut message {
+        style.display = if blowup.is_some() { Display::Flex } else { Display::None };
+        if let Some(blowup) = blowup {
+            text.sections[0].value = blowup.to_string().replace("; ", "\n");
+        }
+    }
+    let Some(blowup) = blowup else {
+        return;
+    };
+    let dimensions = state.simulation.dimensions();
+    let cell = state.render.rendered(dimensions).col as f32 / dimensions.col.max(1) as f32;
+    for &position in &blowup.cells {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 0.0, 0.0, 0.8),
+                    custom_size: Some(Vec2::splat(cell.max(3.0))),
+                    ..default()
+                },
+                transform: Transform::from_translation(state.cell_center(position).extend(2.0)),
+                ..default()
+            },
+            BlowupHighlight,
+        ));
+    }
+}
+
+/// Create the progress indicator, hidden until a generation takes longer
+/// than `PROGRESSIVE_DELAY`
+fn spawn_progress_indicator(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style =
+        TextStyle { font: asset_server.load(CONSOLE_FONT), font_size: PROGRESS_FONT_SIZE, color: Color::WHITE };
+    commands.spawn((
+        TextBundle::from_section("", style).with_style(Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(6.0), bottom: Val::Px(6.0), ..default() },
+            ..default()
+        }),
+        ProgressIndicator,
+    ));
+}
+
+/// Show which generation is being computed while it is drawn as it goes, and
+/// how far it got
+fn show_progress(
+    evolution: Res<Evolution>,
+    state: Res<SimulationState>,
+    mut indicator: Query<(&mut Text, &mut Style), With<ProgressIndicator>>,
+) {
+    let visible = evolution.is_progressive();
+    for (mut text, mut style) in &mut indicator {
+        style.display = if visible { Display::Flex } else { Display::None };
+        if visible {
+            let percent = (evolution.progress * 100.0).round();
+            text.sections[0].value = format!("computing generation {}… {percent}%", state.simulation.generation() + 1);
+        }
+    }
+}
+
+/// Print the time spent in each stage when the application exits, if asked
+fn report_profile(mut exits: EventReader<AppExit>, state: Res<SimulationState>) {
+    if exits.iter().next().is_some() && state.profile {
+        eprint!("{}", profile::report());
+    }
+}
+
+/// Show the timeline bar while paused or finished, if the run can be rewound,
+/// with its marker at the current generation
+#[allow(clippy::type_complexity)]
+fn draw_timeline(
+    state: Res<SimulationState>,
+    app_state: Res<State<AppState>>,
+    width: Res<TimelineWidth>,
+    mut bar: Query<&mut Visibility, (With<TimelineBar>, Without<TimelineMarker>)>,
+    mut marker: Query<(&mut Visibility, &mut Transform), With<TimelineMarker>>,
+) {
+    let range = state.rewind.range().filter(|(start, end)| end > start);
+    let visible = matches!(app_state.current(), AppState::Paused | AppState::Finished) && range.is_some();
+    for mut visibility in &mut bar {
+        visibility.is_visible = visible;
+    }
+    for (mut visibility, mut transform) in &mut marker {
+        visibility.is_visible = visible;
+        if let Some((start, end)) = range {
+            let fraction = (state.simulation.generation() - start) as f32 / (end - start) as f32;
+            transform.translation.x = (fraction.clamp(0.0, 1.0) - 0.5) * width.0;
+        }
+    }
+}
+
+/// Rewind the run to the generation under the cursor while the left button
+/// is held on the timeline bar, or by one keyframe interval with the arrow
+/// keys, to the first generation covered with `Home` and the last one with
+/// `End`
+fn scrub_timeline(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stats: ResMut<SimulationStats>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    let Some((start, end)) = state.rewind.range() else {
+        return;
+    };
+    let generation = state.simulation.generation();
+    let interval = state.rewind.config().interval;
+    let mut target = if keys.just_pressed(KeyCode::Left) {
+        Some(generation - interval)
+    } else if keys.just_pressed(KeyCode::Right) {
+        Some(generation + interval)
+    } else if keys.just_pressed(KeyCode::Home) {
+        Some(start)
+    } else if keys.just_pressed(KeyCode::End) {
+        Some(end)
+    } else {
+        None
+    };
+    if buttons.pressed(MouseButton::Left) {
+        // The cursor is measured from the bottom left corner
+        let cursor = windows.get_primary().and_then(|window| Some((window.cursor_position()?, window.width())));
+        if let Some((cursor, width)) = cursor.filter(|(cursor, _)| cursor.y <= TIMELINE_HEIGHT) {
+            let fraction = (cursor.x / width).clamp(0.0, 1.0);
+            target = Some(start + (fraction * (end - start) as f32).round() as i32);
+        }
+    }
+    let Some(target) = target.map(|target| target.clamp(start, end)).filter(|target| *target != generation) else {
+        return;
+    };
+
+    // Pausing leaves the generation computed at the time to finish
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+    let state = &mut *state;
+    match state.rewind.seek(&mut state.simulation, target) {
+        Ok(generation) => {
+            *stats = SimulationStats { generation, stats: state.simulation.stats() };
+        }
+        Err(error) => error!("could not rewind to generation {target}: {error}"),
+    }
+}
+
+/// Save a snapshot of the universe when `S` is pressed
+#[cfg(feature = "fs")]
+fn save_snapshot(
+    keys: Res<Input<KeyCode>>,
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    if !keys.just_pressed(KeyCode::S) {
+        return;
+    }
+    // Pausing leaves the generation computed at the time to finish
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+
+    let path = match &state.output.snapshot {
+        Some(path) => path.clone(),
+        None => expand_path(Path::new(DEFAULT_SNAPSHOT_PATH), &state.name),
+    };
+    let path = path.as_path();
+    let snapshot = Snapshot::of(&state.simulation);
+    match snapshot.save(path) {
+        Ok(()) => info!("saved snapshot of generation {} to {}", snapshot.generation, path.display()),
+        Err(error) => error!("could not save snapshot to {}: {error}", path.display()),
+    }
+}
+
+/// Export the concentrations of A and B when `E` is pressed
+#[cfg(feature = "fs")]
+fn export_fields(
+    keys: Res<Input<KeyCode>>,
+    mut evolution: ResMut<Evolution>,
+    mut state: ResMut<SimulationState>,
+    mut stepped: EventWriter<SimulationStepped>,
+) {
+    if !keys.just_pressed(KeyCode::E) {
+        return;
+    }
+    // Pausing leaves the generation computed at the time to finish
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+
+    let path = match &state.output.fields {
+        Some(path) => path.clone(),
+        None => expand_path(Path::new(DEFAULT_FIELDS_PATH), &state.name),
+    };
+    let path = path.as_path();
+    match save_fields(state.simulation.universe(), path) {
+        Ok([a, b]) => info!("saved fields to {} and {}", a.display(), b.display()),
+        Err(error) => error!("could not save fields to {}: {error}", path.display()),
+    }
+}
+
+/// Start loading the preset file
+fn load_presets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Presets(asset_server.load("presets.ron")));
+}
+
+/// Switch to the preset of the number key pressed, or to the next or the
+/// previous one when a bumper of a gamepad is pressed
+fn select_preset(
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    presets: Res<Presets>,
+    assets: Res<Assets<PresetAsset>>,
+    mut state: ResMut<SimulationState>,
+) {
+    let key = PRESET_KEYS.iter().position(|key| keys.just_pressed(*key));
+    let step = [(GamepadButtonType::LeftTrigger, -1), (GamepadButtonType::RightTrigger, 1)]
+        .into_iter()
+        .find(|(button, _)| gamepad_just_pressed(&gamepads, &gamepad_buttons, *button))
+        .map(|(_, step)| step);
+    if key.is_none() && step.is_none() {
+        return;
+    }
+    let Some(PresetAsset(library)) = assets.get(&presets.0) else {
+        return;
+    };
+    let count = library.presets.len() as isize;
+    let index = match (key, step) {
+        (Some(index), _) => index,
+        (None, Some(step)) if count > 0 => {
+            let current = state.preset.as_deref().and_then(|current| library.names().position(|name| name == current));
+            match current {
+                Some(current) => (current as isize + step).rem_euclid(count) as usize,
+                // From the first preset forward, or the last one backward
+                None => step.min(0).rem_euclid(count) as usize,
+            }
+        }
+        _ => return,
+    };
+    let Some((name, parameters)) = library.presets.iter().nth(index) else {
+        return;
+    };
+
+    info!("switching to preset {name}");
+    state.set_parameters(*parameters);
+    state.preset = Some(name.clone());
+}
+
+/// Apply the new parameters of the preset in use when the preset file changes
+fn reload_preset(
+    mut events: EventReader<AssetEvent<PresetAsset>>,
+    assets: Res<Assets<PresetAsset>>,
+    mut state: ResMut<SimulationState>,
+) {
+    for event in events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+        let Some(PresetAsset(library)) = assets.get(handle) else {
+            continue;
+        };
+        let Some(parameters) = state.preset.as_deref().and_then(|name| library.get(name)) else {
+            continue;
+        };
+        if parameters != state.simulation.parameters() {
+            info!("preset {} changed, applying it", state.preset.as_deref().unwrap_or_default());
+            state.set_parameters(parameters);
+        }
+    }
+}
+
+/// Configuration file as last read by `reload_config`
+#[cfg(feature = "fs")]
+struct WatchedConfig {
+    timer: Timer,
+    modified: Option<SystemTime>,
+    parameters: Option<Parameters>,
+}
+
+#[cfg(feature = "fs")]
+impl Default for WatchedConfig {
+    fn default() -> Self {
+        WatchedConfig {
+            timer: Timer::new(CONFIG_POLL_INTERVAL, TimerMode::Repeating),
+            modified: None,
+            parameters: None,
+        }
+    }
+}
+
+/// Apply the parameters of the configuration file when they change in it
+/// Its modification time is checked every `CONFIG_POLL_INTERVAL`. Parameters
+/// changed from the window, or on the command line, are kept as long as
+/// those of the file stay the same, whatever else changes in it; the other
+/// settings are only read at launch
+#[cfg(feature = "fs")]
+fn reload_config(time: Res<Time>, mut watched: Local<WatchedConfig>, mut state: ResMut<SimulationState>) {
+    let Some(path) = state.config_file.clone() else {
+        return;
+    };
+    if !watched.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+    if modified == watched.modified {
+        return;
+    }
+    watched.modified = modified;
+    match Parameters::from_file(&path) {
+        // The first reading is the one the run started with
+        Ok(parameters) if watched.parameters.replace(parameters).is_some_and(|last| last != parameters) => {
+            info!("{} changed, applying its parameters", path.display());
+            state.set_parameters(parameters);
+            state.preset = None;
+        }
+        Ok(_) => {}
+        Err(error) => warn!("could not reload {}: {error}", path.display()),
+    }
+}
+
+/// Simulation state once the generation being computed, if any, is put back,
+/// for the commands of the console
+fn collected_state(world: &mut World) -> Mut<'_, SimulationState> {
+    let mut system_state: SystemState<(ResMut<Evolution>, ResMut<SimulationState>, EventWriter<SimulationStepped>)> =
+        SystemState::new(world);
+    let (mut evolution, mut state, mut stepped) = system_state.get_mut(world);
+    collect_evolution(&mut evolution, &mut state, &mut stepped, true);
+    system_state.apply(world);
+    world.resource_mut::<SimulationState>()
+}
+
+/// `set <parameter> <value>`: change one parameter of the simulation
+fn set_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    let [name, value] = arguments else {
+        return Err("usage: set <parameter> <value>".to_string());
+    };
+    let value: f32 = value.parse().map_err(|error| format!("invalid value `{value}`: {error}"))?;
+    let mut state = collected_state(world);
+    let mut parameters = state.simulation.parameters();
+    let parameter = match *name {
+        "d_a" => &mut parameters.d_a,
+        "d_b" => &mut parameters.d_b,
+        "f" => &mut parameters.f,
+        "k" => &mut parameters.k,
+        "r" => &mut parameters.r,
+        other => return Err(format!("unknown parameter `{other}`, expected one of: d_a, d_b, f, k, r")),
+    };
+    *parameter = value;
+    parameters.validate().map_err(|error| error.to_string())?;
+    state.set_parameters(parameters);
+    Ok(format!("{name} = {value}"))
+}
+
+/// `preset <name>`: switch to a preset of the preset file, or a built-in one
+fn preset_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    let [name] = arguments else {
+        return Err("usage: preset <name>".to_string());
+    };
+    let handle = world.resource::<Presets>().0.clone();
+    let library = world.resource::<Assets<PresetAsset>>().get(&handle).map(|PresetAsset(library)| library.clone());
+    let parameters = library
+        .as_ref()
+        .and_then(|library| library.get(name))
+        .or_else(|| Parameters::preset(name))
+        .ok_or_else(|| {
+            let names: Vec<&str> = match &library {
+                Some(library) => library.names().collect(),
+                None => crate::PRESET_NAMES.to_vec(),
+            };
+            format!("unknown preset `{name}`, expected one of: {}", names.join(", "))
+        })?;
+    let mut state = collected_state(world);
+    state.set_parameters(parameters);
+    state.preset = Some(name.to_string());
+    Ok(format!("switched to preset {name}"))
+}
+
+/// `seed <seed>`: start the simulations again at generation 0 from random
+/// cells drawn from `seed`, keeping their parameters
+fn seed_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    let [seed] = arguments else {
+        return Err("usage: seed <seed>".to_string());
+    };
+    let seed: u64 = seed.parse().map_err(|error| format!("invalid seed `{seed}`: {error}"))?;
+    let mut state = collected_state(world);
+    state.reseed(seed)?;
+    let stats = SimulationStats { generation: 0, stats: state.simulation.stats() };
+    *world.resource_mut::<SimulationStats>() = stats;
+    Ok(format!("started again from seed {seed}"))
+}
+
+/// `profile [save|delete] [<name>]`: switch to the saved profile `name`,
+/// save the current setup as `name` or forget it, or list the profiles
+/// without arguments
+#[cfg(feature = "fs")]
+fn profile_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    match arguments {
+        [] => {
+            let names: Vec<&str> = world.resource::<Profiles>().library.names().collect();
+            if names.is_empty() {
+                return Ok("no saved profiles, save one with `profile save <name>`".to_string());
+            }
+            Ok(format!("saved profiles: {}", names.join(", ")))
+        }
+        ["save", name] => {
+            let profile = collected_state(world).profile();
+            let mut profiles = world.resource_mut::<Profiles>();
+            profiles.library.insert(name, profile);
+            profiles.current = Some(name.to_string());
+            let path = profiles.save()?;
+            Ok(format!("saved the profile {name} to {}", path.display()))
+        }
+        ["delete", name] => {
+            let mut profiles = world.resource_mut::<Profiles>();
+            if profiles.library.remove(name).is_none() {
+                return Err(format!("unknown profile `{name}`"));
+            }
+            if profiles.current.as_deref() == Some(*name) {
+                profiles.current = None;
+            }
+            profiles.save()?;
+            Ok(format!("forgot the profile {name}"))
+        }
+        [name] => switch_profile(world, name),
+        _ => Err("usage: profile [save|delete] [<name>]".to_string()),
+    }
+}
+
+/// Switch to the saved profile `name`, resizing the textures to its
+/// dimensions
+#[cfg(feature = "fs")]
+fn switch_profile(world: &mut World, name: &str) -> CommandResult {
+    let profiles = world.resource::<Profiles>();
+    let profile = profiles.library.get(name).ok_or_else(|| {
+        let names: Vec<&str> = profiles.library.names().collect();
+        format!("unknown profile `{name}`, expected one of: {}", names.join(", "))
+    })?;
+    let (rendered, stats) = {
+        let mut state = collected_state(world);
+        state.apply_profile(&profile)?;
+        (state.render.rendered(profile.dimensions), state.simulation.stats())
+    };
+    *world.resource_mut::<SimulationStats>() = SimulationStats { generation: 0, stats };
+    world.resource_scope(|world, mut images: Mut<Assets<Image>>| {
+        resize_textures(world.resource::<MapTextures>(), &mut images, rendered);
+    });
+    world.resource_mut::<Profiles>().current = Some(name.to_string());
+    Ok(format!("switched to profile {name}"))
+}
+
+/// Switch to the next saved profile, in the order of their names, when
+/// `Tab` is pressed
+#[cfg(feature = "fs")]
+fn cycle_profiles(world: &mut World) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(KeyCode::Tab) {
+        return;
+    }
+    let profiles = world.resource::<Profiles>();
+    let Some(name) = profiles.library.next(profiles.current.as_deref()).map(str::to_string) else {
+        return;
+    };
+    match switch_profile(world, &name) {
+        Ok(message) => info!("{message}"),
+        Err(error) => error!("could not switch to profile {name}: {error}"),
+    }
+}
+
+/// `export [<path>]`: save the color map of the universe as a PNG or JPEG
+/// file, by default named after the run and the generation
+#[cfg(feature = "fs")]
+fn export_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    let state = collected_state(world);
+    let path = match arguments {
+        [] => PathBuf::from(format!("{}_{}.png", state.name, state.simulation.generation())),
+        [path] => PathBuf::from(path),
+        _ => return Err("usage: export [<path>]".to_string()),
+    };
+    save_colored_map(state.simulation.colored_map(), state.output.colormap, &path)
+        .map_err(|error| error.to_string())?;
+    Ok(format!("saved the color map to {}", path.display()))
+}
+
+/// `brush <radius> [<a> <b>]`: change the seeds placed with the mouse
+fn brush_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    let parse = |value: &str| value.parse::<f32>().map_err(|error| format!("invalid concentration `{value}`: {error}"));
+    let mut brush = world.resource::<SimulationState>().brush;
+    let radius = match arguments {
+        [radius] => radius,
+        [radius, a, b] | [radius, a, b, _] => {
+            brush.cell = Cell { a: parse(a)?, b: parse(b)? };
+            radius
+        }
+        _ => return Err("usage: brush <radius> [<a> <b> [<jitter>]]".to_string()),
+    };
+    if let [_, _, _, jitter] = arguments {
+        brush.jitter = jitter.parse().map_err(|error| format!("invalid jitter `{jitter}`: {error}"))?;
+    }
+    brush.radius = radius.parse().map_err(|error| format!("invalid radius `{radius}`: {error}"))?;
+    brush.validate().map_err(|error| error.to_string())?;
+    world.resource_mut::<SimulationState>().brush = brush;
+    Ok(format!(
+        "seeds of radius {} with a = {}, b = {}, jitter {}",
+        brush.radius, brush.cell.a, brush.cell.b, brush.jitter
+    ))
+}
+
+/// `stamp <row> <col> <rows> <cols>`: copy the cells of a rectangle from
+/// its top left cell into the stamp; `stamp at <row> <col>`: stamp it
+/// centered on a cell; `stamp rotate` and `stamp mirror`: turn it a quarter
+/// turn clockwise or mirror it; `stamp clear`: place seeds again
+fn stamp_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    let parse = |value: &str| value.parse::<usize>().map_err(|error| format!("invalid cell `{value}`: {error}"));
+    let describe = |stamp: &Stamp| {
+        let (dimensions, orientation) = (stamp.dimensions(), stamp.orientation());
+        let mirrored = if orientation.mirrored { ", mirrored" } else { "" };
+        format!(
+            "stamp of {}x{} cells, turned {} quarter turns{mirrored}",
+            dimensions.row, dimensions.col, orientation.quarter_turns
+        )
+    };
+    match arguments {
+        [] => Ok(world.resource::<Stamping>().stamp.as_ref().map_or("no stamp".to_string(), describe)),
+        ["at", row, col] => {
+            let position = Position { row: parse(row)?, col: parse(col)? };
+            let Some(stamp) = world.resource::<Stamping>().stamp.clone() else {
+                return Err("no stamp, copy one first".to_string());
+            };
+            let rect = collected_state(world).place_stamp(&stamp, position).map_err(|error| error.to_string())?;
+            Ok(format!("stamped from ({}, {})", rect.origin.row, rect.origin.col))
+        }
+        [action @ ("rotate" | "mirror")] => {
+            let mut stamping = world.resource_mut::<Stamping>();
+            let Some(stamp) = &mut stamping.stamp else {
+                return Err("no stamp, copy one first".to_string());
+            };
+            match *action {
+                "rotate" => stamp.rotate(),
+                _ => stamp.mirror(),
+            }
+            Ok(describe(stamp))
+        }
+        ["clear"] => {
+            world.resource_mut::<Stamping>().stamp = None;
+            Ok("clicks place seeds again".to_string())
+        }
+        [row, col, rows, cols] => {
+            let origin = Position { row: parse(row)?, col: parse(col)? };
+            let dimensions = Position { row: parse(rows)?, col: parse(cols)? };
+            let stamp = Stamp::copy(collected_state(world).simulation.universe(), Rect { origin, dimensions })
+                .map_err(|error| error.to_string())?;
+            let message = describe(&stamp);
+            world.resource_mut::<Stamping>().stamp = Some(stamp);
+            Ok(format!("copied a {message}"))
+        }
+        _ => Err("usage: stamp [<row> <col> <rows> <cols>|at <row> <col>|rotate|mirror|clear]".to_string()),
+    }
+}
+
+/// `session [<path>]`: save the simulations with their settings, the camera
+/// and the window to `path`, or to the session output
+#[cfg(feature = "fs")]
+fn session_command(world: &mut World, arguments: &[&str]) -> CommandResult {
+    let path = match arguments {
+        [] => None,
+        [path] => Some(Path::new(path)),
+        _ => return Err("usage: session [<path>]".to_string()),
+    };
+    let window = world.resource::<Windows>().get_primary().map(|window| [window.width(), window.height()]);
+    let camera = world.query_filtered::<&Transform, With<Camera2d>>().iter(world).next().copied();
+    let state = collected_state(world);
+    let path = match path.or(state.output.session.as_deref()) {
+        Some(path) => path.to_path_buf(),
+        None => expand_path(Path::new(DEFAULT_SESSION_PATH), &state.name),
+    };
+    let simulation = &state.simulation;
+    let view = camera.zip(window).map(|(transform, window)| View {
+        translation: transform.translation.to_array(),
+        rotation: transform.rotation.to_array(),
+        scale: transform.scale.to_array(),
+        window,
+    });
+    let session = Session {
+        simulation: Snapshot::of(simulation),
+        comparisons: state.comparisons.iter().map(Snapshot::of).collect(),
+        name: state.name.clone(),
+        seed: state.seed,
+        streams: state.streams,
+        steps: state.max_generations,
+        bounds: simulation.bounds(),
+        boundary: simulation.boundary(),
+        stencil: simulation.stencil(),
+        timeline: simulation.timeline().clone(),
+        schedule: simulation.schedule().clone(),
+        modulation: simulation.modulation().map(|modulation| modulation.config().clone()),
+        reaction: simulation.reaction().and_then(|reaction| reaction.script().cloned()),
+        activity: simulation.activity().map(Activity::tracking),
+        noise: simulation.noise().map(Noise::config),
+        noise_seed: simulation.noise().map(Noise::seed),
+        blowup: simulation.blowup_check(),
+        symmetry: simulation.symmetry(),
+        couplings: state.couplings.clone(),
+        output: state.output.clone(),
+        preset: state.preset.clone(),
+        render: state.render,
+        rewind: state.rewind.config(),
+        stats_interval: state.stats_interval,
+        events: state.events.clone(),
+        initial_cells: state.initial_cells,
+        initial: state.initial,
+        brush: state.brush,
+        view,
+    };
+    session.save(&path).map_err(|error| error.to_string())?;
+    Ok(format!("saved the session to {}", path.display()))
+}
@@ -0,0 +1,231 @@
+/// Benchmarks of the evolution
+/// Times the evolution on the machine it runs on, to compare the backends:
+/// the precisions of the concentrations, see `float`, and the reference
+/// implementation of `reference`. Three stages are measured apart: the
+/// kernel, diffusion and reaction of every cell as computed by
+/// `PendingStep::compute`, the whole step of `Simulation::step`, bounds and
+/// observers included, and the coloring of the color map into the pixels of
+/// the window. Every measure starts with a few evolutions left out, for the
+/// caches and the pattern to settle. The `bench` command and the benchmarks
+/// of `cargo bench` both run them. Not available on the web, whose clock is
+/// not the one of the standard library
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::activity::ActivityTracking;
+use crate::colormap::Colormap;
+use crate::reference::reference_step;
+use crate::render::{region_pixels, RenderConfig};
+use crate::{
+    color_universe, initialize_universe_with_rng, Boundary, Float, Parameters, Position, Simulation, SimulationError,
+    Stencil, Universe, F16, INITIAL_CELLS,
+};
+
+/// Implementation of the evolution being timed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Concentrations in `f32`
+    #[default]
+    F32,
+    /// Concentrations in `f64`
+    F64,
+    /// Concentrations in half precision, computed in `f32`
+    F16,
+    /// Reference implementation, every cell computed from the whole universe
+    /// in `f64`, see `reference`
+    Reference,
+}
+
+/// Names of the backends, as given to the `bench` command
+pub const BACKEND_NAMES: [&str; 4] = ["f32", "f64", "f16", "reference"];
+
+/// Every backend, in the order of `BACKEND_NAMES`
+pub const BACKENDS: [Backend; 4] = [Backend::F32, Backend::F64, Backend::F16, Backend::Reference];
+
+impl Backend {
+    /// Backend with the given name, see `BACKEND_NAMES`
+    pub fn from_name(name: &str) -> Option<Backend> {
+        match name {
+            "f32" => Some(Backend::F32),
+            "f64" => Some(Backend::F64),
+            "f16" => Some(Backend::F16),
+            "reference" => Some(Backend::Reference),
+            _ => None,
+        }
+    }
+
+    /// Name of the backend, one of `BACKEND_NAMES`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::F32 => "f32",
+            Backend::F64 => "f64",
+            Backend::F16 => "f16",
+            Backend::Reference => "reference",
+        }
+    }
+}
+
+/// Settings of a benchmark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchConfig {
+    pub backend: Backend,
+    pub dimensions: Position,
+    pub parameters: Parameters,
+    pub boundary: Boundary,
+    pub stencil: Stencil,
+    /// Skip the quiescent tiles, see `activity`; ignored by the reference
+    pub activity: Option<ActivityTracking>,
+    /// Number of evolutions timed
+    pub steps: i32,
+    /// Number of evolutions computed before the timed ones
+    pub warmup: i32,
+    /// Seed of the random initial cells
+    pub seed: u64,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig {
+            backend: Backend::default(),
+            dimensions: Position { row: 256, col: 256 },
+            parameters: Parameters::default(),
+            boundary: Boundary::default(),
+            stencil: Stencil::default(),
+            activity: None,
+            steps: 200,
+            warmup: 20,
+            seed: 0,
+        }
+    }
+}
+
+impl BenchConfig {
+    /// Fails if nothing would be timed
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidBench(message));
+        if self.dimensions.row == 0 || self.dimensions.col == 0 {
+            return error(format!("the universe is empty, {}x{}", self.dimensions.row, self.dimensions.col));
+        }
+        if self.steps < 1 {
+            return error(format!("at least one evolution must be timed, not {}", self.steps));
+        }
+        if self.warmup < 0 {
+            return error(format!("the warm-up cannot be negative, found {}", self.warmup));
+        }
+        self.parameters.validate()?;
+        Ok(())
+    }
+}
+
+/// Time taken by the evolutions of a benchmark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchReport {
+    pub backend: Backend,
+    pub dimensions: Position,
+    pub steps: i32,
+    /// Time of the kernel alone, see `PendingStep::compute`, or of the
+    /// reference steps
+    pub kernel: Duration,
+    /// Time of the whole steps, kernel included
+    pub step: Duration,
+}
+
+impl BenchReport {
+    /// Number of whole steps per second
+    pub fn steps_per_second(&self) -> f64 {
+        self.steps as f64 / self.step.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+
+    /// Number of cells evolved per second by the kernel
+    pub fn cells_per_second(&self) -> f64 {
+        let cells = self.dimensions.row as f64 * self.dimensions.col as f64 * self.steps as f64;
+        cells / self.kernel.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}x{}: {:.1} steps/s, kernel {:.1} Mcells/s",
+            self.backend.name(),
+            self.dimensions.row,
+            self.dimensions.col,
+            self.steps_per_second(),
+            self.cells_per_second() / 1e6,
+        )
+    }
+}
+
+/// Time the evolutions of `config` on its backend
+/// Fails if the configuration is invalid
+pub fn bench(config: &BenchConfig) -> Result<BenchReport, SimulationError> {
+    config.validate()?;
+    let (kernel, step) = match config.backend {
+        Backend::F32 => bench_simulation(simulation::<f32>(config)?, config),
+        Backend::F64 => bench_simulation(simulation::<f64>(config)?, config),
+        Backend::F16 => bench_simulation(simulation::<F16>(config)?, config),
+        Backend::Reference => {
+            let mut universe = simulation::<f64>(config)?.universe().clone();
+            for _ in 0..config.warmup {
+                universe = reference_step(&config.parameters, config.boundary, &universe);
+            }
+            let start = Instant::now();
+            for _ in 0..config.steps {
+                universe = reference_step(&config.parameters, config.boundary, &universe);
+            }
+            let elapsed = start.elapsed();
+            (elapsed, elapsed)
+        }
+    };
+    Ok(BenchReport { backend: config.backend, dimensions: config.dimensions, steps: config.steps, kernel, step })
+}
+
+/// Simulation of `config` from its random initial cells
+fn simulation<T: Float>(config: &BenchConfig) -> Result<Simulation<T>, SimulationError> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let (universe, _) = initialize_universe_with_rng(&config.dimensions, INITIAL_CELLS, &mut rng);
+    let simulation = Simulation::new(config.parameters, config.dimensions, universe)?
+        .with_boundary(config.boundary)
+        .with_stencil(config.stencil);
+    Ok(match config.activity {
+        Some(tracking) => simulation.with_activity_tracking(tracking),
+        None => simulation,
+    })
+}
+
+/// Time of the kernel and of the whole steps of the timed evolutions of
+/// `simulation`, after the warm-up
+fn bench_simulation<T: Float>(mut simulation: Simulation<T>, config: &BenchConfig) -> (Duration, Duration) {
+    for _ in 0..config.warmup {
+        simulation.step();
+    }
+    let (mut kernel, mut step) = (Duration::ZERO, Duration::ZERO);
+    for _ in 0..config.steps {
+        let started = Instant::now();
+        let pending = simulation.begin_step();
+        let computing = Instant::now();
+        let evolved = pending.compute();
+        kernel += computing.elapsed();
+        simulation.finish_step(evolved);
+        step += started.elapsed();
+    }
+    (kernel, step)
+}
+
+/// Time the coloring of the color map of `universe` into the pixels of the
+/// window, `times` times, with `colormap` and at the resolution of `render`
+pub fn bench_coloring<T: Float>(universe: &Universe<T>, colormap: Colormap, render: RenderConfig, times: u32) -> Duration {
+    let dimensions = Position { row: universe.len(), col: universe.first().map_or(0, Vec::len) };
+    let (rendered, factor) = (render.rendered(dimensions), render.factor(dimensions));
+    let region = (0..rendered.row, 0..rendered.col);
+    let start = Instant::now();
+    for _ in 0..times {
+        let colored_map = color_universe(universe);
+        std::hint::black_box(region_pixels(&colored_map, &region, render, factor, colormap));
+    }
+    start.elapsed()
+}
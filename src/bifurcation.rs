@@ -0,0 +1,385 @@
+/// Bifurcation diagrams
+/// A bifurcation run moves `f` and `k` slowly along a path through the
+/// (f, k) plane, straight lines between given points, while a single
+/// simulation keeps evolving, as in an experiment where the feed is turned
+/// little by little: the pattern at each point grows out of the pattern at
+/// the previous one, so a path going out and back shows hysteresis. At
+/// evenly spaced points of the path the amplitude of B, the dominant
+/// wavelength and the kind of pattern are recorded. With the `fs` feature
+/// they are written to a CSV file and drawn as a diagram, see
+/// `Bifurcation::write`
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+#[cfg(feature = "fs")]
+use std::io::{BufWriter, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(feature = "fs")]
+use image::{Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::classify::PatternKind;
+#[cfg(feature = "fs")]
+use crate::config::{read_file, ConfigError};
+use crate::config::InitialConfig;
+use crate::timeline::{Keyframe, Timeline};
+use crate::{Boundary, Bounds, Parameters, Position, Simulation, SimulationError};
+
+/// Number of pixels on each side of the diagram, and around its axes
+#[cfg(feature = "fs")]
+const DIAGRAM_WIDTH: u32 = 800;
+#[cfg(feature = "fs")]
+const DIAGRAM_HEIGHT: u32 = 400;
+#[cfg(feature = "fs")]
+const DIAGRAM_MARGIN: u32 = 20;
+/// Number of pixels on each side of the marks of the points
+#[cfg(feature = "fs")]
+const MARK_SIZE: u32 = 5;
+
+/// Point of the (f, k) plane
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PathPoint {
+    pub f: f32,
+    pub k: f32,
+}
+
+/// Point written `f,k`
+impl FromStr for PathPoint {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let value = |part: &str| {
+            part.trim()
+                .parse::<f32>()
+                .map_err(|error| format!("invalid value `{part}` in point `{text}`: {error}"))
+        };
+        match text.split(',').collect::<Vec<_>>()[..] {
+            [f, k] => Ok(PathPoint { f: value(f)?, k: value(k)? }),
+            _ => Err(format!("invalid point `{text}`, expected `f,k`")),
+        }
+    }
+}
+
+impl fmt::Display for PathPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.f, self.k)
+    }
+}
+
+/// Measure drawn on the vertical axis of a diagram
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BifurcationMetric {
+    /// Difference between the largest and smallest B
+    #[default]
+    Amplitude,
+    /// Dominant wavelength, missing where there is no pattern
+    Wavelength,
+}
+
+/// Names of the metrics
+pub const BIFURCATION_METRIC_NAMES: [&str; 2] = ["amplitude", "wavelength"];
+
+impl BifurcationMetric {
+    /// Metric with the given name, see `BIFURCATION_METRIC_NAMES`
+    pub fn from_name(name: &str) -> Option<BifurcationMetric> {
+        match name {
+            "amplitude" => Some(BifurcationMetric::Amplitude),
+            "wavelength" => Some(BifurcationMetric::Wavelength),
+            _ => None,
+        }
+    }
+
+    /// Value of the metric at `point`
+    pub fn of(&self, point: &BifurcationPoint) -> Option<f64> {
+        match self {
+            BifurcationMetric::Amplitude => Some(point.amplitude),
+            BifurcationMetric::Wavelength => point.wavelength,
+        }
+    }
+}
+
+/// Settings of a bifurcation run
+/// The parameters other than `f` and `k` keep their value in `base`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bifurcation {
+    pub base: Parameters,
+    /// Points the path goes through, in order
+    pub path: Vec<PathPoint>,
+    /// Number of points where the pattern is recorded, evenly spaced along
+    /// the path, its first and last points included
+    pub samples: usize,
+    /// Generations computed at the first point of the path before the first
+    /// record, for the pattern to form
+    pub settle: i32,
+    /// Generations between two records, over which the parameters move from
+    /// one point to the next
+    pub steps: i32,
+    /// Number of rows and columns of the universe
+    pub dimensions: Position,
+    pub initial: InitialConfig,
+    /// Seed of the initial state
+    pub seed: u64,
+    pub bounds: Bounds,
+    pub boundary: Boundary,
+}
+
+impl Default for Bifurcation {
+    fn default() -> Self {
+        Bifurcation {
+            base: Parameters::default(),
+            path: Vec::new(),
+            samples: 50,
+            settle: 2000,
+            steps: 500,
+            dimensions: Position { row: 128, col: 128 },
+            initial: InitialConfig::default(),
+            seed: 0,
+            bounds: Bounds::default(),
+            boundary: Boundary::default(),
+        }
+    }
+}
+
+/// Pattern recorded at a point of the path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BifurcationPoint {
+    /// Position of the point in the records
+    pub index: usize,
+    /// Length of the path in the (f, k) plane from its first point
+    pub distance: f64,
+    pub parameters: Parameters,
+    pub generation: i32,
+    /// Difference between the largest and smallest B
+    pub amplitude: f64,
+    /// Dominant wavelength in cells, if there is a pattern
+    pub wavelength: Option<f64>,
+    pub kind: PatternKind,
+    /// Why the simulation stopped before this point, e.g. a concentration
+    /// out of bounds
+    pub error: Option<String>,
+}
+
+impl Bifurcation {
+    /// Read the settings of a bifurcation run from a `.ron` or `.toml` file
+    #[cfg(feature = "fs")]
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Bifurcation, ConfigError> {
+        read_file(path.as_ref())
+    }
+
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidBifurcation(message));
+        if self.path.is_empty() {
+            return error("the path needs at least one point".to_string());
+        }
+        if self.samples == 0 {
+            return error("at least one point has to be recorded".to_string());
+        }
+        if self.settle < 0 {
+            return error(format!("the settling time cannot be negative, not {}", self.settle));
+        }
+        if self.steps < 1 {
+            return error(format!("the parameters need at least 1 generation between records, not {}", self.steps));
+        }
+        Ok(())
+    }
+
+    /// Points where the pattern is recorded with their distance from the
+    /// first point of the path, all at that point for a path of no length
+    pub fn samples(&self) -> Vec<(f64, PathPoint)> {
+        let lengths: Vec<f64> = self
+            .path
+            .windows(2)
+            .map(|pair| (pair[1].f as f64 - pair[0].f as f64).hypot(pair[1].k as f64 - pair[0].k as f64))
+            .collect();
+        let total: f64 = lengths.iter().sum();
+        let Some(&first) = self.path.first() else {
+            return Vec::new();
+        };
+        if self.samples <= 1 || total == 0.0 {
+            return vec![(0.0, first); self.samples];
+        }
+
+        (0..self.samples)
+            .map(|index| {
+                let distance = total * index as f64 / (self.samples - 1) as f64;
+                let (mut start, mut segment) = (0.0, 0);
+                while segment + 1 < lengths.len() && start + lengths[segment] < distance {
+                    start += lengths[segment];
+                    segment += 1;
+                }
+                let t = if lengths[segment] > 0.0 { ((distance - start) / lengths[segment]).min(1.0) } else { 0.0 };
+                let (from, to) = (self.path[segment], self.path[segment + 1]);
+                let point = PathPoint {
+                    f: (from.f as f64 + (to.f as f64 - from.f as f64) * t) as f32,
+                    k: (from.k as f64 + (to.k as f64 - from.k as f64) * t) as f32,
+                };
+                (distance, point)
+            })
+            .collect()
+    }
+
+    /// Generation at which the point `index` is recorded
+    fn generation_of(&self, index: usize) -> i32 {
+        self.settle + self.steps * index as i32
+    }
+
+    /// Timeline moving `f` and `k` through the recorded points, reaching
+    /// each one at the generation it is recorded at
+    pub fn timeline(&self) -> Timeline {
+        let samples = self.samples();
+        let keyframes = |value: fn(&PathPoint) -> f32| {
+            samples
+                .iter()
+                .enumerate()
+                .map(|(index, (_, point))| Keyframe { generation: self.generation_of(index), value: value(point) })
+                .collect()
+        };
+        Timeline { f: keyframes(|point| point.f), k: keyframes(|point| point.k), ..Timeline::default() }
+    }
+
+    /// Evolve the simulation along the path and return the records, in
+    /// order
+    /// `on_point` is called with every record and the simulation at that
+    /// point. Fails if the settings are invalid, if the initial universe
+    /// cannot be built or if the path leaves the valid parameters
+    pub fn run<F>(&self, mut on_point: F) -> Result<Vec<BifurcationPoint>, SimulationError>
+    where
+        F: FnMut(&BifurcationPoint, &Simulation),
+    {
+        self.validate()?;
+        let (universe, dimensions) = self.initial.universe(&self.base, self.dimensions, Some(self.seed))?;
+        let first = self.path[0];
+        let base = Parameters { f: first.f, k: first.k, ..self.base };
+        let mut simulation: Simulation = Simulation::new(base, dimensions, universe)?
+            .with_bounds(self.bounds)
+            .with_boundary(self.boundary)
+            .with_timeline(self.timeline())?;
+
+        let mut points = Vec::with_capacity(self.samples);
+        for (index, (distance, point)) in self.samples().into_iter().enumerate() {
+            let generation = self.generation_of(index);
+            simulation.run(generation - simulation.generation());
+
+            let classification = simulation.classify();
+            let b = simulation.stats().b;
+            let record = BifurcationPoint {
+                index,
+                distance,
+                parameters: Parameters { f: point.f, k: point.k, ..self.base },
+                generation: simulation.generation(),
+                amplitude: b.max - b.min,
+                wavelength: classification.wavelength.map(|wavelength| wavelength.wavelength),
+                kind: classification.kind,
+                error: simulation.violation().map(|violation| violation.to_string()),
+            };
+            on_point(&record, &simulation);
+            points.push(record);
+        }
+        Ok(points)
+    }
+
+    /// Evolve the simulation along the path and write the records to
+    /// `directory`: `bifurcation.csv` with one line per point and
+    /// `diagram.png` drawing `metric` along the path, see `diagram`
+    #[cfg(feature = "fs")]
+    pub fn write(&self, directory: &Path, metric: BifurcationMetric) -> Result<Vec<BifurcationPoint>, SimulationError> {
+        fs::create_dir_all(directory)?;
+        let points = self.run(|_, _| {})?;
+        write_records(&points, directory.join("bifurcation.csv"))?;
+        diagram(&points, metric).save(directory.join("diagram.png"))?;
+        Ok(points)
+    }
+}
+
+/// Write one CSV line per point, after a header
+#[cfg(feature = "fs")]
+fn write_records(points: &[BifurcationPoint], path: impl AsRef<Path>) -> Result<(), SimulationError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "index,distance,d_a,d_b,f,k,r,generation,amplitude,wavelength,kind,error")?;
+    for point in points {
+        let Parameters { d_a, d_b, f, k, r, .. } = point.parameters;
+        let wavelength = point.wavelength.map(|wavelength| wavelength.to_string()).unwrap_or_default();
+        let error = point.error.as_deref().unwrap_or_default().replace('"', "'");
+        writeln!(
+            writer,
+            "{},{},{d_a},{d_b},{f},{k},{r},{},{},{wavelength},{},\"{error}\"",
+            point.index, point.distance, point.generation, point.amplitude, point.kind,
+        )?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// Color of the marks of the points of a kind of pattern
+#[cfg(feature = "fs")]
+fn kind_color(kind: PatternKind) -> Rgb<u8> {
+    match kind {
+        PatternKind::Uniform => Rgb([128, 128, 128]),
+        PatternKind::Spots => Rgb([214, 39, 40]),
+        PatternKind::Stripes => Rgb([31, 119, 180]),
+        PatternKind::Labyrinth => Rgb([44, 160, 44]),
+    }
+}
+
+/// Bifurcation diagram of `points`: `metric` from 0 at the bottom to its
+/// largest value at the top, along the path from left to right, each point
+/// marked with the color of its kind of pattern (gray for uniform, red for
+/// spots, blue for stripes and green for labyrinths) and joined to the next
+/// one. Points without a value of the metric are left out
+#[cfg(feature = "fs")]
+pub fn diagram(points: &[BifurcationPoint], metric: BifurcationMetric) -> RgbImage {
+    let mut image = RgbImage::from_pixel(DIAGRAM_WIDTH, DIAGRAM_HEIGHT, Rgb([255, 255, 255]));
+    let (left, bottom) = (DIAGRAM_MARGIN, DIAGRAM_HEIGHT - 1 - DIAGRAM_MARGIN);
+    let (width, height) = (DIAGRAM_WIDTH - 1 - 2 * DIAGRAM_MARGIN, DIAGRAM_HEIGHT - 1 - 2 * DIAGRAM_MARGIN);
+    let axis = Rgb([0, 0, 0]);
+    for x in left..=left + width {
+        image.put_pixel(x, bottom, axis);
+    }
+    for y in bottom - height..=bottom {
+        image.put_pixel(left, y, axis);
+    }
+
+    let length = points.iter().map(|point| point.distance).fold(0.0, f64::max);
+    let largest = points.iter().filter_map(|point| metric.of(point)).fold(0.0, f64::max);
+    let place = |point: &BifurcationPoint| {
+        let value = metric.of(point)?;
+        let along = if length > 0.0 {
+            point.distance / length
+        } else if points.len() > 1 {
+            point.index as f64 / (points.len() - 1) as f64
+        } else {
+            0.0
+        };
+        let up = if largest > 0.0 { value / largest } else { 0.0 };
+        Some(((left as f64 + along * width as f64).round() as i64, (bottom as f64 - up * height as f64).round() as i64))
+    };
+    let placed: Vec<(i64, i64, PatternKind)> =
+        points.iter().filter_map(|point| place(point).map(|(x, y)| (x, y, point.kind))).collect();
+
+    let line = Rgb([200, 200, 200]);
+    for pair in placed.windows(2) {
+        let ((x0, y0, _), (x1, y1, _)) = (pair[0], pair[1]);
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+        for step in 0..=steps {
+            let x = x0 + (x1 - x0) * step / steps;
+            let y = y0 + (y1 - y0) * step / steps;
+            image.put_pixel(x as u32, y as u32, line);
+        }
+    }
+    let half = (MARK_SIZE / 2) as i64;
+    for (x, y, kind) in placed {
+        for dy in -half..=half {
+            for dx in -half..=half {
+                let (x, y) = (x + dx, y + dy);
+                if x >= 0 && y >= 0 && x < DIAGRAM_WIDTH as i64 && y < DIAGRAM_HEIGHT as i64 {
+                    image.put_pixel(x as u32, y as u32, kind_color(kind));
+                }
+            }
+        }
+    }
+    image
+}
@@ -0,0 +1,173 @@
+/// Blow-ups
+/// With rates out of their stable range, e.g. a reproduction rate well above
+/// 1, an evolution overshoots the concentrations out of [0,1], the next ones
+/// amplify the overshoot, and within a few generations the concentrations
+/// are infinite or NaN and the color map is garbage. With a check, see
+/// `Simulation::with_blowup_check`, the universe is scanned every few
+/// generations, the scans staying cheap next to the evolutions, for
+/// concentrations that are not finite or lie further than a margin out of
+/// [0,1]. The cells found are kept in a `Blowup`, along with the parameters
+/// they were evolved with and the rate suspected of the instability: the
+/// window pauses on it and highlights them, and headless runs stop with its
+/// diagnostic. Healthy runs overshoot [0,1] too, e.g. down to -0.7 right
+/// after random cells are seeded, hence the margin. The check is made
+/// before the bounds are applied
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Cell, Float, Parameters, Position, SimulationError, Universe};
+
+/// Settings of the check of the concentrations
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlowupCheck {
+    /// Generations between two scans of the universe, 0 to disable the check
+    pub interval: i32,
+    /// Distance out of [0,1] a finite concentration reaches before it counts
+    /// as blown up
+    pub margin: f32,
+    /// Largest number of blown up cells kept, to be highlighted
+    pub cells: usize,
+}
+
+impl Default for BlowupCheck {
+    fn default() -> Self {
+        BlowupCheck { interval: 10, margin: 1.0, cells: 256 }
+    }
+}
+
+impl BlowupCheck {
+    /// Fails if the interval is negative, or the margin negative or not
+    /// finite
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidBlowup(message));
+        if self.interval < 0 {
+            return error(format!("the interval must not be negative, found {}", self.interval));
+        }
+        if !self.margin.is_finite() || self.margin < 0.0 {
+            return error(format!("the margin must be finite and not negative, found {}", self.margin));
+        }
+        Ok(())
+    }
+
+    /// Whether the universe is scanned once `generation` is reached
+    pub fn is_due(&self, generation: i32) -> bool {
+        self.interval > 0 && generation % self.interval == 0
+    }
+
+    /// Whether `value` is not finite or further than the margin out of [0,1]
+    pub fn is_blown_up(&self, value: f32) -> bool {
+        !(-self.margin..=1.0 + self.margin).contains(&value)
+    }
+
+    /// Blown up cells of `universe` at `generation`, with the parameters
+    /// each cell is evolved with, `None` if there is none
+    pub fn scan<T: Float>(
+        &self,
+        universe: &Universe<T>,
+        generation: i32,
+        parameters_at: impl Fn(Position) -> Parameters,
+    ) -> Option<Blowup> {
+        let mut blowup: Option<Blowup> = None;
+        for (row, cells) in universe.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let (a, b) = (cell.a.to_f32(), cell.b.to_f32());
+                if !(self.is_blown_up(a) || self.is_blown_up(b)) {
+                    continue;
+                }
+                let position = Position { row, col };
+                let blowup = blowup.get_or_insert_with(|| {
+                    let parameters = parameters_at(position);
+                    Blowup {
+                        generation,
+                        parameters,
+                        first: (position, cell.cast()),
+                        cells: Vec::new(),
+                        count: 0,
+                        non_finite: 0,
+                        suspect: Instability::suspect(parameters),
+                    }
+                });
+                if blowup.cells.len() < self.cells {
+                    blowup.cells.push(position);
+                }
+                blowup.count += 1;
+                blowup.non_finite += !(a.is_finite() && b.is_finite()) as usize;
+            }
+        }
+        blowup
+    }
+}
+
+/// Rate out of the range where the evolution is stable
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instability {
+    /// A diffusion rate above 1: the explicit diffusion gives away more than
+    /// a cell has
+    Diffusion { name: &'static str, rate: f32 },
+    /// A reproduction rate above 1: the reaction can turn more A into B than
+    /// a cell has in one evolution
+    Reproduction { rate: f32 },
+}
+
+impl Instability {
+    /// Rate of `parameters` suspected of blowing up the evolution, if any
+    pub fn suspect(parameters: Parameters) -> Option<Instability> {
+        let Parameters { d_a, d_b, r, .. } = parameters;
+        if let Some((name, rate)) = [("d_a", d_a), ("d_b", d_b)].into_iter().find(|(_, rate)| *rate > 1.0) {
+            return Some(Instability::Diffusion { name, rate });
+        }
+        (r > 1.0).then_some(Instability::Reproduction { rate: r })
+    }
+}
+
+impl fmt::Display for Instability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instability::Diffusion { name, rate } => {
+                write!(f, "the diffusion rate {name} = {rate} is above 1, where the diffusion overshoots")
+            }
+            Instability::Reproduction { rate } => {
+                write!(f, "the reproduction rate r = {rate} is above 1, where the reaction overshoots")
+            }
+        }
+    }
+}
+
+/// Concentrations found blown up by a `BlowupCheck`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blowup {
+    /// Generation reached when the universe was scanned
+    pub generation: i32,
+    /// Parameters the first blown up cell is evolved with, modulation
+    /// included
+    pub parameters: Parameters,
+    /// First blown up cell, row by row, and its concentrations
+    pub first: (Position, Cell<f64>),
+    /// Blown up cells, row by row, at most `BlowupCheck::cells` of them
+    pub cells: Vec<Position>,
+    /// Number of blown up cells
+    pub count: usize,
+    /// Number of them with a concentration that is infinite or NaN
+    pub non_finite: usize,
+    /// Rate suspected of the instability, if one is out of its stable range
+    pub suspect: Option<Instability>,
+}
+
+impl fmt::Display for Blowup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (position, cell) = self.first;
+        let Parameters { d_a, d_b, f: feed, k, r, .. } = self.parameters;
+        write!(
+            f,
+            "blow-up at generation {}: {} cells out of range, {} of them not finite, the first at ({}, {}) with a = {}, \
+             b = {}; evolved with d_a = {d_a}, d_b = {d_b}, f = {feed}, k = {k}, r = {r}",
+            self.generation, self.count, self.non_finite, position.row, position.col, cell.a, cell.b
+        )?;
+        match self.suspect {
+            Some(instability) => write!(f, "; suspected instability: {instability}"),
+            None => write!(f, "; no rate is out of its stable range, suspect the reaction or the injections"),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+/// C API
+/// With the `capi` feature the crate exports C functions to create, configure
+/// and step a simulation and to copy its fields into caller buffers. The
+/// header is generated by cbindgen in `OUT_DIR` during the build, and in
+/// `include/ca_turing_pattern.h` with `CA_TURING_PATTERN_INCLUDE_DIR=include`,
+/// and the library itself with
+/// `cargo rustc --release --lib --features capi --crate-type cdylib`
+use std::ptr;
+use std::slice;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::{Parameters, Position, Simulation, INITIAL_CELLS};
+
+/// Simulation handle, created with `ca_simulation_new`
+pub struct CaSimulation {
+    simulation: Simulation,
+}
+
+/// Parameters of the simulation, see `Parameters`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CaParameters {
+    pub d_a: f32,
+    pub d_b: f32,
+    pub f: f32,
+    pub k: f32,
+    pub r: f32,
+}
+
+/// Field copied by `ca_simulation_copy_field`, one of the `CA_FIELD_*`
+/// constants
+/// An integer rather than an enum, since C may pass any value
+pub type CaField = u32;
+/// Concentration of A
+pub const CA_FIELD_A: CaField = 0;
+/// Concentration of B
+pub const CA_FIELD_B: CaField = 1;
+/// Color value of the cells, in [0,1]
+pub const CA_FIELD_COLOR: CaField = 2;
+
+/// Result of the functions, 0 on success and negative on failure
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaStatus {
+    /// Success
+    Ok = 0,
+    /// A pointer given is NULL
+    NullPointer = -1,
+    /// The parameters are invalid, see `Parameters::validate`
+    InvalidParameters = -2,
+    /// The buffer cannot hold every cell
+    BufferTooSmall = -3,
+    /// The field is not one of the `CA_FIELD_*` constants
+    InvalidField = -4,
+}
+
+impl From<CaParameters> for Parameters {
+    fn from(parameters: CaParameters) -> Self {
+        let CaParameters { d_a, d_b, f, k, r } = parameters;
+        Parameters { d_a, d_b, f, k, r, initial: None }
+    }
+}
+
+impl From<Parameters> for CaParameters {
+    fn from(parameters: Parameters) -> Self {
+        let Parameters { d_a, d_b, f, k, r, .. } = parameters;
+        CaParameters { d_a, d_b, f, k, r }
+    }
+}
+
+/// Create a simulation of `rows` x `cols` cells with the default parameters
+/// and random initial cells drawn from `seed`
+/// Returns NULL if a dimension is 0. The simulation must be released with
+/// `ca_simulation_free`
+#[no_mangle]
+pub extern "C" fn ca_simulation_new(rows: usize, cols: usize, seed: u64) -> *mut CaSimulation {
+    if rows == 0 || cols == 0 {
+        return ptr::null_mut();
+    }
+
+    let dimensions = Position { row: rows, col: cols };
+    let Ok(simulation) = Simulation::random(
+        Parameters::default(),
+        dimensions,
+        INITIAL_CELLS,
+        &mut StdRng::seed_from_u64(seed),
+    ) else {
+        return ptr::null_mut();
+    };
+    Box::into_raw(Box::new(CaSimulation { simulation }))
+}
+
+/// Release a simulation created with `ca_simulation_new`
+///
+/// # Safety
+/// `simulation` must be NULL or a pointer returned by `ca_simulation_new`
+/// that was not released yet
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_free(simulation: *mut CaSimulation) {
+    if !simulation.is_null() {
+        drop(Box::from_raw(simulation));
+    }
+}
+
+/// Replace the parameters of the simulation
+/// Returns `CA_STATUS_NULL_POINTER` if `simulation` is NULL and
+/// `CA_STATUS_INVALID_PARAMETERS` if the parameters are invalid, in which
+/// case the current ones are kept
+///
+/// # Safety
+/// `simulation` must be NULL or a valid pointer returned by
+/// `ca_simulation_new`
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_set_parameters(
+    simulation: *mut CaSimulation,
+    parameters: CaParameters,
+) -> CaStatus {
+    let Some(simulation) = simulation.as_mut() else {
+        return CaStatus::NullPointer;
+    };
+    match simulation.simulation.set_parameters(parameters.into()) {
+        Ok(()) => CaStatus::Ok,
+        Err(_) => CaStatus::InvalidParameters,
+    }
+}
+
+/// Write the current parameters of the simulation to `parameters`
+/// Returns `CA_STATUS_NULL_POINTER` if a pointer is NULL
+///
+/// # Safety
+/// `simulation` must be NULL or a valid pointer returned by
+/// `ca_simulation_new`, and `parameters` NULL or writable
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_parameters(
+    simulation: *const CaSimulation,
+    parameters: *mut CaParameters,
+) -> CaStatus {
+    let (Some(simulation), Some(parameters)) = (simulation.as_ref(), parameters.as_mut()) else {
+        return CaStatus::NullPointer;
+    };
+    *parameters = simulation.simulation.parameters().into();
+    CaStatus::Ok
+}
+
+/// Compute `n` evolutions
+/// Returns `CA_STATUS_NULL_POINTER` if `simulation` is NULL
+///
+/// # Safety
+/// `simulation` must be NULL or a valid pointer returned by
+/// `ca_simulation_new`
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_step(simulation: *mut CaSimulation, n: u32) -> CaStatus {
+    let Some(simulation) = simulation.as_mut() else {
+        return CaStatus::NullPointer;
+    };
+    for _ in 0..n {
+        simulation.simulation.step();
+    }
+    CaStatus::Ok
+}
+
+/// Number of evolutions computed so far, -1 if `simulation` is NULL
+///
+/// # Safety
+/// `simulation` must be NULL or a valid pointer returned by
+/// `ca_simulation_new`
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_generation(simulation: *const CaSimulation) -> i32 {
+    simulation.as_ref().map_or(-1, |simulation| simulation.simulation.generation())
+}
+
+/// Number of rows of the universe, 0 if `simulation` is NULL
+///
+/// # Safety
+/// `simulation` must be NULL or a valid pointer returned by
+/// `ca_simulation_new`
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_rows(simulation: *const CaSimulation) -> usize {
+    simulation.as_ref().map_or(0, |simulation| simulation.simulation.dimensions().row)
+}
+
+/// Number of columns of the universe, 0 if `simulation` is NULL
+///
+/// # Safety
+/// `simulation` must be NULL or a valid pointer returned by
+/// `ca_simulation_new`
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_cols(simulation: *const CaSimulation) -> usize {
+    simulation.as_ref().map_or(0, |simulation| simulation.simulation.dimensions().col)
+}
+
+/// Copy `field` into `buffer` in row-major order
+/// `field` is one of the `CA_FIELD_*` constants and `len` the number of
+/// floats in `buffer`, at least rows * cols. Returns
+/// `CA_STATUS_NULL_POINTER` if a pointer is NULL,
+/// `CA_STATUS_INVALID_FIELD` if the field is unknown and
+/// `CA_STATUS_BUFFER_TOO_SMALL` if the buffer is too small
+///
+/// # Safety
+/// `simulation` must be NULL or a valid pointer returned by
+/// `ca_simulation_new`, and `buffer` NULL or pointing to `len` writable
+/// floats
+#[no_mangle]
+pub unsafe extern "C" fn ca_simulation_copy_field(
+    simulation: *const CaSimulation,
+    field: CaField,
+    buffer: *mut f32,
+    len: usize,
+) -> CaStatus {
+    let Some(CaSimulation { simulation }) = simulation.as_ref() else {
+        return CaStatus::NullPointer;
+    };
+    if buffer.is_null() {
+        return CaStatus::NullPointer;
+    }
+    let dimensions = simulation.dimensions();
+    let cells = dimensions.row * dimensions.col;
+    if len < cells {
+        return CaStatus::BufferTooSmall;
+    }
+
+    let buffer = slice::from_raw_parts_mut(buffer, cells);
+    match field {
+        CA_FIELD_A => {
+            for (value, cell) in buffer.iter_mut().zip(simulation.universe().iter().flatten()) {
+                *value = cell.a;
+            }
+        }
+        CA_FIELD_B => {
+            for (value, cell) in buffer.iter_mut().zip(simulation.universe().iter().flatten()) {
+                *value = cell.b;
+            }
+        }
+        CA_FIELD_COLOR => {
+            for (value, color) in buffer.iter_mut().zip(simulation.colored_map().iter().flatten()) {
+                *value = *color;
+            }
+        }
+        _ => return CaStatus::InvalidField,
+    }
+    CaStatus::Ok
+}
@@ -0,0 +1,135 @@
+/// Automatic checkpoints
+/// Long runs periodically save a snapshot in a directory, keeping only the
+/// most recent ones, so they can be resumed after a crash.
+/// Everything but the policy requires the `fs` feature
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::check_interval;
+#[cfg(feature = "fs")]
+use crate::snapshot::{Snapshot, SnapshotError};
+use crate::SimulationError;
+
+/// Prefix of the checkpoint file names, followed by the generation
+#[cfg(feature = "fs")]
+const FILE_PREFIX: &str = "checkpoint_";
+/// Extension of the checkpoint files, which are bincode snapshots
+#[cfg(feature = "fs")]
+const FILE_EXTENSION: &str = "bin";
+/// Extension of the checkpoints being written, which are never listed
+#[cfg(feature = "fs")]
+const TEMPORARY_EXTENSION: &str = "tmp";
+
+/// When and where checkpoints are written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckpointPolicy {
+    /// A checkpoint is written every `interval` generations
+    pub interval: i32,
+    /// Directory where the checkpoints are written, `{run}` standing for
+    /// the name of the run, see `naming`
+    pub directory: PathBuf,
+    /// Number of checkpoints kept, older ones are deleted; 0 keeps all of them
+    pub retention: usize,
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        CheckpointPolicy {
+            interval: 1000,
+            directory: PathBuf::from("checkpoints/{run}"),
+            retention: 3,
+        }
+    }
+}
+
+impl CheckpointPolicy {
+    /// Fails if the interval is below 1
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        check_interval("checkpoints", self.interval)
+    }
+}
+
+/// Writer of checkpoints following a `CheckpointPolicy`
+#[cfg(feature = "fs")]
+pub struct Checkpointer {
+    policy: CheckpointPolicy,
+    /// Checkpoints in the directory, from oldest to newest
+    written: Vec<PathBuf>,
+}
+
+#[cfg(feature = "fs")]
+impl Checkpointer {
+    /// Create the checkpoint directory if needed
+    /// Checkpoints already in the directory count towards the retention.
+    /// Fails if the policy is invalid or the directory cannot be read
+    pub fn new(policy: CheckpointPolicy) -> Result<Checkpointer, SimulationError> {
+        policy.validate()?;
+        fs::create_dir_all(&policy.directory)?;
+        let written = list_checkpoints(&policy.directory)?;
+        Ok(Checkpointer { policy, written })
+    }
+
+    /// Whether a checkpoint has to be written after computing `generation`
+    pub fn is_due(&self, generation: i32) -> bool {
+        generation % self.policy.interval == 0
+    }
+
+    /// Write `snapshot` as a new checkpoint and delete the ones exceeding the
+    /// retention. Returns the path of the new checkpoint
+    /// The checkpoint is written to a temporary file renamed once complete,
+    /// so an interrupted write never leaves a truncated latest checkpoint,
+    /// and the old ones are only deleted after the rename
+    pub fn save(&mut self, snapshot: &Snapshot) -> Result<PathBuf, SnapshotError> {
+        let name = format!("{FILE_PREFIX}{:08}.{FILE_EXTENSION}", snapshot.generation);
+        let path = self.policy.directory.join(&name);
+        let temporary = self.policy.directory.join(format!(".{name}.{TEMPORARY_EXTENSION}"));
+        snapshot.save(&temporary)?;
+        fs::rename(&temporary, &path)?;
+
+        self.written.retain(|written| *written != path);
+        self.written.push(path.clone());
+        if self.policy.retention > 0 && self.written.len() > self.policy.retention {
+            let excess = self.written.len() - self.policy.retention;
+            for old in self.written.drain(..excess) {
+                fs::remove_file(old)?;
+            }
+        }
+        Ok(path)
+    }
+}
+
+/// Checkpoint files in `directory`, from oldest to newest generation
+#[cfg(feature = "fs")]
+pub fn list_checkpoints(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut checkpoints: Vec<(i32, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != FILE_EXTENSION) {
+            continue;
+        }
+        let generation = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix(FILE_PREFIX))
+            .and_then(|generation| generation.parse().ok());
+        if let Some(generation) = generation {
+            checkpoints.push((generation, path));
+        }
+    }
+    checkpoints.sort();
+    Ok(checkpoints.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Most recent checkpoint in `directory`, if any
+#[cfg(feature = "fs")]
+pub fn latest_checkpoint(directory: &Path) -> io::Result<Option<PathBuf>> {
+    Ok(list_checkpoints(directory)?.pop())
+}
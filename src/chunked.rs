@@ -0,0 +1,271 @@
+/// Unbounded universe stored in chunks
+/// An experimental simulation without edges: the universe is a map from the
+/// positions of square chunks to their cells, and a position without a chunk
+/// holds the steady state of the model, A at 1 and no B. Before every
+/// evolution, the neighbours of a chunk whose edge strayed from that state by
+/// more than `epsilon` are allocated, so a pattern grows outward as far as it
+/// goes, and after it the chunks back to the steady state are released.
+/// Positions are signed, the first chunk starting at row 0 and column 0.
+/// The cells are evolved as in `reference`, so a pattern far from where the
+/// universe of a `Simulation` would end evolves the same way. A view, e.g.
+/// the part of the universe under a camera, is read with `window`. The
+/// window does not draw chunked simulations, so no chunk is streamed to a
+/// camera yet: they are run from the command line, `infinite`, and saved as
+/// the color map of their extent
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::evolve_cell;
+use crate::{color_universe, Cell, ColoredMap, Float, Parameters, Position, SimulationError, Universe};
+
+/// Settings of the chunks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChunkedConfig {
+    /// Number of cells on each side of a chunk
+    pub chunk_size: usize,
+    /// Difference of a concentration from the steady state over which a
+    /// chunk grows its neighbours, and under which it is released
+    pub epsilon: f32,
+}
+
+impl Default for ChunkedConfig {
+    fn default() -> Self {
+        ChunkedConfig { chunk_size: 64, epsilon: 1e-4 }
+    }
+}
+
+impl ChunkedConfig {
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidChunks(message));
+        if self.chunk_size < 2 {
+            return error(format!("chunks need at least 2 cells on each side, not {}", self.chunk_size));
+        }
+        if !(self.epsilon.is_finite() && self.epsilon >= 0.0) {
+            return error(format!("the epsilon cannot be negative, not {}", self.epsilon));
+        }
+        Ok(())
+    }
+}
+
+/// Signed position of a cell or a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Coordinates {
+    pub row: i64,
+    pub col: i64,
+}
+
+/// Concentrations of the cells without a chunk
+const STEADY: Cell = Cell { a: 1.0, b: 0.0 };
+
+/// Whether `cell` strayed from the steady state by more than `epsilon`, NaN
+/// counting as having strayed
+fn strayed(cell: &Cell, epsilon: f32) -> bool {
+    !((cell.a - STEADY.a).abs() <= epsilon && (cell.b - STEADY.b).abs() <= epsilon)
+}
+
+/// Simulation of an unbounded universe
+#[derive(Debug, Clone)]
+pub struct ChunkedSimulation {
+    parameters: Parameters,
+    config: ChunkedConfig,
+    /// Cells of every allocated chunk, row by row
+    chunks: HashMap<Coordinates, Vec<Cell>>,
+    generation: i32,
+}
+
+impl ChunkedSimulation {
+    /// Simulation of a universe in the steady state everywhere
+    /// Fails if the parameters or the settings are invalid
+    pub fn new(parameters: Parameters, config: ChunkedConfig) -> Result<ChunkedSimulation, SimulationError> {
+        parameters.validate()?;
+        config.validate()?;
+        Ok(ChunkedSimulation { parameters, config, chunks: HashMap::new(), generation: 0 })
+    }
+
+    /// Simulation of the steady state with `universe` placed at row 0 and
+    /// column 0, e.g. from an `initial::InitialCondition`
+    pub fn from_universe<T: Float>(
+        parameters: Parameters,
+        config: ChunkedConfig,
+        universe: &Universe<T>,
+    ) -> Result<ChunkedSimulation, SimulationError> {
+        let mut simulation = ChunkedSimulation::new(parameters, config)?;
+        for (row, cells) in universe.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                simulation.set_cell(Coordinates { row: row as i64, col: col as i64 }, cell.cast());
+            }
+        }
+        simulation.release();
+        Ok(simulation)
+    }
+
+    pub fn parameters(&self) -> Parameters {
+        self.parameters
+    }
+
+    /// Switch to new parameters, used from the next evolution
+    pub fn set_parameters(&mut self, parameters: Parameters) -> Result<(), SimulationError> {
+        parameters.validate()?;
+        self.parameters = parameters;
+        Ok(())
+    }
+
+    pub fn config(&self) -> ChunkedConfig {
+        self.config
+    }
+
+    /// Number of evolutions computed so far
+    pub fn generation(&self) -> i32 {
+        self.generation
+    }
+
+    /// Number of chunks allocated
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Positions of the allocated chunks, in chunks from the first one
+    pub fn chunk_positions(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Chunk holding the cell at `position`, and the index of the cell in it
+    fn locate(&self, position: Coordinates) -> (Coordinates, usize) {
+        let size = self.config.chunk_size as i64;
+        let chunk = Coordinates { row: position.row.div_euclid(size), col: position.col.div_euclid(size) };
+        let (row, col) = (position.row.rem_euclid(size), position.col.rem_euclid(size));
+        (chunk, (row * size + col) as usize)
+    }
+
+    /// Concentrations of the cell at `position`
+    pub fn cell(&self, position: Coordinates) -> Cell {
+        let (chunk, index) = self.locate(position);
+        self.chunks.get(&chunk).map_or(STEADY, |cells| cells[index])
+    }
+
+    /// Give new concentrations to the cell at `position`, allocating its
+    /// chunk if needed
+    pub fn set_cell(&mut self, position: Coordinates, cell: Cell) {
+        let (chunk, index) = self.locate(position);
+        let size = self.config.chunk_size;
+        self.chunks.entry(chunk).or_insert_with(|| vec![STEADY; size * size])[index] = cell;
+    }
+
+    /// Smallest rectangle of cells covering every allocated chunk, as its
+    /// first cell and its dimensions, `None` if there is none
+    pub fn extent(&self) -> Option<(Coordinates, Position)> {
+        let size = self.config.chunk_size as i64;
+        let rows = self.chunks.keys().map(|chunk| chunk.row);
+        let cols = self.chunks.keys().map(|chunk| chunk.col);
+        let (first_row, last_row) = (rows.clone().min()?, rows.max()?);
+        let (first_col, last_col) = (cols.clone().min()?, cols.max()?);
+        let dimensions = Position {
+            row: ((last_row - first_row + 1) * size) as usize,
+            col: ((last_col - first_col + 1) * size) as usize,
+        };
+        Some((Coordinates { row: first_row * size, col: first_col * size }, dimensions))
+    }
+
+    /// Cells of the rectangle of `dimensions` starting at `origin`, e.g. the
+    /// part of the universe seen by a camera
+    pub fn window(&self, origin: Coordinates, dimensions: Position) -> Universe {
+        (0..dimensions.row as i64)
+            .map(|row| {
+                (0..dimensions.col as i64)
+                    .map(|col| self.cell(Coordinates { row: origin.row + row, col: origin.col + col }))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Color map of `window`
+    pub fn colored_window(&self, origin: Coordinates, dimensions: Position) -> ColoredMap {
+        color_universe(&self.window(origin, dimensions))
+    }
+
+    /// Allocate the neighbours of the chunks whose edge strayed from the
+    /// steady state
+    fn grow(&mut self) {
+        let (size, epsilon) = (self.config.chunk_size, self.config.epsilon);
+        let on_edge = |index: usize| {
+            let (row, col) = (index / size, index % size);
+            row == 0 || col == 0 || row == size - 1 || col == size - 1
+        };
+        let growing: Vec<Coordinates> = self
+            .chunks
+            .iter()
+            .filter(|(_, cells)| cells.iter().enumerate().any(|(index, cell)| on_edge(index) && strayed(cell, epsilon)))
+            .map(|(chunk, _)| *chunk)
+            .collect();
+        for chunk in growing {
+            for d_row in -1..=1 {
+                for d_col in -1..=1 {
+                    let neighbour = Coordinates { row: chunk.row + d_row, col: chunk.col + d_col };
+                    self.chunks.entry(neighbour).or_insert_with(|| vec![STEADY; size * size]);
+                }
+            }
+        }
+    }
+
+    /// Release the chunks back to the steady state
+    fn release(&mut self) {
+        let epsilon = self.config.epsilon;
+        self.chunks.retain(|_, cells| cells.iter().any(|cell| strayed(cell, epsilon)));
+    }
+
+    /// Cells of `chunk` with a ring of their neighbours around them, row by
+    /// row, the steady state standing for the missing chunks
+    fn padded(&self, chunk: Coordinates) -> Vec<Cell> {
+        let size = self.config.chunk_size as i64;
+        let side = size + 2;
+        let origin = Coordinates { row: chunk.row * size - 1, col: chunk.col * size - 1 };
+        let cells = &self.chunks[&chunk];
+        (0..side * side)
+            .map(|index| {
+                let (row, col) = (index / side, index % side);
+                if (1..=size).contains(&row) && (1..=size).contains(&col) {
+                    cells[((row - 1) * size + col - 1) as usize]
+                } else {
+                    self.cell(Coordinates { row: origin.row + row, col: origin.col + col })
+                }
+            })
+            .collect()
+    }
+
+    /// Compute one evolution of every allocated chunk, growing and releasing
+    /// chunks around it
+    pub fn step(&mut self) {
+        self.grow();
+        let size = self.config.chunk_size;
+        let side = size + 2;
+
+        let evolved: HashMap<Coordinates, Vec<Cell>> = self
+            .chunks
+            .keys()
+            .map(|chunk| {
+                let padded = self.padded(*chunk);
+                let cells = (0..size * size)
+                    .map(|index| {
+                        let (row, col) = (index / size + 1, index % size + 1);
+                        evolve_cell(&self.parameters, padded[row * side + col], |d_row, d_col| {
+                            Some(padded[row.wrapping_add_signed(d_row) * side + col.wrapping_add_signed(d_col)])
+                        })
+                    })
+                    .collect();
+                (*chunk, cells)
+            })
+            .collect();
+        self.chunks = evolved;
+        self.release();
+        self.generation += 1;
+    }
+
+    /// Compute `n` evolutions
+    pub fn run(&mut self, n: i32) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+}
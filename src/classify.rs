@@ -0,0 +1,219 @@
+/// Classification of patterns
+/// The B field is thresholded halfway between its extremes and measured with
+/// the Minkowski functionals of the regions rich in B (area, perimeter and
+/// Euler characteristic). Together with the dominant wavelength and isotropy
+/// of `analysis`, they label the pattern as uniform, spots, stripes or a
+/// labyrinth, e.g. to map the parameter space without looking at every run
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{dominant_wavelength, Wavelength};
+use crate::{Float, Simulation, Universe};
+
+/// Patterns whose B varies less than this are uniform
+const UNIFORM_RANGE: f64 = 0.01;
+/// Islands of the minority phase smaller than this many squared wavelengths
+/// on average are spots
+const MAX_SPOT_AREA: f64 = 1.0;
+/// Patterns less isotropic than this are stripes, more isotropic ones
+/// labyrinths
+const MIN_LABYRINTH_ISOTROPY: f64 = 0.5;
+
+/// Kind of pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternKind {
+    /// No pattern, B is (almost) the same everywhere
+    Uniform,
+    /// Isolated islands, rich or poor in B
+    Spots,
+    /// Parallel bands with a preferred direction
+    Stripes,
+    /// Winding bands with no preferred direction
+    Labyrinth,
+}
+
+/// Names of the kinds of pattern, as written in the reports
+pub const PATTERN_KIND_NAMES: [&str; 4] = ["uniform", "spots", "stripes", "labyrinth"];
+
+impl PatternKind {
+    /// Kind with the given name, see `PATTERN_KIND_NAMES`
+    pub fn from_name(name: &str) -> Option<PatternKind> {
+        match name {
+            "uniform" => Some(PatternKind::Uniform),
+            "spots" => Some(PatternKind::Spots),
+            "stripes" => Some(PatternKind::Stripes),
+            "labyrinth" => Some(PatternKind::Labyrinth),
+            _ => None,
+        }
+    }
+
+    /// Name of the kind, one of `PATTERN_KIND_NAMES`
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatternKind::Uniform => "uniform",
+            PatternKind::Spots => "spots",
+            PatternKind::Stripes => "stripes",
+            PatternKind::Labyrinth => "labyrinth",
+        }
+    }
+}
+
+impl fmt::Display for PatternKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Morphological measures of the cells where B is above a threshold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Morphology {
+    pub threshold: f64,
+    /// Share of the cells above the threshold
+    pub area_fraction: f64,
+    /// Number of sides between a cell above and a cell below the threshold,
+    /// per cell
+    pub perimeter: f64,
+    /// Regions of cells above the threshold, side by side
+    pub components: usize,
+    /// Regions of cells below the threshold enclosed by the others, i.e. not
+    /// touching the border of the universe
+    pub holes: usize,
+    /// Regions of cells below the threshold, holes or not
+    pub background_components: usize,
+}
+
+impl Morphology {
+    /// Measures of the cells of `universe` whose B is above `threshold`
+    pub fn of<T: Float>(universe: &Universe<T>, threshold: f64) -> Morphology {
+        let mask: Vec<Vec<bool>> = universe
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.b.to_f64() > threshold).collect())
+            .collect();
+        let cells = mask.iter().map(Vec::len).sum::<usize>().max(1) as f64;
+
+        let area = mask.iter().flatten().filter(|inside| **inside).count();
+        let mut sides = 0;
+        for (row, values) in mask.iter().enumerate() {
+            for (col, inside) in values.iter().enumerate() {
+                let right = values.get(col + 1).is_some_and(|other| other != inside);
+                let below = mask
+                    .get(row + 1)
+                    .and_then(|next| next.get(col))
+                    .is_some_and(|other| other != inside);
+                sides += right as usize + below as usize;
+            }
+        }
+
+        let background = regions(&mask, false);
+        Morphology {
+            threshold,
+            area_fraction: area as f64 / cells,
+            perimeter: sides as f64 / cells,
+            components: regions(&mask, true).len(),
+            holes: background.iter().filter(|touches_border| !**touches_border).count(),
+            background_components: background.len(),
+        }
+    }
+
+    /// Euler characteristic of the cells above the threshold: components
+    /// minus holes
+    pub fn euler_characteristic(&self) -> i64 {
+        self.components as i64 - self.holes as i64
+    }
+}
+
+/// Regions of the cells of `mask` equal to `value`, connected by their sides,
+/// as whether each one touches the border of the mask
+fn regions(mask: &[Vec<bool>], value: bool) -> Vec<bool> {
+    let rows = mask.len();
+    let mut visited: Vec<Vec<bool>> = mask.iter().map(|row| vec![false; row.len()]).collect();
+    let mut regions = Vec::new();
+    let mut stack = Vec::new();
+
+    for row in 0..rows {
+        for col in 0..mask[row].len() {
+            if visited[row][col] || mask[row][col] != value {
+                continue;
+            }
+            visited[row][col] = true;
+            stack.push((row, col));
+            let mut touches_border = false;
+            while let Some((r, c)) = stack.pop() {
+                let cols = mask[r].len();
+                touches_border |= r == 0 || c == 0 || r + 1 == rows || c + 1 == cols;
+                let neighbours = [
+                    (r.wrapping_sub(1), c),
+                    (r + 1, c),
+                    (r, c.wrapping_sub(1)),
+                    (r, c + 1),
+                ];
+                for (nr, nc) in neighbours {
+                    let matches = mask.get(nr).and_then(|next| next.get(nc)) == Some(&value);
+                    if matches && !visited[nr][nc] {
+                        visited[nr][nc] = true;
+                        stack.push((nr, nc));
+                    }
+                }
+            }
+            regions.push(touches_border);
+        }
+    }
+    regions
+}
+
+/// Kind of a pattern with the measures it was decided from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Classification {
+    pub kind: PatternKind,
+    /// None for a uniform pattern
+    pub wavelength: Option<Wavelength>,
+    pub morphology: Morphology,
+}
+
+/// Kind of pattern formed by the B concentrations of `universe`
+/// A pattern is uniform if B barely varies. Otherwise the phase covering
+/// less of the universe, above or below the threshold, forms spots if its
+/// islands are small compared to the squared dominant wavelength; larger
+/// islands are stripes if the pattern has a preferred direction, a labyrinth
+/// if not
+pub fn classify<T: Float>(universe: &Universe<T>) -> Classification {
+    let (min, max) = universe
+        .iter()
+        .flatten()
+        .map(|cell| cell.b.to_f64())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), b| (min.min(b), max.max(b)));
+    let morphology = Morphology::of(universe, (min + max) / 2.0);
+    let wavelength = (max - min >= UNIFORM_RANGE)
+        .then(|| dominant_wavelength(universe))
+        .flatten();
+
+    let kind = match wavelength {
+        Some(wavelength) => {
+            let cells = universe.iter().map(Vec::len).sum::<usize>() as f64;
+            let (area, islands) = if morphology.area_fraction <= 0.5 {
+                (morphology.area_fraction, morphology.components)
+            } else {
+                (1.0 - morphology.area_fraction, morphology.background_components)
+            };
+            let island_area = area * cells / islands.max(1) as f64;
+            if island_area < MAX_SPOT_AREA * wavelength.wavelength.powi(2) {
+                PatternKind::Spots
+            } else if wavelength.isotropy < MIN_LABYRINTH_ISOTROPY {
+                PatternKind::Stripes
+            } else {
+                PatternKind::Labyrinth
+            }
+        }
+        None => PatternKind::Uniform,
+    };
+    Classification { kind, wavelength, morphology }
+}
+
+impl<T: Float> Simulation<T> {
+    /// Kind of pattern of the current universe, see `classify`
+    pub fn classify(&self) -> Classification {
+        classify(self.universe())
+    }
+}
@@ -0,0 +1,263 @@
+/// Color maps
+/// Conversion of the color value of a cell, in [0,1], to an RGB color
+/// Besides the built-in maps, a palette can be extracted from a reference
+/// image by clustering its pixels, so that patterns match an existing art
+/// direction
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use crate::SimulationError;
+
+/// Color map used to draw the color value of the cells
+/// Viridis, magma, cividis, inferno and plasma are perceptually uniform and
+/// remain readable with the common color vision deficiencies; cividis is
+/// designed for them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Colormap {
+    /// Black for 0 up to white for 1
+    #[default]
+    Gray,
+    Viridis,
+    Magma,
+    Cividis,
+    Inferno,
+    Plasma,
+    /// Black below the threshold and white from it, e.g. to generate masks
+    Binary { threshold: f32 },
+    /// Gradient through the colors of a palette
+    Palette(Palette),
+}
+
+/// Names of the color maps, as written in the configuration files
+/// `binary` thresholds at `BINARY_THRESHOLD`
+pub const COLORMAP_NAMES: [&str; 7] = ["gray", "viridis", "magma", "cividis", "inferno", "plasma", "binary"];
+
+/// Default threshold of the binary color map
+pub const BINARY_THRESHOLD: f32 = 0.5;
+
+const GRAY: [[u8; 3]; 2] = [[0, 0, 0], [255, 255, 255]];
+
+const VIRIDIS: [[u8; 3]; 5] = [
+    [68, 1, 84],
+    [59, 82, 139],
+    [33, 145, 140],
+    [94, 201, 98],
+    [253, 231, 37],
+];
+
+const MAGMA: [[u8; 3]; 6] = [
+    [0, 0, 4],
+    [59, 15, 112],
+    [140, 41, 129],
+    [222, 73, 104],
+    [254, 159, 109],
+    [252, 253, 191],
+];
+
+const CIVIDIS: [[u8; 3]; 5] = [
+    [0, 34, 78],
+    [65, 77, 107],
+    [124, 123, 120],
+    [188, 175, 111],
+    [254, 232, 56],
+];
+
+const INFERNO: [[u8; 3]; 6] = [
+    [0, 0, 4],
+    [66, 10, 104],
+    [147, 38, 103],
+    [221, 81, 58],
+    [252, 165, 10],
+    [252, 255, 164],
+];
+
+const PLASMA: [[u8; 3]; 6] = [
+    [13, 8, 135],
+    [106, 0, 168],
+    [177, 42, 144],
+    [225, 100, 98],
+    [252, 166, 54],
+    [240, 249, 33],
+];
+
+impl Colormap {
+    /// Color map with the given name, see `COLORMAP_NAMES`
+    pub fn from_name(name: &str) -> Option<Colormap> {
+        match name {
+            "gray" => Some(Colormap::Gray),
+            "viridis" => Some(Colormap::Viridis),
+            "magma" => Some(Colormap::Magma),
+            "cividis" => Some(Colormap::Cividis),
+            "inferno" => Some(Colormap::Inferno),
+            "plasma" => Some(Colormap::Plasma),
+            "binary" => Some(Colormap::Binary { threshold: BINARY_THRESHOLD }),
+            _ => None,
+        }
+    }
+
+    /// Same color map with the threshold `threshold` if it is binary
+    pub fn with_threshold(self, threshold: f32) -> Colormap {
+        match self {
+            Colormap::Binary { .. } => Colormap::Binary { threshold },
+            colormap => colormap,
+        }
+    }
+
+    /// Fails if the threshold of the binary color map is not a color value
+    /// in [0,1]
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        match self {
+            Colormap::Binary { threshold } if !(0.0..=1.0).contains(threshold) => Err(
+                SimulationError::InvalidColormap(format!("the threshold must be in [0, 1], found {threshold}")),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Colors evenly spaced over [0,1] between which the map interpolates
+    fn stops(&self) -> &[[u8; 3]] {
+        match self {
+            Colormap::Gray | Colormap::Binary { .. } => &GRAY,
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Magma => &MAGMA,
+            Colormap::Cividis => &CIVIDIS,
+            Colormap::Inferno => &INFERNO,
+            Colormap::Plasma => &PLASMA,
+            Colormap::Palette(palette) => palette.colors(),
+        }
+    }
+
+    /// RGB color for a color value
+    /// Values outside [0,1] take the color of the nearest end
+    pub fn color(&self, value: f32) -> [u8; 3] {
+        match self {
+            Colormap::Binary { threshold } if value >= *threshold => GRAY[1],
+            Colormap::Binary { .. } => GRAY[0],
+            _ => interpolate(self.stops(), value),
+        }
+    }
+}
+
+/// Linear interpolation between colors evenly spaced over [0,1]
+fn interpolate(stops: &[[u8; 3]], value: f32) -> [u8; 3] {
+    let value = if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) };
+    let position = value * (stops.len() - 1) as f32;
+    let lower = (position.floor() as usize).min(stops.len() - 2);
+    let t = position - lower as f32;
+
+    let mut color = [0; 3];
+    for (channel, value) in color.iter_mut().enumerate() {
+        let from = stops[lower][channel] as f32;
+        let to = stops[lower + 1][channel] as f32;
+        *value = (from + t * (to - from)).round() as u8;
+    }
+    color
+}
+
+/// Largest number of colors of a palette
+pub const MAX_PALETTE_COLORS: usize = 16;
+
+/// Largest number of pixels of an image clustered to find its palette
+const PALETTE_SAMPLES: usize = 16384;
+
+/// Number of iterations of the clustering of the pixels of an image
+const PALETTE_ITERATIONS: usize = 16;
+
+/// Colors of a gradient, evenly spaced over [0,1]
+/// Written in the configuration files as a list of 2 to
+/// `MAX_PALETTE_COLORS` RGB colors, e.g. `palette([(0, 0, 0), (255, 128, 0)])`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<[u8; 3]>", into = "Vec<[u8; 3]>")]
+pub struct Palette {
+    colors: [[u8; 3]; MAX_PALETTE_COLORS],
+    len: usize,
+}
+
+/// Relative luminance of an RGB color
+fn luminance(color: [f32; 3]) -> f32 {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+impl Palette {
+    /// Palette of `colors`, None unless there are 2 to `MAX_PALETTE_COLORS`
+    pub fn new(colors: &[[u8; 3]]) -> Option<Palette> {
+        if !(2..=MAX_PALETTE_COLORS).contains(&colors.len()) {
+            return None;
+        }
+        let mut palette = Palette { colors: [[0; 3]; MAX_PALETTE_COLORS], len: colors.len() };
+        palette.colors[..colors.len()].copy_from_slice(colors);
+        Some(palette)
+    }
+
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors[..self.len]
+    }
+
+    /// Palette of the `count` dominant colors of `image`, from the darkest to
+    /// the lightest
+    /// The pixels are grouped by k-means clustering, starting from colors
+    /// evenly spaced in luminance so that the result does not depend on
+    /// chance. `count` is limited to 2 to `MAX_PALETTE_COLORS`; None for an
+    /// empty image
+    pub fn from_image(image: &RgbImage, count: usize) -> Option<Palette> {
+        let count = count.clamp(2, MAX_PALETTE_COLORS);
+        let pixels = image.pixels().len();
+        if pixels == 0 {
+            return None;
+        }
+        let mut samples: Vec<[f32; 3]> = image
+            .pixels()
+            .step_by(pixels.div_ceil(PALETTE_SAMPLES))
+            .map(|pixel| pixel.0.map(f32::from))
+            .collect();
+        samples.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+        let mut centers: Vec<[f32; 3]> = (0..count)
+            .map(|index| samples[(2 * index + 1) * samples.len() / (2 * count)])
+            .collect();
+
+        for _ in 0..PALETTE_ITERATIONS {
+            let mut sums = vec![([0.0; 3], 0usize); count];
+            for sample in &samples {
+                let nearest = (0..count)
+                    .min_by(|a, b| distance(*sample, centers[*a]).total_cmp(&distance(*sample, centers[*b])))
+                    .unwrap_or_default();
+                let (sum, size) = &mut sums[nearest];
+                for (sum, value) in sum.iter_mut().zip(sample) {
+                    *sum += value;
+                }
+                *size += 1;
+            }
+            // A color without pixels keeps its previous value
+            for (center, (sum, size)) in centers.iter_mut().zip(sums) {
+                if size > 0 {
+                    *center = sum.map(|sum| sum / size as f32);
+                }
+            }
+        }
+
+        centers.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+        let colors: Vec<[u8; 3]> = centers.iter().map(|center| center.map(|value| value.round() as u8)).collect();
+        Palette::new(&colors)
+    }
+}
+
+impl TryFrom<Vec<[u8; 3]>> for Palette {
+    type Error = String;
+
+    fn try_from(colors: Vec<[u8; 3]>) -> Result<Self, Self::Error> {
+        Palette::new(&colors).ok_or_else(|| {
+            format!("a palette needs 2 to {MAX_PALETTE_COLORS} colors, found {}", colors.len())
+        })
+    }
+}
+
+impl From<Palette> for Vec<[u8; 3]> {
+    fn from(palette: Palette) -> Self {
+        palette.colors().to_vec()
+    }
+}
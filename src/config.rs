@@ -0,0 +1,450 @@
+/// Simulation configuration files
+/// A full experiment setup (parameters, universe size, seed, initial state and
+/// outputs) can be stored in a RON or TOML file, chosen by its extension.
+/// Reading and writing files requires the `fs` feature
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "fs")]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::activation::ActivationTracking;
+use crate::activity::ActivityTracking;
+use crate::animation::AnimationConfig;
+use crate::checkpoint::CheckpointPolicy;
+use crate::blowup::BlowupCheck;
+use crate::conservation::ConservationCheck;
+use crate::colormap::Colormap;
+use crate::export::{FrameSequenceConfig, NormalMapConfig};
+use crate::mesh::MeshConfig;
+use crate::noise::NoiseConfig;
+use crate::initial::{ImageSeed, InitialCondition};
+use crate::layers::Coupling;
+use crate::logger::StatsLogConfig;
+use crate::render::RenderConfig;
+use crate::modulation::ModulationConfig;
+use crate::reaction::ReactionConfig;
+use crate::rewind::RewindConfig;
+use crate::streams::StreamSeeds;
+use crate::symmetry::Symmetry;
+use crate::schedule::Schedule;
+use crate::timeline::Timeline;
+use crate::triggers::ExportTrigger;
+#[cfg(feature = "server")]
+use crate::server::ServerConfig;
+#[cfg(feature = "control")]
+use crate::control::ControlConfig;
+#[cfg(feature = "fs")]
+use crate::snapshot::Snapshot;
+use crate::naming::{expand_path, run_name, RunInfo, DEFAULT_NAME_TEMPLATE};
+use crate::{Boundary, Bounds, Parameters, Position, SimulationError, Stencil, Universe, INITIAL_CELLS};
+
+/// Configuration of a simulation run
+/// Every field is optional in the file, missing ones take the value of
+/// `Config::default()`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Template of the name of the run, titling the window and written
+    /// instead of `{run}` in the paths of the outputs, see `naming`
+    pub name: String,
+    pub parameters: Parameters,
+    /// Number of rows and columns of the universe
+    pub dimensions: Position,
+    /// Seed for the initial state; random if not given
+    pub seed: Option<u64>,
+    /// Number of evolutions to compute
+    pub steps: i32,
+    /// Handling of concentrations leaving [0,1]
+    pub bounds: Bounds,
+    /// Edges of the universe, closed or periodic
+    pub boundary: Boundary,
+    /// Computation of the diffusion, cellwise or conservative
+    pub stencil: Stencil,
+    /// Keyframes of the parameters changing during the run
+    pub timeline: Timeline,
+    /// Pulses of the parameters and injections of B repeated during the
+    /// run, see `schedule`
+    pub schedule: Schedule,
+    /// Field scaling `f` and `k` across the universe, disabled if not given
+    pub modulation: Option<ModulationConfig>,
+    /// Expressions of the reaction terms replacing those of the Gray–Scott
+    /// model, with the `scripting` feature, see `reaction`
+    pub reaction: Option<ReactionConfig>,
+    /// Skip the tiles that stopped changing, disabled if not given, see
+    /// `activity`
+    pub activity: Option<ActivityTracking>,
+    /// Check that the total A+B only changes by what the reaction terms add
+    /// and remove, disabled if not given, see `conservation`
+    pub conservation: Option<ConservationCheck>,
+    /// Scan the universe every few evolutions for concentrations that blew
+    /// up, pausing the window or stopping the run, see `blowup`
+    pub blowup: BlowupCheck,
+    /// Symmetry the universe is projected onto after every evolution,
+    /// disabled if not given, see `symmetry`
+    pub symmetry: Option<Symmetry>,
+    /// Remember the generation at which each cell was activated, disabled
+    /// if not given, see `activation`
+    pub activation: Option<ActivationTracking>,
+    /// Noise added to B after every evolution, disabled if not given, see
+    /// `noise`
+    pub noise: Option<NoiseConfig>,
+    /// Seeds of the random streams, derived from `seed` if not given, see
+    /// `streams`
+    pub streams: StreamSeeds,
+    pub initial: InitialConfig,
+    pub output: OutputConfig,
+    /// Periodic checkpoints of headless runs, disabled if not given
+    pub checkpoint: Option<CheckpointPolicy>,
+    /// Parameters of other simulations drawn next to the first one in the
+    /// window, from the same initial universe
+    pub compare: Vec<Parameters>,
+    /// Couplings between the simulation, layer 0, and the compared ones,
+    /// layers 1, 2, …, see `layers::Coupling`
+    pub couplings: Vec<Coupling>,
+    /// Resolution of the color maps drawn in the window
+    pub render: RenderConfig,
+    /// Keyframes kept to rewind the run in the window
+    pub rewind: RewindConfig,
+    /// Compute the run bit for bit the same on every platform, drawing the
+    /// initial universe from a portable generator, see `deterministic`
+    pub deterministic: bool,
+    /// Serve the run over WebSocket instead of opening a window
+    #[cfg(feature = "server")]
+    pub server: Option<ServerConfig>,
+    /// MIDI and OSC controls of the window, see `control`
+    #[cfg(feature = "control")]
+    pub control: Option<ControlConfig>,
+}
+
+/// Initial state of the universe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InitialConfig {
+    /// Number of random cells starting with A and B present
+    pub cells: usize,
+    /// Generated initial state used instead of the random cells, see
+    /// `initial::InitialCondition`; it takes precedence over the one of the
+    /// parameters
+    pub condition: Option<InitialCondition>,
+    /// Snapshot whose universe is used instead of the random cells
+    pub snapshot: Option<PathBuf>,
+    /// Image whose pixels give the initial B, used instead of the random
+    /// cells; it also sets the dimensions of the universe
+    pub image: Option<ImageSeed>,
+    /// Symmetry with which the random cells are placed around the center,
+    /// each of them coming with its images, or onto which a generated
+    /// condition is projected, see `symmetry`
+    pub symmetry: Option<Symmetry>,
+}
+
+/// Files written by a run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Image file where the final color map is saved
+    pub image: Option<PathBuf>,
+    /// Snapshot file where the final universe is saved
+    pub snapshot: Option<PathBuf>,
+    /// Concentrations of A and B, see `export::save_fields`
+    pub fields: Option<PathBuf>,
+    /// Numbered frames written during headless runs
+    pub frames: Option<FrameSequenceConfig>,
+    /// Animated GIF, APNG or WebP file written at the end of headless runs,
+    /// see `animation`
+    pub animation: Option<AnimationConfig>,
+    /// 16-bit image file where the height map of B is saved, see
+    /// `export::height_map`
+    pub height_map: Option<PathBuf>,
+    /// Normal map of B, see `export::normal_map`
+    pub normal_map: Option<NormalMapConfig>,
+    /// Image file where the activation times are saved at the end of
+    /// headless runs, see `activation::ActivationMap::colored_map`
+    pub activation_map: Option<PathBuf>,
+    /// Mesh of the surface displaced by a species, see `mesh::Mesh`
+    pub mesh: Option<MeshConfig>,
+    /// Statistics appended to a CSV or JSON lines file during headless
+    /// runs, see `logger`
+    pub stats: Option<StatsLogConfig>,
+    /// Exports written during headless runs when events happen, see
+    /// `triggers`
+    pub triggers: Vec<ExportTrigger>,
+    /// Session file written by the `session` command of the console, see
+    /// `session`
+    pub session: Option<PathBuf>,
+    /// Color map of the images and frames
+    pub colormap: Colormap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            name: DEFAULT_NAME_TEMPLATE.to_string(),
+            parameters: Parameters::default(),
+            dimensions: Position { row: 600, col: 600 },
+            seed: None,
+            steps: 700,
+            bounds: Bounds::default(),
+            boundary: Boundary::default(),
+            stencil: Stencil::default(),
+            timeline: Timeline::default(),
+            schedule: Schedule::default(),
+            modulation: None,
+            reaction: None,
+            activity: None,
+            conservation: None,
+            blowup: BlowupCheck::default(),
+            symmetry: None,
+            activation: None,
+            noise: None,
+            streams: StreamSeeds::default(),
+            initial: InitialConfig::default(),
+            output: OutputConfig::default(),
+            checkpoint: None,
+            compare: Vec::new(),
+            couplings: Vec::new(),
+            render: RenderConfig::default(),
+            rewind: RewindConfig::default(),
+            deterministic: false,
+            #[cfg(feature = "server")]
+            server: None,
+            #[cfg(feature = "control")]
+            control: None,
+        }
+    }
+}
+
+impl Config {
+    /// Name of the run, following the template of `name`
+    /// Fails if the template is invalid, see `naming::run_name`
+    pub fn run_name(&self) -> Result<String, SimulationError> {
+        let info = RunInfo {
+            parameters: self.parameters,
+            dimensions: self.dimensions,
+            seed: self.seed,
+            steps: self.steps,
+        };
+        run_name(&self.name, &info)
+    }
+
+    /// Write `name` instead of `{run}` in the paths of the outputs and of
+    /// the checkpoints
+    pub fn name_outputs(&mut self, name: &str) {
+        let output = &mut self.output;
+        let paths = [
+            &mut output.image,
+            &mut output.snapshot,
+            &mut output.fields,
+            &mut output.height_map,
+            &mut output.activation_map,
+            &mut output.session,
+        ];
+        for path in paths.into_iter().flatten() {
+            *path = expand_path(path, name);
+        }
+        let mut paths: Vec<&mut PathBuf> = Vec::new();
+        paths.extend(output.frames.as_mut().map(|frames| &mut frames.directory));
+        paths.extend(output.animation.as_mut().map(|animation| &mut animation.path));
+        paths.extend(output.normal_map.as_mut().map(|normal_map| &mut normal_map.path));
+        paths.extend(output.mesh.as_mut().map(|mesh| &mut mesh.path));
+        paths.extend(output.stats.as_mut().map(|stats| &mut stats.path));
+        paths.extend(output.triggers.iter_mut().map(|trigger| &mut trigger.directory));
+        paths.extend(self.checkpoint.as_mut().map(|checkpoint| &mut checkpoint.directory));
+        for path in paths {
+            *path = expand_path(path, name);
+        }
+    }
+
+    /// Fails if something is done every `interval` generations with an
+    /// interval below 1
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if let Some(frames) = &self.output.frames {
+            frames.validate()?;
+        }
+        if let Some(animation) = &self.output.animation {
+            animation.validate()?;
+        }
+        if let Some(stats) = &self.output.stats {
+            stats.validate()?;
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.validate()?;
+        }
+        self.rewind.validate()?;
+        #[cfg(feature = "server")]
+        if let Some(server) = &self.server {
+            server.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for InitialConfig {
+    fn default() -> Self {
+        InitialConfig { cells: INITIAL_CELLS, condition: None, snapshot: None, image: None, symmetry: None }
+    }
+}
+
+impl InitialConfig {
+    /// Initial universe described by this configuration
+    /// The universe comes from the snapshot if given, otherwise from the image,
+    /// otherwise it has `dimensions` and is generated by the condition, else
+    /// by that of `parameters`, by default `cells` random cells, from `seed`
+    /// (or from a random seed), with their images if placed with a symmetry.
+    /// Returns the universe and its dimensions
+    pub fn universe(
+        &self,
+        parameters: &Parameters,
+        dimensions: Position,
+        seed: Option<u64>,
+    ) -> Result<(Universe, Position), SimulationError> {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(rand::thread_rng()).expect("the thread RNG never fails"),
+        };
+        self.universe_with_rng(parameters, dimensions, &mut rng)
+    }
+
+    /// Initial universe described by this configuration, as `universe` but
+    /// drawn from `rng`, e.g. a `deterministic::PortableRng`
+    pub fn universe_with_rng<R: Rng + ?Sized>(
+        &self,
+        parameters: &Parameters,
+        dimensions: Position,
+        rng: &mut R,
+    ) -> Result<(Universe, Position), SimulationError> {
+        #[cfg(feature = "fs")]
+        if let Some(path) = &self.snapshot {
+            let snapshot = Snapshot::load(path)
+                .map_err(|error| SimulationError::Load(path.clone(), Box::new(error.into())))?;
+            return Ok((snapshot.universe, snapshot.dimensions));
+        }
+        #[cfg(feature = "fs")]
+        if let Some(image) = &self.image {
+            return image
+                .load()
+                .map_err(|error| SimulationError::Load(image.path.clone(), Box::new(error)));
+        }
+        #[cfg(not(feature = "fs"))]
+        if self.snapshot.is_some() || self.image.is_some() {
+            return Err(SimulationError::Unsupported(
+                "initial snapshots and images require the `fs` feature".to_string(),
+            ));
+        }
+
+        let condition = self.condition.or(parameters.initial).unwrap_or(InitialCondition::Random { cells: self.cells });
+        let universe = match (condition, self.symmetry) {
+            (InitialCondition::Random { cells }, Some(symmetry)) => {
+                symmetry.seed_universe(&dimensions, cells, rng).0
+            }
+            (condition, symmetry) => {
+                let (mut universe, _) = condition.generate(&dimensions, rng)?;
+                if let Some(symmetry) = symmetry {
+                    symmetry.project(&mut universe);
+                }
+                universe
+            }
+        };
+        Ok((universe, dimensions))
+    }
+}
+
+/// Error while reading or writing a configuration file
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Ron(ron::Error),
+    Toml(String),
+    /// The extension of the file is neither `.ron` nor `.toml`
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "{error}"),
+            ConfigError::Ron(error) => write!(f, "invalid RON: {error}"),
+            ConfigError::Toml(error) => write!(f, "invalid TOML: {error}"),
+            ConfigError::UnknownFormat(path) => write!(
+                f,
+                "unknown configuration format for {}, expected a .ron or .toml file",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        ConfigError::Io(error)
+    }
+}
+
+/// Supported configuration formats
+#[cfg(feature = "fs")]
+enum Format {
+    Ron,
+    Toml,
+}
+
+#[cfg(feature = "fs")]
+impl Format {
+    fn from_path(path: &Path) -> Result<Format, ConfigError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => Ok(Format::Ron),
+            Some("toml") => Ok(Format::Toml),
+            _ => Err(ConfigError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+/// Read any settings from a `.ron` or `.toml` file
+#[cfg(feature = "fs")]
+pub(crate) fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let format = Format::from_path(path)?;
+    let text = fs::read_to_string(path)?;
+
+    match format {
+        Format::Ron => ron::from_str(&text).map_err(|error| ConfigError::Ron(error.into())),
+        Format::Toml => toml::from_str(&text).map_err(|error| ConfigError::Toml(error.to_string())),
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Config {
+    /// Read a configuration from a `.ron` or `.toml` file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Config, ConfigError> {
+        read_file(path.as_ref())
+    }
+
+    /// Write the configuration to a `.ron` or `.toml` file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let text = match Format::from_path(path)? {
+            Format::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(ConfigError::Ron)?,
+            Format::Toml => toml::to_string_pretty(self).map_err(|error| ConfigError::Toml(error.to_string()))?,
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Parameters {
+    /// Read the parameters from the `parameters` section of a configuration
+    /// file, see `Config::from_file`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Parameters, ConfigError> {
+        Ok(Config::from_file(path)?.parameters)
+    }
+}
@@ -0,0 +1,95 @@
+/// Conservation of mass
+/// Diffusion only moves A and B between cells, so over an evolution the total
+/// A+B of a universe should change by exactly what the reaction terms add and
+/// remove: the feed of A and the death of B, the reproduction turning A into
+/// B without changing the sum. With a check, see
+/// `Simulation::with_conservation_check`, every evolution compares the change
+/// of the total with the one due to the reaction terms, computed from the
+/// universe before the evolution; what is left, the leak, was lost or created
+/// by the discretization or by rounding. Tiles skipped by activity tracking
+/// still count in the reaction terms, so their drift is part of the leak.
+/// The check is made before the bounds are applied. The conservative
+/// stencil, see `Stencil`, keeps the leak of the diffusion to the rounding
+/// of the sums of its fluxes, and evolves every tile even with activity
+/// tracking
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Float, Universe};
+
+/// Settings of the check of the conservation of mass
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConservationCheck {
+    /// Largest leak, relative to the total A+B before the evolution, which
+    /// is not reported
+    pub tolerance: f64,
+    /// Stop the simulation at the first leak over the tolerance instead of
+    /// logging it
+    pub strict: bool,
+}
+
+impl Default for ConservationCheck {
+    fn default() -> Self {
+        ConservationCheck { tolerance: 1e-5, strict: false }
+    }
+}
+
+impl ConservationCheck {
+    /// Whether `balance` leaked more than the tolerance
+    pub fn is_leak(&self, balance: &MassBalance) -> bool {
+        let relative = balance.relative_leak();
+        relative.is_nan() || relative.abs() > self.tolerance
+    }
+}
+
+/// Total A+B of a universe over one evolution
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MassBalance {
+    /// Generation reached by the evolution
+    pub generation: i32,
+    /// Total before the evolution
+    pub before: f64,
+    /// Change of the total due to the reaction terms
+    pub source: f64,
+    /// Total after the evolution
+    pub after: f64,
+}
+
+impl MassBalance {
+    /// Change of the total not due to the reaction terms
+    pub fn leak(&self) -> f64 {
+        self.after - self.before - self.source
+    }
+
+    /// Leak relative to the total before the evolution, the leak itself for
+    /// an empty universe
+    pub fn relative_leak(&self) -> f64 {
+        if self.before == 0.0 {
+            self.leak()
+        } else {
+            self.leak() / self.before
+        }
+    }
+}
+
+impl fmt::Display for MassBalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "total A+B went from {} to {} at generation {}, {} expected from the reaction terms: leak of {:e} ({:e} relative)",
+            self.before,
+            self.after,
+            self.generation,
+            self.before + self.source,
+            self.leak(),
+            self.relative_leak()
+        )
+    }
+}
+
+/// Total A+B of `universe`, in `f64`
+pub fn total_mass<T: Float>(universe: &Universe<T>) -> f64 {
+    universe.iter().flatten().map(|cell| cell.a.to_f64() + cell.b.to_f64()).sum()
+}
@@ -0,0 +1,283 @@
+/// In-app command console
+/// A drop-down console along the top of the window, opened and closed with
+/// the backtick key, runs the commands typed on its line, e.g. `set f 0.035`
+/// or `preset coral`, from the `CommandRegistry`, which every feature can
+/// extend with its own commands through `RegisterCommand`. While it is open
+/// it takes the keyboard and the mouse, so nothing typed reaches the other
+/// controls. `Up` and `Down` go through the lines entered before, `Escape`
+/// closes it and `help` lists the commands. The text is drawn with the font
+/// at `CONSOLE_FONT` in the assets
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+/// Font of the console, in the assets
+pub const CONSOLE_FONT: &str = "fonts/Hack-Regular.ttf";
+const FONT_SIZE: f32 = 16.0;
+/// Lines of output kept above the command line
+const SCROLLBACK: usize = 12;
+const PROMPT: &str = "> ";
+
+/// Message printed by a command, or why it failed
+pub type CommandResult = Result<String, String>;
+
+/// Command of the console
+#[derive(Debug, Clone, Copy)]
+pub struct ConsoleCommand {
+    /// Arguments, e.g. `<parameter> <value>`
+    pub usage: &'static str,
+    /// One line shown by `help`
+    pub help: &'static str,
+    /// Run the command with its arguments, the words after its name
+    pub run: fn(&mut World, &[&str]) -> CommandResult,
+}
+
+/// Commands of the console, by name
+#[derive(Resource, Debug, Clone)]
+pub struct CommandRegistry {
+    commands: BTreeMap<String, ConsoleCommand>,
+}
+
+impl Default for CommandRegistry {
+    /// Registry with the `help` command only
+    fn default() -> Self {
+        let mut registry = CommandRegistry { commands: BTreeMap::new() };
+        registry.register("help", ConsoleCommand { usage: "", help: "list the commands", run: help });
+        registry
+    }
+}
+
+impl CommandRegistry {
+    /// Add `command` under `name`, replacing the command of that name if any
+    pub fn register(&mut self, name: &str, command: ConsoleCommand) {
+        self.commands.insert(name.to_string(), command);
+    }
+
+    pub fn get(&self, name: &str) -> Option<ConsoleCommand> {
+        self.commands.get(name).copied()
+    }
+
+    /// Names of the commands, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(String::as_str)
+    }
+
+    /// Usage and help of every command, one per line
+    pub fn help(&self) -> String {
+        self.commands
+            .iter()
+            .map(|(name, command)| match command.usage {
+                "" => format!("{name}: {}", command.help),
+                usage => format!("{name} {usage}: {}", command.help),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn help(world: &mut World, _: &[&str]) -> CommandResult {
+    Ok(world.resource::<CommandRegistry>().help())
+}
+
+/// Run `line`, a command name followed by its arguments separated by
+/// spaces, with the commands of the `CommandRegistry` of `world`
+/// An empty line does nothing
+pub fn run_command(world: &mut World, line: &str) -> CommandResult {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Ok(String::new());
+    };
+    let arguments: Vec<&str> = words.collect();
+    let registry = world.get_resource::<CommandRegistry>().ok_or("no commands are registered")?;
+    let command = registry.get(name).ok_or_else(|| {
+        format!("unknown command `{name}`, expected one of: {}", registry.names().collect::<Vec<_>>().join(", "))
+    })?;
+    (command.run)(world, &arguments)
+}
+
+/// Adding commands to the console of an application
+pub trait RegisterCommand {
+    /// Add `command` under `name`, see `CommandRegistry::register`
+    fn register_command(&mut self, name: &str, command: ConsoleCommand) -> &mut Self;
+}
+
+impl RegisterCommand for App {
+    fn register_command(&mut self, name: &str, command: ConsoleCommand) -> &mut Self {
+        self.init_resource::<CommandRegistry>();
+        self.world.resource_mut::<CommandRegistry>().register(name, command);
+        self
+    }
+}
+
+/// Content of the console
+#[derive(Resource, Debug, Default)]
+pub struct Console {
+    pub open: bool,
+    /// Line being typed
+    pub input: String,
+    /// Lines entered, from the oldest
+    history: Vec<String>,
+    /// Index in `history` of the line recalled with the arrow keys
+    recalled: Option<usize>,
+    /// Lines entered but not run yet
+    pending: Vec<String>,
+    /// Lines printed, from the oldest, at most `SCROLLBACK`
+    output: VecDeque<String>,
+}
+
+impl Console {
+    /// Add the lines of `text` to the output, dropping the oldest ones
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            if self.output.len() == SCROLLBACK {
+                self.output.pop_front();
+            }
+            self.output.push_back(line.to_string());
+        }
+    }
+
+    /// Run `line` as if it was typed, at the next frame
+    pub fn submit(&mut self, line: &str) {
+        if !line.trim().is_empty() && self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.to_string());
+        }
+        self.pending.push(line.to_string());
+    }
+
+    /// Replace the input by an older line of the history, or a more recent
+    /// one, and then the empty line
+    fn recall(&mut self, older: bool) {
+        let recalled = match (self.recalled, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) => Some(index + 1).filter(|index| *index < self.history.len()),
+        };
+        self.recalled = recalled;
+        self.input = recalled.map(|index| self.history[index].clone()).unwrap_or_default();
+    }
+}
+
+/// Console, its commands and the systems drawing it and running them
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .init_resource::<CommandRegistry>()
+            .add_startup_system(spawn_console)
+            .add_system_to_stage(CoreStage::PreUpdate, type_in_console.after(InputSystem))
+            .add_system(run_commands)
+            .add_system(draw_console.after(run_commands));
+    }
+}
+
+/// Background of the console, hidden while it is closed
+#[derive(Component)]
+struct ConsoleNode;
+
+/// Output and command line of the console
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style = TextStyle { font: asset_server.load(CONSOLE_FONT), font_size: FONT_SIZE, color: Color::WHITE };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    position: UiRect { left: Val::Px(0.0), top: Val::Px(0.0), ..default() },
+                    size: Size::new(Val::Percent(100.0), Val::Auto),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+            ConsoleNode,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_section("", style), ConsoleText));
+        });
+}
+
+/// Open or close the console with the backtick key and edit its line while
+/// it is open, before the other systems see the keys and buttons pressed
+fn type_in_console(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<MouseButton>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut console: ResMut<Console>,
+) {
+    let toggled = keys.just_pressed(KeyCode::Grave) || (console.open && keys.just_pressed(KeyCode::Escape));
+    if toggled {
+        console.open = !console.open;
+    }
+    if !console.open {
+        characters.clear();
+        if toggled {
+            keys.clear();
+        }
+        return;
+    }
+
+    for character in characters.iter().map(|event| event.char) {
+        if !character.is_control() && character != '`' {
+            console.input.push(character);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        console.recall(true);
+    } else if keys.just_pressed(KeyCode::Down) {
+        console.recall(false);
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.recalled = None;
+        console.submit(&line);
+    }
+    keys.clear();
+    buttons.reset_all();
+}
+
+/// Run the lines entered, printing them and what the commands return
+fn run_commands(world: &mut World) {
+    if world.resource::<Console>().pending.is_empty() {
+        return;
+    }
+    let lines = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    for line in lines {
+        let result = run_command(world, &line);
+        let mut console = world.resource_mut::<Console>();
+        console.print(&format!("{PROMPT}{line}"));
+        match result {
+            Ok(message) => console.print(&message),
+            Err(error) => console.print(&format!("error: {error}")),
+        }
+    }
+}
+
+fn draw_console(
+    console: Res<Console>,
+    mut nodes: Query<&mut Style, With<ConsoleNode>>,
+    mut texts: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut style in &mut nodes {
+        style.display = if console.open { Display::Flex } else { Display::None };
+    }
+    let mut lines: Vec<&str> = console.output.iter().map(String::as_str).collect();
+    let prompt = format!("{PROMPT}{}_", console.input);
+    lines.push(&prompt);
+    for mut text in &mut texts {
+        text.sections[0].value = lines.join("\n");
+    }
+}
@@ -0,0 +1,265 @@
+/// Live control of the simulation
+/// MIDI control changes and OSC messages, e.g. from the knobs of a hardware
+/// controller or from a VJ tool, are mapped to the parameters, the brush and
+/// the color map of the window, so a run can be performed live. Each mapping
+/// scales the value of a control, 0 to 127 for MIDI and 0 to 1 for OSC, to a
+/// range of its target. OSC messages are received over UDP, with their first
+/// argument as the value. MIDI is read as raw bytes from a device file, e.g.
+/// `/dev/midi1` or `/dev/snd/midiC1D0` on Linux; other systems can route
+/// their controllers to OSC instead. The messages are decoded on threads of
+/// their own and the changes sent to the application, see `listen`
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::{Colormap, COLORMAP_NAMES};
+
+/// Largest OSC packet received
+const MAX_PACKET: usize = 1536;
+
+/// Settings of the live control
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControlConfig {
+    /// Address on which OSC messages are received, e.g. `0.0.0.0:9000`
+    pub osc: Option<String>,
+    /// Device file from which raw MIDI is read
+    pub midi: Option<PathBuf>,
+    pub mappings: Vec<ControlMapping>,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        ControlConfig { osc: None, midi: None, mappings: default_mappings() }
+    }
+}
+
+/// Mapping of a control to a target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ControlMapping {
+    pub source: ControlSource,
+    pub target: ControlTarget,
+    /// Values of the target at the lowest and highest value of the control
+    pub range: [f32; 2],
+}
+
+/// Control sending values
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlSource {
+    /// MIDI control change of `controller`, on `channel` from 1 to 16 or on
+    /// all of them
+    Cc { channel: Option<u8>, controller: u8 },
+    /// OSC messages to this address
+    Osc(String),
+}
+
+/// What a control changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlTarget {
+    #[serde(rename = "d_a")]
+    DiffusionA,
+    #[serde(rename = "d_b")]
+    DiffusionB,
+    F,
+    K,
+    R,
+    /// Number of cells on each side of the seeds around the clicked one
+    BrushRadius,
+    /// Concentration of B of the seeds
+    BrushB,
+    /// Color map, through the ones of `COLORMAP_NAMES` in order
+    Palette,
+}
+
+/// Value of a target set by a control
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlChange {
+    pub target: ControlTarget,
+    pub value: f32,
+}
+
+impl ControlChange {
+    /// Color map selected by a change of the palette
+    pub fn colormap(&self) -> Option<Colormap> {
+        let last = COLORMAP_NAMES.len() - 1;
+        let index = (self.value.clamp(0.0, 1.0) * last as f32).round() as usize;
+        Colormap::from_name(COLORMAP_NAMES[index.min(last)])
+    }
+}
+
+/// Mappings used when none are configured: the control changes 21 to 25 and
+/// the OSC addresses `/f`, `/k`, `/brush/radius`, `/brush/b` and `/palette`
+pub fn default_mappings() -> Vec<ControlMapping> {
+    let targets = [
+        ("/f", ControlTarget::F, [0.0, 0.25]),
+        ("/k", ControlTarget::K, [0.0, 0.25]),
+        ("/brush/radius", ControlTarget::BrushRadius, [0.0, 20.0]),
+        ("/brush/b", ControlTarget::BrushB, [0.0, 1.0]),
+        ("/palette", ControlTarget::Palette, [0.0, 1.0]),
+    ];
+    targets
+        .into_iter()
+        .enumerate()
+        .flat_map(|(index, (address, target, range))| {
+            [
+                ControlMapping { source: ControlSource::Cc { channel: None, controller: 21 + index as u8 }, target, range },
+                ControlMapping { source: ControlSource::Osc(address.to_string()), target, range },
+            ]
+        })
+        .collect()
+}
+
+/// Changes of the mappings of `mappings` whose source is `source`, whose
+/// value is `position` of the way from its lowest to its highest value
+pub fn map_control(mappings: &[ControlMapping], source: &ControlSource, position: f32) -> Vec<ControlChange> {
+    let position = position.clamp(0.0, 1.0);
+    mappings
+        .iter()
+        .filter(|mapping| match (&mapping.source, source) {
+            (ControlSource::Cc { channel: None, controller }, ControlSource::Cc { controller: received, .. }) => {
+                controller == received
+            }
+            (mapping, source) => mapping == source,
+        })
+        .map(|mapping| {
+            let [low, high] = mapping.range;
+            ControlChange { target: mapping.target, value: low + (high - low) * position }
+        })
+        .collect()
+}
+
+/// Decoder of the control changes of a raw MIDI stream
+/// Keeps the running status between the bytes given to `feed`
+#[derive(Debug, Clone, Default)]
+pub struct MidiDecoder {
+    /// Status of the message being received, if a control change
+    status: Option<u8>,
+    /// Data bytes of the message so far
+    data: Vec<u8>,
+}
+
+impl MidiDecoder {
+    /// Control changes completed by `byte`, as their source and value from
+    /// 0 to 127
+    pub fn feed(&mut self, byte: u8) -> Option<(ControlSource, u8)> {
+        match byte {
+            // Real time messages may come between any bytes
+            0xf8..=0xff => None,
+            0x80..=0xf7 => {
+                self.status = (byte & 0xf0 == 0xb0).then_some(byte);
+                self.data.clear();
+                None
+            }
+            _ => {
+                let status = self.status?;
+                self.data.push(byte);
+                if self.data.len() < 2 {
+                    return None;
+                }
+                let (controller, value) = (self.data[0], self.data[1]);
+                self.data.clear();
+                let channel = Some((status & 0x0f) + 1);
+                Some((ControlSource::Cc { channel, controller }, value))
+            }
+        }
+    }
+}
+
+/// OSC string at the start of `bytes`, and the bytes after its padding
+fn osc_string(bytes: &[u8]) -> Option<(&str, &[u8])> {
+    let end = bytes.iter().position(|byte| *byte == 0)?;
+    let text = std::str::from_utf8(&bytes[..end]).ok()?;
+    let padded = (end + 4) & !3;
+    Some((text, bytes.get(padded..)?))
+}
+
+/// Messages of an OSC packet, bundles included, as their address and their
+/// first argument if it is a number
+pub fn decode_osc(packet: &[u8]) -> Vec<(String, f32)> {
+    let mut messages = Vec::new();
+    if let Some(mut elements) = packet.strip_prefix(b"#bundle\0") {
+        // Time tag, then elements preceded by their size
+        elements = elements.get(8..).unwrap_or_default();
+        while let Some((size, rest)) = elements.split_first_chunk::<4>() {
+            let size = u32::from_be_bytes(*size) as usize;
+            let Some(element) = rest.get(..size) else {
+                break;
+            };
+            messages.extend(decode_osc(element));
+            elements = &rest[size..];
+        }
+        return messages;
+    }
+
+    let Some((address, rest)) = osc_string(packet) else {
+        return messages;
+    };
+    let Some((tags, arguments)) = osc_string(rest) else {
+        return messages;
+    };
+    let value = match tags.strip_prefix(',').and_then(|tags| tags.chars().next()) {
+        Some('f') => arguments.first_chunk::<4>().map(|bytes| f32::from_be_bytes(*bytes)),
+        Some('i') => arguments.first_chunk::<4>().map(|bytes| i32::from_be_bytes(*bytes) as f32),
+        Some('d') => arguments.first_chunk::<8>().map(|bytes| f64::from_be_bytes(*bytes) as f32),
+        _ => None,
+    };
+    if let Some(value) = value {
+        messages.push((address.to_string(), value));
+    }
+    messages
+}
+
+/// Start receiving the controls of `config` on background threads, which
+/// send the changes they map to until the receiver is dropped
+/// Fails if the OSC address cannot be bound or the MIDI device opened
+pub fn listen(config: &ControlConfig) -> io::Result<Receiver<ControlChange>> {
+    let (sender, receiver) = mpsc::channel();
+    if let Some(address) = &config.osc {
+        let socket = UdpSocket::bind(address)?;
+        let (sender, mappings) = (sender.clone(), config.mappings.clone());
+        thread::spawn(move || receive_osc(socket, &mappings, sender));
+    }
+    if let Some(path) = &config.midi {
+        let device = File::open(path)?;
+        let (sender, mappings) = (sender.clone(), config.mappings.clone());
+        thread::spawn(move || receive_midi(device, &mappings, sender));
+    }
+    Ok(receiver)
+}
+
+fn receive_osc(socket: UdpSocket, mappings: &[ControlMapping], sender: Sender<ControlChange>) {
+    let mut packet = [0; MAX_PACKET];
+    while let Ok(size) = socket.recv(&mut packet) {
+        for (address, value) in decode_osc(&packet[..size]) {
+            for change in map_control(mappings, &ControlSource::Osc(address), value) {
+                if sender.send(change).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn receive_midi(device: File, mappings: &[ControlMapping], sender: Sender<ControlChange>) {
+    let mut decoder = MidiDecoder::default();
+    for byte in BufReader::new(device).bytes() {
+        let Ok(byte) = byte else {
+            return;
+        };
+        let Some((source, value)) = decoder.feed(byte) else {
+            continue;
+        };
+        for change in map_control(mappings, &source, value as f32 / 127.0) {
+            if sender.send(change).is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,1940 @@
+/// Simulation core
+/// Cells, universes, parameters and the evolution of the automaton. Nothing
+/// here requires Bevy, so the core is available with
+/// `default-features = false`; with the `bevy` feature the parameters also
+/// implement `Reflect`
+use std::fmt;
+use std::ops::{ControlFlow, Range};
+use std::sync::Arc;
+
+#[cfg(feature = "bevy")]
+use bevy::reflect::Reflect;
+use rand::{thread_rng, Rng};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::activation::{ActivationMap, ActivationTracking};
+use crate::noise::Noise;
+use crate::activity::{Activity, ActivityTracking};
+use crate::blowup::{Blowup, BlowupCheck};
+use crate::conservation::{total_mass, ConservationCheck, MassBalance};
+use crate::error::{check_dimensions, SimulationError};
+use crate::float::Float;
+use crate::initial::InitialCondition;
+use crate::profile;
+use crate::symmetry::{Orbits, Symmetry};
+use crate::modulation::{Modulation, RateFactors};
+use crate::reaction::Reaction;
+use crate::schedule::Schedule;
+use crate::timeline::Timeline;
+
+/// Cell
+/// Pair of values representing the A and B concentrations 
+/// A, B in interval [0,1], `f32` unless another `Float` is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cell<T = f32> {
+    pub a: T,
+    pub b: T,
+}
+
+impl<T: Float> Cell<T> {
+    /// Cell with no A nor B
+    pub fn empty() -> Cell<T> {
+        Cell { a: T::from_f32(0.0), b: T::from_f32(0.0) }
+    }
+
+    /// Same concentrations with another precision
+    pub fn cast<U: Float>(self) -> Cell<U> {
+        Cell { a: U::from_f64(self.a.to_f64()), b: U::from_f64(self.b.to_f64()) }
+    }
+}
+
+/// Position
+/// Pair of values indicating the row,col position of a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Universe to be considered
+/// Area where the simulation will be run
+pub type Universe<T = f32> = Vec<Vec<Cell<T>>>;
+
+/// Color map
+/// Area with colors for each cell
+pub type ColoredMap = Vec<Vec<f32>>;
+
+/// Number of cells with A and B present in a universe created by `initialize_universe`
+pub const INITIAL_CELLS: usize = 3;
+
+/// Initialize universe
+/// Create a universe with given dimensions and some values for the 
+/// A and B components
+pub fn initialize_universe(dimensions: &Position) -> (Universe, ColoredMap) {
+    initialize_universe_with_rng(dimensions, INITIAL_CELLS, &mut thread_rng())
+}
+
+/// Initialize universe from a given random number generator
+/// Same as `initialize_universe`, but with `n` initial cells whose positions
+/// are drawn from `rng`, so a seeded generator reproduces the same universe
+pub fn initialize_universe_with_rng<T: Float, R: Rng + ?Sized>(
+    dimensions: &Position,
+    n: usize,
+    rng: &mut R) -> (Universe<T>, ColoredMap) {
+
+    let mut universe: Universe<T> = vec![vec![Cell::empty(); dimensions.col]; dimensions.row];
+    let mut colored_map: ColoredMap = vec![vec![0.0; dimensions.col]; dimensions.row];
+
+    let mut positions: Vec<Position> = Vec::with_capacity(dimensions.row * dimensions.col);
+    for r in 0..dimensions.row {
+        for c in 0..dimensions.col {
+            positions.push(Position{row: r, col: c});
+        }
+    }
+    
+    positions.shuffle(rng);
+    let mut cell: &mut Cell<T>;
+    for i in 0..n.min(positions.len()) {
+        cell = &mut universe[positions[i].row][positions[i].col];
+        *cell = Cell {a: T::from_f32(1.0), b: T::from_f32(1.0)};
+        colored_map[positions[i].row][positions[i].col] = color_cell(cell);
+    }
+
+    (universe, colored_map)
+}
+
+/// Parameters for the simulation
+/// Parameters required for the simulation of a CA for Turing patterns
+/// Components:
+/// `d_a` -> diffusion rate for element A in interval [0,1]
+/// `d_b` -> diffusion rate for element B in interval [0,1]
+/// `f` -> constant feed rate for element A in interval [0,1]
+/// `k` -> constant death reaction rate for element B in interval [0,1]
+/// `r` -> constant reproduction reaction rate
+/// `initial` -> initial condition of the universe, see `initial_universe`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+pub struct Parameters {
+    pub d_a: f32,
+    pub d_b: f32,
+    pub f: f32,
+    pub k: f32,
+    pub r: f32,
+    #[serde(default)]
+    #[cfg_attr(feature = "bevy", reflect(ignore))]
+    pub initial: Option<InitialCondition>,
+}
+
+/// Names of the built-in parameter presets, usable with `Parameters::preset`
+pub const PRESET_NAMES: [&str; 3] = ["default", "spots", "mitosis"];
+
+impl Parameters {
+    /// Built-in parameter preset
+    /// Return the parameters stored under `name`, or `None` if there is no
+    /// preset with that name. See `PRESET_NAMES` for the available ones
+    pub fn preset(name: &str) -> Option<Parameters> {
+        match name {
+            "default" => Some(Parameters { d_a: 0.6, d_b: 0.3, f: 0.2, k: 0.1, r: 0.5, initial: None }),
+            "spots" => Some(Parameters { d_a: 1.0, d_b: 0.5, f: 0.055, k: 0.117, r: 1.0, initial: None }),
+            "mitosis" => Some(Parameters { d_a: 1.0, d_b: 0.5, f: 0.0367, k: 0.0649, r: 1.0, initial: None }),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Parameters::preset("default").unwrap()
+    }
+}
+
+/// Invalid parameters
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParametersError {
+    /// The parameter `name` is outside of its interval
+    OutOfRange { name: &'static str, value: f32, min: f32, max: f32 },
+}
+
+impl fmt::Display for ParametersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParametersError::OutOfRange { name, value, min, max } if max.is_finite() => {
+                write!(f, "`{name}` is {value}, expected a value in [{min}, {max}]")
+            }
+            ParametersError::OutOfRange { name, value, min, .. } => {
+                write!(f, "`{name}` is {value}, expected a finite value of at least {min}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParametersError {}
+
+/// Valid parameters that are unlikely to form a pattern, see
+/// `Parameters::hints`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParametersHint {
+    /// B diffuses at least as fast as A, so Turing patterns should not form
+    SlowActivator { d_a: f32, d_b: f32 },
+}
+
+impl fmt::Display for ParametersHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParametersHint::SlowActivator { d_a, d_b } => {
+                write!(f, "`d_a` ({d_a}) is not greater than `d_b` ({d_b}), patterns are unlikely to form")
+            }
+        }
+    }
+}
+
+impl Parameters {
+    /// Builder starting from the default parameters
+    pub fn builder() -> ParametersBuilder {
+        ParametersBuilder::default()
+    }
+
+    /// Check the parameters
+    /// The diffusion, feed and death rates must lie in [0,1] and the
+    /// reproduction rate must be finite and not negative. Parameters that
+    /// are valid but unlikely to form a pattern are reported by `hints`
+    pub fn validate(&self) -> Result<(), ParametersError> {
+        let ranges = [
+            ("d_a", self.d_a, 0.0, 1.0),
+            ("d_b", self.d_b, 0.0, 1.0),
+            ("f", self.f, 0.0, 1.0),
+            ("k", self.k, 0.0, 1.0),
+            ("r", self.r, 0.0, f32::INFINITY),
+        ];
+        for (name, value, min, max) in ranges {
+            if !(value.is_finite() && min <= value && value <= max) {
+                return Err(ParametersError::OutOfRange { name, value, min, max });
+            }
+        }
+        Ok(())
+    }
+
+    /// Reasons the parameters are unlikely to form a pattern, e.g. A not
+    /// diffusing faster than B, none if they may
+    pub fn hints(&self) -> Vec<ParametersHint> {
+        let mut hints = Vec::new();
+        if self.d_a <= self.d_b {
+            hints.push(ParametersHint::SlowActivator { d_a: self.d_a, d_b: self.d_b });
+        }
+        hints
+    }
+
+    /// Universe of `dimensions` generated by the initial condition, by
+    /// default `INITIAL_CELLS` random cells, with the random choices drawn
+    /// from `rng`
+    /// Fails if the initial condition is invalid, see `InitialCondition::validate`
+    pub fn initial_universe<T: Float, R: Rng + ?Sized>(
+        &self,
+        dimensions: &Position,
+        rng: &mut R,
+    ) -> Result<(Universe<T>, ColoredMap), SimulationError> {
+        let condition = self.initial.unwrap_or(InitialCondition::Random { cells: INITIAL_CELLS });
+        condition.generate(dimensions, rng)
+    }
+}
+
+/// Builder of parameters
+/// Parameters that are not set keep the value they started from, by default
+/// the ones of `Parameters::default`, and `build` checks the result:
+/// `Parameters::builder().d_a(0.6).f(0.02).build()?`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParametersBuilder {
+    parameters: Parameters,
+}
+
+impl ParametersBuilder {
+    pub fn d_a(mut self, d_a: f32) -> Self {
+        self.parameters.d_a = d_a;
+        self
+    }
+
+    pub fn d_b(mut self, d_b: f32) -> Self {
+        self.parameters.d_b = d_b;
+        self
+    }
+
+    pub fn f(mut self, f: f32) -> Self {
+        self.parameters.f = f;
+        self
+    }
+
+    pub fn k(mut self, k: f32) -> Self {
+        self.parameters.k = k;
+        self
+    }
+
+    pub fn r(mut self, r: f32) -> Self {
+        self.parameters.r = r;
+        self
+    }
+
+    pub fn initial(mut self, initial: InitialCondition) -> Self {
+        self.parameters.initial = Some(initial);
+        self
+    }
+
+    /// Parameters built, if they are valid, whatever their `Parameters::hints`
+    pub fn build(self) -> Result<Parameters, ParametersError> {
+        self.parameters.validate()?;
+        Ok(self.parameters)
+    }
+}
+
+impl From<Parameters> for ParametersBuilder {
+    fn from(parameters: Parameters) -> Self {
+        ParametersBuilder { parameters }
+    }
+}
+
+/// Diffusion between two adjacent cells
+/// Substract from the diffused cell the quantity of components A and B proportional to
+/// its angular relation, i.e. if it is diagonal 0.05 and 0.2 in cc
+/// Similar, add the corresponding quantities of A and B from the
+/// neighbour Cell
+fn get_adjacent_cells_diffusion<T: Float>(
+    d_a: T,
+    d_b: T,
+    angular_rate: T,
+    diffused_cell: &mut Cell<T>, 
+    neighbour: Cell<T>
+    ){
+
+    diffused_cell.a -= angular_rate * d_a * diffused_cell.a;
+    diffused_cell.b -= angular_rate * d_b * diffused_cell.b;
+
+    diffused_cell.a += angular_rate * d_a * neighbour.a;
+    diffused_cell.b += angular_rate * d_b * neighbour.b;
+}
+
+/// Diffusion function for each cell 
+/// Add the adjacent and diagonal values of substance receved due to diffusion
+/// from substance A and B from its neighbours, and also substract the substance
+/// given to its neighbours using `d_a` and `d_b`.
+/// In this case, 0.2 and 0.05 is considered for adjacent and diagonal 
+/// cells, respectively
+fn get_diffusion_in_cell<T: Float>(
+    d_a: T,
+    d_b: T,
+    cell: &Cell<T>, 
+    position: &Position,
+    dimensions: &Position,
+    boundary: Boundary,
+    tile: &Tile<T>) -> Cell<T> {
+
+    let mut diffused_cell = *cell;
+    let (adjacent, diagonal) = (T::from_f64(0.2), T::from_f64(0.05));
+    // With periodic edges every cell has all its neighbours, those across an
+    // edge are in the halo of the tile
+    let periodic = boundary == Boundary::Periodic;
+    let up = periodic || position.row >= 1;
+    let down = periodic || position.row + 1 < dimensions.row;
+    let left = periodic || position.col >= 1;
+    let right = periodic || position.col + 1 < dimensions.col;
+
+    if up && left {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            diagonal,
+            &mut diffused_cell,
+            tile.neighbour(position, -1, -1)
+            );
+    }
+
+    if up {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            adjacent,
+            &mut diffused_cell,
+            tile.neighbour(position, -1, 0)
+            );
+    } 
+
+    if up && right {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            diagonal,
+            &mut diffused_cell,
+            tile.neighbour(position, -1, 1)
+            );
+    }
+
+    if right {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            adjacent,
+            &mut diffused_cell,
+            tile.neighbour(position, 0, 1)
+            );
+    }
+
+    if down && right {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            diagonal,
+            &mut diffused_cell,
+            tile.neighbour(position, 1, 1)
+            );
+    }
+
+    if down {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            adjacent,
+            &mut diffused_cell,
+            tile.neighbour(position, 1, 0)
+            );
+    }
+
+    if down && left {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            diagonal,
+            &mut diffused_cell,
+            tile.neighbour(position, 1, -1)
+            );
+    }
+
+    if left {
+        get_adjacent_cells_diffusion(
+            d_a,
+            d_b,
+            adjacent,
+            &mut diffused_cell,
+            tile.neighbour(position, 0, -1)
+            );
+    }
+
+    diffused_cell
+}
+
+/// Offsets of the rows and columns of the neighbours of a cell, clockwise
+/// from the top left one as `get_diffusion_in_cell` visits them, with the
+/// weight of their diffusion
+pub(crate) const NEIGHBOURS: [(isize, isize, f64); 8] = [
+    (-1, -1, 0.05),
+    (-1, 0, 0.2),
+    (-1, 1, 0.05),
+    (0, 1, 0.2),
+    (1, 1, 0.05),
+    (1, 0, 0.2),
+    (1, -1, 0.05),
+    (0, -1, 0.2),
+];
+
+/// Diffusion function for each cell, from pairwise fluxes
+/// The flux from a neighbour into the cell, `rate * d * (neighbour - cell)`
+/// with the rates of `get_diffusion_in_cell`, is exactly the opposite of the
+/// flux the neighbour computes from the cell, since swapping the operands of
+/// a subtraction only flips the sign of its rounded result: what one cell
+/// gives away is what the other receives, bit for bit, and nothing crosses a
+/// closed edge. The fluxes are summed before being added to the cell, so the
+/// only mass created or destroyed is the rounding of these two sums
+fn get_conservative_diffusion_in_cell<T: Float>(
+    d_a: T,
+    d_b: T,
+    cell: &Cell<T>,
+    position: &Position,
+    dimensions: &Position,
+    boundary: Boundary,
+    tile: &Tile<T>,
+) -> Cell<T> {
+    let periodic = boundary == Boundary::Periodic;
+    let inside = |index: usize, offset: isize, size: usize| {
+        periodic || index.checked_add_signed(offset).is_some_and(|index| index < size)
+    };
+    let mut flux = Cell::empty();
+    for (d_row, d_col, weight) in NEIGHBOURS {
+        if !inside(position.row, d_row, dimensions.row) || !inside(position.col, d_col, dimensions.col) {
+            continue;
+        }
+        let neighbour = tile.neighbour(position, d_row, d_col);
+        let rate = T::from_f64(weight);
+        flux.a += rate * (d_a * (neighbour.a - cell.a));
+        flux.b += rate * (d_b * (neighbour.b - cell.b));
+    }
+    Cell { a: cell.a + flux.a, b: cell.b + flux.b }
+}
+
+/// Change of A and of B of `cell` over one evolution due to the reactions of
+/// the Gray–Scott model: the feed of A, the death of B and the reproduction
+/// A + 2B -> 3B
+pub(crate) fn gray_scott<T: Float>(parameters: &Parameters, cell: Cell<T>) -> [T; 2] {
+    let [f, k, r] = [parameters.f, parameters.k, parameters.r].map(T::from_f32);
+    let reproduction = r * cell.a * cell.b * cell.b;
+    [f * (T::from_f32(1.0) - cell.a) - reproduction, reproduction - k * cell.b]
+}
+
+/// Evolution of `cell` as `reference` computes it: the diffusion with the
+/// neighbours of `NEIGHBOURS`, given by `neighbour` and `None` beyond a
+/// closed edge, each taking from the cell in proportion to what it holds by
+/// then, followed by the reactions of `gray_scott`
+pub(crate) fn evolve_cell<T: Float>(
+    parameters: &Parameters,
+    cell: Cell<T>,
+    neighbour: impl Fn(isize, isize) -> Option<Cell<T>>,
+) -> Cell<T> {
+    evolve_scaled_cell(parameters, T::from_f32(1.0), cell, neighbour)
+}
+
+/// Same as `evolve_cell`, with the diffusion rates divided by `scale`, for
+/// cells spaced further apart than those of the universe
+pub(crate) fn evolve_scaled_cell<T: Float>(
+    parameters: &Parameters,
+    scale: T,
+    cell: Cell<T>,
+    neighbour: impl Fn(isize, isize) -> Option<Cell<T>>,
+) -> Cell<T> {
+    let (d_a, d_b) = (T::from_f32(parameters.d_a) / scale, T::from_f32(parameters.d_b) / scale);
+    let mut diffused = cell;
+    for (d_row, d_col, weight) in NEIGHBOURS {
+        let Some(neighbour) = neighbour(d_row, d_col) else {
+            continue;
+        };
+        let weight = T::from_f64(weight);
+        diffused.a += weight * d_a * (neighbour.a - diffused.a);
+        diffused.b += weight * d_b * (neighbour.b - diffused.b);
+    }
+    let [a, b] = gray_scott(parameters, cell);
+    Cell { a: diffused.a + a, b: diffused.b + b }
+}
+
+/// Transition function
+/// Considers the difussion for each cell,
+/// the feed of A,
+/// the death of B, and
+/// the reproduction A + 2B -> 3B,
+/// or the terms of `reaction` instead of the last three
+#[allow(clippy::too_many_arguments)]
+fn transition<T: Float>(
+    parameters: &Parameters,
+    reaction: Option<&dyn Reaction>,
+    cell: &Cell<T>, 
+    position: &Position,
+    dimensions: &Position,
+    boundary: Boundary,
+    stencil: Stencil,
+    tile: &Tile<T>,
+    colored_map: &mut ColoredMap) -> Cell<T> {
+
+    let mut evolved_cell: Cell<T>;
+
+    let (d_a, d_b) = (T::from_f32(parameters.d_a), T::from_f32(parameters.d_b));
+    evolved_cell = match stencil {
+        Stencil::Cellwise => get_diffusion_in_cell(d_a, d_b, cell, position, dimensions, boundary, tile),
+        Stencil::Conservative => {
+            get_conservative_diffusion_in_cell(d_a, d_b, cell, position, dimensions, boundary, tile)
+        }
+    };
+
+    if let Some(reaction) = reaction {
+        let [a, b] = reaction.react(cell.a.to_f64(), cell.b.to_f64(), parameters);
+        evolved_cell.a += T::from_f64(a);
+        evolved_cell.b += T::from_f64(b);
+        colored_map[position.row][position.col] = color_cell(&evolved_cell);
+        return evolved_cell;
+    }
+
+    evolved_cell.a += T::from_f32(parameters.f) * (T::from_f32(1.0) - cell.a);
+
+    evolved_cell.b -= T::from_f32(parameters.k) * cell.b;
+    
+    // b * b rather than b.powf(2.0): powf is computed by the math library of
+    // the platform, whose rounding differs between platforms, while products
+    // are rounded the same everywhere, see `deterministic`
+    let reproduction_reaction: T = T::from_f32(parameters.r) * cell.a * cell.b * cell.b;
+    evolved_cell.a -= reproduction_reaction;
+    evolved_cell.b += reproduction_reaction;
+    
+    colored_map[position.row][position.col] = color_cell(&evolved_cell);
+
+    evolved_cell
+}
+
+/// Changes to the kinetics of the cells during an evolution
+#[derive(Clone, Copy, Default)]
+struct Kinetics<'a> {
+    /// Factors of `f` and `k` of every cell, see `modulation`
+    factors: Option<&'a RateFactors>,
+    /// Reaction terms replacing those of the Gray–Scott model, see `reaction`
+    reaction: Option<&'a dyn Reaction>,
+    /// Computation of the diffusion between the cells
+    stencil: Stencil,
+}
+
+/// Parameters of the cell at `position`, with `f` and `k` scaled by its
+/// factors if any
+fn modulated(parameters: &Parameters, factors: Option<&RateFactors>, position: &Position) -> Parameters {
+    match factors {
+        Some(factors) => {
+            let [f, k] = factors[position.row][position.col];
+            Parameters { f: parameters.f * f, k: parameters.k * k, ..*parameters }
+        }
+        None => *parameters,
+    }
+}
+
+/// Change of the total A+B of `universe` over one evolution due to the
+/// reaction terms, see `conservation`
+fn reaction_source<T: Float>(parameters: &Parameters, kinetics: Kinetics, universe: &Universe<T>) -> f64 {
+    let mut source = 0.0;
+    for (row, cells) in universe.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            let parameters = modulated(parameters, kinetics.factors, &Position { row, col });
+            let (a, b) = (cell.a.to_f64(), cell.b.to_f64());
+            source += match kinetics.reaction {
+                Some(reaction) => reaction.react(a, b, &parameters).iter().sum(),
+                // The reproduction turns A into B and leaves the sum as is
+                None => parameters.f as f64 * (1.0 - a) - parameters.k as f64 * b,
+            };
+        }
+    }
+    source
+}
+
+/// Number of cells on each side of the tiles of `evolution_universe`
+/// A tile and its halo take about 35 KB with `f32` concentrations, so they
+/// stay in the cache of a core while the tile is evolved
+pub const TILE_SIZE: usize = 64;
+
+/// Copy of a tile of a universe with a halo of one cell around it, in one
+/// contiguous buffer, in the precision the evolution is computed with
+/// The halo holds the neighbours of the cells on the edges of the tile,
+/// from across the edges of the universe with periodic edges; outside of a
+/// closed universe it is left empty and never read
+struct Tile<T> {
+    /// Position in the universe of the first cell of the tile
+    origin: Position,
+    /// Number of cells in a row of the buffer, halo included
+    width: usize,
+    cells: Vec<Cell<T>>,
+}
+
+impl<T: Float> Tile<T> {
+    /// Copy the cells of `rows` and `cols` of `universe`, and their halo
+    fn copy<S: Float<Compute = T>>(
+        universe: &Universe<S>,
+        dimensions: &Position,
+        boundary: Boundary,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Tile<T> {
+        let width = cols.len() + 2;
+        let mut cells = vec![Cell::empty(); (rows.len() + 2) * width];
+        // Row or column of the universe copied at `index` of the buffer
+        let source = |start: usize, index: usize, size: usize| match (start + index).checked_sub(1) {
+            Some(source) if source < size => Some(source),
+            _ if boundary == Boundary::Periodic => Some((start + index + size - 1) % size),
+            _ => None,
+        };
+        for (index, buffer_row) in cells.chunks_exact_mut(width).enumerate() {
+            let Some(row) = source(rows.start, index, dimensions.row) else {
+                continue;
+            };
+            for (index, cell) in buffer_row.iter_mut().enumerate() {
+                if let Some(col) = source(cols.start, index, dimensions.col) {
+                    *cell = universe[row][col].cast();
+                }
+            }
+        }
+        Tile { origin: Position { row: rows.start, col: cols.start }, width, cells }
+    }
+
+    /// Cell at `position` in the universe, which must be in the tile
+    fn cell(&self, position: Position) -> Cell<T> {
+        self.neighbour(&position, 0, 0)
+    }
+
+    /// Cell `d_row` rows and `d_col` columns away from `position`, which must
+    /// be in the tile
+    fn neighbour(&self, position: &Position, d_row: isize, d_col: isize) -> Cell<T> {
+        let row = (position.row + 1 - self.origin.row).wrapping_add_signed(d_row);
+        let col = (position.col + 1 - self.origin.col).wrapping_add_signed(d_col);
+        self.cells[row * self.width + col]
+    }
+}
+
+/// Iterate over all cells in the universe
+/// From the initial state, generate another universe and return it with the
+/// corresponding values of one evolution. The universe is evolved one tile
+/// of `TILE_SIZE` cells at a time, each copied with its halo so that its
+/// neighbours are read from nearby memory, and computed in `T::Compute`
+pub fn evolution_universe<T: Float>(
+    parameters: &Parameters, 
+    dimensions: &Position, 
+    boundary: Boundary,
+    universe: Universe<T>,
+    colored_map: &mut ColoredMap) -> Universe<T> {
+    evolution_universe_with_kinetics(
+        parameters, dimensions, boundary, universe, colored_map, Kinetics::default(), &mut |_, _| {})
+}
+
+/// Same as `evolution_universe`, with the changes of `kinetics`, calling
+/// `progress` with the rows of every row of tiles once their colors are done
+fn evolution_universe_with_kinetics<T: Float>(
+    parameters: &Parameters,
+    dimensions: &Position,
+    boundary: Boundary,
+    universe: Universe<T>,
+    colored_map: &mut ColoredMap,
+    kinetics: Kinetics,
+    progress: &mut dyn FnMut(Range<usize>, &ColoredMap)) -> Universe<T> {
+    let mut evolved_universe: Universe<T> = vec![vec![Cell::empty(); dimensions.col]; dimensions.row];
+
+    for row in (0..dimensions.row).step_by(TILE_SIZE) {
+        let rows = row..(row + TILE_SIZE).min(dimensions.row);
+        for col in (0..dimensions.col).step_by(TILE_SIZE) {
+            let cols = col..(col + TILE_SIZE).min(dimensions.col);
+            let tile = Tile::copy(&universe, dimensions, boundary, rows.clone(), cols.clone());
+            for r in rows.clone() {
+                for c in cols.clone() {
+                    let position = Position {row: r, col: c};
+                    evolved_universe[r][c] = transition(
+                        &modulated(parameters, kinetics.factors, &position),
+                        kinetics.reaction,
+                        &tile.cell(position),
+                        &position,
+                        dimensions,
+                        boundary,
+                        kinetics.stencil,
+                        &tile,
+                        colored_map
+                        ).cast();
+                }
+            }
+        }
+        progress(rows, colored_map);
+    }
+
+    evolved_universe
+}
+
+/// Iterate over the cells of the active tiles only
+/// Same as `evolution_universe` for the tiles that `activity` says are due;
+/// the cells of the other tiles keep their concentrations and colors. The
+/// largest change of every tile is recorded in `activity`
+pub fn evolution_universe_active<T: Float>(
+    parameters: &Parameters,
+    dimensions: &Position,
+    boundary: Boundary,
+    universe: Universe<T>,
+    colored_map: &mut ColoredMap,
+    activity: &mut Activity) -> Universe<T> {
+    evolution_universe_active_with_kinetics(
+        parameters, dimensions, boundary, universe, colored_map, activity, Kinetics::default(), &mut |_, _| {})
+}
+
+/// Same as `evolution_universe_active`, with the changes of `kinetics`,
+/// calling `progress` with the rows of every row of tiles with due tiles once
+/// their colors are done
+#[allow(clippy::too_many_arguments)]
+fn evolution_universe_active_with_kinetics<T: Float>(
+    parameters: &Parameters,
+    dimensions: &Position,
+    boundary: Boundary,
+    universe: Universe<T>,
+    colored_map: &mut ColoredMap,
+    activity: &mut Activity,
+    kinetics: Kinetics,
+    progress: &mut dyn FnMut(Range<usize>, &ColoredMap)) -> Universe<T> {
+    activity.fit(*dimensions);
+    let due = activity.due(boundary);
+    let mut evolved_universe = universe.clone();
+    let mut changes = vec![0.0; due.len()];
+    let mut evolved = 0;
+    // Rows of the tiles evolved last, reported once the next row of tiles starts
+    let mut done: Option<Range<usize>> = None;
+
+    for (tile, change) in changes.iter_mut().enumerate().filter(|(tile, _)| due[*tile]) {
+        evolved += 1;
+        let (rows, cols) = activity.tile_cells(tile);
+        if let Some(rows) = done.take_if(|done| done.start != rows.start) {
+            progress(rows, colored_map);
+        }
+        done = Some(rows.clone());
+        let tile = Tile::copy(&universe, dimensions, boundary, rows.clone(), cols.clone());
+        for r in rows {
+            for c in cols.clone() {
+                let position = Position {row: r, col: c};
+                let cell: Cell<T> = transition(
+                    &modulated(parameters, kinetics.factors, &position),
+                    kinetics.reaction,
+                    &tile.cell(position),
+                    &position,
+                    dimensions,
+                    boundary,
+                    kinetics.stencil,
+                    &tile,
+                    colored_map
+                    ).cast();
+                let difference = (cell.a - universe[r][c].a).to_f64().abs()
+                    .max((cell.b - universe[r][c].b).to_f64().abs());
+                *change = if difference.is_nan() { f64::INFINITY } else { change.max(difference) };
+                evolved_universe[r][c] = cell;
+            }
+        }
+    }
+    if let Some(rows) = done {
+        progress(rows, colored_map);
+    }
+    activity.record(changes, evolved);
+
+    evolved_universe
+}
+
+/// Color visualisation for cell
+/// Give a color for each cell according to the concentrations A and B
+pub fn color_cell<T: Float>(cell: &Cell<T>) -> f32 {
+    if cell.a + cell.b <= T::from_f32(0.) {
+        return 0.;
+    }
+    (cell.b / (cell.a + cell.b)).to_f32()
+}
+
+/// Color map of a universe
+/// Apply `color_cell` to every cell of the universe
+pub fn color_universe<T: Float>(universe: &Universe<T>) -> ColoredMap {
+    universe
+        .iter()
+        .map(|row| row.iter().map(color_cell).collect())
+        .collect()
+}
+
+/// Handling of concentrations leaving [0,1]
+/// Diffusion and reaction can push concentrations out of [0,1], e.g. with
+/// large rates, and past that point they may grow without bound to NaN
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bounds {
+    /// Concentrations are left as computed
+    #[default]
+    Unchecked,
+    /// Concentrations are clamped to [0,1] after every evolution
+    Clamp,
+    /// The simulation stops at the first concentration out of [0,1]
+    Strict,
+}
+
+/// Names of the bounds modes, as written in the configuration files
+pub const BOUNDS_NAMES: [&str; 3] = ["unchecked", "clamp", "strict"];
+
+impl Bounds {
+    /// Mode with the given name, see `BOUNDS_NAMES`
+    pub fn from_name(name: &str) -> Option<Bounds> {
+        match name {
+            "unchecked" => Some(Bounds::Unchecked),
+            "clamp" => Some(Bounds::Clamp),
+            "strict" => Some(Bounds::Strict),
+            _ => None,
+        }
+    }
+}
+
+/// Concentration out of [0,1] found in `Bounds::Strict` mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Violation {
+    /// Generation reached when the cell left [0,1]
+    pub generation: i32,
+    pub position: Position,
+    pub cell: Cell,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cell ({}, {}) has a = {}, b = {} at generation {}, outside of [0, 1]",
+            self.position.row, self.position.col, self.cell.a, self.cell.b, self.generation
+        )
+    }
+}
+
+/// Edges of the universe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Boundary {
+    /// Cells on the edges have fewer neighbours and nothing diffuses out
+    #[default]
+    Closed,
+    /// Opposite edges are joined, so the universe is a torus and its
+    /// patterns can be tiled seamlessly
+    Periodic,
+}
+
+/// Names of the boundaries, as written in the configuration files
+pub const BOUNDARY_NAMES: [&str; 2] = ["closed", "periodic"];
+
+impl Boundary {
+    /// Boundary with the given name, see `BOUNDARY_NAMES`
+    pub fn from_name(name: &str) -> Option<Boundary> {
+        match name {
+            "closed" => Some(Boundary::Closed),
+            "periodic" => Some(Boundary::Periodic),
+            _ => None,
+        }
+    }
+}
+
+/// Computation of the diffusion between neighbouring cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stencil {
+    /// Every cell subtracts what it gives to each neighbour and adds what it
+    /// receives from it, one after the other, rounding in between
+    #[default]
+    Cellwise,
+    /// Every pair of neighbours exchanges one flux, given away by one cell
+    /// and received by the other to the last bit, for quantitative work
+    /// where the mass must be conserved, see `conservation`
+    /// A tile left as it is by the activity tracking would not receive the
+    /// fluxes its evolved neighbours give it, so every tile is evolved
+    Conservative,
+}
+
+/// Names of the stencils, as written in the configuration files
+pub const STENCIL_NAMES: [&str; 2] = ["cellwise", "conservative"];
+
+impl Stencil {
+    /// Stencil with the given name, see `STENCIL_NAMES`
+    pub fn from_name(name: &str) -> Option<Stencil> {
+        match name {
+            "cellwise" => Some(Stencil::Cellwise),
+            "conservative" => Some(Stencil::Conservative),
+            _ => None,
+        }
+    }
+}
+
+/// Resampling of a universe to new dimensions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resampling {
+    /// Every new cell copies the nearest old one
+    Nearest,
+    /// Every new cell interpolates the old ones around it, see
+    /// `Simulation::sample`
+    #[default]
+    Bilinear,
+}
+
+/// Summary of a universe after an evolution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepSummary {
+    /// Number of evolutions computed so far
+    pub generation: i32,
+    pub mean_a: f32,
+    pub mean_b: f32,
+    pub min_b: f32,
+    pub max_b: f32,
+}
+
+/// Observer called after every evolution, see `Simulation::on_step`
+pub type StepObserver = Box<dyn FnMut(&StepSummary) -> ControlFlow<()> + Send + Sync>;
+
+/// Simulation
+/// Parameters, universe and color map of a running simulation, with the
+/// number of evolutions computed so far
+pub struct Simulation<T: Float = f32> {
+    parameters: Parameters,
+    dimensions: Position,
+    universe: Universe<T>,
+    colored_map: ColoredMap,
+    generation: i32,
+    observers: Vec<StepObserver>,
+    /// Set once an observer asked to stop
+    stopped: bool,
+    /// Set while an evolution taken by `begin_step` is not given back
+    busy: bool,
+    bounds: Bounds,
+    boundary: Boundary,
+    stencil: Stencil,
+    violation: Option<Violation>,
+    timeline: Timeline,
+    /// Pulses of the parameters and injections of B
+    schedule: Schedule,
+    /// Tiles evolved during the next evolution, all of them if not tracked
+    activity: Option<Activity>,
+    modulation: Option<Modulation>,
+    /// Factors of the rates of the cells given by the modulation, computed
+    /// again at every evolution if it is animated
+    factors: Option<Arc<RateFactors>>,
+    reaction: Option<Arc<dyn Reaction>>,
+    conservation: Option<ConservationCheck>,
+    /// Mass balance of the last evolution, if conservation is checked
+    balance: Option<MassBalance>,
+    /// Leak over the tolerance that stopped the simulation, with a strict
+    /// conservation check
+    leak: Option<MassBalance>,
+    blowup_check: Option<BlowupCheck>,
+    /// Blown up concentrations found by the last scan, if checked
+    blowup: Option<Blowup>,
+    /// Symmetry the universe is projected onto after every evolution
+    symmetry: Option<Symmetry>,
+    /// Orbits of the cells under the symmetry, computed again once the
+    /// dimensions change
+    orbits: Option<Orbits>,
+    /// Generation at which each cell was activated, if tracked
+    activation: Option<ActivationMap>,
+    /// Noise added to B after every evolution
+    noise: Option<Noise>,
+}
+
+/// The observers are not cloned: the clone starts without any
+impl<T: Float> Clone for Simulation<T> {
+    fn clone(&self) -> Self {
+        Simulation {
+            parameters: self.parameters,
+            dimensions: self.dimensions,
+            universe: self.universe.clone(),
+            colored_map: self.colored_map.clone(),
+            generation: self.generation,
+            observers: Vec::new(),
+            stopped: self.stopped,
+            busy: self.busy,
+            bounds: self.bounds,
+            boundary: self.boundary,
+            stencil: self.stencil,
+            violation: self.violation,
+            timeline: self.timeline.clone(),
+            schedule: self.schedule.clone(),
+            activity: self.activity.clone(),
+            modulation: self.modulation.clone(),
+            factors: self.factors.clone(),
+            reaction: self.reaction.clone(),
+            conservation: self.conservation,
+            balance: self.balance,
+            leak: self.leak,
+            blowup_check: self.blowup_check,
+            blowup: self.blowup.clone(),
+            symmetry: self.symmetry,
+            orbits: self.orbits.clone(),
+            activation: self.activation.clone(),
+            noise: self.noise.clone(),
+        }
+    }
+}
+
+impl<T: Float> fmt::Debug for Simulation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulation")
+            .field("parameters", &self.parameters)
+            .field("dimensions", &self.dimensions)
+            .field("generation", &self.generation)
+            .field("observers", &self.observers.len())
+            .field("stopped", &self.stopped)
+            .field("busy", &self.busy)
+            .field("bounds", &self.bounds)
+            .field("boundary", &self.boundary)
+            .field("stencil", &self.stencil)
+            .field("violation", &self.violation)
+            .field("timeline", &self.timeline)
+            .field("schedule", &self.schedule)
+            .field("activity", &self.activity)
+            .field("modulation", &self.modulation)
+            .field("reaction", &self.reaction)
+            .field("conservation", &self.conservation)
+            .field("leak", &self.leak)
+            .field("blowup_check", &self.blowup_check)
+            .field("blowup", &self.blowup)
+            .field("symmetry", &self.symmetry)
+            .field("activation", &self.activation.as_ref().map(ActivationMap::tracking))
+            .field("noise", &self.noise.as_ref().map(Noise::config))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Float> Simulation<T> {
+    /// Simulation starting from `universe`, of the given dimensions
+    /// Fails if the parameters are invalid or the universe does not have the
+    /// given dimensions
+    pub fn new(
+        parameters: Parameters,
+        dimensions: Position,
+        universe: Universe<T>,
+    ) -> Result<Simulation<T>, SimulationError> {
+        parameters.validate()?;
+        check_dimensions(&universe, dimensions)?;
+        let colored_map = color_universe(&universe);
+        Ok(Simulation::from_parts(parameters, dimensions, universe, colored_map))
+    }
+
+    /// Simulation of the universe generated by the initial condition of the
+    /// parameters, see `Parameters::initial_universe`
+    /// Fails if the parameters or their initial condition are invalid
+    pub fn from_parameters<R: Rng + ?Sized>(
+        parameters: Parameters,
+        dimensions: Position,
+        rng: &mut R,
+    ) -> Result<Simulation<T>, SimulationError> {
+        parameters.validate()?;
+        let (universe, colored_map) = parameters.initial_universe(&dimensions, rng)?;
+        Ok(Simulation::from_parts(parameters, dimensions, universe, colored_map))
+    }
+
+    /// Simulation of an empty universe with `n` random initial cells drawn
+    /// from `rng`, see `initialize_universe_with_rng`
+    /// Fails if the parameters are invalid
+    pub fn random<R: Rng + ?Sized>(
+        parameters: Parameters,
+        dimensions: Position,
+        n: usize,
+        rng: &mut R,
+    ) -> Result<Simulation<T>, SimulationError> {
+        parameters.validate()?;
+        let (universe, colored_map) = initialize_universe_with_rng(&dimensions, n, rng);
+        Ok(Simulation::from_parts(parameters, dimensions, universe, colored_map))
+    }
+
+    fn from_parts(
+        parameters: Parameters,
+        dimensions: Position,
+        universe: Universe<T>,
+        colored_map: ColoredMap,
+    ) -> Simulation<T> {
+        Simulation {
+            parameters,
+            dimensions,
+            universe,
+            colored_map,
+            generation: 0,
+            observers: Vec::new(),
+            stopped: false,
+            busy: false,
+            bounds: Bounds::default(),
+            boundary: Boundary::default(),
+            stencil: Stencil::default(),
+            violation: None,
+            timeline: Timeline::default(),
+            schedule: Schedule::default(),
+            activity: None,
+            modulation: None,
+            factors: None,
+            reaction: None,
+            conservation: None,
+            balance: None,
+            leak: None,
+            blowup_check: None,
+            blowup: None,
+            symmetry: None,
+            orbits: None,
+            activation: None,
+            noise: None,
+        }
+    }
+
+    /// Same simulation, handling out of range concentrations as `bounds` says
+    pub fn with_bounds(mut self, bounds: Bounds) -> Simulation<T> {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Same simulation, with the parameters following `timeline`: before
+    /// every evolution, the parameters with a track take their value at the
+    /// current generation
+    /// Fails if the parameters are invalid at some keyframe, or a pulse of
+    /// the schedule is with them
+    pub fn with_timeline(mut self, timeline: Timeline) -> Result<Simulation<T>, SimulationError> {
+        let timeline = timeline.sorted();
+        timeline.validate(self.parameters)?;
+        validate_changes(&self.schedule, &timeline, self.modulation.as_ref(), self.parameters)?;
+        self.timeline = timeline;
+        Ok(self)
+    }
+
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    /// Same simulation, forced by the pulses of `schedule`: before every
+    /// evolution, the B of the active injections is added, and the evolution
+    /// is computed with the parameters of the active pulses
+    /// Fails if a pulse is invalid, see `Schedule::validate`, with the
+    /// parameters or at some keyframe of the timeline
+    pub fn with_schedule(mut self, schedule: Schedule) -> Result<Simulation<T>, SimulationError> {
+        validate_changes(&schedule, &self.timeline, self.modulation.as_ref(), self.parameters)?;
+        self.schedule = schedule;
+        Ok(self)
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /// Same simulation, only evolving the tiles of the universe that are
+    /// still changing, see `activity`
+    pub fn with_activity_tracking(mut self, tracking: ActivityTracking) -> Simulation<T> {
+        self.activity = Some(Activity::new(tracking, self.dimensions));
+        self
+    }
+
+    /// Activity of the tiles during the last evolution, if tracked
+    pub fn activity(&self) -> Option<&Activity> {
+        self.activity.as_ref()
+    }
+
+    /// Rows and columns of the cells that may have changed since
+    /// `clear_dirty` was last called, by tiles, see `Activity::dirty`;
+    /// `None` if the activity is not tracked, any cell having changed
+    pub fn dirty_cells(&self) -> Option<Vec<(Range<usize>, Range<usize>)>> {
+        self.activity.as_ref().map(Activity::dirty)
+    }
+
+    /// Forget the cells that changed, e.g. once the color map is drawn
+    pub fn clear_dirty(&mut self) {
+        if let Some(activity) = &mut self.activity {
+            activity.clear_dirty();
+        }
+    }
+
+    /// Same simulation, with `f` and `k` of every cell scaled by
+    /// `modulation` at every evolution
+    /// Fails if the scaled rates leave [0,1] with the parameters or at some
+    /// keyframe of the timeline, see `Modulation::validate`
+    pub fn with_modulation(mut self, modulation: Modulation) -> Result<Simulation<T>, SimulationError> {
+        validate_changes(&self.schedule, &self.timeline, Some(&modulation), self.parameters)?;
+        self.modulation = Some(modulation);
+        self.factors = None;
+        self.wake_all();
+        Ok(self)
+    }
+
+    pub fn modulation(&self) -> Option<&Modulation> {
+        self.modulation.as_ref()
+    }
+
+    /// Same simulation, with the reaction terms of `reaction` instead of
+    /// those of the Gray–Scott model
+    pub fn with_reaction(mut self, reaction: Arc<dyn Reaction>) -> Simulation<T> {
+        self.reaction = Some(reaction);
+        self.wake_all();
+        self
+    }
+
+    pub fn reaction(&self) -> Option<&dyn Reaction> {
+        self.reaction.as_deref()
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// Same simulation, checking after every evolution that the total A+B
+    /// only changed by what the reaction terms add and remove, see
+    /// `conservation`
+    pub fn with_conservation_check(mut self, check: ConservationCheck) -> Simulation<T> {
+        self.conservation = Some(check);
+        self
+    }
+
+    pub fn conservation_check(&self) -> Option<ConservationCheck> {
+        self.conservation
+    }
+
+    /// Mass balance of the last evolution, if conservation is checked
+    pub fn mass_balance(&self) -> Option<&MassBalance> {
+        self.balance.as_ref()
+    }
+
+    /// Leak over the tolerance that stopped the simulation, with a strict
+    /// conservation check
+    pub fn leak(&self) -> Option<&MassBalance> {
+        self.leak.as_ref()
+    }
+
+    /// Same simulation, scanning the universe for blown up concentrations
+    /// every few evolutions, see `blowup`
+    /// Fails if the check is invalid, see `BlowupCheck::validate`
+    pub fn with_blowup_check(mut self, check: BlowupCheck) -> Result<Simulation<T>, SimulationError> {
+        check.validate()?;
+        self.blowup_check = Some(check);
+        Ok(self)
+    }
+
+    pub fn blowup_check(&self) -> Option<BlowupCheck> {
+        self.blowup_check
+    }
+
+    /// Blown up concentrations found by the last scan of the universe, if
+    /// checked; the simulation goes on evolving
+    pub fn blowup(&self) -> Option<&Blowup> {
+        self.blowup.as_ref()
+    }
+
+    /// Same simulation, projecting the universe onto `symmetry` after every
+    /// evolution, see `symmetry`
+    /// Every tile is evolved when the activity is tracked, since the
+    /// projection changes cells far from those that changed
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Simulation<T> {
+        self.symmetry = Some(symmetry);
+        self.orbits = None;
+        self
+    }
+
+    pub fn symmetry(&self) -> Option<Symmetry> {
+        self.symmetry
+    }
+
+    /// Same simulation, remembering the generation at which the B of each
+    /// cell first exceeds the threshold of `tracking`, see `activation`
+    pub fn with_activation_tracking(mut self, tracking: ActivationTracking) -> Simulation<T> {
+        self.activation = Some(ActivationMap::new(tracking, &self.universe, self.generation));
+        self
+    }
+
+    /// Activation times of the cells, if tracked
+    pub fn activation(&self) -> Option<&ActivationMap> {
+        self.activation.as_ref()
+    }
+
+    /// Same simulation, adding `noise` to B after every evolution, see
+    /// `noise`
+    /// Every tile is evolved when the activity is tracked, since the noise
+    /// changes every cell
+    pub fn with_noise(mut self, noise: Noise) -> Simulation<T> {
+        self.noise = Some(noise);
+        self
+    }
+
+    /// Noise with the seed of its generator, if any, e.g. to add the same
+    /// noise to another simulation
+    pub fn noise(&self) -> Option<&Noise> {
+        self.noise.as_ref()
+    }
+
+    /// Same simulation, with the edges of the universe given by `boundary`
+    pub fn with_boundary(mut self, boundary: Boundary) -> Simulation<T> {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Same simulation, diffusing between the cells as `stencil` says
+    pub fn with_stencil(mut self, stencil: Stencil) -> Simulation<T> {
+        self.stencil = stencil;
+        self
+    }
+
+    pub fn stencil(&self) -> Stencil {
+        self.stencil
+    }
+
+    /// Concentration out of [0,1] that stopped the simulation, in
+    /// `Bounds::Strict` mode
+    pub fn violation(&self) -> Option<&Violation> {
+        self.violation.as_ref()
+    }
+
+    /// Call `observer` with a summary of the universe after every evolution
+    /// An observer returning `ControlFlow::Break` stops the simulation: `run`
+    /// returns early and `is_stopped` tells the other loops to end
+    pub fn on_step(
+        &mut self,
+        observer: impl FnMut(&StepSummary) -> ControlFlow<()> + Send + Sync + 'static,
+    ) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Whether an observer asked to stop, a concentration left [0,1] in
+    /// `Bounds::Strict` mode, or the mass leaked with a strict conservation
+    /// check
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Summary of the current universe, see `stats` for more statistics
+    /// It is computed after every evolution with observers, so in one pass
+    /// over the cells; the extremes of an empty or blown up universe are 0
+    pub fn summary(&self) -> StepSummary {
+        let cells = (self.dimensions.row * self.dimensions.col).max(1) as f64;
+        let (mut sum_a, mut sum_b) = (0.0, 0.0);
+        let (mut min_b, mut max_b) = (f64::INFINITY, f64::NEG_INFINITY);
+        for cell in self.universe.iter().flatten() {
+            let (a, b) = (cell.a.to_f64(), cell.b.to_f64());
+            sum_a += a;
+            sum_b += b;
+            min_b = min_b.min(b);
+            max_b = max_b.max(b);
+        }
+        StepSummary {
+            generation: self.generation,
+            mean_a: (sum_a / cells) as f32,
+            mean_b: (sum_b / cells) as f32,
+            min_b: if min_b.is_finite() { min_b as f32 } else { 0.0 },
+            max_b: if max_b.is_finite() { max_b as f32 } else { 0.0 },
+        }
+    }
+
+    /// Same simulation, counting evolutions from `generation`, e.g. to resume
+    /// a previous run
+    pub fn with_generation(mut self, generation: i32) -> Simulation<T> {
+        self.generation = generation;
+        self
+    }
+
+    pub fn parameters(&self) -> Parameters {
+        self.parameters
+    }
+
+    /// Parameters of the cell at `position`, with `f` and `k` scaled by the
+    /// modulation if any, e.g. to pick a point of the phase diagram drawn by
+    /// a gradient of both
+    /// Positions outside of the universe have the parameters of the nearest
+    /// cell
+    pub fn parameters_at(&self, position: Position) -> Parameters {
+        let empty = self.dimensions.row == 0 || self.dimensions.col == 0;
+        let Some(modulation) = self.modulation.as_ref().filter(|_| !empty) else {
+            return self.parameters;
+        };
+        let position = Position {
+            row: position.row.min(self.dimensions.row.saturating_sub(1)),
+            col: position.col.min(self.dimensions.col.saturating_sub(1)),
+        };
+        match &self.factors {
+            Some(factors) if !modulation.is_animated() => modulated(&self.parameters, Some(factors), &position),
+            _ => {
+                let factors = modulation.factors(self.dimensions, self.generation);
+                modulated(&self.parameters, Some(&factors), &position)
+            }
+        }
+    }
+
+    /// Use `parameters` for the next evolutions
+    /// Invalid parameters, or parameters a pulse of the schedule is invalid
+    /// with, are refused and the current ones are kept
+    pub fn set_parameters(&mut self, parameters: Parameters) -> Result<(), SimulationError> {
+        parameters.validate()?;
+        validate_changes(&self.schedule, &self.timeline, self.modulation.as_ref(), parameters)?;
+        if parameters != self.parameters {
+            self.wake_all();
+        }
+        self.parameters = parameters;
+        Ok(())
+    }
+
+    pub fn dimensions(&self) -> Position {
+        self.dimensions
+    }
+
+    /// Current universe, empty while `is_busy`
+    pub fn universe(&self) -> &Universe<T> {
+        &self.universe
+    }
+
+    /// Color map of the current universe, empty while `is_busy`
+    pub fn colored_map(&self) -> &ColoredMap {
+        &self.colored_map
+    }
+
+    /// Whether an evolution taken by `begin_step` is not given back to
+    /// `finish_step` yet, the universe and color map being empty meanwhile
+    pub fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    /// Number of evolutions computed so far
+    pub fn generation(&self) -> i32 {
+        self.generation
+    }
+
+    /// Concentrations at the point (`x`, `y`), interpolated bilinearly
+    /// `x` runs along the columns and `y` along the rows, with the center of
+    /// the cell at `row`, `col` at (`col`, `row`). Points outside of the
+    /// universe take the value of the nearest edge, and every point is empty
+    /// while `is_busy`
+    pub fn sample(&self, x: f32, y: f32) -> Cell<T> {
+        if self.busy || self.dimensions.row == 0 || self.dimensions.col == 0 {
+            return Cell::empty();
+        }
+        let x = x.clamp(0.0, (self.dimensions.col - 1) as f32);
+        let y = y.clamp(0.0, (self.dimensions.row - 1) as f32);
+        let (col, row) = (x.floor() as usize, y.floor() as usize);
+        let next_col = (col + 1).min(self.dimensions.col - 1);
+        let next_row = (row + 1).min(self.dimensions.row - 1);
+        let (tx, ty) = (T::from_f32(x - col as f32), T::from_f32(y - row as f32));
+
+        let lerp = |from: T, to: T, t: T| from + (to - from) * t;
+        let corner = |row: usize, col: usize| self.universe[row][col];
+        let (top_left, top_right) = (corner(row, col), corner(row, next_col));
+        let (bottom_left, bottom_right) = (corner(next_row, col), corner(next_row, next_col));
+        Cell {
+            a: lerp(lerp(top_left.a, top_right.a, tx), lerp(bottom_left.a, bottom_right.a, tx), ty),
+            b: lerp(lerp(top_left.b, top_right.b, tx), lerp(bottom_left.b, bottom_right.b, tx), ty),
+        }
+    }
+
+    /// Change the dimensions of the universe, resampling its cells
+    /// The generation and parameters are kept; the activation times, if
+    /// tracked, start again from the resampled universe
+    /// Fails while `is_busy`, the universe being away
+    pub fn resize(&mut self, dimensions: Position, resampling: Resampling) -> Result<(), SimulationError> {
+        if self.busy {
+            return Err(SimulationError::Busy);
+        }
+        let (old, new) = (self.dimensions, dimensions);
+        // Position in the old universe of the center of a new cell
+        let scale = |index: usize, old: usize, new: usize| {
+            (index as f32 + 0.5) * old as f32 / new as f32 - 0.5
+        };
+
+        let universe: Universe<T> = (0..new.row)
+            .map(|row| {
+                (0..new.col)
+                    .map(|col| {
+                        let (x, y) = (scale(col, old.col, new.col), scale(row, old.row, new.row));
+                        match resampling {
+                            Resampling::Nearest => self.sample(x.round(), y.round()),
+                            Resampling::Bilinear => self.sample(x, y),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        self.colored_map = color_universe(&universe);
+        self.universe = universe;
+        self.dimensions = dimensions;
+        self.factors = None;
+        if let Some(activation) = &mut self.activation {
+            *activation = ActivationMap::new(activation.tracking(), &self.universe, self.generation);
+        }
+        self.wake_all();
+        Ok(())
+    }
+
+    /// Give new concentrations to the cell at `position`
+    /// Positions outside of the universe are ignored. Fails while `is_busy`,
+    /// the universe being away
+    pub fn set_cell(&mut self, position: Position, cell: Cell<T>) -> Result<(), SimulationError> {
+        if self.busy {
+            return Err(SimulationError::Busy);
+        }
+        if let Some(target) = self
+            .universe
+            .get_mut(position.row)
+            .and_then(|row| row.get_mut(position.col))
+        {
+            *target = cell;
+            self.colored_map[position.row][position.col] = color_cell(&cell);
+            if let Some(activity) = &mut self.activity {
+                activity.wake(position);
+            }
+        }
+        Ok(())
+    }
+
+    /// Change the concentrations of every cell in one pass, `update` being
+    /// given the position and the cell, e.g. to apply the couplings of layers
+    /// Only the cells that changed are colored again. If the activity is
+    /// tracked the changes count in those of their tiles, so only the tiles
+    /// changed by at least `epsilon` are woken
+    pub fn update_cells(&mut self, mut update: impl FnMut(Position, &mut Cell<T>)) {
+        for (row, (cells, colors)) in self.universe.iter_mut().zip(self.colored_map.iter_mut()).enumerate() {
+            for (col, (cell, color)) in cells.iter_mut().zip(colors.iter_mut()).enumerate() {
+                let before = *cell;
+                update(Position { row, col }, cell);
+                if *cell == before {
+                    continue;
+                }
+                *color = color_cell(cell);
+                if let Some(activity) = &mut self.activity {
+                    let change = (cell.a - before.a).to_f64().abs().max((cell.b - before.b).to_f64().abs());
+                    activity.add_change(Position { row, col }, change);
+                }
+            }
+        }
+    }
+
+    /// Go back, or forward, to `universe` at `generation` with `parameters`,
+    /// e.g. from a `rewind::RewindBuffer`
+    /// The universe keeps its own dimensions. The observers, bounds, edges,
+    /// timeline, schedule and modulation are kept, as are the activation times up to
+    /// `generation`, and a stopped simulation can evolve again. Fails if the
+    /// parameters are invalid or the rows of `universe` are not all as long
+    pub fn restore(
+        &mut self,
+        parameters: Parameters,
+        generation: i32,
+        universe: Universe<T>,
+    ) -> Result<(), SimulationError> {
+        parameters.validate()?;
+        let dimensions = Position { row: universe.len(), col: universe.first().map_or(0, Vec::len) };
+        check_dimensions(&universe, dimensions)?;
+        let resized = dimensions != self.dimensions;
+        if resized {
+            self.factors = None;
+        }
+        self.parameters = parameters;
+        self.dimensions = dimensions;
+        self.colored_map = color_universe(&universe);
+        self.universe = universe;
+        self.generation = generation;
+        self.stopped = false;
+        self.violation = None;
+        self.balance = None;
+        self.leak = None;
+        self.blowup = None;
+        if let Some(activation) = &mut self.activation {
+            if resized {
+                *activation = ActivationMap::new(activation.tracking(), &self.universe, generation);
+            } else {
+                activation.forget_after(generation);
+                activation.update(&self.universe, generation);
+            }
+        }
+        self.wake_all();
+        Ok(())
+    }
+
+    /// Evolve every tile during the next evolution, if the activity is tracked
+    fn wake_all(&mut self) {
+        if let Some(activity) = &mut self.activity {
+            activity.wake_all();
+        }
+    }
+
+    /// Compute one evolution and return the new universe
+    pub fn step(&mut self) -> &Universe<T> {
+        let step = self.begin_step().compute();
+        self.finish_step(step)
+    }
+
+    /// Take the next evolution out of the simulation, to compute it
+    /// elsewhere, e.g. on another thread, and give it back to `finish_step`
+    /// The generation is counted right away, and until the evolution is given
+    /// back the universe and color map of the simulation are empty: `sample`
+    /// gives empty cells, and `set_cell` and `resize` fail
+    pub fn begin_step(&mut self) -> PendingStep<T> {
+        profile::time(profile::PREPARE, || {
+            if !self.timeline.is_empty() {
+                let parameters = self.timeline.parameters_at(self.generation, self.parameters);
+                if parameters != self.parameters {
+                    self.wake_all();
+                }
+                self.parameters = parameters;
+            }
+            let mut parameters = self.parameters;
+            if !self.schedule.is_empty() {
+                parameters = self.schedule.parameters_at(self.generation, self.parameters);
+                if parameters != self.schedule.parameters_at(self.generation - 1, self.parameters) {
+                    self.wake_all();
+                }
+                if self.schedule.inject(self.generation, &mut self.universe) {
+                    self.colored_map = color_universe(&self.universe);
+                    self.wake_all();
+                }
+            }
+            if let Some(modulation) = &self.modulation {
+                let animated = modulation.is_animated();
+                if animated || self.factors.is_none() {
+                    self.factors = Some(Arc::new(modulation.factors(self.dimensions, self.generation)));
+                    if animated {
+                        self.wake_all();
+                    }
+                }
+            }
+            self.generation += 1;
+            self.busy = true;
+            PendingStep {
+                parameters,
+                dimensions: self.dimensions,
+                boundary: self.boundary,
+                stencil: self.stencil,
+                universe: std::mem::take(&mut self.universe),
+                colored_map: std::mem::take(&mut self.colored_map),
+                activity: self.activity.take(),
+                factors: self.factors.clone(),
+                reaction: self.reaction.clone(),
+                conservation: self.conservation.is_some(),
+            }
+        })
+    }
+
+    /// Put back an evolution taken by `begin_step` once computed, check the
+    /// bounds and call the observers, and return the new universe
+    /// Parameters changed in the meantime are used from the next evolution
+    pub fn finish_step(&mut self, step: EvolvedStep<T>) -> &Universe<T> {
+        profile::time(profile::FINISH, || {
+            self.universe = step.universe;
+            self.colored_map = step.colored_map;
+            self.activity = step.activity;
+            self.busy = false;
+            if step.parameters != self.schedule.parameters_at(self.generation - 1, self.parameters) {
+                self.wake_all();
+            }
+
+            if let (Some(check), Some([before, source, after])) = (self.conservation, step.masses) {
+                let balance = MassBalance { generation: self.generation, before, source, after };
+                if check.is_leak(&balance) {
+                    if check.strict {
+                        self.leak = Some(balance);
+                        self.stopped = true;
+                    } else {
+                        tracing::warn!("mass not conserved: {balance}");
+                    }
+                }
+                self.balance = Some(balance);
+            }
+
+            if let Some(noise) = &self.noise {
+                noise.apply(&mut self.universe, self.generation);
+                if self.symmetry.is_none() {
+                    self.colored_map = color_universe(&self.universe);
+                }
+                self.wake_all();
+            }
+
+            if let Some(symmetry) = self.symmetry {
+                if self.orbits.as_ref().is_none_or(|orbits| orbits.dimensions() != self.dimensions) {
+                    self.orbits = Some(Orbits::new(symmetry, self.dimensions));
+                }
+                if let Some(orbits) = &self.orbits {
+                    orbits.project(&mut self.universe);
+                }
+                self.colored_map = color_universe(&self.universe);
+                self.wake_all();
+            }
+
+            if let Some(check) = self.blowup_check.filter(|check| check.is_due(self.generation)) {
+                self.blowup = check.scan(&self.universe, self.generation, |position| self.parameters_at(position));
+            }
+
+            match self.bounds {
+                Bounds::Unchecked => {}
+                Bounds::Clamp => self.clamp(),
+                Bounds::Strict => {
+                    if let Some(violation) = self.find_violation() {
+                        self.violation = Some(violation);
+                        self.stopped = true;
+                    }
+                }
+            }
+
+            if let Some(activation) = &mut self.activation {
+                activation.update(&self.universe, self.generation);
+            }
+
+            if !self.observers.is_empty() {
+                let summary = self.summary();
+                for observer in &mut self.observers {
+                    if observer(&summary).is_break() {
+                        self.stopped = true;
+                    }
+                }
+            }
+        });
+        &self.universe
+    }
+
+    /// Clamp every concentration to [0,1], NaN becoming 0
+    fn clamp(&mut self) {
+        for (row, colors) in self.universe.iter_mut().zip(self.colored_map.iter_mut()) {
+            for (cell, color) in row.iter_mut().zip(colors.iter_mut()) {
+                if !(in_bounds(cell.a) && in_bounds(cell.b)) {
+                    *cell = Cell { a: clamp_concentration(cell.a), b: clamp_concentration(cell.b) };
+                    *color = color_cell(cell);
+                }
+            }
+        }
+    }
+
+    /// First cell with a concentration out of [0,1]
+    fn find_violation(&self) -> Option<Violation> {
+        self.universe.iter().enumerate().find_map(|(row, cells)| {
+            cells.iter().enumerate().find_map(|(col, cell)| {
+                (!(in_bounds(cell.a) && in_bounds(cell.b))).then_some(Violation {
+                    generation: self.generation,
+                    position: Position { row, col },
+                    cell: cell.cast(),
+                })
+            })
+        })
+    }
+
+    /// Compute `n` evolutions, or fewer if the simulation stops, see
+    /// `is_stopped`, and return the last universe
+    pub fn run(&mut self, n: i32) -> &Universe<T> {
+        for _ in 0..n {
+            if self.stopped {
+                break;
+            }
+            self.step();
+        }
+        &self.universe
+    }
+
+    /// Evolutions of the simulation, one at a time, lending the universes,
+    /// e.g. `while let Some(universe) = steps.next() { … }`
+    pub fn steps(&mut self) -> Steps<'_, T> {
+        Steps { simulation: self }
+    }
+
+    /// Current universe, consuming the simulation
+    pub fn into_universe(self) -> Universe<T> {
+        self.universe
+    }
+}
+
+/// Evolution taken out of a simulation, see `Simulation::begin_step`
+#[derive(Debug)]
+pub struct PendingStep<T: Float = f32> {
+    parameters: Parameters,
+    dimensions: Position,
+    boundary: Boundary,
+    stencil: Stencil,
+    universe: Universe<T>,
+    colored_map: ColoredMap,
+    activity: Option<Activity>,
+    factors: Option<Arc<RateFactors>>,
+    reaction: Option<Arc<dyn Reaction>>,
+    /// Whether to measure the mass balance
+    conservation: bool,
+}
+
+impl<T: Float> PendingStep<T> {
+    /// Compute the evolution
+    pub fn compute(self) -> EvolvedStep<T> {
+        self.compute_with_progress(&mut |_, _| {})
+    }
+
+    /// Compute the evolution, calling `progress` with the rows of the color
+    /// map already evolved, one row of tiles at a time, e.g. to draw them
+    /// while a long evolution goes on
+    pub fn compute_with_progress(mut self, progress: &mut dyn FnMut(Range<usize>, &ColoredMap)) -> EvolvedStep<T> {
+        profile::time(profile::DIFFUSION, move || {
+            let kinetics = Kinetics {
+                factors: self.factors.as_deref(),
+                reaction: self.reaction.as_deref(),
+                stencil: self.stencil,
+            };
+            let before = self
+                .conservation
+                .then(|| [total_mass(&self.universe), reaction_source(&self.parameters, kinetics, &self.universe)]);
+            let universe = match &mut self.activity {
+                Some(activity) => {
+                    if self.stencil == Stencil::Conservative {
+                        activity.wake_all();
+                    }
+                    evolution_universe_active_with_kinetics(
+                        &self.parameters,
+                        &self.dimensions,
+                        self.boundary,
+                        self.universe,
+                        &mut self.colored_map,
+                        activity,
+                        kinetics,
+                        progress,
+                    )
+                }
+                None => evolution_universe_with_kinetics(
+                    &self.parameters,
+                    &self.dimensions,
+                    self.boundary,
+                    self.universe,
+                    &mut self.colored_map,
+                    kinetics,
+                    progress,
+                ),
+            };
+            EvolvedStep {
+                parameters: self.parameters,
+                masses: before.map(|[before, source]| [before, source, total_mass(&universe)]),
+                universe,
+                colored_map: self.colored_map,
+                activity: self.activity,
+            }
+        })
+    }
+}
+
+/// Evolution computed by `PendingStep::compute`, to give back to
+/// `Simulation::finish_step`
+#[derive(Debug)]
+pub struct EvolvedStep<T: Float = f32> {
+    /// Parameters the evolution was computed with
+    parameters: Parameters,
+    universe: Universe<T>,
+    colored_map: ColoredMap,
+    activity: Option<Activity>,
+    /// Total A+B before the evolution, its change due to the reaction terms
+    /// and the total after, if measured
+    masses: Option<[f64; 3]>,
+}
+
+/// Check that the pulses of `schedule` are valid, and the rates scaled by
+/// `modulation` stay in [0,1], with `base` and with the parameters of
+/// `timeline` at each of its keyframes, and so in between
+fn validate_changes(
+    schedule: &Schedule,
+    timeline: &Timeline,
+    modulation: Option<&Modulation>,
+    base: Parameters,
+) -> Result<(), SimulationError> {
+    std::iter::once(base)
+        .chain(timeline.generations().map(|generation| timeline.parameters_at(generation, base)))
+        .try_for_each(|parameters| {
+            schedule.validate(parameters)?;
+            let Some(modulation) = modulation else {
+                return Ok(());
+            };
+            std::iter::once(parameters)
+                .chain(schedule.pulses.iter().map(|pulse| pulse.apply(parameters)))
+                .try_for_each(|parameters| modulation.validate(parameters))
+        })
+}
+
+/// Whether `value` is a concentration in [0,1]
+fn in_bounds<T: Float>(value: T) -> bool {
+    T::from_f32(0.0) <= value && value <= T::from_f32(1.0)
+}
+
+/// `value` clamped to [0,1], NaN becoming 0
+fn clamp_concentration<T: Float>(value: T) -> T {
+    let (zero, one) = (T::from_f32(0.0), T::from_f32(1.0));
+    if value.is_nan() || value < zero {
+        zero
+    } else if value > one {
+        one
+    } else {
+        value
+    }
+}
+
+/// Evolutions of a simulation, see `Simulation::steps`
+/// Not an `Iterator`: every universe is borrowed from the simulation until the
+/// next evolution, so none is copied
+pub struct Steps<'a, T: Float = f32> {
+    simulation: &'a mut Simulation<T>,
+}
+
+impl<'a, T: Float> Steps<'a, T> {
+    /// Simulation being evolved, e.g. to read its generation or color map
+    pub fn simulation(&self) -> &Simulation<T> {
+        self.simulation
+    }
+
+    /// Compute the next evolution and return its universe
+    /// Returns `None` once the simulation stopped: an observer asked to stop,
+    /// a concentration left [0,1] in `Bounds::Strict` mode, or the mass
+    /// leaked with a strict conservation check
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&Universe<T>> {
+        if self.simulation.is_stopped() {
+            return None;
+        }
+        Some(self.simulation.step())
+    }
+}
@@ -0,0 +1,55 @@
+/// Deterministic runs
+/// A deterministic run computes bit for bit the same universes on every
+/// platform and with any number of threads, so that its `hash::exact_hash`
+/// can be recorded once and checked anywhere:
+///
+/// - the initial universe is drawn from `PortableRng`, whose stream is fixed
+///   by its algorithm, unlike that of `StdRng`, which may change with the
+///   platform or the version of `rand`
+/// - the cells of an evolution are computed in a fixed order by one thread,
+///   and the threads of the sweeps and textures each compute whole runs of
+///   their own, so no sum depends on how the work is split
+/// - the evolution only adds, subtracts and multiplies, which IEEE 754 rounds
+///   the same everywhere, and Rust never contracts them into fused
+///   multiply-adds or reorders them as fast-math would
+///
+/// What relies on the trigonometry of the platform, whose rounding differs
+/// between math libraries, is refused by `check`: modulation fields,
+/// symmetries and rotated stripes, as well as scripted reaction terms
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::config::Config;
+use crate::initial::InitialCondition;
+use crate::SimulationError;
+
+/// Random generator drawing the same numbers on every platform
+pub type PortableRng = ChaCha8Rng;
+
+/// Portable generator seeded with `seed`
+pub fn portable_rng(seed: u64) -> PortableRng {
+    PortableRng::seed_from_u64(seed)
+}
+
+/// Fails if the run of `config` cannot be computed bit for bit the same on
+/// every platform, naming what prevents it
+/// A generated initial universe also needs a seed, which is not checked here
+/// since a run resumed from a snapshot or replayed does without it
+pub fn check(config: &Config) -> Result<(), SimulationError> {
+    let unsupported = |what: &str| Err(SimulationError::Unsupported(format!("{what} cannot be deterministic")));
+    if config.modulation.is_some() {
+        return unsupported("a modulation field");
+    }
+    if config.reaction.is_some() {
+        return unsupported("a scripted reaction");
+    }
+    if config.symmetry.is_some() || config.initial.symmetry.is_some() {
+        return unsupported("a symmetry");
+    }
+    if let Some(InitialCondition::Stripes { angle, .. }) = config.initial.condition {
+        if angle != 0.0 {
+            return unsupported("rotated stripes");
+        }
+    }
+    Ok(())
+}
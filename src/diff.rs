@@ -0,0 +1,152 @@
+/// Comparison of two universes
+/// Two universes of the same dimensions, e.g. from two saved snapshots of
+/// runs with different backends, seeds or parameters, are compared species
+/// by species: the L2 distance and the largest difference of the cells tell
+/// how far apart they are, and the structural similarity (SSIM) how alike
+/// their patterns look, 1 for identical universes. The SSIM is the mean of
+/// the similarities of windows of `SSIM_WINDOW` cells on each side, half a
+/// window apart, with concentrations ranging over [0, 1]. The differences
+/// can be drawn with `difference_map`
+use serde::{Deserialize, Serialize};
+
+use crate::error::check_dimensions;
+use crate::export::Species;
+use crate::{ColoredMap, Float, Position, SimulationError, Universe};
+
+/// Number of cells on each side of the windows of the SSIM
+pub const SSIM_WINDOW: usize = 8;
+
+/// Constants stabilizing the SSIM of flat windows, for a range of 1
+const SSIM_C1: f64 = 0.01 * 0.01;
+const SSIM_C2: f64 = 0.03 * 0.03;
+
+/// Differences of the concentrations of one species
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpeciesDiff {
+    /// Square root of the sum of the squared differences of the cells
+    pub l2: f64,
+    /// Root mean square of the differences of the cells
+    pub rms: f64,
+    /// Largest difference of a cell
+    pub max: f64,
+    /// Structural similarity, from -1 to 1, which identical universes reach
+    pub ssim: f64,
+}
+
+/// Differences of the A and B concentrations of two universes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+    pub a: SpeciesDiff,
+    pub b: SpeciesDiff,
+}
+
+/// Concentrations of `species` in `universe`, row by row
+fn concentrations<T: Float>(universe: &Universe<T>, species: Species) -> Vec<f64> {
+    let cells = universe.iter().flatten();
+    match species {
+        Species::A => cells.map(|cell| cell.a.to_f64()).collect(),
+        Species::B => cells.map(|cell| cell.b.to_f64()).collect(),
+    }
+}
+
+/// Starts of the windows of the SSIM along a side of `size` cells, the last
+/// one against the end
+fn window_starts(size: usize) -> Vec<usize> {
+    if size <= SSIM_WINDOW {
+        return vec![0];
+    }
+    let last = size - SSIM_WINDOW;
+    let mut starts: Vec<usize> = (0..=last).step_by(SSIM_WINDOW / 2).collect();
+    if starts.last() != Some(&last) {
+        starts.push(last);
+    }
+    starts
+}
+
+/// Mean structural similarity of the fields `first` and `second` of
+/// `dimensions`
+fn ssim(first: &[f64], second: &[f64], dimensions: Position) -> f64 {
+    let mut similarities = Vec::new();
+    for row in window_starts(dimensions.row) {
+        for col in window_starts(dimensions.col) {
+            let rows = row..(row + SSIM_WINDOW).min(dimensions.row);
+            let cols = col..(col + SSIM_WINDOW).min(dimensions.col);
+            let indices = || rows.clone().flat_map(|row| cols.clone().map(move |col| row * dimensions.col + col));
+            let count = indices().count() as f64;
+            let mean_first = indices().map(|index| first[index]).sum::<f64>() / count;
+            let mean_second = indices().map(|index| second[index]).sum::<f64>() / count;
+            let (mut variance_first, mut variance_second, mut covariance) = (0.0, 0.0, 0.0);
+            for index in indices() {
+                let (x, y) = (first[index] - mean_first, second[index] - mean_second);
+                variance_first += x * x;
+                variance_second += y * y;
+                covariance += x * y;
+            }
+            let (variance_first, variance_second, covariance) =
+                (variance_first / count, variance_second / count, covariance / count);
+            similarities.push(
+                (2.0 * mean_first * mean_second + SSIM_C1) * (2.0 * covariance + SSIM_C2)
+                    / ((mean_first.powi(2) + mean_second.powi(2) + SSIM_C1)
+                        * (variance_first + variance_second + SSIM_C2)),
+            );
+        }
+    }
+    similarities.iter().sum::<f64>() / similarities.len() as f64
+}
+
+/// Differences of `species` between `first` and `second`
+fn compare_species<T: Float, U: Float>(
+    first: &Universe<T>,
+    second: &Universe<U>,
+    species: Species,
+    dimensions: Position,
+) -> SpeciesDiff {
+    let (first, second) = (concentrations(first, species), concentrations(second, species));
+    if first.is_empty() {
+        return SpeciesDiff { ssim: 1.0, ..SpeciesDiff::default() };
+    }
+    let differences = first.iter().zip(&second).map(|(first, second)| (first - second).abs());
+    let (squares, max) = differences.fold((0.0, 0.0f64), |(squares, max), difference| {
+        (squares + difference * difference, max.max(difference))
+    });
+    SpeciesDiff {
+        l2: squares.sqrt(),
+        rms: (squares / first.len() as f64).sqrt(),
+        max,
+        ssim: ssim(&first, &second, dimensions),
+    }
+}
+
+/// Compare `first` with `second`, whatever their precisions
+/// Fails if they do not have the same dimensions
+pub fn compare<T: Float, U: Float>(first: &Universe<T>, second: &Universe<U>) -> Result<Comparison, SimulationError> {
+    let dimensions = Position { row: first.len(), col: first.first().map_or(0, Vec::len) };
+    check_dimensions(first, dimensions)?;
+    check_dimensions(second, dimensions)?;
+    Ok(Comparison {
+        a: compare_species(first, second, Species::A, dimensions),
+        b: compare_species(first, second, Species::B, dimensions),
+    })
+}
+
+/// Color map of the differences of `species` between `first` and `second`,
+/// from 0 where they are equal to 1 where they differ the most, to be saved
+/// like any other color map
+/// Fails if they do not have the same dimensions
+pub fn difference_map<T: Float, U: Float>(
+    first: &Universe<T>,
+    second: &Universe<U>,
+    species: Species,
+) -> Result<ColoredMap, SimulationError> {
+    let dimensions = Position { row: first.len(), col: first.first().map_or(0, Vec::len) };
+    check_dimensions(first, dimensions)?;
+    check_dimensions(second, dimensions)?;
+    let (first, second) = (concentrations(first, species), concentrations(second, species));
+    let differences: Vec<f64> = first.iter().zip(&second).map(|(first, second)| (first - second).abs()).collect();
+    let max = differences.iter().copied().fold(0.0, f64::max);
+    let scale = if max > 0.0 { 1.0 / max } else { 0.0 };
+    Ok(differences
+        .chunks(dimensions.col.max(1))
+        .map(|row| row.iter().map(|difference| (difference * scale) as f32).collect())
+        .collect())
+}
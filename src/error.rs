@@ -0,0 +1,221 @@
+/// Errors of the library
+/// `SimulationError` is returned by the constructors of `Simulation`, the
+/// loaders and the exporters. The errors of the file formats (`ConfigError`,
+/// `SnapshotError`, `ReplayError`) convert into it, so they can all be
+/// handled in one place
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use image::ImageError;
+
+use crate::config::ConfigError;
+use crate::layers::Coupling;
+use crate::region::Rect;
+use crate::replay::ReplayError;
+use crate::snapshot::SnapshotError;
+use crate::{ParametersError, Position, Universe, Violation};
+
+/// Error of the simulation library
+#[derive(Debug)]
+pub enum SimulationError {
+    InvalidParameters(ParametersError),
+    /// A universe does not have the expected number of rows and columns
+    DimensionMismatch { expected: Position, found: Position },
+    /// A region does not lie inside a universe of the given dimensions
+    RegionOutOfBounds { region: Rect, dimensions: Position },
+    /// A coupling refers to a layer beyond the given number of layers
+    InvalidCoupling { coupling: Coupling, layers: usize },
+    /// The settings of a texture cannot produce one
+    InvalidTexture(String),
+    /// A mesh needs a universe of at least 2 rows and 2 columns
+    InvalidMesh(Position),
+    /// The parameters of a Lenia simulation give no kernel or growth
+    InvalidLenia(String),
+    /// The factors or the field of a modulation are invalid
+    InvalidModulation(String),
+    /// The tiles of an adaptive grid do not fit its universe, see `adaptive`
+    InvalidAdaptive(String),
+    /// The settings of an unbounded universe are invalid, see `chunked`
+    InvalidChunks(String),
+    /// The path of a bifurcation diagram is invalid, see `bifurcation`
+    InvalidBifurcation(String),
+    /// The settings of a generated initial state are invalid, see
+    /// `initial::InitialCondition`
+    InvalidInitial(String),
+    /// An export trigger is never checked or exports nothing, see `triggers`
+    InvalidTrigger(String),
+    /// The amplitude of the noise is negative or not finite, see `noise`
+    InvalidNoise(String),
+    /// The settings of a benchmark time nothing, see `bench`
+    InvalidBench(String),
+    /// The margin or interval of a blow-up check is negative, see `blowup`
+    InvalidBlowup(String),
+    /// A pulse never happens or its values are invalid, see `schedule`
+    InvalidSchedule(String),
+    /// The template of the names of the runs is invalid, see `naming`
+    InvalidName(String),
+    /// An expression of the reaction terms does not compile or evaluate
+    InvalidReaction(String),
+    /// The edges of a graph are invalid
+    InvalidGraph(String),
+    /// The threshold of the binary color map is not in [0,1], see `colormap`
+    InvalidColormap(String),
+    /// The concentrations or the jitter of the brush are not in [0,1], see
+    /// `session::Brush`
+    InvalidBrush(String),
+    /// Something is done every `interval` generations with an interval
+    /// below 1, so it would never be done
+    InvalidInterval(String),
+    /// A surface or a graph does not have one cell, or position, per vertex
+    /// of its mesh or node
+    VertexMismatch { expected: usize, found: usize },
+    Io(io::Error),
+    Image(ImageError),
+    /// The contents of a file are invalid
+    Format(String),
+    /// A concentration left [0,1] in `Bounds::Strict` mode
+    OutOfBounds(Violation),
+    /// The requested configuration is not supported by this build
+    Unsupported(String),
+    /// The universe is away while an evolution taken by
+    /// `Simulation::begin_step` is not given back
+    Busy,
+    /// Reading the file at the given path failed
+    Load(PathBuf, Box<SimulationError>),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::InvalidParameters(error) => write!(f, "invalid parameters: {error}"),
+            SimulationError::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a universe of {}x{} cells, found {}x{}",
+                expected.row, expected.col, found.row, found.col
+            ),
+            SimulationError::RegionOutOfBounds { region, dimensions } => write!(
+                f,
+                "the region of {}x{} cells at ({}, {}) does not fit in a universe of {}x{} cells",
+                region.dimensions.row,
+                region.dimensions.col,
+                region.origin.row,
+                region.origin.col,
+                dimensions.row,
+                dimensions.col
+            ),
+            SimulationError::InvalidCoupling { coupling, layers } => {
+                write!(f, "the coupling of {coupling} refers to a missing layer, there are {layers}")
+            }
+            SimulationError::InvalidTexture(error) => write!(f, "{error}"),
+            SimulationError::InvalidMesh(dimensions) => write!(
+                f,
+                "a mesh needs a universe of at least 2x2 cells, found {}x{}",
+                dimensions.row, dimensions.col
+            ),
+            SimulationError::InvalidLenia(error) => write!(f, "invalid Lenia parameters: {error}"),
+            SimulationError::InvalidModulation(error) => write!(f, "invalid modulation: {error}"),
+            SimulationError::InvalidAdaptive(error) => write!(f, "invalid adaptive grid: {error}"),
+            SimulationError::InvalidChunks(error) => write!(f, "invalid chunks: {error}"),
+            SimulationError::InvalidBifurcation(error) => write!(f, "invalid bifurcation path: {error}"),
+            SimulationError::InvalidInitial(error) => write!(f, "invalid initial condition: {error}"),
+            SimulationError::InvalidTrigger(error) => write!(f, "invalid export trigger: {error}"),
+            SimulationError::InvalidNoise(error) => write!(f, "invalid noise: {error}"),
+            SimulationError::InvalidBench(error) => write!(f, "invalid benchmark: {error}"),
+            SimulationError::InvalidBlowup(error) => write!(f, "invalid blow-up check: {error}"),
+            SimulationError::InvalidSchedule(error) => write!(f, "invalid schedule: {error}"),
+            SimulationError::InvalidName(error) => write!(f, "invalid run name: {error}"),
+            SimulationError::InvalidReaction(error) => write!(f, "invalid reaction: {error}"),
+            SimulationError::InvalidGraph(error) => write!(f, "invalid graph: {error}"),
+            SimulationError::InvalidColormap(error) => write!(f, "invalid color map: {error}"),
+            SimulationError::InvalidBrush(error) => write!(f, "invalid brush: {error}"),
+            SimulationError::InvalidInterval(error) => write!(f, "invalid interval: {error}"),
+            SimulationError::VertexMismatch { expected, found } => {
+                write!(f, "expected {expected} values, one per vertex or node, found {found}")
+            }
+            SimulationError::Io(error) => write!(f, "{error}"),
+            SimulationError::Image(error) => write!(f, "{error}"),
+            SimulationError::Format(error) => write!(f, "{error}"),
+            SimulationError::OutOfBounds(violation) => write!(f, "{violation}"),
+            SimulationError::Unsupported(error) => write!(f, "{error}"),
+            SimulationError::Busy => write!(f, "the universe is away while an evolution is computed"),
+            SimulationError::Load(path, error) => {
+                write!(f, "could not load {}: {error}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+/// Fails if `interval`, the generations between two of `what`, is below 1
+pub(crate) fn check_interval(what: &str, interval: i32) -> Result<(), SimulationError> {
+    if interval < 1 {
+        return Err(SimulationError::InvalidInterval(format!(
+            "{what} must be at least 1 generation apart, found {interval}"
+        )));
+    }
+    Ok(())
+}
+
+impl From<ParametersError> for SimulationError {
+    fn from(error: ParametersError) -> Self {
+        SimulationError::InvalidParameters(error)
+    }
+}
+
+impl From<io::Error> for SimulationError {
+    fn from(error: io::Error) -> Self {
+        SimulationError::Io(error)
+    }
+}
+
+impl From<ImageError> for SimulationError {
+    fn from(error: ImageError) -> Self {
+        SimulationError::Image(error)
+    }
+}
+
+impl From<ConfigError> for SimulationError {
+    fn from(error: ConfigError) -> Self {
+        match error {
+            ConfigError::Io(error) => SimulationError::Io(error),
+            ConfigError::UnknownFormat(_) => SimulationError::Unsupported(error.to_string()),
+            _ => SimulationError::Format(error.to_string()),
+        }
+    }
+}
+
+impl From<SnapshotError> for SimulationError {
+    fn from(error: SnapshotError) -> Self {
+        match error {
+            SnapshotError::Io(error) => SimulationError::Io(error),
+            SnapshotError::JsonUnsupported => SimulationError::Unsupported(error.to_string()),
+            _ => SimulationError::Format(error.to_string()),
+        }
+    }
+}
+
+impl From<ReplayError> for SimulationError {
+    fn from(error: ReplayError) -> Self {
+        match error {
+            #[cfg(feature = "fs")]
+            ReplayError::Io(error) => SimulationError::Io(error),
+            ReplayError::Ron(_) => SimulationError::Format(error.to_string()),
+            ReplayError::Simulation(error) => error,
+        }
+    }
+}
+
+/// Check that `universe` has `expected` rows of `expected` columns
+pub fn check_dimensions<T>(universe: &Universe<T>, expected: Position) -> Result<(), SimulationError> {
+    let ragged = universe.iter().find(|row| row.len() != expected.col);
+    if universe.len() != expected.row || ragged.is_some() {
+        let found = Position {
+            row: universe.len(),
+            col: ragged.or(universe.first()).map_or(0, |row| row.len()),
+        };
+        return Err(SimulationError::DimensionMismatch { expected, found });
+    }
+    Ok(())
+}
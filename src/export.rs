@@ -0,0 +1,323 @@
+/// Export of simulation results
+/// Functions writing to files require the `fs` feature, the others work on
+/// any writer
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+use std::io::Write;
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+use image::{ImageBuffer, Luma, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::Colormap;
+use crate::error::{check_dimensions, check_interval};
+use crate::{Boundary, Cell, ColoredMap, Position, SimulationError, Universe};
+
+/// Grayscale image with 16 bits per pixel
+pub type HeightMap = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+/// Image from a color map
+/// Each cell becomes one pixel, colored with `colormap`
+pub fn colored_map_to_image(colored_map: &ColoredMap, colormap: Colormap) -> RgbImage {
+    let rows = colored_map.len() as u32;
+    let cols = colored_map.first().map_or(0, |row| row.len()) as u32;
+
+    RgbImage::from_fn(cols, rows, |c, r| {
+        Rgb(colormap.color(colored_map[r as usize][c as usize]))
+    })
+}
+
+/// Save a color map as an image file
+/// The format (e.g. PNG) is deduced from the extension of `path`
+#[cfg(feature = "fs")]
+pub fn save_colored_map(colored_map: &ColoredMap, colormap: Colormap, path: &Path) -> Result<(), SimulationError> {
+    Ok(colored_map_to_image(colored_map, colormap).save(path)?)
+}
+
+/// Settings of a frame sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrameSequenceConfig {
+    /// Directory where the frames are written
+    pub directory: PathBuf,
+    /// A frame is written every `interval` generations
+    pub interval: i32,
+}
+
+impl Default for FrameSequenceConfig {
+    fn default() -> Self {
+        FrameSequenceConfig {
+            directory: PathBuf::from("frames"),
+            interval: 10,
+        }
+    }
+}
+
+impl FrameSequenceConfig {
+    /// Fails if the interval is below 1
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        check_interval("frames", self.interval)
+    }
+}
+
+/// Writer of numbered PNG frames
+/// Frames are named `frame_000001.png`, `frame_000002.png`, … after the
+/// generation divided by the interval, so they can be assembled into an
+/// animation and a resumed run continues the numbering
+#[cfg(feature = "fs")]
+pub struct FrameSequence {
+    config: FrameSequenceConfig,
+    colormap: Colormap,
+}
+
+#[cfg(feature = "fs")]
+impl FrameSequence {
+    /// Create the frame directory if needed
+    /// Fails if the configuration is invalid or the directory cannot be created
+    pub fn new(config: FrameSequenceConfig, colormap: Colormap) -> Result<FrameSequence, SimulationError> {
+        config.validate()?;
+        fs::create_dir_all(&config.directory)?;
+        Ok(FrameSequence { config, colormap })
+    }
+
+    /// Whether a frame has to be written after computing `generation`
+    pub fn is_due(&self, generation: i32) -> bool {
+        generation % self.config.interval == 0
+    }
+
+    /// Write `colored_map` as the frame of `generation` and return its path
+    pub fn write(&self, generation: i32, colored_map: &ColoredMap) -> Result<PathBuf, SimulationError> {
+        let frame = generation / self.config.interval;
+        let path = self.config.directory.join(format!("frame_{frame:06}.png"));
+        save_colored_map(colored_map, self.colormap, &path)?;
+        Ok(path)
+    }
+}
+
+/// Chemical species of the simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Species {
+    A,
+    B,
+}
+
+impl Species {
+    /// Concentration of this species in `cell`
+    pub fn concentration(&self, cell: &Cell) -> f32 {
+        match self {
+            Species::A => cell.a,
+            Species::B => cell.b,
+        }
+    }
+
+    /// Concentration of this species in `cell`, to be changed
+    pub fn concentration_mut<'a>(&self, cell: &'a mut Cell) -> &'a mut f32 {
+        match self {
+            Species::A => &mut cell.a,
+            Species::B => &mut cell.b,
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    fn suffix(&self) -> &'static str {
+        match self {
+            Species::A => "a",
+            Species::B => "b",
+        }
+    }
+}
+
+/// Write the concentrations of `species` as CSV
+/// One line per row of the universe, with the values of its columns
+/// separated by commas
+pub fn write_csv(universe: &Universe, species: Species, mut writer: impl Write) -> Result<(), SimulationError> {
+    shape(universe)?;
+    for row in universe {
+        let line: Vec<String> = row
+            .iter()
+            .map(|cell| species.concentration(cell).to_string())
+            .collect();
+        writeln!(writer, "{}", line.join(","))?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// Rows and columns of `universe`, checking that all its rows have the same
+/// length
+fn shape(universe: &Universe) -> Result<Position, SimulationError> {
+    let dimensions = Position {
+        row: universe.len(),
+        col: universe.first().map_or(0, |row| row.len()),
+    };
+    check_dimensions(universe, dimensions)?;
+    Ok(dimensions)
+}
+
+/// Write the concentrations of `species` as a NumPy `.npy` array
+/// The array has `float32` values and shape `(rows, cols)`
+pub fn write_npy(universe: &Universe, species: Species, mut writer: impl Write) -> Result<(), SimulationError> {
+    let Position { row: rows, col: cols } = shape(universe)?;
+
+    // The header is padded with spaces so that the data starts at a multiple
+    // of 64 bytes, as required by the format
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({rows}, {cols}), }}"
+    );
+    let unpadded = 6 + 2 + 2 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY")?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for cell in universe.iter().flatten() {
+        writer.write_all(&species.concentration(cell).to_le_bytes())?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// Save the concentrations of `species` as CSV, see `write_csv`
+#[cfg(feature = "fs")]
+pub fn save_csv(universe: &Universe, species: Species, path: &Path) -> Result<(), SimulationError> {
+    write_csv(universe, species, BufWriter::new(File::create(path)?))
+}
+
+/// Save the concentrations of `species` as a NumPy `.npy` array, see
+/// `write_npy`
+#[cfg(feature = "fs")]
+pub fn save_npy(universe: &Universe, species: Species, path: &Path) -> Result<(), SimulationError> {
+    write_npy(universe, species, BufWriter::new(File::create(path)?))
+}
+
+/// Save the concentrations of both species
+/// Given `path` = `dir/fields.npy`, write `dir/fields_a.npy` and
+/// `dir/fields_b.npy`. The format is CSV for a `.csv` extension and NumPy
+/// otherwise. Returns the paths written
+#[cfg(feature = "fs")]
+pub fn save_fields(universe: &Universe, path: &Path) -> Result<[PathBuf; 2], SimulationError> {
+    let csv = path.extension().is_some_and(|extension| extension == "csv");
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("fields");
+    let extension = if csv { "csv" } else { "npy" };
+
+    let paths = [Species::A, Species::B].map(|species| {
+        path.with_file_name(format!("{stem}_{}.{extension}", species.suffix()))
+    });
+    for (species, path) in [Species::A, Species::B].into_iter().zip(&paths) {
+        if csv {
+            save_csv(universe, species, path)?;
+        } else {
+            save_npy(universe, species, path)?;
+        }
+    }
+    Ok(paths)
+}
+
+/// Settings of a normal map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NormalMapConfig {
+    /// Image file where the normal map is saved
+    pub path: PathBuf,
+    /// Height of the surface between the lowest and highest concentrations,
+    /// in cells; the larger, the steeper the slopes
+    pub strength: f32,
+}
+
+impl Default for NormalMapConfig {
+    fn default() -> Self {
+        NormalMapConfig {
+            path: PathBuf::from("normal_map.png"),
+            strength: 4.0,
+        }
+    }
+}
+
+/// Concentrations of `species` rescaled from their smallest to their largest
+/// value to [0,1], 0 everywhere if they are all the same
+pub(crate) fn heights(universe: &Universe, species: Species) -> Result<Vec<Vec<f32>>, SimulationError> {
+    shape(universe)?;
+    let (min, max) = universe
+        .iter()
+        .flatten()
+        .map(|cell| species.concentration(cell))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)));
+    let range = if max > min { max - min } else { f32::INFINITY };
+    Ok(universe
+        .iter()
+        .map(|row| row.iter().map(|cell| (species.concentration(cell) - min) / range).collect())
+        .collect())
+}
+
+/// Height map of the concentrations of `species`
+/// The lowest concentration is black and the highest white, with 65536
+/// levels in between, so the map can displace a surface in a 3D engine
+pub fn height_map(universe: &Universe, species: Species) -> Result<HeightMap, SimulationError> {
+    let heights = heights(universe, species)?;
+    let cols = heights.first().map_or(0, |row| row.len()) as u32;
+    Ok(HeightMap::from_fn(cols, heights.len() as u32, |c, r| {
+        Luma([(heights[r as usize][c as usize] * u16::MAX as f32).round() as u16])
+    }))
+}
+
+/// Tangent-space normal map of the surface whose height map is that of
+/// `species`, see `height_map`
+/// The slopes are the differences between the neighbours of every cell
+/// divided by the distance between them: central differences, across the
+/// edges of a periodic universe, and one-sided differences at the edges of a
+/// closed one. The normals are written with red to the right and green up,
+/// as expected by OpenGL and most engines
+pub fn normal_map(
+    universe: &Universe,
+    species: Species,
+    strength: f32,
+    boundary: Boundary,
+) -> Result<RgbImage, SimulationError> {
+    let heights = heights(universe, species)?;
+    let rows = heights.len();
+    let cols = heights.first().map_or(0, |row| row.len());
+    // Index of the neighbour of `index` in the direction of `offset`
+    let neighbour = |index: usize, offset: isize, count: usize| match boundary {
+        Boundary::Closed => index.saturating_add_signed(offset).min(count - 1),
+        Boundary::Periodic => (index as isize + offset).rem_euclid(count as isize) as usize,
+    };
+    // Difference between the neighbours of `index` over the distance between
+    // them, shorter at the edges of a closed universe
+    let slope = |before: f32, after: f32, index: usize, count: usize| match boundary {
+        Boundary::Closed => (after - before) / (neighbour(index, 1, count) - neighbour(index, -1, count)).max(1) as f32,
+        Boundary::Periodic => (after - before) / 2.0,
+    };
+
+    Ok(RgbImage::from_fn(cols as u32, rows as u32, |c, r| {
+        let (r, c) = (r as usize, c as usize);
+        let right = slope(heights[r][neighbour(c, -1, cols)], heights[r][neighbour(c, 1, cols)], c, cols);
+        let down = slope(heights[neighbour(r, -1, rows)][c], heights[neighbour(r, 1, rows)][c], r, rows);
+        let normal = [-strength * right, strength * down, 1.0];
+        let length = normal.iter().map(|x| x * x).sum::<f32>().sqrt();
+        Rgb(normal.map(|x| ((x / length * 0.5 + 0.5) * 255.0).round() as u8))
+    }))
+}
+
+/// Save the height map of `species` as a 16-bit image file, see
+/// `height_map`
+/// The format must support 16 bits per pixel, e.g. PNG
+#[cfg(feature = "fs")]
+pub fn save_height_map(universe: &Universe, species: Species, path: &Path) -> Result<(), SimulationError> {
+    Ok(height_map(universe, species)?.save(path)?)
+}
+
+/// Save the normal map of `species` to `config.path`, see `normal_map`
+#[cfg(feature = "fs")]
+pub fn save_normal_map(
+    universe: &Universe,
+    species: Species,
+    config: &NormalMapConfig,
+    boundary: Boundary,
+) -> Result<(), SimulationError> {
+    Ok(normal_map(universe, species, config.strength, boundary)?.save(&config.path)?)
+}
@@ -0,0 +1,214 @@
+/// Precision of the concentrations
+/// Universes store `f32` concentrations by default. Long runs can use `f64`
+/// instead, e.g. `Simulation::<f64>::random(..)`, to accumulate less rounding
+/// error. Very large universes can store them in half the memory as half
+/// floats, `F16`; the evolution is still computed in `f32` and only its
+/// result is rounded to the storage. Parameters, colors and the file formats
+/// stay `f32`
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+
+/// Floating point type of the concentrations, `f32`, `f64` or `F16`
+pub trait Float:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + AddAssign
+    + SubAssign
+{
+    /// Type the evolution is computed with
+    type Compute: Float<Compute = Self::Compute>;
+
+    fn from_f32(value: f32) -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn to_f32(self) -> f32;
+    fn to_f64(self) -> f64;
+    fn powf(self, n: Self) -> Self;
+    fn floor(self) -> Self;
+    fn is_nan(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($float:ty) => {
+        impl Float for $float {
+            type Compute = $float;
+
+            fn from_f32(value: f32) -> Self {
+                value as $float
+            }
+
+            fn from_f64(value: f64) -> Self {
+                value as $float
+            }
+
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn powf(self, n: Self) -> Self {
+                <$float>::powf(self, n)
+            }
+
+            fn floor(self) -> Self {
+                <$float>::floor(self)
+            }
+
+            fn is_nan(self) -> bool {
+                <$float>::is_nan(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+/// Half float, with 11 significant bits and 5 bits of exponent
+/// Arithmetic goes through `f32`, rounding the result to the nearest half
+/// float
+#[derive(Debug, Clone, Copy, Default)]
+pub struct F16(u16);
+
+impl F16 {
+    /// Bits of the half float, e.g. to upload it as an `R16Float` texture
+    pub fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u16) -> F16 {
+        F16(bits)
+    }
+
+    /// Nearest half float to `value`, ties to even; values too large become
+    /// infinite
+    fn from_f32_value(value: f32) -> F16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x007f_ffff;
+        if exponent == 0xff {
+            let nan = if mantissa != 0 { 0x0200 } else { 0 };
+            return F16(sign | 0x7c00 | nan);
+        }
+
+        // Exponent of the half float, and number of bits of the mantissa
+        // dropped, more for subnormal half floats
+        let exponent = exponent - 127 + 15;
+        if exponent >= 0x1f {
+            return F16(sign | 0x7c00);
+        }
+        let (mantissa, shift, base) = if exponent > 0 {
+            (mantissa, 13, (exponent as u32) << 10)
+        } else if exponent >= -10 {
+            (mantissa | 0x0080_0000, (14 - exponent) as u32, 0)
+        } else {
+            return F16(sign);
+        };
+        let half = base | (mantissa >> shift);
+        let remainder = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round_up = remainder > halfway || (remainder == halfway && half & 1 == 1);
+        // A carry out of the mantissa correctly increments the exponent
+        F16(sign | (half + round_up as u32) as u16)
+    }
+
+    fn to_f32_value(self) -> f32 {
+        let sign = ((self.0 & 0x8000) as u32) << 16;
+        let exponent = ((self.0 >> 10) & 0x1f) as u32;
+        let mantissa = (self.0 & 0x03ff) as u32;
+        match exponent {
+            0 => {
+                let value = mantissa as f32 * 2f32.powi(-24);
+                if sign == 0 { value } else { -value }
+            }
+            0x1f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13)),
+            _ => f32::from_bits(sign | ((exponent + 127 - 15) << 23) | (mantissa << 13)),
+        }
+    }
+}
+
+impl PartialEq for F16 {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_f32_value() == other.to_f32_value()
+    }
+}
+
+impl PartialOrd for F16 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.to_f32_value().partial_cmp(&other.to_f32_value())
+    }
+}
+
+macro_rules! impl_stored_float {
+    ($float:ty) => {
+        impl Float for $float {
+            type Compute = f32;
+
+            fn from_f32(value: f32) -> Self {
+                <$float>::from_f32_value(value)
+            }
+
+            fn from_f64(value: f64) -> Self {
+                <$float>::from_f32_value(value as f32)
+            }
+
+            fn to_f32(self) -> f32 {
+                self.to_f32_value()
+            }
+
+            fn to_f64(self) -> f64 {
+                self.to_f32_value() as f64
+            }
+
+            fn powf(self, n: Self) -> Self {
+                <$float>::from_f32_value(self.to_f32_value().powf(n.to_f32_value()))
+            }
+
+            fn floor(self) -> Self {
+                <$float>::from_f32_value(self.to_f32_value().floor())
+            }
+
+            fn is_nan(self) -> bool {
+                self.to_f32_value().is_nan()
+            }
+        }
+
+        impl_stored_operator!($float, Add, add, AddAssign, add_assign, +);
+        impl_stored_operator!($float, Sub, sub, SubAssign, sub_assign, -);
+        impl_stored_operator!($float, Mul, mul, MulAssign, mul_assign, *);
+        impl_stored_operator!($float, Div, div, DivAssign, div_assign, /);
+    };
+}
+
+macro_rules! impl_stored_operator {
+    ($float:ty, $trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $operator:tt) => {
+        impl std::ops::$trait for $float {
+            type Output = $float;
+
+            fn $method(self, other: $float) -> $float {
+                <$float>::from_f32_value(self.to_f32_value() $operator other.to_f32_value())
+            }
+        }
+
+        impl std::ops::$assign_trait for $float {
+            fn $assign_method(&mut self, other: $float) {
+                *self = *self $operator other;
+            }
+        }
+    };
+}
+
+impl_stored_float!(F16);
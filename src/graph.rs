@@ -0,0 +1,418 @@
+/// Reaction–diffusion on graphs
+/// A graph simulation evolves one cell per node of a network, whose
+/// neighbours are the nodes joined to it by an edge. Diffusion follows the
+/// random walk Laplacian of the weighted graph: a node gives away `d_a` and
+/// `d_b` of its concentrations and receives the same share of the mean of its
+/// neighbours weighted by their edges, as a cell of the grid does with its
+/// eight neighbours; a lattice whose adjacent cells are joined by edges of
+/// weight 4 and diagonal ones of weight 1 gives the shares of the grid. The
+/// reactions are those of `Simulation`, and `SurfaceSimulation` evolves the
+/// graph of a mesh.
+/// Graphs have no position, so they are drawn with a layout given by the
+/// user or computed by a force-directed algorithm
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::BufRead;
+#[cfg(feature = "fs")]
+use std::io::BufReader;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use image::{Rgb, RgbImage};
+use rand::Rng;
+
+use crate::colormap::Colormap;
+use crate::core::gray_scott;
+use crate::mesh::Mesh;
+use crate::{color_cell, Cell, Parameters, SimulationError};
+
+/// Color behind a drawn graph
+const BACKGROUND: [u8; 3] = [40, 40, 40];
+/// Color of the edges of a drawn graph
+const EDGE_COLOR: [u8; 3] = [110, 110, 110];
+
+/// Concentrations of `nodes` nodes with no A nor B but in `n` random nodes
+/// drawn from `rng`, which have both A and B
+/// Same as `initialize_universe_with_rng` for a graph
+pub fn initialize_graph<R: Rng + ?Sized>(nodes: usize, n: usize, rng: &mut R) -> Vec<Cell> {
+    let mut cells = vec![Cell::empty(); nodes];
+    for node in rand::seq::index::sample(rng, nodes, n.min(nodes)) {
+        cells[node] = Cell { a: 1.0, b: 1.0 };
+    }
+    cells
+}
+
+/// Undirected graph with weighted edges
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Graph {
+    /// Neighbours of every node and the weights of their edges, those of
+    /// node `n` at `neighbours[offsets[n]..offsets[n + 1]]`
+    offsets: Vec<usize>,
+    neighbours: Vec<u32>,
+    weights: Vec<f32>,
+}
+
+impl Graph {
+    /// Graph of `nodes` nodes joined by `edges`, pairs of nodes and the
+    /// weight of their edge
+    /// The weights of edges joining the same nodes add up. Fails if an edge
+    /// joins a node to itself or to a missing node, or its weight is not
+    /// positive
+    pub fn from_edges(nodes: usize, edges: &[(u32, u32, f32)]) -> Result<Graph, SimulationError> {
+        let mut adjacent = vec![Vec::new(); nodes];
+        for &(from, to, weight) in edges {
+            if from as usize >= nodes || to as usize >= nodes {
+                return Err(SimulationError::InvalidGraph(format!(
+                    "the edge {from}-{to} joins a missing node, there are {nodes}"
+                )));
+            }
+            if from == to {
+                return Err(SimulationError::InvalidGraph(format!("the edge {from}-{to} joins a node to itself")));
+            }
+            if weight.is_nan() || weight <= 0.0 || weight.is_infinite() {
+                return Err(SimulationError::InvalidGraph(format!(
+                    "the weight of the edge {from}-{to} must be positive, found {weight}"
+                )));
+            }
+            adjacent[from as usize].push((to, weight));
+            adjacent[to as usize].push((from, weight));
+        }
+
+        let mut graph = Graph { offsets: Vec::with_capacity(nodes + 1), ..Graph::default() };
+        graph.offsets.push(0);
+        for mut node in adjacent {
+            node.sort_unstable_by_key(|(neighbour, _)| *neighbour);
+            for (neighbour, weight) in node {
+                if graph.neighbours.len() > *graph.offsets.last().unwrap()
+                    && graph.neighbours.last() == Some(&neighbour)
+                {
+                    *graph.weights.last_mut().unwrap() += weight;
+                } else {
+                    graph.neighbours.push(neighbour);
+                    graph.weights.push(weight);
+                }
+            }
+            graph.offsets.push(graph.neighbours.len());
+        }
+        Ok(graph)
+    }
+
+    /// Graph of the vertices of `mesh` joined by the edges of its triangles,
+    /// all of weight 1
+    /// Fails if a triangle refers to a vertex the mesh does not have
+    pub fn from_mesh(mesh: &Mesh) -> Result<Graph, SimulationError> {
+        let mut edges: Vec<(u32, u32)> = mesh
+            .triangles
+            .iter()
+            .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+            .filter(|(from, to)| from != to)
+            .map(|(from, to)| (from.min(to), from.max(to)))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        let edges: Vec<_> = edges.into_iter().map(|(from, to)| (from, to, 1.0)).collect();
+        Graph::from_edges(mesh.positions.len(), &edges)
+    }
+
+    /// Read a graph from an edge list
+    /// Every line holds the two nodes of an edge, numbered from 0, and
+    /// optionally its weight, 1 by default. The graph has as many nodes as
+    /// the largest node of an edge, or as given by a `nodes <count>` line.
+    /// Empty lines and those starting with `#` are skipped
+    pub fn read_edges(reader: impl BufRead) -> Result<Graph, SimulationError> {
+        let mut nodes = 0;
+        let mut edges = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let error = |message: &str| SimulationError::Format(format!("line {}: {message}", number + 1));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let node = |field: &str| field.parse::<u32>().map_err(|_| error(&format!("invalid node `{field}`")));
+            match fields[..] {
+                [] => {}
+                [first, ..] if first.starts_with('#') => {}
+                ["nodes", count] => {
+                    let count: usize = count.parse().map_err(|_| error(&format!("invalid count `{count}`")))?;
+                    nodes = nodes.max(count);
+                }
+                [from, to] | [from, to, _] => {
+                    let weight = match fields.get(2) {
+                        Some(weight) => weight.parse().map_err(|_| error(&format!("invalid weight `{weight}`")))?,
+                        None => 1.0,
+                    };
+                    let (from, to) = (node(from)?, node(to)?);
+                    nodes = nodes.max(from.max(to) as usize + 1);
+                    edges.push((from, to, weight));
+                }
+                _ => return Err(error("an edge needs two nodes and an optional weight")),
+            }
+        }
+        Graph::from_edges(nodes, &edges)
+    }
+
+    /// Read a graph from an edge list file, see `read_edges`
+    #[cfg(feature = "fs")]
+    pub fn load(path: &Path) -> Result<Graph, SimulationError> {
+        Graph::read_edges(BufReader::new(File::open(path)?))
+            .map_err(|error| SimulationError::Load(path.to_path_buf(), Box::new(error)))
+    }
+
+    /// Number of nodes
+    pub fn nodes(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Neighbours of `node` and the weights of their edges
+    pub fn neighbours(&self, node: usize) -> impl Iterator<Item = (u32, f32)> + '_ {
+        let range = self.offsets[node]..self.offsets[node + 1];
+        self.neighbours[range.clone()].iter().copied().zip(self.weights[range].iter().copied())
+    }
+
+    /// Every edge once, from its lower node to its higher one
+    pub fn edges(&self) -> impl Iterator<Item = (u32, u32, f32)> + '_ {
+        (0..self.nodes()).flat_map(move |node| {
+            self.neighbours(node)
+                .filter(move |(neighbour, _)| *neighbour as usize > node)
+                .map(move |(neighbour, weight)| (node as u32, neighbour, weight))
+        })
+    }
+}
+
+/// Layout of `graph` in [0,1]² found by the force-directed algorithm of
+/// Fruchterman and Reingold after `iterations`, from random positions drawn
+/// from `rng`
+/// Every pair of nodes repels and the edges pull their nodes together, in
+/// proportion to their weights. Takes time in the square of the number of
+/// nodes, so suits graphs of up to a few thousand nodes
+pub fn force_layout<R: Rng + ?Sized>(graph: &Graph, iterations: usize, rng: &mut R) -> Vec<[f32; 2]> {
+    let nodes = graph.nodes();
+    let mut positions: Vec<[f32; 2]> = (0..nodes).map(|_| [rng.gen(), rng.gen()]).collect();
+    if nodes < 2 {
+        return positions;
+    }
+    let distance = (1.0 / nodes as f32).sqrt();
+    let mean_weight = graph.edges().map(|(_, _, weight)| weight).sum::<f32>() / graph.edges().count().max(1) as f32;
+
+    for iteration in 0..iterations {
+        let temperature = 0.1 * (1.0 - iteration as f32 / iterations as f32);
+        let mut displacements = vec![[0.0f32; 2]; nodes];
+        for i in 0..nodes {
+            for j in i + 1..nodes {
+                let delta = [positions[i][0] - positions[j][0], positions[i][1] - positions[j][1]];
+                let length = delta[0].hypot(delta[1]).max(1e-4);
+                let force = distance * distance / length;
+                for axis in 0..2 {
+                    displacements[i][axis] += delta[axis] / length * force;
+                    displacements[j][axis] -= delta[axis] / length * force;
+                }
+            }
+        }
+        for (from, to, weight) in graph.edges() {
+            let (from, to) = (from as usize, to as usize);
+            let delta = [positions[from][0] - positions[to][0], positions[from][1] - positions[to][1]];
+            let length = delta[0].hypot(delta[1]).max(1e-4);
+            let force = length * length / distance * weight / mean_weight;
+            for axis in 0..2 {
+                displacements[from][axis] -= delta[axis] / length * force;
+                displacements[to][axis] += delta[axis] / length * force;
+            }
+        }
+        for (position, displacement) in positions.iter_mut().zip(&displacements) {
+            let length = displacement[0].hypot(displacement[1]);
+            if length > 0.0 {
+                let step = length.min(temperature) / length;
+                position[0] += displacement[0] * step;
+                position[1] += displacement[1] * step;
+            }
+        }
+    }
+
+    // Fit the layout in [0,1]², keeping its proportions
+    let (min, max) = positions.iter().fold(([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]), |(min, max), p| {
+        ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])])
+    });
+    let extent = (max[0] - min[0]).max(max[1] - min[1]).max(f32::EPSILON);
+    positions.iter().map(|p| [(p[0] - min[0]) / extent, (p[1] - min[1]) / extent]).collect()
+}
+
+/// Read a layout, one node per line with its `x` and `y` separated by
+/// whitespace or a comma, in the order of the nodes
+/// Empty lines and those starting with `#` are skipped
+pub fn read_layout(reader: impl BufRead) -> Result<Vec<[f32; 2]>, SimulationError> {
+    let mut positions = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let error = |message: &str| SimulationError::Format(format!("line {}: {message}", number + 1));
+        let fields: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|f| !f.is_empty()).collect();
+        match fields[..] {
+            [] => {}
+            [first, ..] if first.starts_with('#') => {}
+            [x, y] => {
+                let coordinate =
+                    |field: &str| field.parse::<f32>().map_err(|_| error(&format!("invalid number `{field}`")));
+                positions.push([coordinate(x)?, coordinate(y)?]);
+            }
+            _ => return Err(error("a position needs 2 coordinates")),
+        }
+    }
+    Ok(positions)
+}
+
+/// Read a layout from a file, see `read_layout`
+#[cfg(feature = "fs")]
+pub fn load_layout(path: &Path) -> Result<Vec<[f32; 2]>, SimulationError> {
+    read_layout(BufReader::new(File::open(path)?))
+        .map_err(|error| SimulationError::Load(path.to_path_buf(), Box::new(error)))
+}
+
+/// Image of `size` by `size` pixels of `graph` drawn at `layout`, its nodes
+/// as discs of `radius` pixels colored with `colormap` from their color
+/// values `colors` and its edges as lines
+/// The layout is scaled to fill the image, keeping its proportions. Fails if
+/// the layout or the colors do not have one value per node
+pub fn draw_graph(
+    graph: &Graph,
+    layout: &[[f32; 2]],
+    colors: &[f32],
+    colormap: Colormap,
+    size: u32,
+    radius: f32,
+) -> Result<RgbImage, SimulationError> {
+    for found in [layout.len(), colors.len()] {
+        if found != graph.nodes() {
+            return Err(SimulationError::VertexMismatch { expected: graph.nodes(), found });
+        }
+    }
+    let mut image = RgbImage::from_pixel(size, size, Rgb(BACKGROUND));
+    if layout.is_empty() {
+        return Ok(image);
+    }
+
+    let (min, max) = layout.iter().fold(([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]), |(min, max), p| {
+        ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])])
+    });
+    let extent = (max[0] - min[0]).max(max[1] - min[1]);
+    let margin = radius + 1.0;
+    let scale = if extent > 0.0 { (size as f32 - 2.0 * margin).max(0.0) / extent } else { 0.0 };
+    let offset = [
+        (size as f32 - (max[0] - min[0]) * scale) / 2.0,
+        (size as f32 - (max[1] - min[1]) * scale) / 2.0,
+    ];
+    let pixels: Vec<[f32; 2]> =
+        layout.iter().map(|p| [offset[0] + (p[0] - min[0]) * scale, offset[1] + (p[1] - min[1]) * scale]).collect();
+    let mut plot = |x: f32, y: f32, color: [u8; 3]| {
+        if x >= 0.0 && y >= 0.0 && x < size as f32 && y < size as f32 {
+            image.put_pixel(x as u32, y as u32, Rgb(color));
+        }
+    };
+
+    for (from, to, _) in graph.edges() {
+        let ([x0, y0], [x1, y1]) = (pixels[from as usize], pixels[to as usize]);
+        let samples = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize;
+        for sample in 0..=samples {
+            let t = sample as f32 / samples as f32;
+            plot(x0 + (x1 - x0) * t, y0 + (y1 - y0) * t, EDGE_COLOR);
+        }
+    }
+    for ([x, y], value) in pixels.iter().zip(colors) {
+        let color = colormap.color(*value);
+        let reach = radius.ceil() as i32;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if (dx * dx + dy * dy) as f32 <= radius * radius {
+                    plot(x + dx as f32, y + dy as f32, color);
+                }
+            }
+        }
+    }
+    Ok(image)
+}
+
+/// Simulation evolving the nodes of a graph
+#[derive(Debug, Clone)]
+pub struct GraphSimulation {
+    parameters: Parameters,
+    graph: Graph,
+    /// Sum of the weights of the edges of every node
+    degrees: Vec<f32>,
+    cells: Vec<Cell>,
+    /// Color value of every node, see `color_cell`
+    colors: Vec<f32>,
+    generation: i32,
+}
+
+impl GraphSimulation {
+    /// Fails if the parameters are invalid or there is not one cell per node
+    /// of `graph`
+    pub fn new(parameters: Parameters, graph: Graph, cells: Vec<Cell>) -> Result<GraphSimulation, SimulationError> {
+        parameters.validate()?;
+        if cells.len() != graph.nodes() {
+            return Err(SimulationError::VertexMismatch { expected: graph.nodes(), found: cells.len() });
+        }
+        let degrees = (0..graph.nodes()).map(|node| graph.neighbours(node).map(|(_, weight)| weight).sum()).collect();
+        let colors = cells.iter().map(color_cell).collect();
+        Ok(GraphSimulation { parameters, graph, degrees, cells, colors, generation: 0 })
+    }
+
+    pub fn parameters(&self) -> Parameters {
+        self.parameters
+    }
+
+    /// Change the parameters, keeping the concentrations
+    pub fn set_parameters(&mut self, parameters: Parameters) -> Result<(), SimulationError> {
+        parameters.validate()?;
+        self.parameters = parameters;
+        Ok(())
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Concentrations of the nodes, in their order
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Color values of the nodes, in their order
+    pub fn colors(&self) -> &[f32] {
+        &self.colors
+    }
+
+    /// Number of evolutions computed so far
+    pub fn generation(&self) -> i32 {
+        self.generation
+    }
+
+    /// Compute one evolution of every node
+    pub fn step(&mut self) {
+        let Parameters { d_a, d_b, .. } = self.parameters;
+        let evolved: Vec<Cell> = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(node, cell)| {
+                let mut evolved = *cell;
+                if self.degrees[node] > 0.0 {
+                    for (neighbour, weight) in self.graph.neighbours(node) {
+                        let share = weight / self.degrees[node];
+                        let neighbour = self.cells[neighbour as usize];
+                        evolved.a += share * d_a * (neighbour.a - cell.a);
+                        evolved.b += share * d_b * (neighbour.b - cell.b);
+                    }
+                }
+                let [a, b] = gray_scott(&self.parameters, *cell);
+                Cell { a: evolved.a + a, b: evolved.b + b }
+            })
+            .collect();
+        self.colors = evolved.iter().map(color_cell).collect();
+        self.cells = evolved;
+        self.generation += 1;
+    }
+
+    /// Compute `n` evolutions
+    pub fn run(&mut self, n: i32) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+}
@@ -0,0 +1,83 @@
+/// Hashes of universes
+/// The content hash of a universe only depends on its dimensions and on its
+/// concentrations rounded to a number of decimals, read row by row, so it is
+/// the same on every platform and across versions of the crate. Comparing it
+/// with a hash recorded by a reference run checks that a change of
+/// implementation still computes the same universes, up to the rounding
+use crate::{Float, Simulation, Universe};
+
+/// Decimals kept by `content_hash`
+pub const HASH_DECIMALS: u32 = 5;
+
+/// Offset basis and prime of the 64 bits FNV-1a hash
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a hash, written out so it never changes with the standard library
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, value: i64) {
+        for byte in value.to_le_bytes() {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+/// Concentration rounded to `decimals` decimals, as an integer
+/// Every NaN gives the same value, distinct from any number in [0, 1]
+fn quantize<T: Float>(value: T, scale: f64) -> i64 {
+    if value.is_nan() {
+        i64::MIN
+    } else {
+        (value.to_f64() * scale).round() as i64
+    }
+}
+
+/// Hash of `universe` with its concentrations rounded to `decimals` decimals
+pub fn quantized_hash<T: Float>(universe: &Universe<T>, decimals: u32) -> u64 {
+    let scale = 10f64.powi(decimals as i32);
+    let mut hash = Fnv(FNV_OFFSET);
+    hash.write(universe.len() as i64);
+    for row in universe {
+        hash.write(row.len() as i64);
+        for cell in row {
+            hash.write(quantize(cell.a, scale));
+            hash.write(quantize(cell.b, scale));
+        }
+    }
+    hash.0
+}
+
+/// Hash of `universe` with its concentrations rounded to `HASH_DECIMALS`
+/// decimals
+pub fn content_hash<T: Float>(universe: &Universe<T>) -> u64 {
+    quantized_hash(universe, HASH_DECIMALS)
+}
+
+/// Hash of `universe` with the exact bits of its concentrations, which only
+/// matches for universes computed bit for bit the same, see `deterministic`
+pub fn exact_hash<T: Float>(universe: &Universe<T>) -> u64 {
+    let mut hash = Fnv(FNV_OFFSET);
+    hash.write(universe.len() as i64);
+    for row in universe {
+        hash.write(row.len() as i64);
+        for cell in row {
+            hash.write(cell.a.to_f64().to_bits() as i64);
+            hash.write(cell.b.to_f64().to_bits() as i64);
+        }
+    }
+    hash.0
+}
+
+impl<T: Float> Simulation<T> {
+    /// Hash of the current universe, see `content_hash`
+    pub fn content_hash(&self) -> u64 {
+        content_hash(self.universe())
+    }
+
+    /// Exact hash of the current universe, see `exact_hash`
+    pub fn exact_hash(&self) -> u64 {
+        exact_hash(self.universe())
+    }
+}
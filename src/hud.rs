@@ -0,0 +1,120 @@
+/// Heads-up display of the timings of the application
+/// The time spent in each stage of the simulation, see `profile`, is kept in
+/// a Bevy diagnostic of its own, averaged over the last generations, next to
+/// the frame rate. `F3` shows and hides them in the top right corner of the
+/// window, drawn with the font of the console
+use bevy::diagnostic::{Diagnostic, DiagnosticId, Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::console::CONSOLE_FONT;
+use crate::profile::{self, STAGES};
+
+/// Key showing and hiding the display
+pub const HUD_KEY: KeyCode = KeyCode::F3;
+/// Diagnostics of the stages of `profile::STAGES`, in the same order, in
+/// milliseconds
+pub const STAGE_DIAGNOSTICS: [DiagnosticId; 5] = [
+    DiagnosticId::from_u128(0x54e5a23b5b2a43fc9877319cc4d85c81),
+    DiagnosticId::from_u128(0x7744d075f16741bdbd231c15fa433919),
+    DiagnosticId::from_u128(0x96d5b857fbee43a0b010408340fb85dd),
+    DiagnosticId::from_u128(0xe24943b989dd4d54ba6f08f671763412),
+    DiagnosticId::from_u128(0xbb9b795d3060422fb10486c8a907d02a),
+];
+/// Measurements averaged by the diagnostics
+const HISTORY: usize = 20;
+const FONT_SIZE: f32 = 14.0;
+
+/// Whether the display is shown
+#[derive(Resource, Debug, Default)]
+pub struct Hud {
+    pub visible: bool,
+}
+
+/// Diagnostics of the stages and the display showing them
+/// Profiling is enabled once the plugin is added
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        profile::enable();
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugin(FrameTimeDiagnosticsPlugin);
+        }
+        app.init_resource::<Hud>()
+            .add_startup_system(setup_diagnostics)
+            .add_startup_system(spawn_hud)
+            .add_system_to_stage(CoreStage::Last, measure_stages)
+            .add_system(toggle_hud)
+            .add_system(draw_hud.after(toggle_hud));
+    }
+}
+
+/// Text of the display
+#[derive(Component)]
+struct HudText;
+
+fn setup_diagnostics(mut diagnostics: ResMut<Diagnostics>) {
+    for (id, name) in STAGE_DIAGNOSTICS.into_iter().zip(STAGES) {
+        diagnostics.add(Diagnostic::new(id, name, HISTORY).with_suffix("ms"));
+    }
+}
+
+fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style = TextStyle { font: asset_server.load(CONSOLE_FONT), font_size: FONT_SIZE, color: Color::WHITE };
+    commands.spawn((
+        TextBundle::from_section("", style).with_style(Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            position: UiRect { right: Val::Px(6.0), top: Val::Px(6.0), ..default() },
+            ..default()
+        }),
+        HudText,
+    ));
+}
+
+/// Add the mean time of the runs of each stage since the last frame to its
+/// diagnostic, if it ran
+fn measure_stages(mut diagnostics: ResMut<Diagnostics>, mut last: Local<[profile::StageTimes; 5]>) {
+    let times = profile::times();
+    for ((id, name), last) in STAGE_DIAGNOSTICS.into_iter().zip(STAGES).zip(last.iter_mut()) {
+        let Some(stage) = times.get(name).copied() else {
+            continue;
+        };
+        // Profiling may have been reset in the meantime
+        if stage.count > last.count {
+            let total = stage.total.saturating_sub(last.total);
+            let mean = total.as_secs_f64() * 1000.0 / (stage.count - last.count) as f64;
+            diagnostics.add_measurement(id, || mean);
+        }
+        *last = stage;
+    }
+}
+
+fn toggle_hud(keys: Res<Input<KeyCode>>, mut hud: ResMut<Hud>) {
+    if keys.just_pressed(HUD_KEY) {
+        hud.visible = !hud.visible;
+    }
+}
+
+fn draw_hud(hud: Res<Hud>, diagnostics: Res<Diagnostics>, mut texts: Query<(&mut Text, &mut Style), With<HudText>>) {
+    let Ok((mut text, mut style)) = texts.get_single_mut() else {
+        return;
+    };
+    if hud.is_changed() {
+        style.display = if hud.visible { Display::Flex } else { Display::None };
+    }
+    if !hud.visible {
+        return;
+    }
+    let average = |id| diagnostics.get(id).and_then(Diagnostic::average);
+    let mut lines = vec![
+        format!("{:<10}{:>8.1}", "fps", average(FrameTimeDiagnosticsPlugin::FPS).unwrap_or_default()),
+        format!("{:<10}{:>8.2} ms", "frame", average(FrameTimeDiagnosticsPlugin::FRAME_TIME).unwrap_or_default()),
+    ];
+    for (id, name) in STAGE_DIAGNOSTICS.into_iter().zip(STAGES) {
+        if let Some(mean) = average(id) {
+            lines.push(format!("{name:<10}{mean:>8.3} ms"));
+        }
+    }
+    text.sections[0].value = lines.join("\n");
+}
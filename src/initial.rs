@@ -0,0 +1,357 @@
+/// Initial states of the universe
+/// Alternatives to the random cells of `initialize_universe`: generated
+/// conditions, from the canonical square of B in a universe full of A to
+/// noise and geometric shapes, and images
+use std::f32::consts::PI;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+use image::DynamicImage;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color_universe, initialize_universe_with_rng, Cell, ColoredMap, Float, Position, SimulationError, Universe,
+    INITIAL_CELLS,
+};
+
+/// Concentrations of the patches of B put in a universe full of A by the
+/// square, the circles and the stripes, those of Pearson's start
+const PATCH: Cell = Cell { a: 0.5, b: 0.25 };
+
+/// Generated initial state
+/// Apart from `Random` and `Empty`, which start from an empty universe, the
+/// conditions start from the steady state without B, A at 1 everywhere, and
+/// perturb it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum InitialCondition {
+    /// `cells` random cells holding A and B at 1 in an empty universe, see
+    /// `initialize_universe_with_rng`
+    Random { cells: usize },
+    /// An empty universe without any seed, for seeds placed by hand during
+    /// the setup of the application
+    Empty,
+    /// Square in the center, whose side is `size` times the smaller side of
+    /// the universe, the canonical start of the Gray–Scott model
+    Square { size: f32 },
+    /// B drawn uniformly in [0, `amplitude`] in a fraction `density` of the
+    /// cells, taken from A
+    Noise { density: f32, amplitude: f32 },
+    /// Perlin noise of `octaves` octaves, whose largest features are `scale`
+    /// cells wide, scaled to [0, `amplitude`] as B and taken from A
+    Perlin { scale: f32, octaves: u32, amplitude: f32 },
+    /// `count` discs of `radius` cells at random places
+    Circles { count: usize, radius: f32 },
+    /// Stripes of `period` cells, half of it filled, turned by `angle`
+    /// degrees from the vertical
+    Stripes { period: f32, angle: f32 },
+}
+
+/// Names of the initial conditions, usable with `InitialCondition::from_name`
+pub const INITIAL_CONDITION_NAMES: [&str; 7] = ["random", "empty", "square", "noise", "perlin", "circles", "stripes"];
+
+impl InitialCondition {
+    /// Initial condition with the given name and its default settings, see
+    /// `INITIAL_CONDITION_NAMES`
+    pub fn from_name(name: &str) -> Option<InitialCondition> {
+        match name {
+            "random" => Some(InitialCondition::Random { cells: INITIAL_CELLS }),
+            "empty" => Some(InitialCondition::Empty),
+            "square" => Some(InitialCondition::Square { size: 0.1 }),
+            "noise" => Some(InitialCondition::Noise { density: 0.1, amplitude: 0.5 }),
+            "perlin" => Some(InitialCondition::Perlin { scale: 64.0, octaves: 4, amplitude: 0.5 }),
+            "circles" => Some(InitialCondition::Circles { count: 5, radius: 8.0 }),
+            "stripes" => Some(InitialCondition::Stripes { period: 32.0, angle: 0.0 }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InitialCondition::Random { .. } => "random",
+            InitialCondition::Empty => "empty",
+            InitialCondition::Square { .. } => "square",
+            InitialCondition::Noise { .. } => "noise",
+            InitialCondition::Perlin { .. } => "perlin",
+            InitialCondition::Circles { .. } => "circles",
+            InitialCondition::Stripes { .. } => "stripes",
+        }
+    }
+
+    /// Check that the settings describe a universe
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidInitial(message));
+        let fraction = |value: f32| (0.0..=1.0).contains(&value);
+        match *self {
+            InitialCondition::Random { .. } | InitialCondition::Empty => Ok(()),
+            InitialCondition::Square { size } if fraction(size) => Ok(()),
+            InitialCondition::Square { size } => error(format!("the size of the square must be in [0,1], not {size}")),
+            InitialCondition::Noise { density, amplitude } if fraction(density) && fraction(amplitude) => Ok(()),
+            InitialCondition::Noise { density, amplitude } => error(format!(
+                "the density and the amplitude of the noise must be in [0,1], not {density} and {amplitude}"
+            )),
+            InitialCondition::Perlin { scale, octaves, amplitude } if scale >= 1.0 && octaves >= 1 && fraction(amplitude) => {
+                Ok(())
+            }
+            InitialCondition::Perlin { scale, octaves, amplitude } => error(format!(
+                "the scale and the octaves of the Perlin noise must be at least 1 and its amplitude in [0,1], not {scale}, {octaves} and {amplitude}"
+            )),
+            InitialCondition::Circles { radius, .. } if radius >= 0.0 => Ok(()),
+            InitialCondition::Circles { radius, .. } => {
+                error(format!("the radius of the circles cannot be negative, not {radius}"))
+            }
+            InitialCondition::Stripes { period, angle } if period >= 1.0 && angle.is_finite() => Ok(()),
+            InitialCondition::Stripes { period, angle } => error(format!(
+                "the period of the stripes must be at least 1 and their angle finite, not {period} and {angle}"
+            )),
+        }
+    }
+
+    /// Universe of `dimensions` described by this condition, with the random
+    /// choices drawn from `rng`
+    /// Fails if the settings are invalid, see `validate`
+    pub fn generate<T: Float, R: Rng + ?Sized>(
+        &self,
+        dimensions: &Position,
+        rng: &mut R,
+    ) -> Result<(Universe<T>, ColoredMap), SimulationError> {
+        self.validate()?;
+        let steady = || -> Universe { vec![vec![Cell { a: 1.0, b: 0.0 }; dimensions.col]; dimensions.row] };
+        let center = (dimensions.row as f32 / 2.0, dimensions.col as f32 / 2.0);
+        let universe = match *self {
+            InitialCondition::Random { cells } => return Ok(initialize_universe_with_rng(dimensions, cells, rng)),
+            InitialCondition::Empty => return Ok(initialize_universe_with_rng(dimensions, 0, rng)),
+            InitialCondition::Square { size } => {
+                let mut universe = steady();
+                let side = (dimensions.row.min(dimensions.col) as f32 * size).round() as usize;
+                let rows = (dimensions.row - side.min(dimensions.row)) / 2;
+                let cols = (dimensions.col - side.min(dimensions.col)) / 2;
+                for row in universe.iter_mut().skip(rows).take(side) {
+                    for cell in row.iter_mut().skip(cols).take(side) {
+                        *cell = PATCH;
+                    }
+                }
+                universe
+            }
+            InitialCondition::Noise { density, amplitude } => {
+                let mut universe = steady();
+                for cell in universe.iter_mut().flatten() {
+                    if rng.gen::<f32>() < density {
+                        let b = amplitude * rng.gen::<f32>();
+                        *cell = Cell { a: 1.0 - b, b };
+                    }
+                }
+                universe
+            }
+            InitialCondition::Perlin { scale, octaves, amplitude } => {
+                let mut universe = steady();
+                let noise = Perlin::new(rng);
+                for (row, cells) in universe.iter_mut().enumerate() {
+                    for (col, cell) in cells.iter_mut().enumerate() {
+                        let b = amplitude * noise.fractal(col as f32 / scale, row as f32 / scale, octaves);
+                        *cell = Cell { a: 1.0 - b, b };
+                    }
+                }
+                universe
+            }
+            InitialCondition::Circles { count, radius } => {
+                let mut universe = steady();
+                for _ in 0..count {
+                    let center = (
+                        rng.gen::<f32>() * dimensions.row as f32,
+                        rng.gen::<f32>() * dimensions.col as f32,
+                    );
+                    for (row, cells) in universe.iter_mut().enumerate() {
+                        for (col, cell) in cells.iter_mut().enumerate() {
+                            let (d_row, d_col) = (row as f32 + 0.5 - center.0, col as f32 + 0.5 - center.1);
+                            if d_row * d_row + d_col * d_col <= radius * radius {
+                                *cell = PATCH;
+                            }
+                        }
+                    }
+                }
+                universe
+            }
+            InitialCondition::Stripes { period, angle } => {
+                let mut universe = steady();
+                let (sin, cos) = (angle * PI / 180.0).sin_cos();
+                for (row, cells) in universe.iter_mut().enumerate() {
+                    for (col, cell) in cells.iter_mut().enumerate() {
+                        let (d_row, d_col) = (row as f32 + 0.5 - center.0, col as f32 + 0.5 - center.1);
+                        let distance = d_col * cos - d_row * sin;
+                        if (distance / period).rem_euclid(1.0) < 0.5 {
+                            *cell = PATCH;
+                        }
+                    }
+                }
+                universe
+            }
+        };
+
+        let universe: Universe<T> = universe.into_iter().map(|row| row.into_iter().map(Cell::cast).collect()).collect();
+        let colored_map = color_universe(&universe);
+        Ok((universe, colored_map))
+    }
+}
+
+/// Gradient noise of Perlin, from a random permutation
+struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    fn new<R: Rng + ?Sized>(rng: &mut R) -> Perlin {
+        let mut values: Vec<u8> = (0..=255).collect();
+        values.shuffle(rng);
+        let mut permutation = [0; 512];
+        for (index, value) in permutation.iter_mut().enumerate() {
+            *value = values[index % 256];
+        }
+        Perlin { permutation }
+    }
+
+    /// Noise at `x`, `y`, in about [-1,1]
+    fn noise(&self, x: f32, y: f32) -> f32 {
+        let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+        // Dot product of the offset with one of 8 gradients picked by `hash`
+        let gradient = |hash: u8, x: f32, y: f32| match hash & 7 {
+            0 => x + y,
+            1 => x - y,
+            2 => -x + y,
+            3 => -x - y,
+            4 => x,
+            5 => -x,
+            6 => y,
+            _ => -y,
+        };
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (xi, yi) = ((x0 as i64).rem_euclid(256) as usize, (y0 as i64).rem_euclid(256) as usize);
+        let (x, y) = (x - x0, y - y0);
+        let p = &self.permutation;
+        let hash = |dx: usize, dy: usize| p[p[xi + dx] as usize + yi + dy];
+        let (u, v) = (fade(x), fade(y));
+        lerp(
+            v,
+            lerp(u, gradient(hash(0, 0), x, y), gradient(hash(1, 0), x - 1.0, y)),
+            lerp(u, gradient(hash(0, 1), x, y - 1.0), gradient(hash(1, 1), x - 1.0, y - 1.0)),
+        )
+    }
+
+    /// Sum of `octaves` octaves of noise, each twice as fine and half as
+    /// strong as the previous one, scaled to [0,1]
+    fn fractal(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let (mut total, mut weight, mut frequency) = (0.0, 1.0, 1.0);
+        let mut weights = 0.0;
+        for _ in 0..octaves {
+            total += weight * self.noise(x * frequency, y * frequency);
+            weights += weight;
+            weight /= 2.0;
+            frequency *= 2.0;
+        }
+        ((total / weights + 1.0) / 2.0).clamp(0.0, 1.0)
+    }
+}
+
+/// Channel of an image read as a concentration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Perceived brightness of the pixel
+    #[default]
+    Luminance,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+/// Names of the channels, as written in the configuration files
+pub const CHANNEL_NAMES: [&str; 5] = ["luminance", "red", "green", "blue", "alpha"];
+
+impl Channel {
+    /// Channel with the given name, see `CHANNEL_NAMES`
+    pub fn from_name(name: &str) -> Option<Channel> {
+        match name {
+            "luminance" => Some(Channel::Luminance),
+            "red" => Some(Channel::Red),
+            "green" => Some(Channel::Green),
+            "blue" => Some(Channel::Blue),
+            "alpha" => Some(Channel::Alpha),
+            _ => None,
+        }
+    }
+}
+
+/// Image used as initial state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImageSeed {
+    /// PNG or JPEG file
+    pub path: PathBuf,
+    /// Channel mapped to the concentration of B
+    #[serde(default)]
+    pub channel: Channel,
+    /// Use 1 - value, so dark pixels get the most B
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// Universe from an image
+/// The universe has one cell per pixel. B takes the value of `channel`,
+/// scaled to [0,1], and A starts at 1 everywhere
+pub fn universe_from_image(image: &DynamicImage, channel: Channel, invert: bool) -> (Universe, Position) {
+    let image = image.to_rgba8();
+    let dimensions = Position {
+        row: image.height() as usize,
+        col: image.width() as usize,
+    };
+
+    let universe = image
+        .rows()
+        .map(|row| {
+            row.map(|pixel| {
+                let [r, g, b, a] = pixel.0.map(|value| value as f32 / 255.0);
+                let value = match channel {
+                    // Rec. 709 luma coefficients
+                    Channel::Luminance => 0.2126 * r + 0.7152 * g + 0.0722 * b,
+                    Channel::Red => r,
+                    Channel::Green => g,
+                    Channel::Blue => b,
+                    Channel::Alpha => a,
+                };
+                Cell {
+                    a: 1.0,
+                    b: if invert { 1.0 - value } else { value },
+                }
+            })
+            .collect()
+        })
+        .collect();
+
+    (universe, dimensions)
+}
+
+/// Read a PNG or JPEG file and build a universe from it, see
+/// `universe_from_image`
+#[cfg(feature = "fs")]
+pub fn load_image_universe(
+    path: &Path,
+    channel: Channel,
+    invert: bool,
+) -> Result<(Universe, Position), SimulationError> {
+    let image = image::open(path)?;
+    Ok(universe_from_image(&image, channel, invert))
+}
+
+#[cfg(feature = "fs")]
+impl ImageSeed {
+    /// Universe described by this seed, see `load_image_universe`
+    pub fn load(&self) -> Result<(Universe, Position), SimulationError> {
+        load_image_universe(&self.path, self.channel, self.invert)
+    }
+}
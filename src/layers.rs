@@ -0,0 +1,147 @@
+/// Coupled layers
+/// Layered reaction–diffusion models, e.g. of fish skin or feathers, evolve
+/// several universes of the same dimensions together, each with its own
+/// parameters. After every evolution each coupling adds a fraction of the
+/// concentration of a species in one layer to a species in another, for
+/// instance B of the first layer feeding A of the second
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::Species;
+use crate::{Simulation, SimulationError};
+
+/// Transfer from one layer to another after every evolution
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Coupling {
+    /// Index of the layer read
+    pub from: usize,
+    pub source: Species,
+    /// Index of the layer changed
+    pub to: usize,
+    pub target: Species,
+    /// Fraction of the source concentration added to the target, negative
+    /// to remove it instead
+    pub strength: f32,
+}
+
+impl fmt::Display for Coupling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} of layer {} to {:?} of layer {} with strength {}",
+            self.source, self.from, self.target, self.to, self.strength
+        )
+    }
+}
+
+/// Check that `couplings` only refer to the `layers` first layers
+pub fn check_couplings(couplings: &[Coupling], layers: usize) -> Result<(), SimulationError> {
+    match couplings.iter().find(|coupling| coupling.from >= layers || coupling.to >= layers) {
+        Some(coupling) => Err(SimulationError::InvalidCoupling { coupling: *coupling, layers }),
+        None => Ok(()),
+    }
+}
+
+/// Apply `couplings` to `layers`, which must have the same dimensions
+/// All the transfers are computed from the concentrations before any of them
+/// is applied, so the order of the couplings does not matter. Couplings to
+/// missing layers are ignored, see `check_couplings`. Every layer is written
+/// once, whatever the number of couplings to it
+pub fn apply_couplings(couplings: &[Coupling], layers: &mut [&mut Simulation]) {
+    let transfers: Vec<(usize, Species, Vec<Vec<f32>>)> = couplings
+        .iter()
+        .filter(|coupling| coupling.from < layers.len() && coupling.to < layers.len())
+        .map(|coupling| {
+            let amounts = layers[coupling.from]
+                .universe()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| coupling.strength * coupling.source.concentration(cell))
+                        .collect()
+                })
+                .collect();
+            (coupling.to, coupling.target, amounts)
+        })
+        .collect();
+
+    for (index, layer) in layers.iter_mut().enumerate() {
+        let incoming: Vec<_> = transfers.iter().filter(|(to, _, _)| *to == index).collect();
+        if incoming.is_empty() {
+            continue;
+        }
+        layer.update_cells(|position, cell| {
+            for (_, target, amounts) in &incoming {
+                if let Some(amount) = amounts.get(position.row).and_then(|amounts| amounts.get(position.col)) {
+                    *target.concentration_mut(cell) += amount;
+                }
+            }
+        });
+    }
+}
+
+/// Layers evolved together through their couplings
+#[derive(Debug)]
+pub struct LayeredSimulation {
+    layers: Vec<Simulation>,
+    couplings: Vec<Coupling>,
+}
+
+impl LayeredSimulation {
+    /// Fails if the layers do not all have the dimensions of the first one
+    /// or a coupling refers to a missing layer
+    pub fn new(layers: Vec<Simulation>, couplings: Vec<Coupling>) -> Result<LayeredSimulation, SimulationError> {
+        if let Some(first) = layers.first() {
+            let expected = first.dimensions();
+            if let Some(layer) = layers.iter().find(|layer| layer.dimensions() != expected) {
+                return Err(SimulationError::DimensionMismatch { expected, found: layer.dimensions() });
+            }
+        }
+        check_couplings(&couplings, layers.len())?;
+        Ok(LayeredSimulation { layers, couplings })
+    }
+
+    pub fn layers(&self) -> &[Simulation] {
+        &self.layers
+    }
+
+    pub fn couplings(&self) -> &[Coupling] {
+        &self.couplings
+    }
+
+    /// Number of evolutions computed so far, the same for all the layers
+    pub fn generation(&self) -> i32 {
+        self.layers.first().map_or(0, Simulation::generation)
+    }
+
+    /// Whether a layer stopped, e.g. out of bounds in `Bounds::Strict` mode
+    pub fn is_stopped(&self) -> bool {
+        self.layers.iter().any(Simulation::is_stopped)
+    }
+
+    /// Compute one evolution of every layer, then apply the couplings
+    pub fn step(&mut self) {
+        for layer in &mut self.layers {
+            layer.step();
+        }
+        let mut layers: Vec<&mut Simulation> = self.layers.iter_mut().collect();
+        apply_couplings(&self.couplings, &mut layers);
+    }
+
+    /// Compute `n` evolutions, stopping early if a layer stops
+    pub fn run(&mut self, n: i32) {
+        for _ in 0..n {
+            if self.is_stopped() {
+                break;
+            }
+            self.step();
+        }
+    }
+
+    /// Layers, consuming the simulation
+    pub fn into_layers(self) -> Vec<Simulation> {
+        self.layers
+    }
+}
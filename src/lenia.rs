@@ -0,0 +1,294 @@
+/// Lenia
+/// A continuous cellular automaton of the Lenia family: each cell holds one
+/// value in [0,1], and every evolution convolves the field with a large
+/// smooth ring-shaped kernel, maps the result through a growth function and
+/// adds a fraction `dt` of the growth to the cell. The convolution is
+/// computed with 2D FFTs, wrapping around the edges of a periodic universe
+/// and padded with zeros past those of a closed one. The field is also given
+/// as a `Universe` whose B is the value and A its complement, so its color
+/// map is the value and it can be exported and drawn like any other
+use std::fmt;
+use std::sync::Arc;
+
+use rand::Rng;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+
+use crate::{Boundary, Cell, ColoredMap, Position, SimulationError, Universe};
+
+/// Parameters of a Lenia simulation, by default those of the glider Orbium
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeniaParameters {
+    /// Radius of the kernel, in cells
+    pub radius: usize,
+    /// Height of every ring of the kernel, from the center outwards
+    pub peaks: Vec<f32>,
+    /// Potential at which the growth is the largest
+    pub mu: f32,
+    /// Width of the growth function around `mu`
+    pub sigma: f32,
+    /// Fraction of the growth added at every evolution, in (0,1]
+    pub dt: f32,
+}
+
+impl Default for LeniaParameters {
+    fn default() -> Self {
+        LeniaParameters { radius: 13, peaks: vec![1.0], mu: 0.15, sigma: 0.015, dt: 0.1 }
+    }
+}
+
+impl LeniaParameters {
+    /// Check that the parameters give a kernel and a growth function
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: &str| Err(SimulationError::InvalidLenia(message.to_string()));
+        if self.radius == 0 {
+            return error("the radius of the kernel must be at least 1");
+        }
+        if self.peaks.is_empty() || self.peaks.iter().all(|peak| *peak <= 0.0) {
+            return error("the kernel needs a ring with a positive peak");
+        }
+        if self.sigma.is_nan() || self.sigma <= 0.0 {
+            return error("sigma must be positive");
+        }
+        if self.dt.is_nan() || self.dt <= 0.0 || self.dt > 1.0 {
+            return error("dt must be in (0,1]");
+        }
+        Ok(())
+    }
+}
+
+/// Smooth bump of the rings of the kernel, from 0 at 0 and 1 up to 1 at 1/2
+fn core(r: f64) -> f64 {
+    if r <= 0.0 || r >= 1.0 {
+        return 0.0;
+    }
+    (4.0 - 1.0 / (r * (1.0 - r))).exp()
+}
+
+/// Weights of the kernel of `parameters`, of `2 * radius + 1` cells on each
+/// side around its center, summing to 1
+pub fn kernel(parameters: &LeniaParameters) -> Vec<Vec<f64>> {
+    let radius = parameters.radius as f64;
+    let rings = parameters.peaks.len() as f64;
+    let side = 2 * parameters.radius + 1;
+    let mut weights: Vec<Vec<f64>> = (0..side)
+        .map(|row| {
+            (0..side)
+                .map(|col| {
+                    let (dr, dc) = (row as f64 - radius, col as f64 - radius);
+                    let distance = (dr * dr + dc * dc).sqrt() / radius * rings;
+                    match parameters.peaks.get(distance as usize) {
+                        Some(peak) => *peak as f64 * core(distance.fract()),
+                        None => 0.0,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let sum: f64 = weights.iter().flatten().sum();
+    if sum > 0.0 {
+        weights.iter_mut().flatten().for_each(|weight| *weight /= sum);
+    }
+    weights
+}
+
+/// Growth of a cell whose potential, its convolution with the kernel, is
+/// `potential`: from -1 far from `mu` up to 1 at `mu`
+pub fn growth(potential: f64, mu: f64, sigma: f64) -> f64 {
+    2.0 * (-(potential - mu).powi(2) / (2.0 * sigma * sigma)).exp() - 1.0
+}
+
+/// Field of `dimensions` with `patches` squares of `size` cells on each side
+/// at random positions, filled with random values drawn from `rng`
+pub fn initialize_lenia<R: Rng + ?Sized>(
+    dimensions: Position,
+    patches: usize,
+    size: usize,
+    rng: &mut R,
+) -> Vec<Vec<f32>> {
+    let mut field = vec![vec![0.0; dimensions.col]; dimensions.row];
+    let size = size.min(dimensions.row).min(dimensions.col);
+    for _ in 0..patches {
+        if size == 0 {
+            break;
+        }
+        let row = rng.gen_range(0..=dimensions.row - size);
+        let col = rng.gen_range(0..=dimensions.col - size);
+        for cells in &mut field[row..row + size] {
+            for value in &mut cells[col..col + size] {
+                *value = rng.gen();
+            }
+        }
+    }
+    field
+}
+
+/// Simulation of a Lenia field
+#[derive(Clone)]
+pub struct LeniaSimulation {
+    parameters: LeniaParameters,
+    dimensions: Position,
+    boundary: Boundary,
+    field: Vec<Vec<f32>>,
+    /// Number of rows and columns of the transforms, padded past the edges
+    /// of a closed universe
+    padded: Position,
+    /// Transform of the kernel, divided by the number of cells of the
+    /// transforms so that the inverse transform needs no scaling
+    kernel: Vec<Complex<f64>>,
+    forward: [Arc<dyn Fft<f64>>; 2],
+    inverse: [Arc<dyn Fft<f64>>; 2],
+    generation: i32,
+}
+
+impl fmt::Debug for LeniaSimulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeniaSimulation")
+            .field("parameters", &self.parameters)
+            .field("dimensions", &self.dimensions)
+            .field("boundary", &self.boundary)
+            .field("generation", &self.generation)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Transform in place `buffer`, of `rows` rows of `cols` values, along its
+/// rows with `along_rows` and then along its columns with `along_cols`
+fn transform(buffer: &mut [Complex<f64>], rows: usize, cols: usize, along_rows: &dyn Fft<f64>, along_cols: &dyn Fft<f64>) {
+    along_rows.process(buffer);
+    let mut transposed: Vec<Complex<f64>> =
+        (0..rows * cols).map(|index| buffer[(index % rows) * cols + index / rows]).collect();
+    along_cols.process(&mut transposed);
+    for (index, value) in transposed.into_iter().enumerate() {
+        buffer[(index % rows) * cols + index / rows] = value;
+    }
+}
+
+impl LeniaSimulation {
+    /// Fails if the parameters are invalid or `field` does not have
+    /// `dimensions`
+    pub fn new(
+        parameters: LeniaParameters,
+        dimensions: Position,
+        boundary: Boundary,
+        field: Vec<Vec<f32>>,
+    ) -> Result<LeniaSimulation, SimulationError> {
+        parameters.validate()?;
+        let found = Position { row: field.len(), col: field.first().map_or(0, Vec::len) };
+        if found != dimensions || field.iter().any(|row| row.len() != dimensions.col) {
+            return Err(SimulationError::DimensionMismatch { expected: dimensions, found });
+        }
+
+        let padding = match boundary {
+            Boundary::Closed => parameters.radius,
+            Boundary::Periodic => 0,
+        };
+        let padded = Position { row: dimensions.row + padding, col: dimensions.col + padding };
+        let mut planner = FftPlanner::new();
+        let forward = [planner.plan_fft_forward(padded.col), planner.plan_fft_forward(padded.row)];
+        let inverse = [planner.plan_fft_inverse(padded.col), planner.plan_fft_inverse(padded.row)];
+
+        // The center of the kernel goes to the first cell, the cells before
+        // it wrap around to the end of the buffer
+        let cells = padded.row * padded.col;
+        let mut spectrum = vec![Complex::new(0.0, 0.0); cells];
+        let radius = parameters.radius as isize;
+        let weights = if cells > 0 { kernel(&parameters) } else { Vec::new() };
+        for (row, weights) in weights.iter().enumerate() {
+            for (col, weight) in weights.iter().enumerate() {
+                let r = (row as isize - radius).rem_euclid(padded.row as isize) as usize;
+                let c = (col as isize - radius).rem_euclid(padded.col as isize) as usize;
+                spectrum[r * padded.col + c] += weight / cells as f64;
+            }
+        }
+        transform(&mut spectrum, padded.row, padded.col, &*forward[0], &*forward[1]);
+
+        Ok(LeniaSimulation {
+            parameters,
+            dimensions,
+            boundary,
+            field,
+            padded,
+            kernel: spectrum,
+            forward,
+            inverse,
+            generation: 0,
+        })
+    }
+
+    pub fn parameters(&self) -> &LeniaParameters {
+        &self.parameters
+    }
+
+    pub fn dimensions(&self) -> Position {
+        self.dimensions
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Value of every cell, row by row
+    pub fn field(&self) -> &Vec<Vec<f32>> {
+        &self.field
+    }
+
+    /// Number of evolutions computed so far
+    pub fn generation(&self) -> i32 {
+        self.generation
+    }
+
+    /// Convolution of the field with the kernel
+    pub fn potential(&self) -> Vec<Vec<f64>> {
+        let Position { row: rows, col: cols } = self.padded;
+        let mut buffer = vec![Complex::new(0.0, 0.0); rows * cols];
+        for (row, values) in self.field.iter().enumerate() {
+            for (col, value) in values.iter().enumerate() {
+                buffer[row * cols + col] = Complex::new(*value as f64, 0.0);
+            }
+        }
+        transform(&mut buffer, rows, cols, &*self.forward[0], &*self.forward[1]);
+        for (value, weight) in buffer.iter_mut().zip(&self.kernel) {
+            *value *= weight;
+        }
+        transform(&mut buffer, rows, cols, &*self.inverse[0], &*self.inverse[1]);
+        (0..self.dimensions.row)
+            .map(|row| (0..self.dimensions.col).map(|col| buffer[row * cols + col].re).collect())
+            .collect()
+    }
+
+    /// Compute one evolution of every cell
+    pub fn step(&mut self) {
+        let LeniaParameters { mu, sigma, dt, .. } = self.parameters;
+        let potential = self.potential();
+        for (values, potentials) in self.field.iter_mut().zip(potential) {
+            for (value, potential) in values.iter_mut().zip(potentials) {
+                let grown = *value as f64 + dt as f64 * growth(potential, mu as f64, sigma as f64);
+                *value = grown.clamp(0.0, 1.0) as f32;
+            }
+        }
+        self.generation += 1;
+    }
+
+    /// Compute `n` evolutions
+    pub fn run(&mut self, n: i32) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// Color map of the field, the value of every cell
+    pub fn colored_map(&self) -> ColoredMap {
+        self.field.clone()
+    }
+
+    /// Universe with the value of every cell as B and its complement as A
+    pub fn universe(&self) -> Universe {
+        self.field
+            .iter()
+            .map(|row| row.iter().map(|value| Cell { a: 1.0 - value, b: *value }).collect())
+            .collect()
+    }
+}
@@ -0,0 +1,176 @@
+/// Periodic statistics of a run
+/// Every `interval` generations, a line with the means and variances of A and
+/// B, the number of active cells and the dominant wavelength is appended to a
+/// CSV file, or a JSON lines file if its extension is `.jsonl` or `.json`
+/// (with the `json` feature), so a run can be analyzed without custom code.
+/// Everything but the configuration and the records requires the `fs`
+/// feature
+#[cfg(feature = "fs")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "fs")]
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::dominant_wavelength;
+use crate::error::check_interval;
+use crate::stats::stats;
+use crate::{Float, SimulationError, Universe};
+#[cfg(feature = "fs")]
+use crate::Simulation;
+
+/// Header of the CSV files, naming the fields of `StatsRecord`
+pub const CSV_HEADER: &str = "generation,a_mean,a_variance,b_mean,b_variance,active_cells,wavelength";
+
+/// Where and how often the statistics are logged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatsLogConfig {
+    /// File the lines are appended to
+    pub path: PathBuf,
+    /// A line is written every `interval` generations
+    pub interval: i32,
+    /// Concentration of B over which a cell counts as active
+    pub active_threshold: f64,
+    /// Compute the dominant wavelength, the slowest of the statistics
+    pub wavelength: bool,
+}
+
+impl Default for StatsLogConfig {
+    fn default() -> Self {
+        StatsLogConfig { path: PathBuf::from("{run}_stats.csv"), interval: 10, active_threshold: 0.1, wavelength: true }
+    }
+}
+
+impl StatsLogConfig {
+    /// Fails if the interval is below 1
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        check_interval("statistics", self.interval)
+    }
+}
+
+/// Statistics logged for one generation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatsRecord {
+    pub generation: i32,
+    pub a_mean: f64,
+    pub a_variance: f64,
+    pub b_mean: f64,
+    pub b_variance: f64,
+    /// Number of cells whose B is over the threshold
+    pub active_cells: usize,
+    /// Dominant wavelength of B in cells, if there is a pattern, see
+    /// `analysis`
+    pub wavelength: Option<f64>,
+}
+
+impl StatsRecord {
+    /// Statistics of `universe` at `generation`, counting the cells whose B is
+    /// over `active_threshold`
+    pub fn of<T: Float>(generation: i32, universe: &Universe<T>, active_threshold: f64, wavelength: bool) -> StatsRecord {
+        let stats = stats(universe);
+        let active_cells = universe.iter().flatten().filter(|cell| cell.b.to_f64() > active_threshold).count();
+        StatsRecord {
+            generation,
+            a_mean: stats.a.mean,
+            a_variance: stats.a.variance,
+            b_mean: stats.b.mean,
+            b_variance: stats.b.variance,
+            active_cells,
+            wavelength: wavelength.then(|| dominant_wavelength(universe)).flatten().map(|found| found.wavelength),
+        }
+    }
+
+    /// Line of a CSV file with `CSV_HEADER`, the wavelength left empty if
+    /// there is none
+    pub fn csv_line(&self) -> String {
+        let wavelength = self.wavelength.map(|wavelength| wavelength.to_string()).unwrap_or_default();
+        format!(
+            "{},{},{},{},{},{},{wavelength}",
+            self.generation, self.a_mean, self.a_variance, self.b_mean, self.b_variance, self.active_cells
+        )
+    }
+}
+
+/// Format of a log file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl LogFormat {
+    /// Format given by the extension of `path`, CSV unless it is `.jsonl` or
+    /// `.json`
+    pub fn from_path(path: &Path) -> LogFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "json") => LogFormat::JsonLines,
+            _ => LogFormat::Csv,
+        }
+    }
+}
+
+/// Writer of the statistics following a `StatsLogConfig`
+#[cfg(feature = "fs")]
+pub struct StatsLogger {
+    config: StatsLogConfig,
+    format: LogFormat,
+    writer: BufWriter<File>,
+}
+
+#[cfg(feature = "fs")]
+impl StatsLogger {
+    /// Open the log file to append to it, writing the CSV header if the file
+    /// is new or empty
+    /// Fails if the configuration is invalid, if the file cannot be opened,
+    /// or if it is JSON lines without the `json` feature
+    pub fn new(config: StatsLogConfig) -> Result<StatsLogger, SimulationError> {
+        config.validate()?;
+        let format = LogFormat::from_path(&config.path);
+        #[cfg(not(feature = "json"))]
+        if format == LogFormat::JsonLines {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "JSON lines require the `json` feature").into());
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        let empty = file.metadata()?.len() == 0;
+        let mut writer = BufWriter::new(file);
+        if format == LogFormat::Csv && empty {
+            writeln!(writer, "{CSV_HEADER}")?;
+        }
+        Ok(StatsLogger { config, format, writer })
+    }
+
+    /// Whether a line has to be written after computing `generation`
+    pub fn is_due(&self, generation: i32) -> bool {
+        generation % self.config.interval == 0
+    }
+
+    /// Append the statistics of the current universe of `simulation`, and
+    /// return them
+    pub fn log<T: Float>(&mut self, simulation: &Simulation<T>) -> io::Result<StatsRecord> {
+        let record = StatsRecord::of(
+            simulation.generation(),
+            simulation.universe(),
+            self.config.active_threshold,
+            self.config.wavelength,
+        );
+        match self.format {
+            LogFormat::Csv => writeln!(self.writer, "{}", record.csv_line())?,
+            #[cfg(feature = "json")]
+            LogFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, &record)?;
+                writeln!(self.writer)?;
+            }
+            #[cfg(not(feature = "json"))]
+            LogFormat::JsonLines => unreachable!("refused by `new`"),
+        }
+        Ok(record)
+    }
+
+    /// Write the buffered lines to the file
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
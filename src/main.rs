@@ -1,18 +1,2618 @@
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[cfg(feature = "bevy")]
+use ca_turing_pattern::app::{self, SimulationState};
+#[cfg(feature = "bevy")]
+use ca_turing_pattern::scene::{self, SurfaceState};
+use ca_turing_pattern::activation::ActivationTracking;
+use ca_turing_pattern::activity::ActivityTracking;
+use ca_turing_pattern::adaptive::{AdaptiveConfig, AdaptiveSimulation};
+use ca_turing_pattern::animation::{Animation, AnimationConfig};
+use ca_turing_pattern::bench::{bench, Backend, BenchConfig, BACKENDS, BACKEND_NAMES};
+use ca_turing_pattern::bifurcation::{Bifurcation, BifurcationMetric, PathPoint, BIFURCATION_METRIC_NAMES};
+use ca_turing_pattern::chunked::{ChunkedConfig, ChunkedSimulation};
+use ca_turing_pattern::checkpoint::{latest_checkpoint, CheckpointPolicy, Checkpointer};
+use ca_turing_pattern::colormap::{Colormap, Palette, COLORMAP_NAMES};
+use ca_turing_pattern::config::Config;
+use ca_turing_pattern::conservation::{ConservationCheck, MassBalance};
+use ca_turing_pattern::deterministic::{self, portable_rng};
+use ca_turing_pattern::diff::{compare, difference_map};
+use ca_turing_pattern::symmetry::{Symmetry, SYMMETRY_NAMES};
+use ca_turing_pattern::export::{
+    save_colored_map, save_fields, save_height_map, save_normal_map, FrameSequence, FrameSequenceConfig,
+    NormalMapConfig, Species,
+};
+use ca_turing_pattern::initial::{Channel, ImageSeed, InitialCondition, CHANNEL_NAMES, INITIAL_CONDITION_NAMES};
+use ca_turing_pattern::graph::{draw_graph, force_layout, initialize_graph, load_layout, Graph, GraphSimulation};
+use ca_turing_pattern::layers::check_couplings;
+use ca_turing_pattern::modulation::{Field, Modulation, ModulationConfig, FIELD_NAMES};
+use ca_turing_pattern::noise::{Noise, NoiseConfig};
+use ca_turing_pattern::reaction::ReactionConfig;
+use ca_turing_pattern::reference::{Divergence, Validation};
+use ca_turing_pattern::logger::{StatsLogConfig, StatsLogger};
+use ca_turing_pattern::lenia::{initialize_lenia, LeniaParameters, LeniaSimulation};
+use ca_turing_pattern::mesh::{Mesh, MeshConfig};
+use ca_turing_pattern::presets::{find_preset, PresetLibrary, PRESETS_PATH};
+use ca_turing_pattern::profile;
+use ca_turing_pattern::render::{
+    DisplayMode, Downsampling, PixelFormat, DISPLAY_MODE_NAMES, DOWNSAMPLING_NAMES, PIXEL_FORMAT_NAMES,
+};
+use ca_turing_pattern::readback::FieldSnapshot;
+use ca_turing_pattern::replay::{apply_event, Recorder, Replay};
+#[cfg(feature = "bevy")]
+#[cfg(feature = "bevy")]
+use ca_turing_pattern::rewind::RewindBuffer;
+#[cfg(feature = "bevy")]
+use ca_turing_pattern::session::{Brush, Session};
+#[cfg(feature = "server")]
+use ca_turing_pattern::server::{serve, ServerConfig};
+#[cfg(feature = "control")]
+use ca_turing_pattern::control::ControlConfig;
+use ca_turing_pattern::snapshot::Snapshot;
+use ca_turing_pattern::streams::RngStream;
+use ca_turing_pattern::surface::{initialize_surface, SurfaceSimulation};
+use ca_turing_pattern::sweep::{Sweep, SweepRange};
+use ca_turing_pattern::texture::{TextureBatch, TextureConfig};
+use ca_turing_pattern::triggers::TriggerWatcher;
 use ca_turing_pattern::*;
+use clap::{Args, Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
-fn main() {
+/// Cellular automaton simulation of Turing patterns
+#[derive(Parser, Debug)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(flatten)]
+    run: RunArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Arguments of the `run` and `render` commands, also taken without a
+/// command
+#[derive(Args, Debug)]
+struct RunArgs {
+    /// RON or TOML file with the configuration of the run; the other
+    /// arguments override its values. In the window, its parameters are
+    /// applied again whenever they change in it
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Template of the name of the run, titling the window and written
+    /// instead of `{run}` in the paths of the outputs, with the fields f, k,
+    /// d_a, d_b, r, seed, rows, cols and steps between braces
+    /// [default: gs_f{f}_k{k}_seed{seed}]
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Number of rows of the universe [default: 600]
+    #[arg(long)]
+    rows: Option<usize>,
+
+    /// Number of columns of the universe [default: 600]
+    #[arg(long)]
+    cols: Option<usize>,
+
+    /// Named parameter set used as a base for the individual parameters,
+    /// from assets/presets.ron or built in
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Diffusion rate for element A
+    #[arg(long)]
+    d_a: Option<f32>,
+
+    /// Diffusion rate for element B
+    #[arg(long)]
+    d_b: Option<f32>,
+
+    /// Feed rate for element A
+    #[arg(long)]
+    f: Option<f32>,
+
+    /// Death reaction rate for element B
+    #[arg(long)]
+    k: Option<f32>,
+
+    /// Reproduction reaction rate
+    #[arg(long)]
+    r: Option<f32>,
+
+    /// Seed for the initial state; random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Seed of the initial universe alone, instead of `--seed`
+    #[arg(long)]
+    initial_seed: Option<u64>,
+
+    /// Seed of the noise alone, derived from `--seed` if not given
+    #[arg(long)]
+    noise_seed: Option<u64>,
+
+    /// Seed of the jitter of the brush alone, derived from `--seed` if not
+    /// given
+    #[arg(long)]
+    brush_seed: Option<u64>,
+
+    /// Add to B after every evolution a number drawn uniformly from
+    /// [-amplitude, amplitude], e.g. 0.001
+    #[arg(long)]
+    noise: Option<f64>,
+
+    /// Number of evolutions to compute [default: 700]
+    #[arg(long)]
+    steps: Option<i32>,
+
+    /// Image file where the final color map of a headless run is saved
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// File where the final concentrations of A and B of a headless run are
+    /// saved, as `<name>_a` and `<name>_b` (`.csv` for CSV, NumPy otherwise)
+    #[arg(long)]
+    fields: Option<PathBuf>,
+
+    /// 16-bit PNG file where the height map of B at the end of a headless
+    /// run is saved
+    #[arg(long)]
+    height_map: Option<PathBuf>,
+
+    /// Image file where the tangent-space normal map of B at the end of a
+    /// headless run is saved
+    #[arg(long)]
+    normal_map: Option<PathBuf>,
+
+    /// Height of the surface of the normal map between the lowest and
+    /// highest B, in cells [default: 4]
+    #[arg(long)]
+    normal_strength: Option<f32>,
+
+    /// Image file where the generation at which each cell was activated is
+    /// saved at the end of a headless run, the earliest cells brightest
+    #[arg(long)]
+    activation_map: Option<PathBuf>,
+
+    /// Concentration of B over which a cell is activated, see
+    /// `--activation-map` [default: 0.1]
+    #[arg(long)]
+    activation_threshold: Option<f64>,
+
+    /// File where a mesh of the surface displaced by B at the end of a
+    /// headless run is saved (`.glb` for binary glTF, OBJ otherwise)
+    #[arg(long)]
+    mesh: Option<PathBuf>,
+
+    /// Height of the mesh between the lowest and highest B, in cells
+    /// [default: 10]
+    #[arg(long)]
+    mesh_height: Option<f32>,
+
+    /// Thickness of the solid below the mesh, in cells, 0 for an open
+    /// surface [default: 0]
+    #[arg(long)]
+    mesh_base: Option<f32>,
+
+    /// Directory where headless runs write numbered PNG frames
+    #[arg(long)]
+    frames_dir: Option<PathBuf>,
+
+    /// Write a frame every this many generations [default: 10]
+    #[arg(long)]
+    frame_interval: Option<i32>,
+
+    /// Animation written at the end of a headless run: GIF for `.gif`, APNG
+    /// for `.png` or `.apng`, lossless WebP for `.webp`
+    #[arg(long)]
+    animation: Option<PathBuf>,
+
+    /// Capture a frame of the animation every this many generations
+    /// [default: 10]
+    #[arg(long)]
+    animation_interval: Option<i32>,
+
+    /// Milliseconds each frame of the animation is shown for [default: 40]
+    #[arg(long)]
+    animation_delay: Option<u32>,
+
+    /// Color map of the images and frames [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Color value from which the binary color map is white [default: 0.5]
+    #[arg(long, conflicts_with = "colormap_image")]
+    binary_threshold: Option<f32>,
+
+    /// Image whose dominant colors, from the darkest to the lightest, make
+    /// the color map instead of `--colormap`
+    #[arg(long, conflicts_with = "colormap")]
+    colormap_image: Option<PathBuf>,
+
+    /// Number of colors taken from `--colormap-image` [default: 5]
+    #[arg(long, requires = "colormap_image")]
+    palette_colors: Option<usize>,
+
+    /// Generated initial state, with its default settings, instead of the
+    /// random cells: random, empty (without seeds, to place them in the
+    /// window), square, noise, perlin, circles or stripes
+    #[arg(long)]
+    initial: Option<String>,
+
+    /// PNG or JPEG image giving the initial concentration of B; the universe
+    /// takes its size
+    #[arg(long)]
+    from_image: Option<PathBuf>,
+
+    /// Channel of the image read as B: luminance, red, green, blue or alpha
+    /// [default: luminance]
+    #[arg(long)]
+    image_channel: Option<String>,
+
+    /// Give the most B to the darkest pixels of the image
+    #[arg(long)]
+    invert_image: bool,
+
+    /// Snapshot whose universe is used as the initial state
+    #[arg(long)]
+    from_snapshot: Option<PathBuf>,
+
+    /// Snapshot file where the final universe of a headless run is saved
+    /// (`.json` for JSON, bincode otherwise)
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+
+    /// CSV file, or JSON lines file if its extension is `.jsonl`, where the
+    /// statistics of a headless run are appended
+    #[arg(long)]
+    stats_log: Option<PathBuf>,
+
+    /// Generations between two lines of the statistics [default: 10]
+    #[arg(long)]
+    stats_log_interval: Option<i32>,
+
+    /// Write a checkpoint every this many generations
+    #[arg(long)]
+    checkpoint_interval: Option<i32>,
+
+    /// Directory where checkpoints are written, `{run}` standing for the
+    /// name of the run [default: checkpoints/{run}]
+    #[arg(long)]
+    checkpoint_dir: Option<PathBuf>,
+
+    /// Number of checkpoints kept, 0 keeps all of them [default: 3]
+    #[arg(long)]
+    checkpoint_keep: Option<usize>,
+
+    /// Continue the run saved in a checkpoint, or in the latest checkpoint
+    /// of a directory, until `steps` generations are reached, with its
+    /// parameters and edges unless others are given
+    #[arg(long)]
+    resume: Option<PathBuf>,
+
+    /// Serve the run over WebSocket on this address instead of opening a
+    /// window, e.g. 127.0.0.1:9001
+    #[cfg(feature = "server")]
+    #[arg(long)]
+    serve: Option<String>,
+
+    /// Send a frame to the WebSocket clients every this many generations
+    /// [default: 10]
+    #[cfg(feature = "server")]
+    #[arg(long)]
+    stream_interval: Option<i32>,
+
+    /// Change the parameters from the OSC messages received on this
+    /// address, e.g. 0.0.0.0:9000, see `control`
+    #[cfg(feature = "control")]
+    #[arg(long)]
+    osc: Option<String>,
+
+    /// Change the parameters from the MIDI control changes read from this
+    /// device, e.g. /dev/midi1
+    #[cfg(feature = "control")]
+    #[arg(long)]
+    midi: Option<PathBuf>,
+
+    /// Record the seed and the changes made during the run into this replay
+    /// file
+    #[arg(long, conflicts_with_all = ["resume", "replay"])]
+    record: Option<PathBuf>,
+
+    /// Compute again the run recorded in this replay file
+    #[arg(long, conflicts_with = "resume")]
+    replay: Option<PathBuf>,
+
+    /// Open the window on the session saved in this file by the `session`
+    /// command of the console, with its settings instead of the other
+    /// options
+    #[cfg(feature = "bevy")]
+    #[arg(long, conflicts_with_all = ["config", "resume", "replay", "record", "headless"])]
+    session: Option<PathBuf>,
+
+    /// Handling of concentrations leaving [0,1]: unchecked, clamp, or strict
+    /// to stop with an error [default: unchecked]
+    #[arg(long)]
+    bounds: Option<String>,
+
+    /// Edges of the universe: closed, or periodic to join opposite edges
+    /// [default: closed]
+    #[arg(long)]
+    boundary: Option<String>,
+
+    /// Computation of the diffusion: cellwise, or conservative for pairwise
+    /// fluxes that conserve the mass to the rounding of their sums
+    /// [default: cellwise]
+    #[arg(long)]
+    stencil: Option<String>,
+
+    /// Skip the tiles of the universe whose concentrations changed by less
+    /// than this amount during the previous evolution, e.g. 1e-6
+    #[arg(long)]
+    skip_quiescent: Option<f64>,
+
+    /// Check after every evolution that the total A+B only changed by what
+    /// the reaction terms add and remove, reporting leaks larger than this
+    /// fraction of the total, e.g. 1e-5
+    #[arg(long)]
+    check_conservation: Option<f64>,
+
+    /// Stop with an error at the first leak of mass instead of logging it,
+    /// see `--check-conservation`
+    #[arg(long)]
+    strict_conservation: bool,
+
+    /// Generations between two scans of the universe for concentrations
+    /// that are not finite or far out of [0,1], which pause the window or
+    /// stop the run; 0 to disable the scans [default: 10]
+    #[arg(long)]
+    blowup_interval: Option<i32>,
+
+    /// Distance out of [0,1] a concentration reaches before it counts as
+    /// blown up [default: 1]
+    #[arg(long)]
+    blowup_margin: Option<f32>,
+
+    /// Place the random initial cells with this symmetry around the center:
+    /// mirror, rotational:<order> or dihedral:<order>, e.g. rotational:6
+    #[arg(long)]
+    symmetry: Option<String>,
+
+    /// Project the universe onto the symmetry of `--symmetry` after every
+    /// evolution, so that it keeps it
+    #[arg(long, requires = "symmetry")]
+    enforce_symmetry: bool,
+
+    /// Field scaling `f` and `k` across the universe: gradient, radial or
+    /// wave, see `--modulation-f` and `--modulation-k`
+    #[arg(long)]
+    modulation: Option<String>,
+
+    /// PNG or JPEG image whose luminance scales `f` and `k`, stretched over
+    /// the universe, instead of `--modulation`
+    #[arg(long, conflicts_with = "modulation")]
+    modulation_image: Option<PathBuf>,
+
+    /// Factors of `f` where the modulation field is 0 and where it is 1,
+    /// e.g. `0.8,1.2` [default: 1,1]
+    #[arg(long, value_delimiter = ',')]
+    modulation_f: Vec<f32>,
+
+    /// Factors of `k` where the modulation field is 0 and where it is 1
+    /// [default: 1,1]
+    #[arg(long, value_delimiter = ',')]
+    modulation_k: Vec<f32>,
+
+    /// Rhai expression of the change of A of a cell over one evolution, of
+    /// `a`, `b`, `d_a`, `d_b`, `f`, `k` and `r`, e.g.
+    /// `f * (1.0 - a) - r * a * b * b` (needs the `scripting` feature)
+    #[arg(long)]
+    reaction_a: Option<String>,
+
+    /// Rhai expression of the change of B of a cell over one evolution, e.g.
+    /// `r * a * b * b - k * b`
+    #[arg(long)]
+    reaction_b: Option<String>,
+
+    /// Another simulation drawn next to the first one in the window, from
+    /// the same initial universe: a preset name or changes to the
+    /// parameters, e.g. `k=0.062,f=0.035`; can be repeated
+    #[arg(long)]
+    compare: Vec<String>,
+
+    /// Draw blocks of this many cells on each side as one pixel in the
+    /// window, 0 to fit large universes within 1024 pixels [default: 0]
+    #[arg(long)]
+    render_factor: Option<usize>,
+
+    /// Reduction of the blocks drawn as one pixel: nearest or average
+    /// [default: average]
+    #[arg(long)]
+    render_downsampling: Option<String>,
+
+    /// Pixels of the textures of the window: rgba8, or r8 to upload one
+    /// byte per pixel and color it when drawn [default: rgba8]
+    #[arg(long)]
+    pixel_format: Option<String>,
+
+    /// What the window draws: colormap, or composite for A, B and the B of
+    /// the next layer in the red, green and blue channels [default: colormap]
+    #[arg(long)]
+    display: Option<String>,
+
+    /// Number of keyframes kept to rewind the run in the window, 0 to
+    /// disable rewinding [default: 50]
+    #[arg(long)]
+    rewind_keyframes: Option<usize>,
+
+    /// Generations between two keyframes kept to rewind the run, 1 for
+    /// every generation [default: 10]
+    #[arg(long)]
+    rewind_interval: Option<i32>,
+
+    /// Run without opening a window
+    #[arg(long)]
+    headless: bool,
+
+    /// Print the time spent in each stage of the simulation at the end of
+    /// the run, or when the window is closed
+    #[arg(long)]
+    profile: bool,
+
+    /// Compute the run bit for bit the same on every platform, printing the
+    /// exact hash of the final universe of a headless run; needs a seed
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Fail unless the exact hash of the final universe of the headless run
+    /// is this one, in hexadecimal, e.g. one printed by `--deterministic`
+    #[arg(long, requires = "deterministic", value_parser = hash_from_hex)]
+    expect_hash: Option<u64>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Open the window on the simulation, or run it headless with
+    /// `--headless`, as without a command
+    Run(RunArgs),
+    /// Run the simulation headless, writing the frames, images and exports
+    /// of the output
+    Render(RunArgs),
+    /// Convert a snapshot to an image of its color map, its fields as CSV or
+    /// NumPy, or a mesh, chosen by the extension of the output
+    Export(ExportArgs),
+    /// Print the statistics, dominant wavelength and kind of pattern of a
+    /// snapshot
+    Analyze(AnalyzeArgs),
+    /// Compare two snapshots of the same dimensions, printing their L2
+    /// distance and structural similarity, and draw their differences
+    Diff(DiffArgs),
+    /// Run every combination of ranges of parameters headless, writing the
+    /// color map of every run, a summary CSV and a montage
+    Sweep(SweepArgs),
+    /// Move `f` and `k` slowly along a path through the (f, k) plane,
+    /// recording the pattern at evenly spaced points, and draw the
+    /// bifurcation diagram
+    Bifurcation(BifurcationArgs),
+    /// Generate seamless tileable textures of presets headless, writing one
+    /// image per preset and seed
+    Generate(GenerateArgs),
+    /// Evolve a pattern over the vertices of an OBJ mesh and show it on the
+    /// model, or write the colored mesh
+    Surface(SurfaceArgs),
+    /// Evolve a Lenia field, a continuous automaton with a large smooth
+    /// kernel, headless
+    Lenia(LeniaArgs),
+    /// Evolve the nodes of a graph read from an edge list, headless, and
+    /// draw it
+    Graph(GraphArgs),
+    /// Evolve a universe on a grid refined where the patterns are steep and
+    /// coarsened where they are flat, headless (experimental)
+    Adaptive(AdaptiveArgs),
+    /// Evolve a universe without edges, allocated in chunks as the pattern
+    /// grows, headless (experimental)
+    Infinite(InfiniteArgs),
+    /// Step a simulation next to the reference implementation from the same
+    /// seed, printing their largest difference at every generation
+    Validate(ValidateArgs),
+    /// Time the evolution on this machine, printing the steps per second of
+    /// each backend
+    Bench(BenchArgs),
+}
+
+/// Arguments of the `export` command
+#[derive(Args, Debug)]
+struct ExportArgs {
+    /// Snapshot to convert
+    snapshot: PathBuf,
+
+    /// File written: a PNG or JPEG image of the color map, the fields as
+    /// `<name>_a` and `<name>_b` for `.csv` or `.npy`, or a mesh of B for
+    /// `.obj` or `.glb`
+    output: PathBuf,
+
+    /// Color map of the image [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Height of the mesh between the lowest and highest B, in cells
+    /// [default: 10]
+    #[arg(long)]
+    mesh_height: Option<f32>,
+
+    /// Thickness of the solid below the mesh, in cells, 0 for an open
+    /// surface [default: 0]
+    #[arg(long)]
+    mesh_base: Option<f32>,
+}
+
+/// Arguments of the `analyze` command
+#[derive(Args, Debug)]
+struct AnalyzeArgs {
+    /// Snapshot to analyze
+    snapshot: PathBuf,
+
+    /// CSV file where the radial profile of the power spectrum of B is
+    /// written, one line per wavenumber
+    #[arg(long)]
+    spectrum: Option<PathBuf>,
+}
+
+/// Arguments of the `diff` command
+#[derive(Args, Debug)]
+struct DiffArgs {
+    /// Snapshot compared
+    first: PathBuf,
+
+    /// Snapshot it is compared with
+    second: PathBuf,
+
+    /// Image where the differences are drawn, from black where the snapshots
+    /// are equal to white where they differ the most with the gray color map
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Species whose differences are drawn, `a` or `b` [default: b]
+    #[arg(long)]
+    species: Option<String>,
+
+    /// Color map of the image [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+}
+
+/// Arguments of the `sweep` command
+/// Ranges are written `start:end:count`, e.g. `0.02:0.06:5`, or as a single
+/// value
+#[derive(Args, Debug)]
+struct SweepArgs {
+    /// RON or TOML file with the settings of the sweep; the other arguments
+    /// override its values
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Named parameter set giving the parameters without a range
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Range of feed rates for element A
+    #[arg(long)]
+    f: Option<SweepRange>,
+
+    /// Range of death reaction rates for element B
+    #[arg(long)]
+    k: Option<SweepRange>,
+
+    /// Range of diffusion rates for element A
+    #[arg(long)]
+    d_a: Option<SweepRange>,
+
+    /// Range of diffusion rates for element B
+    #[arg(long)]
+    d_b: Option<SweepRange>,
+
+    /// Range of reproduction reaction rates
+    #[arg(long)]
+    r: Option<SweepRange>,
+
+    /// Number of rows of the universes [default: 200]
+    #[arg(long)]
+    rows: Option<usize>,
+
+    /// Number of columns of the universes [default: 200]
+    #[arg(long)]
+    cols: Option<usize>,
+
+    /// Number of evolutions of every run [default: 2000]
+    #[arg(long)]
+    steps: Option<i32>,
+
+    /// Seed of the initial state shared by all the runs [default: 0]
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of runs computed at the same time [default: one per processor]
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Handling of concentrations leaving [0,1]: unchecked, clamp, or strict
+    /// to stop the run [default: unchecked]
+    #[arg(long)]
+    bounds: Option<String>,
+
+    /// Edges of the universes: closed or periodic [default: closed]
+    #[arg(long)]
+    boundary: Option<String>,
+
+    /// Color map of the images [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Color value from which the binary color map is white [default: 0.5]
+    #[arg(long)]
+    binary_threshold: Option<f32>,
+
+    /// Directory where the results are written
+    #[arg(long, default_value = "sweep")]
+    output_dir: PathBuf,
+}
+
+/// Arguments of the `bifurcation` command
+#[derive(Args, Debug)]
+struct BifurcationArgs {
+    /// RON or TOML file with the settings of the run; the other arguments
+    /// override its values
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Named parameter set giving the parameters other than `f` and `k`
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Point `f,k` the path goes through, in order; repeat it for every
+    /// point, e.g. `--through 0.03,0.06 --through 0.05,0.065`
+    #[arg(long, allow_hyphen_values = true)]
+    through: Vec<PathPoint>,
+
+    /// Number of points where the pattern is recorded [default: 50]
+    #[arg(long)]
+    samples: Option<usize>,
+
+    /// Generations computed at the first point before the first record
+    /// [default: 2000]
+    #[arg(long)]
+    settle: Option<i32>,
+
+    /// Generations between two records [default: 500]
+    #[arg(long)]
+    steps: Option<i32>,
+
+    /// Number of rows of the universe [default: 128]
+    #[arg(long)]
+    rows: Option<usize>,
+
+    /// Number of columns of the universe [default: 128]
+    #[arg(long)]
+    cols: Option<usize>,
+
+    /// Initial state of the universe: random, empty, square, noise, perlin,
+    /// circles or stripes [default: random]
+    #[arg(long)]
+    initial: Option<String>,
+
+    /// Seed of the initial state [default: 0]
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Handling of concentrations leaving [0,1]: unchecked, clamp, or strict
+    /// to stop the run [default: unchecked]
+    #[arg(long)]
+    bounds: Option<String>,
+
+    /// Edges of the universe: closed or periodic [default: closed]
+    #[arg(long)]
+    boundary: Option<String>,
+
+    /// Measure drawn on the diagram: amplitude or wavelength
+    /// [default: amplitude]
+    #[arg(long)]
+    metric: Option<String>,
+
+    /// Directory where the records and the diagram are written
+    #[arg(long, default_value = "bifurcation")]
+    output_dir: PathBuf,
+}
+
+/// Arguments of the `generate` command
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    /// Named parameter sets of the patterns, comma separated or repeated
+    #[arg(long, required = true, value_delimiter = ',')]
+    preset: Vec<String>,
+
+    /// Seed of the first texture of every preset [default: 0]
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of textures of every preset, from consecutive seeds
+    #[arg(long, default_value_t = 1)]
+    count: usize,
+
+    /// Number of pixels on each side of the textures, a power of two
+    /// [default: 512]
+    #[arg(long)]
+    size: Option<u32>,
+
+    /// Number of cells on each side of the universes; the fewer, the larger
+    /// the features of the patterns [default: 256]
+    #[arg(long)]
+    cells: Option<usize>,
+
+    /// Number of samples on each side of a pixel [default: 2]
+    #[arg(long)]
+    supersampling: Option<u32>,
+
+    /// Largest number of evolutions of every texture, fewer if its pattern
+    /// converges before [default: 20000]
+    #[arg(long)]
+    steps: Option<i32>,
+
+    /// Number of random cells starting with A and B present [default: 20]
+    #[arg(long)]
+    initial_cells: Option<usize>,
+
+    /// Color map of the textures [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Color value from which the binary color map is white [default: 0.5]
+    #[arg(long, conflicts_with = "colormap_image")]
+    binary_threshold: Option<f32>,
+
+    /// Image whose dominant colors, from the darkest to the lightest, make
+    /// the color map instead of `--colormap`
+    #[arg(long, conflicts_with = "colormap")]
+    colormap_image: Option<PathBuf>,
+
+    /// Number of colors taken from `--colormap-image` [default: 5]
+    #[arg(long, requires = "colormap_image")]
+    palette_colors: Option<usize>,
+
+    /// Number of textures generated at the same time [default: one per
+    /// processor]
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Directory where the textures are written
+    #[arg(long, default_value = "textures")]
+    output_dir: PathBuf,
+}
+
+/// Arguments of the `surface` command
+#[derive(Args, Debug)]
+struct SurfaceArgs {
+    /// OBJ file of the mesh whose vertices are evolved
+    mesh: PathBuf,
+
+    /// Named parameter set of the pattern [default: default]
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Seed of the initial vertices; random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of random vertices starting with A and B present
+    #[arg(long, default_value_t = 20)]
+    initial_cells: usize,
+
+    /// Number of evolutions to compute
+    #[arg(long, default_value_t = 5000)]
+    steps: i32,
+
+    /// Evolutions computed every frame in the window
+    #[arg(long, default_value_t = 10)]
+    steps_per_frame: i32,
+
+    /// Color map of the vertices [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// File where the mesh with the final colors of its vertices is saved
+    /// (`.glb` for binary glTF, OBJ otherwise)
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Run without opening a window
+    #[arg(long, requires = "output")]
+    headless: bool,
+}
+
+/// Arguments of the `adaptive` command
+#[derive(Args, Debug)]
+struct AdaptiveArgs {
+    /// Named parameter set of the pattern [default: default]
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Number of rows of the universe, a multiple of the tile size
+    #[arg(long, default_value_t = 256)]
+    rows: usize,
+
+    /// Number of columns of the universe, a multiple of the tile size
+    #[arg(long, default_value_t = 256)]
+    cols: usize,
+
+    /// Seed of the initial universe; random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of random cells starting with A and B present
+    #[arg(long, default_value_t = 20)]
+    initial_cells: usize,
+
+    /// Number of evolutions to compute
+    #[arg(long, default_value_t = 2000)]
+    steps: i32,
+
+    /// Edges of the universe: closed or periodic [default: closed]
+    #[arg(long)]
+    boundary: Option<String>,
+
+    /// Number of cells on each side of the tiles [default: 32]
+    #[arg(long)]
+    tile_size: Option<usize>,
+
+    /// Number of times a tile can be coarsened [default: 3]
+    #[arg(long)]
+    max_level: Option<u32>,
+
+    /// Change between neighbouring cells over which a tile is refined
+    /// [default: 0.02]
+    #[arg(long)]
+    refine: Option<f64>,
+
+    /// Change between neighbouring cells under which a tile is coarsened
+    /// [default: 0.002]
+    #[arg(long)]
+    coarsen: Option<f64>,
+
+    /// Color map of the image [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Image file where the final color map is saved
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
 
-    let parameters: Parameters = Parameters {
-        d_a: 0.6,
-        d_b: 0.3,
-        f: 0.2,
-        k: 0.1,
-        r: 0.5,
+impl AdaptiveArgs {
+    /// Settings of the grid, the default ones overridden by the arguments
+    fn config(&self) -> AdaptiveConfig {
+        let default = AdaptiveConfig::default();
+        AdaptiveConfig {
+            tile_size: self.tile_size.unwrap_or(default.tile_size),
+            max_level: self.max_level.unwrap_or(default.max_level),
+            refine: self.refine.unwrap_or(default.refine),
+            coarsen: self.coarsen.unwrap_or(default.coarsen),
+            ..default
+        }
+    }
+}
+
+/// Arguments of the `infinite` command
+#[derive(Args, Debug)]
+struct InfiniteArgs {
+    /// Named parameter set of the pattern [default: default]
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Number of rows of the initial region, placed at row 0 and column 0
+    #[arg(long, default_value_t = 64)]
+    rows: usize,
+
+    /// Number of columns of the initial region
+    #[arg(long, default_value_t = 64)]
+    cols: usize,
+
+    /// Initial state of the region: random, square, noise, perlin, circles
+    /// or stripes, in a universe otherwise full of A [default: square]
+    #[arg(long)]
+    initial: Option<String>,
+
+    /// Seed of the initial region; random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of evolutions to compute
+    #[arg(long, default_value_t = 2000)]
+    steps: i32,
+
+    /// Number of cells on each side of the chunks [default: 64]
+    #[arg(long)]
+    chunk_size: Option<usize>,
+
+    /// Color map of the image [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Image file where the color map of every allocated chunk at the end
+    /// is saved
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// Arguments of the `validate` command
+#[derive(Args, Debug)]
+struct ValidateArgs {
+    /// Named parameter set of the simulation [default: default]
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Number of rows of the universe
+    #[arg(long, default_value_t = 128)]
+    rows: usize,
+
+    /// Number of columns of the universe
+    #[arg(long, default_value_t = 128)]
+    cols: usize,
+
+    /// Seed of the initial universe
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Number of random cells starting with A and B present
+    #[arg(long, default_value_t = 20)]
+    initial_cells: usize,
+
+    /// Number of evolutions to compute
+    #[arg(long, default_value_t = 200)]
+    steps: i32,
+
+    /// Edges of the universe: closed or periodic [default: closed]
+    #[arg(long)]
+    boundary: Option<String>,
+
+    /// Validate the evolution of the active tiles only, skipping those
+    /// which changed by less than this amount
+    #[arg(long)]
+    skip_quiescent: Option<f64>,
+
+    /// Fail as soon as a concentration differs from the reference by more
+    /// than this amount
+    #[arg(long)]
+    tolerance: Option<f64>,
+}
+
+/// Arguments of the `bench` command
+#[derive(Args, Debug)]
+struct BenchArgs {
+    /// Backend timed: f32, f64, f16 or reference, repeated to time
+    /// several, or all [default: f32]
+    #[arg(long)]
+    backend: Vec<String>,
+
+    /// Named parameter set of the simulation [default: default]
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Number of rows of the universe
+    #[arg(long, default_value_t = 256)]
+    rows: usize,
+
+    /// Number of columns of the universe
+    #[arg(long, default_value_t = 256)]
+    cols: usize,
+
+    /// Number of evolutions timed
+    #[arg(long, default_value_t = 200)]
+    steps: i32,
+
+    /// Number of evolutions computed before the timed ones
+    #[arg(long, default_value_t = 20)]
+    warmup: i32,
+
+    /// Seed of the initial universe
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Edges of the universe: closed or periodic [default: closed]
+    #[arg(long)]
+    boundary: Option<String>,
+
+    /// Computation of the diffusion: cellwise or conservative
+    /// [default: cellwise]
+    #[arg(long)]
+    stencil: Option<String>,
+
+    /// Skip the tiles which changed by less than this amount during the
+    /// previous evolution
+    #[arg(long)]
+    skip_quiescent: Option<f64>,
+}
+
+/// Arguments of the `graph` command
+#[derive(Args, Debug)]
+struct GraphArgs {
+    /// Edge list of the graph: two nodes, numbered from 0, and an optional
+    /// weight per line
+    edges: PathBuf,
+
+    /// Positions of the nodes, `x y` per line in the order of the nodes;
+    /// computed with a force-directed layout if not given
+    #[arg(long)]
+    layout: Option<PathBuf>,
+
+    /// Iterations of the force-directed layout
+    #[arg(long, default_value_t = 300, conflicts_with = "layout")]
+    layout_iterations: usize,
+
+    /// Named parameter set of the pattern [default: default]
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Seed of the initial nodes and the layout; random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of random nodes starting with A and B present
+    #[arg(long, default_value_t = 5)]
+    initial_cells: usize,
+
+    /// Number of evolutions to compute
+    #[arg(long, default_value_t = 5000)]
+    steps: i32,
+
+    /// Color map of the nodes [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Image file where the graph with the final colors of its nodes is drawn
+    #[arg(long, default_value = "graph.png")]
+    output: PathBuf,
+
+    /// Number of pixels on each side of the image
+    #[arg(long, default_value_t = 800)]
+    size: u32,
+
+    /// Radius of the nodes in the image, in pixels
+    #[arg(long, default_value_t = 4.0)]
+    node_radius: f32,
+}
+
+/// Arguments of the `lenia` command
+/// The parameters default to those of the glider Orbium
+#[derive(Args, Debug)]
+struct LeniaArgs {
+    /// Number of rows of the universe
+    #[arg(long, default_value_t = 128)]
+    rows: usize,
+
+    /// Number of columns of the universe
+    #[arg(long, default_value_t = 128)]
+    cols: usize,
+
+    /// Seed of the initial field; random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of evolutions to compute
+    #[arg(long, default_value_t = 500)]
+    steps: i32,
+
+    /// Radius of the kernel, in cells [default: 13]
+    #[arg(long)]
+    radius: Option<usize>,
+
+    /// Heights of the rings of the kernel, comma separated [default: 1]
+    #[arg(long, value_delimiter = ',')]
+    peaks: Vec<f32>,
+
+    /// Potential at which the growth is the largest [default: 0.15]
+    #[arg(long)]
+    mu: Option<f32>,
+
+    /// Width of the growth function [default: 0.015]
+    #[arg(long)]
+    sigma: Option<f32>,
+
+    /// Fraction of the growth added at every evolution [default: 0.1]
+    #[arg(long)]
+    dt: Option<f32>,
+
+    /// Edges of the universe: closed or periodic [default: periodic]
+    #[arg(long)]
+    boundary: Option<String>,
+
+    /// Number of squares of random values in the initial field
+    #[arg(long, default_value_t = 4)]
+    patches: usize,
+
+    /// Number of cells on each side of the squares of random values
+    #[arg(long, default_value_t = 26)]
+    patch_size: usize,
+
+    /// Color map of the images and frames [default: gray]
+    #[arg(long)]
+    colormap: Option<String>,
+
+    /// Image file where the final field is saved
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// File where the final field is saved as the B of `<name>_b` and its
+    /// complement as the A of `<name>_a` (`.csv` for CSV, NumPy otherwise)
+    #[arg(long)]
+    fields: Option<PathBuf>,
+
+    /// Directory where numbered PNG frames are written
+    #[arg(long)]
+    frames_dir: Option<PathBuf>,
+
+    /// Write a frame every this many generations [default: 10]
+    #[arg(long, requires = "frames_dir")]
+    frame_interval: Option<i32>,
+}
+
+impl LeniaArgs {
+    fn parameters(&self) -> LeniaParameters {
+        let defaults = LeniaParameters::default();
+        LeniaParameters {
+            radius: self.radius.unwrap_or(defaults.radius),
+            peaks: if self.peaks.is_empty() { defaults.peaks } else { self.peaks.clone() },
+            mu: self.mu.unwrap_or(defaults.mu),
+            sigma: self.sigma.unwrap_or(defaults.sigma),
+            dt: self.dt.unwrap_or(defaults.dt),
+        }
+    }
+}
+
+impl RunArgs {
+    /// `base` with the preset and the individual parameters given, checked
+    fn parameters(&self, base: Parameters) -> Result<Parameters, String> {
+        let mut parameters = match &self.preset {
+            Some(preset) => preset_parameters(preset)?,
+            None => base,
+        };
+        if let Some(d_a) = self.d_a {
+            parameters.d_a = d_a;
+        }
+        if let Some(d_b) = self.d_b {
+            parameters.d_b = d_b;
+        }
+        if let Some(f) = self.f {
+            parameters.f = f;
+        }
+        if let Some(k) = self.k {
+            parameters.k = k;
+        }
+        if let Some(r) = self.r {
+            parameters.r = r;
+        }
+        parameters
+            .validate()
+            .map_err(|error| format!("invalid parameters: {error}"))?;
+        Ok(parameters)
+    }
+
+    /// Configuration from the config file (or the defaults), overridden by
+    /// the arguments given explicitly
+    fn config(&self) -> Result<Config, String> {
+        let mut config = match &self.config {
+            Some(path) => Config::from_file(path)
+                .map_err(|error| format!("could not load {}: {error}", path.display()))?,
+            None => Config::default(),
+        };
+
+        if let Some(name) = &self.name {
+            config.name = name.clone();
+        }
+        config.parameters = self.parameters(config.parameters)?;
+        for spec in &self.compare {
+            let compared = compared_parameters(spec, config.parameters)?;
+            compared
+                .validate()
+                .map_err(|error| format!("invalid parameters in `--compare {spec}`: {error}"))?;
+            config.compare.push(compared);
+        }
+
+        if let Some(rows) = self.rows {
+            config.dimensions.row = rows;
+        }
+        if let Some(cols) = self.cols {
+            config.dimensions.col = cols;
+        }
+        if self.seed.is_some() {
+            config.seed = self.seed;
+        }
+        if self.initial_seed.is_some() {
+            config.streams.initial = self.initial_seed;
+        }
+        if self.noise_seed.is_some() {
+            config.streams.noise = self.noise_seed;
+        }
+        if self.brush_seed.is_some() {
+            config.streams.brush = self.brush_seed;
+        }
+        if let Some(amplitude) = self.noise {
+            config.noise = Some(NoiseConfig { amplitude });
+        }
+        if let Some(steps) = self.steps {
+            config.steps = steps;
+        }
+        if self.output.is_some() {
+            config.output.image = self.output.clone();
+        }
+        if self.fields.is_some() {
+            config.output.fields = self.fields.clone();
+        }
+        if self.height_map.is_some() {
+            config.output.height_map = self.height_map.clone();
+        }
+        if self.normal_map.is_some() || self.normal_strength.is_some() {
+            let normal_map = config.output.normal_map.get_or_insert_with(NormalMapConfig::default);
+            if let Some(path) = &self.normal_map {
+                normal_map.path = path.clone();
+            }
+            if let Some(strength) = self.normal_strength {
+                normal_map.strength = strength;
+            }
+        }
+        if self.mesh.is_some() || self.mesh_height.is_some() || self.mesh_base.is_some() {
+            let mesh = config.output.mesh.get_or_insert_with(MeshConfig::default);
+            if let Some(path) = &self.mesh {
+                mesh.path = path.clone();
+            }
+            if let Some(height) = self.mesh_height {
+                mesh.height = height;
+            }
+            if let Some(base) = self.mesh_base {
+                mesh.base = base;
+            }
+        }
+        if self.frames_dir.is_some() || self.frame_interval.is_some() {
+            let frames = config.output.frames.get_or_insert_with(FrameSequenceConfig::default);
+            if let Some(directory) = &self.frames_dir {
+                frames.directory = directory.clone();
+            }
+            if let Some(interval) = self.frame_interval {
+                frames.interval = interval;
+            }
+        }
+        if self.animation.is_some() || self.animation_interval.is_some() || self.animation_delay.is_some() {
+            let animation = config.output.animation.get_or_insert_with(AnimationConfig::default);
+            if let Some(path) = &self.animation {
+                animation.path = path.clone();
+            }
+            if let Some(interval) = self.animation_interval {
+                animation.interval = interval;
+            }
+            if let Some(delay) = self.animation_delay {
+                animation.delay = delay;
+            }
+        }
+        if let Some(bounds) = &self.bounds {
+            config.bounds = bounds_from_name(bounds)?;
+        }
+        if let Some(boundary) = &self.boundary {
+            config.boundary = boundary_from_name(boundary)?;
+        }
+        if let Some(stencil) = &self.stencil {
+            config.stencil = stencil_from_name(stencil)?;
+        }
+        if let Some(factor) = self.render_factor {
+            config.render.factor = factor;
+        }
+        if let Some(downsampling) = &self.render_downsampling {
+            config.render.downsampling = Downsampling::from_name(downsampling).ok_or_else(|| {
+                format!(
+                    "unknown downsampling `{downsampling}`, expected one of: {}",
+                    DOWNSAMPLING_NAMES.join(", ")
+                )
+            })?;
+        }
+        if let Some(format) = &self.pixel_format {
+            config.render.format = PixelFormat::from_name(format).ok_or_else(|| {
+                format!("unknown pixel format `{format}`, expected one of: {}", PIXEL_FORMAT_NAMES.join(", "))
+            })?;
+        }
+        if let Some(mode) = &self.display {
+            config.render.mode = DisplayMode::from_name(mode).ok_or_else(|| {
+                format!("unknown display mode `{mode}`, expected one of: {}", DISPLAY_MODE_NAMES.join(", "))
+            })?;
+        }
+        if let Some(capacity) = self.rewind_keyframes {
+            config.rewind.capacity = capacity;
+        }
+        if let Some(interval) = self.rewind_interval {
+            config.rewind.interval = interval;
+        }
+        if let Some(epsilon) = self.skip_quiescent {
+            config.activity.get_or_insert_with(ActivityTracking::default).epsilon = epsilon;
+        }
+        if let Some(interval) = self.blowup_interval {
+            config.blowup.interval = interval;
+        }
+        if let Some(margin) = self.blowup_margin {
+            config.blowup.margin = margin;
+        }
+        if self.check_conservation.is_some() || self.strict_conservation {
+            let check = config.conservation.get_or_insert_with(ConservationCheck::default);
+            if let Some(tolerance) = self.check_conservation {
+                check.tolerance = tolerance;
+            }
+            check.strict |= self.strict_conservation;
+        }
+        if self.activation_map.is_some() {
+            config.output.activation_map = self.activation_map.clone();
+        }
+        if self.activation_threshold.is_some() || config.output.activation_map.is_some() {
+            let tracking = config.activation.get_or_insert_with(ActivationTracking::default);
+            if let Some(threshold) = self.activation_threshold {
+                tracking.threshold = threshold;
+            }
+        }
+        if let Some(name) = &self.symmetry {
+            let symmetry = Symmetry::from_name(name).ok_or_else(|| {
+                format!("unknown symmetry `{name}`, expected one of: {}", SYMMETRY_NAMES.join(", "))
+            })?;
+            config.initial.symmetry = Some(symmetry);
+            if self.enforce_symmetry {
+                config.symmetry = Some(symmetry);
+            }
+        }
+        if self.modulation.is_some()
+            || self.modulation_image.is_some()
+            || !self.modulation_f.is_empty()
+            || !self.modulation_k.is_empty()
+        {
+            let modulation = config.modulation.get_or_insert_with(ModulationConfig::default);
+            if let Some(name) = &self.modulation {
+                modulation.field = Field::from_name(name).ok_or_else(|| {
+                    format!("unknown modulation `{name}`, expected one of: {}", FIELD_NAMES.join(", "))
+                })?;
+            }
+            if let Some(path) = &self.modulation_image {
+                modulation.field = Field::Image { path: path.clone(), invert: false };
+            }
+            for (name, factors, rate) in
+                [("f", &self.modulation_f, &mut modulation.f), ("k", &self.modulation_k, &mut modulation.k)]
+            {
+                match factors[..] {
+                    [] => {}
+                    [low, high] => *rate = [low, high],
+                    _ => return Err(format!("--modulation-{name} takes two factors, e.g. `0.8,1.2`")),
+                }
+            }
+        }
+        if self.reaction_a.is_some() || self.reaction_b.is_some() {
+            let reaction = config.reaction.get_or_insert_with(ReactionConfig::default);
+            if let Some(a) = &self.reaction_a {
+                reaction.a = a.clone();
+            }
+            if let Some(b) = &self.reaction_b {
+                reaction.b = b.clone();
+            }
+        }
+        if let Some(colormap) = &self.colormap {
+            config.output.colormap = colormap_from_name(colormap)?;
+        }
+        if let Some(path) = &self.colormap_image {
+            config.output.colormap = colormap_from_image(path, self.palette_colors)?;
+        }
+        config.output.colormap = thresholded(config.output.colormap, self.binary_threshold)?;
+        if let Some(name) = &self.initial {
+            config.initial.condition = Some(InitialCondition::from_name(name).ok_or_else(|| {
+                format!(
+                    "unknown initial condition `{name}`, expected one of: {}",
+                    INITIAL_CONDITION_NAMES.join(", ")
+                )
+            })?);
+        }
+        if let Some(path) = &self.from_image {
+            config.initial.image = Some(ImageSeed {
+                path: path.clone(),
+                channel: Channel::default(),
+                invert: false,
+            });
+        }
+        if let Some(image) = &mut config.initial.image {
+            if let Some(channel) = &self.image_channel {
+                image.channel = Channel::from_name(channel).ok_or_else(|| {
+                    format!(
+                        "unknown channel `{channel}`, expected one of: {}",
+                        CHANNEL_NAMES.join(", ")
+                    )
+                })?;
+            }
+            image.invert |= self.invert_image;
+        }
+        if self.from_snapshot.is_some() {
+            config.initial.snapshot = self.from_snapshot.clone();
+        }
+        if self.snapshot.is_some() {
+            config.output.snapshot = self.snapshot.clone();
+        }
+        config.deterministic |= self.deterministic;
+
+        if self.stats_log.is_some() || self.stats_log_interval.is_some() {
+            let log = config.output.stats.get_or_insert_with(StatsLogConfig::default);
+            if let Some(path) = &self.stats_log {
+                log.path = path.clone();
+            }
+            if let Some(interval) = self.stats_log_interval {
+                log.interval = interval;
+            }
+        }
+        if self.checkpoint_interval.is_some()
+            || self.checkpoint_dir.is_some()
+            || self.checkpoint_keep.is_some()
+        {
+            let policy = config.checkpoint.get_or_insert_with(CheckpointPolicy::default);
+            if let Some(interval) = self.checkpoint_interval {
+                policy.interval = interval;
+            }
+            if let Some(directory) = &self.checkpoint_dir {
+                policy.directory = directory.clone();
+            }
+            if let Some(retention) = self.checkpoint_keep {
+                policy.retention = retention;
+            }
+        }
+
+        #[cfg(feature = "control")]
+        if self.osc.is_some() || self.midi.is_some() {
+            let control = config.control.get_or_insert_with(ControlConfig::default);
+            if let Some(address) = &self.osc {
+                control.osc = Some(address.clone());
+            }
+            if let Some(device) = &self.midi {
+                control.midi = Some(device.clone());
+            }
+        }
+        #[cfg(feature = "server")]
+        if self.serve.is_some() || self.stream_interval.is_some() {
+            let server = config.server.get_or_insert_with(ServerConfig::default);
+            if let Some(address) = &self.serve {
+                server.address = address.clone();
+            }
+            if let Some(interval) = self.stream_interval {
+                server.interval = interval;
+            }
+        }
+        config.validate().map_err(|error| error.to_string())?;
+        Ok(config)
+    }
+}
+
+impl SweepArgs {
+    /// Sweep from the config file (or the defaults), overridden by the
+    /// arguments given explicitly
+    fn sweep(&self) -> Result<Sweep, String> {
+        let mut sweep = match &self.config {
+            Some(path) => Sweep::from_file(path)
+                .map_err(|error| format!("could not load {}: {error}", path.display()))?,
+            None => Sweep::default(),
+        };
+
+        if let Some(preset) = &self.preset {
+            sweep.base = preset_parameters(preset)?;
+        }
+        for (range, value) in [
+            (&mut sweep.f, self.f),
+            (&mut sweep.k, self.k),
+            (&mut sweep.d_a, self.d_a),
+            (&mut sweep.d_b, self.d_b),
+            (&mut sweep.r, self.r),
+        ] {
+            if value.is_some() {
+                *range = value;
+            }
+        }
+        if let Some(rows) = self.rows {
+            sweep.dimensions.row = rows;
+        }
+        if let Some(cols) = self.cols {
+            sweep.dimensions.col = cols;
+        }
+        if let Some(steps) = self.steps {
+            sweep.steps = steps;
+        }
+        if let Some(seed) = self.seed {
+            sweep.seed = seed;
+        }
+        if let Some(threads) = self.threads {
+            sweep.threads = threads;
+        }
+        if let Some(bounds) = &self.bounds {
+            sweep.bounds = bounds_from_name(bounds)?;
+        }
+        if let Some(boundary) = &self.boundary {
+            sweep.boundary = boundary_from_name(boundary)?;
+        }
+        Ok(sweep)
+    }
+}
+
+impl BifurcationArgs {
+    fn bifurcation(&self) -> Result<Bifurcation, String> {
+        let mut bifurcation = match &self.config {
+            Some(path) => Bifurcation::from_file(path)
+                .map_err(|error| format!("could not load {}: {error}", path.display()))?,
+            None => Bifurcation::default(),
+        };
+
+        if let Some(preset) = &self.preset {
+            bifurcation.base = preset_parameters(preset)?;
+        }
+        if !self.through.is_empty() {
+            bifurcation.path = self.through.clone();
+        }
+        if let Some(samples) = self.samples {
+            bifurcation.samples = samples;
+        }
+        if let Some(settle) = self.settle {
+            bifurcation.settle = settle;
+        }
+        if let Some(steps) = self.steps {
+            bifurcation.steps = steps;
+        }
+        if let Some(rows) = self.rows {
+            bifurcation.dimensions.row = rows;
+        }
+        if let Some(cols) = self.cols {
+            bifurcation.dimensions.col = cols;
+        }
+        if let Some(name) = &self.initial {
+            bifurcation.initial.condition = Some(InitialCondition::from_name(name).ok_or_else(|| {
+                format!(
+                    "unknown initial condition `{name}`, expected one of: {}",
+                    INITIAL_CONDITION_NAMES.join(", ")
+                )
+            })?);
+        }
+        if let Some(seed) = self.seed {
+            bifurcation.seed = seed;
+        }
+        if let Some(bounds) = &self.bounds {
+            bifurcation.bounds = bounds_from_name(bounds)?;
+        }
+        if let Some(boundary) = &self.boundary {
+            bifurcation.boundary = boundary_from_name(boundary)?;
+        }
+        Ok(bifurcation)
+    }
+}
+
+impl GenerateArgs {
+    fn batch(&self) -> Result<TextureBatch, String> {
+        let patterns = self
+            .preset
+            .iter()
+            .map(|name| Ok((name.clone(), preset_parameters(name)?)))
+            .collect::<Result<_, String>>()?;
+        let defaults = TextureConfig::default();
+        let texture = TextureConfig {
+            cells: self.cells.unwrap_or(defaults.cells),
+            size: self.size.unwrap_or(defaults.size),
+            supersampling: self.supersampling.unwrap_or(defaults.supersampling),
+            steps: self.steps.unwrap_or(defaults.steps),
+            initial_cells: self.initial_cells.unwrap_or(defaults.initial_cells),
+            colormap: match &self.colormap_image {
+                Some(path) => colormap_from_image(path, self.palette_colors)?,
+                None => thresholded(
+                    self.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default(),
+                    self.binary_threshold,
+                )?,
+            },
+        };
+        Ok(TextureBatch {
+            patterns,
+            seed: self.seed.unwrap_or_default(),
+            count: self.count,
+            texture,
+            threads: self.threads.unwrap_or_default(),
+        })
+    }
+}
+
+/// Parameters of the preset `name`, from the preset file or built in
+fn preset_parameters(name: &str) -> Result<Parameters, String> {
+    find_preset(name).ok_or_else(|| {
+        let mut names: Vec<String> = PresetLibrary::load(PRESETS_PATH)
+            .map(|library| library.names().map(String::from).collect())
+            .unwrap_or_default();
+        names.extend(PRESET_NAMES.iter().map(|name| name.to_string()));
+        names.sort();
+        names.dedup();
+        format!("unknown preset `{name}`, expected one of: {}", names.join(", "))
+    })
+}
+
+/// Parameters of a `--compare` argument: the preset `spec`, or `parameters`
+/// with the changes listed in `spec`
+fn compared_parameters(spec: &str, mut parameters: Parameters) -> Result<Parameters, String> {
+    if !spec.contains('=') {
+        return preset_parameters(spec);
+    }
+    for change in spec.split(',') {
+        let (name, value) = change
+            .split_once('=')
+            .ok_or_else(|| format!("invalid change `{change}`, expected `name=value`"))?;
+        let value: f32 = value
+            .trim()
+            .parse()
+            .map_err(|error| format!("invalid value in `{change}`: {error}"))?;
+        let parameter = match name.trim() {
+            "d_a" => &mut parameters.d_a,
+            "d_b" => &mut parameters.d_b,
+            "f" => &mut parameters.f,
+            "k" => &mut parameters.k,
+            "r" => &mut parameters.r,
+            other => {
+                return Err(format!("unknown parameter `{other}`, expected one of: d_a, d_b, f, k, r"))
+            }
+        };
+        *parameter = value;
+    }
+    Ok(parameters)
+}
+
+/// Hash written in hexadecimal, with or without `0x`
+fn hash_from_hex(text: &str) -> Result<u64, String> {
+    u64::from_str_radix(text.trim_start_matches("0x"), 16)
+        .map_err(|error| format!("invalid hash `{text}`: {error}"))
+}
+
+fn bounds_from_name(name: &str) -> Result<Bounds, String> {
+    Bounds::from_name(name).ok_or_else(|| {
+        format!("unknown bounds `{name}`, expected one of: {}", BOUNDS_NAMES.join(", "))
+    })
+}
+
+fn boundary_from_name(name: &str) -> Result<Boundary, String> {
+    Boundary::from_name(name).ok_or_else(|| {
+        format!("unknown boundary `{name}`, expected one of: {}", BOUNDARY_NAMES.join(", "))
+    })
+}
+
+fn stencil_from_name(name: &str) -> Result<Stencil, String> {
+    Stencil::from_name(name).ok_or_else(|| {
+        format!("unknown stencil `{name}`, expected one of: {}", STENCIL_NAMES.join(", "))
+    })
+}
+
+fn colormap_from_name(name: &str) -> Result<Colormap, String> {
+    Colormap::from_name(name).ok_or_else(|| {
+        format!("unknown colormap `{name}`, expected one of: {}", COLORMAP_NAMES.join(", "))
+    })
+}
+
+/// `colormap` with the threshold `threshold`, which only the binary color
+/// map accepts
+/// Fails if the threshold, given or already set, is invalid
+fn thresholded(colormap: Colormap, threshold: Option<f32>) -> Result<Colormap, String> {
+    let colormap = match (colormap, threshold) {
+        (Colormap::Binary { .. }, Some(threshold)) => colormap.with_threshold(threshold),
+        (_, Some(_)) => return Err("`--binary-threshold` requires the binary colormap".to_string()),
+        (colormap, None) => colormap,
+    };
+    colormap.validate().map_err(|error| error.to_string())?;
+    Ok(colormap)
+}
+
+/// Gradient through the `colors` dominant colors of the image at `path`, 5
+/// by default
+fn colormap_from_image(path: &Path, colors: Option<usize>) -> Result<Colormap, String> {
+    let image = image::open(path).map_err(|error| format!("could not load {}: {error}", path.display()))?;
+    Palette::from_image(&image.to_rgb8(), colors.unwrap_or(5))
+        .map(Colormap::Palette)
+        .ok_or_else(|| format!("{} has no pixels", path.display()))
+}
+
+/// Convert a snapshot to the format given by the extension of the output
+fn run_export(args: ExportArgs) -> Result<(), String> {
+    let snapshot = Snapshot::load(&args.snapshot)
+        .map_err(|error| format!("could not load {}: {error}", args.snapshot.display()))?;
+    let path = &args.output;
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    let saved = match extension.to_ascii_lowercase().as_str() {
+        "png" | "jpg" | "jpeg" => {
+            let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+            save_colored_map(&color_universe(&snapshot.universe), colormap, path)
+        }
+        "csv" | "npy" => save_fields(&snapshot.universe, path).map(|_| ()),
+        "obj" | "glb" => {
+            let default = MeshConfig::default();
+            let mesh = MeshConfig {
+                path: path.clone(),
+                height: args.mesh_height.unwrap_or(default.height),
+                base: args.mesh_base.unwrap_or(default.base),
+                ..default
+            };
+            Mesh::from_universe(&snapshot.universe, &mesh).and_then(|built| built.save(path))
+        }
+        _ => return Err(format!("unknown format of {}, expected png, jpg, csv, npy, obj or glb", path.display())),
+    };
+    saved.map_err(|error| format!("could not save {}: {error}", path.display()))
+}
+
+/// Print the statistics and the pattern of a snapshot
+fn run_analyze(args: AnalyzeArgs) -> Result<(), String> {
+    let snapshot = Snapshot::load(&args.snapshot)
+        .map_err(|error| format!("could not load {}: {error}", args.snapshot.display()))?;
+    let field = FieldSnapshot { generation: snapshot.generation, universe: snapshot.universe };
+    let Parameters { d_a, d_b, f, k, r, .. } = snapshot.parameters;
+    println!(
+        "{}x{} cells at generation {}, d_a={d_a} d_b={d_b} f={f} k={k} r={r}",
+        snapshot.dimensions.row, snapshot.dimensions.col, field.generation
+    );
+    let stats = field.stats();
+    for (name, species) in [("A", stats.a), ("B", stats.b)] {
+        println!(
+            "{name}: mean {} variance {} min {} max {} mass {}",
+            species.mean, species.variance, species.min, species.max, species.mass
+        );
+    }
+    let classification = field.classify();
+    match classification.wavelength {
+        Some(wavelength) => println!(
+            "{} with a wavelength of {} cells, isotropy {}",
+            classification.kind, wavelength.wavelength, wavelength.isotropy
+        ),
+        None => println!("{}", classification.kind),
+    }
+
+    if let Some(path) = &args.spectrum {
+        // Bin `i` holds the wavenumbers around `i` cycles over the largest side
+        let side = snapshot.dimensions.row.max(snapshot.dimensions.col).max(1) as f64;
+        let profile = field.spectrum().radial_profile();
+        let lines: String =
+            profile.iter().enumerate().map(|(index, power)| format!("{},{power}\n", index as f64 / side)).collect();
+        std::fs::write(path, format!("wavenumber,power\n{lines}"))
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Print how much two snapshots differ, drawing the differences if asked
+fn run_diff(args: DiffArgs) -> Result<(), String> {
+    let load = |path: &Path| Snapshot::load(path).map_err(|error| format!("could not load {}: {error}", path.display()));
+    let (first, second) = (load(&args.first)?, load(&args.second)?);
+    let comparison = compare(&first.universe, &second.universe).map_err(|error| error.to_string())?;
+    for (name, species) in [("A", comparison.a), ("B", comparison.b)] {
+        println!("{name}: L2 {} rms {} max {} SSIM {}", species.l2, species.rms, species.max, species.ssim);
+    }
+
+    if let Some(path) = &args.output {
+        let species = match args.species.as_deref().unwrap_or("b") {
+            "a" | "A" => Species::A,
+            "b" | "B" => Species::B,
+            name => return Err(format!("unknown species `{name}`, expected a or b")),
+        };
+        let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+        difference_map(&first.universe, &second.universe, species)
+            .and_then(|differences| save_colored_map(&differences, colormap, path))
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Run a sweep and print one line per run
+fn run_sweep(args: SweepArgs) -> Result<(), String> {
+    let sweep = args.sweep()?;
+    let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+    let colormap = thresholded(colormap, args.binary_threshold)?;
+    let runs = sweep
+        .write(&args.output_dir, colormap)
+        .map_err(|error| format!("could not write the sweep to {}: {error}", args.output_dir.display()))?;
+
+    for run in &runs {
+        let Parameters { d_a, d_b, f, k, r, .. } = run.parameters;
+        let outcome = match (&run.error, run.wavelength) {
+            (Some(error), _) => format!("{}, {error}", run.kind),
+            (None, Some(wavelength)) => format!("{}, wavelength {:.2}", run.kind, wavelength.wavelength),
+            (None, None) => run.kind.to_string(),
+        };
+        println!("run {:04} (d_a = {d_a}, d_b = {d_b}, f = {f}, k = {k}, r = {r}): {outcome}", run.index);
+    }
+    Ok(())
+}
+
+/// Compute a bifurcation run, write its diagram and print one line per
+/// recorded point
+fn run_bifurcation(args: BifurcationArgs) -> Result<(), String> {
+    let bifurcation = args.bifurcation()?;
+    let metric = match &args.metric {
+        Some(name) => BifurcationMetric::from_name(name).ok_or_else(|| {
+            format!("unknown metric `{name}`, expected one of: {}", BIFURCATION_METRIC_NAMES.join(", "))
+        })?,
+        None => BifurcationMetric::default(),
+    };
+    let points = bifurcation
+        .write(&args.output_dir, metric)
+        .map_err(|error| format!("could not write the bifurcation run to {}: {error}", args.output_dir.display()))?;
+
+    for point in &points {
+        let Parameters { f, k, .. } = point.parameters;
+        let outcome = match (&point.error, point.wavelength) {
+            (Some(error), _) => format!("{}, {error}", point.kind),
+            (None, Some(wavelength)) => {
+                format!("{}, amplitude {:.3}, wavelength {wavelength:.2}", point.kind, point.amplitude)
+            }
+            (None, None) => format!("{}, amplitude {:.3}", point.kind, point.amplitude),
+        };
+        println!("point {:04} (f = {f}, k = {k}): {outcome}", point.index);
+    }
+    Ok(())
+}
+
+/// Generate a batch of textures and print one line per texture
+fn run_generate(args: GenerateArgs) -> Result<(), String> {
+    let textures = args
+        .batch()?
+        .write(&args.output_dir)
+        .map_err(|error| format!("could not write the textures to {}: {error}", args.output_dir.display()))?;
+
+    for texture in &textures {
+        let outcome = match &texture.error {
+            Some(error) => error.clone(),
+            None if texture.converged => format!("converged after {} generations", texture.generation),
+            None => format!("not converged after {} generations", texture.generation),
+        };
+        println!("{}_{}: {outcome}", texture.name, texture.seed);
+    }
+    Ok(())
+}
+
+/// Evolve a surface, in a window or headless, and save the colored mesh
+fn run_surface(args: SurfaceArgs) -> Result<(), String> {
+    let parameters = args.preset.as_deref().map(preset_parameters).transpose()?.unwrap_or_default();
+    let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+    let mesh = Mesh::load(&args.mesh).map_err(|error| error.to_string())?;
+    let vertices = mesh.positions.len();
+    let cells = match args.seed {
+        Some(seed) => initialize_surface(vertices, args.initial_cells, &mut StdRng::seed_from_u64(seed)),
+        None => initialize_surface(vertices, args.initial_cells, &mut rand::thread_rng()),
+    };
+    let mut simulation = SurfaceSimulation::new(parameters, mesh, cells).map_err(|error| error.to_string())?;
+
+    #[cfg(feature = "bevy")]
+    if !args.headless {
+        let state = SurfaceState {
+            simulation,
+            colormap,
+            steps_per_frame: args.steps_per_frame,
+            steps: args.steps,
+            running: true,
+        };
+        scene::run(state);
+        return Ok(());
+    }
+    #[cfg(not(feature = "bevy"))]
+    if !args.headless {
+        return Err("built without the `bevy` feature, run with --headless".to_string());
+    }
+
+    simulation.run(args.steps);
+    if let Some(path) = &args.output {
+        simulation
+            .into_colored_mesh(colormap)
+            .save(path)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Evolve a graph headless and draw its final colors
+fn run_graph(args: GraphArgs) -> Result<(), String> {
+    let parameters = args.preset.as_deref().map(preset_parameters).transpose()?.unwrap_or_default();
+    let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+    let graph = Graph::load(&args.edges).map_err(|error| error.to_string())?;
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let layout = match &args.layout {
+        Some(path) => load_layout(path).map_err(|error| error.to_string())?,
+        None => force_layout(&graph, args.layout_iterations, &mut rng),
     };
-    let dimensions: Position = Position{row: 600, col: 600};
-    
-    let (universe , mut colored_map) = initialize_universe(&dimensions);
-    
-    total_simulation(700, &parameters, &dimensions, universe, &mut colored_map);
+    let cells = initialize_graph(graph.nodes(), args.initial_cells, &mut rng);
+    let mut simulation = GraphSimulation::new(parameters, graph, cells).map_err(|error| error.to_string())?;
+
+    simulation.run(args.steps);
+    draw_graph(simulation.graph(), &layout, simulation.colors(), colormap, args.size, args.node_radius)
+        .map_err(|error| error.to_string())?
+        .save(&args.output)
+        .map_err(|error| format!("could not save {}: {error}", args.output.display()))
+}
+
+/// Evolve a Lenia field headless, writing its frames and final field
+fn run_lenia(args: LeniaArgs) -> Result<(), String> {
+    let boundary = args.boundary.as_deref().map(boundary_from_name).transpose()?.unwrap_or(Boundary::Periodic);
+    let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+    let dimensions = Position { row: args.rows, col: args.cols };
+    let field = match args.seed {
+        Some(seed) => initialize_lenia(dimensions, args.patches, args.patch_size, &mut StdRng::seed_from_u64(seed)),
+        None => initialize_lenia(dimensions, args.patches, args.patch_size, &mut rand::thread_rng()),
+    };
+    let mut simulation =
+        LeniaSimulation::new(args.parameters(), dimensions, boundary, field).map_err(|error| error.to_string())?;
+
+    let frames = args
+        .frames_dir
+        .clone()
+        .map(|directory| {
+            let interval = args.frame_interval.unwrap_or(FrameSequenceConfig::default().interval);
+            FrameSequence::new(FrameSequenceConfig { directory, interval }, colormap)
+        })
+        .transpose()
+        .map_err(|error| format!("could not create the frame sequence: {error}"))?;
+    for _ in 0..args.steps {
+        simulation.step();
+        if let Some(frames) = &frames {
+            if frames.is_due(simulation.generation()) {
+                frames
+                    .write(simulation.generation(), &simulation.colored_map())
+                    .map_err(|error| format!("could not write frame: {error}"))?;
+            }
+        }
+    }
+
+    if let Some(image) = &args.output {
+        save_colored_map(&simulation.colored_map(), colormap, image)
+            .map_err(|error| format!("could not save {}: {error}", image.display()))?;
+    }
+    if let Some(path) = &args.fields {
+        save_fields(&simulation.universe(), path)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Evolve a universe on an adaptive grid and save its color map
+fn run_adaptive(args: AdaptiveArgs) -> Result<(), String> {
+    let parameters = args.preset.as_deref().map(preset_parameters).transpose()?.unwrap_or_default();
+    let boundary = args.boundary.as_deref().map(boundary_from_name).transpose()?.unwrap_or_default();
+    let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+    let dimensions = Position { row: args.rows, col: args.cols };
+    let config = args.config();
+    let mut simulation = match args.seed {
+        Some(seed) => AdaptiveSimulation::random(
+            parameters,
+            dimensions,
+            boundary,
+            config,
+            args.initial_cells,
+            &mut StdRng::seed_from_u64(seed),
+        ),
+        None => AdaptiveSimulation::random(
+            parameters,
+            dimensions,
+            boundary,
+            config,
+            args.initial_cells,
+            &mut rand::thread_rng(),
+        ),
+    }
+    .map_err(|error| error.to_string())?;
 
+    simulation.run(args.steps);
+    println!(
+        "{} cells computed per evolution at the end, {} on a uniform grid",
+        simulation.cell_count(),
+        dimensions.row * dimensions.col
+    );
+    if let Some(image) = &args.output {
+        save_colored_map(&simulation.colored_map(), colormap, image)
+            .map_err(|error| format!("could not save {}: {error}", image.display()))?;
+    }
+    Ok(())
+}
+
+fn run_infinite(args: InfiniteArgs) -> Result<(), String> {
+    let parameters = args.preset.as_deref().map(preset_parameters).transpose()?.unwrap_or_default();
+    let colormap = args.colormap.as_deref().map(colormap_from_name).transpose()?.unwrap_or_default();
+    let name = args.initial.as_deref().unwrap_or("square");
+    let condition = InitialCondition::from_name(name).ok_or_else(|| {
+        format!("unknown initial condition `{name}`, expected one of: {}", INITIAL_CONDITION_NAMES.join(", "))
+    })?;
+    let default = ChunkedConfig::default();
+    let config = ChunkedConfig { chunk_size: args.chunk_size.unwrap_or(default.chunk_size), ..default };
+    let dimensions = Position { row: args.rows, col: args.cols };
+    let (universe, _): (Universe, _) = match args.seed {
+        Some(seed) => condition.generate(&dimensions, &mut StdRng::seed_from_u64(seed)),
+        None => condition.generate(&dimensions, &mut rand::thread_rng()),
+    }
+    .map_err(|error| error.to_string())?;
+    let mut simulation =
+        ChunkedSimulation::from_universe(parameters, config, &universe).map_err(|error| error.to_string())?;
+
+    simulation.run(args.steps);
+    let Some((origin, extent)) = simulation.extent() else {
+        println!("the universe is back to the steady state everywhere");
+        return Ok(());
+    };
+    println!(
+        "{} chunks allocated at the end, covering {}x{} cells from ({}, {})",
+        simulation.chunk_count(),
+        extent.row,
+        extent.col,
+        origin.row,
+        origin.col
+    );
+    if let Some(image) = &args.output {
+        save_colored_map(&simulation.colored_window(origin, extent), colormap, image)
+            .map_err(|error| format!("could not save {}: {error}", image.display()))?;
+    }
+    Ok(())
+}
+
+/// Step a simulation and the reference implementation together, printing
+/// their largest difference at every generation
+fn run_validate(args: ValidateArgs) -> Result<(), String> {
+    let parameters = args.preset.as_deref().map(preset_parameters).transpose()?.unwrap_or_default();
+    let boundary = args.boundary.as_deref().map(boundary_from_name).transpose()?.unwrap_or_default();
+    let dimensions = Position { row: args.rows, col: args.cols };
+    let simulation = Simulation::<f32>::random(
+        parameters,
+        dimensions,
+        args.initial_cells,
+        &mut StdRng::seed_from_u64(args.seed),
+    )
+    .map_err(|error| error.to_string())?
+    .with_boundary(boundary);
+    let simulation = match args.skip_quiescent {
+        Some(epsilon) => simulation.with_activity_tracking(ActivityTracking { epsilon, ..ActivityTracking::default() }),
+        None => simulation,
+    };
+
+    let mut validation = Validation::new(simulation);
+    let mut largest: Option<Divergence> = None;
+    println!("generation,difference,row,col");
+    for _ in 0..args.steps {
+        let divergence = validation.step();
+        println!(
+            "{},{:e},{},{}",
+            divergence.generation, divergence.difference, divergence.position.row, divergence.position.col
+        );
+        if largest.is_none_or(|largest| divergence.difference > largest.difference) {
+            largest = Some(divergence);
+        }
+        if args.tolerance.is_some_and(|tolerance| divergence.difference > tolerance) {
+            return Err(format!(
+                "cell ({}, {}) differs from the reference by {:e} at generation {}",
+                divergence.position.row, divergence.position.col, divergence.difference, divergence.generation
+            ));
+        }
+    }
+    if let Some(largest) = largest {
+        eprintln!(
+            "largest difference: {:e} at cell ({}, {}), generation {}",
+            largest.difference, largest.position.row, largest.position.col, largest.generation
+        );
+    }
+    Ok(())
+}
+
+fn run_bench(args: BenchArgs) -> Result<(), String> {
+    let backends = match &args.backend[..] {
+        [] => vec![Backend::F32],
+        [all] if all == "all" => BACKENDS.to_vec(),
+        names => names
+            .iter()
+            .map(|name| {
+                Backend::from_name(name).ok_or_else(|| {
+                    format!("unknown backend `{name}`, expected one of: all, {}", BACKEND_NAMES.join(", "))
+                })
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    let config = BenchConfig {
+        dimensions: Position { row: args.rows, col: args.cols },
+        parameters: args.preset.as_deref().map(preset_parameters).transpose()?.unwrap_or_default(),
+        boundary: args.boundary.as_deref().map(boundary_from_name).transpose()?.unwrap_or_default(),
+        stencil: args.stencil.as_deref().map(stencil_from_name).transpose()?.unwrap_or_default(),
+        activity: args.skip_quiescent.map(|epsilon| ActivityTracking { epsilon, ..ActivityTracking::default() }),
+        steps: args.steps,
+        warmup: args.warmup,
+        seed: args.seed,
+        ..BenchConfig::default()
+    };
+    for backend in backends {
+        let report = bench(&BenchConfig { backend, ..config }).map_err(|error| error.to_string())?;
+        println!("{report}");
+    }
+    Ok(())
+}
+
+/// Open the window on the session saved in `path`, printing the time spent
+/// in each stage when it is closed if `profile` is set
+#[cfg(feature = "bevy")]
+fn run_session(path: &Path, profile: bool) -> Result<(), String> {
+    let session = Session::load(path).map_err(|error| format!("could not load {}: {error}", path.display()))?;
+    let restore_error = |error: SimulationError| format!("could not restore {}: {error}", path.display());
+    session.validate().map_err(restore_error)?;
+    let simulation = session.simulation().map_err(restore_error)?;
+    let comparisons = session.comparisons().map_err(restore_error)?;
+    check_couplings(&session.couplings, 1 + comparisons.len()).map_err(|error| error.to_string())?;
+    app::run(SimulationState {
+        simulation,
+        comparisons,
+        couplings: session.couplings,
+        max_generations: session.steps,
+        output: session.output,
+        events: session.events,
+        preset: session.preset,
+        stats_interval: session.stats_interval,
+        render: session.render,
+        show_activation: false,
+        probe: None,
+        recorder: None,
+        config_file: None,
+        rewind: RewindBuffer::new(session.rewind).map_err(restore_error)?,
+        initial_cells: session.initial_cells,
+        initial: session.initial,
+        name: session.name,
+        seed: session.seed,
+        streams: session.streams,
+        brush: session.brush,
+        brush_rng: portable_rng(session.streams.seed(session.seed, RngStream::Brush).unwrap_or_else(rand::random)),
+        view: session.view,
+        profile,
+        #[cfg(feature = "control")]
+        control: None,
+    });
+    Ok(())
+}
+
+/// Snapshot to resume from: `path` itself, or the latest checkpoint if it is
+/// a directory
+fn resume_snapshot(path: &Path) -> Result<Snapshot, String> {
+    let file = if path.is_dir() {
+        latest_checkpoint(path)
+            .map_err(|error| format!("could not read {}: {error}", path.display()))?
+            .ok_or_else(|| format!("no checkpoint found in {}", path.display()))?
+    } else {
+        path.to_path_buf()
+    };
+    Snapshot::load(&file).map_err(|error| format!("could not load {}: {error}", file.display()))
+}
+
+fn run(args: RunArgs) -> Result<(), String> {
+    #[cfg(feature = "bevy")]
+    if let Some(path) = &args.session {
+        return run_session(path, args.profile);
+    }
+    let mut config = args.config()?;
+    // The parameters given win over those of the snapshot resumed
+    let resumed = args.resume.as_deref().map(resume_snapshot).transpose()?;
+    if let Some(snapshot) = &resumed {
+        config.parameters = args.parameters(snapshot.parameters)?;
+        // Same name, and so same checkpoints, as the run resumed
+        if config.seed.is_none() {
+            config.seed = snapshot.seed;
+        }
+    }
+    let replayed = args
+        .replay
+        .as_deref()
+        .map(|path| Replay::load(path).map_err(|error| format!("could not load {}: {error}", path.display())))
+        .transpose()?;
+    if let Some(replay) = &replayed {
+        config.seed = Some(replay.seed);
+    }
+    // Drawn now so that the name of the run gives the seed it ran with
+    let run_seed = *config.seed.get_or_insert_with(rand::random);
+    let name = config.run_name().map_err(|error| error.to_string())?;
+    config.name_outputs(&name);
+    if config.deterministic {
+        deterministic::check(&config).map_err(|error| error.to_string())?;
+    }
+    #[cfg(feature = "server")]
+    let server = config.server.clone();
+    let Config {
+        parameters,
+        dimensions,
+        mut seed,
+        mut steps,
+        bounds,
+        boundary,
+        stencil,
+        timeline,
+        schedule,
+        modulation,
+        reaction,
+        activity,
+        conservation,
+        blowup,
+        symmetry,
+        activation,
+        noise,
+        streams,
+        initial,
+        output,
+        checkpoint,
+        compare,
+        couplings,
+        #[cfg(feature = "bevy")]
+        render,
+        #[cfg(feature = "bevy")]
+        rewind,
+        deterministic,
+        #[cfg(all(feature = "bevy", feature = "control"))]
+        control,
+        ..
+    } = config;
+
+    let field = modulation
+        .clone()
+        .map(|modulation| Modulation::new(modulation).map_err(|error| error.to_string()))
+        .transpose()?;
+    let scripted = reaction
+        .as_ref()
+        .map(|reaction| reaction.compile().map_err(|error| error.to_string()))
+        .transpose()?;
+    let modulated = |simulation: Simulation| {
+        let simulation = match &field {
+            Some(modulation) => simulation.with_modulation(modulation.clone()).map_err(|error| error.to_string())?,
+            None => simulation,
+        };
+        Ok::<_, String>(match &scripted {
+            Some(reaction) => simulation.with_reaction(reaction.clone()),
+            None => simulation,
+        })
+    };
+
+    let mut events = Vec::new();
+    let mut recorder = None;
+    let mut simulation = if let Some(replay) = replayed {
+        let simulation = replay.simulation().map_err(|error| error.to_string())?;
+        steps = replay.steps;
+        events = replay.events;
+        simulation
+    } else if let (Some(path), Some(snapshot)) = (&args.resume, resumed) {
+        #[cfg(feature = "bevy")]
+        {
+            seed = None;
+        }
+        let simulation = Snapshot { parameters, ..snapshot }
+            .into_simulation()
+            .map_err(|error| format!("could not resume from {}: {error}", path.display()))?;
+        // The snapshot keeps its edges unless others are asked for
+        let simulation = match args.boundary {
+            Some(_) => simulation.with_boundary(boundary),
+            None => simulation,
+        };
+        modulated(
+            simulation
+                .with_bounds(bounds)
+                .with_stencil(stencil)
+                .with_timeline(timeline)
+                .map_err(|error| format!("invalid timeline: {error}"))?
+                .with_schedule(schedule)
+                .map_err(|error| error.to_string())?,
+        )?
+    } else {
+        if let Some(path) = &args.record {
+            // A replay needs the seed to rebuild the same initial universe
+            let seed = *seed.get_or_insert_with(rand::random);
+            let replay = Replay {
+                dimensions,
+                initial: initial.clone(),
+                seed,
+                parameters,
+                steps,
+                bounds,
+                boundary,
+                stencil,
+                timeline: timeline.clone(),
+                schedule: schedule.clone(),
+                modulation,
+                reaction,
+                activity,
+                symmetry,
+                noise,
+                streams,
+                deterministic,
+                events: Vec::new(),
+            };
+            recorder = Some(
+                Recorder::new(path, replay)
+                    .map_err(|error| format!("could not write {}: {error}", path.display()))?,
+            );
+        }
+        let (universe, dimensions) = match (streams.seed(seed, RngStream::Initial), deterministic) {
+            (Some(seed), true) => initial.universe_with_rng(&parameters, dimensions, &mut portable_rng(seed)),
+            (None, true) => return Err("a deterministic run needs a --seed".to_string()),
+            (seed, false) => initial.universe(&parameters, dimensions, seed),
+        }
+        .map_err(|error| error.to_string())?;
+        modulated(
+            Simulation::new(parameters, dimensions, universe)
+                .map_err(|error| error.to_string())?
+                .with_bounds(bounds)
+                .with_boundary(boundary)
+                .with_stencil(stencil)
+                .with_timeline(timeline)
+                .map_err(|error| format!("invalid timeline: {error}"))?
+                .with_schedule(schedule)
+                .map_err(|error| error.to_string())?,
+        )?
+    };
+    for hint in simulation.parameters().hints() {
+        eprintln!("warning: {hint}");
+    }
+    // A replay tracks the activity as the recorded run did
+    if let (None, Some(tracking)) = (&args.replay, activity) {
+        simulation = simulation.with_activity_tracking(tracking);
+    }
+    if let Some(check) = conservation {
+        simulation = simulation.with_conservation_check(check);
+    }
+    if blowup.interval > 0 {
+        simulation = simulation.with_blowup_check(blowup).map_err(|error| error.to_string())?;
+    }
+    if let (None, Some(symmetry)) = (&args.replay, symmetry) {
+        simulation = simulation.with_symmetry(symmetry);
+    }
+    if let Some(tracking) = activation {
+        simulation = simulation.with_activation_tracking(tracking);
+    }
+    if let (None, Some(noise)) = (&args.replay, noise) {
+        let seed = streams.seed(seed, RngStream::Noise).unwrap_or_else(rand::random);
+        simulation = simulation.with_noise(Noise::new(noise, seed).map_err(|error| error.to_string())?);
+    }
+
+    #[cfg(feature = "server")]
+    if let Some(server) = &server {
+        return serve(server, simulation, steps, output.colormap, recorder)
+            .map_err(|error| format!("could not serve on {}: {error}", server.address));
+    }
+
+    if args.headless && !(compare.is_empty() && couplings.is_empty()) {
+        return Err(
+            "compared and coupled simulations are only drawn in the window, run without --headless".to_string(),
+        );
+    }
+    check_couplings(&couplings, 1 + compare.len()).map_err(|error| error.to_string())?;
+
+    #[cfg(feature = "bevy")]
+    if !args.headless {
+        let comparisons = compare
+            .into_iter()
+            .map(|parameters| {
+                Simulation::new(parameters, simulation.dimensions(), simulation.universe().clone())
+                    .map_err(|error| error.to_string())
+                    .and_then(|comparison| {
+                        let comparison = modulated(comparison)?
+                            .with_generation(simulation.generation())
+                            .with_bounds(bounds)
+                            .with_boundary(boundary)
+                            .with_stencil(stencil);
+                        // The same noise, to compare the parameters alone
+                        let comparison = match simulation.noise() {
+                            Some(noise) => comparison.with_noise(noise.clone()),
+                            None => comparison,
+                        };
+                        Ok(match activity {
+                            Some(tracking) => comparison.with_activity_tracking(tracking),
+                            None => comparison,
+                        })
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        app::run(SimulationState {
+            simulation,
+            comparisons,
+            couplings,
+            max_generations: steps,
+            output,
+            events,
+            preset: args.preset.clone(),
+            stats_interval: app::DEFAULT_STATS_INTERVAL,
+            render,
+            show_activation: false,
+            probe: None,
+            recorder,
+            config_file: args.config.clone(),
+            rewind: RewindBuffer::new(rewind).map_err(|error| error.to_string())?,
+            initial_cells: initial.cells,
+            initial: initial.condition,
+            name,
+            seed,
+            streams,
+            brush: Brush::default(),
+            brush_rng: portable_rng(streams.seed(seed, RngStream::Brush).unwrap_or_else(rand::random)),
+            view: None,
+            profile: args.profile,
+            #[cfg(feature = "control")]
+            control,
+        });
+        return Ok(());
+    }
+    #[cfg(not(feature = "bevy"))]
+    if !args.headless {
+        return Err("built without the `bevy` feature, run with --headless".to_string());
+    }
+    // Nothing changes during a headless run, the replay file is complete
+    drop(recorder);
+    eprintln!("running {name}");
+
+    let mut checkpointer = checkpoint
+        .map(Checkpointer::new)
+        .transpose()
+        .map_err(|error| format!("could not create the checkpointer: {error}"))?;
+    let mut logger = output
+        .stats
+        .clone()
+        .map(StatsLogger::new)
+        .transpose()
+        .map_err(|error| format!("could not open the statistics log: {error}"))?;
+    let frames = output
+        .frames
+        .clone()
+        .map(|frames| FrameSequence::new(frames, output.colormap))
+        .transpose()
+        .map_err(|error| format!("could not create the frame sequence: {error}"))?;
+    let mut watcher = TriggerWatcher::new(output.triggers.clone()).map_err(|error| error.to_string())?;
+    let mut animation = output
+        .animation
+        .clone()
+        .map(|animation| Animation::new(animation, output.colormap))
+        .transpose()
+        .map_err(|error| error.to_string())?;
+
+    if args.profile {
+        profile::enable();
+    }
+    let mut events = events.into_iter().peekable();
+    let mut largest_leak: Option<MassBalance> = None;
+    while simulation.generation() < steps && !simulation.is_stopped() && simulation.blowup().is_none() {
+        while let Some(timed) = events.next_if(|timed| timed.generation <= simulation.generation()) {
+            apply_event(&timed.event, &mut simulation)
+                .map_err(|error| format!("could not replay generation {}: {error}", timed.generation))?;
+        }
+        simulation.step();
+        let generation = simulation.generation();
+
+        if let Some(&balance) = simulation.mass_balance() {
+            if largest_leak.is_none_or(|largest| balance.relative_leak().abs() > largest.relative_leak().abs()) {
+                largest_leak = Some(balance);
+            }
+        }
+
+        if let Some(frames) = &frames {
+            if frames.is_due(generation) {
+                frames
+                    .write(generation, simulation.colored_map())
+                    .map_err(|error| format!("could not write frame: {error}"))?;
+            }
+        }
+        if let Some(animation) = &mut animation {
+            if animation.is_due(generation) {
+                animation
+                    .push(simulation.colored_map())
+                    .map_err(|error| format!("could not capture a frame of the animation: {error}"))?;
+            }
+        }
+        if let Some(logger) = &mut logger {
+            if logger.is_due(generation) {
+                logger.log(&simulation).map_err(|error| format!("could not log the statistics: {error}"))?;
+            }
+        }
+        for fired in watcher.check(&simulation) {
+            watcher
+                .export(&fired, &simulation, output.colormap)
+                .map_err(|error| format!("could not export the {} event: {error}", fired.name))?;
+        }
+        if let Some(checkpointer) = &mut checkpointer {
+            if checkpointer.is_due(generation) {
+                checkpointer
+                    .save(&Snapshot::of(&simulation).with_seed(Some(run_seed)))
+                    .map_err(|error| format!("could not write checkpoint: {error}"))?;
+            }
+        }
+    }
+
+    if let Some(logger) = &mut logger {
+        logger.flush().map_err(|error| format!("could not log the statistics: {error}"))?;
+    }
+    if args.profile {
+        eprint!("{}", profile::report());
+    }
+    if let Some(violation) = simulation.violation() {
+        return Err(violation.to_string());
+    }
+    if let Some(leak) = simulation.leak() {
+        return Err(format!("mass not conserved: {leak}"));
+    }
+    if let Some(blowup) = simulation.blowup() {
+        return Err(blowup.to_string());
+    }
+    if let Some(largest) = largest_leak {
+        eprintln!("largest leak of mass: {largest}");
+    }
+    if deterministic {
+        println!("exact hash: {:016x}", simulation.exact_hash());
+    }
+    if let Some(expected) = args.expect_hash {
+        let hash = simulation.exact_hash();
+        if hash != expected {
+            return Err(format!("the exact hash is {hash:016x}, not {expected:016x}"));
+        }
+    }
+
+    if let Some(image) = &output.image {
+        save_colored_map(simulation.colored_map(), output.colormap, image)
+            .map_err(|error| format!("could not save {}: {error}", image.display()))?;
+    }
+
+    if let Some(animation) = &animation {
+        animation
+            .save()
+            .map_err(|error| format!("could not save {}: {error}", animation.path().display()))?;
+    }
+
+    if let Some(path) = &output.fields {
+        save_fields(simulation.universe(), path)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+
+    if let Some(path) = &output.height_map {
+        save_height_map(simulation.universe(), Species::B, path)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+
+    if let Some(normal_map) = &output.normal_map {
+        save_normal_map(simulation.universe(), Species::B, normal_map, simulation.boundary())
+            .map_err(|error| format!("could not save {}: {error}", normal_map.path.display()))?;
+    }
+
+    if let (Some(path), Some(activation)) = (&output.activation_map, simulation.activation()) {
+        save_colored_map(&activation.colored_map(), output.colormap, path)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+
+    if let Some(mesh) = &output.mesh {
+        Mesh::from_universe(simulation.universe(), mesh)
+            .and_then(|built| built.save(&mesh.path))
+            .map_err(|error| format!("could not save {}: {error}", mesh.path.display()))?;
+    }
+
+    if let Some(path) = &output.snapshot {
+        Snapshot::of(&simulation)
+            .with_seed(Some(run_seed))
+            .save(path)
+            .map_err(|error| format!("could not save {}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Some(Command::Run(args)) => run(args),
+        Some(Command::Render(args)) => run(RunArgs { headless: true, ..args }),
+        Some(Command::Export(args)) => run_export(args),
+        Some(Command::Analyze(args)) => run_analyze(args),
+        Some(Command::Diff(args)) => run_diff(args),
+        Some(Command::Sweep(args)) => run_sweep(args),
+        Some(Command::Bifurcation(args)) => run_bifurcation(args),
+        Some(Command::Generate(args)) => run_generate(args),
+        Some(Command::Surface(args)) => run_surface(args),
+        Some(Command::Lenia(args)) => run_lenia(args),
+        Some(Command::Graph(args)) => run_graph(args),
+        Some(Command::Adaptive(args)) => run_adaptive(args),
+        Some(Command::Infinite(args)) => run_infinite(args),
+        Some(Command::Validate(args)) => run_validate(args),
+        Some(Command::Bench(args)) => run_bench(args),
+        None => run(cli.run),
+    };
+    if let Err(error) = result {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
 }
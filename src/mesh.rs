@@ -0,0 +1,355 @@
+/// Meshes of the pattern surface
+/// The concentrations of a species displace a grid with one vertex per cell,
+/// as in `export::height_map`, giving a surface that can be imported into
+/// Blender or any engine. With a base the surface is closed by walls and a
+/// flat bottom into a watertight solid, ready to be 3D printed. Meshes are
+/// written as Wavefront OBJ or binary glTF (`.glb`), Y up, one unit per cell.
+/// Meshes can also be read from OBJ files, e.g. to evolve patterns on them
+/// with `surface`
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{BufRead, Write};
+#[cfg(feature = "fs")]
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::{heights, Species};
+use crate::{Position, SimulationError, Universe};
+
+/// Settings of a mesh
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MeshConfig {
+    /// File where the mesh is saved, see `Mesh::save`
+    pub path: PathBuf,
+    pub species: Species,
+    /// Height of the surface between the lowest and highest concentrations,
+    /// in cells
+    pub height: f32,
+    /// Thickness of the solid below the lowest point of the surface, in
+    /// cells, 0 for an open surface
+    pub base: f32,
+}
+
+impl Default for MeshConfig {
+    fn default() -> Self {
+        MeshConfig { path: PathBuf::from("pattern.obj"), species: Species::B, height: 10.0, base: 0.0 }
+    }
+}
+
+/// Triangle mesh
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    /// Unit normal of every vertex
+    pub normals: Vec<[f32; 3]>,
+    /// Texture coordinates of every vertex, matching the color map
+    pub uvs: Vec<[f32; 2]>,
+    /// sRGB color of every vertex, in [0,1], or none
+    pub colors: Vec<[f32; 3]>,
+    /// Vertices of the triangles, counterclockwise seen from outside
+    pub triangles: Vec<[u32; 3]>,
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// Little-endian bytes of `values`
+fn bytes<'a>(values: impl Iterator<Item = &'a f32>) -> Vec<u8> {
+    values.flat_map(|value| value.to_le_bytes()).collect()
+}
+
+impl Mesh {
+    /// Displaced grid of the concentrations of `config.species`
+    /// The cell at `row`, `col` is the vertex at x = `col`, z = `row`. Fails
+    /// for universes of fewer than 2 rows or columns
+    pub fn from_universe(universe: &Universe, config: &MeshConfig) -> Result<Mesh, SimulationError> {
+        let heights = heights(universe, config.species)?;
+        let rows = heights.len();
+        let cols = heights.first().map_or(0, |row| row.len());
+        if rows < 2 || cols < 2 {
+            return Err(SimulationError::InvalidMesh(Position { row: rows, col: cols }));
+        }
+
+        let mut mesh = Mesh::default();
+        let uv = |row: usize, col: usize| [col as f32 / (cols - 1) as f32, row as f32 / (rows - 1) as f32];
+        for (row, heights) in heights.iter().enumerate() {
+            for (col, height) in heights.iter().enumerate() {
+                mesh.positions.push([col as f32, height * config.height, row as f32]);
+                mesh.uvs.push(uv(row, col));
+            }
+        }
+        let vertex = |row: usize, col: usize| (row * cols + col) as u32;
+        for row in 0..rows - 1 {
+            for col in 0..cols - 1 {
+                let (top_left, top_right) = (vertex(row, col), vertex(row, col + 1));
+                let (bottom_left, bottom_right) = (vertex(row + 1, col), vertex(row + 1, col + 1));
+                mesh.triangles.push([top_left, bottom_left, top_right]);
+                mesh.triangles.push([top_right, bottom_left, bottom_right]);
+            }
+        }
+
+        if config.base > 0.0 {
+            // Cells of the edges, going around the grid once
+            let border: Vec<(usize, usize)> = (0..cols - 1)
+                .map(|col| (0, col))
+                .chain((0..rows - 1).map(|row| (row, cols - 1)))
+                .chain((1..cols).rev().map(|col| (rows - 1, col)))
+                .chain((1..rows).rev().map(|row| (row, 0)))
+                .collect();
+            let bottom = -config.base;
+            let first = mesh.positions.len() as u32;
+            // The walls and the bottom have their own vertices so that their
+            // normals are not smoothed with those of the surface
+            for &(row, col) in &border {
+                let top = mesh.positions[vertex(row, col) as usize];
+                mesh.positions.extend([top, [top[0], bottom, top[2]]]);
+                mesh.uvs.extend([uv(row, col); 2]);
+            }
+            let count = border.len() as u32;
+            for index in 0..count {
+                let next = (index + 1) % count;
+                let (top, low) = (first + 2 * index, first + 2 * index + 1);
+                let (next_top, next_low) = (first + 2 * next, first + 2 * next + 1);
+                mesh.triangles.push([top, next_top, low]);
+                mesh.triangles.push([next_top, next_low, low]);
+            }
+
+            let floor = mesh.positions.len() as u32;
+            for &(row, col) in &border {
+                mesh.positions.push([col as f32, bottom, row as f32]);
+                mesh.uvs.push(uv(row, col));
+            }
+            let center = mesh.positions.len() as u32;
+            mesh.positions.push([(cols - 1) as f32 / 2.0, bottom, (rows - 1) as f32 / 2.0]);
+            mesh.uvs.push([0.5, 0.5]);
+            for index in 0..count {
+                mesh.triangles.push([center, floor + index, floor + (index + 1) % count]);
+            }
+        }
+
+        mesh.normals = mesh.vertex_normals();
+        Ok(mesh)
+    }
+
+    /// Read a mesh from Wavefront OBJ
+    /// Only the positions, the texture coordinates and the faces are read;
+    /// polygons are split into triangles around their first vertex and a
+    /// vertex takes the texture coordinates of the first face using it. The
+    /// normals are computed from the faces
+    pub fn read_obj(reader: impl BufRead) -> Result<Mesh, SimulationError> {
+        let mut mesh = Mesh::default();
+        let mut uvs = Vec::new();
+        let mut vertex_uvs: Vec<Option<[f32; 2]>> = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let error = |message: &str| SimulationError::Format(format!("line {}: {message}", number + 1));
+            let mut fields = line.split_whitespace();
+            let values = |fields: std::str::SplitWhitespace| -> Result<Vec<f32>, SimulationError> {
+                fields.map(|field| field.parse().map_err(|_| error(&format!("invalid number `{field}`")))).collect()
+            };
+            match fields.next() {
+                Some("v") => match values(fields)?[..] {
+                    [x, y, z, ..] => {
+                        mesh.positions.push([x, y, z]);
+                        vertex_uvs.push(None);
+                    }
+                    _ => return Err(error("a vertex needs 3 coordinates")),
+                },
+                Some("vt") => match values(fields)?[..] {
+                    [u] => uvs.push([u, 1.0]),
+                    [u, v, ..] => uvs.push([u, 1.0 - v]),
+                    _ => return Err(error("texture coordinates need 1 or 2 values")),
+                },
+                Some("f") => {
+                    // Index of a vertex or texture coordinates, from 1 or
+                    // from the end if negative
+                    let index = |field: &str, count: usize| {
+                        let index: isize = field.parse().map_err(|_| error(&format!("invalid index `{field}`")))?;
+                        let index = if index < 0 { count as isize + index } else { index - 1 };
+                        usize::try_from(index)
+                            .ok()
+                            .filter(|index| *index < count)
+                            .ok_or_else(|| error(&format!("index `{field}` out of range")))
+                    };
+                    let mut corners = Vec::new();
+                    for corner in fields {
+                        let mut parts = corner.split('/');
+                        let vertex = index(parts.next().unwrap_or_default(), mesh.positions.len())?;
+                        if let Some(uv) = parts.next().filter(|uv| !uv.is_empty()) {
+                            let uv = uvs[index(uv, uvs.len())?];
+                            vertex_uvs[vertex].get_or_insert(uv);
+                        }
+                        corners.push(vertex as u32);
+                    }
+                    if corners.len() < 3 {
+                        return Err(error("a face needs at least 3 vertices"));
+                    }
+                    for pair in corners[1..].windows(2) {
+                        mesh.triangles.push([corners[0], pair[0], pair[1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        mesh.uvs = vertex_uvs.into_iter().map(Option::unwrap_or_default).collect();
+        mesh.normals = mesh.vertex_normals();
+        Ok(mesh)
+    }
+
+    /// Read a mesh from an OBJ file, see `read_obj`
+    #[cfg(feature = "fs")]
+    pub fn load(path: &Path) -> Result<Mesh, SimulationError> {
+        Mesh::read_obj(BufReader::new(File::open(path)?))
+            .map_err(|error| SimulationError::Load(path.to_path_buf(), Box::new(error)))
+    }
+
+    /// Normals of the vertices, the mean of the normals of their triangles
+    /// weighted by area
+    fn vertex_normals(&self) -> Vec<[f32; 3]> {
+        let mut normals = vec![[0.0; 3]; self.positions.len()];
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|vertex| self.positions[vertex as usize]);
+            let normal = cross(sub(b, a), sub(c, a));
+            for vertex in triangle {
+                for (sum, value) in normals[*vertex as usize].iter_mut().zip(normal) {
+                    *sum += value;
+                }
+            }
+        }
+        for normal in &mut normals {
+            let length = normal.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if length > 0.0 {
+                *normal = normal.map(|x| x / length);
+            }
+        }
+        normals
+    }
+
+    /// Write the mesh as Wavefront OBJ
+    /// The colors of the vertices follow their positions, as read by
+    /// Blender and MeshLab
+    pub fn write_obj(&self, mut writer: impl Write) -> Result<(), SimulationError> {
+        writeln!(writer, "# ca_turing_pattern")?;
+        for (index, [x, y, z]) in self.positions.iter().enumerate() {
+            match self.colors.get(index) {
+                Some([r, g, b]) => writeln!(writer, "v {x} {y} {z} {r} {g} {b}")?,
+                None => writeln!(writer, "v {x} {y} {z}")?,
+            }
+        }
+        for [u, v] in &self.uvs {
+            // OBJ texture coordinates start at the bottom of the image
+            writeln!(writer, "vt {u} {}", 1.0 - v)?;
+        }
+        for [x, y, z] in &self.normals {
+            writeln!(writer, "vn {x} {y} {z}")?;
+        }
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|vertex| vertex + 1);
+            writeln!(writer, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        Ok(writer.flush()?)
+    }
+
+    /// Write the mesh as binary glTF 2.0, one buffer with the positions,
+    /// normals, texture coordinates, indices and colors, if any
+    pub fn write_glb(&self, mut writer: impl Write) -> Result<(), SimulationError> {
+        let mut buffer = Vec::new();
+        let mut views = Vec::new();
+        let mut view = |bytes: Vec<u8>, target: u32| {
+            views.push(format!(
+                r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{target}}}"#,
+                buffer.len(),
+                bytes.len()
+            ));
+            buffer.extend(bytes);
+        };
+        view(bytes(self.positions.iter().flatten()), 34962);
+        view(bytes(self.normals.iter().flatten()), 34962);
+        view(bytes(self.uvs.iter().flatten()), 34962);
+        view(self.triangles.iter().flatten().flat_map(|index| index.to_le_bytes()).collect(), 34963);
+        // glTF colors are linear
+        let linear = |value: f32| match value {
+            value if value <= 0.04045 => value / 12.92,
+            value => ((value + 0.055) / 1.055).powf(2.4),
+        };
+        let colors: Vec<f32> = self.colors.iter().flatten().map(|value| linear(*value)).collect();
+        let (color_attribute, color_accessor) = if colors.is_empty() {
+            (String::new(), String::new())
+        } else {
+            view(bytes(colors.iter()), 34962);
+            (
+                r#","COLOR_0":4"#.to_string(),
+                format!(r#",{{"bufferView":4,"componentType":5126,"count":{},"type":"VEC3"}}"#, self.colors.len()),
+            )
+        };
+
+        let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+        for position in &self.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        let vertices = self.positions.len();
+        let json = format!(
+            concat!(
+                r#"{{"asset":{{"version":"2.0","generator":"ca_turing_pattern"}},"#,
+                r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+                r#""meshes":[{{"primitives":[{{"attributes":{{"POSITION":0,"NORMAL":1,"TEXCOORD_0":2{color_attribute}}},"indices":3}}]}}],"#,
+                r#""accessors":["#,
+                r#"{{"bufferView":0,"componentType":5126,"count":{vertices},"type":"VEC3","min":{min:?},"max":{max:?}}},"#,
+                r#"{{"bufferView":1,"componentType":5126,"count":{vertices},"type":"VEC3"}},"#,
+                r#"{{"bufferView":2,"componentType":5126,"count":{vertices},"type":"VEC2"}},"#,
+                r#"{{"bufferView":3,"componentType":5125,"count":{indices},"type":"SCALAR"}}{color_accessor}],"#,
+                r#""bufferViews":[{views}],"buffers":[{{"byteLength":{length}}}]}}"#
+            ),
+            vertices = vertices,
+            min = min,
+            max = max,
+            indices = self.triangles.len() * 3,
+            color_attribute = color_attribute,
+            color_accessor = color_accessor,
+            views = views.join(","),
+            length = buffer.len(),
+        );
+
+        // Both chunks are padded to a multiple of 4 bytes, the JSON with
+        // spaces and the buffer with zeros
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        buffer.resize(buffer.len().next_multiple_of(4), 0);
+        let length = 12 + 8 + json.len() + 8 + buffer.len();
+        writer.write_all(b"glTF")?;
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&(length as u32).to_le_bytes())?;
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(b"JSON")?;
+        writer.write_all(&json)?;
+        writer.write_all(&(buffer.len() as u32).to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&buffer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Save the mesh, as binary glTF for a `.glb` extension and OBJ
+    /// otherwise
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &Path) -> Result<(), SimulationError> {
+        let writer = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|extension| extension == "glb") {
+            self.write_glb(writer)
+        } else {
+            self.write_obj(writer)
+        }
+    }
+}
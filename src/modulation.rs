@@ -0,0 +1,216 @@
+/// Modulation of the kinetics by an external field
+/// An environmental gradient, e.g. of temperature or illumination, makes the
+/// reactions vary across the universe: a scalar field in [0,1], read from an
+/// image or computed, possibly moving, scales `f` and `k` of every cell at
+/// every evolution between the factors given for 0 and for 1. For instance a
+/// feed rate growing by a fifth towards the light of a wave is written in RON
+///
+/// ```ron
+/// modulation: Some((
+///     field: wave(wavelength: 80, angle: 30, period: 2000),
+///     f: (1.0, 1.2),
+/// )),
+/// ```
+use std::f32::consts::{PI, SQRT_2};
+use std::path::PathBuf;
+
+use image::imageops::{self, FilterType};
+use image::GrayImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{Parameters, Position, SimulationError};
+
+/// Scalar field modulating the rates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    /// Luminance of a PNG or JPEG file stretched over the universe, from 0
+    /// for black to 1 for white, or the other way round if inverted
+    Image {
+        path: PathBuf,
+        #[serde(default)]
+        invert: bool,
+    },
+    /// Ramp from 0 to 1 across the universe towards `angle`, in degrees
+    /// clockwise from the direction of the columns
+    Gradient { angle: f32 },
+    /// 1 at the center of the universe down to 0 at its corners
+    Radial,
+    /// Sine wave of `wavelength` cells moving towards `angle`, in degrees
+    /// clockwise from the direction of the columns, by one wavelength every
+    /// `period` generations, or still if 0
+    Wave { wavelength: f32, angle: f32, period: i32 },
+}
+
+/// Names of the procedural fields, as given on the command line
+pub const FIELD_NAMES: [&str; 3] = ["gradient", "radial", "wave"];
+
+impl Field {
+    /// Procedural field with the given name and its default settings, see
+    /// `FIELD_NAMES`
+    pub fn from_name(name: &str) -> Option<Field> {
+        match name {
+            "gradient" => Some(Field::Gradient { angle: 0.0 }),
+            "radial" => Some(Field::Radial),
+            "wave" => Some(Field::Wave { wavelength: 100.0, angle: 0.0, period: 1000 }),
+            _ => None,
+        }
+    }
+}
+
+/// Field and the factors of the rates it gives
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModulationConfig {
+    pub field: Field,
+    /// Factors of `f` where the field is 0 and where it is 1, interpolated
+    /// linearly in between
+    pub f: [f32; 2],
+    /// Factors of `k` where the field is 0 and where it is 1
+    pub k: [f32; 2],
+}
+
+impl Default for ModulationConfig {
+    fn default() -> Self {
+        ModulationConfig { field: Field::Gradient { angle: 0.0 }, f: [1.0, 1.0], k: [1.0, 1.0] }
+    }
+}
+
+impl ModulationConfig {
+    /// Check that the factors are not negative and the field can be computed
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidModulation(message));
+        for (name, factors) in [("f", self.f), ("k", self.k)] {
+            if factors.iter().any(|factor| !factor.is_finite() || *factor < 0.0) {
+                return error(format!("the factors of `{name}` must not be negative, found {factors:?}"));
+            }
+        }
+        if let Field::Wave { wavelength, period, .. } = self.field {
+            if !wavelength.is_finite() || wavelength <= 0.0 {
+                return error(format!("the wavelength must be positive, found {wavelength}"));
+            }
+            if period < 0 {
+                return error(format!("the period must not be negative, found {period}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Factors of `f` and `k` of every cell, row by row
+pub type RateFactors = Vec<Vec<[f32; 2]>>;
+
+/// Modulation ready to be computed, with its image loaded
+#[derive(Debug, Clone)]
+pub struct Modulation {
+    config: ModulationConfig,
+    image: Option<GrayImage>,
+}
+
+impl Modulation {
+    /// Fails if the configuration is invalid or its image cannot be read
+    pub fn new(config: ModulationConfig) -> Result<Modulation, SimulationError> {
+        config.validate()?;
+        let image = match &config.field {
+            #[cfg(feature = "fs")]
+            Field::Image { path, invert } => {
+                let mut image = image::open(path)
+                    .map_err(|error| SimulationError::Load(path.clone(), Box::new(error.into())))?
+                    .to_luma8();
+                if *invert {
+                    imageops::invert(&mut image);
+                }
+                Some(image)
+            }
+            #[cfg(not(feature = "fs"))]
+            Field::Image { .. } => {
+                return Err(SimulationError::Unsupported(
+                    "modulation images require the `fs` feature".to_string(),
+                ))
+            }
+            _ => None,
+        };
+        Ok(Modulation { config, image })
+    }
+
+    /// Modulation by the luminance of `image`, see `Field::Image`, whose
+    /// configuration has an empty path
+    pub fn from_image(image: GrayImage, f: [f32; 2], k: [f32; 2]) -> Result<Modulation, SimulationError> {
+        let config = ModulationConfig { field: Field::Image { path: PathBuf::new(), invert: false }, f, k };
+        config.validate()?;
+        Ok(Modulation { config, image: Some(image) })
+    }
+
+    pub fn config(&self) -> &ModulationConfig {
+        &self.config
+    }
+
+    /// Check that `f` and `k` of `parameters`, scaled by any factor of the
+    /// modulation, stay in [0,1]
+    pub fn validate(&self, parameters: Parameters) -> Result<(), SimulationError> {
+        for (name, rate, factors) in [("f", parameters.f, self.config.f), ("k", parameters.k, self.config.k)] {
+            let highest = rate * factors[0].max(factors[1]);
+            if highest > 1.0 {
+                return Err(SimulationError::InvalidModulation(format!(
+                    "`{name}` = {rate} scaled by the factors {factors:?} reaches {highest}, above 1"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the field changes from one generation to the next
+    pub fn is_animated(&self) -> bool {
+        matches!(self.config.field, Field::Wave { period, .. } if period != 0)
+    }
+
+    /// Value of the field at every cell of a universe of `dimensions` at
+    /// `generation`, in [0,1]
+    pub fn field(&self, dimensions: Position, generation: i32) -> Vec<Vec<f32>> {
+        let Position { row: rows, col: cols } = dimensions;
+        if let Some(image) = &self.image {
+            if rows == 0 || cols == 0 || image.width() == 0 || image.height() == 0 {
+                return vec![vec![0.0; cols]; rows];
+            }
+            let image = imageops::resize(image, cols as u32, rows as u32, FilterType::Triangle);
+            return image.rows().map(|row| row.map(|pixel| pixel.0[0] as f32 / 255.0).collect()).collect();
+        }
+
+        // Position of the center of a cell, from 0 to 1 across the universe
+        let center = |index: usize, size: usize| (index as f32 + 0.5) / size as f32;
+        let value = |row: usize, col: usize| match self.config.field {
+            Field::Gradient { angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                // Projection of the point, scaled so that the corners
+                // farthest apart along the ramp are 0 and 1
+                let projection = |x: f32, y: f32| x * cos + y * sin;
+                let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| projection(x, y));
+                let low = corners.iter().copied().fold(f32::INFINITY, f32::min);
+                let high = corners.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                (projection(center(col, cols), center(row, rows)) - low) / (high - low)
+            }
+            Field::Radial => {
+                let (x, y) = (center(col, cols) - 0.5, center(row, rows) - 0.5);
+                1.0 - x.hypot(y) * SQRT_2
+            }
+            Field::Wave { wavelength, angle, period } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let phase = if period == 0 { 0.0 } else { generation as f32 / period as f32 };
+                let distance = (col as f32 * cos + row as f32 * sin) / wavelength;
+                0.5 + 0.5 * (2.0 * PI * (distance - phase)).sin()
+            }
+            Field::Image { .. } => 0.0,
+        };
+        (0..rows).map(|row| (0..cols).map(|col| value(row, col).clamp(0.0, 1.0)).collect()).collect()
+    }
+
+    /// Factors of `f` and `k` of every cell of a universe of `dimensions` at
+    /// `generation`
+    pub fn factors(&self, dimensions: Position, generation: i32) -> RateFactors {
+        let lerp = |[low, high]: [f32; 2], t: f32| low + (high - low) * t;
+        self.field(dimensions, generation)
+            .into_iter()
+            .map(|row| row.into_iter().map(|value| [lerp(self.config.f, value), lerp(self.config.k, value)]).collect())
+            .collect()
+    }
+}
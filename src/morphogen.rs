@@ -0,0 +1,188 @@
+/// Morphogen field
+/// Read-only copy of the concentration of B of a universe, queried by
+/// points of a plane rather than by cells, so that entities moving over the
+/// pattern can react to it: the value under them, which way it increases,
+/// and where the closest spot or stripe is. In the window it is a resource
+/// of the application, see `app::build`, updated after every generation and
+/// placed where the first color map is drawn, so that the points are those
+/// of the world; elsewhere the points are in cells, `x` along the columns
+/// and `y` along the rows, the center of the cell (row, col) being at
+/// (col + 0.5, row + 0.5)
+#[cfg(feature = "bevy")]
+use bevy::prelude::Resource;
+
+use crate::{Float, Position, Universe};
+
+/// Position of a field in the plane of its points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Point of the top left corner of the universe
+    pub origin: [f32; 2],
+    /// Size of a cell along `x` and `y`, negative along `y` if the rows go
+    /// down while `y` goes up, as in the world of the window
+    pub cell_size: [f32; 2],
+}
+
+impl Default for Placement {
+    /// Points in cells
+    fn default() -> Self {
+        Placement { origin: [0.0, 0.0], cell_size: [1.0, 1.0] }
+    }
+}
+
+/// Concentration of B of a universe at one generation
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
+pub struct MorphogenField {
+    generation: i32,
+    dimensions: Position,
+    placement: Placement,
+    /// B of every cell, row by row
+    values: Vec<f32>,
+}
+
+impl MorphogenField {
+    /// Field of `universe` at `generation`, its points in cells
+    pub fn new<T: Float>(universe: &Universe<T>, generation: i32) -> MorphogenField {
+        let dimensions = Position { row: universe.len(), col: universe.first().map_or(0, Vec::len) };
+        let values = universe.iter().flatten().map(|cell| cell.b.to_f32()).collect();
+        MorphogenField { generation, dimensions, placement: Placement::default(), values }
+    }
+
+    /// Same field, with its points placed as `placement` says
+    pub fn with_placement(mut self, placement: Placement) -> MorphogenField {
+        self.placement = placement;
+        self
+    }
+
+    pub fn generation(&self) -> i32 {
+        self.generation
+    }
+
+    pub fn dimensions(&self) -> Position {
+        self.dimensions
+    }
+
+    pub fn placement(&self) -> Placement {
+        self.placement
+    }
+
+    /// B of the cell at `position`, `None` outside of the universe
+    pub fn value(&self, position: Position) -> Option<f32> {
+        (position.row < self.dimensions.row && position.col < self.dimensions.col)
+            .then(|| self.values[position.row * self.dimensions.col + position.col])
+    }
+
+    /// Cell coordinates of `point`, `x` along the columns and `y` along the
+    /// rows
+    pub fn to_cells(&self, point: [f32; 2]) -> [f32; 2] {
+        let Placement { origin, cell_size } = self.placement;
+        [(point[0] - origin[0]) / cell_size[0], (point[1] - origin[1]) / cell_size[1]]
+    }
+
+    /// Point of the cell coordinates `cells`
+    pub fn to_point(&self, cells: [f32; 2]) -> [f32; 2] {
+        let Placement { origin, cell_size } = self.placement;
+        [origin[0] + cells[0] * cell_size[0], origin[1] + cells[1] * cell_size[1]]
+    }
+
+    /// Cell at `point`, `None` outside of the universe
+    pub fn cell_at(&self, point: [f32; 2]) -> Option<Position> {
+        let [x, y] = self.to_cells(point);
+        let inside = x >= 0.0 && y >= 0.0 && (x as usize) < self.dimensions.col && (y as usize) < self.dimensions.row;
+        inside.then_some(Position { row: y as usize, col: x as usize })
+    }
+
+    /// B at `point`, interpolated between the centers of the four closest
+    /// cells, `None` outside of the universe
+    pub fn sample(&self, point: [f32; 2]) -> Option<f32> {
+        self.cell_at(point)?;
+        let [x, y] = self.to_cells(point);
+        Some(self.interpolate(x - 0.5, y - 0.5))
+    }
+
+    /// Change of B per unit along `x` and `y` at `point`, pointing to where
+    /// it increases the most; `None` outside of the universe
+    pub fn gradient(&self, point: [f32; 2]) -> Option<[f32; 2]> {
+        self.cell_at(point)?;
+        let [x, y] = self.to_cells(point);
+        let (x, y) = (x - 0.5, y - 0.5);
+        let dx = (self.interpolate(x + 1.0, y) - self.interpolate(x - 1.0, y)) / 2.0;
+        let dy = (self.interpolate(x, y + 1.0) - self.interpolate(x, y - 1.0)) / 2.0;
+        let [width, height] = self.placement.cell_size;
+        Some([dx / width, dy / height])
+    }
+
+    /// Point of the center of the closest cell within `radius` of `point`
+    /// whose B is a peak, not lower than any of its eight neighbours and
+    /// higher than one of them, e.g. the center of a spot or the ridge of a
+    /// stripe; `None` if there is none
+    /// The radius is in units of the points, and the cells are looked for
+    /// in the square around it
+    pub fn nearest_peak(&self, point: [f32; 2], radius: f32) -> Option<[f32; 2]> {
+        let [x, y] = self.to_cells(point);
+        let [width, height] = self.placement.cell_size.map(f32::abs);
+        let (rx, ry) = (radius / width, radius / height);
+        let range = |center: f32, reach: f32, size: usize| {
+            let start = (center - reach).floor().max(0.0) as usize;
+            let end = ((center + reach).ceil().max(0.0) as usize).min(size);
+            start..end
+        };
+        let cols = range(x, rx, self.dimensions.col);
+        let mut nearest: Option<([f32; 2], f32)> = None;
+        for row in range(y, ry, self.dimensions.row) {
+            for col in cols.clone() {
+                let position = Position { row, col };
+                if !self.is_peak(position) {
+                    continue;
+                }
+                let center = self.to_point([col as f32 + 0.5, row as f32 + 0.5]);
+                let distance = (center[0] - point[0]).hypot(center[1] - point[1]);
+                if distance <= radius && nearest.is_none_or(|(_, closest)| distance < closest) {
+                    nearest = Some((center, distance));
+                }
+            }
+        }
+        nearest.map(|(center, _)| center)
+    }
+
+    /// Whether the cell at `position` is not lower than its neighbours and
+    /// higher than one of them
+    fn is_peak(&self, position: Position) -> bool {
+        let Some(value) = self.value(position) else {
+            return false;
+        };
+        let mut higher = false;
+        for (dr, dc) in [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)] {
+            let neighbour = position
+                .row
+                .checked_add_signed(dr)
+                .zip(position.col.checked_add_signed(dc))
+                .and_then(|(row, col)| self.value(Position { row, col }));
+            match neighbour {
+                Some(neighbour) if neighbour > value => return false,
+                Some(neighbour) if neighbour < value => higher = true,
+                _ => {}
+            }
+        }
+        higher
+    }
+
+    /// B between the centers of the cells, `x` and `y` being the coordinates
+    /// of the cell centers, clamped to the edges of the universe
+    fn interpolate(&self, x: f32, y: f32) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let (cols, rows) = (self.dimensions.col, self.dimensions.row);
+        let x = x.clamp(0.0, (cols - 1) as f32);
+        let y = y.clamp(0.0, (rows - 1) as f32);
+        let (col, row) = (x.floor() as usize, y.floor() as usize);
+        let (next_col, next_row) = ((col + 1).min(cols - 1), (row + 1).min(rows - 1));
+        let (tx, ty) = (x - col as f32, y - row as f32);
+        let at = |row: usize, col: usize| self.values[row * cols + col];
+        let top = at(row, col) * (1.0 - tx) + at(row, next_col) * tx;
+        let bottom = at(next_row, col) * (1.0 - tx) + at(next_row, next_col) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
@@ -0,0 +1,88 @@
+/// Names of the runs
+/// Every run gets an identifier built from its settings, e.g.
+/// `gs_f0.035_k0.065_seed42`, by a template in which the fields of
+/// `NAME_FIELDS` between braces are replaced by their values:
+///
+/// ```ron
+/// name: "gs_f{f}_k{k}_seed{seed}",
+/// ```
+///
+/// The identifier titles the window and is written instead of `{run}` in
+/// the paths of the outputs, the checkpoint directory included, so that the
+/// files of many runs do not overwrite each other
+use std::path::{Path, PathBuf};
+
+use crate::{Parameters, Position, SimulationError};
+
+/// Template of the identifiers, unless configured otherwise
+pub const DEFAULT_NAME_TEMPLATE: &str = "gs_f{f}_k{k}_seed{seed}";
+
+/// Fields usable in a template
+pub const NAME_FIELDS: [&str; 9] = ["f", "k", "d_a", "d_b", "r", "seed", "rows", "cols", "steps"];
+
+/// Placeholder of the identifier in the paths of the outputs
+pub const RUN_PLACEHOLDER: &str = "{run}";
+
+/// Settings of a run named by a template
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunInfo {
+    pub parameters: Parameters,
+    pub dimensions: Position,
+    /// Seed of the run, `random` in the identifier if not given
+    pub seed: Option<u64>,
+    pub steps: i32,
+}
+
+impl RunInfo {
+    /// Value of the field `name` of `NAME_FIELDS`
+    fn field(&self, name: &str) -> Option<String> {
+        let Parameters { d_a, d_b, f, k, r, .. } = self.parameters;
+        Some(match name {
+            "f" => f.to_string(),
+            "k" => k.to_string(),
+            "d_a" => d_a.to_string(),
+            "d_b" => d_b.to_string(),
+            "r" => r.to_string(),
+            "seed" => self.seed.map_or_else(|| "random".to_string(), |seed| seed.to_string()),
+            "rows" => self.dimensions.row.to_string(),
+            "cols" => self.dimensions.col.to_string(),
+            "steps" => self.steps.to_string(),
+            _ => return None,
+        })
+    }
+}
+
+/// Identifier of the run of `info` following `template`
+/// Fails if a brace is not closed or a field is unknown
+pub fn run_name(template: &str, info: &RunInfo) -> Result<String, SimulationError> {
+    let error = |message: String| Err(SimulationError::InvalidName(message));
+    let mut name = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return error(format!("unclosed brace in `{template}`"));
+        };
+        let field = &rest[start + 1..start + end];
+        match info.field(field) {
+            Some(value) => name.push_str(&value),
+            None => {
+                return error(format!("unknown field `{field}`, expected one of: {}", NAME_FIELDS.join(", ")));
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return error(format!("`{name}` cannot name files"));
+    }
+    Ok(name)
+}
+
+/// `path` with `{run}` replaced by the identifier `name`
+pub fn expand_path(path: &Path, name: &str) -> PathBuf {
+    match path.to_str() {
+        Some(text) if text.contains(RUN_PLACEHOLDER) => PathBuf::from(text.replace(RUN_PLACEHOLDER, name)),
+        _ => path.to_path_buf(),
+    }
+}
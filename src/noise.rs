@@ -0,0 +1,80 @@
+/// Noise added to the evolution
+/// Fluctuations of the concentrations, e.g. thermal or from the environment,
+/// are modelled by adding to the B of every cell, after every evolution, a
+/// number drawn uniformly from [-amplitude, amplitude], B staying
+/// non-negative. The numbers come from the noise stream, see `streams`,
+/// drawn by the portable generator of `deterministic`, so that noisy runs
+/// can be deterministic too. Each evolution draws from its own stream of the
+/// generator, numbered by the generation, so that a run restored from a
+/// session or checkpoint adds the same noise as the run it was saved from.
+/// The added B is not part of the reaction terms, so the conservation check
+/// counts it as a leak
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::deterministic::portable_rng;
+use crate::{Float, SimulationError, Universe};
+
+/// Settings of the noise
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoiseConfig {
+    /// Largest change of B of a cell in one evolution
+    pub amplitude: f64,
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        NoiseConfig { amplitude: 0.001 }
+    }
+}
+
+impl NoiseConfig {
+    /// Fails if the amplitude is negative or not finite
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if !self.amplitude.is_finite() || self.amplitude < 0.0 {
+            return Err(SimulationError::InvalidNoise(format!(
+                "the amplitude must not be negative, found {}",
+                self.amplitude
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Noise with the seed of its generator
+#[derive(Debug, Clone)]
+pub struct Noise {
+    config: NoiseConfig,
+    seed: u64,
+}
+
+impl Noise {
+    /// Noise drawn from `seed`, the seed of the noise stream
+    /// Fails if the configuration is invalid
+    pub fn new(config: NoiseConfig, seed: u64) -> Result<Noise, SimulationError> {
+        config.validate()?;
+        Ok(Noise { config, seed })
+    }
+
+    pub fn config(&self) -> NoiseConfig {
+        self.config
+    }
+
+    /// Seed of the noise stream the noise is drawn from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Add the noise of the evolution computing `generation` to `universe`,
+    /// row by row
+    pub fn apply<T: Float>(&self, universe: &mut Universe<T>, generation: i32) {
+        let amplitude = self.config.amplitude;
+        let mut rng = portable_rng(self.seed);
+        rng.set_stream(generation as u64);
+        for cell in universe.iter_mut().flatten() {
+            let b = cell.b + T::from_f64(amplitude * (2.0 * rng.gen::<f64>() - 1.0));
+            cell.b = if b < T::from_f64(0.0) { T::from_f64(0.0) } else { b };
+        }
+    }
+}
@@ -0,0 +1,42 @@
+/// Batches of independent runs
+/// Sweeps and batches of textures compute many runs, each on one thread from
+/// start to end, so no result depends on how the runs are split between the
+/// threads. The threads take the next run as soon as they are done with one,
+/// since runs may take very different times, e.g. when some blow up early
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Compute `run` for every job on `threads` threads, all the available ones
+/// if 0, returning the results in the order of `jobs`
+/// `run` is given the index of the job and the job
+pub(crate) fn run_jobs<J, R, F>(jobs: &[J], threads: usize, run: F) -> Vec<R>
+where
+    J: Sync,
+    R: Send,
+    F: Fn(usize, &J) -> R + Sync,
+{
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(jobs.len()));
+    thread::scope(|scope| {
+        for _ in 0..threads.min(jobs.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(index) else {
+                    break;
+                };
+                let result = run(index, job);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
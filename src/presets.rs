@@ -0,0 +1,71 @@
+/// Preset library
+/// Named parameter sets stored in a RON file (by default
+/// `assets/presets.ron`), so presets can be added or tweaked without
+/// recompiling. The file is a map from names to parameters:
+///
+/// ```ron
+/// {
+///     "spots": (d_a: 1.0, d_b: 0.5, f: 0.055, k: 0.117, r: 1.0),
+/// }
+/// ```
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fs")]
+use crate::SimulationError;
+use crate::Parameters;
+
+/// Preset file read by the application and the command line
+pub const PRESETS_PATH: &str = "assets/presets.ron";
+
+/// Named parameter sets, sorted by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PresetLibrary {
+    pub presets: BTreeMap<String, Parameters>,
+}
+
+impl PresetLibrary {
+    /// Library from the text of a preset file
+    pub fn from_ron(text: &str) -> Result<PresetLibrary, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// Read a preset file
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<Path>) -> Result<PresetLibrary, SimulationError> {
+        let path = path.as_ref();
+        let failed = |error| SimulationError::Load(path.to_path_buf(), Box::new(error));
+        let text = fs::read_to_string(path).map_err(|error| failed(error.into()))?;
+        PresetLibrary::from_ron(&text)
+            .map_err(|error| failed(SimulationError::Format(format!("invalid presets: {error}"))))
+    }
+
+    /// Parameters of the preset `name`
+    pub fn get(&self, name: &str) -> Option<Parameters> {
+        self.presets.get(name).copied()
+    }
+
+    /// Names of the presets, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+}
+
+/// Parameters of the preset `name`, looked up first in the preset file at
+/// `PRESETS_PATH`, if it can be read, then among the built-in presets
+pub fn find_preset(name: &str) -> Option<Parameters> {
+    #[cfg(feature = "fs")]
+    if let Some(parameters) = PresetLibrary::load(PRESETS_PATH)
+        .ok()
+        .and_then(|library| library.get(name))
+    {
+        return Some(parameters);
+    }
+    Parameters::preset(name)
+}
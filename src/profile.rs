@@ -0,0 +1,117 @@
+/// Profiling the stages of the simulation
+/// Every stage of an evolution, and of drawing it in the application, runs
+/// in a `tracing` span named `stage`, with the name of the stage as a field,
+/// which any subscriber sees, e.g. the Chrome tracing of the `trace_chrome`
+/// feature of Bevy. Once enabled with `enable`, the time spent in each stage
+/// is also added up here for the diagnostics of the application and the
+/// report of `--profile`. Times are not measured on the web, where the clock
+/// of the standard library is not available
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Taking an evolution out of the simulation: timeline, modulation
+pub const PREPARE: &str = "prepare";
+/// Diffusion and reaction of every cell, with their gray levels
+pub const DIFFUSION: &str = "diffusion";
+/// Putting an evolution back: bounds and observers
+pub const FINISH: &str = "finish";
+/// Coloring the gray levels of the color maps, in the application
+pub const COLORING: &str = "coloring";
+/// Queueing the colored regions to be uploaded to the textures, in the
+/// application
+pub const UPLOAD: &str = "upload";
+/// Names of the stages, in their order in a generation
+pub const STAGES: [&str; 5] = [PREPARE, DIFFUSION, FINISH, COLORING, UPLOAD];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TIMES: Mutex<BTreeMap<&str, StageTimes>> = Mutex::new(BTreeMap::new());
+
+/// Time spent in one stage since profiling was enabled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimes {
+    /// Number of times the stage ran
+    pub count: u64,
+    pub total: Duration,
+    /// Longest run of the stage
+    pub max: Duration,
+}
+
+impl StageTimes {
+    /// Mean time of one run, zero if the stage never ran
+    pub fn mean(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => self.total.div_f64(count as f64),
+        }
+    }
+}
+
+/// Start adding up the time spent in each stage
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether the time spent in each stage is added up
+pub fn is_enabled() -> bool {
+    !cfg!(target_arch = "wasm32") && ENABLED.load(Ordering::Relaxed)
+}
+
+/// Run `f` as the stage `name`, in a span, timing it if profiling is enabled
+pub fn time<R>(name: &'static str, f: impl FnOnce() -> R) -> R {
+    let _span = tracing::info_span!("stage", name).entered();
+    #[cfg(not(target_arch = "wasm32"))]
+    if is_enabled() {
+        let start = Instant::now();
+        let result = f();
+        record(name, start.elapsed());
+        return result;
+    }
+    f()
+}
+
+/// Add a run of `duration` to the stage `name`
+pub fn record(name: &'static str, duration: Duration) {
+    let mut times = TIMES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let stage = times.entry(name).or_default();
+    stage.count += 1;
+    stage.total += duration;
+    stage.max = stage.max.max(duration);
+}
+
+/// Time spent in each stage that ran, by name
+pub fn times() -> BTreeMap<&'static str, StageTimes> {
+    TIMES.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Forget the time spent so far
+pub fn reset() {
+    TIMES.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+}
+
+/// Table of the time spent in each stage that ran, in milliseconds, the
+/// stages of `STAGES` first
+pub fn report() -> String {
+    let times = times();
+    let mut names: Vec<&str> = STAGES.iter().copied().filter(|name| times.contains_key(name)).collect();
+    names.extend(times.keys().copied().filter(|name| !STAGES.contains(name)));
+
+    let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let mut report = format!("{:<12}{:>10}{:>12}{:>12}{:>12}\n", "stage", "runs", "total ms", "mean ms", "max ms");
+    for name in names {
+        let stage = times[name];
+        let _ = writeln!(
+            report,
+            "{name:<12}{:>10}{:>12.1}{:>12.3}{:>12.3}",
+            stage.count,
+            milliseconds(stage.total),
+            milliseconds(stage.mean()),
+            milliseconds(stage.max),
+        );
+    }
+    report
+}
@@ -0,0 +1,165 @@
+/// Named simulation profiles
+/// A profile is the setup of an experiment: the parameters, the color map,
+/// the dimensions of the universe and its initial condition. Profiles are
+/// saved under a name from the console of the application and switched to
+/// with the console or `Tab`, which goes through them in the order of their
+/// names. They are kept in a RON file of the user configuration directory,
+/// see `default_path`, a map from names to profiles:
+///
+/// ```ron
+/// {
+///     "spots": (
+///         parameters: (d_a: 1.0, d_b: 0.5, f: 0.055, k: 0.117, r: 1.0),
+///         colormap: viridis,
+///         dimensions: (row: 256, col: 256),
+///         initial: circles(count: 5, radius: 8.0),
+///     ),
+/// }
+/// ```
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::env;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::Colormap;
+use crate::initial::InitialCondition;
+use crate::{Parameters, Position, SimulationError};
+
+/// Name of the directory of the application in the user configuration
+/// directory
+#[cfg(feature = "fs")]
+const APPLICATION_DIRECTORY: &str = "ca_turing_pattern";
+/// Name of the profile file in that directory
+#[cfg(feature = "fs")]
+const PROFILES_FILE: &str = "profiles.ron";
+
+/// Setup of an experiment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub parameters: Parameters,
+    pub colormap: Colormap,
+    pub dimensions: Position,
+    pub initial: InitialCondition,
+}
+
+impl Profile {
+    /// Fails if the parameters or the initial condition are invalid, or if
+    /// the universe has no cells
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        self.parameters.validate()?;
+        self.initial.validate()?;
+        self.colormap.validate()?;
+        if self.dimensions.row == 0 || self.dimensions.col == 0 {
+            return Err(SimulationError::InvalidInitial(format!(
+                "a profile needs cells, not {}x{}",
+                self.dimensions.row, self.dimensions.col
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Named profiles, sorted by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProfileLibrary {
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ProfileLibrary {
+    /// Library from the text of a profile file
+    pub fn from_ron(text: &str) -> Result<ProfileLibrary, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// Text of a profile file
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    /// Read a profile file, the library being empty if there is none yet
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<Path>) -> Result<ProfileLibrary, SimulationError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(ProfileLibrary::default());
+        }
+        let failed = |error| SimulationError::Load(path.to_path_buf(), Box::new(error));
+        let text = fs::read_to_string(path).map_err(|error| failed(error.into()))?;
+        ProfileLibrary::from_ron(&text)
+            .map_err(|error| failed(SimulationError::Format(format!("invalid profiles: {error}"))))
+    }
+
+    /// Write the library to a profile file, creating its directory if needed
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SimulationError> {
+        let path = path.as_ref();
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let text = self.to_ron().map_err(|error| SimulationError::Format(error.to_string()))?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Profile `name`
+    pub fn get(&self, name: &str) -> Option<Profile> {
+        self.profiles.get(name).copied()
+    }
+
+    /// Save `profile` as `name`, replacing the profile of that name
+    pub fn insert(&mut self, name: &str, profile: Profile) {
+        self.profiles.insert(name.to_string(), profile);
+    }
+
+    /// Forget the profile `name`, returning it if there was one
+    pub fn remove(&mut self, name: &str) -> Option<Profile> {
+        self.profiles.remove(name)
+    }
+
+    /// Names of the profiles, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// Name of the profile after `current` in the order of the names, the
+    /// first one after the last one or without a current profile
+    pub fn next(&self, current: Option<&str>) -> Option<&str> {
+        current
+            .and_then(|current| self.names().find(|name| *name > current))
+            .or_else(|| self.names().next())
+    }
+}
+
+/// Profile file in the user configuration directory: under
+/// `$XDG_CONFIG_HOME`, or `~/.config`, on Linux and other Unix systems,
+/// `~/Library/Application Support` on macOS and `%APPDATA%` on Windows
+/// `None` if the directory is unknown, e.g. without a home directory
+#[cfg(feature = "fs")]
+pub fn default_path() -> Option<PathBuf> {
+    Some(user_config_dir()?.join(APPLICATION_DIRECTORY).join(PROFILES_FILE))
+}
+
+#[cfg(all(feature = "fs", target_os = "windows"))]
+fn user_config_dir() -> Option<PathBuf> {
+    env::var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(all(feature = "fs", target_os = "macos"))]
+fn user_config_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+}
+
+#[cfg(all(feature = "fs", not(any(target_os = "windows", target_os = "macos"))))]
+fn user_config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|directory| !directory.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
@@ -0,0 +1,179 @@
+/// Python bindings
+/// With the `python` feature the crate builds the `ca_turing_pattern` Python
+/// module (e.g. with `maturin develop --features python`), exposing the
+/// headless simulation as the `Simulation` class:
+///
+/// ```python
+/// from ca_turing_pattern import Simulation
+/// sim = Simulation(200, 200, preset="spots", seed=42)
+/// sim.set_parameters(f=0.05)
+/// sim.step(1000)
+/// b = sim.b()  # numpy.ndarray of shape (200, 200)
+/// ```
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::export::Species;
+use crate::{
+    Bounds, Parameters, ParametersBuilder, Position, Simulation, SimulationError, INITIAL_CELLS,
+    BOUNDS_NAMES, PRESET_NAMES,
+};
+
+/// Simulation of a universe, stepped from Python
+#[pyclass(name = "Simulation", module = "ca_turing_pattern")]
+struct PySimulation {
+    simulation: Simulation,
+}
+
+#[pymethods]
+impl PySimulation {
+    /// Universe of `rows` x `cols` cells with `cells` random initial cells
+    /// `bounds` is one of `BOUNDS_NAMES`
+    #[new]
+    #[pyo3(signature = (rows, cols, preset = "default", seed = None, cells = INITIAL_CELLS, bounds = "unchecked"))]
+    fn new(
+        rows: usize,
+        cols: usize,
+        preset: &str,
+        seed: Option<u64>,
+        cells: usize,
+        bounds: &str,
+    ) -> PyResult<Self> {
+        let parameters = Parameters::preset(preset).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "unknown preset `{preset}`, expected one of: {}",
+                PRESET_NAMES.join(", ")
+            ))
+        })?;
+        let bounds = Bounds::from_name(bounds).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "unknown bounds `{bounds}`, expected one of: {}",
+                BOUNDS_NAMES.join(", ")
+            ))
+        })?;
+        let dimensions = Position { row: rows, col: cols };
+        let simulation = match seed {
+            Some(seed) => Simulation::random(parameters, dimensions, cells, &mut StdRng::seed_from_u64(seed))?,
+            None => Simulation::random(parameters, dimensions, cells, &mut rand::thread_rng())?,
+        };
+
+        Ok(PySimulation { simulation: simulation.with_bounds(bounds) })
+    }
+
+    /// Change some of the parameters, the others keep their value
+    /// Raises ValueError if the new parameters are invalid
+    #[pyo3(signature = (d_a = None, d_b = None, f = None, k = None, r = None))]
+    fn set_parameters(
+        &mut self,
+        d_a: Option<f32>,
+        d_b: Option<f32>,
+        f: Option<f32>,
+        k: Option<f32>,
+        r: Option<f32>,
+    ) -> PyResult<()> {
+        let current = self.simulation.parameters();
+        let parameters = ParametersBuilder::from(current)
+            .d_a(d_a.unwrap_or(current.d_a))
+            .d_b(d_b.unwrap_or(current.d_b))
+            .f(f.unwrap_or(current.f))
+            .k(k.unwrap_or(current.k))
+            .r(r.unwrap_or(current.r))
+            .build()
+            .map_err(SimulationError::from)?;
+        Ok(self.simulation.set_parameters(parameters)?)
+    }
+
+    /// Current parameters as a dict
+    fn parameters(&self) -> std::collections::HashMap<&'static str, f32> {
+        let parameters = self.simulation.parameters();
+        [
+            ("d_a", parameters.d_a),
+            ("d_b", parameters.d_b),
+            ("f", parameters.f),
+            ("k", parameters.k),
+            ("r", parameters.r),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// Compute `n` evolutions, releasing the GIL meanwhile
+    /// Raises ValueError if a concentration leaves [0,1] with the strict bounds
+    #[pyo3(signature = (n = 1))]
+    fn step(&mut self, py: Python<'_>, n: i32) -> PyResult<()> {
+        py.allow_threads(|| {
+            self.simulation.run(n);
+        });
+        match self.simulation.violation() {
+            Some(violation) => Err(SimulationError::OutOfBounds(*violation).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of evolutions computed so far
+    #[getter]
+    fn generation(&self) -> i32 {
+        self.simulation.generation()
+    }
+
+    /// `(rows, cols)` of the universe
+    #[getter]
+    fn shape(&self) -> (usize, usize) {
+        let dimensions = self.simulation.dimensions();
+        (dimensions.row, dimensions.col)
+    }
+
+    /// `(a, b)` at the point `(x, y)`, interpolated between the cells, with
+    /// `x` along the columns and `y` along the rows
+    fn sample(&self, x: f32, y: f32) -> (f32, f32) {
+        let cell = self.simulation.sample(x, y);
+        (cell.a, cell.b)
+    }
+
+    /// Copy of the A concentrations as a `(rows, cols)` float32 array
+    fn a<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        self.field(Species::A).into_pyarray_bound(py)
+    }
+
+    /// Copy of the B concentrations as a `(rows, cols)` float32 array
+    fn b<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        self.field(Species::B).into_pyarray_bound(py)
+    }
+
+    /// Copy of the color map as a `(rows, cols)` float32 array
+    fn colors<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        let dimensions = self.simulation.dimensions();
+        let colored_map = self.simulation.colored_map();
+        Array2::from_shape_fn((dimensions.row, dimensions.col), |(r, c)| colored_map[r][c])
+        .into_pyarray_bound(py)
+    }
+}
+
+impl PySimulation {
+    fn field(&self, species: Species) -> Array2<f32> {
+        let dimensions = self.simulation.dimensions();
+        let universe = self.simulation.universe();
+        Array2::from_shape_fn((dimensions.row, dimensions.col), |(r, c)| {
+            species.concentration(&universe[r][c])
+        })
+    }
+}
+
+impl From<SimulationError> for PyErr {
+    fn from(error: SimulationError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+/// The `ca_turing_pattern` Python module
+#[pymodule]
+fn ca_turing_pattern(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySimulation>()?;
+    m.add("PRESET_NAMES", PRESET_NAMES.to_vec())?;
+    m.add("BOUNDS_NAMES", BOUNDS_NAMES.to_vec())?;
+    Ok(())
+}
@@ -0,0 +1,473 @@
+/// Reaction terms given as scripts
+/// The reactions of the Gray–Scott model, feeding A, killing B and turning
+/// A + 2B into 3B, can be replaced by other kinetics: a `Reaction` gives the
+/// change of A and of B of a cell over one evolution from its
+/// concentrations and its parameters, after `f` and `k` are scaled by the
+/// modulation if any, and the diffusion is added to it. Besides reactions
+/// written in Rust, the `scripting` feature compiles the expressions of a
+/// `ReactionConfig` once, and evaluates them for every cell with the
+/// variables `a`, `b`, `d_a`, `d_b`, `f`, `k` and `r`. Expressions made only
+/// of numbers, these variables, arithmetic and the usual functions are
+/// compiled to a small program evaluated without Rhai, many times faster;
+/// the others, e.g. with conditions, are evaluated by Rhai. For instance the
+/// Gray–Scott reactions with a saturating feed are written in RON
+///
+/// ```ron
+/// reaction: Some((
+///     a: "f * (1.0 - a) / (1.0 + a) - r * a * b * b",
+///     b: "r * a * b * b - k * b",
+/// )),
+/// ```
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::gray_scott;
+use crate::{Cell, Parameters, SimulationError};
+
+/// Reaction terms of the cells, replacing those of the Gray–Scott model
+pub trait Reaction: fmt::Debug + Send + Sync {
+    /// Change of A and of B over one evolution of a cell with concentrations
+    /// `a` and `b` and `parameters`
+    fn react(&self, a: f64, b: f64, parameters: &Parameters) -> [f64; 2];
+
+    /// Expressions the reaction was compiled from, if any, e.g. to save them
+    /// in a session
+    fn script(&self) -> Option<&ReactionConfig> {
+        None
+    }
+}
+
+/// Reactions of the Gray–Scott model, as computed without a `Reaction`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GrayScott;
+
+impl Reaction for GrayScott {
+    fn react(&self, a: f64, b: f64, parameters: &Parameters) -> [f64; 2] {
+        gray_scott(parameters, Cell { a, b })
+    }
+}
+
+/// Expressions of the change of A and of B of a cell, by default those of
+/// the Gray–Scott model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReactionConfig {
+    pub a: String,
+    pub b: String,
+}
+
+impl Default for ReactionConfig {
+    fn default() -> Self {
+        ReactionConfig { a: "f * (1.0 - a) - r * a * b * b".to_string(), b: "r * a * b * b - k * b".to_string() }
+    }
+}
+
+impl ReactionConfig {
+    /// Compile the expressions, see `ScriptedReaction::new`
+    /// Fails without the `scripting` feature
+    pub fn compile(&self) -> Result<Arc<dyn Reaction>, SimulationError> {
+        #[cfg(feature = "scripting")]
+        return Ok(Arc::new(ScriptedReaction::new(self.clone())?));
+        #[cfg(not(feature = "scripting"))]
+        Err(SimulationError::Unsupported("reaction scripts require the `scripting` feature".to_string()))
+    }
+}
+
+/// Names of the variables of the expressions, in the order they are pushed
+#[cfg(feature = "scripting")]
+const VARIABLES: [&str; 7] = ["a", "b", "d_a", "d_b", "f", "k", "r"];
+
+#[cfg(feature = "scripting")]
+thread_local! {
+    /// Scope the expressions are evaluated in, kept from one cell to the next
+    /// so that its variables are not allocated again
+    static SCOPE: std::cell::RefCell<rhai::Scope<'static>> = std::cell::RefCell::new(rhai::Scope::new());
+}
+
+/// Largest number of values on the stack of a `Program`
+#[cfg(feature = "scripting")]
+const STACK_SIZE: usize = 32;
+
+/// Instruction of a `Program`, working on a stack of values
+#[cfg(feature = "scripting")]
+#[derive(Debug, Clone, Copy)]
+enum Instruction {
+    Number(f64),
+    /// Value of the variable at this index of `VARIABLES`
+    Variable(usize),
+    Negate,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+    Function(fn(f64) -> f64),
+    Function2(fn(f64, f64) -> f64),
+}
+
+/// Arithmetic expression compiled to instructions
+#[cfg(feature = "scripting")]
+#[derive(Debug, Clone)]
+struct Program(Vec<Instruction>);
+
+/// Type of the value of a part of an expression, since Rhai computes with
+/// integers when both operands are
+#[cfg(feature = "scripting")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Integer,
+    Float,
+}
+
+/// Compiler of the arithmetic expressions to `Program`, following the
+/// precedence of Rhai: unary operators first, then `**` from the right, then
+/// `*`, `/` and `%` and last `+` and `-`
+/// Every method returns None for what it does not handle, which is left to
+/// Rhai
+#[cfg(feature = "scripting")]
+struct Compiler<'a> {
+    tokens: Vec<&'a str>,
+    next: usize,
+    instructions: Vec<Instruction>,
+    /// Number of values on the stack after the instructions so far, and the
+    /// largest one
+    depth: usize,
+    max_depth: usize,
+}
+
+#[cfg(feature = "scripting")]
+impl<'a> Compiler<'a> {
+    /// Program of `text`, if it is an arithmetic expression
+    fn compile(text: &'a str) -> Option<Program> {
+        let mut compiler =
+            Compiler { tokens: tokenize(text)?, next: 0, instructions: Vec::new(), depth: 0, max_depth: 0 };
+        compiler.sum()?;
+        (compiler.next == compiler.tokens.len() && compiler.max_depth <= STACK_SIZE)
+            .then_some(Program(compiler.instructions))
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.peek() == Some(token);
+        self.next += found as usize;
+        found
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.depth = match instruction {
+            Instruction::Number(_) | Instruction::Variable(_) => self.depth + 1,
+            Instruction::Negate | Instruction::Function(_) => self.depth,
+            _ => self.depth - 1,
+        };
+        self.max_depth = self.max_depth.max(self.depth);
+        self.instructions.push(instruction);
+    }
+
+    /// Apply a binary operator, which Rhai computes on integers, differently,
+    /// if both operands are
+    fn binary(&mut self, instruction: Instruction, left: Kind, right: Kind) -> Option<Kind> {
+        if left == Kind::Integer && right == Kind::Integer {
+            if !matches!(instruction, Instruction::Add | Instruction::Subtract | Instruction::Multiply) {
+                return None;
+            }
+            self.emit(instruction);
+            return Some(Kind::Integer);
+        }
+        self.emit(instruction);
+        Some(Kind::Float)
+    }
+
+    fn sum(&mut self) -> Option<Kind> {
+        let mut kind = self.product()?;
+        loop {
+            let instruction = match self.peek() {
+                Some("+") => Instruction::Add,
+                Some("-") => Instruction::Subtract,
+                _ => return Some(kind),
+            };
+            self.next += 1;
+            let right = self.product()?;
+            kind = self.binary(instruction, kind, right)?;
+        }
+    }
+
+    fn product(&mut self) -> Option<Kind> {
+        let mut kind = self.power()?;
+        loop {
+            let instruction = match self.peek() {
+                Some("*") => Instruction::Multiply,
+                Some("/") => Instruction::Divide,
+                Some("%") => Instruction::Remainder,
+                _ => return Some(kind),
+            };
+            self.next += 1;
+            let right = self.power()?;
+            kind = self.binary(instruction, kind, right)?;
+        }
+    }
+
+    fn power(&mut self) -> Option<Kind> {
+        let base = self.unary()?;
+        if !self.eat("**") {
+            return Some(base);
+        }
+        let exponent = self.power()?;
+        self.binary(Instruction::Power, base, exponent)
+    }
+
+    fn unary(&mut self) -> Option<Kind> {
+        if self.eat("-") {
+            let kind = self.unary()?;
+            self.emit(Instruction::Negate);
+            return Some(kind);
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Option<Kind> {
+        let token = self.peek()?;
+        self.next += 1;
+        if token == "(" {
+            let kind = self.sum()?;
+            return self.eat(")").then_some(kind);
+        }
+        if token.starts_with(|character: char| character.is_ascii_digit()) {
+            let number: f64 = token.replace('_', "").parse().ok()?;
+            self.emit(Instruction::Number(number));
+            let float = token.contains(['.', 'e', 'E']);
+            return Some(if float { Kind::Float } else { Kind::Integer });
+        }
+        if !self.eat("(") {
+            let index = VARIABLES.iter().position(|name| *name == token)?;
+            self.emit(Instruction::Variable(index));
+            return Some(Kind::Float);
+        }
+
+        // Rhai has these functions for floats only
+        let function: Option<fn(f64) -> f64> = match token {
+            "exp" => Some(f64::exp),
+            "ln" => Some(f64::ln),
+            "sqrt" => Some(f64::sqrt),
+            "abs" => Some(f64::abs),
+            "sin" => Some(f64::sin),
+            "cos" => Some(f64::cos),
+            "tan" => Some(f64::tan),
+            "tanh" => Some(f64::tanh),
+            _ => None,
+        };
+        let instruction = match function {
+            Some(function) => {
+                (self.sum()? == Kind::Float).then_some(())?;
+                Instruction::Function(function)
+            }
+            None => {
+                let function: fn(f64, f64) -> f64 = match token {
+                    "min" => f64::min,
+                    "max" => f64::max,
+                    _ => return None,
+                };
+                (self.sum()? == Kind::Float && self.eat(",") && self.sum()? == Kind::Float).then_some(())?;
+                Instruction::Function2(function)
+            }
+        };
+        self.emit(instruction);
+        self.eat(")").then_some(Kind::Float)
+    }
+}
+
+/// Numbers, names and operators of `text`, if it has nothing else
+#[cfg(feature = "scripting")]
+fn tokenize(text: &str) -> Option<Vec<&str>> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let length = if first.is_ascii_digit() {
+            // Digits, a fraction and an exponent, e.g. `1_000.5e-3`
+            let bytes = rest.as_bytes();
+            let mut length = 0;
+            while length < bytes.len() {
+                let byte = bytes[length];
+                let sign = matches!(byte, b'+' | b'-') && matches!(bytes[length - 1], b'e' | b'E');
+                if !(byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_') || sign) {
+                    break;
+                }
+                length += 1;
+            }
+            length
+        } else if first.is_ascii_alphabetic() || first == '_' {
+            rest.find(|character: char| !(character.is_ascii_alphanumeric() || character == '_')).unwrap_or(rest.len())
+        } else if rest.starts_with("**") {
+            2
+        } else if "+-*/%(),".contains(first) {
+            1
+        } else {
+            return None;
+        };
+        tokens.push(&rest[..length]);
+        rest = rest[length..].trim_start();
+    }
+    Some(tokens)
+}
+
+#[cfg(feature = "scripting")]
+impl Program {
+    /// Value of the expression for the values of `VARIABLES`
+    fn evaluate(&self, variables: &[f64; 7]) -> f64 {
+        let mut stack = [0.0; STACK_SIZE];
+        let mut top = 0;
+        for instruction in &self.0 {
+            let (value, operands) = match *instruction {
+                Instruction::Number(value) => (value, 0),
+                Instruction::Variable(index) => (variables[index], 0),
+                Instruction::Negate => (-stack[top - 1], 1),
+                Instruction::Function(function) => (function(stack[top - 1]), 1),
+                instruction => {
+                    let (left, right) = (stack[top - 2], stack[top - 1]);
+                    let value = match instruction {
+                        Instruction::Add => left + right,
+                        Instruction::Subtract => left - right,
+                        Instruction::Multiply => left * right,
+                        Instruction::Divide => left / right,
+                        Instruction::Remainder => left % right,
+                        Instruction::Power => left.powf(right),
+                        Instruction::Function2(function) => function(left, right),
+                        _ => unreachable!("unary instructions are handled above"),
+                    };
+                    (value, 2)
+                }
+            };
+            top -= operands;
+            stack[top] = value;
+            top += 1;
+        }
+        stack[0]
+    }
+}
+
+/// Expression of a `ScriptedReaction`, compiled to a `Program` if possible
+#[cfg(feature = "scripting")]
+#[derive(Debug, Clone)]
+enum Expression {
+    Program(Program),
+    Script(rhai::AST),
+}
+
+/// Reaction computed by the expressions of a `ReactionConfig`
+/// An expression failing on some cell, e.g. calling an unknown function,
+/// gives a NaN change, which `Bounds::Strict` stops at
+#[cfg(feature = "scripting")]
+pub struct ScriptedReaction {
+    config: ReactionConfig,
+    engine: rhai::Engine,
+    /// Expressions of A and of B
+    expressions: [Expression; 2],
+}
+
+#[cfg(feature = "scripting")]
+impl fmt::Debug for ScriptedReaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptedReaction").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "scripting")]
+impl ScriptedReaction {
+    /// Fails if an expression does not parse, or does not give a number for
+    /// a cell of the initial universe with the default parameters
+    pub fn new(config: ReactionConfig) -> Result<ScriptedReaction, SimulationError> {
+        let mut engine = rhai::Engine::new();
+        engine.set_optimization_level(rhai::OptimizationLevel::Full);
+        let mut scope = rhai::Scope::new();
+        for name in VARIABLES {
+            scope.push(name, 0.0);
+        }
+        let compile = |species: &str, text: &str| {
+            engine
+                .compile_expression_with_scope(&scope, text)
+                .map(Expression::Script)
+                .map_err(|error| SimulationError::InvalidReaction(format!("the expression of {species}: {error}")))
+        };
+        let expressions = [compile("A", &config.a)?, compile("B", &config.b)?];
+        let mut reaction = ScriptedReaction { config, engine, expressions };
+
+        let samples = [[1.0, 0.0], [0.5, 0.25], [0.2, 0.9], [0.0, 1.0]];
+        let texts = [reaction.config.a.clone(), reaction.config.b.clone()];
+        for (index, (species, text)) in ["A", "B"].into_iter().zip(&texts).enumerate() {
+            let script = &reaction.expressions[index];
+            let values = samples
+                .iter()
+                .map(|[a, b]| reaction.evaluate(script, *a, *b, &Parameters::default()))
+                .collect::<Result<Vec<f64>, String>>()
+                .map_err(|error| SimulationError::InvalidReaction(format!("the expression of {species}: {error}")))?;
+            // The program is used only if it gives the same values as Rhai,
+            // NaN included, e.g. for a division by zero
+            let Some(program) = Compiler::compile(text) else {
+                continue;
+            };
+            let program = Expression::Program(program);
+            let same = samples.iter().zip(&values).all(|([a, b], value)| {
+                let compiled = reaction.evaluate(&program, *a, *b, &Parameters::default());
+                compiled.is_ok_and(|compiled| {
+                    let both_nan = compiled.is_nan() && value.is_nan();
+                    both_nan || compiled == *value || (compiled - value).abs() <= 1e-12 * value.abs()
+                })
+            });
+            if same {
+                reaction.expressions[index] = program;
+            }
+        }
+        Ok(reaction)
+    }
+
+    pub fn config(&self) -> &ReactionConfig {
+        &self.config
+    }
+
+    /// Whether the expressions of A and of B are compiled, or evaluated by
+    /// Rhai
+    pub fn is_compiled(&self) -> [bool; 2] {
+        self.expressions.each_ref().map(|expression| matches!(expression, Expression::Program(_)))
+    }
+
+    fn evaluate(&self, expression: &Expression, a: f64, b: f64, parameters: &Parameters) -> Result<f64, String> {
+        let Parameters { d_a, d_b, f, k, r, .. } = *parameters;
+        let values = [a, b, d_a as f64, d_b as f64, f as f64, k as f64, r as f64];
+        let expression = match expression {
+            Expression::Program(program) => return Ok(program.evaluate(&values)),
+            Expression::Script(expression) => expression,
+        };
+        SCOPE.with(|scope| {
+            let mut scope = scope.borrow_mut();
+            scope.rewind(0);
+            for (name, value) in VARIABLES.into_iter().zip(values) {
+                scope.push(name, value);
+            }
+            let value =
+                self.engine.eval_ast_with_scope::<rhai::Dynamic>(&mut scope, expression).map_err(|error| error.to_string())?;
+            match value.as_float() {
+                Ok(value) => Ok(value),
+                Err(_) => value.as_int().map(|value| value as f64).map_err(|found| format!("expected a number, found {found}")),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "scripting")]
+impl Reaction for ScriptedReaction {
+    fn react(&self, a: f64, b: f64, parameters: &Parameters) -> [f64; 2] {
+        self.expressions.each_ref().map(|expression| self.evaluate(expression, a, b, parameters).unwrap_or(f64::NAN))
+    }
+
+    fn script(&self) -> Option<&ReactionConfig> {
+        Some(&self.config)
+    }
+}
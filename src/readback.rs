@@ -0,0 +1,196 @@
+/// Reading the fields back from a backend
+/// The analyses of the library (statistics, spectra, classification, hashes,
+/// exports) all read a `Universe` on the CPU. A backend whose concentrations
+/// live elsewhere, e.g. in a storage texture on the GPU, implements
+/// `FieldSource` and answers a readback request with a copy of its universe
+/// once available, without blocking its own evolution: the request returns a
+/// `Readback` at once, completed later from the `ReadbackSender` it came
+/// with. The CPU backends complete it right away, so code written against
+/// `FieldSource` keeps working whichever backend computes the universe
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+use crate::adaptive::AdaptiveSimulation;
+use crate::analysis::{dominant_wavelength, Spectrum, Wavelength};
+use crate::classify::{classify, Classification};
+use crate::colormap::Colormap;
+use crate::hash::content_hash;
+use crate::lenia::LeniaSimulation;
+use crate::render::{DisplayMode, Frame, RenderConfig};
+use crate::stats::{stats, Stats};
+use crate::{color_universe, ColoredMap, Float, Position, Simulation, Universe};
+
+/// Copy of the concentrations of a backend at one generation
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSnapshot<T: Float = f32> {
+    pub generation: i32,
+    pub universe: Universe<T>,
+}
+
+impl<T: Float> FieldSnapshot<T> {
+    pub fn dimensions(&self) -> Position {
+        Position { row: self.universe.len(), col: self.universe.first().map_or(0, Vec::len) }
+    }
+
+    /// Statistics of the universe, see `stats`
+    pub fn stats(&self) -> Stats {
+        stats(&self.universe)
+    }
+
+    /// Power spectrum of B, see `analysis`
+    pub fn spectrum(&self) -> Spectrum {
+        Spectrum::of(&self.universe)
+    }
+
+    /// Dominant wavelength of the pattern, see `analysis`
+    pub fn dominant_wavelength(&self) -> Option<Wavelength> {
+        dominant_wavelength(&self.universe)
+    }
+
+    /// Kind of pattern, see `classify`
+    pub fn classify(&self) -> Classification {
+        classify(&self.universe)
+    }
+
+    /// Hash of the universe, see `hash`
+    pub fn content_hash(&self) -> u64 {
+        content_hash(&self.universe)
+    }
+
+    pub fn colored_map(&self) -> ColoredMap {
+        color_universe(&self.universe)
+    }
+
+    /// Pixels of the universe as the window draws them, see `render::Frame`
+    pub fn frame(&self, config: RenderConfig, colormap: Colormap) -> Frame {
+        match config.mode {
+            DisplayMode::Colormap => Frame::colored_map(&self.colored_map(), self.generation, config, colormap),
+            DisplayMode::Composite => Frame::composite(&self.universe, None, self.generation, config),
+        }
+    }
+}
+
+/// Pending copy of the concentrations of a backend, see
+/// `FieldSource::request_readback`
+#[derive(Debug)]
+pub struct Readback<T: Float = f32> {
+    receiver: Receiver<FieldSnapshot<T>>,
+}
+
+/// Side of a readback completed by the backend
+#[derive(Debug)]
+pub struct ReadbackSender<T: Float = f32> {
+    sender: Sender<FieldSnapshot<T>>,
+}
+
+impl<T: Float> Readback<T> {
+    /// Readback to complete from the returned sender
+    pub fn pending() -> (Readback<T>, ReadbackSender<T>) {
+        let (sender, receiver) = mpsc::channel();
+        (Readback { receiver }, ReadbackSender { sender })
+    }
+
+    /// Readback already completed with `snapshot`
+    pub fn ready(snapshot: FieldSnapshot<T>) -> Readback<T> {
+        let (readback, sender) = Readback::pending();
+        sender.complete(snapshot);
+        readback
+    }
+
+    /// The copy if it arrived, without waiting
+    /// Returns `Err` with the readback, to try again later, while it is
+    /// pending, and `Ok(None)` if the backend dropped the request
+    pub fn try_take(self) -> Result<Option<FieldSnapshot<T>>, Readback<T>> {
+        match self.receiver.try_recv() {
+            Ok(snapshot) => Ok(Some(snapshot)),
+            Err(TryRecvError::Empty) => Err(self),
+            Err(TryRecvError::Disconnected) => Ok(None),
+        }
+    }
+
+    /// Wait for the copy, `None` if the backend dropped the request
+    pub fn wait(self) -> Option<FieldSnapshot<T>> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<T: Float> ReadbackSender<T> {
+    /// Complete the readback with `snapshot`
+    /// Nothing happens if the readback was dropped in the meantime
+    pub fn complete(self, snapshot: FieldSnapshot<T>) {
+        let _ = self.sender.send(snapshot);
+    }
+}
+
+/// Backend whose concentrations can be read back on the CPU
+pub trait FieldSource {
+    /// Precision of the concentrations read back
+    type Precision: Float;
+
+    fn dimensions(&self) -> Position;
+
+    /// Number of evolutions computed so far
+    fn generation(&self) -> i32;
+
+    /// Ask for a copy of the current concentrations, completed once they
+    /// are copied out of the backend
+    fn request_readback(&mut self) -> Readback<Self::Precision>;
+
+    /// Copy of the current concentrations, waiting for it
+    /// `None` if the backend dropped the request
+    fn read_back(&mut self) -> Option<FieldSnapshot<Self::Precision>> {
+        self.request_readback().wait()
+    }
+}
+
+impl<T: Float> FieldSource for Simulation<T> {
+    type Precision = T;
+
+    fn dimensions(&self) -> Position {
+        Simulation::dimensions(self)
+    }
+
+    fn generation(&self) -> i32 {
+        Simulation::generation(self)
+    }
+
+    /// Dropped while an evolution is taken out of the simulation, see
+    /// `Simulation::is_busy`, since its universe is empty meanwhile
+    fn request_readback(&mut self) -> Readback<T> {
+        if self.is_busy() {
+            return Readback::pending().0;
+        }
+        Readback::ready(FieldSnapshot { generation: Simulation::generation(self), universe: self.universe().clone() })
+    }
+}
+
+impl FieldSource for LeniaSimulation {
+    type Precision = f32;
+
+    fn dimensions(&self) -> Position {
+        LeniaSimulation::dimensions(self)
+    }
+
+    fn generation(&self) -> i32 {
+        LeniaSimulation::generation(self)
+    }
+
+    fn request_readback(&mut self) -> Readback {
+        Readback::ready(FieldSnapshot { generation: LeniaSimulation::generation(self), universe: self.universe() })
+    }
+}
+
+impl FieldSource for AdaptiveSimulation {
+    type Precision = f32;
+
+    fn dimensions(&self) -> Position {
+        AdaptiveSimulation::dimensions(self)
+    }
+
+    fn generation(&self) -> i32 {
+        AdaptiveSimulation::generation(self)
+    }
+
+    fn request_readback(&mut self) -> Readback {
+        Readback::ready(FieldSnapshot { generation: AdaptiveSimulation::generation(self), universe: self.universe() })
+    }
+}
@@ -0,0 +1,103 @@
+/// Reference implementation of the evolution
+/// A slow and straightforward transcription of the Gray–Scott update of
+/// `core`: every cell is computed from the whole universe in `f64`, without
+/// tiles, halos, activity tracking nor conversions of precision. The
+/// neighbours are visited in the same order as in `core`, whose diffusion
+/// takes from the cell in proportion to what it already holds, so both
+/// compute the same automaton and any difference between them comes from the
+/// optimizations. `Validation` steps a simulation next to it from the same
+/// universe, e.g. to trust another backend. Modulation, scripted reactions
+/// and timelines are not followed
+use crate::core::evolve_cell;
+use crate::{Boundary, Float, Parameters, Position, Simulation, Universe};
+
+/// Index `offset` away from `index` along a side of `size` cells, if there
+/// is a cell there
+fn neighbour_index(index: usize, offset: isize, size: usize, boundary: Boundary) -> Option<usize> {
+    match boundary {
+        Boundary::Closed => index.checked_add_signed(offset).filter(|index| *index < size),
+        Boundary::Periodic => Some((index as isize + offset).rem_euclid(size as isize) as usize),
+    }
+}
+
+/// Universe after one evolution of `universe` with `parameters`
+pub fn reference_step(parameters: &Parameters, boundary: Boundary, universe: &Universe<f64>) -> Universe<f64> {
+    let (rows, cols) = (universe.len(), universe.first().map_or(0, Vec::len));
+    let mut evolved = universe.clone();
+    for row in 0..rows {
+        for col in 0..cols {
+            evolved[row][col] = evolve_cell(parameters, universe[row][col], |d_row, d_col| {
+                let neighbour_row = neighbour_index(row, d_row, rows, boundary)?;
+                let neighbour_col = neighbour_index(col, d_col, cols, boundary)?;
+                Some(universe[neighbour_row][neighbour_col])
+            });
+        }
+    }
+    evolved
+}
+
+/// Largest difference of a concentration between a universe and the
+/// reference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Divergence {
+    /// Generation of both universes
+    pub generation: i32,
+    /// Cell with the largest difference
+    pub position: Position,
+    /// Largest difference of A or B, infinite if a concentration is NaN in
+    /// only one of them
+    pub difference: f64,
+}
+
+/// Largest difference of a concentration between `universe` and `reference`
+/// at `generation`, which must have the same dimensions
+pub fn divergence<T: Float>(generation: i32, universe: &Universe<T>, reference: &Universe<f64>) -> Divergence {
+    let mut largest = Divergence { generation, position: Position { row: 0, col: 0 }, difference: 0.0 };
+    for (row, (cells, references)) in universe.iter().zip(reference).enumerate() {
+        for (col, (cell, reference)) in cells.iter().zip(references).enumerate() {
+            let difference = [(cell.a.to_f64(), reference.a), (cell.b.to_f64(), reference.b)]
+                .into_iter()
+                .map(|(value, reference)| match (value.is_nan(), reference.is_nan()) {
+                    (true, true) => 0.0,
+                    (false, false) => (value - reference).abs(),
+                    _ => f64::INFINITY,
+                })
+                .fold(0.0, f64::max);
+            if difference > largest.difference {
+                largest = Divergence { generation, position: Position { row, col }, difference };
+            }
+        }
+    }
+    largest
+}
+
+/// Simulation stepped next to the reference implementation
+pub struct Validation<T: Float = f32> {
+    simulation: Simulation<T>,
+    reference: Universe<f64>,
+}
+
+impl<T: Float> Validation<T> {
+    /// Validation of `simulation` from its current universe
+    pub fn new(simulation: Simulation<T>) -> Validation<T> {
+        let reference = simulation.universe().iter().map(|row| row.iter().map(|cell| cell.cast()).collect()).collect();
+        Validation { simulation, reference }
+    }
+
+    /// Compute one evolution of both, with the current parameters of the
+    /// simulation, and return how far apart they are
+    pub fn step(&mut self) -> Divergence {
+        self.reference = reference_step(&self.simulation.parameters(), self.simulation.boundary(), &self.reference);
+        self.simulation.step();
+        divergence(self.simulation.generation(), self.simulation.universe(), &self.reference)
+    }
+
+    pub fn simulation(&self) -> &Simulation<T> {
+        &self.simulation
+    }
+
+    /// Universe of the reference implementation
+    pub fn reference(&self) -> &Universe<f64> {
+        &self.reference
+    }
+}
@@ -0,0 +1,85 @@
+/// Regions of a universe
+/// Rectangular patches can be copied out of a universe, e.g. a converged
+/// pattern, and stamped into another one, possibly larger, to tile or seed it
+use serde::{Deserialize, Serialize};
+
+use crate::error::check_dimensions;
+use crate::{Float, Position, Simulation, SimulationError, Universe};
+
+/// Rectangle of cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    /// Row and column of the top left cell
+    pub origin: Position,
+    /// Number of rows and columns
+    pub dimensions: Position,
+}
+
+impl Rect {
+    /// Whether the rectangle lies inside a universe of `dimensions`
+    pub fn fits(&self, dimensions: Position) -> bool {
+        self.origin.row + self.dimensions.row <= dimensions.row
+            && self.origin.col + self.dimensions.col <= dimensions.col
+    }
+
+    /// Check that the rectangle lies inside a universe of `dimensions`
+    fn check(&self, dimensions: Position) -> Result<(), SimulationError> {
+        if !self.fits(dimensions) {
+            return Err(SimulationError::RegionOutOfBounds { region: *self, dimensions });
+        }
+        Ok(())
+    }
+}
+
+/// Copy of the cells of `universe` inside `rect`
+/// Fails if `rect` does not lie inside the universe
+pub fn crop<T: Float>(universe: &Universe<T>, rect: Rect) -> Result<Universe<T>, SimulationError> {
+    rect.check(universe_dimensions(universe))?;
+    Ok(universe[rect.origin.row..rect.origin.row + rect.dimensions.row]
+        .iter()
+        .map(|row| row[rect.origin.col..rect.origin.col + rect.dimensions.col].to_vec())
+        .collect())
+}
+
+/// Replace the cells of `universe` inside `rect` with those of `patch`
+/// Fails if `rect` does not lie inside the universe or `patch` does not have
+/// the dimensions of `rect`
+pub fn blit<T: Float>(
+    universe: &mut Universe<T>,
+    rect: Rect,
+    patch: &Universe<T>,
+) -> Result<(), SimulationError> {
+    rect.check(universe_dimensions(universe))?;
+    check_dimensions(patch, rect.dimensions)?;
+    for (row, patch_row) in universe[rect.origin.row..].iter_mut().zip(patch) {
+        row[rect.origin.col..rect.origin.col + rect.dimensions.col].copy_from_slice(patch_row);
+    }
+    Ok(())
+}
+
+fn universe_dimensions<T>(universe: &Universe<T>) -> Position {
+    Position {
+        row: universe.len(),
+        col: universe.first().map_or(0, |row| row.len()),
+    }
+}
+
+impl<T: Float> Simulation<T> {
+    /// Copy of the cells inside `rect`, see `crop`
+    pub fn crop(&self, rect: Rect) -> Result<Universe<T>, SimulationError> {
+        crop(self.universe(), rect)
+    }
+
+    /// Replace the cells inside `rect` with those of `patch`, see `blit`
+    /// Also fails while `is_busy`, the universe being away
+    pub fn blit(&mut self, rect: Rect, patch: &Universe<T>) -> Result<(), SimulationError> {
+        rect.check(self.dimensions())?;
+        check_dimensions(patch, rect.dimensions)?;
+        for (r, patch_row) in patch.iter().enumerate() {
+            for (c, cell) in patch_row.iter().enumerate() {
+                self.set_cell(Position { row: rect.origin.row + r, col: rect.origin.col + c }, *cell)?;
+            }
+        }
+        Ok(())
+    }
+}
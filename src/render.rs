@@ -0,0 +1,425 @@
+/// Rendering of color maps at a lower resolution
+/// Universes larger than the window are drawn from a color map reduced by an
+/// integer factor, so that far fewer pixels are pushed every frame while the
+/// simulation keeps its full resolution. Each pixel either takes the color
+/// of one cell of its block or the average of the whole block. Only the
+/// blocks of the cells that changed are colored again and uploaded, see
+/// `dirty_regions`, each pixel taking four bytes of color or, in the `R8`
+/// format, the one byte of its value, colored by the window from a palette.
+/// Instead of the color map, the composite mode draws the concentrations of
+/// the species themselves, each in a channel of the color, A in red and B in
+/// green by default, and in blue the B of the next layer of a layered model,
+/// see `layers`. The `Frame` of a universe holds the exact colors the
+/// window shows, whatever its pixel format, for other crates to process or
+/// composite the output without going through the textures of the window
+use std::borrow::Cow;
+use std::ops::Range;
+
+#[cfg(feature = "bevy")]
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+#[cfg(feature = "bevy")]
+use bevy::render::texture::Image;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::Colormap;
+use crate::{Cell, ColoredMap, Float, Position, Simulation, Universe};
+
+/// Largest number of pixels on each side of a color map drawn with an
+/// automatic factor
+pub const MAX_RENDERED_SIDE: usize = 1024;
+
+/// Reduction of a block of cells to one pixel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Downsampling {
+    /// The pixel takes the color of the first cell of the block
+    Nearest,
+    /// The pixel takes the mean color of the block
+    #[default]
+    Average,
+}
+
+/// Names of the reductions, as written in the configuration files
+pub const DOWNSAMPLING_NAMES: [&str; 2] = ["nearest", "average"];
+
+impl Downsampling {
+    /// Reduction with the given name, see `DOWNSAMPLING_NAMES`
+    pub fn from_name(name: &str) -> Option<Downsampling> {
+        match name {
+            "nearest" => Some(Downsampling::Nearest),
+            "average" => Some(Downsampling::Average),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes of a pixel of the textures of the window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PixelFormat {
+    /// Red, green, blue and alpha, colored with the color map before the
+    /// upload
+    #[default]
+    Rgba8,
+    /// Value of the color map alone, colored when drawn from a palette of
+    /// the color map, see `palette`: a quarter of the bytes to upload
+    R8,
+}
+
+/// Names of the pixel formats, as written in the configuration files
+pub const PIXEL_FORMAT_NAMES: [&str; 2] = ["rgba8", "r8"];
+
+impl PixelFormat {
+    /// Format with the given name, see `PIXEL_FORMAT_NAMES`
+    pub fn from_name(name: &str) -> Option<PixelFormat> {
+        match name {
+            "rgba8" => Some(PixelFormat::Rgba8),
+            "r8" => Some(PixelFormat::R8),
+            _ => None,
+        }
+    }
+
+    /// Number of bytes of a pixel
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Rgba8 => 4,
+            PixelFormat::R8 => 1,
+        }
+    }
+}
+
+/// What the pixels of the window show
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayMode {
+    /// Color map of the universe, colored with the color map of the output
+    #[default]
+    Colormap,
+    /// Concentrations of the species in the channels of the color, see
+    /// `CompositeChannels`; needs the `Rgba8` format
+    Composite,
+}
+
+/// Names of the display modes, as written in the configuration files
+pub const DISPLAY_MODE_NAMES: [&str; 2] = ["colormap", "composite"];
+
+impl DisplayMode {
+    /// Mode with the given name, see `DISPLAY_MODE_NAMES`
+    pub fn from_name(name: &str) -> Option<DisplayMode> {
+        match name {
+            "colormap" => Some(DisplayMode::Colormap),
+            "composite" => Some(DisplayMode::Composite),
+            _ => None,
+        }
+    }
+}
+
+/// Concentration shown in a channel of the composite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompositeSource {
+    /// The channel stays dark
+    None,
+    A,
+    B,
+    /// B of the next layer, when the simulations are coupled as layers
+    Layer,
+}
+
+impl CompositeSource {
+    /// Name of the concentration, as shown in the legend
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            CompositeSource::None => None,
+            CompositeSource::A => Some("A"),
+            CompositeSource::B => Some("B"),
+            CompositeSource::Layer => Some("B of the next layer"),
+        }
+    }
+}
+
+/// Concentrations shown in the red, green and blue channels of the composite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompositeChannels {
+    pub red: CompositeSource,
+    pub green: CompositeSource,
+    pub blue: CompositeSource,
+}
+
+impl Default for CompositeChannels {
+    fn default() -> Self {
+        CompositeChannels { red: CompositeSource::A, green: CompositeSource::B, blue: CompositeSource::Layer }
+    }
+}
+
+impl CompositeChannels {
+    pub fn sources(&self) -> [CompositeSource; 3] {
+        [self.red, self.green, self.blue]
+    }
+}
+
+/// Resolution of the color maps drawn in the window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderConfig {
+    /// Number of cells on each side of the block drawn as one pixel, 0 for
+    /// the smallest factor keeping the sides within `MAX_RENDERED_SIDE`
+    pub factor: usize,
+    pub downsampling: Downsampling,
+    pub format: PixelFormat,
+    pub mode: DisplayMode,
+    /// Channels of the species in the composite mode
+    pub channels: CompositeChannels,
+}
+
+impl RenderConfig {
+    /// Factor used to draw a universe of `dimensions`, at least 1
+    pub fn factor(&self, dimensions: Position) -> usize {
+        match self.factor {
+            0 => dimensions.row.max(dimensions.col).div_ceil(MAX_RENDERED_SIDE).max(1),
+            factor => factor,
+        }
+    }
+
+    /// Number of rows and columns of pixels of a universe of `dimensions`
+    pub fn rendered(&self, dimensions: Position) -> Position {
+        let factor = self.factor(dimensions);
+        Position { row: dimensions.row.div_ceil(factor), col: dimensions.col.div_ceil(factor) }
+    }
+}
+
+/// Color map reduced by `factor`, or `colored_map` itself for a factor of 1
+/// Blocks on the last rows and columns may be smaller than the others
+pub fn downsample(colored_map: &ColoredMap, factor: usize, downsampling: Downsampling) -> Cow<'_, ColoredMap> {
+    if factor <= 1 {
+        return Cow::Borrowed(colored_map);
+    }
+    let cols = colored_map.first().map_or(0, Vec::len);
+    let reduced = colored_map
+        .chunks(factor)
+        .map(|rows| {
+            (0..cols).step_by(factor).map(|col| block_value(rows, col, factor, downsampling, |value| *value)).collect()
+        })
+        .collect();
+    Cow::Owned(reduced)
+}
+
+/// Value of the pixel of the block starting at column `col` of `rows`, the
+/// `value` of its cells
+fn block_value<V>(
+    rows: &[Vec<V>],
+    col: usize,
+    factor: usize,
+    downsampling: Downsampling,
+    value: impl Fn(&V) -> f32,
+) -> f32 {
+    match downsampling {
+        Downsampling::Nearest => value(&rows[0][col]),
+        Downsampling::Average => {
+            let block = rows.iter().flat_map(|row| &row[col..(col + factor).min(row.len())]);
+            let (sum, count) = block.fold((0.0, 0), |(sum, count), cell| (sum + value(cell), count + 1));
+            sum / count as f32
+        }
+    }
+}
+
+/// Rows and columns of pixels, reduced by `factor`, covering the cells of
+/// `dirty`, e.g. the tiles of `Simulation::dirty_cells`, those next to each
+/// other on the same rows being merged to be uploaded at once
+pub fn dirty_regions(dirty: &[(Range<usize>, Range<usize>)], factor: usize) -> Vec<(Range<usize>, Range<usize>)> {
+    let factor = factor.max(1);
+    let pixels = |cells: &Range<usize>| cells.start / factor..cells.end.div_ceil(factor);
+    let mut regions: Vec<(Range<usize>, Range<usize>)> =
+        dirty.iter().map(|(rows, cols)| (pixels(rows), pixels(cols))).collect();
+    regions.sort_by_key(|(rows, cols)| (rows.start, rows.end, cols.start));
+    let mut merged: Vec<(Range<usize>, Range<usize>)> = Vec::with_capacity(regions.len());
+    for (rows, cols) in regions {
+        match merged.last_mut() {
+            Some((last_rows, last_cols)) if *last_rows == rows && last_cols.end >= cols.start => {
+                last_cols.end = last_cols.end.max(cols.end);
+            }
+            _ => merged.push((rows, cols)),
+        }
+    }
+    merged
+}
+
+/// Bytes of the pixels of `region` of `colored_map` reduced by `factor`,
+/// row by row, in `format`
+pub fn region_pixels(
+    colored_map: &ColoredMap,
+    region: &(Range<usize>, Range<usize>),
+    config: RenderConfig,
+    factor: usize,
+    colormap: Colormap,
+) -> Vec<u8> {
+    let (rows, cols) = region;
+    let factor = factor.max(1);
+    let mut pixels = Vec::with_capacity(rows.len() * cols.len() * config.format.bytes_per_pixel());
+    for row in rows.clone() {
+        let start = (row * factor).min(colored_map.len());
+        let block = &colored_map[start..(start + factor).min(colored_map.len())];
+        if block.is_empty() {
+            break;
+        }
+        for col in cols.clone() {
+            let value = block_value(block, col * factor, factor, config.downsampling, |value| *value);
+            match config.format {
+                PixelFormat::Rgba8 => {
+                    let [r, g, b] = colormap.color(value);
+                    pixels.extend([r, g, b, 255]);
+                }
+                PixelFormat::R8 => pixels.push(palette_index(value)),
+            }
+        }
+    }
+    pixels
+}
+
+/// Bytes of the pixels of `region` of the composite of `universe` reduced by
+/// `factor`, row by row, as red, green, blue and alpha bytes, with `layer`
+/// the universe of the next layer, if any
+pub fn composite_pixels<T: Float>(
+    universe: &Universe<T>,
+    layer: Option<&Universe<T>>,
+    region: &(Range<usize>, Range<usize>),
+    config: RenderConfig,
+    factor: usize,
+) -> Vec<u8> {
+    let (rows, cols) = region;
+    let factor = factor.max(1);
+    let mut pixels = Vec::with_capacity(rows.len() * cols.len() * 4);
+    for row in rows.clone() {
+        let start = (row * factor).min(universe.len());
+        let end = (start + factor).min(universe.len());
+        if start == end {
+            break;
+        }
+        let block = &universe[start..end];
+        let layer_block = layer.map(|layer| &layer[start.min(layer.len())..end.min(layer.len())]);
+        for col in cols.clone() {
+            let mean = |rows: &[Vec<Cell<T>>], value: fn(&Cell<T>) -> f32| {
+                block_value(rows, col * factor, factor, config.downsampling, value)
+            };
+            for source in config.channels.sources() {
+                let value = match source {
+                    CompositeSource::None => 0.0,
+                    CompositeSource::A => mean(block, |cell| cell.a.to_f32()),
+                    CompositeSource::B => mean(block, |cell| cell.b.to_f32()),
+                    CompositeSource::Layer => layer_block
+                        .filter(|block| !block.is_empty())
+                        .map_or(0.0, |block| mean(block, |cell| cell.b.to_f32())),
+                };
+                pixels.push(palette_index(value));
+            }
+            pixels.push(255);
+        }
+    }
+    pixels
+}
+
+/// Entry of the palette of a value of a color map in the `R8` format
+fn palette_index(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Colors of the 256 values of the `R8` format with `colormap`, as red,
+/// green, blue and alpha bytes
+pub fn palette(colormap: Colormap) -> Vec<u8> {
+    (0..=255u8)
+        .flat_map(|index| {
+            let [r, g, b] = colormap.color(index as f32 / 255.0);
+            [r, g, b, 255]
+        })
+        .collect()
+}
+
+/// Pixels of a universe as drawn in the window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Generation of the universe drawn
+    pub generation: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Red, green, blue and alpha bytes of the pixels, row by row from the
+    /// top left
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    /// Frame of `simulation` as drawn with `config` and `colormap`, without
+    /// a next layer in the composite mode
+    pub fn of<T: Float>(simulation: &Simulation<T>, config: RenderConfig, colormap: Colormap) -> Frame {
+        match config.mode {
+            DisplayMode::Colormap => {
+                Frame::colored_map(simulation.colored_map(), simulation.generation(), config, colormap)
+            }
+            DisplayMode::Composite => Frame::composite(simulation.universe(), None, simulation.generation(), config),
+        }
+    }
+
+    /// Frame of `colored_map` reduced as `config` says and colored with
+    /// `colormap`; in the `R8` format, the values are colored from the
+    /// entries of the palette the window colors them with, see `palette`
+    pub fn colored_map(colored_map: &ColoredMap, generation: i32, config: RenderConfig, colormap: Colormap) -> Frame {
+        let dimensions = Position { row: colored_map.len(), col: colored_map.first().map_or(0, Vec::len) };
+        let rendered = config.rendered(dimensions);
+        let region = (0..rendered.row, 0..rendered.col);
+        let pixels = region_pixels(colored_map, &region, config, config.factor(dimensions), colormap);
+        let pixels = match config.format {
+            PixelFormat::Rgba8 => pixels,
+            PixelFormat::R8 => {
+                let palette: Vec<[u8; 4]> =
+                    palette(colormap).chunks_exact(4).map(|entry| [entry[0], entry[1], entry[2], entry[3]]).collect();
+                pixels.iter().flat_map(|&index| palette[index as usize]).collect()
+            }
+        };
+        Frame { generation, width: rendered.col as u32, height: rendered.row as u32, pixels }
+    }
+
+    /// Frame of the composite of `universe` reduced as `config` says, with
+    /// `layer` the universe of the next layer, if any
+    pub fn composite<T: Float>(
+        universe: &Universe<T>,
+        layer: Option<&Universe<T>>,
+        generation: i32,
+        config: RenderConfig,
+    ) -> Frame {
+        let dimensions = Position { row: universe.len(), col: universe.first().map_or(0, Vec::len) };
+        let rendered = config.rendered(dimensions);
+        let region = (0..rendered.row, 0..rendered.col);
+        let pixels = composite_pixels(universe, layer, &region, config, config.factor(dimensions));
+        Frame { generation, width: rendered.col as u32, height: rendered.row as u32, pixels }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    /// Copy of the frame as an image
+    pub fn to_image(&self) -> RgbaImage {
+        self.clone().into_image()
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        RgbaImage::from_raw(self.width, self.height, self.pixels).expect("a frame has four bytes per pixel")
+    }
+
+    /// Copy of the frame as a texture of Bevy, in the format of the textures
+    /// of the window
+    #[cfg(feature = "bevy")]
+    pub fn to_bevy_image(&self) -> Image {
+        Image::new(
+            Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            self.pixels.clone(),
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
@@ -0,0 +1,245 @@
+/// Record and replay of simulation runs
+/// A replay file stores how a run started (dimensions, initial state and
+/// seed), its initial parameters and every change made while it was
+/// running, tagged with the generation at which it happened. Playing the
+/// file back computes exactly the same universes as the recorded run
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::activity::ActivityTracking;
+use crate::config::InitialConfig;
+use crate::deterministic::portable_rng;
+use crate::modulation::{Modulation, ModulationConfig};
+use crate::noise::{Noise, NoiseConfig};
+use crate::reaction::ReactionConfig;
+use crate::streams::{RngStream, StreamSeeds};
+use crate::symmetry::Symmetry;
+use crate::schedule::Schedule;
+use crate::timeline::Timeline;
+use crate::{
+    Boundary, Bounds, Cell, Parameters, Position, Resampling, Simulation, SimulationError, Stencil, Universe,
+};
+
+/// Change made to a running simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReplayEvent {
+    /// All the parameters are replaced
+    SetParameters(Parameters),
+    /// Some cells are given new concentrations
+    SetCells(Vec<(Position, Cell)>),
+    /// The universe is resampled to new dimensions
+    Resize(Position, Resampling),
+}
+
+/// Event applied after computing `generation` evolutions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedEvent {
+    pub generation: i32,
+    pub event: ReplayEvent,
+}
+
+/// Recorded run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub dimensions: Position,
+    pub initial: InitialConfig,
+    /// Seed of the run, from which the random streams not given in
+    /// `streams` are derived
+    pub seed: u64,
+    /// Parameters at the start of the run
+    pub parameters: Parameters,
+    /// Number of evolutions of the run
+    pub steps: i32,
+    /// Handling of concentrations leaving [0,1] during the run
+    #[serde(default)]
+    pub bounds: Bounds,
+    /// Edges of the universe during the run
+    #[serde(default)]
+    pub boundary: Boundary,
+    /// Computation of the diffusion during the run
+    #[serde(default)]
+    pub stencil: Stencil,
+    /// Keyframes of the parameters changing during the run
+    #[serde(default)]
+    pub timeline: Timeline,
+    /// Pulses during the run
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// Field scaling `f` and `k` during the run
+    #[serde(default)]
+    pub modulation: Option<ModulationConfig>,
+    /// Reaction terms during the run
+    #[serde(default)]
+    pub reaction: Option<ReactionConfig>,
+    /// Tiles skipped once quiescent during the run, see `activity`
+    #[serde(default)]
+    pub activity: Option<ActivityTracking>,
+    /// Symmetry enforced during the run, see `symmetry`
+    #[serde(default)]
+    pub symmetry: Option<Symmetry>,
+    /// Noise added to B during the run, see `noise`
+    #[serde(default)]
+    pub noise: Option<NoiseConfig>,
+    /// Seeds of the random streams given to the run, see `streams`
+    #[serde(default)]
+    pub streams: StreamSeeds,
+    /// Whether the initial universe was drawn from the portable generator of
+    /// a deterministic run, see `deterministic`
+    #[serde(default)]
+    pub deterministic: bool,
+    /// Changes made during the run, in order
+    pub events: Vec<TimedEvent>,
+}
+
+/// Error while reading, writing or playing a replay
+#[derive(Debug)]
+pub enum ReplayError {
+    #[cfg(feature = "fs")]
+    Io(std::io::Error),
+    Ron(ron::Error),
+    /// The recorded run could not be simulated
+    Simulation(SimulationError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "fs")]
+            ReplayError::Io(error) => write!(f, "{error}"),
+            ReplayError::Ron(error) => write!(f, "invalid replay: {error}"),
+            ReplayError::Simulation(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<SimulationError> for ReplayError {
+    fn from(error: SimulationError) -> Self {
+        ReplayError::Simulation(error)
+    }
+}
+
+impl Replay {
+    /// Read a replay file written by `Recorder`
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, ReplayError> {
+        let text = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        ron::from_str(&text).map_err(|error| ReplayError::Ron(error.into()))
+    }
+
+    /// Write the replay as RON
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(ReplayError::Ron)?;
+        fs::write(path, text).map_err(ReplayError::Io)
+    }
+
+    /// Simulation at the start of the recorded run
+    pub fn simulation(&self) -> Result<Simulation, ReplayError> {
+        let seed = |stream| self.streams.seed(Some(self.seed), stream).unwrap_or(self.seed);
+        let (universe, dimensions) = if self.deterministic {
+            self.initial.universe_with_rng(&self.parameters, self.dimensions, &mut portable_rng(seed(RngStream::Initial)))?
+        } else {
+            self.initial.universe(&self.parameters, self.dimensions, Some(seed(RngStream::Initial)))?
+        };
+        let simulation = Simulation::new(self.parameters, dimensions, universe)?
+            .with_bounds(self.bounds)
+            .with_boundary(self.boundary)
+            .with_stencil(self.stencil)
+            .with_timeline(self.timeline.clone())?
+            .with_schedule(self.schedule.clone())?;
+        let simulation = match &self.modulation {
+            Some(modulation) => simulation.with_modulation(Modulation::new(modulation.clone())?)?,
+            None => simulation,
+        };
+        let simulation = match &self.reaction {
+            Some(reaction) => simulation.with_reaction(reaction.compile()?),
+            None => simulation,
+        };
+        let simulation = match self.activity {
+            Some(tracking) => simulation.with_activity_tracking(tracking),
+            None => simulation,
+        };
+        let simulation = match self.noise {
+            Some(noise) => simulation.with_noise(Noise::new(noise, seed(RngStream::Noise))?),
+            None => simulation,
+        };
+        Ok(match self.symmetry {
+            Some(symmetry) => simulation.with_symmetry(symmetry),
+            None => simulation,
+        })
+    }
+
+    /// Compute the recorded run again
+    /// `on_step` is called after every evolution with the simulation. Returns
+    /// the last universe, or an error if the run left the `Strict` bounds
+    pub fn play(&self, mut on_step: impl FnMut(&Simulation)) -> Result<Universe, ReplayError> {
+        let mut simulation = self.simulation()?;
+        let mut events = self.events.iter().peekable();
+
+        while simulation.generation() < self.steps && !simulation.is_stopped() {
+            while let Some(timed) = events.next_if(|timed| timed.generation <= simulation.generation()) {
+                apply_event(&timed.event, &mut simulation)?;
+            }
+            simulation.step();
+            on_step(&simulation);
+        }
+        if let Some(violation) = simulation.violation() {
+            return Err(SimulationError::OutOfBounds(*violation).into());
+        }
+        Ok(simulation.into_universe())
+    }
+}
+
+/// Apply `event` to a running simulation
+/// Fails if the event sets invalid parameters, or changes the universe
+/// while `Simulation::is_busy`
+pub fn apply_event(event: &ReplayEvent, simulation: &mut Simulation) -> Result<(), SimulationError> {
+    match event {
+        ReplayEvent::SetParameters(parameters) => simulation.set_parameters(*parameters)?,
+        ReplayEvent::SetCells(cells) => {
+            for (position, cell) in cells {
+                simulation.set_cell(*position, *cell)?;
+            }
+        }
+        ReplayEvent::Resize(dimensions, resampling) => simulation.resize(*dimensions, *resampling)?,
+    }
+    Ok(())
+}
+
+/// Recorder of a run
+/// Every recorded event is written to the replay file right away, so the
+/// replay survives the run being interrupted
+#[cfg(feature = "fs")]
+pub struct Recorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+#[cfg(feature = "fs")]
+impl Recorder {
+    /// Start recording a run into `path`
+    pub fn new(path: impl Into<PathBuf>, replay: Replay) -> Result<Recorder, ReplayError> {
+        let recorder = Recorder { path: path.into(), replay };
+        recorder.replay.save(&recorder.path)?;
+        Ok(recorder)
+    }
+
+    /// Record `event`, applied after computing `generation` evolutions
+    pub fn record(&mut self, generation: i32, event: ReplayEvent) -> Result<(), ReplayError> {
+        self.replay.events.push(TimedEvent { generation, event });
+        self.replay.save(&self.path)
+    }
+
+    /// Recorded run so far
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
@@ -0,0 +1,161 @@
+/// Rewinding a run
+/// A rewind buffer keeps keyframes of the recent generations of a
+/// simulation in memory: every generation, as a ring buffer of the last
+/// ones, or one every few generations, the generations between them being
+/// computed again from the keyframe before. Seeking takes the simulation
+/// back, or forward again, to any generation still covered, e.g. to find
+/// when and where a feature of the pattern appeared. Changes made between two
+/// keyframes, e.g. seeds placed by hand, are lost when computing the
+/// generations again unless the simulation is marked as changed, so that a
+/// keyframe is taken right after them
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::check_interval;
+use crate::snapshot::Snapshot;
+use crate::{Simulation, SimulationError};
+
+/// Number of keyframes kept and generations between two of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RewindConfig {
+    /// Number of keyframes kept, the oldest being dropped; 0 disables the
+    /// buffer
+    pub capacity: usize,
+    /// A keyframe is taken every `interval` generations, 1 for every one
+    pub interval: i32,
+}
+
+impl Default for RewindConfig {
+    fn default() -> Self {
+        RewindConfig { capacity: 50, interval: 10 }
+    }
+}
+
+impl RewindConfig {
+    /// Fails if the interval is below 1
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        check_interval("rewind keyframes", self.interval)
+    }
+}
+
+/// Keyframes of the recent generations of a simulation
+#[derive(Debug, Clone, Default)]
+pub struct RewindBuffer {
+    config: RewindConfig,
+    /// From the oldest generation to the newest
+    frames: VecDeque<Snapshot>,
+    /// Latest generation reached, which may be past the last keyframe
+    end: Option<i32>,
+    /// Whether the simulation changed since the last keyframe
+    marked: bool,
+}
+
+impl RewindBuffer {
+    /// Empty buffer, taking its first keyframe at the first `record`
+    /// Fails if the configuration is invalid
+    pub fn new(config: RewindConfig) -> Result<RewindBuffer, SimulationError> {
+        config.validate()?;
+        Ok(RewindBuffer { config, marked: true, ..RewindBuffer::default() })
+    }
+
+    pub fn config(&self) -> RewindConfig {
+        self.config
+    }
+
+    /// Take a keyframe of `simulation` if its generation is due or it was
+    /// marked as changed
+    /// Call it after every evolution. Once the simulation went back, the
+    /// keyframes past its generation are dropped as it evolves again
+    pub fn record(&mut self, simulation: &Simulation) {
+        let generation = simulation.generation();
+        if self.marked || generation % self.config.interval == 0 {
+            self.keep(simulation);
+        } else {
+            self.truncate(generation);
+            self.end = Some(generation);
+        }
+    }
+
+    /// Take the next keyframe whatever its generation, the simulation having
+    /// been changed, e.g. by hand, since the last one
+    pub fn mark(&mut self) {
+        self.marked = true;
+    }
+
+    /// Whether the simulation was marked as changed since the last keyframe
+    pub fn is_marked(&self) -> bool {
+        self.marked
+    }
+
+    /// Take a keyframe of `simulation` whatever its generation, replacing the
+    /// one of the same generation, e.g. right after a change made by hand
+    pub fn keep(&mut self, simulation: &Simulation) {
+        self.marked = false;
+        if self.config.capacity == 0 {
+            return;
+        }
+        let generation = simulation.generation();
+        self.truncate(generation);
+        if self.frames.back().map(|frame| frame.generation) == Some(generation) {
+            self.frames.pop_back();
+        }
+        if self.frames.len() == self.config.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(Snapshot::of(simulation));
+        self.end = Some(generation);
+    }
+
+    /// Drop the keyframes past `generation`, the simulation having gone back
+    fn truncate(&mut self, generation: i32) {
+        while self.frames.back().is_some_and(|frame| frame.generation > generation) {
+            self.frames.pop_back();
+        }
+    }
+
+    /// Drop all the keyframes, e.g. when the universe is resized
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.end = None;
+        self.marked = true;
+    }
+
+    /// Drop all the keyframes and take none from now on, e.g. once
+    /// simulations are compared
+    pub fn disable(&mut self) {
+        self.config.capacity = 0;
+        self.clear();
+    }
+
+    /// Keyframes, from the oldest to the newest
+    pub fn frames(&self) -> impl Iterator<Item = &Snapshot> {
+        self.frames.iter()
+    }
+
+    /// First and last generations that can be sought, if any
+    pub fn range(&self) -> Option<(i32, i32)> {
+        let start = self.frames.front()?.generation;
+        Some((start, self.end.unwrap_or(start).max(start)))
+    }
+
+    /// Take `simulation` to `generation`, clamped to `range`, from the
+    /// keyframe before it and computing the generations in between, and
+    /// return the generation reached
+    /// The observers of the simulation are called for the generations
+    /// computed again. Without any keyframe the simulation is left as is
+    pub fn seek(&self, simulation: &mut Simulation, generation: i32) -> Result<i32, SimulationError> {
+        let Some((start, end)) = self.range() else {
+            return Ok(simulation.generation());
+        };
+        let generation = generation.clamp(start, end);
+        let index = self.frames.partition_point(|frame| frame.generation <= generation) - 1;
+        let frame = &self.frames[index];
+        simulation.restore(frame.parameters, frame.generation, frame.universe.clone())?;
+        while simulation.generation() < generation && !simulation.is_stopped() {
+            simulation.step();
+        }
+        Ok(simulation.generation())
+    }
+}
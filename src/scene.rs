@@ -0,0 +1,139 @@
+/// 3D scene of a surface simulation with Bevy
+/// The mesh of a `SurfaceSimulation` is drawn lit in the middle of the
+/// window, turning slowly, with the color of every vertex following its
+/// concentrations. `Space` pauses and resumes the evolution, which computes
+/// a few generations every frame until the generation limit
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+use crate::colormap::Colormap;
+use crate::surface::SurfaceSimulation;
+
+/// Angular speed of the model around the vertical axis, in radians per second
+const ROTATION_SPEED: f32 = 0.3;
+
+/// Surface simulation drawn in the scene
+#[derive(Resource)]
+pub struct SurfaceState {
+    pub simulation: SurfaceSimulation,
+    pub colormap: Colormap,
+    /// Generations computed every frame
+    pub steps_per_frame: i32,
+    /// Generation at which the evolution stops
+    pub steps: i32,
+    pub running: bool,
+}
+
+/// Model of the surface in the scene
+#[derive(Component)]
+struct Model(Handle<Mesh>);
+
+/// Open a window and run the surface simulation in it
+/// Blocks until the window is closed
+pub fn run(state: SurfaceState) {
+    App::new()
+        .insert_resource(state)
+        .insert_resource(AmbientLight { color: Color::WHITE, brightness: 0.3 })
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            window: WindowDescriptor { title: "Turing patterns".to_string(), ..default() },
+            ..default()
+        }))
+        .add_startup_system(setup)
+        .add_system(toggle_running)
+        .add_system(evolve)
+        .add_system(rotate)
+        .run();
+}
+
+/// Colors of the vertices in the linear space of Bevy
+fn vertex_colors(state: &SurfaceState) -> Vec<[f32; 4]> {
+    state
+        .simulation
+        .vertex_colors(state.colormap)
+        .into_iter()
+        .map(|[r, g, b]| Color::rgb(r, g, b).as_linear_rgba_f32())
+        .collect()
+}
+
+/// Create the model centered on the origin, the light and the camera, far
+/// enough to see the whole model
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    state: Res<SurfaceState>,
+) {
+    let surface = state.simulation.mesh();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, surface.positions.clone());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, surface.normals.clone());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, surface.uvs.clone());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors(&state));
+    mesh.set_indices(Some(Indices::U32(surface.triangles.iter().flatten().copied().collect())));
+
+    let (min, max) = surface.positions.iter().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), position| (min.min(Vec3::from(*position)), max.max(Vec3::from(*position))),
+    );
+    let (center, radius) = if surface.positions.is_empty() {
+        (Vec3::ZERO, 1.0)
+    } else {
+        ((min + max) / 2.0, ((max - min).length() / 2.0).max(f32::EPSILON))
+    };
+
+    let handle = meshes.add(mesh);
+    commands
+        .spawn(SpatialBundle::default())
+        .insert(Model(handle.clone()))
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: handle,
+                material: materials.add(StandardMaterial {
+                    base_color: Color::WHITE,
+                    perceptual_roughness: 0.8,
+                    double_sided: true,
+                    cull_mode: None,
+                    ..default()
+                }),
+                transform: Transform::from_translation(-center),
+                ..default()
+            });
+        });
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(1.0, 2.0, 1.5).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, radius, 2.5 * radius).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn toggle_running(keys: Res<Input<KeyCode>>, mut state: ResMut<SurfaceState>) {
+    if keys.just_pressed(KeyCode::Space) {
+        state.running = !state.running;
+    }
+}
+
+/// Compute the generations of the frame and update the colors of the model
+fn evolve(mut state: ResMut<SurfaceState>, mut meshes: ResMut<Assets<Mesh>>, models: Query<&Model>) {
+    let remaining = state.steps - state.simulation.generation();
+    if !state.running || remaining <= 0 {
+        return;
+    }
+    let steps = state.steps_per_frame.clamp(1, remaining);
+    state.simulation.run(steps);
+
+    let colors = vertex_colors(&state);
+    for Model(handle) in &models {
+        if let Some(mesh) = meshes.get_mut(handle) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors.clone());
+        }
+    }
+}
+
+fn rotate(time: Res<Time>, mut models: Query<&mut Transform, With<Model>>) {
+    for mut transform in &mut models {
+        transform.rotate_y(ROTATION_SPEED * time.delta_seconds());
+    }
+}
@@ -0,0 +1,174 @@
+/// Scheduled pulses
+/// Periodic forcing of the evolution: a pulse sets some parameters to other
+/// values, or injects B into a region, for a few generations, repeated at a
+/// fixed period. For instance `f` raised to 0.09 for 50 generations every
+/// 1000 generations, and B injected at the center at generation 500, are
+/// written in TOML
+///
+/// ```toml
+/// [[schedule.pulses]]
+/// start = 1000
+/// period = 1000
+/// duration = 50
+/// f = 0.09
+///
+/// [[schedule.pulses]]
+/// start = 500
+/// inject = { region = { origin = { row = 90, col = 90 }, dimensions = { row = 20, col = 20 } }, amount = 0.25 }
+/// ```
+///
+/// The parameters of a pulse replace those of the simulation, timeline
+/// included, during the evolutions it covers, after which the simulation
+/// goes back to its own. Pulses overlapping each other are applied in order
+use serde::{Deserialize, Serialize};
+
+use crate::region::Rect;
+use crate::{Float, Parameters, SimulationError, Universe};
+
+/// B added to the cells of a region
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Injection {
+    /// Cells receiving B; those out of the universe, e.g. once it was
+    /// resized, are left out
+    pub region: Rect,
+    /// B added to every cell of the region at every evolution of the pulse,
+    /// B staying at most 1
+    pub amount: f32,
+}
+
+/// Change repeated at a fixed period
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Pulse {
+    /// Generation the first pulse starts at, the number of evolutions
+    /// computed before it
+    pub start: i32,
+    /// Generations between the starts of two pulses, 0 for a single pulse
+    pub period: i32,
+    /// Number of evolutions each pulse lasts
+    pub duration: i32,
+    /// Number of pulses, unlimited if not given
+    pub count: Option<u32>,
+    /// Values of the parameters during the pulse, the parameters without one
+    /// keeping theirs
+    pub d_a: Option<f32>,
+    pub d_b: Option<f32>,
+    pub f: Option<f32>,
+    pub k: Option<f32>,
+    pub r: Option<f32>,
+    pub inject: Option<Injection>,
+}
+
+impl Default for Pulse {
+    fn default() -> Self {
+        Pulse {
+            start: 0,
+            period: 0,
+            duration: 1,
+            count: None,
+            d_a: None,
+            d_b: None,
+            f: None,
+            k: None,
+            r: None,
+            inject: None,
+        }
+    }
+}
+
+impl Pulse {
+    /// Whether the evolution computed from `generation` is part of a pulse
+    pub fn is_active(&self, generation: i32) -> bool {
+        let Some(elapsed) = generation.checked_sub(self.start).filter(|elapsed| *elapsed >= 0) else {
+            return false;
+        };
+        if self.period == 0 {
+            return elapsed < self.duration;
+        }
+        let index = elapsed / self.period;
+        self.count.is_none_or(|count| (index as u32) < count) && elapsed % self.period < self.duration
+    }
+
+    /// `parameters` with the values of the pulse
+    pub(crate) fn apply(&self, parameters: Parameters) -> Parameters {
+        Parameters {
+            d_a: self.d_a.unwrap_or(parameters.d_a),
+            d_b: self.d_b.unwrap_or(parameters.d_b),
+            f: self.f.unwrap_or(parameters.f),
+            k: self.k.unwrap_or(parameters.k),
+            r: self.r.unwrap_or(parameters.r),
+            ..parameters
+        }
+    }
+
+    fn changes_parameters(&self) -> bool {
+        [self.d_a, self.d_b, self.f, self.k, self.r].iter().any(Option::is_some)
+    }
+}
+
+/// Pulses of a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Schedule {
+    pub pulses: Vec<Pulse>,
+}
+
+impl Schedule {
+    /// Whether there is no pulse
+    pub fn is_empty(&self) -> bool {
+        self.pulses.is_empty()
+    }
+
+    /// Fails if a pulse never happens, its injection is not a concentration
+    /// or its parameters are invalid with `base`
+    pub fn validate(&self, base: Parameters) -> Result<(), SimulationError> {
+        for (index, pulse) in self.pulses.iter().enumerate() {
+            let error = |message: String| Err(SimulationError::InvalidSchedule(format!("pulse {index}: {message}")));
+            if pulse.start < 0 || pulse.period < 0 {
+                return error(format!("the start and period cannot be negative, found {} and {}", pulse.start, pulse.period));
+            }
+            if pulse.duration < 1 || pulse.count == Some(0) {
+                return error("the pulse lasts no evolution".to_string());
+            }
+            if let Some(injection) = pulse.inject {
+                if !(0.0..=1.0).contains(&injection.amount) {
+                    return error(format!("the amount injected must be in [0, 1], found {}", injection.amount));
+                }
+            } else if !pulse.changes_parameters() {
+                return error("the pulse neither changes a parameter nor injects B".to_string());
+            }
+            pulse.apply(base).validate().or_else(|parameters| error(parameters.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Parameters of the evolution computed from `generation`: `base`, with
+    /// the values of the pulses active then
+    pub fn parameters_at(&self, generation: i32, base: Parameters) -> Parameters {
+        self.pulses
+            .iter()
+            .filter(|pulse| pulse.is_active(generation))
+            .fold(base, |parameters, pulse| pulse.apply(parameters))
+    }
+
+    /// Add the B of the injections active at `generation` to `universe`,
+    /// returning whether any was
+    pub fn inject<T: Float>(&self, generation: i32, universe: &mut Universe<T>) -> bool {
+        let mut injected = false;
+        for pulse in self.pulses.iter().filter(|pulse| pulse.is_active(generation)) {
+            let Some(Injection { region, amount }) = pulse.inject else {
+                continue;
+            };
+            let rows = universe.iter_mut().skip(region.origin.row).take(region.dimensions.row);
+            for row in rows {
+                for cell in row.iter_mut().skip(region.origin.col).take(region.dimensions.col) {
+                    let b = cell.b + T::from_f32(amount);
+                    cell.b = if b > T::from_f32(1.0) { T::from_f32(1.0) } else { b };
+                }
+            }
+            injected = true;
+        }
+        injected
+    }
+}
@@ -0,0 +1,219 @@
+/// WebSocket server mode
+/// The simulation runs headless and every few generations its color map is
+/// sent as a PNG image (a binary message) to all connected clients. Clients
+/// steer the simulation by sending text messages with a JSON object of the
+/// parameters to change, e.g. `{"f": 0.04, "k": 0.06}`
+use std::io::{self, Cursor};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use image::ImageOutputFormat;
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message, WebSocket};
+
+use crate::colormap::Colormap;
+use crate::error::check_interval;
+use crate::export::colored_map_to_image;
+use crate::replay::{Recorder, ReplayEvent};
+use crate::{ColoredMap, Parameters, Simulation, SimulationError};
+
+/// Frames queued for a client before new ones are dropped
+const CLIENT_QUEUE: usize = 2;
+/// Time a client waits for incoming messages before sending queued frames
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Time the simulation waits between checks for messages once finished
+const IDLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Settings of the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address the server listens on
+    pub address: String,
+    /// A frame is sent every `interval` generations
+    pub interval: i32,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: "127.0.0.1:9001".to_string(),
+            interval: 10,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Fails if the interval is below 1
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        check_interval("streamed frames", self.interval)
+    }
+}
+
+/// Parameter change requested by a client
+/// Missing fields keep their current value
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterUpdate {
+    pub d_a: Option<f32>,
+    pub d_b: Option<f32>,
+    pub f: Option<f32>,
+    pub k: Option<f32>,
+    pub r: Option<f32>,
+}
+
+impl ParameterUpdate {
+    /// Apply the change to `parameters`
+    pub fn apply(&self, parameters: &mut Parameters) {
+        parameters.d_a = self.d_a.unwrap_or(parameters.d_a);
+        parameters.d_b = self.d_b.unwrap_or(parameters.d_b);
+        parameters.f = self.f.unwrap_or(parameters.f);
+        parameters.k = self.k.unwrap_or(parameters.k);
+        parameters.r = self.r.unwrap_or(parameters.r);
+    }
+}
+
+/// Frame shared by every client
+type Frame = Arc<Vec<u8>>;
+
+/// Clients connected to the server
+#[derive(Default)]
+struct Clients {
+    senders: Vec<SyncSender<Frame>>,
+    /// Last frame sent, given to clients as soon as they connect
+    latest: Option<Frame>,
+}
+
+impl Clients {
+    /// Queue `frame` for every client, forgetting the disconnected ones
+    /// Clients that are too slow to keep up miss the frame
+    fn broadcast(&mut self, frame: Frame) {
+        self.senders.retain(|sender| match sender.try_send(frame.clone()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+        self.latest = Some(frame);
+    }
+}
+
+/// PNG encoding of a color map
+fn encode_frame(colored_map: &ColoredMap, colormap: Colormap) -> Frame {
+    let mut bytes = Vec::new();
+    colored_map_to_image(colored_map, colormap)
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .expect("encoding a PNG in memory cannot fail");
+    Arc::new(bytes)
+}
+
+/// Run `simulation` up to `steps` generations while serving
+/// it. Once the last generation is reached the server keeps running, so new
+/// clients still get the final frame. The parameter changes of the clients
+/// are recorded by `recorder` if given. Only returns if the configuration
+/// is invalid or the server cannot listen on its address
+pub fn serve(
+    config: &ServerConfig,
+    mut simulation: Simulation,
+    steps: i32,
+    colormap: Colormap,
+    mut recorder: Option<Recorder>,
+) -> Result<(), SimulationError> {
+    config.validate()?;
+    let listener = TcpListener::bind(&config.address)?;
+    let clients = Arc::new(Mutex::new(Clients::default()));
+    let (updates, received_updates) = mpsc::channel();
+
+    {
+        let clients = clients.clone();
+        thread::spawn(move || accept_clients(listener, clients, updates));
+    }
+
+    clients.lock().unwrap().broadcast(encode_frame(simulation.colored_map(), colormap));
+
+    loop {
+        for update in received_updates.try_iter() {
+            let mut parameters = simulation.parameters();
+            update.apply(&mut parameters);
+            if let Err(error) = simulation.set_parameters(parameters) {
+                eprintln!("ignoring parameters: {error}");
+                continue;
+            }
+            if let Some(recorder) = &mut recorder {
+                if let Err(error) = recorder.record(simulation.generation(), ReplayEvent::SetParameters(parameters)) {
+                    eprintln!("could not record parameter change: {error}");
+                }
+            }
+        }
+        if simulation.generation() >= steps || simulation.is_stopped() {
+            thread::sleep(IDLE_INTERVAL);
+            continue;
+        }
+
+        simulation.step();
+        let generation = simulation.generation();
+        if let Some(violation) = simulation.violation() {
+            eprintln!("stopping: {violation}");
+        }
+
+        if generation % config.interval == 0 || generation == steps || simulation.is_stopped() {
+            clients.lock().unwrap().broadcast(encode_frame(simulation.colored_map(), colormap));
+        }
+    }
+}
+
+/// Accept connections, serving each client in its own thread
+fn accept_clients(listener: TcpListener, clients: Arc<Mutex<Clients>>, updates: Sender<ParameterUpdate>) {
+    for stream in listener.incoming().flatten() {
+        let (sender, frames) = mpsc::sync_channel(CLIENT_QUEUE);
+        {
+            let mut clients = clients.lock().unwrap();
+            if let Some(latest) = &clients.latest {
+                let _ = sender.try_send(latest.clone());
+            }
+            clients.senders.push(sender);
+        }
+
+        let updates = updates.clone();
+        thread::spawn(move || {
+            if let Ok(socket) = tungstenite::accept(stream) {
+                serve_client(socket, frames, updates);
+            }
+        });
+    }
+}
+
+/// Send the frames to a client and forward its parameter changes, until it
+/// disconnects
+fn serve_client(mut socket: WebSocket<TcpStream>, frames: Receiver<Frame>, updates: Sender<ParameterUpdate>) {
+    if socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)).is_err() {
+        return;
+    }
+
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<ParameterUpdate>(&text) {
+                Ok(update) => {
+                    if updates.send(update).is_err() {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    let _ = socket.send(Message::Text(format!("invalid parameters: {error}")));
+                }
+            },
+            Ok(Message::Close(_)) => return,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(error))
+                if matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+
+        for frame in frames.try_iter() {
+            if socket.send(Message::Binary(frame.to_vec())).is_err() {
+                return;
+            }
+        }
+    }
+}
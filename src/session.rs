@@ -0,0 +1,212 @@
+/// Sessions of the application
+/// A session keeps everything needed to continue an experiment in a later
+/// sitting, in one file: the snapshots of the simulation and of the compared
+/// ones, the settings of the run (seed, generation limit, bounds, edges,
+/// timeline, modulation, reaction, couplings), the color map and the other
+/// outputs, the preset in use, the brush seeding the cells, and the camera
+/// and size of the window. It is written from the console of the application
+/// and restored on launch with `--session`. Like snapshots, sessions are
+/// written with bincode; reading and writing files requires the `fs` feature
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{BufReader, BufWriter};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::activity::ActivityTracking;
+use crate::blowup::BlowupCheck;
+use crate::config::OutputConfig;
+use crate::error::check_interval;
+use crate::initial::InitialCondition;
+use crate::layers::Coupling;
+use crate::modulation::{Modulation, ModulationConfig};
+use crate::noise::{Noise, NoiseConfig};
+use crate::reaction::ReactionConfig;
+use crate::render::RenderConfig;
+use crate::replay::TimedEvent;
+use crate::rewind::RewindConfig;
+#[cfg(feature = "fs")]
+use crate::snapshot::SnapshotError;
+use crate::snapshot::Snapshot;
+use crate::streams::{RngStream, StreamSeeds};
+use crate::symmetry::Symmetry;
+use crate::schedule::Schedule;
+use crate::timeline::Timeline;
+use crate::{Boundary, Bounds, Cell, Simulation, SimulationError, Stencil};
+
+/// Seeds placed with the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Brush {
+    /// A seed is a square of this many cells on each side of the clicked one
+    pub radius: usize,
+    /// Concentrations given to the cells of a seed
+    pub cell: Cell,
+    /// Largest fraction of the concentrations randomly taken off each cell
+    /// of a seed, drawn from the brush stream, see `streams`
+    pub jitter: f32,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Brush { radius: 2, cell: Cell { a: 1.0, b: 1.0 }, jitter: 0.0 }
+    }
+}
+
+impl Brush {
+    /// Fails if a concentration or the jitter is not in [0,1]
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let values = [("a", self.cell.a), ("b", self.cell.b), ("the jitter", self.jitter)];
+        match values.into_iter().find(|(_, value)| !(0.0..=1.0).contains(value)) {
+            Some((name, value)) => {
+                Err(SimulationError::InvalidBrush(format!("{name} must be in [0, 1], found {value}")))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Camera and window of the application
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct View {
+    /// Position of the camera
+    pub translation: [f32; 3],
+    /// Rotation of the camera, as a quaternion
+    pub rotation: [f32; 4],
+    /// Scale of the camera
+    pub scale: [f32; 3],
+    /// Width and height of the window, in logical pixels
+    pub window: [f32; 2],
+}
+
+/// Saved session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub simulation: Snapshot,
+    /// Simulations drawn next to `simulation`, see `app::SimulationState`
+    pub comparisons: Vec<Snapshot>,
+    /// Name of the run, see `naming`
+    pub name: String,
+    /// Seed of the run, if it had one
+    pub seed: Option<u64>,
+    /// Seeds of the random streams given to the run, see `streams`
+    pub streams: StreamSeeds,
+    /// Generation at which the evolution stops
+    pub steps: i32,
+    pub bounds: Bounds,
+    pub boundary: Boundary,
+    pub stencil: Stencil,
+    pub timeline: Timeline,
+    pub schedule: Schedule,
+    pub modulation: Option<ModulationConfig>,
+    pub reaction: Option<ReactionConfig>,
+    pub activity: Option<ActivityTracking>,
+    pub noise: Option<NoiseConfig>,
+    /// Seed of the noise stream the noise was drawn from, see `Noise::seed`
+    pub noise_seed: Option<u64>,
+    pub blowup: Option<BlowupCheck>,
+    /// Symmetry the universe of `simulation` is projected onto, see
+    /// `symmetry`
+    pub symmetry: Option<Symmetry>,
+    pub couplings: Vec<Coupling>,
+    pub output: OutputConfig,
+    /// Name of the preset in use
+    pub preset: Option<String>,
+    pub render: RenderConfig,
+    pub rewind: RewindConfig,
+    /// Generations between two updates of the statistics
+    pub stats_interval: i32,
+    /// Changes still to be applied, e.g. from a replay
+    pub events: Vec<TimedEvent>,
+    /// Number of random cells of the universes drawn by the `seed` command
+    pub initial_cells: usize,
+    /// Generated initial condition of the universes drawn by the `seed`
+    /// command, `initial_cells` random cells if there is none
+    pub initial: Option<InitialCondition>,
+    pub brush: Brush,
+    /// Camera and window, if saved from the application
+    pub view: Option<View>,
+}
+
+impl Session {
+    /// Read a session written by `Session::save`
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Session, SnapshotError> {
+        let mut session: Session = bincode::deserialize_from(BufReader::new(File::open(path)?))?;
+        session.simulation = session.simulation.validate()?;
+        let dimensions = session.simulation.dimensions;
+        session.comparisons = std::mem::take(&mut session.comparisons)
+            .into_iter()
+            .map(|comparison| match comparison.dimensions == dimensions {
+                true => comparison.validate(),
+                false => Err(SnapshotError::DimensionMismatch),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(session)
+    }
+
+    /// Fails if the brush is invalid, or the statistics are updated or the
+    /// rewind keyframes taken less than 1 generation apart
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        self.brush.validate()?;
+        self.rewind.validate()?;
+        check_interval("statistics", self.stats_interval)
+    }
+
+    /// Write the session to `path`
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        bincode::serialize_into(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Simulation continuing from the saved snapshot of `simulation`, with
+    /// the settings of the session
+    pub fn simulation(&self) -> Result<Simulation, SimulationError> {
+        let simulation = self
+            .restore(&self.simulation)?
+            .with_timeline(self.timeline.clone())?
+            .with_schedule(self.schedule.clone())?;
+        Ok(match self.symmetry {
+            Some(symmetry) => simulation.with_symmetry(symmetry),
+            None => simulation,
+        })
+    }
+
+    /// Compared simulations continuing from their saved snapshots
+    pub fn comparisons(&self) -> Result<Vec<Simulation>, SimulationError> {
+        self.comparisons.iter().map(|snapshot| self.restore(snapshot)).collect()
+    }
+
+    fn restore(&self, snapshot: &Snapshot) -> Result<Simulation, SimulationError> {
+        let mut simulation = snapshot
+            .clone()
+            .into_simulation()?
+            .with_bounds(self.bounds)
+            .with_boundary(self.boundary)
+            .with_stencil(self.stencil);
+        if let Some(modulation) = &self.modulation {
+            simulation = simulation.with_modulation(Modulation::new(modulation.clone())?)?;
+        }
+        if let Some(reaction) = &self.reaction {
+            simulation = simulation.with_reaction(reaction.compile()?);
+        }
+        if let Some(tracking) = self.activity {
+            simulation = simulation.with_activity_tracking(tracking);
+        }
+        if let Some(noise) = self.noise {
+            let seed = self
+                .noise_seed
+                .or_else(|| self.streams.seed(self.seed, RngStream::Noise))
+                .unwrap_or_else(rand::random);
+            simulation = simulation.with_noise(Noise::new(noise, seed)?);
+        }
+        if let Some(check) = self.blowup {
+            simulation = simulation.with_blowup_check(check)?;
+        }
+        Ok(simulation)
+    }
+}
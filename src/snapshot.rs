@@ -0,0 +1,189 @@
+/// Snapshots of the universe
+/// A snapshot stores everything needed to continue a run later: the
+/// parameters, the dimensions, the edges, the generation reached, every cell
+/// and the seed of the run if it is known.
+/// Snapshots are written with bincode, or as JSON when the file has a `.json`
+/// extension and the `json` feature is enabled. Reading and writing files
+/// requires the `fs` feature
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io;
+#[cfg(feature = "fs")]
+use std::io::{BufReader, BufWriter, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{color_universe, Boundary, ColoredMap, Parameters, Position, Simulation, SimulationError, Universe};
+
+/// State of a simulation at a given generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub parameters: Parameters,
+    pub dimensions: Position,
+    /// Edges of the universe
+    pub boundary: Boundary,
+    /// Number of evolutions computed to reach `universe`
+    pub generation: i32,
+    pub universe: Universe,
+    /// Seed the run started from, if known, so that a resumed run keeps its
+    /// name and its checkpoints
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Error while reading or writing a snapshot
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Bincode(bincode::Error),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// The file has a `.json` extension but the `json` feature is disabled
+    JsonUnsupported,
+    /// The universe does not have the dimensions stored in the snapshot
+    DimensionMismatch,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{error}"),
+            SnapshotError::Bincode(error) => write!(f, "invalid snapshot: {error}"),
+            #[cfg(feature = "json")]
+            SnapshotError::Json(error) => write!(f, "invalid JSON snapshot: {error}"),
+            SnapshotError::JsonUnsupported => {
+                write!(f, "JSON snapshots require the `json` feature")
+            }
+            SnapshotError::DimensionMismatch => {
+                write!(f, "the universe does not match the dimensions of the snapshot")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(error: bincode::Error) -> Self {
+        SnapshotError::Bincode(error)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for SnapshotError {
+    fn from(error: serde_json::Error) -> Self {
+        SnapshotError::Json(error)
+    }
+}
+
+/// Whether `path` should be read or written as JSON
+#[cfg(feature = "fs")]
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "json")
+}
+
+impl Snapshot {
+    /// Snapshot from the bincode bytes written by `Snapshot::to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+        let snapshot: Snapshot = bincode::deserialize(bytes)?;
+        snapshot.validate()
+    }
+
+    /// Snapshot encoded with bincode
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Check that the universe has the stored dimensions
+    pub(crate) fn validate(self) -> Result<Snapshot, SnapshotError> {
+        if self.universe.len() != self.dimensions.row
+            || self.universe.iter().any(|row| row.len() != self.dimensions.col)
+        {
+            return Err(SnapshotError::DimensionMismatch);
+        }
+        Ok(self)
+    }
+
+    /// Read a snapshot written by `Snapshot::save`
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Snapshot, SnapshotError> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+
+        let snapshot: Snapshot = if is_json(path) {
+            #[cfg(feature = "json")]
+            {
+                serde_json::from_reader(reader)?
+            }
+            #[cfg(not(feature = "json"))]
+            {
+                return Err(SnapshotError::JsonUnsupported);
+            }
+        } else {
+            bincode::deserialize_from(reader)?
+        };
+        snapshot.validate()
+    }
+
+    /// Write the snapshot to `path`
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+
+        if is_json(path) {
+            #[cfg(feature = "json")]
+            {
+                serde_json::to_writer(BufWriter::new(File::create(path)?), self)?;
+                return Ok(());
+            }
+            #[cfg(not(feature = "json"))]
+            {
+                return Err(SnapshotError::JsonUnsupported);
+            }
+        }
+
+        // Flushed here so that a failed write is an error, not lost on drop
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Color map of the stored universe
+    pub fn colored_map(&self) -> ColoredMap {
+        color_universe(&self.universe)
+    }
+
+    /// Snapshot of the current state of `simulation`
+    pub fn of(simulation: &Simulation) -> Snapshot {
+        Snapshot {
+            parameters: simulation.parameters(),
+            dimensions: simulation.dimensions(),
+            boundary: simulation.boundary(),
+            generation: simulation.generation(),
+            universe: simulation.universe().clone(),
+            seed: None,
+        }
+    }
+
+    /// Same snapshot, recording the seed the run started from
+    pub fn with_seed(self, seed: Option<u64>) -> Snapshot {
+        Snapshot { seed, ..self }
+    }
+
+    /// Simulation continuing from the stored generation, with the stored edges
+    pub fn into_simulation(self) -> Result<Simulation, SimulationError> {
+        Ok(Simulation::new(self.parameters, self.dimensions, self.universe)?
+            .with_boundary(self.boundary)
+            .with_generation(self.generation))
+    }
+}
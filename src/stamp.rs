@@ -0,0 +1,111 @@
+/// Stamps
+/// A patch copied out of a universe, e.g. a spot or a stretch of stripes of
+/// an evolved pattern, see `region::crop`, to be stamped elsewhere in it or
+/// in another universe, see `region::blit`, turned by quarter turns and
+/// mirrored, to build controlled initial conditions from evolved patterns.
+/// In the window, dragging with `Ctrl` held over a color map copies the cells
+/// under the drag, clicks then stamp them instead of placing seeds, `R` and
+/// `M` turn and mirror the stamp, and the `stamp` command of the console
+/// does the same without the mouse
+use crate::region::{crop, Rect};
+use crate::{Float, Position, Simulation, SimulationError, Universe};
+
+/// How a stamp is turned before being stamped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// Number of quarter turns clockwise, from 0 to 3
+    pub quarter_turns: u8,
+    /// Whether the patch is mirrored left to right, before being turned
+    pub mirrored: bool,
+}
+
+/// Patch of cells to be stamped
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stamp<T: Float = f32> {
+    /// Cells as copied, before being oriented
+    patch: Universe<T>,
+    orientation: Orientation,
+}
+
+impl<T: Float> Stamp<T> {
+    /// Stamp of `patch`, as it is
+    pub fn new(patch: Universe<T>) -> Stamp<T> {
+        Stamp { patch, orientation: Orientation::default() }
+    }
+
+    /// Stamp of the cells of `universe` inside `rect`
+    /// Fails if `rect` does not lie inside the universe
+    pub fn copy(universe: &Universe<T>, rect: Rect) -> Result<Stamp<T>, SimulationError> {
+        Ok(Stamp::new(crop(universe, rect)?))
+    }
+
+    /// Same stamp, oriented as `orientation` says
+    pub fn with_orientation(mut self, orientation: Orientation) -> Stamp<T> {
+        self.orientation = Orientation { quarter_turns: orientation.quarter_turns % 4, ..orientation };
+        self
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Turn the stamp by a quarter turn clockwise
+    pub fn rotate(&mut self) {
+        self.orientation.quarter_turns = (self.orientation.quarter_turns + 1) % 4;
+    }
+
+    /// Mirror the stamp left to right, as it is currently turned
+    pub fn mirror(&mut self) {
+        // Mirroring after turning is mirroring before turning the other way
+        let Orientation { quarter_turns, mirrored } = self.orientation;
+        self.orientation = Orientation { quarter_turns: (4 - quarter_turns) % 4, mirrored: !mirrored };
+    }
+
+    /// Number of rows and columns of the stamp as oriented
+    pub fn dimensions(&self) -> Position {
+        let rows = self.patch.len();
+        let cols = self.patch.first().map_or(0, Vec::len);
+        match self.orientation.quarter_turns % 2 {
+            0 => Position { row: rows, col: cols },
+            _ => Position { row: cols, col: rows },
+        }
+    }
+
+    /// Cells of the stamp as oriented
+    pub fn oriented(&self) -> Universe<T> {
+        let mut patch = self.patch.clone();
+        if self.orientation.mirrored {
+            patch.iter_mut().for_each(|row| row.reverse());
+        }
+        for _ in 0..self.orientation.quarter_turns {
+            let cols = patch.first().map_or(0, Vec::len);
+            patch = (0..cols).map(|col| patch.iter().rev().map(|row| row[col]).collect()).collect();
+        }
+        patch
+    }
+
+    /// Rectangle covered by the stamp centered on `center` in a universe of
+    /// `dimensions`, moved inside it if it crosses its edges
+    pub fn rect_at(&self, center: Position, dimensions: Position) -> Rect {
+        let size = self.dimensions();
+        let origin = |center: usize, size: usize, length: usize| {
+            center.saturating_sub(size / 2).min(length.saturating_sub(size))
+        };
+        Rect {
+            origin: Position {
+                row: origin(center.row, size.row, dimensions.row),
+                col: origin(center.col, size.col, dimensions.col),
+            },
+            dimensions: size,
+        }
+    }
+
+    /// Stamp the cells into `simulation`, centered on `center`, returning
+    /// the rectangle they now cover
+    /// Fails if the stamp is larger than the universe
+    pub fn stamp(&self, simulation: &mut Simulation<T>, center: Position) -> Result<Rect, SimulationError> {
+        let rect = self.rect_at(center, simulation.dimensions());
+        simulation.blit(rect, &self.oriented())?;
+        Ok(rect)
+    }
+}
@@ -0,0 +1,111 @@
+/// Statistics of a universe
+/// Mean, variance, extremes and total mass of each species, e.g. to check
+/// whether a run has converged, show readouts or log a run to CSV. They are
+/// accumulated in `f64` whatever the precision of the universe
+#[cfg(feature = "bevy")]
+use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
+
+use crate::{Float, Simulation, Universe};
+
+/// Largest change per generation of the means and variances of A and B for
+/// which a universe has converged, see `Stats::change_since`
+pub const CONVERGENCE_TOLERANCE: f64 = 1e-9;
+
+/// Statistics of the concentrations of one species
+/// All zero for an empty universe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+pub struct SpeciesStats {
+    pub mean: f64,
+    /// Population variance
+    pub variance: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Sum of the concentrations over all the cells
+    pub mass: f64,
+}
+
+impl SpeciesStats {
+    /// Statistics of `values`, read twice so the variance is computed around
+    /// the mean
+    fn of<I: Iterator<Item = f64>>(values: impl Fn() -> I) -> SpeciesStats {
+        let (mut count, mut mass) = (0usize, 0.0);
+        let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+        for value in values() {
+            count += 1;
+            mass += value;
+            min = min.min(value);
+            max = max.max(value);
+        }
+        if count == 0 {
+            return SpeciesStats::default();
+        }
+
+        let mean = mass / count as f64;
+        let variance = values().map(|value| (value - mean).powi(2)).sum::<f64>() / count as f64;
+        SpeciesStats { mean, variance, min, max, mass }
+    }
+}
+
+/// Statistics of the A and B concentrations of a universe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+pub struct Stats {
+    pub a: SpeciesStats,
+    pub b: SpeciesStats,
+}
+
+impl Stats {
+    /// Largest change per generation of the means and variances of A and B
+    /// since `previous`, computed `generations` evolutions earlier
+    pub fn change_since(&self, previous: &Stats, generations: i32) -> f64 {
+        let generations = generations.max(1) as f64;
+        [
+            self.a.mean - previous.a.mean,
+            self.a.variance - previous.a.variance,
+            self.b.mean - previous.b.mean,
+            self.b.variance - previous.b.variance,
+        ]
+        .iter()
+        .map(|difference| difference.abs() / generations)
+        .fold(0.0, f64::max)
+    }
+}
+
+/// Statistics of the cells of `universe`
+pub fn stats<T: Float>(universe: &Universe<T>) -> Stats {
+    let cells = || universe.iter().flatten();
+    Stats {
+        a: SpeciesStats::of(|| cells().map(|cell| cell.a.to_f64())),
+        b: SpeciesStats::of(|| cells().map(|cell| cell.b.to_f64())),
+    }
+}
+
+impl<T: Float> Simulation<T> {
+    /// Statistics of the current universe, see `stats`
+    pub fn stats(&self) -> Stats {
+        stats(self.universe())
+    }
+
+    /// Compute up to `max_steps` evolutions, stopping once the statistics
+    /// change by less than `CONVERGENCE_TOLERANCE` per generation, checked
+    /// every `interval` evolutions
+    /// Returns whether the universe converged
+    pub fn run_until_converged(&mut self, max_steps: i32, interval: i32) -> bool {
+        let interval = interval.max(1);
+        let mut previous = self.stats();
+        let mut remaining = max_steps;
+        while remaining > 0 && !self.is_stopped() {
+            let steps = interval.min(remaining);
+            self.run(steps);
+            remaining -= steps;
+            let current = self.stats();
+            if current.change_since(&previous, steps) < CONVERGENCE_TOLERANCE {
+                return true;
+            }
+            previous = current;
+        }
+        false
+    }
+}
@@ -0,0 +1,88 @@
+/// Random streams
+/// The randomness of a run is split into named streams, each drawn from its
+/// own generator, so that drawing more or fewer numbers from one never
+/// shifts the others: re-rolling the noise added at every evolution, see
+/// `noise`, keeps the initial universe, and the jitter of the seeds placed
+/// with the brush changes neither. The seed of each stream is given on its
+/// own or derived from the seed of the run, the initial stream using it
+/// unchanged so that seeded runs draw the same universes as before:
+///
+/// ```ron
+/// seed: Some(7),
+/// streams: (noise: Some(42)),
+/// ```
+use serde::{Deserialize, Serialize};
+
+/// Named source of randomness of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RngStream {
+    /// Initial universe, see `initial`
+    Initial,
+    /// Noise added to B at every evolution, see `noise`
+    Noise,
+    /// Jitter of the seeds placed with the brush, see `session::Brush`
+    Brush,
+}
+
+/// Names of the streams, as written in the configuration files
+pub const RNG_STREAM_NAMES: [&str; 3] = ["initial", "noise", "brush"];
+
+impl RngStream {
+    /// Stream with the given name, see `RNG_STREAM_NAMES`
+    pub fn from_name(name: &str) -> Option<RngStream> {
+        match name {
+            "initial" => Some(RngStream::Initial),
+            "noise" => Some(RngStream::Noise),
+            "brush" => Some(RngStream::Brush),
+            _ => None,
+        }
+    }
+
+    /// Name of the stream, one of `RNG_STREAM_NAMES`
+    pub fn name(&self) -> &'static str {
+        match self {
+            RngStream::Initial => "initial",
+            RngStream::Noise => "noise",
+            RngStream::Brush => "brush",
+        }
+    }
+}
+
+/// Seed of `stream` derived from the seed of a run: the seed itself for the
+/// initial stream, and a SplitMix64 hash of it mixed with the name of the
+/// stream for the others
+pub fn stream_seed(seed: u64, stream: RngStream) -> u64 {
+    if stream == RngStream::Initial {
+        return seed;
+    }
+    let tag = stream.name().bytes().fold(0u64, |tag, byte| tag << 8 | byte as u64);
+    let mut z = (seed ^ tag).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Seeds given to some streams, the others being derived from the seed of
+/// the run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamSeeds {
+    pub initial: Option<u64>,
+    pub noise: Option<u64>,
+    pub brush: Option<u64>,
+}
+
+impl StreamSeeds {
+    /// Seed of `stream` in a run seeded with `seed`: the one given for the
+    /// stream, else derived from `seed`, see `stream_seed`; `None` if there
+    /// is neither, for a stream drawn from entropy
+    pub fn seed(&self, seed: Option<u64>, stream: RngStream) -> Option<u64> {
+        let given = match stream {
+            RngStream::Initial => self.initial,
+            RngStream::Noise => self.noise,
+            RngStream::Brush => self.brush,
+        };
+        given.or_else(|| seed.map(|seed| stream_seed(seed, stream)))
+    }
+}
@@ -0,0 +1,89 @@
+/// Reaction–diffusion on surfaces
+/// Instead of a grid of cells, a surface simulation evolves one cell per
+/// vertex of a triangle mesh, e.g. a fish or a shell read from an OBJ file,
+/// whose neighbours are the vertices sharing an edge with it. Diffusion
+/// follows the graph Laplacian of the mesh: a vertex gives away `d_a` and
+/// `d_b` of its concentrations and receives the same share of the mean of
+/// its neighbours, as a cell of the grid does with its eight neighbours: the
+/// mesh is evolved as a `GraphSimulation` of its edges. The reactions are
+/// those of `Simulation`. Vertices should be evenly spaced, the size of the
+/// features of the pattern being counted in edges
+use rand::Rng;
+
+use crate::colormap::Colormap;
+use crate::graph::{initialize_graph, Graph, GraphSimulation};
+use crate::mesh::Mesh;
+use crate::{Cell, Parameters, SimulationError};
+
+/// Concentrations of the vertices of a mesh with no A nor B but in `n` random
+/// vertices drawn from `rng`, which have both A and B
+/// Same as `initialize_universe_with_rng` for a surface
+pub fn initialize_surface<R: Rng + ?Sized>(vertices: usize, n: usize, rng: &mut R) -> Vec<Cell> {
+    initialize_graph(vertices, n, rng)
+}
+
+/// Simulation evolving the vertices of a mesh
+#[derive(Debug, Clone)]
+pub struct SurfaceSimulation {
+    mesh: Mesh,
+    /// Simulation of the graph of the mesh
+    graph: GraphSimulation,
+}
+
+impl SurfaceSimulation {
+    /// Fails if the parameters are invalid or there is not one cell per
+    /// vertex of `mesh`
+    pub fn new(parameters: Parameters, mesh: Mesh, cells: Vec<Cell>) -> Result<SurfaceSimulation, SimulationError> {
+        let graph = GraphSimulation::new(parameters, Graph::from_mesh(&mesh)?, cells)?;
+        Ok(SurfaceSimulation { mesh, graph })
+    }
+
+    pub fn parameters(&self) -> Parameters {
+        self.graph.parameters()
+    }
+
+    /// Change the parameters, keeping the concentrations
+    pub fn set_parameters(&mut self, parameters: Parameters) -> Result<(), SimulationError> {
+        self.graph.set_parameters(parameters)
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    /// Concentrations of the vertices, in the order of the mesh
+    pub fn cells(&self) -> &[Cell] {
+        self.graph.cells()
+    }
+
+    /// Color values of the vertices, in the order of the mesh
+    pub fn colors(&self) -> &[f32] {
+        self.graph.colors()
+    }
+
+    /// Number of evolutions computed so far
+    pub fn generation(&self) -> i32 {
+        self.graph.generation()
+    }
+
+    /// Compute one evolution of every vertex
+    pub fn step(&mut self) {
+        self.graph.step();
+    }
+
+    /// Compute `n` evolutions
+    pub fn run(&mut self, n: i32) {
+        self.graph.run(n);
+    }
+
+    /// sRGB color of every vertex with `colormap`, in [0,1]
+    pub fn vertex_colors(&self, colormap: Colormap) -> Vec<[f32; 3]> {
+        self.colors().iter().map(|value| colormap.color(*value).map(|channel| channel as f32 / 255.0)).collect()
+    }
+
+    /// Mesh colored with `colormap`, consuming the simulation
+    pub fn into_colored_mesh(self, colormap: Colormap) -> Mesh {
+        let colors = self.vertex_colors(colormap);
+        Mesh { colors, ..self.mesh }
+    }
+}
@@ -0,0 +1,335 @@
+/// Parameter sweeps
+/// A sweep runs every combination of ranges of parameters, usually `f` and
+/// `k`, from the same initial universe, in parallel, and reports the
+/// statistics, dominant wavelength and kind of pattern of each run. With the
+/// `fs` feature the final color maps, a summary CSV and a montage of all the
+/// runs can be written to a directory
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+#[cfg(feature = "fs")]
+use std::io::{BufWriter, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::str::FromStr;
+#[cfg(feature = "fs")]
+use std::sync::Mutex;
+
+#[cfg(feature = "fs")]
+use image::imageops::{self, FilterType};
+#[cfg(feature = "fs")]
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Wavelength;
+use crate::classify::PatternKind;
+#[cfg(feature = "fs")]
+use crate::colormap::Colormap;
+#[cfg(feature = "fs")]
+use crate::config::{read_file, ConfigError};
+use crate::config::InitialConfig;
+#[cfg(feature = "fs")]
+use crate::export::colored_map_to_image;
+use crate::parallel::run_jobs;
+use crate::stats::Stats;
+use crate::{Boundary, Bounds, Parameters, Position, Simulation, SimulationError, Universe};
+
+/// Largest number of pixels on each side of a tile of the montage
+#[cfg(feature = "fs")]
+const MAX_TILE_SIZE: u32 = 128;
+
+/// Evenly spaced values of a parameter, from `start` to `end` included
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SweepRange {
+    pub start: f32,
+    pub end: f32,
+    /// Number of values, 1 keeps only `start`
+    pub count: usize,
+}
+
+impl SweepRange {
+    /// Range of the single value `value`
+    pub fn single(value: f32) -> SweepRange {
+        SweepRange { start: value, end: value, count: 1 }
+    }
+
+    /// Values of the range, in increasing order if `start` < `end`
+    pub fn values(&self) -> Vec<f32> {
+        if self.count <= 1 {
+            return vec![self.start];
+        }
+        let step = (self.end - self.start) / (self.count - 1) as f32;
+        (0..self.count).map(|index| self.start + step * index as f32).collect()
+    }
+}
+
+/// Range written `start:end:count`, or a single value
+impl FromStr for SweepRange {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let value = |part: &str| {
+            part.trim()
+                .parse::<f32>()
+                .map_err(|error| format!("invalid value `{part}` in range `{text}`: {error}"))
+        };
+        match text.split(':').collect::<Vec<_>>()[..] {
+            [single] => Ok(SweepRange::single(value(single)?)),
+            [start, end, count] => Ok(SweepRange {
+                start: value(start)?,
+                end: value(end)?,
+                count: count
+                    .trim()
+                    .parse()
+                    .map_err(|error| format!("invalid count `{count}` in range `{text}`: {error}"))?,
+            }),
+            _ => Err(format!("invalid range `{text}`, expected `start:end:count` or a value")),
+        }
+    }
+}
+
+impl fmt::Display for SweepRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.start, self.end, self.count)
+    }
+}
+
+/// Settings of a sweep
+/// The parameters without a range keep their value in `base`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sweep {
+    pub base: Parameters,
+    pub f: Option<SweepRange>,
+    pub k: Option<SweepRange>,
+    pub d_a: Option<SweepRange>,
+    pub d_b: Option<SweepRange>,
+    pub r: Option<SweepRange>,
+    /// Number of rows and columns of the universes
+    pub dimensions: Position,
+    /// Number of evolutions of every run
+    pub steps: i32,
+    /// Initial state shared by all the runs
+    pub initial: InitialConfig,
+    /// Seed of the initial state, the same for all the runs
+    pub seed: u64,
+    pub bounds: Bounds,
+    pub boundary: Boundary,
+    /// Number of runs computed at the same time, 0 for one per processor
+    pub threads: usize,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep {
+            base: Parameters::default(),
+            f: None,
+            k: None,
+            d_a: None,
+            d_b: None,
+            r: None,
+            dimensions: Position { row: 200, col: 200 },
+            steps: 2000,
+            initial: InitialConfig::default(),
+            seed: 0,
+            bounds: Bounds::default(),
+            boundary: Boundary::default(),
+            threads: 0,
+        }
+    }
+}
+
+/// Result of one run of a sweep
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepRun {
+    /// Position of the run in `Sweep::parameters`
+    pub index: usize,
+    pub parameters: Parameters,
+    /// Generation the run stopped at
+    pub generation: i32,
+    pub stats: Stats,
+    pub wavelength: Option<Wavelength>,
+    pub kind: PatternKind,
+    /// Why the run failed or stopped early, e.g. invalid parameters or a
+    /// concentration out of bounds
+    pub error: Option<String>,
+}
+
+impl Sweep {
+    /// Read the settings of a sweep from a `.ron` or `.toml` file
+    #[cfg(feature = "fs")]
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Sweep, ConfigError> {
+        read_file(path.as_ref())
+    }
+
+    /// Values taken by `f`, i.e. the number of columns of the montage
+    fn f_values(&self) -> Vec<f32> {
+        self.f.unwrap_or(SweepRange::single(self.base.f)).values()
+    }
+
+    /// Parameters of every run
+    /// `f` changes fastest, then `k`, `r`, `d_b` and `d_a`
+    pub fn parameters(&self) -> Vec<Parameters> {
+        let values = |range: Option<SweepRange>, base: f32| range.unwrap_or(SweepRange::single(base)).values();
+        let mut parameters = Vec::new();
+        for d_a in values(self.d_a, self.base.d_a) {
+            for d_b in values(self.d_b, self.base.d_b) {
+                for r in values(self.r, self.base.r) {
+                    for k in values(self.k, self.base.k) {
+                        for f in self.f_values() {
+                            parameters.push(Parameters { d_a, d_b, f, k, r, ..self.base });
+                        }
+                    }
+                }
+            }
+        }
+        parameters
+    }
+
+    /// Compute all the runs, in parallel, and return their results in the
+    /// order of `parameters`
+    /// `on_run` is called, from the thread that computed it, with the result
+    /// and the final simulation of every run that could start. Fails only if
+    /// the initial universe cannot be built
+    pub fn run<F>(&self, on_run: F) -> Result<Vec<SweepRun>, SimulationError>
+    where
+        F: Fn(&SweepRun, &Simulation) + Sync,
+    {
+        let (universe, dimensions) = self.initial.universe(&self.base, self.dimensions, Some(self.seed))?;
+        Ok(run_jobs(&self.parameters(), self.threads, |index, parameters| {
+            self.run_one(index, *parameters, dimensions, universe.clone(), &on_run)
+        }))
+    }
+
+    fn run_one<F>(
+        &self,
+        index: usize,
+        parameters: Parameters,
+        dimensions: Position,
+        universe: Universe,
+        on_run: &F,
+    ) -> SweepRun
+    where
+        F: Fn(&SweepRun, &Simulation),
+    {
+        let mut simulation: Simulation = match Simulation::new(parameters, dimensions, universe) {
+            Ok(simulation) => simulation.with_bounds(self.bounds).with_boundary(self.boundary),
+            Err(error) => {
+                return SweepRun {
+                    index,
+                    parameters,
+                    generation: 0,
+                    stats: Stats::default(),
+                    wavelength: None,
+                    kind: PatternKind::Uniform,
+                    error: Some(error.to_string()),
+                }
+            }
+        };
+        simulation.run(self.steps);
+
+        let classification = simulation.classify();
+        let run = SweepRun {
+            index,
+            parameters,
+            generation: simulation.generation(),
+            stats: simulation.stats(),
+            wavelength: classification.wavelength,
+            kind: classification.kind,
+            error: simulation.violation().map(|violation| violation.to_string()),
+        };
+        on_run(&run, &simulation);
+        run
+    }
+
+    /// Compute all the runs and write their results to `directory`:
+    /// `run_<index>.png` with the final color map of every run,
+    /// `summary.csv` with one line per run and `montage.png` with all the
+    /// color maps side by side, one column per value of `f`
+    #[cfg(feature = "fs")]
+    pub fn write(&self, directory: &Path, colormap: Colormap) -> Result<Vec<SweepRun>, SimulationError> {
+        fs::create_dir_all(directory)?;
+        let tiles = Mutex::new(Vec::new());
+        let failed = Mutex::new(None);
+
+        let runs = self.run(|run, simulation| {
+            let image = colored_map_to_image(simulation.colored_map(), colormap);
+            let path = directory.join(format!("run_{:04}.png", run.index));
+            if let Err(error) = image.save(&path) {
+                failed.lock().unwrap().get_or_insert(SimulationError::from(error));
+            }
+            let scale = MAX_TILE_SIZE as f32 / image.width().max(image.height()).max(1) as f32;
+            let tile = if scale < 1.0 {
+                let width = ((image.width() as f32 * scale) as u32).max(1);
+                let height = ((image.height() as f32 * scale) as u32).max(1);
+                imageops::resize(&image, width, height, FilterType::Nearest)
+            } else {
+                image
+            };
+            tiles.lock().unwrap().push((run.index, tile));
+        })?;
+        if let Some(error) = failed.into_inner().unwrap() {
+            return Err(error);
+        }
+
+        write_summary(&runs, directory.join("summary.csv"))?;
+        let columns = self.f_values().len();
+        montage(tiles.into_inner().unwrap(), runs.len(), columns).save(directory.join("montage.png"))?;
+        Ok(runs)
+    }
+}
+
+/// Write one CSV line per run, after a header
+#[cfg(feature = "fs")]
+fn write_summary(runs: &[SweepRun], path: impl AsRef<Path>) -> Result<(), SimulationError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(
+        writer,
+        "index,d_a,d_b,f,k,r,generation,\
+         mean_a,variance_a,min_a,max_a,mass_a,mean_b,variance_b,min_b,max_b,mass_b,\
+         wavelength,isotropy,kind,error"
+    )?;
+    for run in runs {
+        let Parameters { d_a, d_b, f, k, r, .. } = run.parameters;
+        let Stats { a, b } = run.stats;
+        let (wavelength, isotropy) = run
+            .wavelength
+            .map_or((String::new(), String::new()), |w| (w.wavelength.to_string(), w.isotropy.to_string()));
+        let error = run.error.as_deref().unwrap_or_default().replace('"', "'");
+        writeln!(
+            writer,
+            "{},{d_a},{d_b},{f},{k},{r},{},{},{},{},{},{},{},{},{},{},{},{wavelength},{isotropy},{},\"{error}\"",
+            run.index,
+            run.generation,
+            a.mean,
+            a.variance,
+            a.min,
+            a.max,
+            a.mass,
+            b.mean,
+            b.variance,
+            b.min,
+            b.max,
+            b.mass,
+            run.kind,
+        )?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// Image with the tiles of `count` runs in a grid of `columns` columns, in
+/// the order of their indices; runs without a tile are left black
+#[cfg(feature = "fs")]
+fn montage(tiles: Vec<(usize, RgbImage)>, count: usize, columns: usize) -> RgbImage {
+    let columns = columns.max(1);
+    let rows = count.div_ceil(columns);
+    let width = tiles.iter().map(|(_, tile)| tile.width()).max().unwrap_or(1);
+    let height = tiles.iter().map(|(_, tile)| tile.height()).max().unwrap_or(1);
+
+    let mut montage = RgbImage::new(width * columns as u32, height * rows.max(1) as u32);
+    for (index, tile) in tiles {
+        let (row, col) = (index / columns, index % columns);
+        imageops::replace(&mut montage, &tile, (col as u32 * width) as i64, (row as u32 * height) as i64);
+    }
+    montage
+}
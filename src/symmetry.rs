@@ -0,0 +1,193 @@
+/// Symmetric patterns
+/// Seeds placed with a symmetry around the center of the universe grow into
+/// mandala-like patterns, as long as the evolution keeps the symmetry:
+/// rounding, the edges of the universe and, for orders other than 2 and 4,
+/// the square grid itself break it little by little. A simulation can
+/// enforce it by projecting its universe onto the symmetry after every
+/// evolution, see `Simulation::with_symmetry`: every cell takes the average
+/// of the cells it is mapped to by the symmetry, the nearest ones for
+/// rotations falling between cells
+use std::f32::consts::TAU;
+use std::fmt;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{color_cell, Cell, ColoredMap, Float, Position, Universe};
+
+/// Symmetry around the center of the universe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Symmetry {
+    /// Reflection across the vertical axis through the center
+    Mirror,
+    /// Rotations by every multiple of a turn divided by the order
+    Rotational(u32),
+    /// Rotations of `Rotational`, and their reflections across the vertical
+    /// axis
+    Dihedral(u32),
+}
+
+/// Names of the symmetries, `rotational` and `dihedral` followed by their
+/// order, e.g. `rotational:6`
+pub const SYMMETRY_NAMES: [&str; 3] = ["mirror", "rotational:<order>", "dihedral:<order>"];
+
+impl Symmetry {
+    /// Symmetry with the given name, see `SYMMETRY_NAMES`
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        let (kind, order) = match name.split_once(':') {
+            Some((kind, order)) => (kind, Some(order.parse().ok().filter(|order| *order >= 1)?)),
+            None => (name, None),
+        };
+        match (kind, order) {
+            ("mirror", None) => Some(Symmetry::Mirror),
+            ("rotational", Some(order)) => Some(Symmetry::Rotational(order)),
+            ("dihedral", Some(order)) => Some(Symmetry::Dihedral(order)),
+            _ => None,
+        }
+    }
+
+    /// Number of images of a point, itself included
+    pub fn order(&self) -> usize {
+        match self {
+            Symmetry::Mirror => 2,
+            Symmetry::Rotational(order) => (*order).max(1) as usize,
+            Symmetry::Dihedral(order) => 2 * (*order).max(1) as usize,
+        }
+    }
+
+    /// Images of the point `row`, `col` away from the center, itself first
+    fn images(&self, row: f32, col: f32) -> Vec<(f32, f32)> {
+        let rotations = |order: u32, row: f32, col: f32| {
+            (0..order.max(1)).map(move |turn| {
+                let (sin, cos) = (TAU * turn as f32 / order.max(1) as f32).sin_cos();
+                (row * cos + col * sin, col * cos - row * sin)
+            })
+        };
+        match *self {
+            Symmetry::Mirror => vec![(row, col), (row, -col)],
+            Symmetry::Rotational(order) => rotations(order, row, col).collect(),
+            Symmetry::Dihedral(order) => rotations(order, row, col).chain(rotations(order, row, -col)).collect(),
+        }
+    }
+
+    /// Cells of a universe of `dimensions` the cell at `position` is mapped
+    /// to, itself first, leaving out those falling outside of the universe
+    pub fn orbit(&self, position: Position, dimensions: Position) -> Vec<Position> {
+        let center = ((dimensions.row as f32 - 1.0) / 2.0, (dimensions.col as f32 - 1.0) / 2.0);
+        let (row, col) = (position.row as f32 - center.0, position.col as f32 - center.1);
+        self.images(row, col)
+            .into_iter()
+            .filter_map(|(row, col)| {
+                let (row, col) = ((row + center.0).round(), (col + center.1).round());
+                let inside = row >= 0.0 && col >= 0.0 && row < dimensions.row as f32 && col < dimensions.col as f32;
+                inside.then_some(Position { row: row as usize, col: col as usize })
+            })
+            .collect()
+    }
+
+    /// Empty universe with `n` random cells, drawn from `rng`, and their
+    /// images holding A and B
+    pub fn seed_universe<T: Float, R: Rng + ?Sized>(
+        &self,
+        dimensions: &Position,
+        n: usize,
+        rng: &mut R,
+    ) -> (Universe<T>, ColoredMap) {
+        let mut universe: Universe<T> = vec![vec![Cell::empty(); dimensions.col]; dimensions.row];
+        let mut colored_map: ColoredMap = vec![vec![0.0; dimensions.col]; dimensions.row];
+        if dimensions.row == 0 || dimensions.col == 0 {
+            return (universe, colored_map);
+        }
+        let seed = Cell { a: T::from_f32(1.0), b: T::from_f32(1.0) };
+        for _ in 0..n {
+            let position = Position { row: rng.gen_range(0..dimensions.row), col: rng.gen_range(0..dimensions.col) };
+            for image in self.orbit(position, *dimensions) {
+                universe[image.row][image.col] = seed;
+                colored_map[image.row][image.col] = color_cell(&seed);
+            }
+        }
+        (universe, colored_map)
+    }
+
+    /// Give every cell of `universe` the average of its orbit
+    /// The orbits are computed on every call, see `Orbits` to keep them
+    pub fn project<T: Float>(&self, universe: &mut Universe<T>) {
+        let dimensions = Position { row: universe.len(), col: universe.first().map_or(0, Vec::len) };
+        Orbits::new(*self, dimensions).project(universe);
+    }
+}
+
+/// Orbits of the cells of a universe of some dimensions, computed once for
+/// the projections of every evolution
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orbits {
+    symmetry: Symmetry,
+    dimensions: Position,
+    /// Index in `images` of the first image of every cell, row by row, and
+    /// the number of images last
+    starts: Vec<usize>,
+    /// Images of every cell, as their index row by row
+    images: Vec<usize>,
+}
+
+impl Orbits {
+    pub fn new(symmetry: Symmetry, dimensions: Position) -> Orbits {
+        let cells = dimensions.row * dimensions.col;
+        let mut starts = Vec::with_capacity(cells + 1);
+        let mut images = Vec::with_capacity(cells * symmetry.order());
+        for row in 0..dimensions.row {
+            for col in 0..dimensions.col {
+                starts.push(images.len());
+                let orbit = symmetry.orbit(Position { row, col }, dimensions);
+                images.extend(orbit.into_iter().map(|image| image.row * dimensions.col + image.col));
+            }
+        }
+        starts.push(images.len());
+        Orbits { symmetry, dimensions, starts, images }
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    pub fn dimensions(&self) -> Position {
+        self.dimensions
+    }
+
+    /// Cells the cell at `position` is mapped to, as `Symmetry::orbit` gives
+    /// them
+    pub fn orbit(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        let index = position.row * self.dimensions.col + position.col;
+        let cols = self.dimensions.col;
+        self.images[self.starts[index]..self.starts[index + 1]]
+            .iter()
+            .map(move |image| Position { row: image / cols, col: image % cols })
+    }
+
+    /// Give every cell of `universe`, which has the dimensions of the
+    /// orbits, the average of its orbit
+    pub fn project<T: Float>(&self, universe: &mut Universe<T>) {
+        let cols = self.dimensions.col;
+        let cells: Vec<Cell<T>> = universe.iter().flatten().copied().collect();
+        for (index, window) in self.starts.windows(2).enumerate() {
+            let orbit = &self.images[window[0]..window[1]];
+            let (a, b) = orbit.iter().fold((0.0, 0.0), |(a, b), image| {
+                let cell = cells[*image];
+                (a + cell.a.to_f64(), b + cell.b.to_f64())
+            });
+            let count = orbit.len() as f64;
+            universe[index / cols][index % cols] = Cell { a: T::from_f64(a / count), b: T::from_f64(b / count) };
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Symmetry::Mirror => write!(f, "mirror"),
+            Symmetry::Rotational(order) => write!(f, "rotational:{order}"),
+            Symmetry::Dihedral(order) => write!(f, "dihedral:{order}"),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+/// Tileable textures
+/// A pattern grown on a universe with periodic edges has no seams, so an
+/// image of it sampled periodically can be repeated side by side, e.g. as an
+/// organic texture in a game. A texture runs a periodic simulation until its
+/// pattern converges, then samples the color map bilinearly across the
+/// edges into a square image whose side is a power of two, averaging several
+/// samples per pixel to smooth it. A batch generates textures of several
+/// patterns from several seeds in parallel
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::sync::Mutex;
+
+use image::{Rgb, RgbImage};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::Colormap;
+use crate::parallel::run_jobs;
+use crate::{
+    initialize_universe_with_rng, Boundary, ColoredMap, Parameters, Position, Simulation, SimulationError,
+};
+
+/// Generations between two checks of the convergence of a texture
+pub const CONVERGENCE_INTERVAL: i32 = 100;
+
+/// Settings of a texture
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextureConfig {
+    /// Number of cells on each side of the universe; the fewer, the larger
+    /// the features of the pattern on the texture
+    pub cells: usize,
+    /// Number of pixels on each side of the texture, a power of two
+    pub size: u32,
+    /// Number of samples on each side of a pixel, averaged
+    pub supersampling: u32,
+    /// Largest number of evolutions, fewer if the pattern converges before
+    pub steps: i32,
+    /// Number of random cells starting with A and B present
+    pub initial_cells: usize,
+    pub colormap: Colormap,
+}
+
+impl Default for TextureConfig {
+    fn default() -> Self {
+        TextureConfig { cells: 256, size: 512, supersampling: 2, steps: 20000, initial_cells: 20, colormap: Colormap::default() }
+    }
+}
+
+impl TextureConfig {
+    /// Check that the texture can be generated
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if !self.size.is_power_of_two() {
+            return Err(SimulationError::InvalidTexture(format!(
+                "the size of a texture must be a power of two, found {}",
+                self.size
+            )));
+        }
+        if self.cells == 0 {
+            return Err(SimulationError::InvalidTexture("a texture needs at least one cell".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Generated texture
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub image: RgbImage,
+    /// Number of evolutions computed
+    pub generation: i32,
+    /// Whether the pattern converged before the last evolution allowed
+    pub converged: bool,
+}
+
+/// Grow the pattern of `parameters` from random cells drawn from `seed` and
+/// sample it into a texture
+pub fn generate_texture(parameters: Parameters, config: &TextureConfig, seed: u64) -> Result<Texture, SimulationError> {
+    config.validate()?;
+    let dimensions = Position { row: config.cells, col: config.cells };
+    let (universe, _) = initialize_universe_with_rng(&dimensions, config.initial_cells, &mut StdRng::seed_from_u64(seed));
+    let mut simulation: Simulation =
+        Simulation::new(parameters, dimensions, universe)?.with_boundary(Boundary::Periodic);
+    let converged = simulation.run_until_converged(config.steps, CONVERGENCE_INTERVAL);
+    if let Some(violation) = simulation.violation() {
+        return Err(SimulationError::OutOfBounds(*violation));
+    }
+
+    Ok(Texture {
+        image: tileable_image(simulation.colored_map(), config.size, config.supersampling, config.colormap),
+        generation: simulation.generation(),
+        converged,
+    })
+}
+
+/// Image of `size` by `size` pixels of the color map, joining its opposite
+/// edges
+/// Each pixel averages `supersampling` by `supersampling` samples of the
+/// color map interpolated bilinearly, wrapping around the edges, so the image
+/// tiles seamlessly if the color map came from a periodic universe
+pub fn tileable_image(colored_map: &ColoredMap, size: u32, supersampling: u32, colormap: Colormap) -> RgbImage {
+    let rows = colored_map.len();
+    let cols = colored_map.first().map_or(0, Vec::len);
+    if rows == 0 || cols == 0 {
+        return RgbImage::new(size, size);
+    }
+    let samples = supersampling.max(1);
+
+    // Value at the point (`x`, `y`) of the color map, in cells, with the
+    // center of the cell at `row`, `col` at (`col` + 0.5, `row` + 0.5)
+    let sample = |x: f32, y: f32| {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (col, row) = (x.floor(), y.floor());
+        let (tx, ty) = (x - col, y - row);
+        let col = (col as isize).rem_euclid(cols as isize) as usize;
+        let row = (row as isize).rem_euclid(rows as isize) as usize;
+        let (next_col, next_row) = ((col + 1) % cols, (row + 1) % rows);
+        let lerp = |from: f32, to: f32, t: f32| from + (to - from) * t;
+        lerp(
+            lerp(colored_map[row][col], colored_map[row][next_col], tx),
+            lerp(colored_map[next_row][col], colored_map[next_row][next_col], tx),
+            ty,
+        )
+    };
+
+    let scale = (cols as f32 / size as f32, rows as f32 / size as f32);
+    RgbImage::from_fn(size, size, |px, py| {
+        let mut sum = 0.0;
+        for i in 0..samples {
+            for j in 0..samples {
+                let x = (px as f32 + (j as f32 + 0.5) / samples as f32) * scale.0;
+                let y = (py as f32 + (i as f32 + 0.5) / samples as f32) * scale.1;
+                sum += sample(x, y);
+            }
+        }
+        Rgb(colormap.color(sum / (samples * samples) as f32))
+    })
+}
+
+/// Textures of several patterns, each grown from several seeds
+#[derive(Debug, Clone)]
+pub struct TextureBatch {
+    /// Names and parameters of the patterns
+    pub patterns: Vec<(String, Parameters)>,
+    /// Seed of the first texture of every pattern, the next ones take the
+    /// following seeds
+    pub seed: u64,
+    /// Number of textures of every pattern
+    pub count: usize,
+    pub texture: TextureConfig,
+    /// Number of textures generated at the same time, 0 for one per
+    /// processor
+    pub threads: usize,
+}
+
+/// Outcome of one texture of a batch
+#[derive(Debug, Clone)]
+pub struct BatchTexture {
+    /// Index of the texture in the order of `TextureBatch::jobs`
+    pub index: usize,
+    /// Name of the pattern
+    pub name: String,
+    pub seed: u64,
+    pub generation: i32,
+    pub converged: bool,
+    /// Why the texture could not be generated, e.g. a concentration out of
+    /// bounds
+    pub error: Option<String>,
+}
+
+impl TextureBatch {
+    /// Index of the pattern and seed of every texture
+    /// The seeds change fastest, wrapping around after `u64::MAX`
+    pub fn jobs(&self) -> Vec<(usize, u64)> {
+        (0..self.patterns.len())
+            .flat_map(|pattern| (0..self.count as u64).map(move |offset| (pattern, self.seed.wrapping_add(offset))))
+            .collect()
+    }
+
+    /// Generate all the textures, in parallel, and return their outcomes in
+    /// the order of `jobs`
+    /// `on_texture` is called, from the thread that generated it, with the
+    /// outcome and the image of every texture generated. Fails only if the
+    /// settings of the textures are invalid
+    pub fn run<F>(&self, on_texture: F) -> Result<Vec<BatchTexture>, SimulationError>
+    where
+        F: Fn(&BatchTexture, &RgbImage) + Sync,
+    {
+        self.texture.validate()?;
+        Ok(run_jobs(&self.jobs(), self.threads, |index, &(pattern, seed)| {
+            let (name, parameters) = &self.patterns[pattern];
+            let mut outcome =
+                BatchTexture { index, name: name.clone(), seed, generation: 0, converged: false, error: None };
+            match generate_texture(*parameters, &self.texture, seed) {
+                Ok(texture) => {
+                    outcome.generation = texture.generation;
+                    outcome.converged = texture.converged;
+                    on_texture(&outcome, &texture.image);
+                }
+                Err(error) => outcome.error = Some(error.to_string()),
+            }
+            outcome
+        }))
+    }
+
+    /// Generate all the textures and write them to `directory` as
+    /// `<name>_<seed>.png`
+    #[cfg(feature = "fs")]
+    pub fn write(&self, directory: &Path) -> Result<Vec<BatchTexture>, SimulationError> {
+        self.texture.validate()?;
+        fs::create_dir_all(directory)?;
+        let failed = Mutex::new(None);
+        let textures = self.run(|texture, image| {
+            let path = directory.join(format!("{}_{}.png", texture.name, texture.seed));
+            if let Err(error) = image.save(&path) {
+                failed.lock().unwrap().get_or_insert(SimulationError::from(error));
+            }
+        })?;
+        match failed.into_inner().unwrap() {
+            Some(error) => Err(error),
+            None => Ok(textures),
+        }
+    }
+}
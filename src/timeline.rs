@@ -0,0 +1,100 @@
+/// Parameter timelines
+/// A timeline makes parameters drift during a run: each parameter can have a
+/// track of keyframes giving its value at some generations, interpolated
+/// linearly in between. For instance `k` ramping from 0.05 to 0.065 over
+/// 5000 generations is written in RON
+///
+/// ```ron
+/// timeline: (
+///     k: [(generation: 0, value: 0.05), (generation: 5000, value: 0.065)],
+/// ),
+/// ```
+use serde::{Deserialize, Serialize};
+
+use crate::{Parameters, ParametersError};
+
+/// Value of a parameter at a generation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub generation: i32,
+    pub value: f32,
+}
+
+/// Keyframes of the parameters; parameters without any keep their value
+/// Before the first keyframe of a track its parameter has the value of that
+/// keyframe, after the last one the value of the last one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeline {
+    pub d_a: Vec<Keyframe>,
+    pub d_b: Vec<Keyframe>,
+    pub f: Vec<Keyframe>,
+    pub k: Vec<Keyframe>,
+    pub r: Vec<Keyframe>,
+}
+
+/// Value of the track `keyframes` at `generation`, None for an empty track
+fn value_at(keyframes: &[Keyframe], generation: i32) -> Option<f32> {
+    let after = keyframes.partition_point(|keyframe| keyframe.generation <= generation);
+    match (after.checked_sub(1).map(|before| keyframes[before]), keyframes.get(after).copied()) {
+        (Some(before), Some(next)) => {
+            let t = (generation - before.generation) as f32 / (next.generation - before.generation) as f32;
+            Some(before.value + (next.value - before.value) * t)
+        }
+        (Some(keyframe), None) | (None, Some(keyframe)) => Some(keyframe.value),
+        (None, None) => None,
+    }
+}
+
+impl Timeline {
+    /// Whether no parameter has keyframes
+    pub fn is_empty(&self) -> bool {
+        self.tracks().iter().all(|track| track.is_empty())
+    }
+
+    fn tracks(&self) -> [&Vec<Keyframe>; 5] {
+        [&self.d_a, &self.d_b, &self.f, &self.k, &self.r]
+    }
+
+    /// Generations of the keyframes of every track
+    pub fn generations(&self) -> impl Iterator<Item = i32> + '_ {
+        self.tracks().into_iter().flatten().map(|keyframe| keyframe.generation)
+    }
+
+    fn tracks_mut(&mut self) -> [&mut Vec<Keyframe>; 5] {
+        [&mut self.d_a, &mut self.d_b, &mut self.f, &mut self.k, &mut self.r]
+    }
+
+    /// Same timeline with the keyframes of every track sorted by generation
+    pub fn sorted(mut self) -> Timeline {
+        for track in self.tracks_mut() {
+            track.sort_by_key(|keyframe| keyframe.generation);
+        }
+        self
+    }
+
+    /// Parameters at `generation`: those of `base`, with the value of their
+    /// track for the parameters that have one
+    /// The tracks must be sorted, see `sorted`
+    pub fn parameters_at(&self, generation: i32, base: Parameters) -> Parameters {
+        let value = |track: &[Keyframe], base: f32| value_at(track, generation).unwrap_or(base);
+        Parameters {
+            d_a: value(&self.d_a, base.d_a),
+            d_b: value(&self.d_b, base.d_b),
+            f: value(&self.f, base.f),
+            k: value(&self.k, base.k),
+            r: value(&self.r, base.r),
+            ..base
+        }
+    }
+
+    /// Check that the parameters are valid at every keyframe, starting from
+    /// `base`, and so in between since they change linearly
+    /// The tracks must be sorted, see `sorted`
+    pub fn validate(&self, base: Parameters) -> Result<(), ParametersError> {
+        self.tracks()
+            .iter()
+            .flat_map(|track| track.iter())
+            .try_for_each(|keyframe| self.parameters_at(keyframe.generation, base).validate())
+    }
+}
@@ -0,0 +1,228 @@
+/// Exports triggered by events
+/// Headless runs can save a snapshot, the color map or the statistics when
+/// something happens in the universe rather than at fixed intervals: once it
+/// converged, at chosen generations, or whenever the kind of its pattern
+/// changes, see `classify`. Each trigger binds one event to its exports,
+/// written to its directory as `<event>_<generation>.bin`, `.png` and
+/// `.csv`, e.g. `converged_00004200.png` or `labyrinth_00001500.bin`. They
+/// are listed in the output of a configuration file:
+///
+/// ```ron
+/// triggers: [
+///     (on: converged(interval: 100), export: [snapshot, image]),
+///     (on: milestones(generations: [1000, 5000]), export: [image, stats]),
+///     (on: pattern_change(interval: 250), export: [image], directory: "kinds"),
+/// ]
+/// ```
+///
+/// Writing the exports requires the `fs` feature
+#[cfg(feature = "fs")]
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::classify::{classify, PatternKind};
+#[cfg(feature = "fs")]
+use crate::colormap::Colormap;
+#[cfg(feature = "fs")]
+use crate::export::save_colored_map;
+#[cfg(feature = "fs")]
+use crate::logger::{StatsLogConfig, StatsRecord, CSV_HEADER};
+#[cfg(feature = "fs")]
+use crate::snapshot::Snapshot;
+use crate::stats::{Stats, CONVERGENCE_TOLERANCE};
+use crate::{Float, Simulation, SimulationError};
+
+/// Event of a run firing a trigger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TriggerEvent {
+    /// The statistics changed by less than `stats::CONVERGENCE_TOLERANCE`
+    /// per generation since the previous check, `interval` generations
+    /// earlier; fires once
+    Converged { interval: i32 },
+    /// The generation reached one of `generations`
+    Milestones { generations: Vec<i32> },
+    /// The kind of the pattern, checked every `interval` generations, is not
+    /// the one of the previous check
+    PatternChange { interval: i32 },
+}
+
+/// What a trigger saves when its event fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportAction {
+    /// Snapshot of the universe, to resume or analyze it
+    Snapshot,
+    /// Image of the color map
+    Image,
+    /// CSV file of one line of statistics, see `logger`
+    Stats,
+}
+
+/// Event bound to the exports it triggers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportTrigger {
+    pub on: TriggerEvent,
+    pub export: Vec<ExportAction>,
+    /// Directory where the exports are written
+    #[serde(default = "default_directory")]
+    pub directory: PathBuf,
+}
+
+fn default_directory() -> PathBuf {
+    PathBuf::from("exports")
+}
+
+impl ExportTrigger {
+    /// Fails if the event is never checked or the trigger exports nothing
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let error = |message: String| Err(SimulationError::InvalidTrigger(message));
+        match &self.on {
+            TriggerEvent::Converged { interval } | TriggerEvent::PatternChange { interval } if *interval < 1 => {
+                return error(format!("the interval must be at least 1, not {interval}"));
+            }
+            TriggerEvent::Milestones { generations } if generations.is_empty() => {
+                return error("milestones need at least one generation".to_string());
+            }
+            _ => {}
+        }
+        if self.export.is_empty() {
+            return error("a trigger needs something to export".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Event that fired after an evolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FiredTrigger {
+    /// Index of the trigger in the list given to `TriggerWatcher::new`
+    pub trigger: usize,
+    /// Name of the event, starting the names of the files: `converged`,
+    /// `milestone`, or the name of the new kind of pattern
+    pub name: String,
+    pub generation: i32,
+}
+
+/// What a trigger remembers of the previous checks
+#[derive(Debug, Clone)]
+enum TriggerState {
+    Converged { previous: Option<Stats>, fired: bool },
+    Milestones,
+    PatternChange { previous: Option<PatternKind> },
+}
+
+/// Watcher of the events of a run, checked after every evolution
+#[derive(Debug, Clone)]
+pub struct TriggerWatcher {
+    triggers: Vec<ExportTrigger>,
+    states: Vec<TriggerState>,
+}
+
+impl TriggerWatcher {
+    /// Watcher of `triggers`, none of which has fired yet
+    /// Fails if one of them is invalid
+    pub fn new(triggers: Vec<ExportTrigger>) -> Result<TriggerWatcher, SimulationError> {
+        for trigger in &triggers {
+            trigger.validate()?;
+        }
+        let states = triggers
+            .iter()
+            .map(|trigger| match trigger.on {
+                TriggerEvent::Converged { .. } => TriggerState::Converged { previous: None, fired: false },
+                TriggerEvent::Milestones { .. } => TriggerState::Milestones,
+                TriggerEvent::PatternChange { .. } => TriggerState::PatternChange { previous: None },
+            })
+            .collect();
+        Ok(TriggerWatcher { triggers, states })
+    }
+
+    pub fn triggers(&self) -> &[ExportTrigger] {
+        &self.triggers
+    }
+
+    /// Events fired by the generation `simulation` just reached
+    pub fn check<T: Float>(&mut self, simulation: &Simulation<T>) -> Vec<FiredTrigger> {
+        let generation = simulation.generation();
+        let mut fired = Vec::new();
+        for (index, (trigger, state)) in self.triggers.iter().zip(&mut self.states).enumerate() {
+            let name = match (&trigger.on, state) {
+                (TriggerEvent::Converged { interval }, TriggerState::Converged { previous, fired }) => {
+                    if *fired || generation % interval != 0 {
+                        continue;
+                    }
+                    let current = simulation.stats();
+                    let converged = previous
+                        .replace(current)
+                        .is_some_and(|previous| current.change_since(&previous, *interval) < CONVERGENCE_TOLERANCE);
+                    if !converged {
+                        continue;
+                    }
+                    *fired = true;
+                    "converged".to_string()
+                }
+                (TriggerEvent::Milestones { generations }, TriggerState::Milestones) => {
+                    if !generations.contains(&generation) {
+                        continue;
+                    }
+                    "milestone".to_string()
+                }
+                (TriggerEvent::PatternChange { interval }, TriggerState::PatternChange { previous }) => {
+                    if generation % interval != 0 {
+                        continue;
+                    }
+                    let kind = classify(simulation.universe()).kind;
+                    if previous.replace(kind).is_none_or(|previous| previous == kind) {
+                        continue;
+                    }
+                    kind.name().to_string()
+                }
+                _ => unreachable!("the state of a trigger follows its event"),
+            };
+            fired.push(FiredTrigger { trigger: index, name, generation });
+        }
+        fired
+    }
+
+    /// Write the exports of `fired` for the current universe of
+    /// `simulation`, the images with `colormap`, creating the directory of
+    /// the trigger if needed. Returns the paths of the files written
+    #[cfg(feature = "fs")]
+    pub fn export(
+        &self,
+        fired: &FiredTrigger,
+        simulation: &Simulation,
+        colormap: Colormap,
+    ) -> Result<Vec<PathBuf>, SimulationError> {
+        let trigger = &self.triggers[fired.trigger];
+        fs::create_dir_all(&trigger.directory)?;
+        let stem = format!("{}_{:08}", fired.name, fired.generation);
+        let mut written = Vec::new();
+        for action in &trigger.export {
+            let path = match action {
+                ExportAction::Snapshot => {
+                    let path = trigger.directory.join(format!("{stem}.bin"));
+                    Snapshot::of(simulation).save(&path)?;
+                    path
+                }
+                ExportAction::Image => {
+                    let path = trigger.directory.join(format!("{stem}.png"));
+                    save_colored_map(simulation.colored_map(), colormap, &path)?;
+                    path
+                }
+                ExportAction::Stats => {
+                    let path = trigger.directory.join(format!("{stem}.csv"));
+                    let threshold = StatsLogConfig::default().active_threshold;
+                    let record = StatsRecord::of(simulation.generation(), simulation.universe(), threshold, true);
+                    fs::write(&path, format!("{CSV_HEADER}\n{}\n", record.csv_line()))?;
+                    path
+                }
+            };
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,135 @@
+/// Incremental uploads of the textures of the window
+/// Bevy uploads the whole image of a texture again whenever the image is
+/// changed, megabytes every frame for a large universe even once most of
+/// its pattern stopped changing. The color maps are instead drawn by
+/// queueing the bytes of the regions that changed, see
+/// `render::dirty_regions`, which are written straight into the textures on
+/// the GPU during the frame; the images themselves only create the textures.
+/// Textures in the `R8` format, see `render::PixelFormat`, hold the values
+/// of the color map alone and are drawn by `ColormapMaterial`, which colors
+/// them from a palette
+use std::num::NonZeroU32;
+use std::ops::Range;
+
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, ShaderRef, TextureAspect,
+};
+use bevy::render::renderer::RenderQueue;
+use bevy::render::{Extract, RenderApp, RenderStage};
+use bevy::sprite::{Material2d, Material2dPlugin};
+
+/// Shader of `ColormapMaterial`, in the assets
+const COLORMAP_SHADER: &str = "shaders/colormap.wgsl";
+
+/// Bytes of a region of a texture, written during the frame
+#[derive(Debug, Clone)]
+pub struct TextureUpload {
+    pub texture: Handle<Image>,
+    /// Column and row of the top left pixel of the region
+    pub origin: [u32; 2],
+    /// Width and height of the region, in pixels
+    pub size: [u32; 2],
+    /// Pixels of the region, row by row
+    pub data: Vec<u8>,
+}
+
+/// Uploads queued during the frame
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TextureUploads(pub Vec<TextureUpload>);
+
+impl TextureUploads {
+    /// Queue `data`, the pixels of the rows and columns of `region` of
+    /// `texture`
+    pub fn push(&mut self, texture: &Handle<Image>, region: &(Range<usize>, Range<usize>), data: Vec<u8>) {
+        let (rows, cols) = region;
+        self.0.push(TextureUpload {
+            texture: texture.clone(),
+            origin: [cols.start as u32, rows.start as u32],
+            size: [cols.len() as u32, rows.len() as u32],
+            data,
+        });
+    }
+}
+
+/// Material drawing an `R8` texture of the values of a color map with the
+/// colors of a palette
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "3f0b6c1e-8d2a-4b7f-a3c5-9e4d2f1a7b60"]
+pub struct ColormapMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    pub values: Handle<Image>,
+    /// Texture of 256 by 1 pixels, the colors of the values, see
+    /// `render::palette`
+    #[texture(2)]
+    #[sampler(3)]
+    pub palette: Handle<Image>,
+}
+
+impl Material2d for ColormapMaterial {
+    fn fragment_shader() -> ShaderRef {
+        COLORMAP_SHADER.into()
+    }
+}
+
+/// Plugin writing the `TextureUploads` of every frame and drawing the
+/// `ColormapMaterial`
+pub struct UploadPlugin;
+
+impl Plugin for UploadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextureUploads>()
+            .add_plugin(Material2dPlugin::<ColormapMaterial>::default())
+            .add_system_to_stage(CoreStage::First, clear_uploads);
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<TextureUploads>()
+                .add_system_to_stage(RenderStage::Extract, extract_uploads)
+                .add_system_to_stage(RenderStage::Queue, write_uploads);
+        }
+    }
+}
+
+/// Forget the uploads of the previous frame, already extracted
+fn clear_uploads(mut uploads: ResMut<TextureUploads>) {
+    if !uploads.0.is_empty() {
+        uploads.0.clear();
+    }
+}
+
+/// Take the uploads of the frame into the render world
+fn extract_uploads(uploads: Extract<Res<TextureUploads>>, mut queued: ResMut<TextureUploads>) {
+    queued.0.extend(uploads.0.iter().cloned());
+}
+
+/// Write the uploads into their textures, once the textures of the images
+/// changed during the frame are created
+/// Uploads that do not fit their texture, e.g. queued before it was
+/// resized, are dropped
+fn write_uploads(mut uploads: ResMut<TextureUploads>, images: Res<RenderAssets<Image>>, queue: Res<RenderQueue>) {
+    for upload in uploads.0.drain(..) {
+        let Some(image) = images.get(&upload.texture) else {
+            continue;
+        };
+        let ([x, y], [width, height]) = (upload.origin, upload.size);
+        let bytes_per_row = width * image.texture_format.describe().block_size as u32;
+        let fits = x + width <= image.size.x as u32 && y + height <= image.size.y as u32;
+        if width == 0 || height == 0 || !fits || upload.data.len() != (bytes_per_row * height) as usize {
+            continue;
+        }
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &image.texture,
+                mip_level: 0,
+                origin: Origin3d { x, y, z: 0 },
+                aspect: TextureAspect::All,
+            },
+            &upload.data,
+            ImageDataLayout { offset: 0, bytes_per_row: NonZeroU32::new(bytes_per_row), rows_per_image: None },
+            Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Spectra and dominant wavelengths of sinusoidal patterns, see `analysis`
+use std::f64::consts::TAU;
+
+use ca_turing_pattern::analysis::{dominant_wavelength, Spectrum};
+use ca_turing_pattern::*;
+
+const DIMENSIONS: Position = Position { row: 48, col: 64 };
+
+/// Universe whose B is `b` of the column and row
+fn universe(b: impl Fn(f64, f64) -> f64) -> Universe {
+    (0..DIMENSIONS.row)
+        .map(|row| (0..DIMENSIONS.col).map(|col| Cell { a: 0.5, b: b(col as f64, row as f64) as f32 }).collect())
+        .collect()
+}
+
+/// Stripes along the rows, `wavelength` cells apart
+fn stripes(wavelength: f64) -> Universe {
+    universe(|x, _| 0.5 + 0.4 * (x * TAU / wavelength).sin())
+}
+
+#[test]
+fn stripes_have_their_wavelength_and_a_direction() {
+    for wavelength in [8.0, 16.0] {
+        let found = dominant_wavelength(&stripes(wavelength)).unwrap();
+        assert!((found.wavelength - wavelength).abs() < 1e-6, "{found:?}");
+        assert!((found.wavenumber - 1.0 / wavelength).abs() < 1e-9, "{found:?}");
+        assert!(found.power > 0.99, "{found:?}");
+        assert!(found.isotropy < 0.01, "{found:?}");
+    }
+}
+
+#[test]
+fn sums_of_perpendicular_waves_have_no_direction() {
+    let spots = universe(|x, y| 0.5 + 0.2 * (x * TAU / 8.0).cos() + 0.2 * (y * TAU / 8.0).cos());
+    let found = dominant_wavelength(&spots).unwrap();
+    assert!((found.wavelength - 8.0).abs() < 1e-6, "{found:?}");
+    assert!(found.isotropy > 0.99, "{found:?}");
+}
+
+#[test]
+fn spectra_peak_at_the_frequency_of_the_wave() {
+    // 4 cycles along the columns, and the mean taken out
+    let spectrum = Spectrum::of(&stripes(16.0));
+    assert_eq!(spectrum.dimensions, DIMENSIONS);
+    assert!(spectrum.power[0][0] < 1e-6);
+    let peak = spectrum.power[0][4];
+    assert!(peak > 0.0 && (spectrum.power[0][60] - peak).abs() < 1e-6 * peak);
+    let rest: f64 = spectrum.power.iter().flatten().sum::<f64>() - 2.0 * peak;
+    assert!(rest < 1e-6 * peak, "{rest} besides {peak}");
+    assert_eq!(spectrum.radial_profile().iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0, 4);
+}
+
+#[test]
+fn uniform_universes_have_no_wavelength() {
+    assert_eq!(dominant_wavelength(&universe(|_, _| 0.3)), None);
+    assert_eq!(dominant_wavelength::<f32>(&Vec::new()), None);
+}
@@ -0,0 +1,100 @@
+//! Round trips of the animated exports through the decoders of `image`,
+//! comparing the decoded pixels with the colors of the captured color maps,
+//! and the intervals the frames are captured at
+use std::io::Cursor;
+
+use ca_turing_pattern::animation::{Animation, AnimationConfig};
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::*;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, Frame, RgbaImage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+const WIDTH: usize = 131;
+const HEIGHT: usize = 97;
+
+/// Color maps of different kinds: noise, filling the codes of the LZW
+/// streams of GIF more than once, a gradient, one value and two
+fn colored_maps() -> Vec<ColoredMap> {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let map = |value: &mut dyn FnMut(usize, usize) -> f32| {
+        (0..HEIGHT).map(|row| (0..WIDTH).map(|col| value(row, col)).collect()).collect()
+    };
+    vec![
+        map(&mut |_, _| rng.gen()),
+        map(&mut |row, col| (row * WIDTH + col) as f32 / (WIDTH * HEIGHT) as f32),
+        map(&mut |_, _| 0.3),
+        map(&mut |row, col| ((row + col) % 2) as f32),
+    ]
+}
+
+/// Encoded animation of the color maps of `colored_maps`
+fn encode(extension: &str, colormap: Colormap) -> Vec<u8> {
+    let config = AnimationConfig { path: format!("animation.{extension}").into(), interval: 1, delay: 40 };
+    let mut animation = Animation::new(config, colormap).unwrap();
+    for colored_map in colored_maps() {
+        animation.push(&colored_map).unwrap();
+    }
+    let mut bytes = Vec::new();
+    animation.encode(&mut bytes).unwrap();
+    bytes
+}
+
+/// Check that `frames` show the color maps, their values being mapped
+/// through `color`
+fn assert_frames(frames: Vec<Frame>, color: impl Fn(f32) -> [u8; 3]) {
+    let colored_maps = colored_maps();
+    assert_eq!(frames.len(), colored_maps.len());
+    for (index, (frame, colored_map)) in frames.into_iter().zip(colored_maps).enumerate() {
+        assert_eq!(frame.delay().numer_denom_ms(), (40, 1), "delay of frame {index}");
+        let buffer: RgbaImage = frame.into_buffer();
+        assert_eq!(buffer.dimensions(), (WIDTH as u32, HEIGHT as u32));
+        for (row, values) in colored_map.iter().enumerate() {
+            for (col, value) in values.iter().enumerate() {
+                let [red, green, blue] = color(*value);
+                let pixel = buffer.get_pixel(col as u32, row as u32).0;
+                assert_eq!(pixel, [red, green, blue, 255], "pixel ({row}, {col}) of frame {index}");
+            }
+        }
+    }
+}
+
+#[test]
+fn gif_frames_decode_to_the_sampled_colors() {
+    let colormap = Colormap::Viridis;
+    let bytes = encode("gif", colormap);
+    let frames = GifDecoder::new(Cursor::new(bytes)).unwrap().into_frames().collect_frames().unwrap();
+    assert_frames(frames, |value| colormap.color((value.clamp(0.0, 1.0) * 255.0).round() / 255.0));
+}
+
+#[test]
+fn apng_frames_decode_to_the_colors() {
+    let colormap = Colormap::Magma;
+    let bytes = encode("png", colormap);
+    let decoder = PngDecoder::new(Cursor::new(bytes)).unwrap();
+    assert!(decoder.is_apng());
+    let frames = decoder.apng().into_frames().collect_frames().unwrap();
+    assert_frames(frames, |value| colormap.color(value));
+}
+
+#[test]
+fn webp_frames_decode_to_the_colors() {
+    let colormap = Colormap::Plasma;
+    let bytes = encode("webp", colormap);
+    let frames = WebPDecoder::new(Cursor::new(bytes)).unwrap().into_frames().collect_frames().unwrap();
+    assert_frames(frames, |value| colormap.color(value));
+}
+
+#[test]
+fn frame_intervals_below_one_are_refused() {
+    for interval in [0, -2] {
+        let config = AnimationConfig { interval, ..AnimationConfig::default() };
+        assert!(matches!(config.validate(), Err(SimulationError::InvalidInterval(_))));
+        assert!(matches!(Animation::new(config, Colormap::Gray), Err(SimulationError::InvalidInterval(_))));
+    }
+    let animation = Animation::new(AnimationConfig { interval: 3, ..AnimationConfig::default() }, Colormap::Gray).unwrap();
+    assert_eq!((1..=9).filter(|&generation| animation.is_due(generation)).collect::<Vec<_>>(), [3, 6, 9]);
+}
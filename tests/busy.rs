@@ -0,0 +1,38 @@
+//! Reads and edits of a simulation whose universe is taken by `begin_step`
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const DIMENSIONS: Position = Position { row: 12, col: 9 };
+
+fn simulation() -> Simulation {
+    Simulation::random(Parameters::default(), DIMENSIONS, 6, &mut ChaCha8Rng::seed_from_u64(3)).unwrap()
+}
+
+#[test]
+fn busy_simulations_sample_empty_cells() {
+    let mut simulation = simulation();
+    let step = simulation.begin_step();
+    assert_eq!(simulation.sample(4.0, 3.5), Cell::empty());
+    simulation.finish_step(step.compute());
+    assert_eq!(simulation.sample(4.0, 3.0), simulation.universe()[3][4]);
+}
+
+#[test]
+fn busy_simulations_refuse_edits() {
+    let mut simulation = simulation();
+    let position = Position { row: 2, col: 7 };
+    let cell = Cell { a: 0.25, b: 0.75 };
+    let step = simulation.begin_step();
+    assert!(matches!(simulation.set_cell(position, cell), Err(SimulationError::Busy)));
+    let resized = Position { row: 6, col: 5 };
+    assert!(matches!(simulation.resize(resized, Resampling::Nearest), Err(SimulationError::Busy)));
+    simulation.finish_step(step.compute());
+    assert_eq!(simulation.dimensions(), DIMENSIONS);
+    assert_ne!(simulation.universe()[position.row][position.col], cell);
+
+    simulation.set_cell(position, cell).unwrap();
+    assert_eq!(simulation.universe()[position.row][position.col], cell);
+    simulation.resize(resized, Resampling::Nearest).unwrap();
+    assert_eq!(simulation.dimensions(), resized);
+}
@@ -0,0 +1,47 @@
+//! NULL pointers and error codes of the C API
+#![cfg(feature = "capi")]
+use std::ptr;
+
+use ca_turing_pattern::capi::*;
+
+#[test]
+fn null_pointers_are_refused() {
+    let parameters = CaParameters { d_a: 1.0, d_b: 0.5, f: 0.055, k: 0.062, r: 1.0 };
+    let mut written = parameters;
+    let mut buffer = [0.0; 4];
+    unsafe {
+        assert_eq!(ca_simulation_set_parameters(ptr::null_mut(), parameters), CaStatus::NullPointer);
+        assert_eq!(ca_simulation_parameters(ptr::null(), &mut written), CaStatus::NullPointer);
+        assert_eq!(ca_simulation_step(ptr::null_mut(), 1), CaStatus::NullPointer);
+        assert_eq!(ca_simulation_generation(ptr::null()), -1);
+        assert_eq!((ca_simulation_rows(ptr::null()), ca_simulation_cols(ptr::null())), (0, 0));
+        let status = ca_simulation_copy_field(ptr::null(), CA_FIELD_A, buffer.as_mut_ptr(), buffer.len());
+        assert_eq!(status, CaStatus::NullPointer);
+        ca_simulation_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn failures_have_their_own_codes() {
+    unsafe {
+        let simulation = ca_simulation_new(8, 6, 1);
+        assert!(!simulation.is_null());
+        assert_eq!(ca_simulation_step(simulation, 3), CaStatus::Ok);
+        assert_eq!(ca_simulation_generation(simulation), 3);
+
+        let invalid = CaParameters { d_a: -1.0, d_b: 0.5, f: 0.055, k: 0.062, r: 1.0 };
+        assert_eq!(ca_simulation_set_parameters(simulation, invalid), CaStatus::InvalidParameters);
+        let mut parameters = invalid;
+        assert_eq!(ca_simulation_parameters(simulation, ptr::null_mut()), CaStatus::NullPointer);
+        assert_eq!(ca_simulation_parameters(simulation, &mut parameters), CaStatus::Ok);
+        assert_eq!(parameters.d_a, 0.6);
+
+        let mut buffer = vec![0.0; 48];
+        assert_eq!(ca_simulation_copy_field(simulation, CA_FIELD_B, ptr::null_mut(), 48), CaStatus::NullPointer);
+        assert_eq!(ca_simulation_copy_field(simulation, CA_FIELD_B, buffer.as_mut_ptr(), 47), CaStatus::BufferTooSmall);
+        assert_eq!(ca_simulation_copy_field(simulation, CA_FIELD_B, buffer.as_mut_ptr(), 48), CaStatus::Ok);
+        assert_eq!(ca_simulation_copy_field(simulation, 3, buffer.as_mut_ptr(), 48), CaStatus::InvalidField);
+        assert_eq!(ca_simulation_copy_field(simulation, u32::MAX, buffer.as_mut_ptr(), 48), CaStatus::InvalidField);
+        ca_simulation_free(simulation);
+    }
+}
@@ -0,0 +1,84 @@
+//! Checkpoints written by a `Checkpointer`: retention of the latest ones,
+//! writes that never leave a partial checkpoint behind and valid intervals
+#![cfg(feature = "fs")]
+use std::fs;
+use std::path::PathBuf;
+
+use ca_turing_pattern::checkpoint::{latest_checkpoint, list_checkpoints, CheckpointPolicy, Checkpointer};
+use ca_turing_pattern::snapshot::Snapshot;
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Empty directory of the test `name`
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("ca_turing_pattern_checkpoint_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+fn simulation() -> Simulation {
+    let dimensions = Position { row: 8, col: 6 };
+    Simulation::random(Parameters::default(), dimensions, 4, &mut ChaCha8Rng::seed_from_u64(9)).unwrap()
+}
+
+#[test]
+fn only_the_latest_checkpoints_are_kept() {
+    let directory = directory("retention");
+    let policy = CheckpointPolicy { interval: 2, directory: directory.clone(), retention: 2 };
+    let mut checkpointer = Checkpointer::new(policy.clone()).unwrap();
+    let mut simulation = simulation();
+    for _ in 0..7 {
+        simulation.step();
+        if checkpointer.is_due(simulation.generation()) {
+            checkpointer.save(&Snapshot::of(&simulation)).unwrap();
+        }
+    }
+    let checkpoints = list_checkpoints(&directory).unwrap();
+    let names: Vec<_> = checkpoints.iter().map(|path| path.file_name().unwrap().to_owned()).collect();
+    assert_eq!(names, ["checkpoint_00000004.bin", "checkpoint_00000006.bin"]);
+
+    // A new checkpointer counts the checkpoints already written
+    let mut checkpointer = Checkpointer::new(policy).unwrap();
+    simulation.step();
+    let latest = checkpointer.save(&Snapshot::of(&simulation)).unwrap();
+    assert_eq!(list_checkpoints(&directory).unwrap().len(), 2);
+    let resumed = Snapshot::load(&latest).unwrap();
+    assert_eq!((resumed.generation, &resumed.universe), (8, simulation.universe()));
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn partial_writes_are_never_resumed() {
+    let directory = directory("partial");
+    let policy = CheckpointPolicy { interval: 1, directory: directory.clone(), retention: 0 };
+    let mut checkpointer = Checkpointer::new(policy).unwrap();
+    let mut simulation = simulation();
+    simulation.step();
+    let written = checkpointer.save(&Snapshot::of(&simulation)).unwrap();
+
+    // What a write interrupted at generation 2 leaves behind, replaced by the
+    // complete one below
+    fs::write(directory.join(".checkpoint_00000002.bin.tmp"), [1, 2, 3]).unwrap();
+    assert_eq!(latest_checkpoint(&directory).unwrap(), Some(written));
+
+    // Completed writes leave no temporary file
+    simulation.step();
+    let written = checkpointer.save(&Snapshot::of(&simulation)).unwrap();
+    assert_eq!(latest_checkpoint(&directory).unwrap(), Some(written.clone()));
+    assert_eq!(Snapshot::load(&written).unwrap().generation, 2);
+    let entries = fs::read_dir(&directory).unwrap().count();
+    assert_eq!(entries, 2, "the checkpoints of generations 1 and 2");
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn intervals_below_one_are_refused() {
+    let directory = directory("interval");
+    for interval in [0, -3] {
+        let policy = CheckpointPolicy { interval, directory: directory.clone(), retention: 2 };
+        assert!(matches!(policy.validate(), Err(SimulationError::InvalidInterval(_))));
+        assert!(matches!(Checkpointer::new(policy), Err(SimulationError::InvalidInterval(_))));
+    }
+    assert!(!directory.exists());
+}
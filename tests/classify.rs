@@ -0,0 +1,61 @@
+//! Kinds and morphologies of known patterns, see `classify`
+use std::f64::consts::TAU;
+
+use ca_turing_pattern::classify::{classify, Morphology, PatternKind, PATTERN_KIND_NAMES};
+use ca_turing_pattern::*;
+
+const DIMENSIONS: Position = Position { row: 48, col: 64 };
+
+/// Universe whose B is `b` of the column and row
+fn universe(b: impl Fn(f64, f64) -> f64) -> Universe {
+    (0..DIMENSIONS.row)
+        .map(|row| (0..DIMENSIONS.col).map(|col| Cell { a: 0.5, b: b(col as f64, row as f64) as f32 }).collect())
+        .collect()
+}
+
+#[test]
+fn sinusoids_along_one_axis_are_stripes() {
+    let classification = classify(&universe(|x, _| 0.5 + 0.4 * (x * TAU / 8.0).sin()));
+    assert_eq!(classification.kind, PatternKind::Stripes);
+    assert!((classification.wavelength.unwrap().wavelength - 8.0).abs() < 1e-6);
+    // One band per wavelength, crossing the universe
+    assert_eq!(classification.morphology.components, 8);
+    assert_eq!(classification.morphology.holes, 0);
+}
+
+#[test]
+fn peaks_of_perpendicular_waves_are_spots() {
+    let wave = |value: f64| (value * TAU / 8.0).cos();
+    let classification = classify(&universe(|x, y| (wave(x) + wave(y) - 1.0).max(0.0)));
+    assert_eq!(classification.kind, PatternKind::Spots);
+    // One spot per wavelength along each side, cut in halves at the edges
+    assert!(classification.morphology.components >= 6 * 8, "{:?}", classification.morphology);
+    assert!(classification.morphology.area_fraction < 0.25, "{:?}", classification.morphology);
+}
+
+#[test]
+fn flat_universes_are_uniform() {
+    let classification = classify(&universe(|x, _| 0.3 + 0.001 * (x * TAU / 8.0).sin()));
+    assert_eq!(classification.kind, PatternKind::Uniform);
+    assert_eq!(classification.wavelength, None);
+}
+
+#[test]
+fn rings_have_one_hole() {
+    let ring = universe(|x, y| {
+        let distance = (x - 32.0).hypot(y - 24.0);
+        if (8.0..14.0).contains(&distance) { 1.0 } else { 0.0 }
+    });
+    let morphology = Morphology::of(&ring, 0.5);
+    assert_eq!((morphology.components, morphology.holes, morphology.background_components), (1, 1, 2));
+    assert_eq!(morphology.euler_characteristic(), 0);
+    assert!(morphology.area_fraction > 0.0 && morphology.perimeter > 0.0);
+}
+
+#[test]
+fn kinds_are_named() {
+    for name in PATTERN_KIND_NAMES {
+        assert_eq!(PatternKind::from_name(name).unwrap().name(), name);
+    }
+    assert_eq!(PatternKind::from_name("waves"), None);
+}
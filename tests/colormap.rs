@@ -0,0 +1,21 @@
+//! Thresholds of the binary color map, see `Colormap::validate`
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::*;
+
+#[test]
+fn thresholds_are_color_values() {
+    for threshold in [0.0, 0.25, 1.0] {
+        assert!(Colormap::Binary { threshold }.validate().is_ok(), "{threshold}");
+    }
+    for threshold in [-0.1, 1.5, f32::NAN, f32::INFINITY] {
+        let error = Colormap::Binary { threshold }.validate().unwrap_err();
+        assert!(matches!(error, SimulationError::InvalidColormap(_)), "{threshold}: {error:?}");
+    }
+}
+
+#[test]
+fn maps_from_configurations_are_checked() {
+    let colormap: Colormap = ron::from_str("binary(threshold: 2.0)").unwrap();
+    assert!(colormap.validate().is_err());
+    assert!(Colormap::Viridis.with_threshold(2.0).validate().is_ok());
+}
@@ -0,0 +1,45 @@
+//! Conservation of mass by the conservative stencil, see `conservation`
+use ca_turing_pattern::activity::ActivityTracking;
+use ca_turing_pattern::conservation::{total_mass, ConservationCheck};
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Rates without any reaction, so that only the diffusion changes the cells
+const DIFFUSION: Parameters = Parameters { d_a: 0.8, d_b: 0.4, f: 0.0, k: 0.0, r: 0.0, initial: None };
+
+/// Simulation of a universe of 96x80 cells with a few seeds, diffusing
+/// between closed edges with the conservative stencil
+fn simulation() -> Simulation<f64> {
+    let mut rng = ChaCha8Rng::seed_from_u64(11);
+    Simulation::random(DIFFUSION, Position { row: 96, col: 80 }, 6, &mut rng)
+        .unwrap()
+        .with_boundary(Boundary::Closed)
+        .with_stencil(Stencil::Conservative)
+        .with_conservation_check(ConservationCheck { tolerance: 1e-12, strict: true })
+}
+
+#[test]
+fn closed_universes_keep_their_mass() {
+    let mut simulation = simulation();
+    let before = total_mass(simulation.universe());
+    for _ in 0..200 {
+        simulation.step();
+        let balance = simulation.mass_balance().expect("the mass is checked");
+        assert!(balance.relative_leak().abs() <= 1e-12, "{balance}");
+    }
+    assert!(!simulation.is_stopped());
+    let after = total_mass(simulation.universe());
+    assert!(((after - before) / before).abs() <= 1e-12, "{before} became {after}");
+}
+
+#[test]
+fn activity_tracking_skips_no_tile() {
+    let tracking = ActivityTracking { tile_size: 16, epsilon: 1e-3 };
+    let mut tracked = simulation().with_activity_tracking(tracking);
+    let mut untracked = simulation();
+    tracked.run(100);
+    untracked.run(100);
+    assert!(!tracked.is_stopped());
+    assert_eq!(tracked.universe(), untracked.universe());
+}
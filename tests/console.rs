@@ -0,0 +1,50 @@
+//! Running the lines typed in the console, see `console`
+#![cfg(feature = "bevy")]
+use bevy::prelude::*;
+use ca_turing_pattern::console::*;
+
+/// `echo <words>`: the arguments, separated by `|`
+fn echo(_: &mut World, arguments: &[&str]) -> CommandResult {
+    match arguments {
+        [] => Err("usage: echo <words>".to_string()),
+        words => Ok(words.join("|")),
+    }
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.register_command("echo", ConsoleCommand { usage: "<words>", help: "repeat the words", run: echo });
+    app
+}
+
+#[test]
+fn lines_are_split_into_a_name_and_arguments() {
+    let mut app = app();
+    assert_eq!(run_command(&mut app.world, "echo a b"), Ok("a|b".to_string()));
+    assert_eq!(run_command(&mut app.world, "  echo\ta   b  "), Ok("a|b".to_string()));
+    assert_eq!(run_command(&mut app.world, "echo"), Err("usage: echo <words>".to_string()));
+    assert_eq!(run_command(&mut app.world, ""), Ok(String::new()));
+    assert_eq!(run_command(&mut app.world, "   "), Ok(String::new()));
+}
+
+#[test]
+fn unknown_commands_list_the_known_ones() {
+    let mut app = app();
+    assert_eq!(
+        run_command(&mut app.world, "Echo a"),
+        Err("unknown command `Echo`, expected one of: echo, help".to_string())
+    );
+    assert_eq!(run_command(&mut World::new(), "echo a"), Err("no commands are registered".to_string()));
+}
+
+#[test]
+fn help_lists_the_usage_of_every_command() {
+    let mut app = app();
+    let help = "echo <words>: repeat the words\nhelp: list the commands".to_string();
+    assert_eq!(run_command(&mut app.world, "help"), Ok(help));
+    // A command registered again replaces the previous one
+    app.register_command("echo", ConsoleCommand { usage: "", help: "say nothing", run: |_, _| Ok(String::new()) });
+    assert_eq!(run_command(&mut app.world, "echo a"), Ok(String::new()));
+    assert_eq!(app.world.resource::<CommandRegistry>().names().collect::<Vec<_>>(), ["echo", "help"]);
+    assert!(app.world.resource::<CommandRegistry>().help().starts_with("echo: say nothing\n"));
+}
@@ -0,0 +1,75 @@
+//! Regression tests of the simulation against the content hashes of
+//! reference runs recorded in `tests/fixtures/hashes.ron`
+use ca_turing_pattern::config::InitialConfig;
+use ca_turing_pattern::deterministic::portable_rng;
+use ca_turing_pattern::*;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    preset: String,
+    dimensions: Position,
+    seed: u64,
+    steps: i32,
+    hash: u64,
+}
+
+fn fixtures() -> Vec<Fixture> {
+    ron::from_str(include_str!("fixtures/hashes.ron")).expect("the fixtures are valid RON")
+}
+
+/// Simulation of a fixture before any evolution
+fn simulation<T: Float>(fixture: &Fixture) -> Simulation<T> {
+    let parameters = Parameters::preset(&fixture.preset).expect("the fixtures use built-in presets");
+    let (universe, dimensions) = InitialConfig::default()
+        .universe(&parameters, fixture.dimensions, Some(fixture.seed))
+        .expect("random universes can always be built");
+    let universe = universe
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.cast()).collect())
+        .collect();
+    Simulation::new(parameters, dimensions, universe).expect("the fixtures are valid")
+}
+
+#[test]
+fn reference_runs_keep_their_hashes() {
+    for fixture in fixtures() {
+        let mut simulation = simulation::<f32>(&fixture);
+        simulation.run(fixture.steps);
+        assert_eq!(simulation.content_hash(), fixture.hash, "hash of {fixture:?}");
+    }
+}
+
+#[test]
+fn hashes_depend_on_the_concentrations() {
+    let fixture = &fixtures()[0];
+    let mut simulation = simulation::<f32>(fixture);
+    let hash = simulation.content_hash();
+    simulation.set_cell(Position { row: 0, col: 0 }, Cell { a: 0.5, b: 0.5 }).unwrap();
+    assert_ne!(simulation.content_hash(), hash);
+}
+
+#[test]
+fn initial_hashes_do_not_depend_on_the_precision() {
+    for fixture in fixtures() {
+        assert_eq!(
+            simulation::<f64>(&fixture).content_hash(),
+            simulation::<f32>(&fixture).content_hash(),
+            "hash of {fixture:?}"
+        );
+    }
+}
+
+/// Exact hash of a run drawn from the portable generator, recorded once: it
+/// must be the same on every platform, see `deterministic`
+#[test]
+fn deterministic_runs_keep_their_exact_hash() {
+    let dimensions = Position { row: 64, col: 48 };
+    let parameters = Parameters::preset("mitosis").expect("mitosis is a built-in preset");
+    let (universe, dimensions) = InitialConfig::default()
+        .universe_with_rng(&parameters, dimensions, &mut portable_rng(42))
+        .expect("random universes can always be built");
+    let mut simulation = Simulation::new(parameters, dimensions, universe).expect("the run is valid");
+    simulation.run(200);
+    assert_eq!(simulation.exact_hash(), 0x1378_066d_5a3e_d1d1);
+}
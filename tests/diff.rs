@@ -0,0 +1,72 @@
+//! Distances and similarities of two universes, see `diff`
+use ca_turing_pattern::diff::{compare, difference_map};
+use ca_turing_pattern::export::Species;
+use ca_turing_pattern::*;
+
+const DIMENSIONS: Position = Position { row: 12, col: 20 };
+
+/// Stripes of B around 0.5, and A the other way round
+fn stripes() -> Universe {
+    (0..DIMENSIONS.row)
+        .map(|_| {
+            (0..DIMENSIONS.col)
+                .map(|col| {
+                    let b = 0.5 + 0.4 * (col as f32 * std::f32::consts::TAU / 5.0).sin();
+                    Cell { a: 1.0 - b, b }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn identical_universes_are_at_distance_0_and_similar() {
+    let comparison = compare(&stripes(), &stripes()).unwrap();
+    for species in [comparison.a, comparison.b] {
+        assert_eq!((species.l2, species.rms, species.max), (0.0, 0.0, 0.0));
+        assert!((species.ssim - 1.0).abs() < 1e-12, "{}", species.ssim);
+    }
+    assert!(difference_map(&stripes(), &stripes(), Species::B).unwrap().iter().flatten().all(|value| *value == 0.0));
+}
+
+#[test]
+fn distances_add_the_differences_of_the_cells() {
+    let mut changed = stripes();
+    changed[3][4].b += 0.3;
+    changed[11][19].b -= 0.4;
+    let comparison = compare(&stripes(), &changed).unwrap();
+    assert_eq!((comparison.a.l2, comparison.a.max), (0.0, 0.0));
+    assert!((comparison.b.l2 - 0.5).abs() < 1e-6, "{}", comparison.b.l2);
+    assert!((comparison.b.max - 0.4).abs() < 1e-6, "{}", comparison.b.max);
+    let cells = (DIMENSIONS.row * DIMENSIONS.col) as f64;
+    assert!((comparison.b.rms - 0.5 / cells.sqrt()).abs() < 1e-6, "{}", comparison.b.rms);
+    assert!(comparison.b.ssim < 1.0 && comparison.b.ssim > 0.5, "{}", comparison.b.ssim);
+
+    let map = difference_map(&stripes(), &changed, Species::B).unwrap();
+    assert_eq!((map[11][19], map[0][0]), (1.0, 0.0));
+    assert!((map[3][4] - 0.75).abs() < 1e-5, "{}", map[3][4]);
+}
+
+#[test]
+fn inverted_fields_are_dissimilar() {
+    let inverted: Universe =
+        stripes().iter().map(|row| row.iter().map(|cell| Cell { a: cell.a, b: 1.0 - cell.b }).collect()).collect();
+    let comparison = compare(&stripes(), &inverted).unwrap();
+    // Same means, opposite variations
+    assert!(comparison.b.ssim < -0.9, "{}", comparison.b.ssim);
+    // A is unchanged
+    assert!((comparison.a.ssim - 1.0).abs() < 1e-12, "{}", comparison.a.ssim);
+    assert!(comparison.b.max > 0.7);
+}
+
+#[test]
+fn precisions_are_compared_and_dimensions_checked() {
+    let precise: Universe<f64> = stripes().iter().map(|row| row.iter().map(|cell| cell.cast()).collect()).collect();
+    let comparison = compare(&stripes(), &precise).unwrap();
+    assert_eq!((comparison.a.max, comparison.b.max), (0.0, 0.0));
+
+    let mut smaller = stripes();
+    smaller.pop();
+    assert!(compare(&stripes(), &smaller).is_err());
+    assert!(difference_map(&stripes(), &smaller, Species::A).is_err());
+}
@@ -0,0 +1,142 @@
+//! Outputs of the `export` module written to temporary directories
+#![cfg(feature = "fs")]
+use std::fs;
+use std::path::PathBuf;
+
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::export::{
+    normal_map, save_fields, write_csv, write_npy, FrameSequence, FrameSequenceConfig, Species,
+};
+use ca_turing_pattern::*;
+
+const DIMENSIONS: Position = Position { row: 3, col: 5 };
+
+/// Universe of `DIMENSIONS` whose concentrations tell their cell apart
+fn universe() -> Universe {
+    (0..DIMENSIONS.row)
+        .map(|row| {
+            (0..DIMENSIONS.col)
+                .map(|col| Cell { a: (row * 10 + col) as f32 / 100.0, b: 1.0 - (row * 10 + col) as f32 / 100.0 })
+                .collect()
+        })
+        .collect()
+}
+
+/// Values of `species` in row-major order
+fn values(species: Species) -> Vec<f32> {
+    universe().iter().flatten().map(|cell| species.concentration(cell)).collect()
+}
+
+/// Shape and values of a NumPy array written by `write_npy`, checking its
+/// header
+fn read_npy(bytes: &[u8]) -> (String, Vec<f32>) {
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let data = 10 + header_len;
+    assert_eq!(data % 64, 0, "the data starts at {data}");
+    let header = std::str::from_utf8(&bytes[10..data]).unwrap();
+    assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, "), "{header}");
+    assert!(header.ends_with('\n'));
+    let shape = header.split("'shape': ").nth(1).unwrap().split(')').next().unwrap().to_string() + ")";
+    let values = bytes[data..].chunks(4).map(|value| f32::from_le_bytes(value.try_into().unwrap())).collect();
+    (shape, values)
+}
+
+/// Values of each line of a CSV file written by `write_csv`
+fn read_csv(text: &str) -> Vec<Vec<f32>> {
+    text.lines().map(|line| line.split(',').map(|value| value.parse().unwrap()).collect()).collect()
+}
+
+/// Empty directory of the test `name`
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("ca_turing_pattern_export_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+#[test]
+fn frames_are_numbered_after_the_interval() {
+    let directory = directory("frames");
+    let config = FrameSequenceConfig { directory: directory.clone(), interval: 5 };
+    let frames = FrameSequence::new(config, Colormap::Gray).unwrap();
+    let colored_map: ColoredMap = vec![vec![0.0, 0.5, 1.0]; 2];
+    let due: Vec<i32> = (1..=20).filter(|&generation| frames.is_due(generation)).collect();
+    assert_eq!(due, [5, 10, 15, 20]);
+    let path = frames.write(15, &colored_map).unwrap();
+    assert_eq!(path, directory.join("frame_000003.png"));
+    assert!(path.exists());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn frame_intervals_below_one_are_refused() {
+    let directory = directory("frame_interval");
+    for interval in [0, -1] {
+        let config = FrameSequenceConfig { directory: directory.clone(), interval };
+        assert!(matches!(config.validate(), Err(SimulationError::InvalidInterval(_))));
+        assert!(matches!(FrameSequence::new(config, Colormap::Gray), Err(SimulationError::InvalidInterval(_))));
+    }
+    assert!(!directory.exists());
+}
+
+#[test]
+fn npy_arrays_have_the_shape_of_the_universe() {
+    for species in [Species::A, Species::B] {
+        let mut bytes = Vec::new();
+        write_npy(&universe(), species, &mut bytes).unwrap();
+        let (shape, values) = read_npy(&bytes);
+        assert_eq!(shape, "(3, 5)");
+        assert_eq!(values, self::values(species));
+    }
+}
+
+#[test]
+fn csv_files_have_one_line_per_row() {
+    let mut bytes = Vec::new();
+    write_csv(&universe(), Species::B, &mut bytes).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+    assert_eq!(text.lines().count(), DIMENSIONS.row);
+    assert!(text.ends_with('\n'));
+    let rows = read_csv(&text);
+    assert!(rows.iter().all(|row| row.len() == DIMENSIONS.col));
+    assert_eq!(rows.concat(), values(Species::B));
+}
+
+#[test]
+fn ragged_universes_are_not_written() {
+    let mut universe = universe();
+    universe[1].pop();
+    assert!(write_npy(&universe, Species::A, Vec::new()).is_err());
+    assert!(write_csv(&universe, Species::A, Vec::new()).is_err());
+}
+
+#[test]
+fn fields_are_saved_next_to_each_other() {
+    let directory = directory("fields");
+    fs::create_dir_all(&directory).unwrap();
+
+    let paths = save_fields(&universe(), &directory.join("fields.npy")).unwrap();
+    assert_eq!(paths, [directory.join("fields_a.npy"), directory.join("fields_b.npy")]);
+    for (species, path) in [Species::A, Species::B].into_iter().zip(&paths) {
+        let (shape, values) = read_npy(&fs::read(path).unwrap());
+        assert_eq!(shape, "(3, 5)");
+        assert_eq!(values, self::values(species));
+    }
+
+    let paths = save_fields(&universe(), &directory.join("fields.csv")).unwrap();
+    assert_eq!(paths, [directory.join("fields_a.csv"), directory.join("fields_b.csv")]);
+    for (species, path) in [Species::A, Species::B].into_iter().zip(&paths) {
+        assert_eq!(read_csv(&fs::read_to_string(path).unwrap()).concat(), values(species));
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn closed_edges_keep_the_slope_of_a_ramp() {
+    // B rises by the same step from column to column
+    let ramp: Universe = (0..4).map(|_| (0..6).map(|col| Cell { a: 0.0, b: col as f32 / 5.0 }).collect()).collect();
+    let image = normal_map(&ramp, Species::B, 2.0, Boundary::Closed).unwrap();
+    let normal = *image.get_pixel(2, 1);
+    assert!(normal[0] < 128 && normal[1] == 128, "{normal:?}");
+    assert!(image.pixels().all(|pixel| *pixel == normal), "{image:?}");
+}
@@ -0,0 +1,89 @@
+//! Round trips and rounding errors of the half float storage, `F16`, and
+//! its evolution, see `float`
+use ca_turing_pattern::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Values in [0,1]: a fine grid, with the ends, and random ones
+fn concentrations() -> Vec<f32> {
+    let mut rng = ChaCha8Rng::seed_from_u64(11);
+    (0..=10_000).map(|step| step as f32 / 10_000.0).chain((0..10_000).map(|_| rng.gen())).collect()
+}
+
+#[test]
+fn half_floats_round_trip() {
+    for bits in 0..=u16::MAX {
+        let half = F16::from_bits(bits);
+        let value = half.to_f32();
+        if value.is_nan() {
+            assert!(F16::from_f32(value).is_nan(), "{bits:#06x}");
+        } else {
+            assert_eq!(F16::from_f32(value).to_bits(), bits, "{bits:#06x} is {value}");
+            assert_eq!(F16::from_f64(value as f64).to_bits(), bits, "{bits:#06x} is {value}");
+        }
+    }
+}
+
+#[test]
+fn half_floats_round_to_the_nearest() {
+    for value in concentrations() {
+        let error = (F16::from_f32(value).to_f32() - value).abs();
+        // Half of the spacing of the half floats around the value: 11
+        // significant bits for the normal ones, steps of 2^-24 below 2^-14
+        let bound = (value * 2f32.powi(-11)).max(2f32.powi(-25));
+        assert!(error <= bound, "{value} is off by {error}, more than {bound}");
+    }
+    // Ties go to the even mantissa
+    assert_eq!(F16::from_f32(1.0 + 2f32.powi(-11)).to_f32(), 1.0);
+    assert_eq!(F16::from_f32(1.0 + 3.0 * 2f32.powi(-11)).to_f32(), 1.0 + 2f32.powi(-9));
+    // The largest half float, and beyond it infinity
+    assert_eq!(F16::from_f32(65504.0).to_f32(), 65504.0);
+    assert_eq!(F16::from_f32(65520.0).to_f32(), f32::INFINITY);
+    assert_eq!(F16::from_f32(-1e-9).to_f32(), 0.0);
+}
+
+/// One evolution of the storage `T` is that of `f32` rounded to `T`
+fn assert_rounded_evolution<T: Float>() {
+    let dimensions = Position { row: 24, col: 24 };
+    let mut simulation: Simulation<f32> =
+        Simulation::random(Parameters::default(), dimensions, 20, &mut ChaCha8Rng::seed_from_u64(2)).unwrap();
+    simulation.run(3);
+    let universe: Universe<T> =
+        simulation.universe().iter().map(|row| row.iter().map(|cell| cell.cast()).collect()).collect();
+    let start: Universe<f32> = universe.iter().map(|row| row.iter().map(|cell| cell.cast()).collect()).collect();
+
+    let mut stored = Simulation::new(Parameters::default(), dimensions, universe).unwrap();
+    let mut computed = Simulation::new(Parameters::default(), dimensions, start).unwrap();
+    stored.step();
+    computed.step();
+    for (stored, computed) in stored.universe().iter().flatten().zip(computed.universe().iter().flatten()) {
+        assert_eq!(*stored, computed.cast::<T>());
+    }
+}
+
+#[test]
+fn evolutions_are_computed_in_f32_and_rounded() {
+    assert_rounded_evolution::<F16>();
+}
+
+#[test]
+fn half_floats_evolve_like_f32() {
+    let dimensions = Position { row: 32, col: 32 };
+    let parameters = Parameters::preset("mitosis").unwrap();
+    let mut start = vec![vec![Cell { a: 1.0, b: 0.0 }; dimensions.col]; dimensions.row];
+    for row in &mut start[12..20] {
+        row[12..20].fill(Cell { a: 0.5, b: 0.25 });
+    }
+    let universe: Universe<F16> = start.iter().map(|row| row.iter().map(|cell| cell.cast()).collect()).collect();
+    let mut stored = Simulation::new(parameters, dimensions, universe.clone()).unwrap();
+    let mut computed = Simulation::new(parameters, dimensions, start).unwrap();
+    stored.run(200);
+    computed.run(200);
+    // The updates are not all lost to the rounding: the pattern spreads as
+    // it does in f32
+    assert_ne!(stored.universe(), &universe);
+    let mean_b = |universe: Vec<f32>| universe.iter().sum::<f32>() / universe.len() as f32;
+    let stored_b = mean_b(stored.universe().iter().flatten().map(|cell| cell.b.to_f32()).collect());
+    let computed_b = mean_b(computed.universe().iter().flatten().map(|cell| cell.b).collect());
+    assert!((stored_b - computed_b).abs() < 1e-3, "{stored_b} instead of {computed_b}");
+}
@@ -0,0 +1,24 @@
+//! Graphs of the edges of meshes, see `Graph::from_mesh`
+use ca_turing_pattern::graph::Graph;
+use ca_turing_pattern::mesh::Mesh;
+use ca_turing_pattern::*;
+
+/// Square split in two triangles sharing the edge 0-2
+fn square(triangles: Vec<[u32; 3]>) -> Mesh {
+    let positions = vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+    Mesh { positions, triangles, ..Mesh::default() }
+}
+
+#[test]
+fn shared_edges_are_counted_once() {
+    let graph = Graph::from_mesh(&square(vec![[0, 1, 2], [0, 2, 3]])).unwrap();
+    assert_eq!(graph.nodes(), 4);
+    let edges: Vec<_> = graph.edges().collect();
+    assert_eq!(edges, [(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (1, 2, 1.0), (2, 3, 1.0)]);
+}
+
+#[test]
+fn triangles_of_missing_vertices_are_rejected() {
+    let error = Graph::from_mesh(&square(vec![[0, 1, 2], [0, 2, 7]])).unwrap_err();
+    assert!(matches!(error, SimulationError::InvalidGraph(_)), "{error:?}");
+}
@@ -0,0 +1,58 @@
+//! Initial conditions selected from the parameters, see
+//! `Parameters::initial_universe`
+use ca_turing_pattern::config::InitialConfig;
+use ca_turing_pattern::initial::InitialCondition;
+use ca_turing_pattern::snapshot::Snapshot;
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const DIMENSIONS: Position = Position { row: 40, col: 30 };
+
+#[test]
+fn simulations_start_from_the_condition_of_their_parameters() {
+    let condition = InitialCondition::Circles { count: 3, radius: 4.0 };
+    let parameters = Parameters::builder().initial(condition).build().unwrap();
+    let simulation: Simulation = Simulation::from_parameters(parameters, DIMENSIONS, &mut ChaCha8Rng::seed_from_u64(3))
+        .expect("the parameters are valid");
+    let (universe, _): (Universe, _) = condition.generate(&DIMENSIONS, &mut ChaCha8Rng::seed_from_u64(3)).unwrap();
+    assert_eq!(simulation.universe(), &universe);
+
+    let random: Simulation =
+        Simulation::from_parameters(Parameters::default(), DIMENSIONS, &mut ChaCha8Rng::seed_from_u64(3)).unwrap();
+    let cells = random.universe().iter().flatten().filter(|cell| cell.b > 0.0).count();
+    assert_eq!(cells, INITIAL_CELLS);
+}
+
+#[test]
+fn the_condition_of_the_configuration_takes_precedence() {
+    let parameters = Parameters::builder().initial(InitialCondition::Square { size: 0.5 }).build().unwrap();
+    let square = InitialConfig::default().universe(&parameters, DIMENSIONS, Some(1)).unwrap().0;
+    assert_eq!(square[20][15], Cell { a: 0.5, b: 0.25 });
+    assert_eq!(square[0][0], Cell { a: 1.0, b: 0.0 });
+
+    let initial = InitialConfig { condition: Some(InitialCondition::Empty), ..InitialConfig::default() };
+    let empty = initial.universe(&parameters, DIMENSIONS, Some(1)).unwrap().0;
+    assert!(empty.iter().flatten().all(|cell| cell.b == 0.0));
+}
+
+#[test]
+fn conditions_are_read_with_the_parameters() {
+    let parameters: Parameters =
+        ron::from_str("(d_a: 1.0, d_b: 0.5, f: 0.055, k: 0.117, r: 1.0, initial: Some(square(size: 0.2)))").unwrap();
+    assert_eq!(parameters.initial, Some(InitialCondition::Square { size: 0.2 }));
+    let parameters: Parameters = ron::from_str("(d_a: 1.0, d_b: 0.5, f: 0.055, k: 0.117, r: 1.0)").unwrap();
+    assert_eq!(parameters.initial, None);
+}
+
+#[test]
+fn conditions_are_kept_in_snapshots() {
+    for initial in [None, Some(InitialCondition::Square { size: 0.2 })] {
+        let parameters = Parameters { initial, ..Parameters::default() };
+        let simulation: Simulation =
+            Simulation::from_parameters(parameters, DIMENSIONS, &mut ChaCha8Rng::seed_from_u64(5)).unwrap();
+        let snapshot = Snapshot::from_bytes(&Snapshot::of(&simulation).to_bytes().unwrap()).unwrap();
+        assert_eq!(snapshot.parameters.initial, initial);
+        assert_eq!(&snapshot.universe, simulation.universe());
+    }
+}
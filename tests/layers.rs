@@ -0,0 +1,47 @@
+//! Couplings of layers with activity tracking: only the tiles the couplings
+//! change by at least `epsilon` are woken, see `Simulation::update_cells`
+use ca_turing_pattern::activity::ActivityTracking;
+use ca_turing_pattern::export::Species;
+use ca_turing_pattern::layers::{Coupling, LayeredSimulation};
+use ca_turing_pattern::*;
+
+const DIMENSIONS: Position = Position { row: 64, col: 64 };
+
+/// Two layers of steady cells, with one cell of the first seeded with B
+/// and B of the first layer feeding B of the second with `strength`
+fn layers(strength: f32) -> LayeredSimulation {
+    let layer = |seeded: bool| {
+        let mut universe = vec![vec![Cell { a: 1.0, b: 0.0 }; DIMENSIONS.col]; DIMENSIONS.row];
+        if seeded {
+            universe[5][5] = Cell { a: 0.5, b: 0.5 };
+        }
+        Simulation::new(Parameters::default(), DIMENSIONS, universe)
+            .unwrap()
+            .with_activity_tracking(ActivityTracking { tile_size: 16, epsilon: 1e-6 })
+    };
+    let coupling = Coupling { from: 0, source: Species::B, to: 1, target: Species::B, strength };
+    LayeredSimulation::new(vec![layer(true), layer(false)], vec![coupling]).unwrap()
+}
+
+fn evolved_fraction(layers: &LayeredSimulation) -> f64 {
+    layers.layers()[1].activity().unwrap().evolved_fraction()
+}
+
+#[test]
+fn couplings_wake_the_tiles_they_change() {
+    let mut layers = layers(0.5);
+    layers.step();
+    assert_eq!(evolved_fraction(&layers), 1.0);
+    layers.step();
+    // The tile of the seeded cell and its three neighbours
+    assert_eq!(evolved_fraction(&layers), 4.0 / 16.0);
+    assert!(layers.layers()[1].universe()[5][5].b > 0.0);
+}
+
+#[test]
+fn couplings_below_epsilon_wake_no_tile() {
+    let mut layers = layers(1e-9);
+    layers.step();
+    layers.step();
+    assert_eq!(evolved_fraction(&layers), 0.0);
+}
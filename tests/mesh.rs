@@ -0,0 +1,147 @@
+//! Meshes of universes: closed solids, OBJ files read back and the layout of
+//! binary glTF, see `mesh`
+use std::collections::HashMap;
+
+use ca_turing_pattern::export::Species;
+use ca_turing_pattern::mesh::{Mesh, MeshConfig};
+use ca_turing_pattern::*;
+
+/// Non-square universe with a bump of B
+fn universe() -> Universe {
+    (0..4)
+        .map(|row| (0..6).map(|col| Cell { a: 0.5, b: ((row * col) % 5) as f32 / 4.0 }).collect())
+        .collect()
+}
+
+fn mesh(base: f32) -> Mesh {
+    let config = MeshConfig { species: Species::B, height: 3.0, base, ..MeshConfig::default() };
+    Mesh::from_universe(&universe(), &config).unwrap()
+}
+
+/// Triangles of `mesh` between the vertices at the same position merged
+fn welded_triangles(mesh: &Mesh) -> Vec<[usize; 3]> {
+    let mut ids = HashMap::new();
+    let welded: Vec<usize> = mesh
+        .positions
+        .iter()
+        .map(|position| {
+            let count = ids.len();
+            *ids.entry(position.map(f32::to_bits)).or_insert(count)
+        })
+        .collect();
+    mesh.triangles.iter().map(|triangle| triangle.map(|vertex| welded[vertex as usize])).collect()
+}
+
+/// Volume enclosed by `mesh`, positive if its triangles are counterclockwise
+/// seen from outside
+fn signed_volume(mesh: &Mesh) -> f32 {
+    let volume = |triangle: &[u32; 3]| {
+        let [a, b, c] = triangle.map(|vertex| mesh.positions[vertex as usize]);
+        let cross = [b[1] * c[2] - b[2] * c[1], b[2] * c[0] - b[0] * c[2], b[0] * c[1] - b[1] * c[0]];
+        (a[0] * cross[0] + a[1] * cross[1] + a[2] * cross[2]) / 6.0
+    };
+    mesh.triangles.iter().map(volume).sum()
+}
+
+#[test]
+fn bases_close_the_surface_into_a_solid() {
+    let mesh = mesh(1.5);
+    // Every edge is crossed once in each direction by the triangles on its
+    // sides: the solid is closed and its triangles turn the same way
+    let mut edges = HashMap::new();
+    for [a, b, c] in welded_triangles(&mesh) {
+        for edge in [(a, b), (b, c), (c, a)] {
+            *edges.entry(edge).or_insert(0) += 1;
+        }
+    }
+    for (&(from, to), &count) in &edges {
+        assert_eq!(count, 1, "edge {from}-{to}");
+        assert_eq!(edges.get(&(to, from)), Some(&1), "edge {from}-{to} has no opposite");
+    }
+    // Outwards, with at least the volume of the base below the 5x3 cells
+    assert!(signed_volume(&mesh) >= 1.5 * 15.0, "{}", signed_volume(&mesh));
+    assert_eq!(mesh.normals.len(), mesh.positions.len());
+}
+
+#[test]
+fn surfaces_without_base_face_up() {
+    let mesh = mesh(0.0);
+    assert_eq!(mesh.positions.len(), 24);
+    assert_eq!(mesh.triangles.len(), 2 * 5 * 3);
+    assert!(mesh.normals.iter().all(|normal| normal[1] > 0.0), "{:?}", mesh.normals);
+}
+
+#[test]
+fn obj_faces_take_relative_indices_and_skip_normals() {
+    let obj = "\
+v 0 0 0
+v 1 0 0
+v 1 0 1
+v 0 0 1
+vt 0 0
+vt 1 1
+vn 0 1 0
+f -4//1 -1//1 -2//1 -3//1
+f 1/1 3/2/1 2/1/1
+";
+    let mesh = Mesh::read_obj(obj.as_bytes()).unwrap();
+    assert_eq!(mesh.positions.len(), 4);
+    assert_eq!(mesh.triangles, [[0, 3, 2], [0, 2, 1], [0, 2, 1]]);
+    // The first face gives no texture coordinates, the second its own
+    assert_eq!(mesh.uvs, [[0.0, 1.0], [0.0, 1.0], [1.0, 0.0], [0.0, 0.0]]);
+    assert!(mesh.normals.iter().all(|normal| *normal == [0.0, 1.0, 0.0]), "{:?}", mesh.normals);
+
+    for face in ["f -5 1 2", "f 1 2 5", "f 1 2"] {
+        let obj = format!("v 0 0 0\nv 1 0 0\nv 1 0 1\nv 0 0 1\n{face}\n");
+        assert!(matches!(Mesh::read_obj(obj.as_bytes()), Err(SimulationError::Format(_))), "{face}");
+    }
+}
+
+#[test]
+fn obj_files_read_back_the_mesh() {
+    let mesh = mesh(2.0);
+    let mut obj = Vec::new();
+    mesh.write_obj(&mut obj).unwrap();
+    let read = Mesh::read_obj(obj.as_slice()).unwrap();
+    assert_eq!(read.positions, mesh.positions);
+    assert_eq!(read.triangles, mesh.triangles);
+    let close = |a: &[f32], b: &[f32]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5);
+    assert!(read.uvs.iter().zip(&mesh.uvs).all(|(a, b)| close(a, b)));
+    assert!(read.normals.iter().zip(&mesh.normals).all(|(a, b)| close(a, b)));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn glb_chunks_are_padded_to_4_bytes() {
+    let mut mesh = mesh(1.0);
+    mesh.colors = mesh.positions.iter().map(|position| [position[1] / 3.0, 0.5, 1.0]).collect();
+    let mut glb = Vec::new();
+    mesh.write_glb(&mut glb).unwrap();
+
+    let word = |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap()) as usize;
+    assert_eq!(&glb[..4], b"glTF");
+    assert_eq!((word(4), word(8)), (2, glb.len()));
+    let json_length = word(12);
+    assert_eq!(&glb[16..20], b"JSON");
+    assert_eq!(json_length % 4, 0);
+    let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+    let binary = 20 + json_length;
+    let binary_length = word(binary);
+    assert_eq!(&glb[binary + 4..binary + 8], b"BIN\0");
+    assert_eq!(binary_length % 4, 0);
+    assert_eq!(binary + 8 + binary_length, glb.len());
+
+    assert_eq!(json["asset"]["version"], "2.0");
+    assert!(json["buffers"][0]["byteLength"].as_u64().unwrap() as usize <= binary_length);
+    let accessors = json["accessors"].as_array().unwrap();
+    assert_eq!(accessors.len(), 5);
+    assert_eq!(accessors[0]["count"].as_u64().unwrap() as usize, mesh.positions.len());
+    assert_eq!(accessors[3]["count"].as_u64().unwrap() as usize, 3 * mesh.triangles.len());
+    // The views follow each other in the buffer
+    let mut offset = 0;
+    for view in json["bufferViews"].as_array().unwrap() {
+        assert_eq!(view["byteOffset"].as_u64().unwrap(), offset);
+        offset += view["byteLength"].as_u64().unwrap();
+    }
+    assert_eq!(json["buffers"][0]["byteLength"].as_u64(), Some(offset));
+}
@@ -0,0 +1,33 @@
+//! Rates scaled by a modulation, see `Modulation::validate`
+use ca_turing_pattern::modulation::{Modulation, ModulationConfig};
+use ca_turing_pattern::timeline::{Keyframe, Timeline};
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Modulation scaling `f` up to `factor` times
+fn modulation(factor: f32) -> Modulation {
+    Modulation::new(ModulationConfig { f: [1.0, factor], ..ModulationConfig::default() }).unwrap()
+}
+
+fn simulation(f: f32) -> Simulation {
+    let parameters = Parameters::builder().f(f).build().unwrap();
+    Simulation::random(parameters, Position { row: 8, col: 8 }, 4, &mut ChaCha8Rng::seed_from_u64(0)).unwrap()
+}
+
+#[test]
+fn scaled_rates_stay_in_range() {
+    assert!(simulation(0.2).with_modulation(modulation(5.0)).is_ok());
+    let error = simulation(0.25).with_modulation(modulation(5.0)).unwrap_err();
+    assert!(matches!(error, SimulationError::InvalidModulation(_)), "{error:?}");
+}
+
+#[test]
+fn changes_of_the_parameters_are_checked_with_the_modulation() {
+    let mut simulation = simulation(0.1).with_modulation(modulation(4.0)).unwrap();
+    assert!(simulation.set_parameters(Parameters { f: 0.3, ..simulation.parameters() }).is_err());
+    assert_eq!(simulation.parameters().f, 0.1);
+
+    let timeline = Timeline { f: vec![Keyframe { generation: 100, value: 0.5 }], ..Timeline::default() };
+    assert!(simulation.with_timeline(timeline).is_err());
+}
@@ -0,0 +1,65 @@
+//! Queries of a morphogen field by points of the plane, see `morphogen`
+use ca_turing_pattern::morphogen::{MorphogenField, Placement};
+use ca_turing_pattern::*;
+
+/// Field of 4 rows and 6 columns whose B rises by 0.1 per column, with a
+/// peak at row 2, column 1
+fn field() -> MorphogenField {
+    let mut universe: Universe =
+        (0..4).map(|_| (0..6).map(|col| Cell { a: 0.5, b: col as f32 / 10.0 }).collect()).collect();
+    universe[2][1].b = 0.9;
+    MorphogenField::new(&universe, 7)
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn samples_interpolate_between_the_cell_centers() {
+    let field = field();
+    assert_eq!((field.generation(), field.dimensions()), (7, Position { row: 4, col: 6 }));
+    assert_eq!(field.value(Position { row: 2, col: 1 }), Some(0.9));
+    assert_eq!(field.value(Position { row: 4, col: 0 }), None);
+    assert!(close(field.sample([4.5, 0.5]).unwrap(), 0.4));
+    assert!(close(field.sample([4.0, 0.5]).unwrap(), 0.35));
+    // Past the last center, the value of the edge
+    assert!(close(field.sample([5.9, 3.9]).unwrap(), 0.5));
+    for outside in [[-0.1, 1.0], [6.0, 1.0], [1.0, 4.0]] {
+        assert_eq!(field.sample(outside), None, "{outside:?}");
+        assert_eq!(field.gradient(outside), None, "{outside:?}");
+    }
+}
+
+#[test]
+fn gradients_point_up_the_slope() {
+    let [dx, dy] = field().gradient([3.5, 0.5]).unwrap();
+    assert!(close(dx, 0.1) && close(dy, 0.0), "{dx} {dy}");
+    // The peak pulls towards it from its right
+    let [dx, _] = field().gradient([2.5, 2.5]).unwrap();
+    assert!(dx < 0.0, "{dx}");
+}
+
+#[test]
+fn peaks_are_found_within_the_radius() {
+    let field = field();
+    assert_eq!(field.nearest_peak([3.0, 2.5], 2.0), Some([1.5, 2.5]));
+    assert_eq!(field.nearest_peak([4.5, 2.5], 1.0).map(|point| point[0]), Some(5.5));
+    assert_eq!(field.nearest_peak([3.0, 0.5], 0.4), None);
+}
+
+#[test]
+fn placements_map_points_to_cells() {
+    // Cells of 2 units, the rows going down while y goes up
+    let placement = Placement { origin: [-6.0, 4.0], cell_size: [2.0, -2.0] };
+    let field = field().with_placement(placement);
+    assert_eq!(field.placement(), placement);
+    assert_eq!(field.to_cells([-6.0, 4.0]), [0.0, 0.0]);
+    assert_eq!(field.to_point([3.0, 2.5]), [0.0, -1.0]);
+    assert_eq!(field.cell_at([-3.0, -1.0]), Some(Position { row: 2, col: 1 }));
+    assert_eq!(field.sample([-3.0, -1.0]), Some(0.9));
+    // Along x the values rise by 0.1 per cell of 2 units
+    let [dx, _] = field.gradient([1.0, 3.0]).unwrap();
+    assert!(close(dx, 0.05), "{dx}");
+    assert_eq!(field.nearest_peak([0.0, -1.0], 5.0), Some([-3.0, -1.0]));
+}
@@ -0,0 +1,55 @@
+//! Names of the runs, see `naming`
+use std::path::Path;
+
+use ca_turing_pattern::naming::{expand_path, run_name, RunInfo, DEFAULT_NAME_TEMPLATE};
+use ca_turing_pattern::*;
+
+fn info() -> RunInfo {
+    RunInfo {
+        parameters: Parameters { d_a: 1.0, d_b: 0.5, f: 0.035, k: 0.065, r: 1.0, initial: None },
+        dimensions: Position { row: 128, col: 256 },
+        seed: Some(42),
+        steps: 5000,
+    }
+}
+
+#[test]
+fn fields_are_replaced() {
+    assert_eq!(run_name(DEFAULT_NAME_TEMPLATE, &info()).unwrap(), "gs_f0.035_k0.065_seed42");
+    let name = run_name("{rows}x{cols}_{steps}_{d_a}_{d_b}_{r}", &info()).unwrap();
+    assert_eq!(name, "128x256_5000_1_0.5_1");
+    assert_eq!(run_name("plain", &info()).unwrap(), "plain");
+    let random = RunInfo { seed: None, ..info() };
+    assert_eq!(run_name("seed{seed}", &random).unwrap(), "seedrandom");
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    for template in ["gs_{feed}", "{}", "{F}"] {
+        let error = run_name(template, &info()).expect_err(template);
+        assert!(matches!(error, SimulationError::InvalidName(_)), "{template}: {error:?}");
+    }
+}
+
+#[test]
+fn unclosed_braces_are_rejected() {
+    for template in ["gs_{f", "{", "gs_{f}_{k"] {
+        let error = run_name(template, &info()).expect_err(template);
+        assert!(matches!(error, SimulationError::InvalidName(_)), "{template}: {error:?}");
+    }
+}
+
+#[test]
+fn names_that_cannot_name_files_are_rejected() {
+    for template in ["runs/{f}", "runs\\{f}", ""] {
+        let error = run_name(template, &info()).expect_err(template);
+        assert!(matches!(error, SimulationError::InvalidName(_)), "{template}: {error:?}");
+    }
+}
+
+#[test]
+fn paths_are_expanded() {
+    assert_eq!(expand_path(Path::new("checkpoints/{run}"), "gs"), Path::new("checkpoints/gs"));
+    assert_eq!(expand_path(Path::new("{run}_stats.csv"), "gs"), Path::new("gs_stats.csv"));
+    assert_eq!(expand_path(Path::new("out.png"), "gs"), Path::new("out.png"));
+}
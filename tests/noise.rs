@@ -0,0 +1,42 @@
+//! Noise added to B, see `noise`
+use ca_turing_pattern::noise::{Noise, NoiseConfig};
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Noisy simulation of a random universe
+fn simulation() -> Simulation<f64> {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let noise = Noise::new(NoiseConfig { amplitude: 0.01 }, 42).unwrap();
+    Simulation::random(Parameters::default(), Position { row: 32, col: 32 }, 20, &mut rng).unwrap().with_noise(noise)
+}
+
+#[test]
+fn restored_runs_add_the_same_noise() {
+    let mut uninterrupted = simulation();
+    uninterrupted.run(20);
+
+    let mut first = simulation();
+    first.run(10);
+    let noise = first.noise().unwrap().clone();
+    let mut restored = Simulation::new(first.parameters(), first.dimensions(), first.universe().clone())
+        .unwrap()
+        .with_generation(first.generation())
+        .with_noise(Noise::new(noise.config(), noise.seed()).unwrap());
+    restored.run(10);
+    assert_eq!(restored.universe(), uninterrupted.universe());
+}
+
+#[test]
+fn generations_draw_different_noise() {
+    let noise = Noise::new(NoiseConfig { amplitude: 0.1 }, 42).unwrap();
+    let universes = [1, 2].map(|generation| {
+        let mut universe: Universe<f64> = vec![vec![Cell { a: 0.5, b: 0.5 }; 8]; 8];
+        noise.apply(&mut universe, generation);
+        universe
+    });
+    assert_ne!(universes[0], universes[1]);
+    let mut again: Universe<f64> = vec![vec![Cell { a: 0.5, b: 0.5 }; 8]; 8];
+    noise.apply(&mut again, 2);
+    assert_eq!(again, universes[1]);
+}
@@ -0,0 +1,53 @@
+//! Observers of the evolutions, see `Simulation::on_step`, with clones of
+//! observed simulations and their evolutions iterated by `Simulation::steps`
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+fn simulation() -> Simulation {
+    let dimensions = Position { row: 16, col: 16 };
+    Simulation::random(Parameters::default(), dimensions, 8, &mut ChaCha8Rng::seed_from_u64(6)).unwrap()
+}
+
+#[test]
+fn clones_evolve_alike_without_the_observers() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut simulation = simulation();
+    let counted = Arc::clone(&calls);
+    simulation.on_step(move |_| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        ControlFlow::Continue(())
+    });
+    simulation.run(2);
+
+    let mut clone = simulation.clone();
+    assert_eq!(clone.generation(), 2);
+    clone.run(3);
+    simulation.run(3);
+    assert_eq!(clone.universe(), simulation.universe());
+    assert_eq!(calls.load(Ordering::Relaxed), 5);
+}
+
+#[test]
+fn steps_stop_with_the_observers() {
+    let mut simulation = simulation();
+    let mut reference = simulation.clone();
+    simulation.on_step(|summary| match summary.generation {
+        4 => ControlFlow::Break(()),
+        _ => ControlFlow::Continue(()),
+    });
+    let mut steps = simulation.steps();
+    let mut count = 0;
+    while let Some(universe) = steps.next() {
+        count += 1;
+        if count == 4 {
+            assert_eq!(universe, reference.run(4));
+        }
+    }
+    assert_eq!(count, 4);
+    assert!(simulation.steps().next().is_none());
+}
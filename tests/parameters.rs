@@ -0,0 +1,17 @@
+//! Validation of the parameters, and the hints of the valid ones unlikely
+//! to form a pattern, see `Parameters::validate` and `Parameters::hints`
+use ca_turing_pattern::*;
+
+#[test]
+fn rates_out_of_range_are_refused() {
+    let error = Parameters::builder().f(5.0).build().unwrap_err();
+    assert_eq!(error, ParametersError::OutOfRange { name: "f", value: 5.0, min: 0.0, max: 1.0 });
+    assert!(Parameters::builder().r(f32::INFINITY).build().is_err());
+}
+
+#[test]
+fn slow_activators_are_only_hinted() {
+    let parameters = Parameters::builder().d_a(0.3).d_b(0.5).build().expect("the rates are in range");
+    assert_eq!(parameters.hints(), [ParametersHint::SlowActivator { d_a: 0.3, d_b: 0.5 }]);
+    assert!(Parameters::default().hints().is_empty());
+}
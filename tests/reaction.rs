@@ -0,0 +1,75 @@
+//! Tests of the reactions compiled from the expressions of a
+//! `ReactionConfig`, against the values Rhai gives and the built-in reaction
+#![cfg(feature = "scripting")]
+use ca_turing_pattern::reaction::{GrayScott, Reaction, ReactionConfig, ScriptedReaction};
+use ca_turing_pattern::*;
+
+/// Reaction with `a` as the expression of A, and 0 as that of B
+fn reaction(a: &str) -> Result<ScriptedReaction, SimulationError> {
+    ScriptedReaction::new(ReactionConfig { a: a.to_string(), b: "0.0".to_string() })
+}
+
+/// Change of A given by `a` for a cell with concentrations 0.5 and 0.25
+fn value(a: &str) -> f64 {
+    let reaction = reaction(a).unwrap_or_else(|error| panic!("`{a}` compiles: {error}"));
+    assert!(reaction.is_compiled()[0], "`{a}` is compiled to a program");
+    reaction.react(0.5, 0.25, &Parameters::default())[0]
+}
+
+#[test]
+fn operators_keep_their_precedence() {
+    assert_eq!(value("1.0 + 2.0 * 3.0"), 7.0);
+    assert_eq!(value("(1.0 + 2.0) * 3.0"), 9.0);
+    assert_eq!(value("8.0 - 4.0 - 2.0"), 2.0);
+    assert_eq!(value("8.0 / 4.0 / 2.0"), 1.0);
+    assert_eq!(value("a + b * 2.0"), 1.0);
+    assert_eq!(value("7.0 % 4.0 * 2.0"), 6.0);
+    assert_eq!(value("2.0 ** 3.0 ** 2.0"), 512.0);
+    assert_eq!(value("2.0 * 3.0 ** 2.0"), 18.0);
+}
+
+#[test]
+fn unary_minus_binds_tighter_than_the_operators() {
+    assert_eq!(value("-a * 2.0"), -1.0);
+    assert_eq!(value("-a + b"), -0.25);
+    assert_eq!(value("1.0 - -b"), 1.25);
+    assert_eq!(value("-(a + b)"), -0.75);
+    // As in Rhai, the minus applies to 2 before the power
+    assert_eq!(value("-2.0 ** 2.0"), 4.0);
+}
+
+#[test]
+fn unknown_identifiers_are_rejected() {
+    for text in ["c * a", "a * feed", "sqrt2(a)"] {
+        let error = reaction(text).expect_err(text);
+        assert!(matches!(error, SimulationError::InvalidReaction(_)), "{text}: {error:?}");
+    }
+}
+
+#[test]
+fn division_by_zero_follows_the_floats() {
+    assert_eq!(value("a / 0.0"), f64::INFINITY);
+    assert_eq!(value("-a / 0.0"), f64::NEG_INFINITY);
+    assert!(value("(a - a) / 0.0").is_nan());
+}
+
+#[test]
+fn default_expressions_match_the_built_in_reaction() {
+    let reaction = ReactionConfig::default().compile().expect("the default expressions compile");
+    assert_eq!(reaction.script(), Some(&ReactionConfig::default()));
+    let scripted = ScriptedReaction::new(ReactionConfig::default()).unwrap();
+    assert_eq!(scripted.is_compiled(), [true, true]);
+    for name in PRESET_NAMES {
+        let parameters = Parameters::preset(name).unwrap();
+        for a in 0..=20 {
+            for b in 0..=20 {
+                let (a, b) = (a as f64 / 20.0, b as f64 / 20.0);
+                assert_eq!(
+                    reaction.react(a, b, &parameters),
+                    GrayScott.react(a, b, &parameters),
+                    "{name} at a = {a}, b = {b}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Readbacks of the fields of every CPU backend, see `readback`
+use ca_turing_pattern::adaptive::{AdaptiveConfig, AdaptiveSimulation};
+use ca_turing_pattern::lenia::{LeniaParameters, LeniaSimulation};
+use ca_turing_pattern::readback::FieldSource;
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const DIMENSIONS: Position = Position { row: 64, col: 64 };
+
+#[test]
+fn simulation_reads_back_its_universe() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let mut simulation: Simulation = Simulation::random(Parameters::default(), DIMENSIONS, 20, &mut rng).unwrap();
+    simulation.run(5);
+    let snapshot = simulation.read_back().expect("the simulation is not busy");
+    assert_eq!(snapshot.generation, 5);
+    assert_eq!(&snapshot.universe, simulation.universe());
+}
+
+#[test]
+fn busy_simulation_drops_the_readback() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let mut simulation: Simulation = Simulation::random(Parameters::default(), DIMENSIONS, 20, &mut rng).unwrap();
+    let step = simulation.begin_step();
+    assert!(simulation.is_busy());
+    assert_eq!(simulation.read_back(), None);
+    simulation.finish_step(step.compute());
+    assert!(!simulation.is_busy());
+    let snapshot = simulation.read_back().expect("the evolution was given back");
+    assert_eq!((snapshot.generation, snapshot.dimensions()), (1, DIMENSIONS));
+}
+
+#[test]
+fn lenia_reads_back_its_field() {
+    let field = (0..DIMENSIONS.row)
+        .map(|row| (0..DIMENSIONS.col).map(|col| ((row * col) % 7) as f32 / 7.0).collect())
+        .collect();
+    let mut simulation =
+        LeniaSimulation::new(LeniaParameters::default(), DIMENSIONS, Boundary::Periodic, field).unwrap();
+    simulation.run(3);
+    let snapshot = simulation.read_back().expect("Lenia completes its readbacks");
+    assert_eq!(snapshot.generation, 3);
+    assert_eq!(snapshot.universe, simulation.universe());
+}
+
+#[test]
+fn adaptive_simulation_reads_back_its_universe() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let mut simulation = AdaptiveSimulation::random(
+        Parameters::default(),
+        DIMENSIONS,
+        Boundary::Periodic,
+        AdaptiveConfig::default(),
+        20,
+        &mut rng,
+    )
+    .unwrap();
+    simulation.run(5);
+    let snapshot = simulation.read_back().expect("the adaptive simulation completes its readbacks");
+    assert_eq!((snapshot.generation, snapshot.dimensions()), (5, DIMENSIONS));
+    assert_eq!(snapshot.universe, simulation.universe());
+}
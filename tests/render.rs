@@ -0,0 +1,41 @@
+//! Frames of the color maps in the pixel formats of the window, see `render`
+use ca_turing_pattern::colormap::Colormap;
+use ca_turing_pattern::render::{Frame, PixelFormat, RenderConfig};
+use ca_turing_pattern::*;
+
+const COLORMAPS: [Colormap; 4] =
+    [Colormap::Gray, Colormap::Viridis, Colormap::Magma, Colormap::Binary { threshold: 0.5 }];
+
+/// Frame of `colored_map` in `format`, one pixel per cell
+fn frame(colored_map: &ColoredMap, format: PixelFormat, colormap: Colormap) -> Frame {
+    let config = RenderConfig { factor: 1, format, ..RenderConfig::default() };
+    Frame::colored_map(colored_map, 7, config, colormap)
+}
+
+#[test]
+fn formats_give_the_same_bytes_on_the_palette() {
+    // Every entry of the palette of `R8`, and values out of [0,1]
+    let colored_map: ColoredMap =
+        vec![(0..=255).map(|level| level as f32 / 255.0).collect(), [-0.5, 1.5].repeat(128)];
+    for colormap in COLORMAPS {
+        let rgba = frame(&colored_map, PixelFormat::Rgba8, colormap);
+        let r8 = frame(&colored_map, PixelFormat::R8, colormap);
+        assert_eq!((rgba.width, rgba.height, rgba.generation), (256, 2, 7));
+        assert_eq!(r8.as_bytes(), rgba.as_bytes(), "{colormap:?}");
+    }
+}
+
+#[test]
+fn r8_colors_the_values_rounded_to_the_palette() {
+    let colored_map: ColoredMap =
+        (0..16).map(|row| (0..16).map(|col| ((row * 16 + col) as f32 * 0.618).fract()).collect()).collect();
+    let rounded: ColoredMap = colored_map
+        .iter()
+        .map(|row| row.iter().map(|value| (value * 255.0).round() / 255.0).collect())
+        .collect();
+    for colormap in COLORMAPS {
+        let r8 = frame(&colored_map, PixelFormat::R8, colormap);
+        assert_eq!(r8, frame(&rounded, PixelFormat::Rgba8, colormap), "{colormap:?}");
+        assert_eq!(r8.as_bytes().len(), 16 * 16 * 4);
+    }
+}
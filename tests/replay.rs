@@ -0,0 +1,75 @@
+//! Runs recorded by a `Recorder` while they are changed, and played back
+//! from the replay file, see `replay`
+#![cfg(feature = "fs")]
+use std::fs;
+
+use ca_turing_pattern::config::InitialConfig;
+use ca_turing_pattern::replay::{apply_event, Recorder, Replay, ReplayEvent};
+use ca_turing_pattern::*;
+
+const STEPS: i32 = 60;
+
+fn replay() -> Replay {
+    Replay {
+        dimensions: Position { row: 24, col: 18 },
+        initial: InitialConfig { cells: 6, ..InitialConfig::default() },
+        seed: 11,
+        parameters: Parameters::default(),
+        steps: STEPS,
+        bounds: Bounds::default(),
+        boundary: Boundary::default(),
+        stencil: Stencil::default(),
+        timeline: Default::default(),
+        schedule: Default::default(),
+        modulation: None,
+        reaction: None,
+        activity: None,
+        symmetry: None,
+        noise: None,
+        streams: Default::default(),
+        deterministic: false,
+        events: Vec::new(),
+    }
+}
+
+/// Changes made after computing the generation they are paired with
+fn events() -> Vec<(i32, ReplayEvent)> {
+    let cells = (3..6).map(|col| (Position { row: 4, col }, Cell { a: 0.5, b: 0.25 })).collect();
+    let parameters = Parameters { f: 0.037, k: 0.06, ..Parameters::default() };
+    vec![
+        (5, ReplayEvent::SetCells(cells)),
+        (12, ReplayEvent::SetParameters(parameters)),
+        (20, ReplayEvent::Resize(Position { row: 30, col: 21 }, Resampling::Bilinear)),
+        (20, ReplayEvent::SetCells(vec![(Position { row: 29, col: 20 }, Cell { a: 0.0, b: 1.0 })])),
+        (41, ReplayEvent::Resize(Position { row: 16, col: 16 }, Resampling::Nearest)),
+    ]
+}
+
+#[test]
+fn played_runs_end_with_the_recorded_universe() {
+    let path = std::env::temp_dir().join(format!("ca_turing_pattern_replay_{}.ron", std::process::id()));
+    let mut recorder = Recorder::new(&path, replay()).unwrap();
+    let mut simulation = replay().simulation().unwrap();
+    let mut events = events().into_iter().peekable();
+    while simulation.generation() < STEPS {
+        simulation.step();
+        let generation = simulation.generation();
+        while let Some((generation, event)) = events.next_if(|(due, _)| *due == generation) {
+            apply_event(&event, &mut simulation).unwrap();
+            recorder.record(generation, event).unwrap();
+        }
+    }
+    assert!(events.next().is_none());
+    assert_eq!(simulation.dimensions(), Position { row: 16, col: 16 });
+
+    let replay = Replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.events.len(), 5);
+    let mut generations = Vec::new();
+    let universe = replay.play(|simulation| generations.push(simulation.generation())).unwrap();
+    assert_eq!(generations, (1..=STEPS).collect::<Vec<_>>());
+    assert_eq!(&universe, simulation.universe());
+    // The events did change the run
+    let unchanged = Replay { events: Vec::new(), ..replay }.play(|_| {}).unwrap();
+    assert_ne!(unchanged, universe);
+}
@@ -0,0 +1,67 @@
+//! Keyframes of a run and seeks back to them, see `rewind`
+use ca_turing_pattern::rewind::{RewindBuffer, RewindConfig};
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+fn simulation() -> Simulation {
+    let dimensions = Position { row: 20, col: 14 };
+    Simulation::random(Parameters::default(), dimensions, 8, &mut ChaCha8Rng::seed_from_u64(5)).unwrap()
+}
+
+/// `simulation` evolved `steps` times, recording the keyframes of `rewind`
+fn record(simulation: &mut Simulation, rewind: &mut RewindBuffer, steps: i32) {
+    for _ in 0..steps {
+        simulation.step();
+        rewind.record(simulation);
+    }
+}
+
+/// Universe at `generation`, evolved from the start without rewinding
+fn universe_at(generation: i32) -> Universe {
+    let mut simulation = simulation();
+    simulation.run(generation);
+    simulation.into_universe()
+}
+
+#[test]
+fn seeks_restore_the_generation_and_the_universe() {
+    let mut simulation = simulation();
+    let mut rewind = RewindBuffer::new(RewindConfig { capacity: 4, interval: 5 }).unwrap();
+    record(&mut simulation, &mut rewind, 23);
+    // The first generation, marked, and every fifth were kept, and only the
+    // last 4 of them remain
+    let generations: Vec<i32> = rewind.frames().map(|frame| frame.generation).collect();
+    assert_eq!(generations, [5, 10, 15, 20]);
+    assert_eq!(rewind.range(), Some((5, 23)));
+
+    for generation in [12, 5, 23, 17] {
+        assert_eq!(rewind.seek(&mut simulation, generation).unwrap(), generation);
+        assert_eq!(simulation.generation(), generation);
+        assert_eq!(simulation.universe(), &universe_at(generation));
+    }
+    // Out of range, the seeks stop at the ends
+    assert_eq!(rewind.seek(&mut simulation, 2).unwrap(), 5);
+    assert_eq!(rewind.seek(&mut simulation, 40).unwrap(), 23);
+}
+
+#[test]
+fn evolving_after_a_seek_drops_the_later_keyframes() {
+    let mut simulation = simulation();
+    let mut rewind = RewindBuffer::new(RewindConfig { capacity: 10, interval: 3 }).unwrap();
+    record(&mut simulation, &mut rewind, 12);
+    rewind.seek(&mut simulation, 4).unwrap();
+    record(&mut simulation, &mut rewind, 1);
+    let generations: Vec<i32> = rewind.frames().map(|frame| frame.generation).collect();
+    assert_eq!(generations, [1, 3]);
+    assert_eq!(rewind.range(), Some((1, 5)));
+}
+
+#[test]
+fn intervals_below_one_are_refused() {
+    for interval in [0, -4] {
+        let config = RewindConfig { interval, ..RewindConfig::default() };
+        assert!(matches!(config.validate(), Err(SimulationError::InvalidInterval(_))));
+        assert!(matches!(RewindBuffer::new(config), Err(SimulationError::InvalidInterval(_))));
+    }
+}
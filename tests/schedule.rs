@@ -0,0 +1,72 @@
+//! Pulses of a schedule, see `schedule`
+use ca_turing_pattern::modulation::{Modulation, ModulationConfig};
+use ca_turing_pattern::region::Rect;
+use ca_turing_pattern::schedule::{Injection, Pulse, Schedule};
+use ca_turing_pattern::*;
+
+/// Generations from 0 to 99 at which `pulse` is active
+fn active(pulse: Pulse) -> Vec<i32> {
+    (0..100).filter(|generation| pulse.is_active(*generation)).collect()
+}
+
+#[test]
+fn single_pulse_starts_and_lasts() {
+    let pulse = Pulse { start: 10, duration: 3, f: Some(0.05), ..Pulse::default() };
+    assert_eq!(active(pulse), [10, 11, 12]);
+    assert_eq!(active(Pulse { start: 0, ..pulse }), [0, 1, 2]);
+    assert_eq!(active(Pulse { duration: 1, ..pulse }), [10]);
+}
+
+#[test]
+fn periodic_pulses_repeat() {
+    let pulse = Pulse { start: 5, period: 20, duration: 2, f: Some(0.05), ..Pulse::default() };
+    assert_eq!(active(pulse), [5, 6, 25, 26, 45, 46, 65, 66, 85, 86]);
+    // Pulses as long as their period never stop
+    assert_eq!(active(Pulse { start: 90, period: 4, duration: 4, ..pulse }), (90..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn counted_pulses_stop() {
+    let pulse = Pulse { start: 5, period: 20, duration: 2, count: Some(2), f: Some(0.05), ..Pulse::default() };
+    assert_eq!(active(pulse), [5, 6, 25, 26]);
+    assert_eq!(active(Pulse { count: Some(1), ..pulse }), [5, 6]);
+    // The count of a single pulse changes nothing
+    assert_eq!(active(Pulse { period: 0, count: Some(3), ..pulse }), [5, 6]);
+}
+
+#[test]
+fn generations_near_the_limits_are_not_active() {
+    let pulse = Pulse { start: 10, period: 20, duration: 2, f: Some(0.05), ..Pulse::default() };
+    assert!(!pulse.is_active(i32::MIN));
+    assert!(!pulse.is_active(-10));
+    assert!(!pulse.is_active(9));
+}
+
+#[test]
+fn injections_add_b_up_to_1() {
+    let region = Rect { origin: Position { row: 1, col: 1 }, dimensions: Position { row: 2, col: 5 } };
+    let pulse = Pulse { inject: Some(Injection { region, amount: 0.25 }), ..Pulse::default() };
+    let schedule = Schedule { pulses: vec![pulse] };
+    let mut universe: Universe<f64> = vec![vec![Cell { a: 1.0, b: 0.875 }; 3]; 4];
+    assert!(schedule.inject(0, &mut universe));
+    let b: Vec<Vec<f64>> = universe.iter().map(|row| row.iter().map(|cell| cell.b).collect()).collect();
+    assert_eq!(b, [vec![0.875; 3], vec![0.875, 1.0, 1.0], vec![0.875, 1.0, 1.0], vec![0.875; 3]]);
+    assert!(!schedule.inject(1, &mut universe));
+}
+
+#[test]
+fn pulses_are_checked_with_the_modulation() {
+    let modulation = || Modulation::new(ModulationConfig { f: [1.0, 2.0], ..ModulationConfig::default() }).unwrap();
+    let simulation = || -> Simulation {
+        let universe = vec![vec![Cell { a: 1.0, b: 0.0 }; 4]; 4];
+        Simulation::new(Parameters::default(), Position { row: 4, col: 4 }, universe).unwrap()
+    };
+    let pulse = |f: f32| Schedule { pulses: vec![Pulse { start: 10, f: Some(f), ..Pulse::default() }] };
+    assert!(simulation().with_modulation(modulation()).unwrap().with_schedule(pulse(0.4)).is_ok());
+
+    // The scaled `f` of the pulse reaches 1.2
+    let error = simulation().with_modulation(modulation()).unwrap().with_schedule(pulse(0.6)).unwrap_err();
+    assert!(matches!(error, SimulationError::InvalidModulation(_)), "{error:?}");
+    let error = simulation().with_schedule(pulse(0.6)).unwrap().with_modulation(modulation()).unwrap_err();
+    assert!(matches!(error, SimulationError::InvalidModulation(_)), "{error:?}");
+}
@@ -0,0 +1,18 @@
+//! Settings restored with a session, see `session`
+use ca_turing_pattern::session::Brush;
+use ca_turing_pattern::*;
+
+#[test]
+fn brushes_hold_concentrations() {
+    assert!(Brush::default().validate().is_ok());
+    let brushes = [
+        Brush { cell: Cell { a: 1.5, b: 0.5 }, ..Brush::default() },
+        Brush { cell: Cell { a: 0.5, b: -0.1 }, ..Brush::default() },
+        Brush { cell: Cell { a: f32::NAN, b: 0.5 }, ..Brush::default() },
+        Brush { jitter: 2.0, ..Brush::default() },
+    ];
+    for brush in brushes {
+        let error = brush.validate().unwrap_err();
+        assert!(matches!(error, SimulationError::InvalidBrush(_)), "{brush:?}: {error:?}");
+    }
+}
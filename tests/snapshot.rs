@@ -0,0 +1,36 @@
+//! Round trips of snapshots, which keep the edges of the universe and the
+//! seed of the run
+use ca_turing_pattern::snapshot::Snapshot;
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn snapshots_keep_the_edges() {
+    let dimensions = Position { row: 20, col: 24 };
+    let mut simulation: Simulation =
+        Simulation::random(Parameters::default(), dimensions, 10, &mut ChaCha8Rng::seed_from_u64(4))
+            .unwrap()
+            .with_boundary(Boundary::Periodic);
+    simulation.run(5);
+
+    let snapshot = Snapshot::from_bytes(&Snapshot::of(&simulation).to_bytes().unwrap()).unwrap();
+    assert_eq!(snapshot.boundary, Boundary::Periodic);
+    let mut restored = snapshot.into_simulation().unwrap();
+    assert_eq!(restored.boundary(), Boundary::Periodic);
+    assert_eq!(restored.generation(), 5);
+
+    simulation.run(5);
+    restored.run(5);
+    assert_eq!(restored.universe(), simulation.universe());
+}
+
+#[test]
+fn snapshots_keep_the_seed_of_the_run() {
+    let simulation: Simulation =
+        Simulation::random(Parameters::default(), Position { row: 6, col: 5 }, 3, &mut ChaCha8Rng::seed_from_u64(4))
+            .unwrap();
+    assert_eq!(Snapshot::of(&simulation).seed, None);
+    let snapshot = Snapshot::of(&simulation).with_seed(Some(13));
+    assert_eq!(Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap().seed, Some(13));
+}
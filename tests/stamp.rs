@@ -0,0 +1,59 @@
+//! Patches copied out of a universe and stamped turned or mirrored, see
+//! `stamp`
+use ca_turing_pattern::region::Rect;
+use ca_turing_pattern::stamp::{Orientation, Stamp};
+use ca_turing_pattern::*;
+
+/// Rows of B values as cells
+fn cells(rows: &[&[f32]]) -> Universe {
+    rows.iter().map(|row| row.iter().map(|&b| Cell { a: 1.0, b }).collect()).collect()
+}
+
+fn patch() -> Stamp {
+    Stamp::new(cells(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]]))
+}
+
+#[test]
+fn stamps_turn_clockwise_and_mirror_left_to_right() {
+    let mut stamp = patch();
+    stamp.rotate();
+    assert_eq!(stamp.dimensions(), Position { row: 3, col: 2 });
+    assert_eq!(stamp.oriented(), cells(&[&[4.0, 1.0], &[5.0, 2.0], &[6.0, 3.0]]));
+    // Mirrored as it is shown, after being turned
+    stamp.mirror();
+    assert_eq!(stamp.orientation(), Orientation { quarter_turns: 3, mirrored: true });
+    assert_eq!(stamp.oriented(), cells(&[&[1.0, 4.0], &[2.0, 5.0], &[3.0, 6.0]]));
+    stamp.mirror();
+    assert_eq!(stamp.oriented(), cells(&[&[4.0, 1.0], &[5.0, 2.0], &[6.0, 3.0]]));
+    (0..3).for_each(|_| stamp.rotate());
+    assert_eq!(stamp, patch());
+    let mirrored = patch().with_orientation(Orientation { quarter_turns: 6, mirrored: true });
+    assert_eq!(mirrored.orientation().quarter_turns, 2);
+    assert_eq!(mirrored.oriented(), cells(&[&[4.0, 5.0, 6.0], &[1.0, 2.0, 3.0]]));
+}
+
+#[test]
+fn copies_keep_the_cells_inside_the_rect() {
+    let universe = cells(&[&[0.0, 1.0, 2.0, 3.0], &[4.0, 5.0, 6.0, 7.0], &[8.0, 9.0, 10.0, 11.0]]);
+    let rect = Rect { origin: Position { row: 1, col: 1 }, dimensions: Position { row: 2, col: 2 } };
+    assert_eq!(Stamp::copy(&universe, rect).unwrap().oriented(), cells(&[&[5.0, 6.0], &[9.0, 10.0]]));
+    let outside = Rect { origin: Position { row: 2, col: 3 }, dimensions: Position { row: 2, col: 2 } };
+    assert!(Stamp::copy(&universe, outside).is_err());
+}
+
+#[test]
+fn stamps_are_moved_inside_the_universe() {
+    let dimensions = Position { row: 6, col: 6 };
+    let universe = vec![vec![Cell::empty(); 6]; 6];
+    let mut simulation = Simulation::new(Parameters::default(), dimensions, universe).unwrap();
+    let mut stamp = patch();
+    stamp.rotate();
+    let rect = stamp.stamp(&mut simulation, Position { row: 0, col: 5 }).unwrap();
+    assert_eq!(rect, Rect { origin: Position { row: 0, col: 4 }, dimensions: Position { row: 3, col: 2 } });
+    let stamped: Vec<Vec<f32>> =
+        simulation.universe()[..3].iter().map(|row| row[4..].iter().map(|cell| cell.b).collect()).collect();
+    assert_eq!(stamped, [[4.0, 1.0], [5.0, 2.0], [6.0, 3.0]]);
+    assert_eq!(stamp.rect_at(Position { row: 3, col: 3 }, dimensions).origin, Position { row: 2, col: 2 });
+    let large = Stamp::new(vec![vec![Cell::empty(); 7]; 2]);
+    assert!(large.stamp(&mut simulation, Position { row: 3, col: 3 }).is_err());
+}
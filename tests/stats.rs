@@ -0,0 +1,44 @@
+//! Summaries and statistics of universes, see `stats`, and their logs, see
+//! `logger`
+use ca_turing_pattern::logger::StatsLogConfig;
+use ca_turing_pattern::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn summaries_match_the_statistics() {
+    let dimensions = Position { row: 30, col: 20 };
+    let mut simulation: Simulation =
+        Simulation::random(Parameters::default(), dimensions, 40, &mut ChaCha8Rng::seed_from_u64(4)).unwrap();
+    simulation.run(10);
+    let (summary, stats) = (simulation.summary(), simulation.stats());
+    assert_eq!(summary.generation, 10);
+    assert!((summary.mean_a as f64 - stats.a.mean).abs() < 1e-6);
+    assert!((summary.mean_b as f64 - stats.b.mean).abs() < 1e-6);
+    assert_eq!(summary.min_b, stats.b.min as f32);
+    assert_eq!(summary.max_b, stats.b.max as f32);
+}
+
+#[test]
+fn extremes_that_are_not_finite_are_0() {
+    let dimensions = Position { row: 2, col: 2 };
+    let mut universe = vec![vec![Cell { a: 1.0, b: 0.5 }; 2]; 2];
+    universe[0][1].b = f32::INFINITY;
+    universe[1][0].b = f32::NEG_INFINITY;
+    let simulation = Simulation::new(Parameters::default(), dimensions, universe).unwrap();
+    let summary = simulation.summary();
+    assert_eq!((summary.min_b, summary.max_b), (0.0, 0.0));
+
+    let empty: Simulation = Simulation::new(Parameters::default(), Position { row: 0, col: 0 }, Vec::new()).unwrap();
+    let summary = empty.summary();
+    assert_eq!((summary.mean_a, summary.mean_b, summary.min_b, summary.max_b), (0.0, 0.0, 0.0, 0.0));
+}
+
+#[test]
+fn log_intervals_below_one_are_refused() {
+    for interval in [0, -10] {
+        let config = StatsLogConfig { interval, ..StatsLogConfig::default() };
+        assert!(matches!(config.validate(), Err(SimulationError::InvalidInterval(_))));
+    }
+    assert!(StatsLogConfig::default().validate().is_ok());
+}
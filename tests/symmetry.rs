@@ -0,0 +1,39 @@
+//! Projections onto the symmetries, see `symmetry`
+use ca_turing_pattern::symmetry::{Orbits, Symmetry};
+use ca_turing_pattern::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+const SYMMETRIES: [Symmetry; 4] =
+    [Symmetry::Mirror, Symmetry::Rotational(4), Symmetry::Rotational(6), Symmetry::Dihedral(3)];
+
+#[test]
+fn orbits_are_those_of_the_symmetry() {
+    let dimensions = Position { row: 9, col: 12 };
+    for symmetry in SYMMETRIES {
+        let orbits = Orbits::new(symmetry, dimensions);
+        for row in 0..dimensions.row {
+            for col in 0..dimensions.col {
+                let position = Position { row, col };
+                let orbit: Vec<Position> = orbits.orbit(position).collect();
+                assert_eq!(orbit, symmetry.orbit(position, dimensions), "{symmetry} at {position:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn projections_make_mirrored_cells_equal() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let mut universe: Universe<f64> =
+        (0..6).map(|_| (0..7).map(|_| Cell { a: rng.gen(), b: rng.gen() }).collect()).collect();
+    let before = universe.clone();
+    Symmetry::Mirror.project(&mut universe);
+    for (row, cells) in universe.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            assert_eq!(*cell, cells[6 - col]);
+            let mean = (before[row][col].b + before[row][6 - col].b) / 2.0;
+            assert!((cell.b - mean).abs() < 1e-12);
+        }
+    }
+}
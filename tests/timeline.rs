@@ -0,0 +1,70 @@
+//! Keyframes of a timeline interpolated along the generations, see
+//! `timeline`
+use ca_turing_pattern::timeline::{Keyframe, Timeline};
+use ca_turing_pattern::*;
+
+fn keyframes(keyframes: &[(i32, f32)]) -> Vec<Keyframe> {
+    keyframes.iter().map(|&(generation, value)| Keyframe { generation, value }).collect()
+}
+
+/// `f` going up then down, and `k` held from generation 50
+fn timeline() -> Timeline {
+    Timeline {
+        f: keyframes(&[(100, 0.02), (0, 0.01), (300, 0.06)]),
+        k: keyframes(&[(50, 0.0625)]),
+        ..Timeline::default()
+    }
+    .sorted()
+}
+
+#[test]
+fn keyframes_give_their_value() {
+    let (timeline, base) = (timeline(), Parameters::default());
+    for (generation, f) in [(0, 0.01), (100, 0.02), (300, 0.06)] {
+        let parameters = timeline.parameters_at(generation, base);
+        assert_eq!((parameters.f, parameters.k), (f, 0.0625), "at {generation}");
+    }
+}
+
+#[test]
+fn values_between_keyframes_are_interpolated_linearly() {
+    let (timeline, base) = (timeline(), Parameters::default());
+    let f = |generation| timeline.parameters_at(generation, base).f;
+    assert!((f(50) - 0.015).abs() < 1e-7, "{}", f(50));
+    assert!((f(200) - 0.04).abs() < 1e-7, "{}", f(200));
+    assert!((f(250) - 0.05).abs() < 1e-7, "{}", f(250));
+    // Monotonic between two keyframes
+    assert!((100..300).all(|generation| f(generation) <= f(generation + 1)));
+}
+
+#[test]
+fn tracks_hold_their_ends_and_others_keep_the_base() {
+    let (timeline, base) = (timeline(), Parameters::default());
+    assert_eq!(timeline.parameters_at(-20, base).f, 0.01);
+    assert_eq!(timeline.parameters_at(1000, base).f, 0.06);
+    assert_eq!(timeline.parameters_at(0, base).k, 0.0625);
+    let parameters = timeline.parameters_at(120, base);
+    assert_eq!((parameters.d_a, parameters.d_b, parameters.r), (base.d_a, base.d_b, base.r));
+    assert_eq!(Timeline::default().parameters_at(120, base), base);
+    assert!(Timeline::default().is_empty() && !timeline.is_empty());
+}
+
+#[test]
+fn simulations_follow_the_timeline() {
+    let base = Parameters::default();
+    let universe = vec![vec![Cell { a: 1.0, b: 0.0 }; 8]; 6];
+    let mut simulation =
+        Simulation::new(base, Position { row: 6, col: 8 }, universe).unwrap().with_timeline(timeline()).unwrap();
+    // Every evolution uses the parameters of the generation it starts from
+    simulation.run(151);
+    assert_eq!(simulation.parameters(), timeline().parameters_at(150, base));
+}
+
+#[test]
+fn invalid_keyframes_are_refused() {
+    let timeline = Timeline { d_b: keyframes(&[(0, 0.2), (10, -1.0)]), ..Timeline::default() };
+    assert!(timeline.validate(Parameters::default()).is_err());
+    let universe = vec![vec![Cell { a: 1.0, b: 0.0 }; 4]; 4];
+    let simulation = Simulation::new(Parameters::default(), Position { row: 4, col: 4 }, universe).unwrap();
+    assert!(matches!(simulation.with_timeline(timeline), Err(SimulationError::InvalidParameters(_))));
+}